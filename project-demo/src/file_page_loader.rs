@@ -47,17 +47,28 @@
 //! | tamanho de cada página | 8 bytes           |
 //! | indices das páginas    | n_pages * 8 bytes |
 //!
+//! O carregador informa o tamanho de página do header e o número de páginas
+//! em `PageLoader::capabilities`, e a Mmu os confere com os seus ao ser
+//! construída: como ela só usa páginas de 256 bytes (veja
+//! `vm::mmu::PAGE_SIZE`), um arquivo com páginas de outro tamanho é
+//! recusado. O comando `swapctl` da demo cria arquivos vazios, com páginas
+//! de 256 bytes, e mostra o header.
+//!
 //! ---
 //!
 //! Exagerei? *Sim*. :P
 
 use std::{
     fs::File,
-    io::{Read, Seek, SeekFrom, Write},
+    io::{self, Read, Seek, SeekFrom, Write},
     path::Path,
 };
 
-use vm::page_loader::PageLoader;
+use vm::{
+    error::{LoaderError, LoaderOperation},
+    mmu::PAGE_SIZE,
+    page_loader::{LoaderCapabilities, PageLoader},
+};
 
 /// O header do swap file.
 #[derive(Debug)]
@@ -84,14 +95,21 @@ impl<const N_PAGES: usize> SwapFilePageLoader<N_PAGES> {
     /// Lê o header e o interpreta.
     fn parse_header(file: &mut File) -> std::io::Result<SwapFileHeader<N_PAGES>> {
         let mut n_pages_buf = vec![0u8; std::mem::size_of::<usize>()];
-        file.read(&mut n_pages_buf[..])?;
+        file.read_exact(&mut n_pages_buf[..])?;
         let n_pages = usize::from_le_bytes(n_pages_buf.try_into().unwrap());
-        assert_eq!(n_pages, N_PAGES);
+        if n_pages != N_PAGES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "o swap file tem {} páginas, mas a MMU tem {}; gere o arquivo de novo",
+                    n_pages, N_PAGES
+                ),
+            ));
+        }
 
         let mut page_size_buf = vec![0u8; std::mem::size_of::<usize>()];
-        file.read(&mut page_size_buf[..])?;
+        file.read_exact(&mut page_size_buf[..])?;
         let page_size = usize::from_le_bytes(page_size_buf.try_into().unwrap());
-
         let mut indices_buf = vec![0; n_pages * std::mem::size_of::<usize>()];
 
         file.read_exact(&mut indices_buf[..])?;
@@ -119,26 +137,91 @@ impl<const N_PAGES: usize> SwapFilePageLoader<N_PAGES> {
 
         let header = SwapFilePageLoader::parse_header(&mut file)?;
 
+        // A seção de dados só tem páginas inteiras.
+        let data_len = file.metadata()?.len() as usize - std::mem::size_of_val(&header);
+        if !data_len.is_multiple_of(header.page_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "a seção de dados do swap file tem {} bytes, que não é múltiplo de páginas de {} bytes",
+                    data_len, header.page_size
+                ),
+            ));
+        }
+
         let loader = SwapFilePageLoader { file, header };
 
         Ok(loader)
     }
+
+    /// Cria (ou sobrescreve) um swap file vazio com páginas de
+    /// `vm::mmu::PAGE_SIZE` bytes, sem nenhuma página guardada.
+    pub fn create<P: AsRef<Path>>(filename: &P) -> std::io::Result<SwapFilePageLoader<N_PAGES>> {
+        let mut file = File::create(filename)?;
+        file.write_all(&N_PAGES.to_le_bytes())?;
+        file.write_all(&PAGE_SIZE.to_le_bytes())?;
+        file.write_all(&vec![0; N_PAGES * std::mem::size_of::<usize>()])?;
+
+        SwapFilePageLoader::new(filename)
+    }
+
+    /// Quantas páginas estão guardadas no arquivo.
+    pub fn stored_pages(&self) -> usize {
+        self.header
+            .indices
+            .iter()
+            .filter(|&&index| index != 0)
+            .count()
+    }
 }
 
-impl<const N_PAGES: usize> PageLoader for SwapFilePageLoader<N_PAGES> {
-    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
-        if self.header.indices[page_number] == 0 {
-            // 0 significa que a página nao esta presente. No mundo real
-            // isso iria causar violação de acesso + crash, mas aqui
-            // vamos preencher com 0.
+impl<const N_PAGES: usize> SwapFilePageLoader<N_PAGES> {
+    /// Descreve uma falha numa operação com a página.
+    fn error(operation: LoaderOperation, page_number: usize, error: io::Error) -> LoaderError {
+        LoaderError::new("file", operation, page_number, error)
+    }
 
-            for i in target {
-                *i = 0;
-            }
+    /// O índice da página na lista de índices, ou um erro se a página não
+    /// cabe no header.
+    fn index(&self, operation: LoaderOperation, page_number: usize) -> Result<usize, LoaderError> {
+        self.header
+            .indices
+            .get(page_number)
+            .copied()
+            .ok_or_else(|| {
+                LoaderError::new(
+                    "file",
+                    operation,
+                    page_number,
+                    format!("o swap file só tem {} páginas", N_PAGES),
+                )
+            })
+    }
 
-            return;
+    /// Um erro se o buffer da Mmu não tem o tamanho das páginas do arquivo:
+    /// ler ou escrever com outro tamanho misturaria páginas vizinhas.
+    fn check_buffer(
+        &self,
+        operation: LoaderOperation,
+        page_number: usize,
+        len: usize,
+    ) -> Result<(), LoaderError> {
+        if len != self.header.page_size {
+            return Err(LoaderError::new(
+                "file",
+                operation,
+                page_number,
+                format!(
+                    "o swap file tem páginas de {} bytes, mas o frame tem {}",
+                    self.header.page_size, len
+                ),
+            ));
         }
 
+        Ok(())
+    }
+
+    fn load(&mut self, page_number: usize, target: &mut [u8]) -> io::Result<()> {
         // A partir da lista de índices, calcula a posição do começo da página
         // na seção de dados do arquivo. A seção começa no primeiro byte
         // depois do header, e cada entrada na seção tem page_size bytes,
@@ -147,14 +230,13 @@ impl<const N_PAGES: usize> PageLoader for SwapFilePageLoader<N_PAGES> {
         let offset = (self.header.indices[page_number] - 1) * self.header.page_size;
 
         self.file
-            .seek(SeekFrom::Start((starting_idx + offset).try_into().unwrap()))
-            .unwrap();
+            .seek(SeekFrom::Start((starting_idx + offset).try_into().unwrap()))?;
 
         // Depois de encontrar, apenas lemos page_size bytes contíguos.
-        self.file.read(target).unwrap();
+        self.file.read_exact(target)
     }
 
-    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+    fn flush(&mut self, page_number: usize, buffer: &[u8]) -> io::Result<()> {
         // Essa função é meio... macarronada.
         // Eu poderia refatorar ela, mas estou sem tempo :(
 
@@ -168,16 +250,16 @@ impl<const N_PAGES: usize> PageLoader for SwapFilePageLoader<N_PAGES> {
             // Mas temos que fazer tudo isso escovando bytes.
 
             let offset = std::mem::size_of::<SwapFileHeader<N_PAGES>>();
-            self.file.seek(SeekFrom::End(0)).unwrap();
-            let cur_position = self.file.stream_position().unwrap();
+            self.file.seek(SeekFrom::End(0))?;
+            let cur_position = self.file.stream_position()?;
 
             let cur_position = cur_position as usize - offset;
 
-            let cur_idx = cur_position / 4;
+            let cur_idx = cur_position / self.header.page_size;
 
             let new_idx = cur_idx + 1;
 
-            self.file.write(buffer).unwrap();
+            self.file.write_all(buffer)?;
 
             self.header.indices[page_number] = new_idx;
 
@@ -186,11 +268,10 @@ impl<const N_PAGES: usize> PageLoader for SwapFilePageLoader<N_PAGES> {
             let indices_offset = (2 * sz) + (page_number * sz);
 
             self.file
-                .seek(SeekFrom::Start(indices_offset.try_into().unwrap()))
-                .unwrap();
+                .seek(SeekFrom::Start(indices_offset.try_into().unwrap()))?;
             let bytes = new_idx.to_le_bytes();
 
-            self.file.write(&bytes).unwrap();
+            self.file.write_all(&bytes)
         } else {
             // Aqui é mais fácil -- a página já existe no arquivo. Vamos só atualizar
             // a seção de dados calculando sua posição no arquivo e sobrescrevendo page_size
@@ -200,10 +281,137 @@ impl<const N_PAGES: usize> PageLoader for SwapFilePageLoader<N_PAGES> {
             let offset = (self.header.indices[page_number] - 1) * self.header.page_size;
 
             self.file
-                .seek(SeekFrom::Start((starting_idx + offset).try_into().unwrap()))
-                .unwrap();
+                .seek(SeekFrom::Start((starting_idx + offset).try_into().unwrap()))?;
 
-            self.file.write(buffer).unwrap();
+            self.file.write_all(buffer)
         }
     }
+
+    /// Escreve várias páginas de uma vez. As que já estão no arquivo são
+    /// sobrescritas no lugar, como em `flush`; as novas vão juntas para o
+    /// fim da seção de dados, numa escrita só, e a lista de índices é
+    /// regravada uma vez, em vez de uma vez por página.
+    fn flush_batch(&mut self, pages: &[(usize, &[u8])]) -> io::Result<()> {
+        let mut new_pages: Vec<usize> = Vec::new();
+        let mut appended: Vec<u8> = Vec::new();
+
+        for &(page_number, buffer) in pages {
+            if self.header.indices[page_number] != 0 {
+                self.flush(page_number, buffer)?;
+            } else if let Some(position) = new_pages.iter().position(|&page| page == page_number) {
+                // A mesma página nova duas vezes no lote: vale a última.
+                let start = position * self.header.page_size;
+                appended[start..start + buffer.len()].copy_from_slice(buffer);
+            } else {
+                new_pages.push(page_number);
+                appended.extend_from_slice(buffer);
+            }
+        }
+
+        if new_pages.is_empty() {
+            return Ok(());
+        }
+
+        let offset = std::mem::size_of::<SwapFileHeader<N_PAGES>>();
+        self.file.seek(SeekFrom::End(0))?;
+        let cur_position = self.file.stream_position()? as usize - offset;
+        let first_idx = cur_position / self.header.page_size + 1;
+
+        self.file.write_all(&appended)?;
+
+        for (i, &page_number) in new_pages.iter().enumerate() {
+            self.header.indices[page_number] = first_idx + i;
+        }
+
+        let indices: Vec<u8> = self
+            .header
+            .indices
+            .iter()
+            .flat_map(|index| index.to_le_bytes())
+            .collect();
+
+        self.file.seek(SeekFrom::Start(
+            (2 * std::mem::size_of::<usize>()).try_into().unwrap(),
+        ))?;
+        self.file.write_all(&indices)
+    }
+}
+
+impl<const N_PAGES: usize> PageLoader for SwapFilePageLoader<N_PAGES> {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        if let Err(error) = self.try_load_page_into(page_number, target) {
+            panic!("{}", error);
+        }
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        if let Err(error) = self.try_flush_page(page_number, buffer) {
+            panic!("{}", error);
+        }
+    }
+
+    fn backend(&self) -> &str {
+        "file"
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        Some(self.header.page_size)
+    }
+
+    // As escritas são feitas na hora, segurando o acesso, mas várias páginas
+    // novas saem numa escrita só (veja `flush_batch`). Páginas ausentes
+    // são lidas como zeros.
+    fn capabilities(&self) -> LoaderCapabilities {
+        LoaderCapabilities {
+            batch_flush: true,
+            asynchronous: false,
+            sparse: true,
+            max_pages: Some(N_PAGES),
+            page_size: Some(self.header.page_size),
+        }
+    }
+
+    fn try_load_page_into(
+        &mut self,
+        page_number: usize,
+        target: &mut [u8],
+    ) -> Result<(), LoaderError> {
+        self.check_buffer(LoaderOperation::Load, page_number, target.len())?;
+
+        if self.index(LoaderOperation::Load, page_number)? == 0 {
+            // 0 significa que a página nao esta presente. No mundo real
+            // isso iria causar violação de acesso + crash, mas aqui
+            // vamos preencher com 0.
+            target.fill(0);
+
+            return Ok(());
+        }
+
+        self.load(page_number, target)
+            .map_err(|error| Self::error(LoaderOperation::Load, page_number, error))
+    }
+
+    fn try_flush_page(&mut self, page_number: usize, buffer: &[u8]) -> Result<(), LoaderError> {
+        self.check_buffer(LoaderOperation::Flush, page_number, buffer.len())?;
+        self.index(LoaderOperation::Flush, page_number)?;
+
+        self.flush(page_number, buffer)
+            .map_err(|error| Self::error(LoaderOperation::Flush, page_number, error))
+    }
+
+    fn try_flush_pages(&mut self, pages: &[(usize, &[u8])]) -> Result<(), LoaderError> {
+        for &(page_number, buffer) in pages {
+            self.check_buffer(LoaderOperation::Flush, page_number, buffer.len())?;
+            self.index(LoaderOperation::Flush, page_number)?;
+        }
+
+        // Uma falha no meio do lote é atribuída à primeira página.
+        self.flush_batch(pages).map_err(|error| {
+            Self::error(
+                LoaderOperation::Flush,
+                pages.first().map_or(0, |page| page.0),
+                error,
+            )
+        })
+    }
 }
@@ -13,9 +13,239 @@
 //!
 //! - `r <address>`: lê o byte no endereço `<address>` e apresenta na stdout;
 //! - `w <address> <byte>`: escreve o byte `<byte>` em `<address>`;
+//! - `x <address>`: busca o byte de instrução em `<address>`, como `r`, mas
+//!   precisando da permissão de execução da página;
+//! - `m <pid> <nome> <página> <n> [permissões]`: mapeia a região `<nome>` do
+//!   processo `<pid>`, com `<n>` páginas a partir da página `<página>`. As
+//!   permissões (como `r--`, `r-x` ou `rw-`, o padrão) não são impostas, mas
+//!   os acessos que as contrariam aparecem no relatório de permissões, no
+//!   final;
+//! - `u <pid> <nome>`: desmapeia a região `<nome>` do processo `<pid>`,
+//!   liberando suas páginas;
+//! - `swappiness <pid> <nome> <valor>`: muda a swappiness da região `<nome>`
+//!   do processo `<pid>`, de 0 a 100 (em decimal): com 100, o padrão, suas
+//!   páginas saem sempre que o replacer as escolhe; com menos, elas ganham
+//!   mais chances (veja `vm::region`);
+//! - `qos <pid> [frames] [borrow]`: reserva `<frames>` frames só para as
+//!   páginas do processo `<pid>`, isolados ou, com `borrow`, podendo crescer
+//!   pelo pool compartilhado; sem `<frames>`, tira a reserva. O tamanho, a
+//!   ocupação e a taxa de page faults de cada partição aparecem no final
+//!   (veja `vm::partition`);
+//! - `sys <chamada> ...`: faz uma chamada de sistema, como um programa de
+//!   usuário (veja `vm::syscall`), e mostra o resultado ou o erro:
+//!   - `sys spawn <n>`: cria um processo com `<n>` páginas reservadas para o
+//!     heap, mostrando o seu pid;
+//!   - `sys brk <pid> <address>` / `sys sbrk <pid> <incremento>`: move o fim
+//!     do heap do processo para `<address>`, ou `<incremento>` bytes (que
+//!     pode ser negativo, como `-0x100`);
+//!   - `sys mmap <pid> <n> [permissões]` / `sys munmap <pid> <address>`:
+//!     mapeia `<n>` páginas anônimas, mostrando o endereço, ou desmapeia o
+//!     mapeamento que começa em `<address>`;
+//!   - `sys fork <pid>` / `sys exit <pid>`: cria um filho do processo, com
+//!     as páginas compartilhadas com copy-on-write, ou termina o processo;
+//!   - `sys stack <pid> <address>`: estende a pilha do processo para baixo
+//!     até `<address>`, como numa falta logo abaixo dela;
+//!   - `sys limit <pid> <heap|stack|rss> [bytes]`: limita o tamanho do
+//!     heap, da pilha ou do resident set do processo, ou tira o limite. As
+//!     vezes em que os limites recusaram memória aparecem no final;
+//! - `malloc <pid> <n>` / `free <pid> <address>`: aloca `<n>` bytes no
+//!   heap do processo (criado com `sys spawn`), mostrando o endereço, ou
+//!   libera o bloco em `<address>`. Os cabeçalhos dos blocos ficam na
+//!   memória do processo, então o alocador também faz acessos (veja
+//!   `vm::heap`);
+//! - `heap <pid> [first-fit|buddy]`: escolhe a estratégia do alocador do
+//!   processo, antes do primeiro `malloc` (o padrão é first-fit), ou mostra
+//!   o uso e a fragmentação do heap. Os heaps são mostrados também no final;
+//! - `top`: mostra o tamanho virtual, o resident set size e a memória
+//!   compartilhada de cada processo, e quantos frames o compartilhamento
+//!   economiza;
+//! - `share <src> <dst>`: faz a página `<dst>` compartilhar, com
+//!   copy-on-write, o frame da página `<src>` (veja `vm::sharing`);
+//! - `swapout <pid>`: tira da memória todas as páginas do processo `<pid>`,
+//!   guardando o seu working set;
+//! - `swapin <pid> [demanda]`: traz o processo `<pid>` de volta, carregando
+//!   o working set guardado, ou só por demanda com `demanda`;
+//! - `flush <página>` / `sync`: escreve no swap file a página, ou todas as
+//!   páginas residentes sujas, que continuam em memória, limpas. No final, a
+//!   demo faz um `sync`, para que as escritas não se percam (veja
+//!   `Mmu::flush_all`);
+//! - `t`: um tick do relógio, que envelhece as páginas residentes;
+//! - `pin <página>` / `unpin <página>`: fixa a página em memória (carregando-a
+//!   se preciso), ou a libera para ser substituída de novo;
+//! - `soft <página> <bit> [0|1]`: mostra, liga ou desliga um bit de software
+//!   da página (de 0 a 3), uma marca guardada na page table que sobrevive à
+//!   substituição da página (veja `Mmu::set_software_bit`). Os bits ligados
+//!   aparecem no estado da page table (`s`);
+//! - `mode <s|u>`: troca a CPU para o modo supervisor (`s`) ou usuário
+//!   (`u`), no qual os próximos acessos são feitos;
+//! - `supervisor <página> <n>`: marca `<n>` páginas a partir de `<página>`
+//!   como só do supervisor. Os acessos a elas em modo usuário falham, e são
+//!   listados no final (veja `vm::privilege`);
+//! - `prot <página> <n> <permissões>`: muda a proteção de `<n>` páginas a
+//!   partir de `<página>` (como `r-x` para código e `rw-` para dados, com
+//!   W^X). Os acessos que ela não permite falham com uma falta de proteção,
+//!   como um segfault (veja `Mmu::set_protection`);
+//! - `mprotect <endereço> <n> <permissões>`: muda a proteção dos `<n>` bytes
+//!   a partir de `<endereço>`, como o `mprotect`. As páginas cobertas só em
+//!   parte são protegidas inteiras (veja `Mmu::mprotect`). Com `---`, faz
+//!   uma página de guarda;
+//! - `dma r <frame> <offset>` / `dma w <frame> <offset> <byte>`: lê ou escreve
+//!   direto no frame, sem tradução, como um dispositivo de DMA. Acessos a
+//!   frames livres ou de páginas não fixadas são listados no final (veja
+//!   `vm::dma`);
+//! - `io map <página de I/O> <página>` / `io unmap <página de I/O>`: fixa a
+//!   página e a mapeia na IOMMU, ou desfaz isso;
+//! - `io r <iova>` / `io w <iova> <byte>`: lê ou escreve como um dispositivo
+//!   atrás da IOMMU, pelo endereço de I/O (veja `vm::iommu`); acessos a
+//!   páginas de I/O não mapeadas são bloqueados;
+//! - `age`: mostra o histórico do bit de referência de cada página residente;
+//! - `audit [n]`: mostra as últimas `n` (padrão 1) decisões de substituição,
+//!   da mais recente para a mais antiga, com o motivo e o estado do replacer
+//!   antes de cada uma;
+//! - `g <padrão> <n>`: gera e executa `<n>` acessos sintéticos, onde
+//!   `<padrão>` é `uniform`, `loop` (sobre 16 páginas), `zipf` (expoente 1)
+//!   ou `hotcold` (90% dos acessos em 10% das páginas);
+//! - `trace <nome>`: executa os acessos de um trace dos fixtures (veja
+//!   `vm::fixtures`), por exemplo `trace loop_12_pages`;
+//! - `s`: mostra o estado da MMU (frames, page table e replacer);
+//! - `state <arquivo>`: guarda as páginas residentes, com seus frames e
+//!   flags, em `<arquivo>`, como TOML (veja `vm::state`), para começar outra
+//!   execução do mesmo ponto com `--state`;
+//! - `rewind <n>`: volta a simulação `<n>` acessos no tempo, restaurando
+//!   memória, page table, replacer, estatísticas e o arquivo de swap;
+//! - `cp <dst> <src> <n>`: copia `<n>` bytes de `<src>` para `<dst>` (os
+//!   trechos podem se sobrepor), com um acesso por página em cada ponta;
+//! - `fill <dst> <byte> <n>`: preenche `<n>` bytes a partir de `<dst>` com
+//!   `<byte>`, com um acesso por página;
+//! - `load <address> <arquivo>`: copia o conteúdo de `<arquivo>` para a
+//!   memória virtual a partir de `<address>`, sem contar como acessos;
+//! - `e <formato> <arquivo>`: exporta o trace dos acessos feitos até agora
+//!   para `<arquivo>`, onde `<formato>` é `csv`, `lackey` (valgrind),
+//!   `champsim` ou `timeline` (ocupação dos frames ao longo do tempo, em JSON);
 //!
-//! Note que todos os valores *são em hexadecimal*. Outros valores causarão um
-//! panic na aplicação.
+//! Note que todos os endereços e bytes *são em hexadecimal*. Outros valores
+//! causarão um panic na aplicação.
+//!
+//! Se o carregador falhar numa leitura ou escrita (o swap file não pôde ser
+//! lido, por exemplo), a demo mostra o acesso, a página, o frame e a causa,
+//! e continua com o próximo comando.
+//!
+//! ## Opções
+//!
+//! - `-v`: modo verboso. Cada acesso mostra também o que a MMU fez, por
+//!   exemplo `HIT` ou `FAULT (evicted 0x3A, writeback)`, sem precisar
+//!   habilitar os logs com `RUST_LOG`.
+//! - `--strict`: modo estrito. Acessos a páginas fora de regiões mapeadas
+//!   (nunca mapeadas, ou usadas depois de `u`) são listados no final, com o
+//!   histórico da região.
+//! - `--state <arquivo>`: começa com as páginas residentes, os frames e as
+//!   flags descritos em `<arquivo>`, um documento TOML (veja `vm::state`)
+//!   escrito à mão ou pelo comando `state`. Serve para montar exatamente a
+//!   configuração inicial de um exercício; o documento precisa ter
+//!   `frames = 256`.
+//! - `--pt <frame>`: coloca a page table dentro da memória física, nos
+//!   frames a partir de `<frame>` (em hexadecimal), e a mapeia, somente
+//!   leitura, nas páginas virtuais 0xFC a 0xFF. Assim `r 0xFC00` lê o
+//!   frame da página 0, `r 0xFC02` suas flags e `r 0xFC03` sua proteção
+//!   (veja `PageTableEntry::encode`).
+//! - `--tlb <entradas>`: simula um TLB com esse número de entradas (em
+//!   hexadecimal). No final, mostra a taxa de acerto, o reach e quanto do
+//!   working set ele cobre.
+//! - `--ghost <entradas>`: guarda numa lista fantasma as últimas páginas
+//!   substituídas (esse número, em hexadecimal), e separa as page faults em
+//!   misses frios e de capacidade (veja `vm::ghost`).
+//! - `--3c`: classifica cada miss como frio, de capacidade ou de conflito, e
+//!   mostra a divisão no final (veja `vm::three_cs`).
+//! - `--thp`: habilita as páginas grandes transparentes: a cada tick, trechos
+//!   alinhados de 8 páginas residentes viram uma página grande, que ocupa
+//!   uma entrada só do TLB (veja `vm::thp`).
+//! - `--tiers <frames>`: divide a memória em camadas: os primeiros frames
+//!   (esse número, em hexadecimal) são rápidos e o resto é lento. A cada
+//!   tick, as páginas lentas com pelo menos 4 acessos desde o tick anterior
+//!   sobem para a camada rápida, até 4 por tick. No final, mostra a fração
+//!   dos acessos de cada camada e as migrações (veja `vm::tier`).
+//! - `--loader <nome>`: o carregador de páginas. `file` (o padrão) usa o
+//!   arquivo `swapfile.bin`; `overlay` lê do `swapfile.bin` mas guarda os
+//!   writebacks à parte, descartados no final, sem alterar o arquivo (veja
+//!   `OverlayPageLoader`); `memory` guarda as páginas só na memória,
+//!   começando zeradas, sem tocar no arquivo; `fixture:<nome>` também, mas
+//!   começando com uma imagem de swap dos fixtures, como
+//!   `fixture:page_numbers_16`. O arquivo escreve na hora, então com ele o
+//!   `wsclock` não limpa páginas em segundo plano (veja
+//!   `LoaderCapabilities`).
+//! - `--replacer <nome>`: a política de substituição: `fifo` (o padrão),
+//!   `clock`, `clock-pro`, `lru`, `lru-2`, `mru`, `lfu`, `mfu`, `s3-fifo`,
+//!   `2q`, `slru`, `nru`, `aging`, `ws`, `wsclock`, `random` ou `adaptive`,
+//!   que alterna entre o LRU e o LFU (veja `ReplacerRegistry` e
+//!   `AdaptivePageReplacer`).
+//! - `--seed <semente>`: a semente (em hexadecimal, padrão 0) de tudo que é
+//!   aleatório na execução, como o comando `g`. A mesma entrada com a mesma
+//!   semente produz sempre as mesmas estatísticas.
+//!
+//! ## Cenários
+//!
+//! `cargo run -- scenario <nome>` executa um cenário pronto (veja
+//! `vm::scenario`) em vez de ler comandos da entrada padrão:
+//!
+//! - `defrag`: fragmenta a memória física alocando e liberando regiões, e
+//!   mostra o efeito da compactação.
+//! - `virt`: executa uma carga numa Mmu de guest sobre uma Mmu de host, com
+//!   paginação aninhada e com shadow page tables, e compara o tempo efetivo
+//!   de acesso das duas com o nativo.
+//! - `migrate`: migra a memória de um guest em execução, copiando de novo
+//!   as páginas escritas durante cada rodada até convergir.
+//! - `balloon`: repete a migração com taxas de escrita diferentes, com e sem
+//!   balão, e estima o tempo total e o downtime de cada uma.
+//! - `swap`: tira um processo da memória e o traz de volta, por demanda e
+//!   com o working set guardado na saída, e compara os refaults.
+//! - `thp`: executa a mesma carga com e sem páginas grandes transparentes, e
+//!   compara os misses do TLB.
+//! - `replacers`: executa as mesmas cargas com cada política de
+//!   substituição (FIFO, relógio, LFU com e sem decaimento, MFU, MRU,
+//!   S3-FIFO, NRU, Aging, working set, WSClock, 2Q, SLRU, LRU-2, Clock-Pro,
+//!   aleatória e a adaptativa entre LRU e LFU) e compara os misses entre si
+//!   e com os do algoritmo ótimo, e compara o LRU exato com relógios de k
+//!   bits de referência; depois, repete uma carga com uma região protegida
+//!   por swappiness cada vez menor, e uma carga em duas fases trocando de
+//!   política no meio. Por fim, mostra, para cada política, a idade das
+//!   vítimas, a distância até o refault e os misses pelos três Cs (frios, de
+//!   capacidade e de conflito).
+//! - `tiers`: executa uma carga cujo conjunto quente muda no meio, numa
+//!   memória com um quarto dos frames rápidos, sem migrações e com a
+//!   promoção das páginas quentes, e compara a fração dos acessos servida
+//!   pela camada rápida e o tempo médio de acesso.
+//! - `partitions`: dois processos dividem a memória em três fases (com um
+//!   vizinho barulhento, com um processo ocioso e com um que cresce), sem
+//!   reserva, com uma reserva isolada e com uma que empresta frames do pool
+//!   compartilhado, e compara a taxa de page faults de cada processo.
+//! - `persistence`: escreve um padrão em muito mais páginas do que cabem na
+//!   memória, desliga a Mmu escrevendo as páginas sujas, e confere que uma
+//!   nova Mmu sobre o mesmo carregador lê de volta cada byte.
+//! - `fork [porcentagem]`: faz um fork com copy-on-write e um exec, com o
+//!   filho escrevendo na porcentagem dada das páginas (por padrão, 0%, 10%,
+//!   25%, 50% e 100%), e mostra quantos frames o COW economiza e quanto
+//!   custam as quebras.
+//! - `belady [páginas...]`: executa a sequência de páginas dada (em
+//!   decimal; por padrão, a clássica 1 2 3 4 1 2 5 1 2 3 4 5) com o FIFO, em
+//!   memórias de 1 até um frame por página distinta (no máximo 16), e
+//!   mostra onde um frame a mais levou a mais faltas (veja
+//!   `vm::simulate::belady_anomaly`).
+//! - `diff <trace> <política> <política> [frames]`: executa um trace dos
+//!   fixtures com as duas políticas de substituição, numa memória de
+//!   `[frames]` frames (em decimal, 8 por padrão), e mostra onde elas se
+//!   separaram: o primeiro acesso tratado de forma diferente, a diferença
+//!   acumulada de page faults e as páginas mais afetadas (veja
+//!   `vm::trace_diff`).
+//!
+//! ## Swap file
+//!
+//! `cargo run -- swapctl <comando>` manipula swap files (veja
+//! `file_page_loader`) em vez de executar a demo:
+//!
+//! - `create <arquivo>`: cria um swap file vazio com páginas de 256 bytes,
+//!   o tamanho das páginas da Mmu (veja `vm::mmu::PAGE_SIZE`).
+//! - `info <arquivo>`: mostra o número de páginas, o tamanho delas e
+//!   quantas estão guardadas no arquivo.
 //!
 //! ### Exemplo
 //!
@@ -29,10 +259,34 @@
 
 mod file_page_loader;
 
-use std::io::BufRead;
+use std::{cell::Cell, collections::BTreeMap, fs::File, io::BufRead, rc::Rc};
 
-use vm::{mmu::Mmu, page_loader::PageLoader, page_replacer::FIFOPageReplacer};
+use vm::{
+    analysis::LocalityReport,
+    cost::CostModel,
+    dma::DmaCheck,
+    fixtures,
+    heap::{Heap, Strategy},
+    iommu::{Iommu, IommuStats},
+    mmu::Mmu,
+    overlay_loader::OverlayPageLoader,
+    page_loader::{LoaderRegistry, PageLoader},
+    page_replacer::ReplacerRegistry,
+    page_table::SOFTWARE_BITS,
+    partition::Reservation,
+    privilege::PrivilegeMode,
+    region::Permissions,
+    rng::Rng,
+    scenario, simulate,
+    state::PageTableState,
+    syscall::{Kernel, Resource, SyscallError},
+    tier::{PromotionPolicy, TierConfig},
+    timeline::FrameTimeline,
+    trace::AccessRecord,
+    workload::{Pattern, WorkloadGenerator},
+};
 
+#[allow(dead_code)]
 struct StubPageLoader;
 
 impl PageLoader for StubPageLoader {
@@ -50,36 +304,309 @@ impl PageLoader for StubPageLoader {
     }
 }
 
+/// Executa um comando de `swapctl` (veja a seção "Swap file" acima).
+fn swapctl(args: &[String]) {
+    type SwapFile = file_page_loader::SwapFilePageLoader<256>;
+
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("create"), Some(filename)) => match SwapFile::create(filename) {
+            Ok(_) => println!(
+                "swapctl: {} criado com 256 páginas de {} bytes",
+                filename,
+                vm::mmu::PAGE_SIZE
+            ),
+            Err(err) => println!("swapctl: {}: {}", filename, err),
+        },
+        (Some("info"), Some(filename)) => match SwapFile::new(filename) {
+            Ok(swapfile) => {
+                println!("páginas: 256");
+                println!("tamanho de página: {} bytes", swapfile.page_size().unwrap());
+                println!("páginas guardadas: {}", swapfile.stored_pages());
+            }
+            Err(err) => println!("swapctl: {}: {}", filename, err),
+        },
+        _ => println!("uso: swapctl create <arquivo> | swapctl info <arquivo>"),
+    }
+}
+
+/// Descreve o resultado de um acesso: `HIT`, `FAULT` ou
+/// `FAULT (evicted 0x3A, writeback)`.
+fn describe_access(record: &AccessRecord) -> String {
+    if record.hit {
+        return "HIT".to_string();
+    }
+
+    match record.evicted {
+        Some(page) if record.writeback => format!("FAULT (evicted {:#04X}, writeback)", page),
+        Some(page) => format!("FAULT (evicted {:#04X})", page),
+        None => "FAULT".to_string(),
+    }
+}
+
+fn belady(args: &[String]) {
+    let reference: Vec<usize> = if args.is_empty() {
+        vec![1, 2, 3, 4, 1, 2, 5, 1, 2, 3, 4, 5]
+    } else {
+        match args.iter().map(|arg| arg.parse()).collect() {
+            Ok(reference) => reference,
+            Err(_) => {
+                println!("sequência inválida: {}", args.join(" "));
+                return;
+            }
+        }
+    };
+
+    // Com um frame por página distinta, só há misses frios, e mais frames
+    // não mudam nada.
+    let mut distinct = reference.clone();
+    distinct.sort_unstable();
+    distinct.dedup();
+
+    match simulate::belady_anomaly(&reference, distinct.len().clamp(1, 16)) {
+        Ok(report) => report.print_report(),
+        Err(err) => println!("belady: {}", err),
+    }
+}
+
+fn diff(args: &[String]) {
+    let [trace, a, b, rest @ ..] = args else {
+        println!("uso: diff <trace> <política> <política> [frames]");
+        return;
+    };
+
+    let frames = match rest.first().map(|arg| arg.parse()) {
+        None => 8,
+        Some(Ok(frames)) => frames,
+        Some(Err(_)) => {
+            println!("número de frames inválido: {}", rest[0]);
+            return;
+        }
+    };
+
+    let trace = match fixtures::trace(trace) {
+        Ok(trace) => trace,
+        Err(err) => {
+            println!("diff: {}", err);
+            return;
+        }
+    };
+
+    match simulate::diff_replacers(&trace, simulate::Geometry::new(frames), [a, b]) {
+        Ok(diff) => diff.print_report(),
+        Err(err) => println!("diff: {}", err),
+    }
+}
+
 fn main() {
     env_logger::init();
 
-    let swapfile = file_page_loader::SwapFilePageLoader::<256>::new(&"./swapfile.bin").unwrap();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("scenario") {
+        match args.get(1).map(String::as_str) {
+            Some("defrag") => scenario::defragmentation().print_report(),
+            Some("virt") => scenario::virtualization().print_report(),
+            Some("migrate") => scenario::live_migration().print_report(),
+            Some("balloon") => scenario::balloon_migration().print_report(),
+            Some("thp") => scenario::transparent_huge_pages().print_report(),
+            Some("swap") => scenario::process_swap().print_report(),
+            Some("replacers") => scenario::replacement_policies().print_report(),
+            Some("tiers") => scenario::tiered_memory().print_report(),
+            Some("partitions") => scenario::frame_partitions().print_report(),
+            Some("persistence") => scenario::persistence().print_report(),
+            Some("fork") => match args.get(2).map(|arg| arg.parse::<f64>()) {
+                None => scenario::fork_exec(&[0.0, 0.1, 0.25, 0.5, 1.0]).print_report(),
+                Some(Ok(percent)) => scenario::fork_exec(&[percent / 100.0]).print_report(),
+                Some(Err(_)) => println!("porcentagem inválida: {}", args[2]),
+            },
+            Some("belady") => belady(&args[2..]),
+            Some("diff") => diff(&args[2..]),
+            other => println!("cenário inválido: {}", other.unwrap_or("")),
+        }
+
+        return;
+    }
+
+    if args.first().map(String::as_str) == Some("swapctl") {
+        swapctl(&args[1..]);
+        return;
+    }
+
+    let verbose = args.iter().any(|arg| arg == "-v");
+    let strict = args.iter().any(|arg| arg == "--strict");
+    let seed = args
+        .iter()
+        .position(|arg| arg == "--seed")
+        .map(|i| u64::from_str_radix(&args[i + 1][2..], 16).unwrap())
+        .unwrap_or(0);
+
+    // Cada comando `g` usa uma semente própria, derivada da semente da execução.
+    let mut workload_seeds = Rng::for_component(seed, "demo");
+
+    let loader_name = args
+        .iter()
+        .position(|arg| arg == "--loader")
+        .map(|i| args[i + 1].as_str())
+        .unwrap_or("file");
+
+    let mut loaders = LoaderRegistry::new();
+    loaders.register("file", || {
+        let swapfile = file_page_loader::SwapFilePageLoader::<256>::new(&"./swapfile.bin")?;
+        Ok(Box::new(swapfile))
+    });
+    loaders.register("overlay", || {
+        let swapfile = file_page_loader::SwapFilePageLoader::<256>::new(&"./swapfile.bin")?;
+        Ok(Box::new(OverlayPageLoader::new(swapfile)))
+    });
+
+    let loader = match loaders.create(loader_name) {
+        Ok(loader) => loader,
+        Err(err) => {
+            println!("--loader: {}", err);
+            println!(
+                "carregadores disponíveis: {}",
+                loaders.names().collect::<Vec<_>>().join(", ")
+            );
+            return;
+        }
+    };
+
+    let replacer_name = args
+        .iter()
+        .position(|arg| arg == "--replacer")
+        .map(|i| args[i + 1].as_str())
+        .unwrap_or("fifo");
+
+    let replacers = ReplacerRegistry::new();
+    let Some(replacer) = replacers.create(replacer_name, seed) else {
+        println!("--replacer: política desconhecida: {}", replacer_name);
+        println!(
+            "políticas disponíveis: {}",
+            replacers.names().collect::<Vec<_>>().join(", ")
+        );
+        return;
+    };
 
     // Cria uma MMU com:
     // - 65536 bytes (64kb) de memória...;
     // - ...divididos em 256 frames...;
     // - ...populados por 256 páginas.
-    let mut mmu = Mmu::<65536, 256, 256, _, _>::new(FIFOPageReplacer::new(), swapfile);
+    let mut mmu = match Mmu::<65536, 256, 256, _, _>::try_new(replacer, loader) {
+        Ok(mmu) => mmu,
+        Err(err) => {
+            println!("--loader: {}", err);
+            return;
+        }
+    };
 
     // Utilize essa construção para modificar o arquivo swap (veja README.md)
-    //let mut mmu = Mmu::<256, 1, 256, _, _>::new(FIFOPageReplacer::new(), swapfile);
+    //let mut mmu = Mmu::<256, 1, 256, _, _>::new(FIFOPageReplacer::new(), loader);
+
+    // Guarda todos os acessos, para que possam ser exportados com o comando `e`.
+    mmu.enable_trace();
+    mmu.set_strict(strict);
+    mmu.enable_audit(32);
+    mmu.enable_time_travel(64);
+
+    if let Some(i) = args.iter().position(|arg| arg == "--state") {
+        let filename = &args[i + 1];
+
+        let result = std::fs::read_to_string(filename)
+            .map_err(|err| err.to_string())
+            .and_then(|text| PageTableState::from_toml(&text).map_err(|err| err.to_string()))
+            .and_then(|state| mmu.load_state(&state).map_err(|err| err.to_string()));
+
+        if let Err(err) = result {
+            println!("--state: {}: {}", filename, err);
+        }
+    }
+
+    if let Some(i) = args.iter().position(|arg| arg == "--pt") {
+        let base_frame = usize::from_str_radix(&args[i + 1][2..], 16).unwrap();
+
+        match mmu.place_page_table_in_memory(base_frame) {
+            Ok(()) => mmu.map_page_table(0xFC),
+            Err(err) => println!("--pt: {}", err),
+        }
+    }
+
+    if args.iter().any(|arg| arg == "--thp") {
+        mmu.enable_thp();
+    }
+
+    if args.iter().any(|arg| arg == "--3c") {
+        mmu.enable_three_cs();
+    }
+
+    if let Some(i) = args.iter().position(|arg| arg == "--ghost") {
+        let entries = usize::from_str_radix(&args[i + 1][2..], 16).unwrap();
+        mmu.enable_ghost_list(entries);
+    }
+
+    if let Some(i) = args.iter().position(|arg| arg == "--tlb") {
+        let entries = usize::from_str_radix(&args[i + 1][2..], 16).unwrap();
+        mmu.enable_tlb(entries);
+    }
+
+    if let Some(i) = args.iter().position(|arg| arg == "--tiers") {
+        let fast_frames = usize::from_str_radix(&args[i + 1][2..], 16).unwrap();
+        mmu.enable_tiers(TierConfig::new(
+            fast_frames,
+            PromotionPolicy::HotPages {
+                threshold: 4,
+                budget: 4,
+            },
+        ));
+    }
+
+    // Os dispositivos do comando `io` acessam a memória por esta IOMMU.
+    let mut iommu = Iommu::<256>::new();
+    let mut kernel = Kernel::new();
+    let mut heaps = BTreeMap::new();
+
+    // Guarda o último acesso feito, para o modo verboso.
+    let last_access = Rc::new(Cell::new(None));
+    {
+        let last_access = last_access.clone();
+        mmu.add_observer(move |record: &AccessRecord| last_access.set(Some(*record)));
+    }
 
     let mut stdin = std::io::stdin().lock();
     let mut line = String::new();
 
-    while let Ok(_) = stdin.read_line(&mut line) {
+    while stdin.read_line(&mut line).is_ok() {
         let mut tokens = line.split(" ");
 
-        let cmd = tokens.next().unwrap_or("INVALID");
+        let cmd = tokens.next().unwrap_or("INVALID").trim_end();
 
         match cmd {
-            "r" => {
+            "r" | "x" => {
                 let address = tokens.next().unwrap().trim();
                 let address = usize::from_str_radix(&address[2..], 16).unwrap();
 
-                let value = mmu.read(address);
+                let value = match cmd {
+                    "r" => mmu.read(address),
+                    _ => mmu.fetch(address),
+                };
+                let value = match value {
+                    Ok(value) => value,
+                    Err(error) => {
+                        println!("erro: {}", error);
+                        last_access.take();
+                        line.clear();
+                        continue;
+                    }
+                };
 
-                println!("{:#06X} => {:#X}", address, value);
+                match last_access.take() {
+                    Some(record) if verbose => println!(
+                        "{:#06X} => {:#X}  {}",
+                        address,
+                        value,
+                        describe_access(&record)
+                    ),
+                    _ => println!("{:#06X} => {:#X}", address, value),
+                }
             }
             "w" => {
                 let address = tokens.next().unwrap().trim();
@@ -88,7 +615,546 @@ fn main() {
                 let value = tokens.next().unwrap().trim();
                 let value = u8::from_str_radix(&value[2..], 16).unwrap();
 
-                mmu.write(address, value);
+                if let Err(error) = mmu.write(address, value) {
+                    println!("erro: {}", error);
+                }
+
+                if let Some(record) = last_access.take().filter(|_| verbose) {
+                    println!(
+                        "{:#06X} <= {:#X}  {}",
+                        address,
+                        value,
+                        describe_access(&record)
+                    );
+                }
+            }
+            "m" => {
+                let pid = tokens.next().unwrap().trim();
+                let pid = usize::from_str_radix(&pid[2..], 16).unwrap();
+
+                let name = tokens.next().unwrap().trim();
+
+                let first_page = tokens.next().unwrap().trim();
+                let first_page = usize::from_str_radix(&first_page[2..], 16).unwrap();
+
+                let page_count = tokens.next().unwrap().trim();
+                let page_count = usize::from_str_radix(&page_count[2..], 16).unwrap();
+
+                let permissions = match tokens.next().map(str::trim) {
+                    Some(permissions) => match Permissions::parse(permissions) {
+                        Some(permissions) => permissions,
+                        None => {
+                            println!("permissões inválidas: {}", permissions);
+                            line.clear();
+                            continue;
+                        }
+                    },
+                    None => Permissions::READ_WRITE,
+                };
+
+                if let Err(err) = mmu.map_region_with_permissions(
+                    pid,
+                    name,
+                    first_page..first_page + page_count,
+                    permissions,
+                ) {
+                    println!("m: {}", err);
+                }
+            }
+            "u" => {
+                let pid = tokens.next().unwrap().trim();
+                let pid = usize::from_str_radix(&pid[2..], 16).unwrap();
+
+                let name = tokens.next().unwrap().trim();
+
+                mmu.unmap_region(pid, name);
+            }
+            "swappiness" => {
+                let pid = tokens.next().unwrap().trim();
+                let pid = usize::from_str_radix(&pid[2..], 16).unwrap();
+
+                let name = tokens.next().unwrap().trim();
+                let swappiness = tokens.next().unwrap().trim().parse().unwrap();
+
+                if !mmu.set_region_swappiness(pid, name, swappiness) {
+                    println!("região inválida: {}", name);
+                }
+            }
+            "qos" => {
+                let pid = tokens.next().unwrap().trim();
+                let pid = usize::from_str_radix(&pid[2..], 16).unwrap();
+
+                let reservation = tokens.next().map(str::trim).map(|frames| {
+                    let frames = usize::from_str_radix(&frames[2..], 16).unwrap();
+
+                    match tokens.next().map(str::trim) {
+                        Some("borrow") => Reservation::borrowing(frames),
+                        _ => Reservation::isolated(frames),
+                    }
+                });
+
+                if let Err(error) = mmu.set_frame_reservation(pid, reservation) {
+                    println!("erro: {}", error);
+                }
+            }
+            "top" => {
+                println!("{:>6} {:>10} {:>10} {:>10}", "PID", "VSZ", "RSS", "SHR");
+
+                for pid in mmu.regions().pids() {
+                    let stats = mmu.process_stats(pid);
+
+                    println!(
+                        "{:>6} {:>10} {:>10} {:>10}",
+                        pid,
+                        stats.virtual_size(),
+                        stats.resident_set_size(),
+                        stats.shared_pages * stats.page_size
+                    );
+                }
+
+                println!(
+                    "frames compartilhados: {}, economizados: {}, quebras de COW: {}",
+                    mmu.stats.shared_frames(),
+                    mmu.stats.saved_frames(),
+                    mmu.stats.cow_breaks()
+                );
+            }
+            "swapout" => {
+                let pid = tokens.next().unwrap().trim();
+                let pid = usize::from_str_radix(&pid[2..], 16).unwrap();
+
+                match mmu.swap_out_process(pid) {
+                    Ok(pages) => println!("swapout: {} páginas saíram da memória", pages),
+                    Err(err) => println!("swapout: {}", err),
+                }
+            }
+            "swapin" => {
+                let pid = tokens.next().unwrap().trim();
+                let pid = usize::from_str_radix(&pid[2..], 16).unwrap();
+
+                let prefault = tokens.next().map(str::trim) != Some("demanda");
+
+                match mmu.swap_in_process(pid, prefault) {
+                    Ok(pages) => println!("swapin: {} páginas carregadas", pages),
+                    Err(err) => println!("swapin: {}", err),
+                }
+            }
+            "flush" => {
+                let page = tokens.next().unwrap().trim();
+                let page = usize::from_str_radix(&page[2..], 16).unwrap();
+
+                match mmu.flush_page(page) {
+                    Ok(true) => println!("flush: página {:#04X} escrita", page),
+                    Ok(false) => println!("flush: a página {:#04X} não está suja", page),
+                    Err(err) => println!("flush: {}", err),
+                }
+            }
+            "sync" => match mmu.flush_all() {
+                Ok(pages) => println!("sync: {} páginas escritas", pages),
+                Err(err) => println!("sync: {}", err),
+            },
+            "t" => {
+                mmu.tick();
+            }
+            "share" => {
+                let src = tokens.next().unwrap().trim();
+                let src = usize::from_str_radix(&src[2..], 16).unwrap();
+                let dst = tokens.next().unwrap().trim();
+                let dst = usize::from_str_radix(&dst[2..], 16).unwrap();
+
+                match mmu.share_page(src, dst) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        println!("share: a página {:#04X} não pode ser compartilhada", src)
+                    }
+                    Err(error) => println!("share: erro: {}", error),
+                }
+            }
+            "pin" | "unpin" => {
+                let page = tokens.next().unwrap().trim();
+                let page = usize::from_str_radix(&page[2..], 16).unwrap();
+
+                if cmd == "pin" {
+                    if let Err(error) = mmu.pin_page(page) {
+                        println!("pin: erro: {}", error);
+                    }
+                } else {
+                    mmu.unpin_page(page);
+                }
+            }
+            "soft" => {
+                let mut values = tokens.map(|token| {
+                    let token = token.trim();
+                    usize::from_str_radix(&token[2..], 16).unwrap()
+                });
+                let page = values.next().unwrap();
+                let bit = values.next().unwrap() as u32;
+
+                if bit >= SOFTWARE_BITS {
+                    println!("soft: o bit {} não existe", bit);
+                } else if let Some(value) = values.next() {
+                    mmu.set_software_bit(page, bit, value != 0);
+                } else {
+                    println!(
+                        "soft: página {:#04X}, bit {}: {}",
+                        page,
+                        bit,
+                        mmu.software_bit(page, bit) as u8
+                    );
+                }
+            }
+            "sys" => {
+                let call = tokens.next().unwrap().trim();
+                let mut args = tokens.map(str::trim);
+                let mut hex = || {
+                    let token = args.next().unwrap();
+                    usize::from_str_radix(&token[2..], 16).unwrap()
+                };
+
+                let result = match call {
+                    "spawn" => kernel
+                        .spawn(&mmu, hex())
+                        .map(|pid| format!("pid {:#X}", pid)),
+                    "brk" => {
+                        let pid = hex();
+                        kernel
+                            .brk(&mut mmu, pid, hex())
+                            .map(|address| format!("break em {:#06X}", address))
+                    }
+                    "sbrk" => {
+                        let pid = hex();
+                        let increment = args.next().unwrap();
+                        let increment = match increment.strip_prefix('-') {
+                            Some(increment) => -isize::from_str_radix(&increment[2..], 16).unwrap(),
+                            None => isize::from_str_radix(&increment[2..], 16).unwrap(),
+                        };
+
+                        kernel
+                            .sbrk(&mut mmu, pid, increment)
+                            .map(|address| format!("break anterior em {:#06X}", address))
+                    }
+                    "mmap" => {
+                        let pid = hex();
+                        let pages = hex();
+                        let permissions = args
+                            .next()
+                            .map_or(Some(Permissions::READ_WRITE), Permissions::parse);
+
+                        match permissions {
+                            Some(permissions) => kernel
+                                .mmap(&mut mmu, pid, pages, permissions)
+                                .map(|address| format!("mapeado em {:#06X}", address)),
+                            None => Ok("permissões inválidas".to_string()),
+                        }
+                    }
+                    "munmap" => {
+                        let pid = hex();
+                        kernel
+                            .munmap(&mut mmu, pid, hex())
+                            .map(|()| "ok".to_string())
+                    }
+                    "fork" => kernel
+                        .fork(&mut mmu, hex())
+                        .map(|pid| format!("pid {:#X}", pid)),
+                    "exit" => kernel.exit(&mut mmu, hex()).map(|()| "ok".to_string()),
+                    "stack" => {
+                        let pid = hex();
+                        kernel
+                            .grow_stack(&mut mmu, pid, hex())
+                            .map(|address| format!("pilha a partir de {:#06X}", address))
+                    }
+                    "limit" => {
+                        let pid = hex();
+                        let resource = match args.next().unwrap() {
+                            "heap" => Some(Resource::Heap),
+                            "stack" => Some(Resource::Stack),
+                            "rss" => Some(Resource::Rss),
+                            _ => None,
+                        };
+                        let bytes = args
+                            .next()
+                            .map(|token| usize::from_str_radix(&token[2..], 16).unwrap());
+
+                        match (resource, kernel.limits(pid)) {
+                            (Some(resource), Some(mut limits)) => {
+                                limits.set(resource, bytes);
+                                kernel
+                                    .set_limits(&mut mmu, pid, limits)
+                                    .map(|()| format!("{} = {:?}", resource, bytes))
+                            }
+                            (None, _) => Ok("recurso inválido".to_string()),
+                            (_, None) => Err(SyscallError::NoSuchProcess(pid)),
+                        }
+                    }
+                    call => Ok(format!("chamada inválida: {}", call)),
+                };
+
+                match result {
+                    Ok(message) => println!("{}: {}", call, message),
+                    Err(error) => println!("{}: erro: {}", call, error),
+                }
+            }
+            "malloc" | "free" => {
+                let mut values = tokens.map(|token| {
+                    let token = token.trim();
+                    usize::from_str_radix(&token[2..], 16).unwrap()
+                });
+                let pid = values.next().unwrap();
+                let heap = heaps
+                    .entry(pid)
+                    .or_insert_with(|| Heap::new(pid, Strategy::default()));
+
+                let result = if cmd == "malloc" {
+                    heap.malloc(&mut kernel, &mut mmu, values.next().unwrap())
+                        .map(|ptr| format!("{:#06X}", ptr))
+                } else {
+                    heap.free(&mut mmu, values.next().unwrap())
+                        .map(|()| "ok".to_string())
+                };
+
+                match result {
+                    Ok(message) => println!("{}: {}", cmd, message),
+                    Err(error) => println!("{}: erro: {}", cmd, error),
+                }
+            }
+            "heap" => {
+                let pid = tokens.next().unwrap().trim();
+                let pid = usize::from_str_radix(&pid[2..], 16).unwrap();
+
+                match tokens.next().map(str::trim) {
+                    Some(_) if heaps.get(&pid).is_some_and(|heap| heap.bounds().is_some()) => {
+                        println!("heap: o heap do processo {} já está em uso", pid);
+                    }
+                    Some(strategy) => match Strategy::parse(strategy) {
+                        Some(strategy) => {
+                            heaps.insert(pid, Heap::new(pid, strategy));
+                        }
+                        None => println!("estratégia inválida: {}", strategy),
+                    },
+                    None => match heaps.get(&pid) {
+                        Some(heap) => {
+                            println!("===== Heap do processo {} ({}) =====", pid, heap.strategy());
+                            heap.stats(&mut mmu).print_report();
+                        }
+                        None => println!("heap: o processo {} não tem heap", pid),
+                    },
+                }
+            }
+            "mode" => match tokens.next().unwrap().trim() {
+                "s" => mmu.set_mode(PrivilegeMode::Supervisor),
+                "u" => mmu.set_mode(PrivilegeMode::User),
+                mode => println!("modo inválido: {}", mode),
+            },
+            "supervisor" => {
+                let page = tokens.next().unwrap().trim();
+                let page = usize::from_str_radix(&page[2..], 16).unwrap();
+                let count = tokens.next().unwrap().trim();
+                let count = usize::from_str_radix(&count[2..], 16).unwrap();
+
+                mmu.set_supervisor_only(page..page + count, true);
+            }
+            "prot" => {
+                let page = tokens.next().unwrap().trim();
+                let page = usize::from_str_radix(&page[2..], 16).unwrap();
+                let count = tokens.next().unwrap().trim();
+                let count = usize::from_str_radix(&count[2..], 16).unwrap();
+
+                let protection = tokens.next().unwrap().trim();
+                match Permissions::parse(protection) {
+                    Some(protection) => mmu.set_protection(page..page + count, protection),
+                    None => println!("permissões inválidas: {}", protection),
+                }
+            }
+            "mprotect" => {
+                let address = tokens.next().unwrap().trim();
+                let address = usize::from_str_radix(&address[2..], 16).unwrap();
+                let len = tokens.next().unwrap().trim();
+                let len = usize::from_str_radix(&len[2..], 16).unwrap();
+
+                let protection = tokens.next().unwrap().trim();
+                match Permissions::parse(protection) {
+                    Some(protection) => {
+                        let pages = mmu.mprotect(address..address + len, protection);
+                        println!(
+                            "páginas {:#04X}..{:#04X} agora são {}",
+                            pages.start, pages.end, protection
+                        );
+                    }
+                    None => println!("permissões inválidas: {}", protection),
+                }
+            }
+            "io" => {
+                let op = tokens.next().unwrap().trim();
+                let mut values = tokens.map(|token| {
+                    let token = token.trim();
+                    usize::from_str_radix(&token[2..], 16).unwrap()
+                });
+                let x = values.next().unwrap();
+
+                match op {
+                    "map" => {
+                        if let Err(error) = iommu.map_page(&mut mmu, x, values.next().unwrap()) {
+                            println!("io map: erro: {}", error);
+                        }
+                    }
+                    "unmap" => {
+                        if let Err(error) = iommu.unmap_page(&mut mmu, x) {
+                            println!("io unmap: erro: {}", error);
+                        }
+                    }
+                    "w" => {
+                        if let Err(fault) =
+                            iommu.write(&mut mmu, x, &[values.next().unwrap() as u8])
+                        {
+                            println!("io: bloqueado: {}", fault);
+                        }
+                    }
+                    _ => {
+                        let mut value = [0];
+                        match iommu.read(&mut mmu, x, &mut value) {
+                            Ok(()) => println!("iova {:#06X} => {:#X}", x, value[0]),
+                            Err(fault) => println!("io: bloqueado: {}", fault),
+                        }
+                    }
+                }
+            }
+            "dma" => {
+                let op = tokens.next().unwrap().trim();
+                let mut values = tokens.map(|token| {
+                    let token = token.trim();
+                    usize::from_str_radix(&token[2..], 16).unwrap()
+                });
+                let (frame, offset) = (values.next().unwrap(), values.next().unwrap());
+                let mut dma = mmu.physical(DmaCheck::Flag);
+
+                if op == "w" {
+                    dma.write(frame, offset, &[values.next().unwrap() as u8]);
+                } else {
+                    let mut value = [0];
+                    dma.read(frame, offset, &mut value);
+                    println!("frame {:#04X} + {:#04X} => {:#X}", frame, offset, value[0]);
+                }
+            }
+            "age" => {
+                print!("{}", mmu.fmt_reference_history());
+            }
+            "audit" => {
+                let n = tokens
+                    .next()
+                    .map(|n| usize::from_str_radix(&n.trim()[2..], 16).unwrap())
+                    .unwrap_or(1);
+
+                for entry in mmu.audit().unwrap().iter_back().take(n) {
+                    println!("{}", entry);
+                }
+            }
+            "trace" => match fixtures::trace(tokens.next().unwrap().trim()) {
+                Ok(accesses) => {
+                    for access in accesses {
+                        if let Err(error) = access.apply(&mut mmu) {
+                            println!("erro: {}", error);
+                        }
+                    }
+                }
+                Err(err) => println!("{}", err),
+            },
+            "g" => {
+                let pattern = match tokens.next().unwrap().trim() {
+                    "uniform" => Pattern::Uniform,
+                    "loop" => Pattern::Loop { pages: 16 },
+                    "zipf" => Pattern::Zipf { exponent: 1.0 },
+                    "hotcold" => Pattern::HotCold {
+                        hot_pages: 25,
+                        hot_fraction: 0.9,
+                    },
+                    other => {
+                        println!("padrão inválido: {}", other);
+                        line.clear();
+                        continue;
+                    }
+                };
+
+                let n = tokens.next().unwrap().trim();
+                let n = usize::from_str_radix(&n[2..], 16).unwrap();
+
+                let mut generator =
+                    WorkloadGenerator::new(workload_seeds.next_u64(), pattern, 256, 256);
+
+                for access in generator.generate(n) {
+                    if let Err(error) = access.apply(&mut mmu) {
+                        println!("erro: {}", error);
+                    }
+                }
+            }
+            "s" => {
+                print!("{}", mmu.fmt_state());
+            }
+            "state" => {
+                let filename = tokens.next().unwrap().trim();
+
+                if let Err(err) = std::fs::write(filename, mmu.export_state().to_toml()) {
+                    println!("state: {}: {}", filename, err);
+                }
+            }
+            "rewind" => {
+                let n = tokens.next().unwrap().trim();
+                let n = usize::from_str_radix(&n[2..], 16).unwrap();
+
+                if let Err(err) = mmu.rewind(n) {
+                    println!("rewind: {}", err);
+                }
+            }
+            "cp" | "fill" => {
+                let mut values = tokens.map(|token| {
+                    let token = token.trim();
+                    usize::from_str_radix(&token[2..], 16).unwrap()
+                });
+                let (dst, x, len) = (
+                    values.next().unwrap(),
+                    values.next().unwrap(),
+                    values.next().unwrap(),
+                );
+
+                let result = if cmd == "cp" {
+                    mmu.copy(dst, x, len)
+                } else {
+                    mmu.fill(dst, x as u8, len)
+                };
+
+                if let Err(err) = result {
+                    println!("{}: {}", cmd, err);
+                }
+            }
+            "load" => {
+                let address = tokens.next().unwrap().trim();
+                let address = usize::from_str_radix(&address[2..], 16).unwrap();
+                let filename = tokens.next().unwrap().trim();
+
+                match std::fs::read(filename) {
+                    Ok(image) => {
+                        if let Err(err) = mmu.load_image(address, &image) {
+                            println!("load: {}: {}", filename, err);
+                        }
+                    }
+                    Err(err) => println!("load: {}: {}", filename, err),
+                }
+            }
+            "e" => {
+                let format = tokens.next().unwrap().trim();
+                let filename = tokens.next().unwrap().trim();
+
+                let file = File::create(filename).unwrap();
+                let trace = mmu.trace().unwrap();
+
+                match format {
+                    "csv" => trace.write_csv(file).unwrap(),
+                    "lackey" => trace.write_lackey(file).unwrap(),
+                    "champsim" => trace.write_champsim(file).unwrap(),
+                    "timeline" => FrameTimeline::from_records(trace.records())
+                        .write_json(file)
+                        .unwrap(),
+                    _ => println!("formato inválido: {}", format),
+                }
             }
             "" => {
                 break;
@@ -101,5 +1167,66 @@ fn main() {
         line.clear();
     }
 
+    match mmu.flush_all() {
+        Ok(0) => {}
+        Ok(pages) => println!("sync: {} páginas sujas escritas no swap file", pages),
+        Err(err) => println!("sync: {}", err),
+    }
+
+    if strict {
+        println!("===== Violações =====");
+        for violation in mmu.violations() {
+            println!("{}", violation);
+        }
+    }
+
+    if mmu.regions().iter().next().is_some() {
+        mmu.permission_report().print_report();
+    }
+
+    if !mmu.dma_violations().is_empty() {
+        println!("===== Violações de DMA =====");
+        for violation in mmu.dma_violations() {
+            println!("{}", violation);
+        }
+    }
+
+    for (pid, heap) in &heaps {
+        println!("===== Heap do processo {} ({}) =====", pid, heap.strategy());
+        heap.stats(&mut mmu).print_report();
+    }
+
+    if kernel.processes().next().is_some() {
+        kernel.limit_hits(&mmu).print_report();
+    }
+
+    if !mmu.privilege_faults().is_empty() {
+        println!("===== Faltas de privilégio =====");
+        for fault in mmu.privilege_faults() {
+            println!("{}", fault);
+        }
+    }
+
     mmu.stats.print_stats();
+    if let Some(breakdown) = mmu.miss_breakdown() {
+        breakdown.print_report();
+    }
+    if let Some(report) = mmu.tlb_report() {
+        report.print_report();
+    }
+    if let Some(report) = mmu.tier_report() {
+        report.print_report();
+    }
+    if let Some(report) = mmu.partition_report() {
+        report.print_report();
+    }
+    if iommu.stats() != IommuStats::default() {
+        iommu.stats().print_stats();
+    }
+    println!(
+        "Tempo efetivo de acesso: {:.2} ns",
+        mmu.stats.effective_access_time(&CostModel::default())
+    );
+
+    LocalityReport::from_records(mmu.trace().unwrap().records()).print_report();
 }
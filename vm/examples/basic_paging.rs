@@ -0,0 +1,34 @@
+//! Paginação por demanda com o mínimo: uma Mmu pequena, o FIFO e um
+//! carregador em memória.
+//!
+//! A Mmu tem 4 frames para 256 páginas virtuais de 256 bytes. Escrevemos um
+//! byte em 6 páginas; as duas últimas já não cabem e tiram as primeiras da
+//! memória, que são escritas no "disco" (o carregador) por estarem sujas. Ao
+//! ler de novo a primeira página, ela volta com o conteúdo escrito.
+//!
+//! ```text
+//! cargo run -p vm --example basic_paging
+//! ```
+
+use vm::{mmu::Mmu, page_loader::MemoryPageLoader, page_replacer::FIFOPageReplacer};
+
+fn main() {
+    let mut mmu =
+        Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+
+    for page in 0..6 {
+        mmu.write((page << 8) | 0x10, 0xA0 + page as u8).unwrap();
+    }
+
+    // As páginas 0 e 1 saíram da memória; o carregador tem o seu conteúdo.
+    let on_disk = mmu.loader().page(0).map(|page| page[0x10]);
+    println!("página 0 no disco: {:#04X?}", on_disk);
+
+    // Ler a página 0 causa uma page fault, e ela volta do disco.
+    let value = mmu.read(0x0010).unwrap();
+    println!("página 0 de volta na memória: {:#04X}", value);
+    assert_eq!(value, 0xA0);
+
+    println!("{}", mmu.fmt_state());
+    mmu.stats.print_stats();
+}
@@ -0,0 +1,68 @@
+//! Um carregador de páginas escrito fora da crate.
+//!
+//! O carregador é o "disco" da Mmu: ela chama `load_page_into` numa page
+//! fault e `flush_page` para escrever uma página suja que sai da memória.
+//! Este carregador gera o conteúdo de cada página na hora (cada byte é o
+//! número da página mais o deslocamento) e só guarda as páginas escritas, e
+//! conta as operações, para mostrar quando a Mmu vai ao disco.
+//!
+//! ```text
+//! cargo run -p vm --example custom_loader
+//! ```
+
+use std::collections::HashMap;
+
+use vm::{mmu::Mmu, page_loader::PageLoader, page_replacer::FIFOPageReplacer};
+
+#[derive(Default)]
+struct GeneratedPageLoader {
+    written: HashMap<usize, Vec<u8>>,
+    loads: usize,
+    flushes: usize,
+}
+
+impl PageLoader for GeneratedPageLoader {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        self.loads += 1;
+
+        match self.written.get(&page_number) {
+            Some(page) => target.copy_from_slice(page),
+            None => {
+                for (offset, byte) in target.iter_mut().enumerate() {
+                    *byte = (page_number + offset) as u8;
+                }
+            }
+        }
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        self.flushes += 1;
+        self.written.insert(page_number, buffer.to_vec());
+    }
+}
+
+fn main() {
+    let mut mmu = Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        GeneratedPageLoader::default(),
+    );
+
+    println!(
+        "byte 0x05 da página 0x10: {:#04X}",
+        mmu.read(0x1005).unwrap()
+    );
+
+    // A escrita deixa a página 0x10 suja; duas outras páginas a tiram da
+    // memória, e ela é escrita no carregador.
+    mmu.write(0x1005, 0xEE).unwrap();
+    mmu.read(0x2000).unwrap();
+    mmu.read(0x3000).unwrap();
+
+    println!("de volta do carregador: {:#04X}", mmu.read(0x1005).unwrap());
+    println!(
+        "o carregador fez {} leituras e {} escritas",
+        mmu.loader().loads,
+        mmu.loader().flushes
+    );
+    assert_eq!(mmu.loader().flushes, 1);
+}
@@ -0,0 +1,69 @@
+//! Uma política de substituição escrita fora da crate.
+//!
+//! Qualquer tipo que implemente `PageReplacer` pode ser a política da Mmu.
+//! Aqui, o LIFO: sai a página carregada mais recentemente. É uma política
+//! ruim em geral, mas num laço maior que a memória ela mantém as primeiras
+//! páginas e acerta parte dos acessos, enquanto o FIFO erra todos.
+//!
+//! ```text
+//! cargo run -p vm --example custom_replacer
+//! ```
+
+use std::fmt;
+
+use vm::{
+    fixtures,
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::{FIFOPageReplacer, PageEvent, PageReplacer},
+};
+
+/// As páginas em memória, da carregada há mais tempo para a mais recente.
+#[derive(Default)]
+struct LifoPageReplacer {
+    stack: Vec<usize>,
+}
+
+impl PageReplacer for LifoPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(page) => self.stack.push(page),
+            PageEvent::Unmapped(page) => self.stack.retain(|&p| p != page),
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        self.stack.pop()
+    }
+
+    fn victim_reason(&self) -> String {
+        "a página carregada mais recentemente".to_string()
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "lifo: {:02X?}", self.stack)
+    }
+}
+
+/// Executa o trace `loop_12_pages` (veja `vm::fixtures`) numa Mmu com 8
+/// frames e devolve o número de misses.
+fn misses<R: PageReplacer>(replacer: R) -> usize {
+    let mut mmu = Mmu::<{ 8 * 256 }, 8, 256, _, _>::new(replacer, MemoryPageLoader::new());
+
+    for access in fixtures::trace("loop_12_pages").unwrap() {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    mmu.stats.misses()
+}
+
+fn main() {
+    let lifo = misses(LifoPageReplacer::default());
+    let fifo = misses(FIFOPageReplacer::new());
+
+    println!("laço de 12 páginas em 8 frames, 600 acessos:");
+    println!("  LIFO: {} misses", lifo);
+    println!("  FIFO: {} misses", fifo);
+    assert!(lifo < fifo);
+}
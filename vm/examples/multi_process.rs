@@ -0,0 +1,53 @@
+//! Vários processos dividindo a memória física.
+//!
+//! Cada processo declara as suas regiões de páginas virtuais (veja o módulo
+//! `region`); a Mmu conta o tamanho virtual e o resident set size de cada
+//! um, como o `top`. O processo 2 usa uma região somente leitura e escreve
+//! nela, o que aparece no relatório de permissões. Depois, o processo 1 sai
+//! da memória inteiro e volta com o seu working set.
+//!
+//! ```text
+//! cargo run -p vm --example multi_process
+//! ```
+
+use vm::{
+    mmu::Mmu, page_loader::MemoryPageLoader, page_replacer::ClockPageReplacer, region::Permissions,
+};
+
+fn main() {
+    let mut mmu =
+        Mmu::<{ 16 * 256 }, 16, 256, _, _>::new(ClockPageReplacer::new(), MemoryPageLoader::new());
+
+    mmu.map_region(1, "heap", 0x10..0x18).unwrap();
+    mmu.map_region(1, "pilha", 0xF0..0xF2).unwrap();
+    mmu.map_region(2, "heap", 0x20..0x28).unwrap();
+    mmu.map_region_with_permissions(2, "texto", 0x30..0x34, Permissions::READ_ONLY)
+        .unwrap();
+
+    for page in 0x10..0x16 {
+        mmu.write(page << 8, 1).unwrap();
+    }
+    mmu.write(0xF0 << 8, 1).unwrap();
+    for page in 0x20..0x24 {
+        mmu.read(page << 8).unwrap();
+    }
+    mmu.write(0x30 << 8, 0xCC).unwrap();
+
+    for pid in [1, 2] {
+        let stats = mmu.process_stats(pid);
+        println!(
+            "processo {}: VSZ {} bytes, RSS {} bytes",
+            pid,
+            stats.virtual_size(),
+            stats.resident_set_size()
+        );
+    }
+
+    mmu.permission_report().print_report();
+
+    let out = mmu.swap_out_process(1).unwrap();
+    println!("processo 1 saiu da memória: {} páginas", out);
+    let back = mmu.swap_in_process(1, true).unwrap();
+    println!("processo 1 voltou: {} páginas do working set", back);
+    assert_eq!(out, back);
+}
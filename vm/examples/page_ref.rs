@@ -0,0 +1,68 @@
+//! Quanto custa copiar uma página do carregador, e quanto se economiza lendo
+//! o buffer dele direto (`PageLoader::page_ref`).
+//!
+//! Para cada tamanho de página, um `MemoryPageLoader` guarda 64 páginas, e
+//! lemos todas muitas vezes: primeiro com `load_page_into` num buffer
+//! próprio, que é uma cópia por leitura, depois com `page_ref`, que só
+//! devolve uma referência. O somatório dos bytes impede que o compilador
+//! descarte as leituras. Rode em modo release para números que façam
+//! sentido:
+//!
+//! ```text
+//! cargo run -p vm --release --example page_ref
+//! ```
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use vm::page_loader::{MemoryPageLoader, PageLoader};
+
+const PAGES: usize = 64;
+
+/// Repete `read` até ler ~256 MiB e devolve o tempo médio por página.
+fn per_page(page_size: usize, mut read: impl FnMut(usize) -> u64) -> Duration {
+    let rounds = (256 << 20) / (page_size * PAGES);
+    let start = Instant::now();
+    let mut sum = 0;
+
+    for _ in 0..rounds {
+        for page_number in 0..PAGES {
+            sum += read(page_number);
+        }
+    }
+
+    black_box(sum);
+    start.elapsed() / (rounds * PAGES) as u32
+}
+
+fn main() {
+    println!(
+        "{:>10} {:>14} {:>14} {:>8}",
+        "página", "load_page_into", "page_ref", "ganho"
+    );
+
+    for page_size in [256, 4 << 10, 64 << 10, 1 << 20] {
+        let image: Vec<u8> = (0..page_size * PAGES).map(|i| i as u8).collect();
+        let mut loader: MemoryPageLoader = MemoryPageLoader::from_image(&image, page_size);
+        let mut buffer = vec![0; page_size];
+
+        let copied = per_page(page_size, |page_number| {
+            loader.load_page_into(page_number, &mut buffer);
+            black_box(&buffer)[page_size - 1] as u64
+        });
+        let borrowed = per_page(page_size, |page_number| {
+            let page = loader.page_ref(page_number).unwrap();
+            black_box(page)[page_size - 1] as u64
+        });
+
+        println!(
+            "{:>10} {:>14?} {:>14?} {:>7.1}x",
+            page_size,
+            copied,
+            borrowed,
+            copied.as_secs_f64() / borrowed.as_secs_f64().max(1e-12)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! O TLB e o seu reach.
+//!
+//! Com todas as páginas em memória, um TLB de 8 entradas acerta quase tudo
+//! num laço de 6 páginas, mas erra todas as traduções num laço de 12: o
+//! working set passou do reach. Com as páginas grandes transparentes (veja o
+//! módulo `thp`), uma entrada cobre 8 páginas e o laço de 12 volta a caber.
+//!
+//! ```text
+//! cargo run -p vm --example tlb
+//! ```
+
+use vm::{mmu::Mmu, page_loader::MemoryPageLoader, page_replacer::FIFOPageReplacer};
+
+/// Executa um laço de `pages` páginas com um TLB de 8 entradas e devolve a
+/// taxa de acerto do TLB.
+fn hit_rate(pages: usize, thp: bool) -> f64 {
+    let mut mmu =
+        Mmu::<{ 64 * 256 }, 64, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    mmu.enable_tlb(8);
+    mmu.set_tick_interval(Some(64));
+    if thp {
+        mmu.enable_thp();
+    }
+
+    for i in 0..4096 {
+        mmu.read((i % pages) << 8).unwrap();
+    }
+
+    let report = mmu.tlb_report().unwrap();
+    if thp {
+        report.print_report();
+    }
+
+    report
+        .counts
+        .values()
+        .map(|counts| counts.hits)
+        .sum::<usize>() as f64
+        / 4096.0
+}
+
+fn main() {
+    let small = hit_rate(6, false);
+    let large = hit_rate(12, false);
+    let huge = hit_rate(12, true);
+
+    println!(
+        "laço de 6 páginas:                 {:>6.2} % de acertos",
+        small * 100.0
+    );
+    println!(
+        "laço de 12 páginas:                {:>6.2} % de acertos",
+        large * 100.0
+    );
+    println!(
+        "laço de 12, com páginas grandes:   {:>6.2} % de acertos",
+        huge * 100.0
+    );
+    assert!(large < small && large < huge);
+}
@@ -0,0 +1,233 @@
+//! Análise de localidade de um trace.
+//!
+//! Mede o quanto um padrão de acessos tem localidade espacial (acessos
+//! próximos de acessos recentes) e temporal (reuso de páginas acessadas
+//! recentemente), com os scores de Weinberg et al. (SC '05), que ficam entre
+//! 0 (nenhuma localidade) e 1 (localidade perfeita):
+//!
+//! - **espacial**: a partir do histograma de strides (a distância, em bytes,
+//!   entre um acesso e o anterior), `Σ P(|stride| = s) / s` para `s >= 1`. Um
+//!   trace puramente sequencial tem score 1; strides grandes contribuem pouco.
+//! - **temporal**: a partir da distribuição das distâncias de reuso (quantas
+//!   páginas distintas foram acessadas entre dois usos da mesma página),
+//!   agrupadas em potências de 2. Reusos próximos pesam mais do que reusos
+//!   distantes, e páginas nunca reusadas não contribuem.
+//!
+//! As mesmas distâncias dizem quantos frames o LRU precisaria (Mattson et
+//! al., 1970): com `F` frames, um acesso é um hit exatamente quando a sua
+//! distância de reuso é menor que `F`. Então uma passada pelo trace responde
+//! à taxa de acerto do LRU para todos os tamanhos de memória de uma vez (veja
+//! `LocalityReport::frames_for_hit_rate`).
+
+use std::collections::BTreeMap;
+
+use crate::trace::AccessRecord;
+
+/// As métricas de localidade de um trace.
+pub struct LocalityReport {
+    /// Quantas vezes cada stride (em bytes) apareceu entre acessos consecutivos.
+    pub stride_histogram: BTreeMap<isize, usize>,
+    /// A CDF das distâncias de reuso: `reuse_cdf[i]` é a fração dos acessos
+    /// cuja distância de reuso é no máximo `2^i`. Acessos sem reuso (a
+    /// primeira vez que uma página é vista) ficam fora de todos os baldes.
+    pub reuse_cdf: Vec<f64>,
+    /// Score de localidade espacial, entre 0 e 1.
+    pub spatial_score: f64,
+    /// Score de localidade temporal, entre 0 e 1.
+    pub temporal_score: f64,
+    /// Quantos acessos tiveram cada distância de reuso:
+    /// `stack_distances[d]` conta os reusos com `d` páginas distintas
+    /// acessadas desde o uso anterior.
+    pub stack_distances: Vec<usize>,
+    /// O número de acessos do trace.
+    pub accesses: usize,
+}
+
+impl LocalityReport {
+    /// Calcula as métricas a partir dos registros de um trace.
+    pub fn from_records(records: &[AccessRecord]) -> Self {
+        let stride_histogram = stride_histogram(records);
+        let stack_distances = stack_distances(records);
+        let reuse_cdf = cdf_from_distances(&stack_distances, records.len());
+
+        LocalityReport {
+            spatial_score: spatial_score(&stride_histogram),
+            temporal_score: temporal_score(&reuse_cdf),
+            stride_histogram,
+            reuse_cdf,
+            stack_distances,
+            accesses: records.len(),
+        }
+    }
+
+    /// A taxa de acerto do LRU exato com `frames` frames, entre 0 e 1.
+    pub fn lru_hit_rate(&self, frames: usize) -> f64 {
+        let hits: usize = self.stack_distances.iter().take(frames).sum();
+
+        hits as f64 / self.accesses.max(1) as f64
+    }
+
+    /// O menor número de frames com que o LRU teria uma taxa de acerto de
+    /// pelo menos `target` (entre 0 e 1) neste trace. `None` se nem com
+    /// memória infinita: a primeira vez que cada página aparece é sempre um
+    /// miss.
+    pub fn frames_for_hit_rate(&self, target: f64) -> Option<usize> {
+        let needed = (target * self.accesses as f64).ceil() as usize;
+        let mut hits = 0;
+
+        if needed == 0 {
+            return Some(0);
+        }
+
+        for (distance, count) in self.stack_distances.iter().enumerate() {
+            hits += count;
+
+            if hits >= needed {
+                return Some(distance + 1);
+            }
+        }
+
+        None
+    }
+
+    pub fn print_report(&self) {
+        println!("===== Localidade =====");
+        println!("Score espacial: {:.3}", self.spatial_score);
+        println!("Score temporal: {:.3}", self.temporal_score);
+
+        // Os strides mais frequentes dizem mais do que o histograma inteiro.
+        let mut strides: Vec<_> = self.stride_histogram.iter().collect();
+        strides.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+        println!("Strides mais comuns:");
+        for (stride, count) in strides.iter().take(5) {
+            println!("  {:>+8}: {}", stride, count);
+        }
+
+        println!("CDF de distância de reuso:");
+        for (i, fraction) in self.reuse_cdf.iter().enumerate() {
+            println!("  <= {:>6}: {:>6.2} %", 1usize << i, fraction * 100.0);
+        }
+
+        match self.frames_for_hit_rate(ADVISED_HIT_RATE) {
+            Some(frames) => println!(
+                "Frames para {:.0} % de acertos com o LRU: {}",
+                ADVISED_HIT_RATE * 100.0,
+                frames
+            ),
+            None => println!(
+                "Frames para {:.0} % de acertos com o LRU: inalcançável (no máximo {:.2} %)",
+                ADVISED_HIT_RATE * 100.0,
+                self.lru_hit_rate(usize::MAX) * 100.0
+            ),
+        }
+    }
+}
+
+/// A taxa de acerto para a qual `print_report` sugere um número de frames.
+pub const ADVISED_HIT_RATE: f64 = 0.95;
+
+/// Conta os strides entre acessos consecutivos.
+pub fn stride_histogram(records: &[AccessRecord]) -> BTreeMap<isize, usize> {
+    let mut histogram = BTreeMap::new();
+
+    for pair in records.windows(2) {
+        let stride = pair[1].address as isize - pair[0].address as isize;
+        *histogram.entry(stride).or_insert(0) += 1;
+    }
+
+    histogram
+}
+
+/// Conta as distâncias de reuso, por página: o elemento `d` é o número de
+/// acessos a uma página com `d` páginas distintas acessadas desde o seu uso
+/// anterior. A primeira vez de cada página não conta.
+pub fn stack_distances(records: &[AccessRecord]) -> Vec<usize> {
+    // A pilha LRU: a página no fim é a mais recente. A distância de reuso de
+    // uma página é sua profundidade na pilha.
+    let mut stack: Vec<usize> = Vec::new();
+    let mut counts: Vec<usize> = Vec::new();
+
+    for record in records {
+        if let Some(pos) = stack.iter().rposition(|&page| page == record.page_number) {
+            let distance = stack.len() - 1 - pos;
+
+            if counts.len() <= distance {
+                counts.resize(distance + 1, 0);
+            }
+            counts[distance] += 1;
+
+            stack.remove(pos);
+        }
+
+        stack.push(record.page_number);
+    }
+
+    counts
+}
+
+/// Calcula a CDF das distâncias de reuso, por página, em baldes de potências
+/// de 2.
+pub fn reuse_cdf(records: &[AccessRecord]) -> Vec<f64> {
+    cdf_from_distances(&stack_distances(records), records.len())
+}
+
+/// Agrupa as distâncias de reuso em baldes de potências de 2 e acumula.
+fn cdf_from_distances(stack_distances: &[usize], accesses: usize) -> Vec<f64> {
+    let mut buckets: Vec<usize> = Vec::new();
+
+    for (distance, &count) in stack_distances.iter().enumerate() {
+        // O balde i guarda as distâncias em (2^(i-1), 2^i].
+        let bucket = match distance {
+            0 | 1 => 0,
+            d => (usize::BITS - (d - 1).leading_zeros()) as usize,
+        };
+
+        if buckets.len() <= bucket {
+            buckets.resize(bucket + 1, 0);
+        }
+        buckets[bucket] += count;
+    }
+
+    let total = accesses.max(1) as f64;
+    let mut accumulated = 0;
+
+    buckets
+        .iter()
+        .map(|count| {
+            accumulated += count;
+            accumulated as f64 / total
+        })
+        .collect()
+}
+
+/// O score espacial a partir do histograma de strides.
+pub fn spatial_score(stride_histogram: &BTreeMap<isize, usize>) -> f64 {
+    let total: usize = stride_histogram.values().sum();
+
+    if total == 0 {
+        return 0.0;
+    }
+
+    stride_histogram
+        .iter()
+        .filter(|(stride, _)| **stride != 0)
+        .map(|(stride, count)| *count as f64 / total as f64 / stride.unsigned_abs() as f64)
+        .sum()
+}
+
+/// O score temporal a partir da CDF de reuso: cada balde `i` contribui com a
+/// fração de acessos que caíram nele, ponderada por `(n - i) / n`, onde `n` é
+/// o número de baldes.
+pub fn temporal_score(reuse_cdf: &[f64]) -> f64 {
+    let n = reuse_cdf.len();
+    let mut previous = 0.0;
+    let mut score = 0.0;
+
+    for (i, cdf) in reuse_cdf.iter().enumerate() {
+        score += (cdf - previous) * (n - i) as f64 / n as f64;
+        previous = *cdf;
+    }
+
+    score
+}
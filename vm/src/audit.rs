@@ -0,0 +1,104 @@
+//! Log de auditoria das decisões de substituição.
+//!
+//! Quando uma substituição surpreende numa demonstração ("por que ele tirou
+//! justamente essa página?"), o trace só diz qual página saiu. O log de
+//! auditoria guarda, para as últimas K substituições, também o motivo dado
+//! pelo replacer e o estado dele logo antes da decisão (veja
+//! `PageReplacer::fmt_state`), de forma que dá para navegar para trás nas
+//! decisões sem reexecutar nada.
+
+use std::{collections::VecDeque, fmt};
+
+/// Uma decisão de substituição.
+#[derive(Clone, Debug)]
+pub struct EvictionAudit {
+    /// O acesso que causou a substituição.
+    pub time: usize,
+    /// A página que causou a page fault.
+    pub faulting_page: usize,
+    /// A página escolhida para sair.
+    pub victim: usize,
+    /// O frame liberado.
+    pub frame_index: usize,
+    /// Se a página escolhida estava suja.
+    pub writeback: bool,
+    /// O motivo da escolha, segundo o replacer.
+    pub reason: String,
+    /// O estado do replacer logo antes da escolha: os candidatos e o que ele
+    /// sabia sobre eles.
+    pub replacer_state: String,
+}
+
+impl fmt::Display for EvictionAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "acesso #{}: página {:#04X} substituiu {:#04X} (frame {:#04X}){}",
+            self.time,
+            self.faulting_page,
+            self.victim,
+            self.frame_index,
+            if self.writeback {
+                ", com writeback"
+            } else {
+                ""
+            }
+        )?;
+        writeln!(f, "  motivo: {}", self.reason)?;
+        write!(f, "  estado antes: {}", self.replacer_state)
+    }
+}
+
+/// As últimas `capacity` decisões de substituição.
+pub struct AuditLog {
+    capacity: usize,
+    entries: VecDeque<EvictionAudit>,
+}
+
+impl AuditLog {
+    /// Constrói um log vazio que guarda até `capacity` decisões.
+    pub fn new(capacity: usize) -> Self {
+        AuditLog {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Adiciona uma decisão, descartando a mais antiga se o log está cheio.
+    pub fn push(&mut self, entry: EvictionAudit) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    /// Descarta as decisões feitas a partir do acesso `time`.
+    pub fn truncate_at(&mut self, time: usize) {
+        self.entries.retain(|entry| entry.time < time);
+    }
+
+    /// A `n`-ésima decisão contando de trás para frente: 0 é a mais recente.
+    pub fn back(&self, n: usize) -> Option<&EvictionAudit> {
+        self.entries.iter().rev().nth(n)
+    }
+
+    /// Itera sobre as decisões, da mais recente para a mais antiga.
+    pub fn iter_back(&self) -> impl Iterator<Item = &EvictionAudit> {
+        self.entries.iter().rev()
+    }
+
+    /// Quantas decisões estão guardadas.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Se não há nenhuma decisão guardada.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
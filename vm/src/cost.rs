@@ -0,0 +1,114 @@
+//! Modelo de custo dos acessos à memória.
+//!
+//! A Mmu só conta hits, misses e writebacks; este módulo atribui um tempo a
+//! cada um desses eventos, para que o tempo total (e o tempo efetivo de acesso)
+//! de uma execução possa ser calculado como nos exercícios do livro.
+//!
+//! O tratamento de uma page fault é modelado como no Silberschatz:
+//!
+//! 1. trap para o sistema operacional;
+//! 2. o processo bloqueia esperando a leitura da página, e o escalonador troca
+//!    para outro processo (e de volta quando a leitura termina);
+//! 3. se a página substituída estava suja, ela é escrita no disco;
+//! 4. a página é lida do disco;
+//! 5. o acesso é refeito, agora como um acesso normal à memória.
+//!
+//! A tradução do endereço também pode ter custo: numa page table de vários
+//! níveis, o hardware lê uma entrada de cada nível (o "page walk"), e os
+//! níveis intermediários podem estar num cache próprio (como os
+//! paging-structure caches dos x86). O walk acontece em todo acesso cuja
+//! tradução não está no TLB (em todos, sem um TLB), e de novo quando o
+//! acesso é refeito depois de uma page fault.
+//!
+//! Todos os tempos são em nanossegundos.
+
+/// Os custos de cada evento da simulação, em nanossegundos.
+#[derive(Copy, Clone, Debug)]
+pub struct CostModel {
+    /// Um acesso à memória principal.
+    pub memory_access: f64,
+    /// O trap para o kernel (salvar registradores, identificar a fault).
+    pub trap_overhead: f64,
+    /// Uma troca de contexto.
+    pub context_switch: f64,
+    /// Quantas trocas de contexto cada page fault causa. Com um escalonador,
+    /// o processo que causou a fault bloqueia e outro assume a CPU, então são
+    /// duas (uma para sair, outra para voltar). Sem outros processos, zero.
+    pub context_switches_per_fault: u32,
+    /// Ler uma página do disco.
+    pub page_read: f64,
+    /// Escrever uma página suja de volta no disco.
+    pub page_write: f64,
+    /// Quantos níveis a page table tem, ou seja, quantas entradas o page
+    /// walk lê. 0 ignora o custo da tradução, como no exemplo do livro; 1 é
+    /// a page table plana da Mmu.
+    pub page_table_levels: u32,
+    /// A fração das leituras de níveis intermediários (todos menos o último)
+    /// que acertam no cache de page walk e não custam um acesso à memória.
+    pub walk_cache_hit_rate: f64,
+    /// Uma saída do guest para o hypervisor (VM exit) e a volta. Só é usado
+    /// na simulação de virtualização (veja o módulo `shadow`).
+    pub vm_exit: f64,
+    /// Enviar uma página pela rede, na migração ao vivo (veja
+    /// `scenario::balloon_migration`).
+    pub page_transfer: f64,
+    /// Copiar uma página de um frame para outro, na quebra de um
+    /// copy-on-write (veja o módulo `sharing`).
+    pub page_copy: f64,
+}
+
+/// Os valores do exemplo do livro: 200ns por acesso à memória e 8ms para
+/// ler ou escrever uma página.
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            memory_access: 200.0,
+            trap_overhead: 1_000.0,
+            context_switch: 5_000.0,
+            context_switches_per_fault: 2,
+            page_read: 8_000_000.0,
+            page_write: 8_000_000.0,
+            page_table_levels: 0,
+            walk_cache_hit_rate: 0.0,
+            vm_exit: 1_500.0,
+            // Uma página de 4 KiB a 1 Gbit/s.
+            page_transfer: 32_768.0,
+            // Um memcpy de 4 KiB.
+            page_copy: 1_000.0,
+        }
+    }
+}
+
+impl CostModel {
+    /// O tempo para tratar uma page fault, sem contar o acesso refeito no
+    /// final. `writeback` indica se a página substituída estava suja.
+    pub fn fault_service_time(&self, writeback: bool) -> f64 {
+        let mut time = self.trap_overhead
+            + self.context_switches_per_fault as f64 * self.context_switch
+            + self.page_read;
+
+        if writeback {
+            time += self.page_write;
+        }
+
+        time
+    }
+
+    /// O tempo para quebrar um copy-on-write: o trap da escrita numa página
+    /// protegida e a cópia da página para um frame novo.
+    pub fn cow_break_time(&self) -> f64 {
+        self.trap_overhead + self.page_copy
+    }
+
+    /// O tempo médio de um page walk: uma leitura por nível, exceto as dos
+    /// níveis intermediários que acertam no cache.
+    pub fn page_walk_time(&self) -> f64 {
+        if self.page_table_levels == 0 {
+            return 0.0;
+        }
+
+        let intermediate = (self.page_table_levels - 1) as f64;
+
+        self.memory_access * (1.0 + intermediate * (1.0 - self.walk_cache_hit_rate))
+    }
+}
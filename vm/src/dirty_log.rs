@@ -0,0 +1,52 @@
+//! Registro de páginas escritas, para migração ao vivo.
+//!
+//! Para migrar uma máquina virtual sem pará-la, o hypervisor copia toda a
+//! memória enquanto o guest continua rodando, e depois copia de novo só as
+//! páginas que o guest escreveu nesse meio tempo, repetindo até sobrar pouca
+//! coisa (veja `scenario::live_migration`). Para isso ele precisa saber quais
+//! páginas foram escritas desde a última cópia: é o que o `DirtyBitmap`
+//! guarda (veja `Mmu::collect_and_clear_dirty_bitmap`).
+//!
+//! Diferente do bit de sujeira da page table, que diz se a página precisa de
+//! writeback e é desligado quando ela sai da memória, o bitmap registra toda
+//! escrita, esteja a página em memória ou não.
+
+/// Um bitmap com um bit por página.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirtyBitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl DirtyBitmap {
+    /// Constrói um bitmap vazio para `len` páginas.
+    pub fn new(len: usize) -> Self {
+        DirtyBitmap {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// Marca a página.
+    pub fn set(&mut self, page_number: usize) {
+        self.words[page_number / 64] |= 1 << (page_number % 64);
+    }
+
+    /// Se a página está marcada.
+    pub fn is_set(&self, page_number: usize) -> bool {
+        self.words[page_number / 64] & (1 << (page_number % 64)) != 0
+    }
+
+    /// Quantas páginas estão marcadas.
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Itera sobre as páginas marcadas, em ordem.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|&page_number| self.is_set(page_number))
+    }
+}
@@ -0,0 +1,109 @@
+//! Acesso direto à memória física (DMA), sem passar pela page table.
+//!
+//! Um dispositivo que faz DMA (um disco, uma placa de rede) lê e escreve
+//! direto nos frames, pelo endereço físico, sem saber nada de páginas. O
+//! sistema operacional passa para o dispositivo o frame de um buffer e
+//! espera a transferência terminar; se nesse meio tempo a página do buffer
+//! for substituída, o dispositivo escreve no frame de outra página, que
+//! agora ocupa aquele frame, e os dados do buffer se perdem.
+//!
+//! Por isso o sistema operacional *fixa* ("pin") as páginas dos buffers de
+//! DMA antes da transferência (veja `Mmu::pin_page`): uma página fixada
+//! continua em memória, no mesmo frame, até ser liberada.
+//!
+//! `PhysicalAccessor` (veja `Mmu::physical`) faz esse papel de dispositivo.
+//! Com `DmaCheck::Flag` ou `DmaCheck::Panic`, todo acesso a um frame livre ou
+//! a um frame cuja página não está fixada é registrado como violação (veja
+//! `Mmu::dma_violations`) ou interrompe a execução, mostrando exatamente o
+//! acesso que seria um bug num sistema de verdade.
+//!
+//! Como um driver faria ao fim da transferência, uma escrita por DMA marca a
+//! página do frame como suja, para que o conteúdo não se perca num writeback.
+
+use std::fmt;
+
+use crate::{mmu::Mmu, page_loader::PageLoader, page_replacer::PageReplacer, trace::AccessKind};
+
+/// O que fazer quando o DMA toca um frame que não está fixado.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DmaCheck {
+    /// Não verifica nada.
+    Off,
+    /// Registra uma violação e continua.
+    Flag,
+    /// Interrompe a execução com um panic.
+    Panic,
+}
+
+/// Um acesso por DMA a um frame que não estava fixado.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DmaViolation {
+    /// O número de acessos feitos pela Mmu até o momento.
+    pub time: usize,
+    pub kind: AccessKind,
+    pub frame_index: usize,
+    /// A página que ocupava o frame, ou `None` se o frame estava livre.
+    pub page_number: Option<usize>,
+}
+
+impl fmt::Display for DmaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            AccessKind::Read => "leitura",
+            AccessKind::Write => "escrita",
+            AccessKind::Fetch => "busca de instrução",
+        };
+
+        write!(
+            f,
+            "acesso #{}: {} por DMA no frame {:#04X} ",
+            self.time, kind, self.frame_index
+        )?;
+
+        match self.page_number {
+            Some(page_number) => write!(f, "da página {:#04X}, que não está fixada", page_number),
+            None => write!(f, "livre"),
+        }
+    }
+}
+
+/// Acesso aos frames da Mmu pelo índice, como um dispositivo de DMA.
+pub struct PhysicalAccessor<
+    'a,
+    const MEM_SIZE: usize,
+    const FRAME_COUNT: usize,
+    const PAGE_COUNT: usize,
+    REPLACER: PageReplacer,
+    LOADER: PageLoader,
+> {
+    pub(crate) mmu: &'a mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+    pub(crate) check: DmaCheck,
+}
+
+impl<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    > PhysicalAccessor<'_, MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>
+{
+    /// Lê `buffer.len()` bytes do frame, a partir de `offset`.
+    pub fn read(&mut self, frame_index: usize, offset: usize, buffer: &mut [u8]) {
+        self.mmu
+            .dma_access(self.check, AccessKind::Read, frame_index, offset, buffer);
+    }
+
+    /// Escreve `data` no frame, a partir de `offset`.
+    pub fn write(&mut self, frame_index: usize, offset: usize, data: &[u8]) {
+        let mut data = data.to_vec();
+
+        self.mmu.dma_access(
+            self.check,
+            AccessKind::Write,
+            frame_index,
+            offset,
+            &mut data,
+        );
+    }
+}
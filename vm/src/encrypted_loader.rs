@@ -0,0 +1,240 @@
+//! Swap criptografado, com troca de chave sem parar a simulação.
+//!
+//! `EncryptedPageLoader` envolve outro carregador: as páginas são cifradas
+//! no writeback e decifradas na carga, então o carregador de baixo (o
+//! arquivo de swap, por exemplo) só vê o texto cifrado.
+//!
+//! A cifra é um XOR com uma sequência gerada a partir da chave e do número da
+//! página (veja o módulo `rng`). Ela serve para demonstrar o mecanismo, **não
+//! é segura** e não deve ser usada para proteger nada de verdade.
+//!
+//! ## Troca de chave
+//!
+//! `start_key_rotation` passa a cifrar os novos writebacks com uma chave
+//! nova, e marca todas as páginas cifradas com chaves antigas como
+//! pendentes. Cada chamada a `rotation_step` relê, recifra e reescreve
+//! algumas páginas pendentes, de forma que a troca pode ser intercalada com
+//! os acessos da Mmu (veja `Mmu::loader_mut`), como uma passada em segundo
+//! plano. Durante a troca, cada página lembra com qual geração de chave foi
+//! cifrada, então uma page fault em qualquer página é atendida com a chave
+//! certa. Quando não sobra nenhuma página pendente, as chaves antigas são
+//! descartadas.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    error::LoaderError,
+    page_loader::{LoaderCapabilities, PageLoader},
+    rng::Rng,
+};
+
+/// Um carregador que cifra as páginas guardadas em outro carregador.
+pub struct EncryptedPageLoader<L> {
+    inner: L,
+    /// As chaves de cada geração; as já descartadas são `None`.
+    keys: Vec<Option<u64>>,
+    /// A geração da chave com que cada página escrita foi cifrada. Páginas
+    /// que nunca foram escritas não estão aqui, e são lidas sem decifrar.
+    page_generations: HashMap<usize, usize>,
+    /// As páginas que ainda precisam ser recifradas com a chave atual.
+    pending: VecDeque<usize>,
+    /// Quantas páginas foram recifradas desde a criação.
+    reencrypted: usize,
+    /// O tamanho de uma página, visto no primeiro writeback.
+    page_size: usize,
+}
+
+impl<L: PageLoader> EncryptedPageLoader<L> {
+    /// Envolve o carregador, cifrando com a chave dada.
+    pub fn new(inner: L, key: u64) -> Self {
+        EncryptedPageLoader {
+            inner,
+            keys: vec![Some(key)],
+            page_generations: HashMap::new(),
+            pending: VecDeque::new(),
+            reencrypted: 0,
+            page_size: 0,
+        }
+    }
+
+    /// O carregador de baixo, que guarda as páginas cifradas.
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    /// O carregador de baixo, para mexer direto no texto cifrado.
+    pub fn inner_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// A geração da chave atual: 0 para a chave inicial, mais 1 a cada troca.
+    pub fn generation(&self) -> usize {
+        self.keys.len() - 1
+    }
+
+    /// Se há uma troca de chave em andamento.
+    pub fn is_rotating(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Quantas páginas ainda precisam ser recifradas.
+    pub fn pending_pages(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Quantas páginas foram recifradas por trocas de chave.
+    pub fn reencrypted_pages(&self) -> usize {
+        self.reencrypted
+    }
+
+    /// Começa a trocar para uma chave nova. Os writebacks passam a usar a
+    /// chave nova imediatamente; as páginas já escritas são recifradas por
+    /// `rotation_step`. Se uma troca anterior não terminou, ela continua
+    /// junto com esta.
+    pub fn start_key_rotation(&mut self, key: u64) {
+        self.keys.push(Some(key));
+
+        let current = self.generation();
+        let mut pending: Vec<_> = self
+            .page_generations
+            .iter()
+            .filter(|(_, generation)| **generation != current)
+            .map(|(page_number, _)| *page_number)
+            .collect();
+        pending.sort();
+
+        self.pending = pending.into();
+        self.discard_old_keys();
+    }
+
+    /// Recifra até `max_pages` páginas pendentes com a chave atual,
+    /// devolvendo quantas foram recifradas.
+    pub fn rotation_step(&mut self, max_pages: usize) -> usize {
+        let mut done = 0;
+
+        while done < max_pages {
+            let Some(page_number) = self.pending.pop_front() else {
+                break;
+            };
+
+            // Um writeback durante a troca já pode ter recifrado a página.
+            if self.page_generations.get(&page_number) == Some(&self.generation()) {
+                continue;
+            }
+
+            let page = self.read_decrypted(page_number);
+            self.flush_page(page_number, &page);
+
+            self.reencrypted += 1;
+            done += 1;
+        }
+
+        self.discard_old_keys();
+
+        done
+    }
+
+    /// Troca a chave e recifra todas as páginas de uma vez.
+    pub fn rotate_key(&mut self, key: u64) {
+        self.start_key_rotation(key);
+        while self.rotation_step(usize::MAX) > 0 {}
+    }
+
+    /// Descarta as chaves que nenhuma página usa mais.
+    fn discard_old_keys(&mut self) {
+        let current = self.generation();
+
+        for (generation, key) in self.keys.iter_mut().enumerate() {
+            if generation != current && !self.page_generations.values().any(|g| *g == generation) {
+                *key = None;
+            }
+        }
+    }
+
+    /// Lê e decifra uma página já escrita.
+    fn read_decrypted(&mut self, page_number: usize) -> Vec<u8> {
+        let mut page = vec![0; self.page_size];
+        self.load_page_into(page_number, &mut page);
+
+        page
+    }
+
+    /// Cifra `page` com a chave atual e a escreve.
+    fn write_encrypted(&mut self, page_number: usize, page: &[u8]) -> Result<(), LoaderError> {
+        let generation = self.generation();
+        let mut encrypted = page.to_vec();
+
+        self.page_size = page.len();
+
+        apply_keystream(self.keys[generation].unwrap(), page_number, &mut encrypted);
+        self.inner.try_flush_page(page_number, &encrypted)?;
+        self.page_generations.insert(page_number, generation);
+
+        Ok(())
+    }
+
+    /// Decifra uma página lida do carregador de baixo.
+    fn decrypt(&self, page_number: usize, target: &mut [u8]) {
+        if let Some(generation) = self.page_generations.get(&page_number) {
+            let key = self.keys[*generation].expect("chave de página em uso descartada");
+            apply_keystream(key, page_number, target);
+        }
+    }
+}
+
+/// Cifra ou decifra (é a mesma operação) o buffer da página com a chave.
+fn apply_keystream(key: u64, page_number: usize, buffer: &mut [u8]) {
+    let mut rng = Rng::new(key ^ (page_number as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+    for chunk in buffer.chunks_mut(8) {
+        let stream = rng.next_u64().to_le_bytes();
+
+        for (byte, key_byte) in chunk.iter_mut().zip(stream) {
+            *byte ^= key_byte;
+        }
+    }
+}
+
+impl<L: PageLoader> PageLoader for EncryptedPageLoader<L> {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        self.inner.load_page_into(page_number, target);
+        self.decrypt(page_number, target);
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        if let Err(error) = self.write_encrypted(page_number, buffer) {
+            panic!("{}", error);
+        }
+    }
+
+    fn backend(&self) -> &str {
+        self.inner.backend()
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.inner.page_size()
+    }
+
+    // Cada página é cifrada e escrita sozinha, então não há o que agrupar.
+    fn capabilities(&self) -> LoaderCapabilities {
+        LoaderCapabilities {
+            batch_flush: false,
+            ..self.inner.capabilities()
+        }
+    }
+
+    fn try_load_page_into(
+        &mut self,
+        page_number: usize,
+        target: &mut [u8],
+    ) -> Result<(), LoaderError> {
+        self.inner.try_load_page_into(page_number, target)?;
+        self.decrypt(page_number, target);
+
+        Ok(())
+    }
+
+    fn try_flush_page(&mut self, page_number: usize, buffer: &[u8]) -> Result<(), LoaderError> {
+        self.write_encrypted(page_number, buffer)
+    }
+}
@@ -0,0 +1,227 @@
+//! Os erros do caminho de paginação, com o contexto para o diagnóstico.
+//!
+//! Uma falha no fundo da paginação (o arquivo de swap que não pôde ser lido
+//! numa page fault, por exemplo) acontece longe de quem fez o acesso. O
+//! carregador descreve a falha num `LoaderError`, com o seu nome e a página;
+//! a Mmu completa o contexto com o acesso que a causou, num `AccessError`,
+//! junto com as faltas de privilégio (veja o módulo `privilege`) e de
+//! proteção (veja `Mmu::set_protection`). `Mmu::read`, `Mmu::write` e
+//! `Mmu::fetch` o devolvem dentro de um `MmuError`, que também descreve os
+//! acessos a endereços inválidos e as inconsistências entre a page table e
+//! o replacer. Como os outros erros do crate, eles derivam `Display` e
+//! `Error` com o `thiserror`:
+//!
+//! ```text
+//! leitura em 0x1234 (página 0x12, frame 0x03): o carregador 'file' não
+//! conseguiu carregar a página 0x12: failed to fill whole buffer
+//! ```
+
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::{
+    mmu::PAGE_SIZE,
+    privilege::PrivilegeFault,
+    region::{Permissions, Pid},
+    trace::AccessKind,
+};
+
+/// A operação de um carregador que falhou.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoaderOperation {
+    /// A carga de uma página numa page fault (veja
+    /// `PageLoader::load_page_into`).
+    Load,
+    /// O writeback de uma página suja (veja `PageLoader::flush_page`).
+    Flush,
+}
+
+impl fmt::Display for LoaderOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoaderOperation::Load => write!(f, "carregar"),
+            LoaderOperation::Flush => write!(f, "escrever"),
+        }
+    }
+}
+
+/// Uma falha de um carregador de páginas.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error(
+    "o carregador '{backend}' não conseguiu {operation} a página {page_number:#04X}: {message}"
+)]
+pub struct LoaderError {
+    /// O nome do carregador (veja `PageLoader::backend`).
+    pub backend: String,
+    pub operation: LoaderOperation,
+    pub page_number: usize,
+    /// A descrição da causa, dada pelo carregador.
+    pub message: String,
+}
+
+impl LoaderError {
+    pub fn new(
+        backend: &str,
+        operation: LoaderOperation,
+        page_number: usize,
+        message: impl fmt::Display,
+    ) -> Self {
+        LoaderError {
+            backend: backend.to_string(),
+            operation,
+            page_number,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// O carregador não serve para a Mmu (veja `PageLoader::capabilities` e
+/// `Mmu::try_new`), ou a Mmu não pode ser construída com essa geometria.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum LoaderMismatch {
+    /// Os frames da Mmu, `MEM_SIZE / FRAME_COUNT`, não têm 256 bytes: a
+    /// tradução divide os endereços de 16 bits em 8 bits de página e 8 de
+    /// offset, qualquer que seja o carregador.
+    #[error(
+        "os frames da Mmu têm {frame_size} bytes, mas a tradução só suporta páginas de {} bytes",
+        PAGE_SIZE
+    )]
+    FrameSize {
+        /// O tamanho dos frames da Mmu.
+        frame_size: usize,
+    },
+    /// O carregador tem páginas de um tamanho diferente do dos frames.
+    #[error(
+        "o carregador '{backend}' tem páginas de {loader_page_size} bytes, mas os frames da Mmu têm {frame_size} bytes"
+    )]
+    PageSize {
+        /// O nome do carregador (veja `PageLoader::backend`).
+        backend: String,
+        /// O tamanho das páginas do carregador.
+        loader_page_size: usize,
+        /// O tamanho dos frames da Mmu, `MEM_SIZE / FRAME_COUNT`.
+        frame_size: usize,
+    },
+    /// O carregador guarda menos páginas que as do espaço de endereçamento.
+    #[error("o carregador '{backend}' guarda só {max_pages} páginas, mas a Mmu tem {page_count}")]
+    PageCount {
+        backend: String,
+        /// Quantas páginas o carregador guarda.
+        max_pages: usize,
+        /// Quantas páginas a Mmu tem, `PAGE_COUNT`.
+        page_count: usize,
+    },
+}
+
+/// Por que um acesso falhou.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum AccessFailure {
+    /// O carregador falhou numa operação do acesso. A falha pode ser de
+    /// outra página que o acesso causou: o writeback da vítima, por exemplo.
+    #[error(transparent)]
+    Loader(LoaderError),
+    /// Um acesso em modo usuário a uma página do supervisor, que não
+    /// aconteceu.
+    #[error("a página é só do supervisor, e a CPU está em modo usuário")]
+    Privilege(PrivilegeFault),
+    /// Um acesso que a proteção da página, dada aqui, não permite (veja
+    /// `Mmu::set_protection`), e que não aconteceu.
+    #[error("a página é {0}, e não permite o acesso")]
+    Protection(Permissions),
+    /// A page fault passaria o processo dono da página do seu limite de
+    /// RSS, em páginas (veja `Mmu::set_rss_limit`), e o acesso não
+    /// aconteceu.
+    #[error("o processo {pid} já tem {limit} páginas em memória, o seu limite de RSS")]
+    RssLimit { pid: Pid, limit: usize },
+    /// A page fault (ou a quebra de um COW) precisava de uma vítima, mas o
+    /// replacer não tinha nenhuma página que pudesse sair (veja
+    /// `PageReplacer::pick_replacement_page`), e o acesso não aconteceu.
+    #[error("nenhuma página em memória pode ser substituída")]
+    NoEvictablePage,
+}
+
+/// Um acesso à memória que não pôde ser completado, com o contexto do
+/// acesso e a falha que o interrompeu.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error(
+    "{} em {address:#06X} (página {page_number:#04X}{}): {cause}",
+    kind_name(*.kind),
+    FrameSuffix(*.frame_index)
+)]
+pub struct AccessError {
+    pub kind: AccessKind,
+    /// O endereço virtual acessado.
+    pub address: usize,
+    /// A página do endereço acessado.
+    pub page_number: usize,
+    /// O frame da página que falhou, se ela tinha um.
+    pub frame_index: Option<usize>,
+    #[source]
+    pub cause: AccessFailure,
+}
+
+/// Um acesso de `Mmu::read` ou `Mmu::write` que não aconteceu, ou que
+/// aconteceu com uma falha.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum MmuError {
+    /// O endereço está fora do espaço de endereçamento: passa de 16 bits,
+    /// ou a sua página passa de `PAGE_COUNT`. O acesso não aconteceu.
+    #[error(
+        "{} em {address:#06X}: o endereço está fora das {page_count} páginas do espaço de endereçamento",
+        kind_name(*.kind)
+    )]
+    InvalidAddress {
+        kind: AccessKind,
+        address: usize,
+        /// Quantas páginas a Mmu tem, `PAGE_COUNT`.
+        page_count: usize,
+    },
+    /// A page table e o replacer discordam: a vítima escolhida pelo
+    /// replacer está fixada, ou não está em memória. É um bug do replacer
+    /// (veja `PageReplacer::pick_replacement_page`), e o acesso não
+    /// aconteceu.
+    #[error("{} em {address:#06X}: page table inconsistente: {message}", kind_name(*.kind))]
+    PageTableInconsistency {
+        kind: AccessKind,
+        address: usize,
+        /// A descrição da inconsistência.
+        message: String,
+    },
+    /// A falha de um acesso válido: do carregador, que não impede o acesso,
+    /// ou de privilégio, do limite de RSS ou a falta de uma vítima, que
+    /// impedem (veja `AccessFailure`).
+    #[error(transparent)]
+    Access(#[from] AccessError),
+}
+
+impl MmuError {
+    /// O `AccessError` da falha, se ela é de um acesso válido.
+    pub fn access_error(&self) -> Option<&AccessError> {
+        match self {
+            MmuError::Access(error) => Some(error),
+            MmuError::InvalidAddress { .. } | MmuError::PageTableInconsistency { .. } => None,
+        }
+    }
+}
+
+/// O nome do tipo de acesso, nas mensagens.
+pub(crate) fn kind_name(kind: AccessKind) -> &'static str {
+    match kind {
+        AccessKind::Read => "leitura",
+        AccessKind::Write => "escrita",
+        AccessKind::Fetch => "busca de instrução",
+    }
+}
+
+/// O frame de um `AccessError`, se a página tinha um, nas mensagens.
+struct FrameSuffix(Option<usize>);
+
+impl fmt::Display for FrameSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(frame_index) => write!(f, ", frame {:#04X}", frame_index),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Rajadas de page faults: sequências de acessos seguidos que causaram page
+//! fault.
+//!
+//! A taxa de misses da execução inteira esconde quando as faults aconteceram.
+//! Uma carga que troca de fase (passa a usar outro conjunto de páginas) causa
+//! uma rajada longa logo depois da troca, e uma memória pequena demais para o
+//! working set causa rajadas longas o tempo todo: é o thrashing. Por isso a
+//! Mmu registra o comprimento de cada rajada num histograma, e guarda onde
+//! aconteceu a maior, para achar a troca de fase ao ajustar o prefetch ou as
+//! quotas.
+//!
+//! Uma rajada com pelo menos `STORM_FAULTS` faults é uma tempestade. Uma
+//! troca de fase causa uma ou outra; quando metade dos acessos são faults
+//! dentro de tempestades, a memória está em thrashing (veja
+//! `is_thrashing`).
+
+use std::ops::Range;
+
+use crate::{
+    histogram::Histogram,
+    sync::{AtomicUsize, Ordering},
+};
+
+/// Quantas faults seguidas fazem de uma rajada uma tempestade.
+pub const STORM_FAULTS: usize = 16;
+
+/// As rajadas de page faults de uma execução.
+///
+/// Como o resto das estatísticas, os contadores são atômicos (veja
+/// `MmuStats`).
+#[derive(Debug, Default)]
+pub struct FaultBursts {
+    /// O comprimento de cada rajada terminada (por um hit).
+    lengths: Histogram,
+    /// O acesso em que começou a rajada em andamento.
+    current_start: AtomicUsize,
+    /// Quantas faults tem a rajada em andamento; 0 se o último acesso foi um
+    /// hit.
+    current_len: AtomicUsize,
+    /// O acesso em que começou a maior rajada, terminada ou não.
+    largest_start: AtomicUsize,
+    /// Quantas faults tem a maior rajada.
+    largest_len: AtomicUsize,
+    /// Quantas rajadas, terminadas ou não, chegaram a `STORM_FAULTS` faults.
+    storms: AtomicUsize,
+    /// Quantas faults aconteceram dentro das tempestades.
+    storm_faults: AtomicUsize,
+}
+
+impl FaultBursts {
+    /// Registra o acesso `time`, que foi uma fault ou um hit.
+    pub(crate) fn record(&self, time: usize, fault: bool) {
+        if !fault {
+            let len = self.current_len.swap(0, Ordering::Relaxed);
+            if len > 0 {
+                self.lengths.record(len);
+            }
+
+            return;
+        }
+
+        let len = self.current_len.fetch_add(1, Ordering::Relaxed) + 1;
+        if len == 1 {
+            self.current_start.store(time, Ordering::Relaxed);
+        }
+        if len == STORM_FAULTS {
+            self.storms.fetch_add(1, Ordering::Relaxed);
+            self.storm_faults.fetch_add(STORM_FAULTS, Ordering::Relaxed);
+        } else if len > STORM_FAULTS {
+            self.storm_faults.fetch_add(1, Ordering::Relaxed);
+        }
+        if len > self.largest_len.load(Ordering::Relaxed) {
+            self.largest_start.store(
+                self.current_start.load(Ordering::Relaxed),
+                Ordering::Relaxed,
+            );
+            self.largest_len.store(len, Ordering::Relaxed);
+        }
+    }
+
+    /// O comprimento, em faults, de cada rajada já terminada. A rajada em
+    /// andamento entra quando um hit a terminar (veja `current`).
+    pub fn lengths(&self) -> &Histogram {
+        &self.lengths
+    }
+
+    /// Quantas faults tem a rajada em andamento (0 se o último acesso foi um
+    /// hit).
+    pub fn current(&self) -> usize {
+        self.current_len.load(Ordering::Relaxed)
+    }
+
+    /// Os acessos da maior rajada, terminada ou não, como um intervalo de
+    /// índices de acesso. `None` se não houve nenhuma fault.
+    pub fn largest(&self) -> Option<Range<usize>> {
+        let len = self.largest_len.load(Ordering::Relaxed);
+        let start = self.largest_start.load(Ordering::Relaxed);
+
+        (len > 0).then(|| start..start + len)
+    }
+
+    /// Quantas rajadas chegaram a `STORM_FAULTS` faults seguidas.
+    pub fn storms(&self) -> usize {
+        self.storms.load(Ordering::Relaxed)
+    }
+
+    /// Quantas faults aconteceram dentro das tempestades.
+    pub fn storm_faults(&self) -> usize {
+        self.storm_faults.load(Ordering::Relaxed)
+    }
+
+    /// Se a memória parece estar em thrashing: pelo menos metade dos
+    /// `accesses` acessos da execução foram faults dentro de tempestades.
+    pub fn is_thrashing(&self, accesses: usize) -> bool {
+        self.storms() > 0 && 2 * self.storm_faults() >= accesses
+    }
+
+    /// Substitui o conteúdo pelo de `other`.
+    pub fn copy_from(&self, other: &FaultBursts) {
+        self.lengths.copy_from(&other.lengths);
+
+        for (counter, other) in [
+            (&self.current_start, &other.current_start),
+            (&self.current_len, &other.current_len),
+            (&self.largest_start, &other.largest_start),
+            (&self.largest_len, &other.largest_len),
+            (&self.storms, &other.storms),
+            (&self.storm_faults, &other.storm_faults),
+        ] {
+            counter.store(other.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+    }
+
+    /// Imprime o histograma, a maior rajada e as tempestades.
+    pub fn print(&self, accesses: usize) {
+        self.lengths.print("faults");
+
+        if self.current() > 0 {
+            println!("  Rajada em andamento: {} faults", self.current());
+        }
+
+        if let Some(largest) = self.largest() {
+            println!(
+                "  Maior rajada: {} faults, nos acessos {}..{}",
+                largest.len(),
+                largest.start,
+                largest.end
+            );
+        }
+
+        println!(
+            "  Tempestades (≥ {} faults seguidas): {}, com {} faults{}",
+            STORM_FAULTS,
+            self.storms(),
+            self.storm_faults(),
+            if self.is_thrashing(accesses) {
+                " -- thrashing!"
+            } else {
+                ""
+            }
+        );
+    }
+}
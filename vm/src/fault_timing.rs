@@ -0,0 +1,96 @@
+//! Tempo gasto em cada fase do tratamento de uma page fault.
+//!
+//! `Mmu::handle_page_fault` mede (em tempo real, com `Instant`) quanto tempo
+//! gasta em cada fase:
+//!
+//! - escolha da vítima: perguntar ao replacer qual página sai;
+//! - writeback: salvar a página suja no disco;
+//! - carga: ler a página nova do disco;
+//! - contabilidade: todo o resto (page table, eventos do replacer,
+//!   auditoria, estatísticas...).
+//!
+//! Assim dá para justificar com números uma otimização (por exemplo, fazer o
+//! writeback em segundo plano): basta comparar quanto do tratamento é gasto
+//! em cada fase, antes e depois.
+
+use std::time::Duration;
+
+use crate::histogram::Histogram;
+
+/// Uma fase do tratamento de uma page fault.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FaultPhase {
+    VictimSelection,
+    Writeback,
+    Load,
+    Bookkeeping,
+}
+
+impl FaultPhase {
+    /// Todas as fases, na ordem em que acontecem.
+    pub const ALL: [FaultPhase; 4] = [
+        FaultPhase::VictimSelection,
+        FaultPhase::Writeback,
+        FaultPhase::Load,
+        FaultPhase::Bookkeeping,
+    ];
+
+    /// O nome da fase, para relatórios.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FaultPhase::VictimSelection => "escolha da vítima",
+            FaultPhase::Writeback => "writeback",
+            FaultPhase::Load => "carga",
+            FaultPhase::Bookkeeping => "contabilidade",
+        }
+    }
+}
+
+/// A distribuição do tempo, em nanossegundos, de cada fase.
+///
+/// A escolha da vítima e o writeback só são registrados nas faults em que
+/// aconteceram; a carga e a contabilidade, em todas.
+#[derive(Clone, Debug, Default)]
+pub struct FaultTimings {
+    phases: [Histogram; 4],
+}
+
+impl FaultTimings {
+    /// Registra a duração de uma fase.
+    pub fn record(&self, phase: FaultPhase, duration: Duration) {
+        self.phases[phase as usize].record(duration.as_nanos() as usize);
+    }
+
+    /// A distribuição do tempo de uma fase, em nanossegundos.
+    pub fn phase(&self, phase: FaultPhase) -> &Histogram {
+        &self.phases[phase as usize]
+    }
+
+    /// Substitui todas as distribuições pelas de `other`.
+    pub fn copy_from(&self, other: &FaultTimings) {
+        for (phase, other) in self.phases.iter().zip(&other.phases) {
+            phase.copy_from(other);
+        }
+    }
+
+    /// Imprime uma tabela com média e percentis de cada fase.
+    pub fn print(&self) {
+        println!(
+            "  {:<18} {:>8} {:>10} {:>10} {:>10}",
+            "fase", "amostras", "média", "p50 ≤", "p99 ≤"
+        );
+
+        for phase in FaultPhase::ALL {
+            let histogram = self.phase(phase);
+
+            println!(
+                "  {:<18} {:>8} {:>10.0} {:>10} {:>10}",
+                phase.name(),
+                histogram.count(),
+                histogram.mean(),
+                histogram.percentile(0.5),
+                histogram.percentile(0.99)
+            );
+        }
+    }
+}
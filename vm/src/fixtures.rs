@@ -0,0 +1,176 @@
+//! Dados de teste prontos: traces de acessos e imagens de swap pequenas.
+//!
+//! Os arquivos ficam em `vm/fixtures/v<versão>/` e são embutidos na crate,
+//! então exemplos, testes e documentação usam sempre os mesmos dados, sem
+//! gerá-los na hora nem depender do diretório de trabalho:
+//!
+//! ```
+//! let accesses = vm::fixtures::trace("loop_12_pages").unwrap();
+//! assert_eq!(accesses.len(), 600);
+//! ```
+//!
+//! Um trace é um arquivo texto com um acesso por linha, nos comandos da demo
+//! (em hexadecimal): `r <endereço>` ou `w <endereço> <byte>`. A primeira
+//! linha identifica o formato e a versão (`# vm-fixture trace v1`); as
+//! outras linhas começadas por `#` são comentários, que descrevem a carga.
+//!
+//! Uma imagem de swap tem as páginas de 256 bytes em sequência, como o
+//! `swapfile.bin` da demo. `swap_loader` a carrega num `MemoryPageLoader`, e
+//! o `LoaderRegistry` tem um carregador `fixture:<nome>` para cada imagem.
+//!
+//! Os arquivos de uma versão nunca mudam: uma mudança nos dados é uma versão
+//! nova, num diretório novo, para que os números esperados pelos testes
+//! continuem valendo.
+
+use thiserror::Error;
+
+use crate::{page_loader::MemoryPageLoader, trace::AccessKind, workload::Access};
+
+/// A versão dos dados embutidos.
+pub const VERSION: u32 = 1;
+
+/// O tamanho das páginas das imagens de swap.
+pub const PAGE_SIZE: usize = 256;
+
+/// A primeira linha de um trace desta versão.
+const TRACE_HEADER: &str = "# vm-fixture trace v1";
+
+const TRACES: &[(&str, &str)] = &[
+    (
+        "hot_cold_32_pages",
+        include_str!("../fixtures/v1/traces/hot_cold_32_pages.trace"),
+    ),
+    (
+        "loop_12_pages",
+        include_str!("../fixtures/v1/traces/loop_12_pages.trace"),
+    ),
+    (
+        "matrix_transpose_16",
+        include_str!("../fixtures/v1/traces/matrix_transpose_16.trace"),
+    ),
+    (
+        "scan_with_hot_set",
+        include_str!("../fixtures/v1/traces/scan_with_hot_set.trace"),
+    ),
+];
+
+const SWAP_IMAGES: &[(&str, &[u8])] = &[
+    (
+        "page_numbers_16",
+        include_bytes!("../fixtures/v1/swap/page_numbers_16.bin"),
+    ),
+    (
+        "text_4_pages",
+        include_bytes!("../fixtures/v1/swap/text_4_pages.bin"),
+    ),
+];
+
+/// Erro ao buscar ou interpretar um fixture.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FixtureError {
+    /// Não há fixture com esse nome.
+    #[error("fixture desconhecido: {0}")]
+    Unknown(String),
+    /// Uma linha de um trace não é um acesso válido. A linha começa em 1.
+    #[error("linha {line} do trace: {message}")]
+    Parse { line: usize, message: String },
+}
+
+/// Os nomes dos traces, em ordem.
+pub fn trace_names() -> impl Iterator<Item = &'static str> {
+    TRACES.iter().map(|(name, _)| *name)
+}
+
+/// Os nomes das imagens de swap, em ordem.
+pub fn swap_image_names() -> impl Iterator<Item = &'static str> {
+    SWAP_IMAGES.iter().map(|(name, _)| *name)
+}
+
+/// O texto de um trace, com os comentários.
+pub fn trace_text(name: &str) -> Result<&'static str, FixtureError> {
+    TRACES
+        .iter()
+        .find(|(trace, _)| *trace == name)
+        .map(|(_, text)| *text)
+        .ok_or_else(|| FixtureError::Unknown(name.to_string()))
+}
+
+/// Os acessos de um trace.
+pub fn trace(name: &str) -> Result<Vec<Access>, FixtureError> {
+    parse_trace(trace_text(name)?)
+}
+
+/// Interpreta um trace no formato dos fixtures (veja a documentação do
+/// módulo). O cabeçalho é obrigatório.
+pub fn parse_trace(text: &str) -> Result<Vec<Access>, FixtureError> {
+    let mut lines = text.lines().enumerate();
+
+    if lines.next().map(|(_, line)| line.trim()) != Some(TRACE_HEADER) {
+        return Err(FixtureError::Parse {
+            line: 1,
+            message: format!("esperava o cabeçalho '{}'", TRACE_HEADER),
+        });
+    }
+
+    let mut accesses = Vec::new();
+
+    for (i, line) in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: &str| FixtureError::Parse {
+            line: i + 1,
+            message: format!("{}: '{}'", message, line),
+        };
+        let hex = |token: Option<&str>| {
+            token
+                .and_then(|token| token.strip_prefix("0x"))
+                .and_then(|digits| usize::from_str_radix(digits, 16).ok())
+        };
+
+        let mut tokens = line.split_whitespace();
+        let kind = tokens.next();
+        let address = hex(tokens.next()).ok_or_else(|| error("endereço inválido"))?;
+
+        let access = match kind {
+            Some("r") => Access {
+                kind: AccessKind::Read,
+                address,
+                value: 0,
+            },
+            Some("w") => Access {
+                kind: AccessKind::Write,
+                address,
+                value: hex(tokens.next())
+                    .and_then(|value| u8::try_from(value).ok())
+                    .ok_or_else(|| error("byte inválido"))?,
+            },
+            _ => return Err(error("acesso inválido")),
+        };
+
+        if tokens.next().is_some() {
+            return Err(error("sobrou texto na linha"));
+        }
+
+        accesses.push(access);
+    }
+
+    Ok(accesses)
+}
+
+/// O conteúdo de uma imagem de swap.
+pub fn swap_image(name: &str) -> Result<&'static [u8], FixtureError> {
+    SWAP_IMAGES
+        .iter()
+        .find(|(image, _)| *image == name)
+        .map(|(_, bytes)| *bytes)
+        .ok_or_else(|| FixtureError::Unknown(name.to_string()))
+}
+
+/// Um carregador com as páginas de uma imagem de swap. As páginas depois do
+/// fim da imagem começam zeradas, e os writebacks ficam só no carregador.
+pub fn swap_loader(name: &str) -> Result<MemoryPageLoader, FixtureError> {
+    Ok(MemoryPageLoader::from_image(swap_image(name)?, PAGE_SIZE))
+}
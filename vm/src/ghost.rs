@@ -0,0 +1,90 @@
+//! A lista fantasma das páginas substituídas.
+//!
+//! Uma page fault sozinha não diz por que a página não estava em memória:
+//! ela pode nunca ter sido carregada (um miss frio, inevitável com qualquer
+//! política), ou pode ter sido substituída há pouco, por falta de frames (um
+//! miss de capacidade, um refault). Para separar os dois, a Mmu pode guardar
+//! os números das últimas páginas substituídas, sem o conteúdo; uma page
+//! fault numa página da lista é um refault.
+//!
+//! A lista é limitada, como a `workingset` do Linux, que só reconhece como
+//! refault uma página que voltaria a caber se a memória fosse maior: uma
+//! página substituída há mais tempo do que a lista lembra conta como um miss
+//! frio. Com uma capacidade igual ao número de frames, um refault é uma
+//! página que caberia numa memória com o dobro de frames.
+//!
+//! A lista só depende das substituições, e não da política, então o mesmo
+//! critério vale para comparar políticas diferentes.
+
+use std::collections::VecDeque;
+
+/// As últimas páginas substituídas, da mais antiga para a mais nova.
+#[derive(Clone, Debug)]
+pub struct GhostList {
+    capacity: usize,
+    pages: VecDeque<usize>,
+}
+
+impl GhostList {
+    /// Constrói uma lista vazia, que guarda até `capacity` páginas.
+    pub fn new(capacity: usize) -> Self {
+        GhostList {
+            capacity,
+            pages: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Quantas páginas a lista guarda, no máximo.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Quantas páginas a lista guarda agora.
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Se a lista está vazia.
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    /// Se a página está na lista.
+    pub fn contains(&self, page_number: usize) -> bool {
+        self.pages.contains(&page_number)
+    }
+
+    /// Guarda uma página que acabou de ser substituída, esquecendo a mais
+    /// antiga se a lista está cheia.
+    pub fn insert(&mut self, page_number: usize) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.remove(page_number);
+
+        if self.pages.len() == self.capacity {
+            self.pages.pop_front();
+        }
+
+        self.pages.push_back(page_number);
+    }
+
+    /// Tira a página da lista, devolvendo se ela estava lá: numa page fault,
+    /// se a página é um refault.
+    pub fn remove(&mut self, page_number: usize) -> bool {
+        match self.pages.iter().position(|&page| page == page_number) {
+            Some(position) => {
+                self.pages.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Itera sobre as páginas, da substituída há mais tempo para a mais
+    /// recente.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.pages.iter().copied()
+    }
+}
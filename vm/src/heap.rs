@@ -0,0 +1,581 @@
+//! Um alocador de heap (`malloc`/`free`) dentro do espaço de endereçamento de
+//! um processo.
+//!
+//! O `Heap` pede memória ao kernel com `sbrk` (veja o módulo `syscall`) e a
+//! divide em blocos. Como num alocador de verdade, os metadados ficam na
+//! própria memória do processo: cada bloco começa com um cabeçalho de 2
+//! bytes (o tamanho do bloco, com o bit 0 dizendo se ele está em uso),
+//! lido e escrito pela Mmu. Então cada `malloc` e `free` também faz
+//! acessos, e as page faults do alocador aparecem junto com as do programa.
+//! As páginas novas do heap são *demand-zero*: só são carregadas (vazias)
+//! quando um cabeçalho ou um dado é escrito nelas.
+//!
+//! Há duas estratégias (veja `Strategy`):
+//!
+//! - **first-fit**: percorre os blocos em ordem de endereço e usa o primeiro
+//!   livre que cabe, dividindo-o se sobrar espaço. Blocos livres vizinhos
+//!   são juntados durante o percurso. Se nenhum cabe, o heap cresce com
+//!   `sbrk` só o necessário;
+//! - **buddy**: no primeiro `malloc`, reserva de uma vez uma arena com a
+//!   maior potência de 2 de bytes que cabe no heap do processo. Cada bloco
+//!   tem uma potência de 2 de bytes; um bloco grande demais é dividido ao
+//!   meio até caber, e um bloco liberado é juntado com o seu "irmão" (o
+//!   endereço com o bit do tamanho trocado) enquanto ele também estiver
+//!   livre. Não há fragmentação externa entre irmãos, mas cada pedido é
+//!   arredondado para cima: a fragmentação interna é maior.
+//!
+//! O heap é dono do break do processo: o programa não deve chamar `sbrk`
+//! por conta própria depois do primeiro `malloc`. `Heap::stats` mostra as
+//! duas fragmentações, para comparar as estratégias.
+
+use std::{collections::BTreeMap, fmt, ops::Range};
+
+use log::{debug, warn};
+use thiserror::Error;
+
+use crate::{
+    mmu::Mmu,
+    page_loader::PageLoader,
+    page_replacer::PageReplacer,
+    region::Pid,
+    syscall::{Kernel, SyscallError},
+};
+
+/// O tamanho do cabeçalho de cada bloco.
+pub const HEADER_SIZE: usize = 2;
+
+/// O menor bloco do first-fit: um cabeçalho e 2 bytes de dados.
+const MIN_FIRST_FIT_BLOCK: usize = 4;
+
+/// O menor bloco do buddy.
+const MIN_BUDDY_BLOCK: usize = 16;
+
+/// Como o heap escolhe os blocos.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    #[default]
+    FirstFit,
+    Buddy,
+}
+
+impl Strategy {
+    /// Lê o nome da estratégia, `first-fit` ou `buddy`.
+    pub fn parse(text: &str) -> Option<Strategy> {
+        match text {
+            "first-fit" => Some(Strategy::FirstFit),
+            "buddy" => Some(Strategy::Buddy),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::FirstFit => write!(f, "first-fit"),
+            Strategy::Buddy => write!(f, "buddy"),
+        }
+    }
+}
+
+/// Um `malloc` ou `free` que falhou.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Error)]
+pub enum HeapError {
+    /// O kernel não deu mais memória, ou a arena do buddy está cheia.
+    #[error("sem memória ({})", out_of_memory_cause(.0))]
+    OutOfMemory(Option<SyscallError>),
+    /// O ponteiro passado ao `free` não é de um bloco em uso.
+    #[error("{0:#06X} não é um bloco em uso")]
+    InvalidPointer(usize),
+    /// A Mmu não conseguiu ler ou escrever o cabeçalho nesse endereço.
+    #[error("o acesso ao cabeçalho em {0:#06X} falhou")]
+    Fault(usize),
+}
+
+/// A causa de um `HeapError::OutOfMemory`, nas mensagens.
+fn out_of_memory_cause(error: &Option<SyscallError>) -> String {
+    match error {
+        Some(error) => error.to_string(),
+        None => "arena cheia".to_string(),
+    }
+}
+
+impl From<SyscallError> for HeapError {
+    fn from(error: SyscallError) -> Self {
+        HeapError::OutOfMemory(Some(error))
+    }
+}
+
+/// Um bloco do heap, visto pelo seu cabeçalho.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    /// O endereço do cabeçalho.
+    pub address: usize,
+    /// O tamanho do bloco, com o cabeçalho.
+    pub size: usize,
+    pub used: bool,
+}
+
+/// O uso e a fragmentação do heap.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Os bytes entre o começo e o fim do heap.
+    pub heap_bytes: usize,
+    /// Quantos blocos estão em uso.
+    pub used_blocks: usize,
+    /// Os bytes pedidos nos `malloc` dos blocos em uso.
+    pub requested_bytes: usize,
+    /// Os bytes dos blocos em uso, com os cabeçalhos e o arredondamento.
+    pub used_bytes: usize,
+    /// Os bytes dos blocos livres.
+    pub free_bytes: usize,
+    /// O maior trecho livre contíguo, juntando os blocos livres vizinhos.
+    pub largest_free: usize,
+}
+
+impl HeapStats {
+    /// A fração dos bytes em uso que não foi pedida: cabeçalhos e
+    /// arredondamento.
+    pub fn internal_fragmentation(&self) -> f64 {
+        if self.used_bytes == 0 {
+            return 0.0;
+        }
+
+        1.0 - self.requested_bytes as f64 / self.used_bytes as f64
+    }
+
+    /// A fração dos bytes livres que não está no maior trecho livre: quanto
+    /// da memória livre não serve para um pedido grande.
+    pub fn external_fragmentation(&self) -> f64 {
+        if self.free_bytes == 0 {
+            return 0.0;
+        }
+
+        1.0 - self.largest_free as f64 / self.free_bytes as f64
+    }
+
+    pub fn print_report(&self) {
+        println!("Tamanho do heap:      {} bytes", self.heap_bytes);
+        println!(
+            "Em uso:               {} bytes em {} blocos ({} pedidos)",
+            self.used_bytes, self.used_blocks, self.requested_bytes
+        );
+        println!(
+            "Livre:                {} bytes (maior trecho: {})",
+            self.free_bytes, self.largest_free
+        );
+        println!(
+            "Fragmentação interna: {:.2} %",
+            self.internal_fragmentation() * 100.0
+        );
+        println!(
+            "Fragmentação externa: {:.2} %",
+            self.external_fragmentation() * 100.0
+        );
+    }
+}
+
+/// O heap de um processo.
+pub struct Heap {
+    pid: Pid,
+    strategy: Strategy,
+    /// O começo e o fim dos blocos, a partir do primeiro `malloc`.
+    bounds: Option<(usize, usize)>,
+    /// Os bytes pedidos em cada bloco em uso, pelo ponteiro devolvido.
+    allocations: BTreeMap<usize, usize>,
+}
+
+impl Heap {
+    /// Constrói o heap do processo, ainda vazio: a memória é pedida ao
+    /// kernel no primeiro `malloc`.
+    pub fn new(pid: Pid, strategy: Strategy) -> Self {
+        Heap {
+            pid,
+            strategy,
+            bounds: None,
+            allocations: BTreeMap::new(),
+        }
+    }
+
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Os endereços do heap, do primeiro bloco ao fim do último, se já houve
+    /// um `malloc`.
+    pub fn bounds(&self) -> Option<Range<usize>> {
+        self.bounds.map(|(start, end)| start..end)
+    }
+
+    /// Os blocos em uso, como `(ponteiro, bytes pedidos)`.
+    pub fn allocations(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.allocations.iter().map(|(&ptr, &size)| (ptr, size))
+    }
+
+    /// Aloca um bloco com pelo menos `size` bytes, devolvendo o endereço dos
+    /// dados (logo depois do cabeçalho).
+    pub fn malloc<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        kernel: &mut Kernel,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        size: usize,
+    ) -> Result<usize, HeapError> {
+        let block = match self.strategy {
+            Strategy::FirstFit => self.first_fit_malloc(kernel, mmu, size)?,
+            Strategy::Buddy => self.buddy_malloc(kernel, mmu, size)?,
+        };
+        let ptr = block + HEADER_SIZE;
+
+        debug!(
+            "heap: processo {} alocou {} bytes em {:#06X}",
+            self.pid, size, ptr
+        );
+
+        self.allocations.insert(ptr, size);
+
+        Ok(ptr)
+    }
+
+    /// Libera o bloco alocado em `ptr`.
+    pub fn free<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        ptr: usize,
+    ) -> Result<(), HeapError> {
+        if self.allocations.remove(&ptr).is_none() {
+            return Err(HeapError::InvalidPointer(ptr));
+        }
+
+        debug!("heap: processo {} liberou {:#06X}", self.pid, ptr);
+
+        let address = ptr - HEADER_SIZE;
+        let (size, _) = read_header(mmu, address)?;
+        write_header(mmu, address, size, false)?;
+
+        if self.strategy == Strategy::Buddy {
+            self.buddy_merge(mmu, address, size)?;
+        }
+
+        Ok(())
+    }
+
+    /// Os blocos do heap, em ordem de endereço, lidos sem contar como
+    /// acessos (veja `Mmu::peek_page`). Uma falha do carregador causa um
+    /// panic.
+    pub fn blocks<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+    ) -> Vec<Block> {
+        let Some((start, end)) = self.bounds else {
+            return Vec::new();
+        };
+
+        let mut blocks = Vec::new();
+        let mut address = start;
+
+        while address < end {
+            let page = mmu
+                .peek_page(address >> 8)
+                .unwrap_or_else(|error| panic!("heap: {}", error));
+            let offset = address & 0xFF;
+            let header = u16::from_le_bytes([page[offset], page[offset + 1]]) as usize;
+            let size = header & !1;
+            if size == 0 {
+                break;
+            }
+
+            blocks.push(Block {
+                address,
+                size,
+                used: header & 1 == 1,
+            });
+            address += size;
+        }
+
+        blocks
+    }
+
+    /// O uso e a fragmentação do heap agora.
+    pub fn stats<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+    ) -> HeapStats {
+        let mut stats = HeapStats {
+            heap_bytes: self.bounds.map_or(0, |(start, end)| end - start),
+            requested_bytes: self.allocations.values().sum(),
+            ..Default::default()
+        };
+        let mut free_run = 0;
+
+        for block in self.blocks(mmu) {
+            if block.used {
+                stats.used_blocks += 1;
+                stats.used_bytes += block.size;
+                free_run = 0;
+            } else {
+                stats.free_bytes += block.size;
+                free_run += block.size;
+                stats.largest_free = stats.largest_free.max(free_run);
+            }
+        }
+
+        stats
+    }
+
+    fn first_fit_malloc<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        kernel: &mut Kernel,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        size: usize,
+    ) -> Result<usize, HeapError> {
+        let need = (HEADER_SIZE + size.next_multiple_of(2)).max(MIN_FIRST_FIT_BLOCK);
+
+        let (start, end) = match self.bounds {
+            Some(bounds) => bounds,
+            None => {
+                // Os cabeçalhos ficam em endereços pares.
+                let brk = kernel.sbrk(mmu, self.pid, 0)?;
+                let start = kernel.sbrk(mmu, self.pid, (brk & 1) as isize)? + (brk & 1);
+                self.bounds = Some((start, start));
+
+                (start, start)
+            }
+        };
+
+        // O último bloco livre visto, se ele vai até o fim do heap.
+        let mut tail = None;
+        let mut address = start;
+
+        while address < end {
+            let (mut size, used) = read_header(mmu, address)?;
+
+            if !used {
+                // Junta os blocos livres que vêm depois deste.
+                let mut merged = false;
+                while address + size < end {
+                    let (next_size, next_used) = read_header(mmu, address + size)?;
+                    if next_used {
+                        break;
+                    }
+
+                    size += next_size;
+                    merged = true;
+                }
+                if merged {
+                    write_header(mmu, address, size, false)?;
+                }
+
+                if size >= need {
+                    return self.split(mmu, address, size, need);
+                }
+
+                if address + size == end {
+                    tail = Some((address, size));
+                }
+            }
+
+            address += size;
+        }
+
+        // Nada cabe: o heap cresce, aproveitando o bloco livre do fim.
+        let (address, free) = tail.unwrap_or((end, 0));
+        kernel.sbrk(mmu, self.pid, (need - free) as isize)?;
+        self.bounds = Some((start, address + need));
+        write_header(mmu, address, need, true)?;
+
+        Ok(address)
+    }
+
+    /// Usa `need` bytes do bloco livre, deixando o resto como um bloco livre
+    /// se couber um.
+    fn split<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        address: usize,
+        size: usize,
+        need: usize,
+    ) -> Result<usize, HeapError> {
+        if size - need >= MIN_FIRST_FIT_BLOCK {
+            write_header(mmu, address + need, size - need, false)?;
+            write_header(mmu, address, need, true)?;
+        } else {
+            write_header(mmu, address, size, true)?;
+        }
+
+        Ok(address)
+    }
+
+    fn buddy_malloc<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        kernel: &mut Kernel,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        size: usize,
+    ) -> Result<usize, HeapError> {
+        let need = (HEADER_SIZE + size)
+            .next_power_of_two()
+            .max(MIN_BUDDY_BLOCK);
+
+        let (start, end) = match self.bounds {
+            Some(bounds) => bounds,
+            None => {
+                let process = kernel
+                    .process(self.pid)
+                    .ok_or(SyscallError::NoSuchProcess(self.pid))?;
+                let available = (process.heap.end << 8) - process.brk;
+                if available < MIN_BUDDY_BLOCK {
+                    return Err(SyscallError::NoMemory.into());
+                }
+
+                // A arena toda é mapeada de uma vez, mas as páginas só são
+                // carregadas quando tocadas.
+                let arena = 1 << available.ilog2();
+                let start = kernel.sbrk(mmu, self.pid, arena as isize)?;
+                write_header(mmu, start, arena, false)?;
+                self.bounds = Some((start, start + arena));
+
+                (start, start + arena)
+            }
+        };
+
+        // O menor bloco livre que cabe, o primeiro em caso de empate.
+        let mut best: Option<(usize, usize)> = None;
+        let mut address = start;
+
+        while address < end {
+            let (size, used) = read_header(mmu, address)?;
+
+            if !used && size >= need && best.is_none_or(|(_, best_size)| size < best_size) {
+                best = Some((address, size));
+            }
+
+            address += size;
+        }
+
+        let (address, mut size) = best.ok_or(HeapError::OutOfMemory(None))?;
+
+        while size > need {
+            size /= 2;
+            write_header(mmu, address + size, size, false)?;
+        }
+        write_header(mmu, address, size, true)?;
+
+        Ok(address)
+    }
+
+    /// Junta o bloco recém-liberado com o seu irmão, enquanto ele também
+    /// estiver livre e inteiro.
+    fn buddy_merge<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        mut address: usize,
+        mut size: usize,
+    ) -> Result<(), HeapError> {
+        let (start, end) = self.bounds.unwrap();
+
+        while size < end - start {
+            let buddy = start + ((address - start) ^ size);
+            if read_header(mmu, buddy)? != (size, false) {
+                break;
+            }
+
+            address = address.min(buddy);
+            size *= 2;
+            write_header(mmu, address, size, false)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Lê o cabeçalho do bloco: o tamanho e se está em uso. Uma falha da Mmu
+/// vira um `HeapError::Fault`, como as dos syscalls viram `EFAULT`.
+fn read_header<
+    const MEM_SIZE: usize,
+    const FRAME_COUNT: usize,
+    const PAGE_COUNT: usize,
+    REPLACER: PageReplacer,
+    LOADER: PageLoader,
+>(
+    mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+    address: usize,
+) -> Result<(usize, bool), HeapError> {
+    let mut header = [0; HEADER_SIZE];
+    mmu.read_into(address, &mut header).map_err(|error| {
+        warn!("heap: {}", error);
+        HeapError::Fault(address)
+    })?;
+    let header = u16::from_le_bytes(header) as usize;
+
+    Ok((header & !1, header & 1 == 1))
+}
+
+fn write_header<
+    const MEM_SIZE: usize,
+    const FRAME_COUNT: usize,
+    const PAGE_COUNT: usize,
+    REPLACER: PageReplacer,
+    LOADER: PageLoader,
+>(
+    mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+    address: usize,
+    size: usize,
+    used: bool,
+) -> Result<(), HeapError> {
+    mmu.write_from(address, &((size | used as usize) as u16).to_le_bytes())
+        .map_err(|error| {
+            warn!("heap: {}", error);
+            HeapError::Fault(address)
+        })
+}
@@ -0,0 +1,146 @@
+//! Um histograma simples, com baldes em potências de 2.
+//!
+//! Usado pelas estatísticas que são distribuições (por exemplo, por quantos
+//! acessos uma página fica suja antes do writeback), onde a média sozinha
+//! esconde o formato da distribuição.
+
+use crate::sync::{AtomicUsize, Ordering};
+
+/// O número de baldes: um para o zero e um para cada bit de `usize`.
+const BUCKETS: usize = usize::BITS as usize + 1;
+
+/// Um histograma de valores inteiros. O balde `i` conta os valores em
+/// `[2^(i-1), 2^i)`, e o balde 0 conta os zeros.
+///
+/// Os contadores são atômicos, então o histograma pode ser atualizado por
+/// várias threads (e lido enquanto é atualizado) sem um lock.
+#[derive(Debug)]
+pub struct Histogram {
+    buckets: [AtomicUsize; BUCKETS],
+    count: AtomicUsize,
+    sum: AtomicUsize,
+    max: AtomicUsize,
+}
+
+impl Histogram {
+    /// Constrói um histograma vazio.
+    pub fn new() -> Self {
+        Histogram {
+            buckets: std::array::from_fn(|_| AtomicUsize::new(0)),
+            count: AtomicUsize::new(0),
+            sum: AtomicUsize::new(0),
+            max: AtomicUsize::new(0),
+        }
+    }
+
+    /// Registra um valor.
+    pub fn record(&self, value: usize) {
+        let bucket = (usize::BITS - value.leading_zeros()) as usize;
+
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+    }
+
+    /// Quantos valores foram registrados.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// O maior valor registrado.
+    pub fn max(&self) -> usize {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    /// A média dos valores registrados.
+    pub fn mean(&self) -> f64 {
+        let count = self.count();
+
+        if count == 0 {
+            return 0.0;
+        }
+
+        self.sum.load(Ordering::Relaxed) as f64 / count as f64
+    }
+
+    /// Uma estimativa do percentil `q` (entre 0 e 1): o maior valor possível
+    /// no balde onde o percentil cai. Como os baldes dobram de tamanho, a
+    /// estimativa pode ser até 2x o valor real.
+    pub fn percentile(&self, q: f64) -> usize {
+        let count = self.count();
+
+        if count == 0 {
+            return 0;
+        }
+
+        let rank = ((q * count as f64).ceil() as usize).max(1);
+        let mut seen = 0;
+
+        for (_, end, bucket_count) in self.buckets() {
+            seen += bucket_count;
+
+            if seen >= rank {
+                return (end - 1).min(self.max());
+            }
+        }
+
+        self.max()
+    }
+
+    /// Substitui o conteúdo deste histograma pelo de `other`.
+    pub fn copy_from(&self, other: &Histogram) {
+        for (bucket, other) in self.buckets.iter().zip(&other.buckets) {
+            bucket.store(other.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+
+        self.count.store(other.count(), Ordering::Relaxed);
+        self.sum
+            .store(other.sum.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.max.store(other.max(), Ordering::Relaxed);
+    }
+
+    /// Itera sobre os baldes não vazios, como `(início, fim, contagem)`,
+    /// onde o balde conta os valores em `[início, fim)`.
+    pub fn buckets(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (i, count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .map(|(i, count)| match i {
+                0 => (0, 1, count),
+                i => (1 << (i - 1), 1 << i, count),
+            })
+    }
+
+    /// Imprime o histograma, uma linha por balde não vazio.
+    pub fn print(&self, unit: &str) {
+        println!(
+            "  {} amostras, média {:.2} {}, máximo {} {}",
+            self.count(),
+            self.mean(),
+            unit,
+            self.max(),
+            unit
+        );
+
+        for (start, end, count) in self.buckets() {
+            println!("  [{:>6}, {:>6}): {}", start, end, count);
+        }
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Histogram {
+    fn clone(&self) -> Self {
+        let histogram = Histogram::new();
+        histogram.copy_from(self);
+        histogram
+    }
+}
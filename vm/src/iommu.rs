@@ -0,0 +1,348 @@
+//! IOMMU: tradução dos endereços usados pelos dispositivos.
+//!
+//! Com DMA puro (veja o módulo `dma`), o dispositivo recebe endereços físicos
+//! e pode escrever em qualquer frame. Uma IOMMU coloca uma page table também
+//! na frente dos dispositivos: eles usam endereços de I/O ("IOVA"), que a
+//! IOMMU traduz para frames com uma page table própria, mantida pelo sistema
+//! operacional. Um acesso a um endereço de I/O não mapeado é uma fault da
+//! IOMMU, e o acesso é bloqueado em vez de corromper a memória.
+//!
+//! A page table do dispositivo é um `PageTable`, a mesma estrutura da Mmu.
+//! Diferente da Mmu, não há page faults tratáveis: o dispositivo não pode
+//! esperar uma página ser carregada, então o sistema operacional mapeia (e
+//! fixa, veja `Mmu::pin_page`) as páginas antes da transferência, com
+//! `Iommu::map_page`.
+//!
+//! Um endereço de I/O além das `IO_PAGE_COUNT` páginas da IOMMU também é
+//! bloqueado, com uma fault própria; mapear uma página de I/O que não existe
+//! é um erro.
+
+use log::{debug, warn};
+use thiserror::Error;
+
+use crate::{
+    dma::DmaCheck,
+    error::{kind_name, MmuError},
+    mmu::Mmu,
+    page_loader::PageLoader,
+    page_replacer::PageReplacer,
+    page_table::PageTable,
+    trace::AccessKind,
+};
+
+/// Um acesso de dispositivo bloqueado pela IOMMU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Error)]
+pub enum IommuFault {
+    /// A página de I/O do endereço não está mapeada.
+    #[error("{} em {iova:#06X}: página de I/O não mapeada", kind_name(*.kind))]
+    Unmapped { kind: AccessKind, iova: usize },
+    /// O endereço está além das páginas de I/O da IOMMU.
+    #[error("{} em {iova:#06X}: fora das páginas de I/O", kind_name(*.kind))]
+    OutOfRange { kind: AccessKind, iova: usize },
+}
+
+impl IommuFault {
+    /// O tipo do acesso bloqueado.
+    pub fn kind(&self) -> AccessKind {
+        match *self {
+            IommuFault::Unmapped { kind, .. } | IommuFault::OutOfRange { kind, .. } => kind,
+        }
+    }
+
+    /// O endereço de I/O acessado.
+    pub fn iova(&self) -> usize {
+        match *self {
+            IommuFault::Unmapped { iova, .. } | IommuFault::OutOfRange { iova, .. } => iova,
+        }
+    }
+}
+
+/// Erro ao mapear ou desmapear uma página de I/O.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum IommuError {
+    /// A página de I/O não existe: a IOMMU tem `io_page_count` páginas.
+    #[error("a página de I/O {io_page:#04X} não existe: a IOMMU tem {io_page_count} páginas")]
+    OutOfRange {
+        io_page: usize,
+        io_page_count: usize,
+    },
+    /// A Mmu não conseguiu fixar a página virtual (veja `Mmu::pin_page`).
+    #[error(transparent)]
+    Mmu(MmuError),
+}
+
+impl From<MmuError> for IommuError {
+    fn from(error: MmuError) -> Self {
+        IommuError::Mmu(error)
+    }
+}
+
+/// As estatísticas da IOMMU.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IommuStats {
+    /// Traduções bem-sucedidas (uma por página tocada).
+    pub translations: usize,
+    /// Acessos bloqueados, por falta de mapeamento ou fora das páginas de
+    /// I/O.
+    pub faults: usize,
+    pub bytes_read: usize,
+    pub bytes_written: usize,
+}
+
+impl IommuStats {
+    pub fn print_stats(&self) {
+        println!("===== Estatísticas da IOMMU =====");
+        println!("Traduções: {}", self.translations);
+        println!("  Faults:  {}", self.faults);
+        println!("Bytes lidos pelos dispositivos:   {}", self.bytes_read);
+        println!("Bytes escritos pelos dispositivos: {}", self.bytes_written);
+    }
+}
+
+/// Uma IOMMU com `IO_PAGE_COUNT` páginas de I/O.
+pub struct Iommu<const IO_PAGE_COUNT: usize> {
+    page_table: PageTable<IO_PAGE_COUNT>,
+    /// A página virtual da Mmu mapeada em cada página de I/O por `map_page`.
+    pages: [Option<usize>; IO_PAGE_COUNT],
+    stats: IommuStats,
+    faults: Vec<IommuFault>,
+}
+
+impl<const IO_PAGE_COUNT: usize> Iommu<IO_PAGE_COUNT> {
+    /// Constrói uma IOMMU sem nenhum mapeamento.
+    pub fn new() -> Self {
+        Iommu {
+            page_table: PageTable::new(),
+            pages: [None; IO_PAGE_COUNT],
+            stats: IommuStats::default(),
+            faults: Vec::new(),
+        }
+    }
+
+    /// As estatísticas até agora.
+    pub fn stats(&self) -> IommuStats {
+        self.stats
+    }
+
+    /// Os acessos bloqueados, em ordem.
+    pub fn faults(&self) -> &[IommuFault] {
+        &self.faults
+    }
+
+    /// O frame mapeado na página de I/O, se há um.
+    pub fn frame(&self, io_page: usize) -> Option<usize> {
+        if io_page >= IO_PAGE_COUNT {
+            return None;
+        }
+
+        self.page_table.get(io_page).map(|entry| entry.frame_index)
+    }
+
+    /// Recusa uma página de I/O além de `IO_PAGE_COUNT`.
+    fn check_io_page(io_page: usize) -> Result<(), IommuError> {
+        if io_page < IO_PAGE_COUNT {
+            return Ok(());
+        }
+
+        Err(IommuError::OutOfRange {
+            io_page,
+            io_page_count: IO_PAGE_COUNT,
+        })
+    }
+
+    /// Mapeia a página de I/O direto num frame. O frame deveria pertencer a
+    /// uma página fixada; se não pertence, os acessos a ele aparecem em
+    /// `Mmu::dma_violations`.
+    pub fn map(&mut self, io_page: usize, frame_index: usize) -> Result<(), IommuError> {
+        self.unmap(io_page)?;
+
+        debug!(
+            "iommu: página de I/O {:#04X} mapeada no frame {:#04X}",
+            io_page, frame_index
+        );
+
+        self.page_table.set(io_page, frame_index);
+
+        Ok(())
+    }
+
+    /// Remove o mapeamento da página de I/O. Uma página virtual mapeada por
+    /// `map_page` continua fixada; use `unmap_page` para desfixá-la também.
+    pub fn unmap(&mut self, io_page: usize) -> Result<(), IommuError> {
+        Self::check_io_page(io_page)?;
+
+        self.page_table.invalidate(io_page);
+        self.pages[io_page] = None;
+
+        Ok(())
+    }
+
+    /// Fixa a página virtual na Mmu e mapeia a página de I/O no seu frame,
+    /// como o `dma_map_page` do Linux. Os erros são os de `Mmu::pin_page`,
+    /// ou uma página de I/O que não existe; num erro, nada é mapeado.
+    pub fn map_page<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        io_page: usize,
+        page_number: usize,
+    ) -> Result<(), IommuError> {
+        Self::check_io_page(io_page)?;
+        mmu.pin_page(page_number)?;
+
+        let frame_index = mmu
+            .frame_map()
+            .iter()
+            .position(|page| *page == Some(page_number))
+            .unwrap();
+
+        self.map(io_page, frame_index)?;
+        self.pages[io_page] = Some(page_number);
+
+        Ok(())
+    }
+
+    /// Remove o mapeamento da página de I/O e desfixa a página virtual
+    /// mapeada nela por `map_page`. O erro é o de `unmap`.
+    pub fn unmap_page<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        io_page: usize,
+    ) -> Result<(), IommuError> {
+        Self::check_io_page(io_page)?;
+
+        if let Some(page_number) = self.pages[io_page] {
+            mmu.unpin_page(page_number);
+        }
+
+        self.unmap(io_page)
+    }
+
+    /// Traduz o endereço de I/O em `(frame, offset)`, registrando a fault se
+    /// ele não está mapeado ou está além das páginas de I/O.
+    fn translate(&mut self, iova: usize, kind: AccessKind) -> Result<(usize, usize), IommuFault> {
+        let io_page = iova >> 8;
+        let offset = iova & 0xFF;
+
+        let entry = match io_page < IO_PAGE_COUNT {
+            true => self.page_table.get(io_page),
+            false => None,
+        };
+
+        match entry {
+            Some(entry) => {
+                self.stats.translations += 1;
+                self.page_table.mark_referenced(io_page);
+
+                Ok((entry.frame_index, offset))
+            }
+            None => {
+                let fault = match io_page < IO_PAGE_COUNT {
+                    true => IommuFault::Unmapped { kind, iova },
+                    false => IommuFault::OutOfRange { kind, iova },
+                };
+
+                warn!("iommu: acesso bloqueado: {}", fault);
+                self.stats.faults += 1;
+                self.faults.push(fault);
+
+                Err(fault)
+            }
+        }
+    }
+
+    /// O dispositivo lê `buffer.len()` bytes a partir do endereço de I/O.
+    /// Os bytes de páginas não mapeadas (ou além das páginas de I/O) são
+    /// lidos como 0xFF, como num barramento sem resposta. Se alguma parte da
+    /// leitura foi bloqueada, devolve a primeira fault.
+    pub fn read<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        iova: usize,
+        buffer: &mut [u8],
+    ) -> Result<(), IommuFault> {
+        let mut result = Ok(());
+        let mut done = 0;
+
+        while done < buffer.len() {
+            let len = (buffer.len() - done).min(0x100 - (iova + done) % 0x100);
+            let chunk = &mut buffer[done..done + len];
+
+            match self.translate(iova + done, AccessKind::Read) {
+                Ok((frame_index, offset)) => {
+                    mmu.physical(DmaCheck::Flag)
+                        .read(frame_index, offset, chunk);
+                    self.stats.bytes_read += len;
+                }
+                Err(fault) => {
+                    chunk.fill(0xFF);
+                    result = result.and(Err(fault));
+                }
+            }
+
+            done += len;
+        }
+
+        result
+    }
+
+    /// O dispositivo escreve `data` a partir do endereço de I/O. As escritas
+    /// em páginas não mapeadas (ou além das páginas de I/O) são descartadas.
+    /// Se alguma parte da escrita foi bloqueada, devolve a primeira fault.
+    pub fn write<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        iova: usize,
+        data: &[u8],
+    ) -> Result<(), IommuFault> {
+        let mut result = Ok(());
+        let mut done = 0;
+
+        while done < data.len() {
+            let len = (data.len() - done).min(0x100 - (iova + done) % 0x100);
+            let chunk = &data[done..done + len];
+
+            match self.translate(iova + done, AccessKind::Write) {
+                Ok((frame_index, offset)) => {
+                    mmu.physical(DmaCheck::Flag)
+                        .write(frame_index, offset, chunk);
+                    self.stats.bytes_written += len;
+                }
+                Err(fault) => result = result.and(Err(fault)),
+            }
+
+            done += len;
+        }
+
+        result
+    }
+}
+
+impl<const IO_PAGE_COUNT: usize> Default for Iommu<IO_PAGE_COUNT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
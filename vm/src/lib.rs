@@ -1,4 +1,44 @@
+pub mod analysis;
+pub mod audit;
+pub mod cost;
+pub mod dirty_log;
+pub mod dma;
+pub mod encrypted_loader;
+pub mod error;
+pub mod fault_burst;
+pub mod fault_timing;
+pub mod fixtures;
+pub mod ghost;
+pub mod heap;
+pub mod histogram;
+pub mod iommu;
 pub mod mmu;
+pub mod nested;
+pub mod overlay_loader;
 pub mod page_loader;
 pub mod page_replacer;
 pub mod page_table;
+pub mod partition;
+pub mod privilege;
+pub mod refcount;
+pub mod region;
+pub mod rng;
+pub mod scenario;
+pub mod shadow;
+pub mod sharing;
+pub mod simulate;
+pub mod state;
+mod sync;
+pub mod syscall;
+pub mod thp;
+pub mod three_cs;
+pub mod tier;
+pub mod time_travel;
+pub mod timeline;
+pub mod tlb;
+pub mod trace;
+pub mod trace_diff;
+#[cfg(feature = "typed")]
+pub mod typed;
+pub mod word;
+pub mod workload;
@@ -4,42 +4,610 @@
 //! Esse módulo implementa a lógica principal de gerenciamento de memória,
 //! terceirizando alguns comportamentos para módulos adjacentes.
 
-use std::{collections::VecDeque, ops::Range};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Write,
+    ops::{Deref, DerefMut, Range},
+    time::{Duration, Instant},
+};
 
-use log::{debug, info};
+use log::{debug, info, log_enabled, trace, warn, Level};
 
 use crate::{
-    page_loader::PageLoader,
-    page_replacer::{PageEvent, PageReplacer},
-    page_table::PageTable,
+    audit::{AuditLog, EvictionAudit},
+    cost::CostModel,
+    dirty_log::DirtyBitmap,
+    dma::{DmaCheck, DmaViolation, PhysicalAccessor},
+    error::{AccessError, AccessFailure, LoaderError, LoaderMismatch, MmuError},
+    fault_burst::FaultBursts,
+    fault_timing::{FaultPhase, FaultTimings},
+    ghost::GhostList,
+    histogram::Histogram,
+    page_loader::{LoaderCapabilities, PageLoader},
+    page_replacer::{PageEvent, PageReplacer, ReplacerState, Victim},
+    page_table::{
+        PageTable, PageTableEntry, PlacementError, PTE_MAX_FRAMES, PTE_SIZE, SOFTWARE_BITS,
+    },
+    partition::{
+        FaultScope, FramePartitions, Partition, PartitionError, PartitionReport, PartitionRow,
+        Reservation, VictimScope,
+    },
+    privilege::{PrivilegeFault, PrivilegeMode},
+    refcount::FrameRefCounts,
+    region::{
+        AccessViolation, PermissionReport, Permissions, Pid, ProcessStats, RegionError, RegionMap,
+    },
+    sharing::SharedFrames,
+    state::{PageState, PageTableState, StateError},
+    sync::{Arc, AtomicUsize, Ordering},
+    thp::{huge_page_of, HUGE_PAGE_PAGES},
+    three_cs::{MissBreakdown, ThreeCs},
+    tier::{Migration, Tier, TierConfig, TierReport, TieredMemory},
+    time_travel::{JournalEntry, LoggedAccess, RewindError, TimeTravel},
+    tlb::{Tlb, TlbReport},
+    trace::{AccessKind, AccessObserver, AccessRecord, Trace},
+    word::Endianness,
 };
 
-#[derive(Default)]
+/// O tamanho das páginas e dos frames, em bytes. Os endereços virtuais têm
+/// 16 bits: os 8 de cima são a página e os 8 de baixo, o offset (veja
+/// `Mmu::try_new`).
+pub const PAGE_SIZE: usize = 256;
+
+/// As estatísticas da Mmu.
+///
+/// Todos os contadores são atômicos: eles podem ser atualizados sem um lock
+/// e lidos ao vivo por outra thread (veja `Mmu::stats_handle`) enquanto a
+/// simulação roda. Cada contador é consistente por si só, mas uma leitura
+/// concorrente pode ver, por exemplo, um hit já contado e o acesso seguinte
+/// ainda não.
+#[derive(Default, Debug)]
 pub struct MmuStats {
-    hits: usize,
-    misses: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    writebacks: AtomicUsize,
+    flushes: AtomicUsize,
+    ticks: AtomicUsize,
+    page_table_reads: AtomicUsize,
+    page_table_writes: AtomicUsize,
+    cow_breaks: AtomicUsize,
+    shared_frames: AtomicUsize,
+    saved_frames: AtomicUsize,
+    peak_saved_frames: AtomicUsize,
+    process_swap_outs: AtomicUsize,
+    swap_prefaults: AtomicUsize,
+    swap_refaults: AtomicUsize,
+    thp_promotions: AtomicUsize,
+    thp_demotions: AtomicUsize,
+    thp_migrated_pages: AtomicUsize,
+    swappiness_skips: AtomicUsize,
+    rss_limit_hits: AtomicUsize,
+    protection_faults: AtomicUsize,
+    instruction_fetches: AtomicUsize,
+    instruction_misses: AtomicUsize,
+    cold_misses: AtomicUsize,
+    capacity_misses: AtomicUsize,
+    cleaned_pages: AtomicUsize,
+    tlb_hits: AtomicUsize,
+    tlb_misses: AtomicUsize,
+    tlb_huge_hits: AtomicUsize,
+    dirty_lifetime_accesses: Histogram,
+    dirty_lifetime_ticks: Histogram,
+    victim_ages: Histogram,
+    refault_distances: Histogram,
+    working_set_sizes: Histogram,
+    fault_timings: FaultTimings,
+    fault_bursts: FaultBursts,
 }
 
 impl MmuStats {
+    /// Número de page hits.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Número de page faults.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Número de páginas sujas escritas de volta no disco ao sair da
+    /// memória, como vítimas de uma page fault ou num swap-out.
+    pub fn writebacks(&self) -> usize {
+        self.writebacks.load(Ordering::Relaxed)
+    }
+
+    /// Número de páginas sujas escritas no disco sem sair da memória: por
+    /// `Mmu::flush_page`, `Mmu::flush_all` ou a pedido do replacer (veja
+    /// `cleaned_pages`). Elas não contam em `writebacks`.
+    pub fn flushes(&self) -> usize {
+        self.flushes.load(Ordering::Relaxed)
+    }
+
+    /// Número de ticks (veja `Mmu::tick`).
+    pub fn ticks(&self) -> usize {
+        self.ticks.load(Ordering::Relaxed)
+    }
+
+    /// Número de leituras de entradas da page table na memória física (veja
+    /// `Mmu::place_page_table_in_memory`).
+    pub fn page_table_reads(&self) -> usize {
+        self.page_table_reads.load(Ordering::Relaxed)
+    }
+
+    /// Número de escritas de entradas da page table na memória física.
+    pub fn page_table_writes(&self) -> usize {
+        self.page_table_writes.load(Ordering::Relaxed)
+    }
+
+    /// Número de quebras de copy-on-write (veja o módulo `sharing`).
+    pub fn cow_breaks(&self) -> usize {
+        self.cow_breaks.load(Ordering::Relaxed)
+    }
+
+    /// Quantos frames são compartilhados agora.
+    pub fn shared_frames(&self) -> usize {
+        self.shared_frames.load(Ordering::Relaxed)
+    }
+
+    /// Quantos frames o compartilhamento economiza agora.
+    pub fn saved_frames(&self) -> usize {
+        self.saved_frames.load(Ordering::Relaxed)
+    }
+
+    /// O maior número de frames economizados ao mesmo tempo.
+    pub fn peak_saved_frames(&self) -> usize {
+        self.peak_saved_frames.load(Ordering::Relaxed)
+    }
+
+    /// Número de processos inteiros tirados da memória (veja
+    /// `Mmu::swap_out_process`).
+    pub fn process_swap_outs(&self) -> usize {
+        self.process_swap_outs.load(Ordering::Relaxed)
+    }
+
+    /// Número de páginas trazidas de volta de uma vez, com o working set, por
+    /// `Mmu::swap_in_process`.
+    pub fn swap_prefaults(&self) -> usize {
+        self.swap_prefaults.load(Ordering::Relaxed)
+    }
+
+    /// Número de page faults em páginas que saíram da memória com o seu
+    /// processo e ainda não tinham voltado.
+    pub fn swap_refaults(&self) -> usize {
+        self.swap_refaults.load(Ordering::Relaxed)
+    }
+
+    /// Número de páginas grandes formadas (veja o módulo `thp`).
+    pub fn thp_promotions(&self) -> usize {
+        self.thp_promotions.load(Ordering::Relaxed)
+    }
+
+    /// Número de páginas grandes desfeitas.
+    pub fn thp_demotions(&self) -> usize {
+        self.thp_demotions.load(Ordering::Relaxed)
+    }
+
+    /// Número de páginas movidas de frame para formar páginas grandes.
+    pub fn thp_migrated_pages(&self) -> usize {
+        self.thp_migrated_pages.load(Ordering::Relaxed)
+    }
+
+    /// Número de vezes que uma página escolhida pelo replacer foi poupada
+    /// pela swappiness da sua região (veja `Mmu::set_region_swappiness`).
+    pub fn swappiness_skips(&self) -> usize {
+        self.swappiness_skips.load(Ordering::Relaxed)
+    }
+
+    /// Número de acessos recusados porque a page fault passaria o processo
+    /// do seu limite de RSS (veja `Mmu::set_rss_limit`).
+    pub fn rss_limit_hits(&self) -> usize {
+        self.rss_limit_hits.load(Ordering::Relaxed)
+    }
+
+    /// Número de acessos recusados pela proteção da página (veja
+    /// `Mmu::set_protection`).
+    pub fn protection_faults(&self) -> usize {
+        self.protection_faults.load(Ordering::Relaxed)
+    }
+
+    /// Número de buscas de instrução (veja `Mmu::fetch`), que também contam
+    /// em `accesses`.
+    pub fn instruction_fetches(&self) -> usize {
+        self.instruction_fetches.load(Ordering::Relaxed)
+    }
+
+    /// Número de page faults nas buscas de instrução.
+    pub fn instruction_misses(&self) -> usize {
+        self.instruction_misses.load(Ordering::Relaxed)
+    }
+
+    /// Número de acessos a dados: leituras e escritas.
+    pub fn data_accesses(&self) -> usize {
+        self.accesses() - self.instruction_fetches()
+    }
+
+    /// Número de page faults nos acessos a dados.
+    pub fn data_misses(&self) -> usize {
+        self.misses() - self.instruction_misses()
+    }
+
+    /// Número de páginas sujas escritas no disco a pedido do replacer, sem
+    /// sair da memória (veja `PageReplacer::scheduled_writebacks`). Essas
+    /// escritas também contam em `flushes`.
+    pub fn cleaned_pages(&self) -> usize {
+        self.cleaned_pages.load(Ordering::Relaxed)
+    }
+
+    /// Número de traduções que acertaram o TLB (veja `Mmu::enable_tlb`).
+    pub fn tlb_hits(&self) -> usize {
+        self.tlb_hits.load(Ordering::Relaxed)
+    }
+
+    /// Número de hits no TLB numa entrada de página grande (veja o módulo
+    /// `thp`), que também contam em `tlb_hits`.
+    pub fn tlb_huge_hits(&self) -> usize {
+        self.tlb_huge_hits.load(Ordering::Relaxed)
+    }
+
+    /// Número de traduções que erraram o TLB e precisaram do page walk.
+    pub fn tlb_misses(&self) -> usize {
+        self.tlb_misses.load(Ordering::Relaxed)
+    }
+
+    /// Número de page faults em páginas fora da lista fantasma: carregadas
+    /// pela primeira vez, ou substituídas há mais tempo do que a lista
+    /// lembra. Só é contado com a lista habilitada (veja
+    /// `Mmu::enable_ghost_list`).
+    pub fn cold_misses(&self) -> usize {
+        self.cold_misses.load(Ordering::Relaxed)
+    }
+
+    /// Número de page faults em páginas da lista fantasma, substituídas há
+    /// pouco: os refaults. Só é contado com a lista habilitada.
+    pub fn capacity_misses(&self) -> usize {
+        self.capacity_misses.load(Ordering::Relaxed)
+    }
+
+    /// Substitui todos os contadores pelos de `other`, sem trocar a
+    /// instância (quem tem um `stats_handle` continua vendo os valores).
+    pub fn copy_from(&self, other: &MmuStats) {
+        self.hits.store(other.hits(), Ordering::Relaxed);
+        self.misses.store(other.misses(), Ordering::Relaxed);
+        self.writebacks.store(other.writebacks(), Ordering::Relaxed);
+        self.flushes.store(other.flushes(), Ordering::Relaxed);
+        self.ticks.store(other.ticks(), Ordering::Relaxed);
+        self.page_table_reads
+            .store(other.page_table_reads(), Ordering::Relaxed);
+        self.page_table_writes
+            .store(other.page_table_writes(), Ordering::Relaxed);
+        self.cow_breaks.store(other.cow_breaks(), Ordering::Relaxed);
+        self.shared_frames
+            .store(other.shared_frames(), Ordering::Relaxed);
+        self.saved_frames
+            .store(other.saved_frames(), Ordering::Relaxed);
+        self.peak_saved_frames
+            .store(other.peak_saved_frames(), Ordering::Relaxed);
+        self.process_swap_outs
+            .store(other.process_swap_outs(), Ordering::Relaxed);
+        self.swap_prefaults
+            .store(other.swap_prefaults(), Ordering::Relaxed);
+        self.swap_refaults
+            .store(other.swap_refaults(), Ordering::Relaxed);
+        self.thp_promotions
+            .store(other.thp_promotions(), Ordering::Relaxed);
+        self.thp_demotions
+            .store(other.thp_demotions(), Ordering::Relaxed);
+        self.thp_migrated_pages
+            .store(other.thp_migrated_pages(), Ordering::Relaxed);
+        self.swappiness_skips
+            .store(other.swappiness_skips(), Ordering::Relaxed);
+        self.rss_limit_hits
+            .store(other.rss_limit_hits(), Ordering::Relaxed);
+        self.protection_faults
+            .store(other.protection_faults(), Ordering::Relaxed);
+        self.instruction_fetches
+            .store(other.instruction_fetches(), Ordering::Relaxed);
+        self.instruction_misses
+            .store(other.instruction_misses(), Ordering::Relaxed);
+        self.cold_misses
+            .store(other.cold_misses(), Ordering::Relaxed);
+        self.capacity_misses
+            .store(other.capacity_misses(), Ordering::Relaxed);
+        self.cleaned_pages
+            .store(other.cleaned_pages(), Ordering::Relaxed);
+        self.tlb_hits.store(other.tlb_hits(), Ordering::Relaxed);
+        self.tlb_misses.store(other.tlb_misses(), Ordering::Relaxed);
+        self.tlb_huge_hits
+            .store(other.tlb_huge_hits(), Ordering::Relaxed);
+        self.dirty_lifetime_accesses
+            .copy_from(&other.dirty_lifetime_accesses);
+        self.dirty_lifetime_ticks
+            .copy_from(&other.dirty_lifetime_ticks);
+        self.victim_ages.copy_from(&other.victim_ages);
+        self.refault_distances.copy_from(&other.refault_distances);
+        self.working_set_sizes.copy_from(&other.working_set_sizes);
+        self.fault_timings.copy_from(&other.fault_timings);
+        self.fault_bursts.copy_from(&other.fault_bursts);
+    }
+
+    /// Por quantos acessos cada página escrita de volta ficou suja antes do
+    /// writeback.
+    pub fn dirty_lifetime_accesses(&self) -> &Histogram {
+        &self.dirty_lifetime_accesses
+    }
+
+    /// Por quantos ticks cada página escrita de volta ficou suja antes do
+    /// writeback.
+    pub fn dirty_lifetime_ticks(&self) -> &Histogram {
+        &self.dirty_lifetime_ticks
+    }
+
+    /// Há quantos acessos cada página substituída tinha sido carregada.
+    pub fn victim_ages(&self) -> &Histogram {
+        &self.victim_ages
+    }
+
+    /// Quantos acessos cada página substituída ficou fora da memória até a
+    /// page fault que a trouxe de volta. As que não voltaram não aparecem
+    /// aqui (veja `unreturned_victims`).
+    pub fn refault_distances(&self) -> &Histogram {
+        &self.refault_distances
+    }
+
+    /// Quantas substituições não foram seguidas, até agora, da volta da
+    /// vítima.
+    pub fn unreturned_victims(&self) -> usize {
+        self.victim_ages
+            .count()
+            .saturating_sub(self.refault_distances.count())
+    }
+
+    /// O tamanho do working set calculado pelo replacer, depois de cada
+    /// acesso, em páginas. Vazio se a política não calcula um (veja
+    /// `PageReplacer::working_set_size`).
+    pub fn working_set_sizes(&self) -> &Histogram {
+        &self.working_set_sizes
+    }
+
+    /// O tempo real gasto em cada fase do tratamento das page faults.
+    pub fn fault_timings(&self) -> &FaultTimings {
+        &self.fault_timings
+    }
+
+    /// As rajadas de page faults seguidas (veja o módulo `fault_burst`).
+    pub fn fault_bursts(&self) -> &FaultBursts {
+        &self.fault_bursts
+    }
+
+    /// Número total de acessos.
+    pub fn accesses(&self) -> usize {
+        self.hits() + self.misses()
+    }
+
+    /// A fração das traduções que precisou do page walk: a taxa de misses
+    /// do TLB quando ele está habilitado, e 1 sem ele, quando todo acesso
+    /// lê a page table.
+    pub fn page_walk_rate(&self) -> f64 {
+        let translations = self.tlb_hits() + self.tlb_misses();
+
+        if translations == 0 {
+            return 1.0;
+        }
+
+        self.tlb_misses() as f64 / translations as f64
+    }
+
+    /// O tempo total simulado da execução, em nanossegundos, segundo o
+    /// modelo de custo dado. Cada acesso custa um acesso à memória, e um
+    /// page walk quando a tradução não está no TLB; cada page fault soma o
+    /// tempo de tratamento, incluindo trap, trocas de contexto e o writeback
+    /// quando houve, e o walk refeito. As escritas de páginas que continuam
+    /// em memória (veja `flushes`) custam uma escrita cada.
+    pub fn total_time(&self, cost: &CostModel) -> f64 {
+        self.accesses() as f64
+            * (self.page_walk_rate() * cost.page_walk_time() + cost.memory_access)
+            + self.misses() as f64 * (cost.fault_service_time(false) + cost.page_walk_time())
+            + (self.writebacks() + self.flushes()) as f64 * cost.page_write
+    }
+
+    /// O tempo efetivo de acesso (EAT), em nanossegundos, a partir das taxas
+    /// observadas:
+    ///
+    /// ```text
+    /// EAT = m * walk + (1 - p) * ma + p * (tempo de tratamento da fault + walk + ma)
+    /// ```
+    ///
+    /// onde `p` é a taxa de page faults, `ma` o tempo de acesso à memória,
+    /// `walk` o tempo do page walk (veja `CostModel::page_walk_time`) e `m`
+    /// a fração das traduções que precisou dele (veja `page_walk_rate`). O
+    /// tempo de tratamento inclui o writeback na fração `d` das faults cuja
+    /// página substituída estava suja; as escritas de `flushes` não entram
+    /// em `d`, porque não seguram nenhuma fault.
+    pub fn effective_access_time(&self, cost: &CostModel) -> f64 {
+        if self.accesses() == 0 {
+            return 0.0;
+        }
+
+        let misses = self.misses();
+
+        let p = misses as f64 / self.accesses() as f64;
+        let d = if misses == 0 {
+            0.0
+        } else {
+            self.writebacks() as f64 / misses as f64
+        };
+
+        let fault_time = cost.fault_service_time(false) + d * cost.page_write;
+
+        let walk = cost.page_walk_time();
+
+        self.page_walk_rate() * walk
+            + (1.0 - p) * cost.memory_access
+            + p * (fault_time + walk + cost.memory_access)
+    }
+
     pub fn print_stats(&self) {
-        let total = self.hits + self.misses;
-        let miss_rate = self.misses as f32 / total as f32;
+        let (hits, misses, writebacks) = (self.hits(), self.misses(), self.writebacks());
+
+        let total = hits + misses;
+        let miss_rate = misses as f32 / total as f32;
 
         println!("===== Estatísticas da MMU =====");
         println!("Total de acessos: {}", total);
-        println!(
-            "  Misses: {:>6} ({:>6.2} %)",
-            self.misses,
-            miss_rate * 100.0
-        );
+        println!("  Misses: {:>6} ({:>6.2} %)", misses, miss_rate * 100.0);
         println!(
             "  Hits:   {:>6} ({:>6.2} %)",
-            self.hits,
+            hits,
             (1.0 - miss_rate) * 100.0
         );
+        println!("  Writebacks: {}", writebacks);
+
+        if self.flushes() > 0 {
+            println!("  Páginas escritas sem sair da memória: {}", self.flushes());
+        }
+
+        if self.instruction_fetches() > 0 {
+            println!(
+                "  Buscas de instrução: {} ({} misses); acessos a dados: {} ({} misses)",
+                self.instruction_fetches(),
+                self.instruction_misses(),
+                self.data_accesses(),
+                self.data_misses()
+            );
+        }
+
+        if self.tlb_hits() + self.tlb_misses() > 0 {
+            println!(
+                "  TLB: {} hits, {} misses (page walk em {:.2} % das traduções)",
+                self.tlb_hits(),
+                self.tlb_misses(),
+                self.page_walk_rate() * 100.0
+            );
+        }
+
+        if self.cleaned_pages() > 0 {
+            println!(
+                "  Páginas limpas a pedido do replacer: {}",
+                self.cleaned_pages()
+            );
+        }
+
+        if self.cold_misses() + self.capacity_misses() > 0 {
+            println!(
+                "  Misses frios: {}, de capacidade (refaults): {}",
+                self.cold_misses(),
+                self.capacity_misses()
+            );
+        }
+
+        if self.page_table_reads() > 0 {
+            println!(
+                "  Page table na memória: {} leituras, {} escritas",
+                self.page_table_reads(),
+                self.page_table_writes()
+            );
+        }
+
+        if self.peak_saved_frames() > 0 {
+            println!(
+                "  Frames compartilhados: {} (economizando {}, no máximo {})",
+                self.shared_frames(),
+                self.saved_frames(),
+                self.peak_saved_frames()
+            );
+            println!("  Quebras de COW: {}", self.cow_breaks());
+        }
+
+        if self.thp_promotions() > 0 {
+            println!(
+                "  Páginas grandes: {} formadas, {} desfeitas, {} páginas movidas, {} hits no TLB",
+                self.thp_promotions(),
+                self.thp_demotions(),
+                self.thp_migrated_pages(),
+                self.tlb_huge_hits()
+            );
+        }
+
+        if self.swappiness_skips() > 0 {
+            println!(
+                "  Vítimas poupadas pela swappiness: {}",
+                self.swappiness_skips()
+            );
+        }
+
+        if self.rss_limit_hits() > 0 {
+            println!(
+                "  Acessos recusados pelo limite de RSS: {}",
+                self.rss_limit_hits()
+            );
+        }
+
+        if self.protection_faults() > 0 {
+            println!("  Faltas de proteção: {}", self.protection_faults());
+        }
+
+        if self.process_swap_outs() > 0 {
+            println!(
+                "  Processos tirados da memória: {} ({} páginas trazidas com o working set, {} refaults)",
+                self.process_swap_outs(),
+                self.swap_prefaults(),
+                self.swap_refaults()
+            );
+        }
+
+        if writebacks + self.flushes() > 0 {
+            println!("Tempo sujo até o writeback (acessos):");
+            self.dirty_lifetime_accesses.print("acessos");
+
+            if self.ticks() > 0 {
+                println!("Tempo sujo até o writeback (ticks):");
+                self.dirty_lifetime_ticks.print("ticks");
+            }
+        }
+
+        if self.victim_ages.count() > 0 {
+            println!("Idade das vítimas desde a carga (acessos):");
+            self.victim_ages.print("acessos");
+
+            println!(
+                "Distância até o refault das vítimas (acessos; {} não voltaram):",
+                self.unreturned_victims()
+            );
+            self.refault_distances.print("acessos");
+        }
+
+        if self.working_set_sizes.count() > 0 {
+            println!("Tamanho do working set da política (páginas):");
+            self.working_set_sizes.print("páginas");
+        }
+
+        if misses > 0 {
+            println!("Rajadas de page faults seguidas (faults):");
+            self.fault_bursts.print(total);
+
+            println!("Tempo de tratamento das page faults (ns):");
+            self.fault_timings.print();
+        }
+    }
+}
+
+impl Clone for MmuStats {
+    fn clone(&self) -> Self {
+        let stats = MmuStats::default();
+        stats.copy_from(self);
+        stats
     }
 }
 
+/// Um trecho contíguo da memória virtual, como um `iovec`: `len` bytes a
+/// partir de `address`. Veja `Mmu::gather` e `Mmu::scatter`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IoSegment {
+    pub address: usize,
+    pub len: usize,
+}
+
 /// Uma struct parametrizada pelo tamanho da memória, pelo número de frames,
 /// pelo número de páginas e pelos tipos do carregador de páginas e da política
 /// de substituição de páginas.
@@ -52,16 +620,147 @@ pub struct Mmu<
 > {
     /// Um array de MEM_SIZE bytes representa a memória.
     memory: [u8; MEM_SIZE],
+    /// O resto do estado, que a viagem no tempo copia e restaura de uma vez
+    /// (veja `MmuState`).
+    state: MmuState<PAGE_COUNT>,
+    /// A implementação da política de substituição.
+    replacer: REPLACER,
+    /// A implementação do carregador de páginas.
+    loader: LoaderSlot<LOADER>,
+    /// Instância de monitoramento de estatísticas.
+    pub stats: Arc<MmuStats>,
+    /// O log de acessos, se habilitado.
+    trace: Option<Trace>,
+    /// Observadores notificados a cada acesso.
+    observers: Vec<Box<dyn AccessObserver>>,
+    /// O log das últimas decisões de substituição, se habilitado.
+    audit: Option<AuditLog>,
+    /// Snapshots e logs para voltar no tempo, se habilitado.
+    time_travel: Option<TimeTravel<REPLACER, MmuSnapshot<PAGE_COUNT, REPLACER>>>,
+}
+
+/// O carregador da Mmu. Só fica vazio quando `Mmu::shutdown` o devolve, e
+/// então o `Drop` da Mmu não tem mais onde escrever as páginas sujas.
+struct LoaderSlot<LOADER>(Option<LOADER>);
+
+impl<LOADER> Deref for LoaderSlot<LOADER> {
+    type Target = LOADER;
+
+    fn deref(&self) -> &LOADER {
+        self.0.as_ref().expect("mmu: o carregador já foi devolvido")
+    }
+}
+
+impl<LOADER> DerefMut for LoaderSlot<LOADER> {
+    fn deref_mut(&mut self) -> &mut LOADER {
+        self.0.as_mut().expect("mmu: o carregador já foi devolvido")
+    }
+}
+
+/// Uma vítima escolhida pelo replacer, com o estado dele antes da escolha,
+/// se a auditoria está habilitada.
+#[derive(Clone)]
+struct ReservedVictim {
+    victim: Victim,
+    replacer_state: Option<String>,
+}
+
+/// O estado da Mmu que a viagem no tempo copia e restaura inteiro. A
+/// memória, o replacer e as estatísticas também voltam, mas ficam de fora:
+/// a memória é um array grande demais para a pilha, o replacer só se copia
+/// com `TimeTravel::clone_replacer` e as estatísticas são restauradas no
+/// lugar, para quem tem um `stats_handle`. Um campo novo da Mmu entra aqui,
+/// e o snapshot o leva sem mais nada.
+#[derive(Clone)]
+struct MmuState<const PAGE_COUNT: usize> {
     /// Uma fila de frames ainda não alocados na memória principal.
     free_frames: VecDeque<usize>,
     /// A page table.
     page_table: PageTable<PAGE_COUNT>,
-    /// A implementação da política de substituição.
+    /// O que o carregador sabe fazer (veja `PageLoader::capabilities`).
+    capabilities: LoaderCapabilities,
+    /// As regiões mapeadas (e já desmapeadas).
+    regions: RegionMap,
+    /// No modo estrito, acessos fora de regiões vivas são registrados como
+    /// violações.
+    strict: bool,
+    /// As violações detectadas no modo estrito.
+    violations: Vec<AccessViolation>,
+    /// A cada quantos acessos a Mmu chama `tick` sozinha, se chama.
+    tick_interval: Option<usize>,
+    /// A ordem dos bytes dos acessos de mais de um byte (veja o módulo
+    /// `word`).
+    endianness: Endianness,
+    /// As páginas escritas desde a última coleta (veja
+    /// `collect_and_clear_dirty_bitmap`).
+    dirty_log: DirtyBitmap,
+    /// Os frames reservados para a page table, se ela está na memória física.
+    page_table_frames: Option<Range<usize>>,
+    /// Os acessos por DMA a frames não fixados (veja o módulo `dma`).
+    dma_violations: Vec<DmaViolation>,
+    /// O modo de privilégio da CPU (veja o módulo `privilege`).
+    mode: PrivilegeMode,
+    /// As páginas que só o supervisor acessa.
+    supervisor_pages: BTreeSet<usize>,
+    /// Os acessos em modo usuário a páginas do supervisor.
+    privilege_faults: Vec<PrivilegeFault>,
+    /// O máximo de páginas em memória de cada processo com limite.
+    rss_limits: BTreeMap<Pid, usize>,
+    /// As reservas de frames dos processos (veja o módulo `partition`).
+    partitions: FramePartitions,
+    /// Os frames compartilhados com copy-on-write (veja o módulo `sharing`).
+    shared: SharedFrames,
+    /// Quantas entradas da page table apontam para cada frame (veja o módulo
+    /// `refcount`).
+    refcounts: FrameRefCounts,
+    /// Os frames economizados pelo compartilhamento ao longo do tempo, como
+    /// `(acesso, frames)`, a cada mudança.
+    sharing_history: Vec<(usize, usize)>,
+    /// O working set de cada processo tirado da memória por
+    /// `swap_out_process`, que ainda não voltou.
+    working_sets: BTreeMap<Pid, Vec<usize>>,
+    /// As páginas que saíram da memória com o seu processo e ainda não
+    /// voltaram; uma page fault numa delas é um refault.
+    swapped_pages: BTreeSet<usize>,
+    /// Em que acesso cada página residente foi carregada.
+    loaded_at: BTreeMap<usize, usize>,
+    /// A posição de cada página no estado inicial (veja `load_state`), que
+    /// desempata `loaded_at`: todas foram carregadas antes do primeiro
+    /// acesso.
+    state_order: BTreeMap<usize, usize>,
+    /// Em que acesso cada página substituída saiu da memória, até ela voltar.
+    evicted_at: BTreeMap<usize, usize>,
+    /// As últimas páginas substituídas, se habilitado (veja o módulo
+    /// `ghost`).
+    ghost: Option<GhostList>,
+    /// A classificação dos misses nos três Cs, se habilitada (veja o módulo
+    /// `three_cs`).
+    three_cs: Option<ThreeCs>,
+    /// A primeira falha do carregador no acesso em andamento, com o frame da
+    /// página que falhou. O acesso a devolve ao terminar (veja
+    /// `Mmu::read`): entre dois acessos, é sempre `None`.
+    loader_error: Option<(LoaderError, Option<usize>)>,
+    /// A vítima escolhida antes da page fault ou da quebra de COW do acesso
+    /// em andamento (veja `reserve_victim`). Como `loader_error`, é sempre
+    /// `None` entre dois acessos.
+    reserved_victim: Option<ReservedVictim>,
+    /// O TLB, se habilitado.
+    tlb: Option<Tlb>,
+    /// Se as páginas grandes transparentes estão habilitadas (veja o módulo
+    /// `thp`).
+    thp: bool,
+    /// As páginas grandes, pela primeira página de cada uma.
+    huge_pages: BTreeSet<usize>,
+    /// A memória em camadas, se habilitada.
+    tiers: Option<TieredMemory>,
+}
+
+/// Uma cópia do estado da Mmu, usada pela viagem no tempo.
+struct MmuSnapshot<const PAGE_COUNT: usize, REPLACER> {
+    memory: Vec<u8>,
     replacer: REPLACER,
-    /// A implementação do carregador de páginas.
-    loader: LOADER,
-    /// Instância de monitoramento de estatísticas.
-    pub stats: MmuStats,
+    stats: MmuStats,
+    state: MmuState<PAGE_COUNT>,
 }
 
 impl<
@@ -76,158 +775,3725 @@ where
     LOADER: PageLoader,
 {
     /// Constrói uma nova instância de Mmu.
+    ///
+    /// Causa um panic se o carregador não serve para a Mmu (páginas de outro
+    /// tamanho que o dos frames, por exemplo); use `try_new` para tratar o
+    /// erro.
     pub fn new(replacer: REPLACER, loader: LOADER) -> Self {
-        let free_frames = (0..FRAME_COUNT).into_iter().collect();
+        Self::try_new(replacer, loader).unwrap_or_else(|err| panic!("mmu: {}", err))
+    }
+
+    /// Constrói uma nova instância de Mmu, perguntando antes ao carregador
+    /// o que ele sabe fazer (veja `PageLoader::capabilities`).
+    ///
+    /// Os frames, `MEM_SIZE / FRAME_COUNT`, precisam ter `PAGE_SIZE` bytes:
+    /// a tradução tira a página e o offset dos bits do endereço, e com
+    /// frames maiores várias páginas virtuais cairiam no mesmo frame.
+    ///
+    /// O tamanho das páginas do carregador, se ele tem um, precisa ser o
+    /// tamanho dos frames, `MEM_SIZE / FRAME_COUNT`: com tamanhos
+    /// diferentes, cada page fault copiaria um pedaço da página errada para
+    /// o frame. E o carregador precisa guardar todas as `PAGE_COUNT`
+    /// páginas, se tem um limite. O resto das capacidades liga ou desliga a
+    /// limpeza em segundo plano e os writebacks agrupados.
+    pub fn try_new(replacer: REPLACER, loader: LOADER) -> Result<Self, LoaderMismatch> {
+        let frame_size = MEM_SIZE / FRAME_COUNT;
+        if frame_size != PAGE_SIZE || !MEM_SIZE.is_multiple_of(FRAME_COUNT) {
+            return Err(LoaderMismatch::FrameSize { frame_size });
+        }
+
+        let capabilities = loader.capabilities();
 
-        Mmu {
+        if let Some(loader_page_size) = capabilities.page_size {
+            if loader_page_size != frame_size {
+                return Err(LoaderMismatch::PageSize {
+                    backend: loader.backend().to_string(),
+                    loader_page_size,
+                    frame_size,
+                });
+            }
+        }
+
+        if let Some(max_pages) = capabilities.max_pages {
+            if max_pages < PAGE_COUNT {
+                return Err(LoaderMismatch::PageCount {
+                    backend: loader.backend().to_string(),
+                    max_pages,
+                    page_count: PAGE_COUNT,
+                });
+            }
+        }
+
+        let free_frames = (0..FRAME_COUNT).collect();
+
+        Ok(Mmu {
             memory: [0; MEM_SIZE],
-            free_frames,
-            page_table: PageTable::new(),
+            state: MmuState {
+                free_frames,
+                page_table: PageTable::new(),
+                capabilities,
+                regions: RegionMap::new(),
+                strict: false,
+                violations: Vec::new(),
+                tick_interval: None,
+                endianness: Endianness::default(),
+                dirty_log: DirtyBitmap::new(PAGE_COUNT),
+                page_table_frames: None,
+                dma_violations: Vec::new(),
+                mode: PrivilegeMode::default(),
+                supervisor_pages: BTreeSet::new(),
+                privilege_faults: Vec::new(),
+                rss_limits: BTreeMap::new(),
+                partitions: FramePartitions::new(),
+                shared: SharedFrames::new(),
+                refcounts: FrameRefCounts::new(FRAME_COUNT),
+                sharing_history: Vec::new(),
+                working_sets: BTreeMap::new(),
+                swapped_pages: BTreeSet::new(),
+                loaded_at: BTreeMap::new(),
+                state_order: BTreeMap::new(),
+                evicted_at: BTreeMap::new(),
+                ghost: None,
+                three_cs: None,
+                loader_error: None,
+                reserved_victim: None,
+                tlb: None,
+                thp: false,
+                huge_pages: BTreeSet::new(),
+                tiers: None,
+            },
             replacer,
-            loader,
-            stats: MmuStats::default(),
-        }
+            loader: LoaderSlot(Some(loader)),
+            stats: Arc::new(MmuStats::default()),
+            trace: None,
+            observers: Vec::new(),
+            audit: None,
+            time_travel: None,
+        })
     }
 
-    /// Converte um índice de frame num range que pode ser utilizado
-    /// para indexar a array memory.
-    fn frame_idx_to_range(frame_idx: usize) -> Range<usize> {
-        let frame_size = MEM_SIZE / FRAME_COUNT;
+    /// O que o carregador sabe fazer, como perguntado na construção.
+    pub fn loader_capabilities(&self) -> LoaderCapabilities {
+        self.state.capabilities
+    }
 
-        Range {
-            start: frame_idx * frame_size,
-            end: (frame_idx + 1) * frame_size,
-        }
+    /// O carregador de páginas.
+    pub fn loader(&self) -> &LOADER {
+        &self.loader
     }
 
-    /// Faz o tratamento de uma page fault.
-    fn handle_page_fault(&mut self, page_number: usize) -> usize {
-        // Aqui, inicialmente vamos escolher em qual frame carregar a página.
-        // Tenta pegar um frame que ainda não foi utilizado.
-        let frame_idx = match self.free_frames.pop_front() {
-            // Se conseguiu, retorna seu índice imediatamente, e vamos utilizá-lo.
-            Some(empty_idx) => empty_idx,
-            None => {
-                // Se não há frames vazios, vamos escolher uma página para ser substituída.
-                // Para isso, vamos chamar o nosso replacer.
-                let evicted_page_idx = self.replacer.pick_replacement_page();
+    /// O carregador de páginas, para operações que ele faz entre os acessos
+    /// (por exemplo, `EncryptedPageLoader::rotation_step`).
+    pub fn loader_mut(&mut self) -> &mut LOADER {
+        &mut self.loader
+    }
 
-                // Olhamos para dentro da entrada da page table desta página, e verificamos
-                // se a página está dirty. Se sim, então nós vamos chamar nosso loader
-                // para fazer o flush de volta para disco.
-                let evicted_page = self.page_table.get(evicted_page_idx).unwrap();
-                if evicted_page.dirty {
-                    debug!(
-                        "mmu: página {:#06X} suja, salvando antes de sobrescrever",
-                        evicted_page_idx
-                    );
+    /// A política de substituição, para consultar o que ela sabe além do
+    /// estado em `fmt_state` (por exemplo, `AdaptivePageReplacer::active`).
+    pub fn replacer(&self) -> &REPLACER {
+        &self.replacer
+    }
 
-                    let frame_range = Self::frame_idx_to_range(evicted_page.frame_index);
-                    let frame = &self.memory[frame_range];
+    /// Uma referência compartilhada às estatísticas, que pode ser enviada a
+    /// outra thread para acompanhar a simulação ao vivo.
+    pub fn stats_handle(&self) -> Arc<MmuStats> {
+        self.stats.clone()
+    }
 
-                    self.loader.flush_page(evicted_page_idx, frame);
-                }
+    /// Habilita o registro de todos os acessos em um `Trace`.
+    pub fn enable_trace(&mut self) {
+        if self.trace.is_none() {
+            self.trace = Some(Trace::new());
+        }
+    }
 
-                let idx = evicted_page.frame_index;
+    /// O log de acessos, se habilitado.
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
 
-                // Invalida a página na page table.
-                self.page_table.invalidate(page_number);
+    /// Habilita o log de auditoria, guardando as últimas `capacity` decisões
+    /// de substituição.
+    pub fn enable_audit(&mut self, capacity: usize) {
+        self.audit = Some(AuditLog::new(capacity));
+    }
 
-                // E finalmente retornamos o frame no qual essa página estava guardada.
-                idx
-            }
-        };
+    /// O log de auditoria, se habilitado.
+    pub fn audit(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
 
-        // Já que temos o frame, atualizamos a entrada na page table.
-        self.page_table.set(page_number, frame_idx);
+    /// Habilita a lista fantasma, com as últimas `capacity` páginas
+    /// substituídas, que separa as page faults em misses frios e de
+    /// capacidade (veja o módulo `ghost`).
+    pub fn enable_ghost_list(&mut self, capacity: usize) {
+        self.state.ghost = Some(GhostList::new(capacity));
+        self.checkpoint();
+    }
 
-        // Olhamos para a janela na memória que é o frame.
-        let frame_range = Self::frame_idx_to_range(frame_idx);
-        let frame = &mut self.memory[frame_range];
+    /// A lista fantasma, se habilitada.
+    pub fn ghost_list(&self) -> Option<&GhostList> {
+        self.state.ghost.as_ref()
+    }
 
-        // Chama o loader para carregar a página no frame.
-        self.loader.load_page_into(page_number, frame);
+    /// Habilita a classificação dos misses em frios, de capacidade e de
+    /// conflito, a partir do próximo acesso (veja o módulo `three_cs`).
+    pub fn enable_three_cs(&mut self) {
+        self.state.three_cs = Some(ThreeCs::new(FRAME_COUNT));
+        self.checkpoint();
+    }
 
-        // Avisa o replacer, que pode usar esse evento para seus cálculos.
-        self.replacer.page_event(PageEvent::Loaded(page_number));
+    /// Os misses classificados pelos três Cs, se a classificação está
+    /// habilitada.
+    pub fn miss_breakdown(&self) -> Option<MissBreakdown> {
+        self.state.three_cs.as_ref().map(ThreeCs::breakdown)
+    }
 
-        // Retorna o índice do frame.
-        frame_idx
+    /// Habilita um TLB com `entries` entradas (veja o módulo `tlb`).
+    pub fn enable_tlb(&mut self, entries: usize) {
+        self.state.tlb = Some(Tlb::new(entries, MEM_SIZE / FRAME_COUNT));
+        self.checkpoint();
     }
 
-    // Função principal que faz a translação entre um endereço virtual e um
-    // endereço físico (no nosso caso, modelado por um range dentro da array de
-    // memória e um offset dentro desse range).
-    fn translate_addr(&mut self, address: usize, mark_dirty: bool) -> (Range<usize>, usize) {
-        let address = address & 0xFFFF; // trunca o endereco para 16 bits
+    /// O TLB, se habilitado.
+    pub fn tlb(&self) -> Option<&Tlb> {
+        self.state.tlb.as_ref()
+    }
 
-        let page_number = (address & 0xFF00) >> 8; // top 8 bits
-        let page_offset = address & 0x00FF; // bottom 8 bits
+    /// O reach do TLB comparado com o working set atual, se o TLB está
+    /// habilitado.
+    pub fn tlb_report(&self) -> Option<TlbReport> {
+        self.state.tlb.as_ref().map(|tlb| TlbReport {
+            capacity: tlb.capacity(),
+            page_size: MEM_SIZE / FRAME_COUNT,
+            counts: tlb.counts_by_page_size().clone(),
+            base_reach: tlb.base_reach(),
+            reach: tlb.reach(),
+            working_set: self.working_set_size() * (MEM_SIZE / FRAME_COUNT),
+        })
+    }
 
-        info!(
-            "mmu: acesso addr {:#06X} page_num={:#02X} page_offset={:#02X}",
-            address, page_number, page_offset
-        );
+    /// Registra um observador que será chamado depois de cada acesso.
+    pub fn add_observer<O: AccessObserver + 'static>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
 
-        let frame_idx = match self.page_table.get(page_number) {
-            Some(entry) => {
-                // Se houve page hit, já sabemos imediatamente qual o frame
-                // que queremos acessar.
-                debug!("mmu: page hit");
-                self.stats.hits += 1;
-                entry.frame_index
-            }
-            None => {
-                // Se houve page fault, vamos escolher qual o frame será carregado,
-                // e vamos carregar a página nele.
-                debug!("mmu: page fault! tratando...");
-                self.stats.misses += 1;
-                self.handle_page_fault(page_number)
+    /// Descreve o estado atual da Mmu em formato legível: os frames ocupados,
+    /// a page table e o estado interno do replacer.
+    pub fn fmt_state(&self) -> String {
+        let frames = self.frame_map();
+
+        let mut out = String::new();
+
+        // Escrever numa String nunca falha, então podemos ignorar os erros.
+        let _ = writeln!(out, "frames:");
+        for (frame_idx, page_number) in frames.iter().enumerate() {
+            if let Some(page_number) = page_number {
+                let _ = writeln!(
+                    out,
+                    "  frame {:#04X}: página {:#04X}",
+                    frame_idx, page_number
+                );
             }
-        };
+        }
+        let _ = writeln!(out, "  livres: {}", self.state.free_frames.len());
 
-        // Quando a ação é uma escrita, também vamos marcar a dirty flag
-        // para que a página seja reescrita de volta em disco.
-        if mark_dirty {
-            self.page_table.mark_dirty(page_number);
+        if let Some(frames) = &self.state.page_table_frames {
+            let _ = writeln!(
+                out,
+                "  page table: frames {:#04X}..{:#04X}",
+                frames.start, frames.end
+            );
         }
 
-        // Emite um evento para cálculo do replacer.
-        self.replacer.page_event(PageEvent::Touched(page_number));
+        let _ = writeln!(out, "page table:");
+        for line in self.state.page_table.to_string().lines() {
+            let _ = writeln!(out, "  {}", line);
+        }
 
-        // Calcula a janela do frame dentro da array memória.
-        let frame_range = Self::frame_idx_to_range(frame_idx);
+        let _ = writeln!(out, "replacer:");
+        let _ = writeln!(out, "  {}", ReplacerState(&self.replacer));
 
-        debug!(
-            "mmu: página {:#02X} mapeada para frame físico idx={:#02X} [{:#02X}; {:#02X})",
-            page_number, frame_idx, &frame_range.start, &frame_range.end
-        );
+        out
+    }
+
+    /// O mapa de frames: para cada frame, a página que o ocupa, ou `None` se
+    /// o frame está livre.
+    pub fn frame_map(&self) -> Vec<Option<usize>> {
+        let mut frames = vec![None; FRAME_COUNT];
+
+        for (page_number, entry) in self.state.page_table.iter() {
+            frames[entry.frame_index] = Some(page_number);
+        }
+
+        frames
+    }
+
+    /// Desmapeia (libera) uma página: ela sai da page table, seu frame volta
+    /// para a lista de frames livres e seu conteúdo é descartado, mesmo que
+    /// esteja suja -- como um `munmap` de memória anônima. Os bits de
+    /// software da página são apagados; fora isso, não faz nada se a página
+    /// não está em memória.
+    pub fn unmap_page(&mut self, page_number: usize) {
+        self.release_page(page_number);
+        self.state.page_table.clear_software_bits(page_number);
+        self.checkpoint();
+    }
+
+    /// Tira a página da page table e devolve seu frame para a lista de
+    /// frames livres.
+    fn release_page(&mut self, page_number: usize) {
+        if let Some(entry) = self.state.page_table.get(page_number) {
+            debug!(
+                "mmu: desmapeando página {:#04X} (frame {:#04X})",
+                page_number, entry.frame_index
+            );
+
+            self.state.page_table.invalidate(page_number);
+            self.state.refcounts.decrement(entry.frame_index);
+            self.store_pte(page_number);
+            self.state.loaded_at.remove(&page_number);
+
+            // Um mapeamento da própria page table não devolve o frame, que
+            // continua reservado para ela; um frame compartilhado continua com
+            // as outras páginas.
+            if self.state.shared.is_shared(entry.frame_index) {
+                self.state.shared.remove(entry.frame_index, page_number);
+                self.replacer.page_event(PageEvent::Unmapped(page_number));
+                self.update_sharing_stats();
+            } else if !self.is_page_table_frame(entry.frame_index) {
+                self.state.free_frames.push_back(entry.frame_index);
+                self.replacer.page_event(PageEvent::Unmapped(page_number));
+            }
+
+            self.demote_broken_huge_pages();
+            self.check_refcounts();
+        }
+    }
+
+    /// Fixa a página em memória, carregando-a se preciso (o que conta como
+    /// uma leitura do seu primeiro byte): até `unpin_page`, ela não é mais
+    /// candidata a substituição e continua no mesmo frame. É o que o sistema
+    /// operacional faz com os buffers de DMA (veja o módulo `dma`).
+    ///
+    /// A fixação fica no flag `pinned` da entrada da page table, que some
+    /// se a página for desmapeada. Para o replacer, a página fixada some,
+    /// como se tivesse sido desmapeada, e volta como recém-carregada ao ser
+    /// liberada (veja `PageReplacer::pick_replacement_page`).
+    ///
+    /// Os erros são os da leitura (veja `read`) e, se a página está num
+    /// frame compartilhado sem um frame para a cópia, o da quebra do COW
+    /// (`AccessFailure::NoEvictablePage`); num erro, a página não é fixada.
+    pub fn pin_page(&mut self, page_number: usize) -> Result<(), MmuError> {
+        if self.state.page_table.is_pinned(page_number) {
+            return Ok(());
+        }
+
+        self.read(page_number << 8)?;
+
+        // A página precisa de um frame só seu, que não vai ser trocado por
+        // uma quebra de COW depois.
+        let frame_idx = self.state.page_table.get(page_number).unwrap().frame_index;
+        if self.state.shared.is_shared(frame_idx) {
+            self.reserve_victim(AccessKind::Read, page_number << 8, Some(page_number))?;
+            self.break_cow(page_number, frame_idx);
+        }
+
+        debug!("mmu: página {:#04X} fixada", page_number);
+
+        self.replacer.page_event(PageEvent::Unmapped(page_number));
+        self.state.page_table.set_pinned(page_number, true);
+        self.store_pte(page_number);
+        self.checkpoint();
+
+        Ok(())
+    }
+
+    /// Libera uma página fixada por `pin_page`, que volta a poder ser
+    /// substituída.
+    pub fn unpin_page(&mut self, page_number: usize) {
+        if !self.state.page_table.is_pinned(page_number) {
+            return;
+        }
+
+        self.state.page_table.set_pinned(page_number, false);
+        self.store_pte(page_number);
+
+        debug!("mmu: página {:#04X} liberada", page_number);
+
+        self.replacer.page_event(PageEvent::Loaded(page_number));
+        self.checkpoint();
+    }
+
+    /// Se a página está fixada.
+    pub fn is_pinned(&self, page_number: usize) -> bool {
+        self.state.page_table.is_pinned(page_number)
+    }
+
+    /// Faz a página `dst` apontar para o mesmo frame de `src`, com
+    /// copy-on-write, como o `fork` faz com as páginas do pai e do filho.
+    /// `src` é carregada se preciso (o que conta como uma leitura do seu
+    /// primeiro byte); o conteúdo anterior de `dst` é descartado. Veja o
+    /// módulo `sharing`.
+    ///
+    /// Devolve `false`, sem fazer nada, se `src` está fixada ou mapeia a page
+    /// table: esses frames não podem ser compartilhados, e quem chamou deve
+    /// copiar a página. Os erros são os da leitura de `src` (veja `read`);
+    /// num erro, `dst` não muda.
+    pub fn share_page(&mut self, src: usize, dst: usize) -> Result<bool, MmuError> {
+        if src == dst {
+            return Ok(true);
+        }
+
+        if self.state.page_table.is_pinned(src) {
+            return Ok(false);
+        }
+
+        self.read(src << 8)?;
+
+        let frame_idx = self.state.page_table.get(src).unwrap().frame_index;
+        if self.is_page_table_frame(frame_idx) {
+            return Ok(false);
+        }
+
+        self.release_page(dst);
+
+        debug!(
+            "mmu: página {:#04X} compartilha o frame {:#04X} da página {:#04X}",
+            dst, frame_idx, src
+        );
+
+        // O disco não tem o conteúdo de `dst`: ela já começa suja.
+        self.state.page_table.set(dst, frame_idx);
+        self.state.refcounts.increment(frame_idx);
+        self.state
+            .page_table
+            .mark_dirty(dst, (self.stats.accesses(), self.stats.ticks()));
+        self.store_pte(dst);
+        self.state.loaded_at.insert(dst, self.stats.accesses());
+        self.state.evicted_at.remove(&dst);
+        self.forget_ghost(dst);
+        self.replacer.page_event(PageEvent::Loaded(dst));
+
+        self.state.shared.share(frame_idx, src, dst);
+        self.update_sharing_stats();
+        self.check_refcounts();
+        self.checkpoint();
+
+        Ok(true)
+    }
+
+    /// Os frames compartilhados agora.
+    pub fn shared_frames(&self) -> &SharedFrames {
+        &self.state.shared
+    }
+
+    /// Os contadores de referências dos frames.
+    pub fn refcounts(&self) -> &FrameRefCounts {
+        &self.state.refcounts
+    }
+
+    /// Quantos frames o compartilhamento economizava ao longo do tempo, como
+    /// `(acesso, frames)`, a cada mudança.
+    pub fn sharing_history(&self) -> &[(usize, usize)] {
+        &self.state.sharing_history
+    }
+
+    /// Um acesso aos frames pelo índice, sem tradução, como um dispositivo
+    /// de DMA. Veja o módulo `dma`.
+    pub fn physical(
+        &mut self,
+        check: DmaCheck,
+    ) -> PhysicalAccessor<'_, MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER> {
+        PhysicalAccessor { mmu: self, check }
+    }
+
+    /// Os acessos por DMA a frames não fixados, registrados com
+    /// `DmaCheck::Flag`.
+    pub fn dma_violations(&self) -> &[DmaViolation] {
+        &self.state.dma_violations
+    }
+
+    /// Lê para `buffer`, ou escreve a partir dele, os bytes do frame a partir
+    /// de `offset`, verificando antes se o frame está fixado.
+    pub(crate) fn dma_access(
+        &mut self,
+        check: DmaCheck,
+        kind: AccessKind,
+        frame_index: usize,
+        offset: usize,
+        buffer: &mut [u8],
+    ) {
+        let frame_range = Self::frame_idx_to_range(frame_index);
+        let start = frame_range.start + offset;
+        let end = start + buffer.len();
+
+        assert!(
+            end <= frame_range.end,
+            "DMA de {} bytes a partir de {:#04X} passa do fim do frame {:#04X}",
+            buffer.len(),
+            offset,
+            frame_index
+        );
+
+        // Os frames da page table nunca saem do lugar.
+        let page_table_frame = self.is_page_table_frame(frame_index);
+        let page_number = self
+            .state
+            .page_table
+            .iter()
+            .find(|(_, entry)| entry.frame_index == frame_index)
+            .map(|(page_number, _)| page_number);
+        let pinned = page_table_frame
+            || page_number.is_some_and(|page| self.state.page_table.is_pinned(page));
+
+        if check != DmaCheck::Off && !pinned {
+            let violation = DmaViolation {
+                time: self.stats.accesses(),
+                kind,
+                frame_index,
+                page_number,
+            };
+
+            if check == DmaCheck::Panic {
+                panic!("mmu: {}", violation);
+            }
+
+            warn!("mmu: {}", violation);
+            self.state.dma_violations.push(violation);
+        }
+
+        match kind {
+            AccessKind::Read | AccessKind::Fetch => {
+                buffer.copy_from_slice(&self.memory[start..end])
+            }
+            AccessKind::Write if page_table_frame => {
+                warn!(
+                    "mmu: escrita por DMA no frame {:#04X} ignorada: o frame guarda a page table",
+                    frame_index
+                );
+            }
+            AccessKind::Write => {
+                self.memory[start..end].copy_from_slice(buffer);
+
+                if let Some(page_number) = page_number {
+                    self.state
+                        .page_table
+                        .mark_dirty(page_number, (self.stats.accesses(), self.stats.ticks()));
+                    self.store_pte(page_number);
+                    self.state.dirty_log.set(page_number);
+                }
+
+                self.checkpoint();
+            }
+        }
+    }
+
+    /// Mapeia uma região nomeada de páginas para um processo, com permissão
+    /// de leitura e escrita.
+    pub fn map_region(
+        &mut self,
+        pid: Pid,
+        name: &str,
+        pages: Range<usize>,
+    ) -> Result<(), RegionError> {
+        self.map_region_with_permissions(pid, name, pages, Permissions::READ_WRITE)
+    }
+
+    /// Mapeia uma região nomeada de páginas para um processo, declarando suas
+    /// permissões (veja `permission_report`). Uma região com páginas além de
+    /// `PAGE_COUNT` é recusada.
+    pub fn map_region_with_permissions(
+        &mut self,
+        pid: Pid,
+        name: &str,
+        pages: Range<usize>,
+        permissions: Permissions,
+    ) -> Result<(), RegionError> {
+        Self::check_region_pages(&pages)?;
+
+        debug!(
+            "mmu: mapeando região '{}' do processo {} {:#04X?} ({})",
+            name, pid, pages, permissions
+        );
+
+        self.state
+            .regions
+            .map(pid, name, pages, permissions, self.stats.accesses());
+        self.checkpoint();
+
+        Ok(())
+    }
+
+    /// Recusa as páginas de uma região se elas passam de `PAGE_COUNT`.
+    fn check_region_pages(pages: &Range<usize>) -> Result<(), RegionError> {
+        if pages.end > PAGE_COUNT {
+            return Err(RegionError::OutOfRange {
+                pages: pages.clone(),
+                page_count: PAGE_COUNT,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Troca a política de substituição durante a execução, devolvendo a
+    /// anterior. O novo replacer recebe as páginas que o anterior conhecia
+    /// (as residentes, menos as fixadas e as da page table) como se
+    /// tivessem acabado de ser carregadas, da usada há mais tempo para a mais
+    /// recente segundo o histórico do bit de referência (veja
+    /// `PageTableEntry::age`), e depois um `Touched` para cada página com o
+    /// bit de referência ligado e um `Dirtied` para cada página suja.
+    ///
+    /// Para trocar por uma política de outro tipo, use uma Mmu com um
+    /// `Box<dyn PageReplacer>`.
+    pub fn replace_policy(&mut self, replacer: REPLACER) -> REPLACER {
+        let mut pages: Vec<(usize, PageTableEntry)> = self
+            .state
+            .page_table
+            .iter()
+            .filter(|(_, entry)| !entry.pinned && !self.is_page_table_frame(entry.frame_index))
+            .collect();
+        pages.sort_by_key(|&(page_number, entry)| (entry.age, entry.referenced, page_number));
+
+        debug!(
+            "mmu: trocando a política de substituição ({} páginas residentes)",
+            pages.len()
+        );
+
+        let previous = std::mem::replace(&mut self.replacer, replacer);
+
+        for &(page_number, _) in &pages {
+            self.replacer.page_event(PageEvent::Loaded(page_number));
+        }
+
+        for &(page_number, entry) in &pages {
+            if entry.referenced {
+                self.replacer.page_event(PageEvent::Touched(page_number));
+            }
+            if entry.dirty {
+                self.replacer.page_event(PageEvent::Dirtied(page_number));
+            }
+        }
+
+        self.checkpoint();
+
+        previous
+    }
+
+    /// Muda a swappiness, de 0 a 100, da região viva do processo com esse
+    /// nome: o quanto as suas páginas podem ser escolhidas como vítimas (veja
+    /// o módulo `region`). Devolve se a região existe.
+    pub fn set_region_swappiness(&mut self, pid: Pid, name: &str, swappiness: usize) -> bool {
+        let found = self.state.regions.set_swappiness(pid, name, swappiness);
+        self.checkpoint();
+
+        found
+    }
+
+    /// Os acessos feitos em cada região comparados com as permissões
+    /// declaradas.
+    pub fn permission_report(&self) -> PermissionReport {
+        PermissionReport::from_regions(&self.state.regions)
+    }
+
+    /// Desmapeia a região viva do processo com esse nome, liberando todas as
+    /// suas páginas (veja `unmap_page`). Não faz nada se não há tal região.
+    pub fn unmap_region(&mut self, pid: Pid, name: &str) {
+        if let Some(pages) = self.state.regions.unmap(pid, name, self.stats.accesses()) {
+            debug!(
+                "mmu: desmapeando região '{}' do processo {} {:#04X?}",
+                name, pid, pages
+            );
+
+            for page_number in pages {
+                self.discard_page(page_number);
+            }
+        }
+
+        self.checkpoint();
+    }
+
+    /// Muda as páginas da região viva do processo com esse nome. As páginas
+    /// que saem da região são liberadas, como em `unmap_region`, e as que
+    /// entram começam vazias. Devolve se a região existe; as novas páginas
+    /// são recusadas, como em `map_region`, se passam de `PAGE_COUNT`.
+    pub fn resize_region(
+        &mut self,
+        pid: Pid,
+        name: &str,
+        pages: Range<usize>,
+    ) -> Result<bool, RegionError> {
+        Self::check_region_pages(&pages)?;
+
+        let Some(previous) = self.state.regions.resize(pid, name, pages.clone()) else {
+            return Ok(false);
+        };
+
+        debug!(
+            "mmu: região '{}' do processo {} de {:#04X?} para {:#04X?}",
+            name, pid, previous, pages
+        );
+
+        for page_number in previous.filter(|page_number| !pages.contains(page_number)) {
+            self.discard_page(page_number);
+        }
+
+        self.checkpoint();
+
+        Ok(true)
+    }
+
+    /// Libera a página de uma região desmapeada e esquece o que se sabia
+    /// dela: o conteúdo no swap, a última substituição e a ghost list.
+    fn discard_page(&mut self, page_number: usize) {
+        self.release_page(page_number);
+        self.state.page_table.clear_software_bits(page_number);
+        self.state.swapped_pages.remove(&page_number);
+        self.state.evicted_at.remove(&page_number);
+        self.forget_ghost(page_number);
+    }
+
+    /// Tira da memória todas as páginas residentes das regiões vivas do
+    /// processo, escrevendo as sujas no disco, como o swapping de processos
+    /// inteiros. As páginas fixadas e as que mapeiam a page table ficam.
+    ///
+    /// Antes, guarda o working set do processo: as suas páginas residentes
+    /// referenciadas desde o último tick ou nos 8 anteriores (veja
+    /// `working_set_size`), que `swap_in_process` pode trazer de volta de
+    /// uma vez.
+    ///
+    /// Os writebacks acontecem fora de um acesso, então, como em
+    /// `load_image`, o histórico da viagem no tempo é descartado. Se o
+    /// carregador falha, a página que ele não escreveu fica em memória, suja,
+    /// as outras saem, e a falha é devolvida.
+    ///
+    /// Devolve quantas páginas saíram da memória.
+    pub fn swap_out_process(&mut self, pid: Pid) -> Result<usize, LoaderError> {
+        let pages: Vec<usize> = self
+            .state
+            .regions
+            .iter()
+            .filter(|region| region.is_live() && region.pid == pid)
+            .flat_map(|region| region.pages.clone())
+            .filter(|&page_number| {
+                self.state.page_table.get(page_number).is_some_and(|entry| {
+                    !entry.pinned && !self.is_page_table_frame(entry.frame_index)
+                })
+            })
+            .collect();
+
+        let working_set: Vec<usize> = pages
+            .iter()
+            .copied()
+            .filter(|&page_number| {
+                Self::in_working_set(&self.state.page_table.get(page_number).unwrap())
+            })
+            .collect();
+
+        debug!(
+            "mmu: processo {} sai da memória ({} páginas, working set de {})",
+            pid,
+            pages.len(),
+            working_set.len()
+        );
+
+        // Os writebacks do processo inteiro vão juntos, se o carregador
+        // escreve em lote.
+        let dirty: Vec<(usize, PageTableEntry)> = pages
+            .iter()
+            .map(|&page_number| (page_number, self.state.page_table.get(page_number).unwrap()))
+            .filter(|(_, entry)| entry.dirty)
+            .collect();
+        self.write_back_pages(&dirty);
+        self.stats
+            .writebacks
+            .fetch_add(dirty.len(), Ordering::Relaxed);
+
+        let error = self.state.loader_error.take().map(|(error, _)| error);
+        let mut swapped_out = 0;
+        for &page_number in &pages {
+            if error
+                .as_ref()
+                .is_some_and(|error| error.page_number == page_number)
+            {
+                continue;
+            }
+
+            self.release_page(page_number);
+            self.state.swapped_pages.insert(page_number);
+            swapped_out += 1;
+        }
+
+        self.state.working_sets.insert(pid, working_set);
+        self.stats.process_swap_outs.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(time_travel) = self.time_travel.as_mut() {
+            time_travel.clear();
+        }
+        self.checkpoint();
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(swapped_out),
+        }
+    }
+
+    /// Traz de volta um processo tirado da memória por `swap_out_process`.
+    /// Com `prefault`, as páginas do working set guardado são carregadas de
+    /// uma vez, enquanto houver frames livres (a volta de um processo não
+    /// substitui páginas dos outros); sem, o processo volta só por demanda,
+    /// com uma page fault em cada página que tocar.
+    ///
+    /// Se o carregador falha, a carga para: a página que falhou e as
+    /// seguintes do working set voltam por demanda, e o erro é devolvido.
+    ///
+    /// Devolve quantas páginas foram carregadas.
+    pub fn swap_in_process(&mut self, pid: Pid, prefault: bool) -> Result<usize, LoaderError> {
+        let working_set = self.state.working_sets.remove(&pid).unwrap_or_default();
+        let mut loaded = 0;
+        let mut result = Ok(());
+
+        for page_number in working_set.into_iter().filter(|_| prefault) {
+            if self.state.page_table.get(page_number).is_some() {
+                continue;
+            }
+
+            let Some(frame_idx) = self.state.free_frames.pop_front() else {
+                break;
+            };
+
+            if let Err(error) = self.loader.try_load_page_into(
+                page_number,
+                &mut self.memory[Self::frame_idx_to_range(frame_idx)],
+            ) {
+                warn!(
+                    "mmu: processo {}: a página {:#04X} não pôde ser carregada: {}",
+                    pid, page_number, error
+                );
+                self.state.free_frames.push_front(frame_idx);
+                result = Err(error);
+                break;
+            }
+
+            self.state.page_table.set(page_number, frame_idx);
+            self.state.refcounts.increment(frame_idx);
+            self.store_pte(page_number);
+            self.record_load(page_number);
+            self.replacer.page_event(PageEvent::Loaded(page_number));
+            self.state.swapped_pages.remove(&page_number);
+
+            loaded += 1;
+        }
+
+        debug!(
+            "mmu: processo {} volta para a memória ({} páginas carregadas)",
+            pid, loaded
+        );
+
+        self.stats
+            .swap_prefaults
+            .fetch_add(loaded, Ordering::Relaxed);
+        self.check_refcounts();
+        self.checkpoint();
+
+        result.map(|()| loaded)
+    }
+
+    /// Escreve a página no disco, se ela está em memória e suja, como o
+    /// `msync` de uma página. Ela continua em memória, agora limpa. As
+    /// páginas que mapeiam a page table ficam de fora.
+    ///
+    /// Como em `flush_all`, o histórico da viagem no tempo é descartado.
+    /// Se o carregador falha, a página continua suja.
+    ///
+    /// Devolve se a página foi escrita.
+    pub fn flush_page(&mut self, page_number: usize) -> Result<bool, LoaderError> {
+        let dirty: Vec<(usize, PageTableEntry)> = self
+            .state
+            .page_table
+            .get(page_number)
+            .filter(|entry| entry.dirty && !self.is_page_table_frame(entry.frame_index))
+            .map(|entry| (page_number, entry))
+            .into_iter()
+            .collect();
+
+        self.flush_pages(&dirty).map(|flushed| flushed > 0)
+    }
+
+    /// Escreve no disco todas as páginas residentes sujas, que continuam em
+    /// memória, agora limpas, como o `sync` do Unix. As páginas que mapeiam a
+    /// page table ficam de fora. Sem isso, uma página modificada só chega ao
+    /// disco quando é substituída; o `Drop` da Mmu chama esta função, para
+    /// que as páginas sujas não se percam no fim do programa.
+    ///
+    /// Como em `swap_out_process`, o histórico da viagem no tempo é
+    /// descartado. Se o carregador falha, a página que ele não escreveu
+    /// continua suja.
+    ///
+    /// Devolve quantas páginas foram escritas.
+    pub fn flush_all(&mut self) -> Result<usize, LoaderError> {
+        let dirty: Vec<(usize, PageTableEntry)> = self
+            .state
+            .page_table
+            .iter()
+            .filter(|(_, entry)| entry.dirty && !self.is_page_table_frame(entry.frame_index))
+            .collect();
+
+        self.flush_pages(&dirty)
+    }
+
+    /// Escreve as páginas sujas no disco e as marca como limpas.
+    fn flush_pages(&mut self, dirty: &[(usize, PageTableEntry)]) -> Result<usize, LoaderError> {
+        debug!("mmu: flush de {} páginas sujas", dirty.len());
+        self.write_back_pages(dirty);
+        self.stats.flushes.fetch_add(dirty.len(), Ordering::Relaxed);
+
+        let error = self.state.loader_error.take().map(|(error, _)| error);
+        for &(page_number, _) in dirty {
+            if error
+                .as_ref()
+                .is_none_or(|error| error.page_number != page_number)
+            {
+                self.state.page_table.mark_clean(page_number);
+                self.store_pte(page_number);
+            }
+        }
+
+        if let Some(time_travel) = self.time_travel.as_mut() {
+            time_travel.clear();
+        }
+        self.checkpoint();
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(dirty.len()),
+        }
+    }
+
+    /// Desliga a Mmu: escreve no disco as páginas sujas (veja `flush_all`)
+    /// e devolve o carregador, com o qual uma nova Mmu enxerga a memória
+    /// como esta a deixou.
+    pub fn shutdown(mut self) -> Result<LOADER, LoaderError> {
+        self.flush_all()?;
+        info!("mmu: desligada");
+
+        Ok(self
+            .loader
+            .0
+            .take()
+            .expect("mmu: o carregador já foi devolvido"))
+    }
+
+    /// As regiões mapeadas até agora, vivas ou não.
+    pub fn regions(&self) -> &RegionMap {
+        &self.state.regions
+    }
+
+    /// O tamanho virtual e o resident set size de um processo, contando as
+    /// páginas das suas regiões vivas.
+    pub fn process_stats(&self, pid: Pid) -> ProcessStats {
+        let mut stats = ProcessStats {
+            page_size: MEM_SIZE / FRAME_COUNT,
+            ..Default::default()
+        };
+
+        for region in self
+            .state
+            .regions
+            .iter()
+            .filter(|region| region.is_live() && region.pid == pid)
+        {
+            stats.virtual_pages += region.pages.len();
+            stats.resident_pages += region
+                .pages
+                .clone()
+                .filter(|&page_number| self.state.page_table.get(page_number).is_some())
+                .count();
+            stats.shared_pages += region
+                .pages
+                .clone()
+                .filter(|&page_number| {
+                    self.state
+                        .page_table
+                        .get(page_number)
+                        .is_some_and(|entry| self.state.shared.is_shared(entry.frame_index))
+                })
+                .count();
+        }
+
+        stats
+    }
+
+    /// Habilita ou desabilita o modo estrito. Nele, todo acesso a uma página
+    /// que não pertence a nenhuma região viva (nunca mapeada, ou usada depois
+    /// do unmap) é registrado como uma violação. O acesso em si continua
+    /// acontecendo normalmente.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.state.strict = strict;
+        self.checkpoint();
+    }
+
+    /// As violações detectadas no modo estrito.
+    pub fn violations(&self) -> &[AccessViolation] {
+        &self.state.violations
+    }
+
+    /// Limita quantas páginas das regiões vivas do processo podem estar em
+    /// memória ao mesmo tempo, ou tira o limite com `None`, como o
+    /// `RLIMIT_RSS`. Um acesso que causaria uma page fault numa página do
+    /// processo com o limite atingido é recusado, como uma falta de
+    /// privilégio: não acontece e não conta como acesso. Páginas já em
+    /// memória continuam lá se o limite diminui.
+    ///
+    /// O Linux ignora o `RLIMIT_RSS` (os cgroups recuperam páginas do
+    /// próprio processo em vez de falhar); aqui a falha é proposital, para
+    /// que um programa em laço descontrolado pare logo.
+    pub fn set_rss_limit(&mut self, pid: Pid, pages: Option<usize>) {
+        match pages {
+            Some(pages) => self.state.rss_limits.insert(pid, pages),
+            None => self.state.rss_limits.remove(&pid),
+        };
+
+        self.checkpoint();
+    }
+
+    /// O limite de RSS do processo, em páginas, se ele tem um.
+    pub fn rss_limit(&self, pid: Pid) -> Option<usize> {
+        self.state.rss_limits.get(&pid).copied()
+    }
+
+    /// Reserva frames só para as páginas do processo, ou tira a reserva com
+    /// `None` (veja o módulo `partition`). As reservas precisam deixar pelo
+    /// menos um frame para o pool compartilhado. As páginas já em memória não
+    /// mudam de frame: um processo acima da sua reserva, ou sem ela, só
+    /// perde páginas para as próximas page faults.
+    pub fn set_frame_reservation(
+        &mut self,
+        pid: Pid,
+        reservation: Option<Reservation>,
+    ) -> Result<(), PartitionError> {
+        let available = FRAME_COUNT - self.state.page_table_frames.as_ref().map_or(0, Range::len);
+        self.state.partitions.set(pid, reservation, available)?;
+
+        debug!("mmu: reserva do processo {}: {:?}", pid, reservation);
+
+        self.checkpoint();
+        Ok(())
+    }
+
+    /// A reserva de frames do processo, se ele tem uma.
+    pub fn frame_reservation(&self, pid: Pid) -> Option<Reservation> {
+        self.state.partitions.reservation(pid)
+    }
+
+    /// As reservas e os contadores das partições.
+    pub fn partitions(&self) -> &FramePartitions {
+        &self.state.partitions
+    }
+
+    /// O tamanho, a ocupação e a taxa de page faults de cada partição, ou
+    /// `None` se nenhum processo tem reserva.
+    pub fn partition_report(&self) -> Option<PartitionReport> {
+        if self.state.partitions.is_empty() {
+            return None;
+        }
+
+        let mut rows: Vec<_> = self
+            .state
+            .partitions
+            .reservations()
+            .map(|(pid, reservation)| PartitionRow {
+                partition: Partition::Reserved(pid),
+                frames: reservation.frames,
+                borrow: reservation.borrow,
+                resident_pages: self.process_stats(pid).resident_pages,
+                stats: self.state.partitions.stats(Partition::Reserved(pid)),
+            })
+            .collect();
+
+        let (shared_used, shared_frames) = self.shared_pool_usage();
+        rows.push(PartitionRow {
+            partition: Partition::Shared,
+            frames: shared_frames,
+            borrow: false,
+            resident_pages: shared_used,
+            stats: self.state.partitions.stats(Partition::Shared),
+        });
+
+        Some(PartitionReport { rows })
+    }
+
+    /// Quantas páginas residentes estão cobradas do pool compartilhado, e
+    /// quantos frames ele tem. As páginas de um processo com reserva só são
+    /// cobradas do pool além da reserva.
+    fn shared_pool_usage(&self) -> (usize, usize) {
+        let page_table_frames = self.state.page_table_frames.as_ref().map_or(0, Range::len);
+        let size = FRAME_COUNT - page_table_frames - self.state.partitions.reserved_frames();
+
+        let in_reservations: usize = self
+            .state
+            .partitions
+            .reservations()
+            .map(|(pid, reservation)| {
+                self.process_stats(pid)
+                    .resident_pages
+                    .min(reservation.frames)
+            })
+            .sum();
+
+        (
+            self.state
+                .page_table
+                .iter()
+                .count()
+                .saturating_sub(in_reservations),
+            size,
+        )
+    }
+
+    /// Onde a page fault na página pode buscar o seu frame, com as reservas
+    /// (veja o módulo `partition`). `None` se nenhum processo tem reserva.
+    fn fault_scope(&self, page_number: usize) -> Option<FaultScope> {
+        if self.state.partitions.is_empty() {
+            return None;
+        }
+
+        let owner = self
+            .state
+            .regions
+            .find_live(page_number)
+            .map(|region| region.pid);
+        let reserved =
+            owner.and_then(|pid| self.state.partitions.reservation(pid).map(|r| (pid, r)));
+
+        Some(match reserved {
+            Some((pid, reservation))
+                if self.process_stats(pid).resident_pages < reservation.frames =>
+            {
+                FaultScope {
+                    use_free: true,
+                    victims: VictimScope::Shared,
+                }
+            }
+            Some((pid, reservation)) if !reservation.borrow => FaultScope {
+                use_free: false,
+                victims: VictimScope::Process(pid),
+            },
+            _ => {
+                let (used, size) = self.shared_pool_usage();
+
+                FaultScope {
+                    use_free: used < size,
+                    victims: VictimScope::Shared,
+                }
+            }
+        })
+    }
+
+    /// Se a página pode ser a vítima de uma page fault com esse escopo.
+    fn in_victim_scope(&self, scope: Option<VictimScope>, page_number: usize) -> bool {
+        let owner = self
+            .state
+            .regions
+            .find_live(page_number)
+            .map(|region| region.pid);
+
+        match scope {
+            None => true,
+            Some(VictimScope::Process(pid)) => owner == Some(pid),
+            // Uma página é cobrada do pool compartilhado se o processo não
+            // tem reserva, ou se ele já passou dela.
+            Some(VictimScope::Shared) => {
+                match owner.and_then(|pid| self.state.partitions.reservation(pid).map(|r| (pid, r)))
+                {
+                    Some((pid, reservation)) => {
+                        self.process_stats(pid).resident_pages > reservation.frames
+                    }
+                    None => true,
+                }
+            }
+        }
+    }
+
+    /// Troca o modo de privilégio da CPU, no qual os próximos acessos são
+    /// feitos (veja o módulo `privilege`).
+    pub fn set_mode(&mut self, mode: PrivilegeMode) {
+        debug!("mmu: CPU em modo {}", mode);
+
+        self.state.mode = mode;
+        self.checkpoint();
+    }
+
+    /// O modo de privilégio atual da CPU.
+    pub fn mode(&self) -> PrivilegeMode {
+        self.state.mode
+    }
+
+    /// Marca as páginas como só do supervisor, ou as devolve aos dois modos.
+    pub fn set_supervisor_only(&mut self, pages: Range<usize>, supervisor_only: bool) {
+        for page_number in pages {
+            if supervisor_only {
+                self.state.supervisor_pages.insert(page_number);
+            } else {
+                self.state.supervisor_pages.remove(&page_number);
+            }
+        }
+
+        self.checkpoint();
+    }
+
+    /// Se a página é só do supervisor.
+    pub fn is_supervisor_only(&self, page_number: usize) -> bool {
+        self.state.supervisor_pages.contains(&page_number)
+    }
+
+    /// Os acessos em modo usuário a páginas do supervisor, que não
+    /// aconteceram.
+    pub fn privilege_faults(&self) -> &[PrivilegeFault] {
+        &self.state.privilege_faults
+    }
+
+    /// Muda a proteção das páginas, os acessos que elas permitem, como o
+    /// `mprotect`. Um acesso que a proteção não permite é uma falta de
+    /// proteção (o segfault de um processo), e não acontece: como numa
+    /// falta de privilégio, a página não é traduzida, e a falta não conta
+    /// como acesso. Uma busca de instrução (veja `fetch`) precisa da
+    /// permissão de execução, e páginas de código `r-x` com páginas de
+    /// dados `rw-` impõem W^X.
+    ///
+    /// Até aqui, as páginas permitem tudo (`Permissions::ALL`). A proteção
+    /// é da página, e continua a mesma quando ela sai da memória e volta.
+    /// As traduções das páginas no TLB são invalidadas.
+    pub fn set_protection(&mut self, pages: Range<usize>, protection: Permissions) {
+        debug!("mmu: páginas {:?} agora são {}", pages, protection);
+
+        for page_number in pages {
+            self.state
+                .page_table
+                .set_protection(page_number, protection);
+            self.store_pte(page_number);
+
+            if let Some(tlb) = self.state.tlb.as_mut() {
+                tlb.invalidate(page_number);
+            }
+        }
+
+        self.checkpoint();
+    }
+
+    /// Os acessos que a página permite.
+    pub fn protection(&self, page_number: usize) -> Permissions {
+        self.state.page_table.protection(page_number)
+    }
+
+    /// Muda a proteção dos endereços virtuais `addresses`, como o
+    /// `mprotect` sobre um intervalo de bytes, e devolve as páginas
+    /// afetadas. Serve para as páginas de guarda (`Permissions::NONE`) e os
+    /// segmentos de dados só de leitura.
+    ///
+    /// A proteção é da página inteira, então ela não se divide: uma página
+    /// coberta só em parte pelo intervalo é protegida inteira, e os bytes
+    /// dela fora do intervalo mudam junto. O intervalo é arredondado para
+    /// fora, até as bordas das páginas, como o Linux arredonda o tamanho. Um
+    /// intervalo vazio não muda nada.
+    ///
+    /// Entra em pânico se o intervalo passa do espaço de endereçamento.
+    pub fn mprotect(&mut self, addresses: Range<usize>, protection: Permissions) -> Range<usize> {
+        assert!(
+            addresses.end <= PAGE_COUNT << 8,
+            "mmu: o intervalo {:#06X}..{:#06X} passa das {} páginas do espaço de endereçamento",
+            addresses.start,
+            addresses.end,
+            PAGE_COUNT
+        );
+
+        if addresses.is_empty() {
+            return addresses.start >> 8..addresses.start >> 8;
+        }
+
+        let pages = addresses.start >> 8..addresses.end.div_ceil(256);
+        self.set_protection(pages.clone(), protection);
+
+        pages
+    }
+
+    /// Liga ou desliga o bit de software `bit` (de 0 a `SOFTWARE_BITS - 1`)
+    /// da página, uma marca que a Mmu guarda mas não interpreta (veja
+    /// `PageTableEntry::software`). Não é um acesso: a página não é
+    /// carregada, nem referenciada.
+    ///
+    /// Os bits sobrevivem a tudo o que tira a página da memória sem
+    /// desmapeá-la: substituição, swap do processo, quebra de copy-on-write
+    /// e compactação. Eles só são apagados quando a página é desmapeada, com
+    /// `unmap_page` ou com o fim da sua região, e voltam com a viagem no
+    /// tempo, como o resto da page table.
+    pub fn set_software_bit(&mut self, page_number: usize, bit: u32, value: bool) {
+        self.state
+            .page_table
+            .set_software_bit(page_number, bit, value);
+        self.store_pte(page_number);
+        self.checkpoint();
+    }
+
+    /// O bit de software `bit` da página (veja `set_software_bit`).
+    pub fn software_bit(&self, page_number: usize, bit: u32) -> bool {
+        self.software_bits(page_number) & (1 << bit) != 0
+    }
+
+    /// Todos os bits de software da página, no bit 0 em diante.
+    pub fn software_bits(&self, page_number: usize) -> u8 {
+        self.state.page_table.software_bits(page_number)
+    }
+
+    /// Um tick do relógio: envelhece todas as páginas residentes, passando o
+    /// bit de referência para o contador de idade (veja
+    /// `PageTableEntry::age`) e desligando-o.
+    pub fn tick(&mut self) {
+        self.age_pages();
+        self.checkpoint();
+    }
+
+    fn age_pages(&mut self) {
+        self.state.page_table.age_all();
+        self.store_all_ptes();
+        let ticks = self.stats.ticks.fetch_add(1, Ordering::Relaxed) + 1;
+
+        trace!("mmu: tick #{}", ticks);
+        self.replacer.tick();
+
+        if self.state.tiers.is_some() {
+            self.migrate_tiers();
+        }
+        if self.state.thp {
+            self.promote_huge_pages();
+        }
+    }
+
+    /// Habilita a memória em camadas (veja o módulo `tier`): os primeiros
+    /// `config.fast_frames` frames são a camada rápida, e a cada tick a
+    /// política de `config` migra páginas entre as camadas.
+    pub fn enable_tiers(&mut self, config: TierConfig) {
+        self.state.tiers = Some(TieredMemory::new(config));
+        self.checkpoint();
+    }
+
+    /// A memória em camadas, se habilitada.
+    pub fn tiers(&self) -> Option<&TieredMemory> {
+        self.state.tiers.as_ref()
+    }
+
+    /// O relatório da memória em camadas, se habilitada.
+    pub fn tier_report(&self) -> Option<TierReport> {
+        self.state.tiers.as_ref().map(|tiers| TierReport {
+            config: *tiers.config(),
+            slow_frames: FRAME_COUNT.saturating_sub(tiers.config().fast_frames),
+            page_size: MEM_SIZE / FRAME_COUNT,
+            stats: *tiers.stats(),
+        })
+    }
+
+    /// Faz as migrações entre as camadas que a política decidir.
+    fn migrate_tiers(&mut self) {
+        let movable: Vec<(usize, usize)> = self
+            .state
+            .page_table
+            .iter()
+            .filter(|&(page_number, entry)| {
+                !entry.pinned
+                    && !self.state.shared.is_shared(entry.frame_index)
+                    && !self.is_page_table_frame(entry.frame_index)
+                    && !self.state.huge_pages.contains(&huge_page_of(page_number))
+            })
+            .map(|(page_number, entry)| (page_number, entry.frame_index))
+            .collect();
+
+        let tiers = self.state.tiers.as_mut().unwrap();
+        let mut free_fast: Vec<usize> = self
+            .state
+            .free_frames
+            .iter()
+            .copied()
+            .filter(|&frame_idx| tiers.config().tier_of(frame_idx) == Tier::Fast)
+            .collect();
+        free_fast.sort_unstable();
+        let migrations = tiers.plan(&movable, &free_fast);
+
+        for migration in migrations {
+            match migration {
+                Migration::Promote { page, to } => {
+                    let from = self.state.page_table.get(page).unwrap().frame_index;
+
+                    self.memory.copy_within(
+                        Self::frame_idx_to_range(from),
+                        Self::frame_idx_to_range(to).start,
+                    );
+                    self.state.page_table.move_to_frame(page, to);
+                    self.state.refcounts.decrement(from);
+                    self.state.refcounts.increment(to);
+                    self.store_pte(page);
+                    self.state.free_frames.retain(|&frame_idx| frame_idx != to);
+                    self.state.free_frames.push_back(from);
+
+                    debug!(
+                        "mmu: página {:#04X} promovida do frame {:#04X} para o {:#04X}",
+                        page, from, to
+                    );
+                }
+                Migration::Swap { promoted, demoted } => {
+                    let slow = self.state.page_table.get(promoted).unwrap().frame_index;
+                    let fast = self.state.page_table.get(demoted).unwrap().frame_index;
+
+                    let content = self.memory[Self::frame_idx_to_range(slow)].to_vec();
+                    self.memory.copy_within(
+                        Self::frame_idx_to_range(fast),
+                        Self::frame_idx_to_range(slow).start,
+                    );
+                    self.memory[Self::frame_idx_to_range(fast)].copy_from_slice(&content);
+                    self.state.page_table.move_to_frame(promoted, fast);
+                    self.state.page_table.move_to_frame(demoted, slow);
+                    self.store_pte(promoted);
+                    self.store_pte(demoted);
+
+                    debug!(
+                        "mmu: página {:#04X} promovida para o frame {:#04X}, e a {:#04X} rebaixada para o {:#04X}",
+                        promoted, fast, demoted, slow
+                    );
+                }
+            }
+        }
+
+        self.check_refcounts();
+    }
+
+    /// Habilita as páginas grandes transparentes: a cada tick, os trechos
+    /// alinhados de páginas todas residentes viram páginas grandes (veja o
+    /// módulo `thp`).
+    pub fn enable_thp(&mut self) {
+        self.state.thp = true;
+        self.checkpoint();
+    }
+
+    /// As páginas grandes atuais, pela primeira página de cada uma.
+    pub fn huge_pages(&self) -> &BTreeSet<usize> {
+        &self.state.huge_pages
+    }
+
+    /// Promove todos os trechos que podem virar páginas grandes.
+    fn promote_huge_pages(&mut self) {
+        for first_page in (0..PAGE_COUNT - PAGE_COUNT % HUGE_PAGE_PAGES).step_by(HUGE_PAGE_PAGES) {
+            if !self.state.huge_pages.contains(&first_page) {
+                self.promote(first_page);
+            }
+        }
+    }
+
+    /// Tenta formar a página grande que começa em `first_page`: todas as
+    /// páginas precisam estar residentes, fora de frames compartilhados ou da
+    /// page table, e precisa haver um grupo alinhado de frames onde cada
+    /// frame está livre ou já é de uma delas. As páginas são movidas para
+    /// esse grupo, em ordem.
+    fn promote(&mut self, first_page: usize) {
+        let pages = first_page..first_page + HUGE_PAGE_PAGES;
+
+        let mut frames = Vec::with_capacity(HUGE_PAGE_PAGES);
+        for page_number in pages.clone() {
+            match self.state.page_table.get(page_number) {
+                Some(entry)
+                    if !self.state.shared.is_shared(entry.frame_index)
+                        && !self.is_page_table_frame(entry.frame_index) =>
+                {
+                    frames.push(entry.frame_index)
+                }
+                _ => return,
+            }
+        }
+
+        // O grupo de frames escolhido é o que exige menos movimentos. Além
+        // das páginas do trecho, as páginas de outros trechos que estão no
+        // grupo são movidas para frames livres fora dele, desde que possam
+        // mudar de frame e haja frames livres suficientes. Uma página fixada
+        // não pode mudar de frame.
+        let frame_map = self.frame_map();
+        let movable = |page_number: usize, frame_idx: usize| {
+            !self.state.page_table.is_pinned(page_number)
+                && !self.state.shared.is_shared(frame_idx)
+                && !self.is_page_table_frame(frame_idx)
+                && !self.state.huge_pages.contains(&huge_page_of(page_number))
+        };
+
+        // (primeiro frame, páginas de fora no grupo, movimentos)
+        type Candidate = (usize, Vec<(usize, usize)>, usize);
+        let mut best: Option<Candidate> = None;
+        for first_frame in (0..FRAME_COUNT - FRAME_COUNT % HUGE_PAGE_PAGES).step_by(HUGE_PAGE_PAGES)
+        {
+            let group = first_frame..first_frame + HUGE_PAGE_PAGES;
+            let mut foreign = Vec::new();
+            let mut moves = 0;
+            let mut feasible = true;
+
+            for (frame_idx, page_number) in group.clone().zip(pages.clone()) {
+                if frames[page_number - first_page] != frame_idx {
+                    moves += 1;
+                    feasible &= !self.state.page_table.is_pinned(page_number);
+                }
+
+                match frame_map[frame_idx] {
+                    Some(occupant) if !pages.contains(&occupant) => {
+                        feasible &= movable(occupant, frame_idx);
+                        foreign.push((occupant, frame_idx));
+                    }
+                    Some(_) => {}
+                    None => feasible &= !self.is_page_table_frame(frame_idx),
+                }
+            }
+
+            let free_outside = self
+                .state
+                .free_frames
+                .iter()
+                .filter(|frame_idx| !group.contains(frame_idx))
+                .count();
+            let cost = moves + foreign.len();
+
+            if feasible
+                && foreign.len() <= free_outside
+                && best
+                    .as_ref()
+                    .is_none_or(|(_, _, best_cost)| cost < *best_cost)
+            {
+                best = Some((first_frame, foreign, cost));
+            }
+        }
+
+        let Some((target, foreign, _)) = best else {
+            return;
+        };
+        let group = target..target + HUGE_PAGE_PAGES;
+
+        // Primeiro, as páginas de fora do trecho saem do grupo.
+        for (page_number, frame_idx) in foreign {
+            let position = self
+                .state
+                .free_frames
+                .iter()
+                .position(|free| !group.contains(free))
+                .unwrap();
+            let new_frame_idx = self.state.free_frames.remove(position).unwrap();
+
+            self.memory.copy_within(
+                Self::frame_idx_to_range(frame_idx),
+                Self::frame_idx_to_range(new_frame_idx).start,
+            );
+            self.state
+                .page_table
+                .move_to_frame(page_number, new_frame_idx);
+            self.state.refcounts.decrement(frame_idx);
+            self.state.refcounts.increment(new_frame_idx);
+            self.store_pte(page_number);
+            self.state.free_frames.push_back(frame_idx);
+            self.stats
+                .thp_migrated_pages
+                .fetch_add(1, Ordering::Relaxed);
+        }
+
+        let contents: Vec<Vec<u8>> = frames
+            .iter()
+            .map(|&frame_idx| self.memory[Self::frame_idx_to_range(frame_idx)].to_vec())
+            .collect();
+
+        self.state
+            .free_frames
+            .retain(|frame_idx| !group.contains(frame_idx));
+        for &frame_idx in &frames {
+            self.state.refcounts.decrement(frame_idx);
+
+            if !group.contains(&frame_idx) {
+                self.state.free_frames.push_back(frame_idx);
+            }
+        }
+
+        let mut moved = 0;
+        for ((page_number, new_frame_idx), content) in pages.zip(group).zip(contents) {
+            if self.state.page_table.get(page_number).unwrap().frame_index != new_frame_idx {
+                moved += 1;
+            }
+
+            self.memory[Self::frame_idx_to_range(new_frame_idx)].copy_from_slice(&content);
+            self.state
+                .page_table
+                .move_to_frame(page_number, new_frame_idx);
+            self.state.refcounts.increment(new_frame_idx);
+            self.store_pte(page_number);
+        }
+
+        debug!(
+            "mmu: páginas {:#04X}..{:#04X} viraram uma página grande nos frames {:#04X}.. ({} movidas)",
+            first_page,
+            first_page + HUGE_PAGE_PAGES,
+            target,
+            moved
+        );
+
+        self.state.huge_pages.insert(first_page);
+        self.stats.thp_promotions.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .thp_migrated_pages
+            .fetch_add(moved, Ordering::Relaxed);
+        self.check_refcounts();
+    }
+
+    /// Desfaz as páginas grandes que não estão mais inteiras: alguma página
+    /// saiu da memória ou do seu frame.
+    fn demote_broken_huge_pages(&mut self) {
+        let broken: Vec<usize> = self
+            .state
+            .huge_pages
+            .iter()
+            .copied()
+            .filter(|&first_page| {
+                let Some(first) = self.state.page_table.get(first_page) else {
+                    return true;
+                };
+
+                (1..HUGE_PAGE_PAGES).any(|i| {
+                    self.state
+                        .page_table
+                        .get(first_page + i)
+                        .map(|entry| entry.frame_index)
+                        != Some(first.frame_index + i)
+                })
+            })
+            .collect();
+
+        for first_page in broken {
+            debug!("mmu: página grande {:#04X} desfeita", first_page);
+
+            self.state.huge_pages.remove(&first_page);
+            self.stats.thp_demotions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Faz a Mmu chamar `tick` sozinha a cada `interval` acessos. `None`
+    /// desliga os ticks automáticos, e então só o usuário da Mmu chama `tick`.
+    pub fn set_tick_interval(&mut self, interval: Option<usize>) {
+        self.state.tick_interval = interval.filter(|&interval| interval > 0);
+        self.checkpoint();
+    }
+
+    /// Troca a ordem dos bytes de `read_u16`, `write_u32` e dos outros
+    /// acessos de mais de um byte (veja o módulo `word`).
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.state.endianness = endianness;
+        self.checkpoint();
+    }
+
+    /// A ordem dos bytes dos acessos de mais de um byte.
+    pub fn endianness(&self) -> Endianness {
+        self.state.endianness
+    }
+
+    /// O número de páginas no working set: as páginas residentes
+    /// referenciadas desde o último tick ou nos 8 anteriores.
+    pub fn working_set_size(&self) -> usize {
+        self.state
+            .page_table
+            .iter()
+            .filter(|(_, entry)| Self::in_working_set(entry))
+            .count()
+    }
+
+    fn in_working_set(entry: &PageTableEntry) -> bool {
+        entry.referenced || entry.age != 0
+    }
+
+    /// O histórico do bit de referência de uma página residente nos últimos
+    /// 8 ticks (o bit mais significativo é o tick mais recente), ou `None` se
+    /// a página não está em memória.
+    pub fn reference_history(&self, page_number: usize) -> Option<u8> {
+        self.state
+            .page_table
+            .get(page_number)
+            .map(|entry| entry.age)
+    }
+
+    /// Descreve o histórico de referência de todas as páginas residentes, uma
+    /// por linha, ordenadas da mais nova para a mais velha -- a ordem na qual
+    /// o algoritmo Aging as escolheria, de trás para frente.
+    pub fn fmt_reference_history(&self) -> String {
+        let mut pages: Vec<_> = self.state.page_table.iter().collect();
+        pages.sort_by(|(a_page, a), (b_page, b)| b.age.cmp(&a.age).then(a_page.cmp(b_page)));
+
+        let mut out = String::new();
+
+        for (page_number, entry) in pages {
+            let _ = writeln!(
+                out,
+                "página {:#04X}: {:08b}{}",
+                page_number,
+                entry.age,
+                if entry.referenced { " [R]" } else { "" }
+            );
+        }
+
+        out
+    }
+
+    /// Compacta a memória física: move as páginas residentes para os
+    /// primeiros frames, em ordem de page number, de forma que páginas
+    /// consecutivas fiquem em frames consecutivos e os frames livres fiquem
+    /// juntos no final.
+    ///
+    /// Como no mundo real, a compactação não é vista pelos endereços
+    /// virtuais: só os frames mudam, e as entradas da page table são
+    /// atualizadas para apontar para os novos frames.
+    ///
+    /// As páginas fixadas (veja `pin_page`) não saem dos seus frames, que
+    /// um dispositivo pode estar usando (veja o módulo `iommu`): as outras
+    /// páginas são compactadas em volta delas, e um frame livre pode ficar
+    /// entre elas.
+    pub fn compact(&mut self) {
+        // Os frames da page table (e as páginas que a mapeiam) e os frames
+        // das páginas fixadas ficam onde estão. Um frame compartilhado com
+        // uma página fixada fica também.
+        let fixed: BTreeSet<usize> = (0..FRAME_COUNT)
+            .filter(|&frame_idx| self.is_page_table_frame(frame_idx))
+            .chain(
+                self.state
+                    .page_table
+                    .iter()
+                    .filter(|(page_number, _)| self.state.page_table.is_pinned(*page_number))
+                    .map(|(_, entry)| entry.frame_index),
+            )
+            .collect();
+        let resident: Vec<_> = self
+            .state
+            .page_table
+            .iter()
+            .filter(|(_, entry)| !fixed.contains(&entry.frame_index))
+            .collect();
+        let mut frames: Vec<_> = (0..FRAME_COUNT)
+            .filter(|frame_idx| !fixed.contains(frame_idx))
+            .collect();
+        let mut compacted = vec![0; MEM_SIZE];
+
+        for &frame_idx in &fixed {
+            let range = Self::frame_idx_to_range(frame_idx);
+            compacted[range.clone()].copy_from_slice(&self.memory[range]);
+        }
+
+        // Os frames em uso, na ordem da primeira página de cada um (`iter` já
+        // devolve as páginas em ordem de page number). Um frame compartilhado
+        // aparece uma vez só, e continua compartilhado no novo lugar.
+        let mut used: Vec<usize> = Vec::new();
+        for (_, entry) in &resident {
+            if !used.contains(&entry.frame_index) {
+                used.push(entry.frame_index);
+            }
+        }
+
+        let new_frame_idx = |frame_idx: usize| match used.iter().position(|&used| used == frame_idx)
+        {
+            Some(position) => frames[position],
+            None => frame_idx,
+        };
+
+        for &old_frame_idx in &used {
+            let old_range = Self::frame_idx_to_range(old_frame_idx);
+            let new_range = Self::frame_idx_to_range(new_frame_idx(old_frame_idx));
+
+            compacted[new_range].copy_from_slice(&self.memory[old_range]);
+        }
+
+        for (page_number, entry) in &resident {
+            self.state
+                .page_table
+                .move_to_frame(*page_number, new_frame_idx(entry.frame_index));
+        }
+
+        let moves: Vec<_> = used
+            .iter()
+            .map(|&frame_idx| (frame_idx, new_frame_idx(frame_idx)))
+            .collect();
+        self.state.refcounts.move_frames(&moves);
+        self.state.shared.remap(new_frame_idx);
+        self.memory.copy_from_slice(&compacted);
+        self.state.free_frames = frames.split_off(used.len()).into();
+        self.store_all_ptes();
+        self.demote_broken_huge_pages();
+        self.check_refcounts();
+
+        debug!(
+            "mmu: memória compactada, {} páginas movidas",
+            resident.len()
+        );
+
+        self.checkpoint();
+    }
+
+    /// Volta a simulação `n_accesses` acessos no tempo, restaurando
+    /// exatamente o estado da Mmu (e do disco) daquele momento. Veja o módulo
+    /// `time_travel` para os detalhes.
+    ///
+    /// O trace é truncado no novo "agora". Os observadores não são chamados
+    /// pelos acessos reexecutados.
+    ///
+    /// Se o carregador falha ao desfazer uma escrita no disco, a volta para
+    /// no meio: a Mmu fica onde estava, o disco fica com só parte das
+    /// escritas desfeitas e o histórico é descartado.
+    pub fn rewind(&mut self, n_accesses: usize) -> Result<(), RewindError> {
+        let now = self.stats.accesses();
+        let time_travel = self.time_travel.as_mut().ok_or(RewindError::Disabled)?;
+
+        let earliest = time_travel.snapshots[0].0;
+        if n_accesses > now - earliest {
+            return Err(RewindError::TooFar {
+                available: now - earliest,
+            });
+        }
+
+        let target = now - n_accesses;
+        let snapshot_idx = time_travel
+            .snapshots
+            .iter()
+            .rposition(|(time, _)| *time <= target)
+            .unwrap();
+        let snapshot_time = time_travel.snapshots[snapshot_idx].0;
+
+        debug!(
+            "mmu: voltando para o acesso #{} a partir do snapshot do acesso #{}",
+            target, snapshot_time
+        );
+
+        // Desfaz as escritas no disco feitas depois do snapshot, da mais nova
+        // para a mais velha.
+        while time_travel
+            .journal
+            .back()
+            .is_some_and(|entry| entry.time >= snapshot_time)
+        {
+            let entry = time_travel.journal.pop_back().unwrap();
+            if let Err(error) = self
+                .loader
+                .try_flush_page(entry.page_number, &entry.previous)
+            {
+                warn!("mmu: a volta no tempo parou: {}", error);
+                time_travel.clear();
+                self.checkpoint();
+                return Err(RewindError::Loader(error));
+            }
+        }
+
+        // Os acessos entre o snapshot e o alvo serão reexecutados (e
+        // registrados de novo); o que vem depois do alvo é descartado.
+        let replay: Vec<_> = time_travel
+            .accesses
+            .iter()
+            .filter(|(time, _)| (snapshot_time..target).contains(time))
+            .map(|(_, access)| access.clone())
+            .collect();
+        time_travel
+            .accesses
+            .retain(|(time, _)| *time < snapshot_time);
+        time_travel.snapshots.truncate(snapshot_idx + 1);
+
+        let clone_replacer = time_travel.clone_replacer;
+        let snapshot = &time_travel.snapshots[snapshot_idx].1;
+
+        self.memory.copy_from_slice(&snapshot.memory);
+        self.replacer = clone_replacer(&snapshot.replacer);
+        self.stats.copy_from(&snapshot.stats);
+        self.state = snapshot.state.clone();
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.truncate_at(snapshot_time);
+        }
+        if let Some(audit) = self.audit.as_mut() {
+            audit.truncate_at(snapshot_time);
+        }
+
+        let observers = std::mem::take(&mut self.observers);
+
+        // Os acessos refeitos já aconteceram uma vez, e os seus erros já
+        // foram devolvidos.
+        for access in replay {
+            match access {
+                LoggedAccess::Read(address) => {
+                    let _ = self.read(address);
+                }
+                LoggedAccess::Fetch(address) => {
+                    let _ = self.fetch(address);
+                }
+                LoggedAccess::Write(address, value) => {
+                    let _ = self.write(address, value);
+                }
+                LoggedAccess::WriteSlice(address, data) => {
+                    let _ = self.write_slice(address, &data);
+                }
+                LoggedAccess::Fill(address, value, len) => {
+                    let _ = self.fill_slice(address, value, len);
+                }
+            }
+        }
+
+        self.observers = observers;
+
+        Ok(())
+    }
+
+    /// Tira um snapshot do estado atual, se a viagem no tempo está habilitada.
+    fn checkpoint(&mut self) {
+        let Some(time_travel) = self.time_travel.as_ref() else {
+            return;
+        };
+
+        let snapshot = MmuSnapshot {
+            memory: self.memory.to_vec(),
+            replacer: (time_travel.clone_replacer)(&self.replacer),
+            stats: MmuStats::clone(&self.stats),
+            state: self.state.clone(),
+        };
+
+        let time = self.stats.accesses();
+        self.time_travel
+            .as_mut()
+            .unwrap()
+            .push_snapshot(time, snapshot);
+    }
+
+    /// Registra um acesso no log da viagem no tempo, tirando um snapshot a
+    /// cada `interval` acessos.
+    fn log_access(&mut self, access: LoggedAccess) {
+        let time = self.stats.accesses();
+
+        let Some(time_travel) = self.time_travel.as_mut() else {
+            return;
+        };
+
+        time_travel.accesses.push_back((time - 1, access));
+
+        if time.is_multiple_of(time_travel.interval) {
+            self.checkpoint();
+        }
+    }
+
+    /// Converte um índice de frame num range que pode ser utilizado
+    /// para indexar a array memory.
+    fn frame_idx_to_range(frame_idx: usize) -> Range<usize> {
+        let frame_size = MEM_SIZE / FRAME_COUNT;
+
+        Range {
+            start: frame_idx * frame_size,
+            end: (frame_idx + 1) * frame_size,
+        }
+    }
+
+    /// Faz o tratamento de uma page fault.
+    ///
+    /// Retorna o frame no qual a página foi carregada e, se houve substituição,
+    /// a página substituída e se ela precisou de writeback.
+    fn handle_page_fault(&mut self, page_number: usize) -> (usize, Option<(usize, bool)>) {
+        // Medimos cada fase do tratamento; o que sobrar é contabilidade.
+        let start = Instant::now();
+        let mut measured = Duration::ZERO;
+
+        // Com a lista fantasma, a page fault é um refault se a página saiu
+        // há pouco. Olhamos antes da substituição que esta page fault vai
+        // fazer, que não conta para a distância.
+        if let Some(ghost) = self.state.ghost.as_mut() {
+            let counter = match ghost.remove(page_number) {
+                true => &self.stats.capacity_misses,
+                false => &self.stats.cold_misses,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let (frame_idx, evicted) = self.take_frame(page_number, &mut measured);
+
+        // Já que temos o frame, atualizamos a entrada na page table.
+        self.state.page_table.set(page_number, frame_idx);
+        self.state.refcounts.increment(frame_idx);
+
+        // Olhamos para a janela na memória que é o frame.
+        let frame_range = Self::frame_idx_to_range(frame_idx);
+        let frame = &mut self.memory[frame_range];
+
+        // Chama o loader para carregar a página no frame.
+        let phase_start = Instant::now();
+        let result = self.loader.try_load_page_into(page_number, frame);
+        self.record_loader_error(result, Some(frame_idx));
+        measured += self.record_phase(FaultPhase::Load, phase_start);
+
+        // Avisa o replacer, que pode usar esse evento para seus cálculos.
+        self.record_load(page_number);
+        self.replacer.page_event(PageEvent::Loaded(page_number));
+        self.demote_broken_huge_pages();
+        self.check_refcounts();
+
+        self.stats.fault_timings.record(
+            FaultPhase::Bookkeeping,
+            start.elapsed().saturating_sub(measured),
+        );
+
+        if log_enabled!(Level::Trace) {
+            trace!("mmu: estado após page fault:\n{}", self.fmt_state());
+        }
+
+        // Retorna o índice do frame.
+        (frame_idx, evicted)
+    }
+
+    /// Escolhe o frame para a página: um livre, se há, ou o de uma página
+    /// escolhida pelo replacer, que é substituída. Soma a `measured` o tempo
+    /// das fases medidas.
+    ///
+    /// Retorna o frame e, se houve substituição, a página substituída e se
+    /// ela precisou de writeback.
+    fn take_frame(
+        &mut self,
+        page_number: usize,
+        measured: &mut Duration,
+    ) -> (usize, Option<(usize, bool)>) {
+        // Aqui, inicialmente vamos escolher em qual frame carregar a página.
+        // Tenta pegar um frame que ainda não foi utilizado, se a partição da
+        // página pode usá-lo.
+        let scope = self.fault_scope(page_number);
+        let free_frame = match scope {
+            Some(scope) if !scope.use_free => None,
+            _ => self.pop_free_frame(),
+        };
+
+        match free_frame {
+            // Se conseguiu, retorna seu índice imediatamente, e vamos utilizá-lo.
+            Some(empty_idx) => (empty_idx, None),
+            None => {
+                // Se não há frames vazios, vamos escolher uma página para ser substituída.
+                // A vítima já foi escolhida antes do acesso, numa page fault
+                // ou numa quebra de COW (veja `reserve_victim`); sem ela, o
+                // acesso nem teria começado.
+                let ReservedVictim {
+                    victim,
+                    replacer_state,
+                } = self
+                    .state
+                    .reserved_victim
+                    .take()
+                    .expect("mmu: frame pedido sem uma vítima reservada");
+                *measured += self.clean_scheduled_pages();
+
+                // Olhamos para dentro da entrada da page table desta página, e verificamos
+                // se a página está dirty. Se sim, então nós vamos chamar nosso loader
+                // para fazer o flush de volta para disco.
+                let evicted_page_idx = victim.page;
+                let evicted_page = self.state.page_table.get(evicted_page_idx).unwrap();
+                if evicted_page.dirty {
+                    *measured += self.write_back(evicted_page_idx, evicted_page);
+                }
+
+                let idx = victim.frame;
+
+                if let (Some(audit), Some(replacer_state)) = (self.audit.as_mut(), replacer_state) {
+                    audit.push(EvictionAudit {
+                        time: self.stats.accesses() - 1,
+                        faulting_page: page_number,
+                        victim: evicted_page_idx,
+                        frame_index: idx,
+                        writeback: evicted_page.dirty,
+                        reason: self.replacer.victim_reason(),
+                        replacer_state,
+                    });
+                }
+
+                self.record_eviction(evicted_page_idx);
+
+                // Invalida a página na page table. Se o frame era
+                // compartilhado, as outras páginas nele também saem.
+                self.state.page_table.invalidate(evicted_page_idx);
+                self.state.refcounts.decrement(idx);
+                self.replacer
+                    .page_event(PageEvent::Evicted(evicted_page_idx));
+                *measured += self.evict_sharers(idx, evicted_page_idx);
+
+                // E finalmente retornamos o frame no qual essa página estava guardada.
+                (idx, Some((evicted_page_idx, evicted_page.dirty)))
+            }
+        }
+    }
+
+    /// Anota quando a página foi carregada e, se ela tinha sido substituída,
+    /// a distância até o refault.
+    fn record_load(&mut self, page_number: usize) {
+        let now = self.stats.accesses().saturating_sub(1);
+
+        if let Some(evicted_at) = self.state.evicted_at.remove(&page_number) {
+            self.stats.refault_distances.record(now - evicted_at);
+        }
+
+        self.state.loaded_at.insert(page_number, now);
+    }
+
+    /// Anota a idade da vítima e quando ela saiu da memória, para medir a
+    /// distância até o refault.
+    fn record_eviction(&mut self, page_number: usize) {
+        let now = self.stats.accesses().saturating_sub(1);
+
+        if let Some(loaded_at) = self.state.loaded_at.remove(&page_number) {
+            self.stats.victim_ages.record(now - loaded_at);
+        }
+
+        self.state.evicted_at.insert(page_number, now);
+
+        if let Some(ghost) = self.state.ghost.as_mut() {
+            ghost.insert(page_number);
+        }
+    }
+
+    /// Tira a página da lista fantasma, se habilitada: o seu conteúdo foi
+    /// descartado, e a próxima page fault nela não é um refault.
+    fn forget_ghost(&mut self, page_number: usize) {
+        if let Some(ghost) = self.state.ghost.as_mut() {
+            ghost.remove(page_number);
+        }
+    }
+
+    /// Chama o replacer para escolher a vítima (veja `pick_victim`),
+    /// somando a `measured` o tempo da escolha. Se a auditoria está
+    /// habilitada, guarda antes o estado dele, que é o que explica a decisão.
+    fn select_victim(
+        &mut self,
+        measured: &mut Duration,
+        scope: Option<VictimScope>,
+        exclude: Option<usize>,
+    ) -> Result<Option<ReservedVictim>, String> {
+        let replacer_state = self
+            .audit
+            .as_ref()
+            .map(|_| ReplacerState(&self.replacer).to_string());
+
+        let phase_start = Instant::now();
+        let victim = self.pick_victim(scope, exclude);
+        *measured += self.record_phase(FaultPhase::VictimSelection, phase_start);
+
+        Ok(victim?.map(|victim| ReservedVictim {
+            victim,
+            replacer_state,
+        }))
+    }
+
+    /// Pede ao replacer uma vítima que a swappiness da sua região aceite (veja
+    /// `RegionMap::accept_victim`). Uma página poupada volta para o replacer
+    /// como se tivesse acabado de ser carregada. Depois de `FRAME_COUNT`
+    /// páginas poupadas seguidas, todas as residentes tiveram a sua chance, e
+    /// a próxima escolha é aceita. `None` se o replacer não tem vítima.
+    ///
+    /// Com as reservas de frames, as páginas fora de `scope` voltam para o
+    /// replacer do mesmo jeito, sem chegar à swappiness, e nunca são aceitas.
+    /// `exclude` também nunca é aceita: é a página cujo COW vai ser quebrado
+    /// (veja `reserve_victim`).
+    fn pick_victim(
+        &mut self,
+        scope: Option<VictimScope>,
+        exclude: Option<usize>,
+    ) -> Result<Option<Victim>, String> {
+        for _ in 0..FRAME_COUNT {
+            let Some(page_number) = self.replacer.pick_replacement_page() else {
+                return Ok(None);
+            };
+
+            if Some(page_number) == exclude {
+                self.replacer.page_event(PageEvent::Loaded(page_number));
+                continue;
+            }
+
+            if !self.in_victim_scope(scope, page_number) {
+                trace!(
+                    "mmu: página {:#04X} fora da partição da page fault",
+                    page_number
+                );
+                self.replacer.page_event(PageEvent::Loaded(page_number));
+                continue;
+            }
+
+            if self.state.regions.accept_victim(page_number) {
+                return self.victim(page_number).map(Some);
+            }
+
+            debug!(
+                "mmu: página {:#04X} poupada pela swappiness da sua região",
+                page_number
+            );
+
+            self.replacer.page_event(PageEvent::Loaded(page_number));
+            self.stats.swappiness_skips.fetch_add(1, Ordering::Relaxed);
+        }
+
+        match self.replacer.pick_replacement_page() {
+            Some(page_number)
+                if Some(page_number) != exclude && self.in_victim_scope(scope, page_number) =>
+            {
+                self.victim(page_number).map(Some)
+            }
+            Some(page_number) => {
+                self.replacer.page_event(PageEvent::Loaded(page_number));
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Completa a página escolhida pelo replacer com o seu frame. Uma página
+    /// fora da memória ou fixada é um bug do replacer (veja
+    /// `PageReplacer::pick_replacement_page`), descrito no erro.
+    fn victim(&self, page_number: usize) -> Result<Victim, String> {
+        match self.state.page_table.get(page_number) {
+            Some(entry) if entry.pinned => Err(format!(
+                "o replacer escolheu a página {:#04X}, que está fixada",
+                page_number
+            )),
+            Some(entry) => Ok(Victim {
+                page: page_number,
+                frame: entry.frame_index,
+            }),
+            None => Err(format!(
+                "o replacer escolheu a página {:#04X}, que não está em memória",
+                page_number
+            )),
+        }
+    }
+
+    /// Tira um frame da lista de frames livres: com a memória em camadas,
+    /// um da camada rápida, se há; senão, o primeiro da lista.
+    fn pop_free_frame(&mut self) -> Option<usize> {
+        let fast = self.state.tiers.as_ref().and_then(|tiers| {
+            self.state
+                .free_frames
+                .iter()
+                .position(|&frame_idx| tiers.config().tier_of(frame_idx) == Tier::Fast)
+        });
+
+        match fast {
+            Some(position) => self.state.free_frames.remove(position),
+            None => self.state.free_frames.pop_front(),
+        }
+    }
+
+    /// Escreve no disco as páginas que o replacer decidiu limpar (veja
+    /// `PageReplacer::scheduled_writebacks`), que continuam em memória,
+    /// agora limpas. Devolve o tempo gasto.
+    fn clean_scheduled_pages(&mut self) -> Duration {
+        let scheduled = self.replacer.scheduled_writebacks();
+
+        // Sem escritas assíncronas, a limpeza seguraria a page fault tanto
+        // quanto o writeback da vítima; as páginas ficam sujas, e são
+        // escritas quando saírem.
+        if !self.state.capabilities.asynchronous {
+            return Duration::ZERO;
+        }
+
+        let pages: Vec<(usize, PageTableEntry)> = scheduled
+            .into_iter()
+            .filter_map(|page_number| {
+                self.state
+                    .page_table
+                    .get(page_number)
+                    .filter(|entry| entry.dirty)
+                    .map(|entry| (page_number, entry))
+            })
+            .collect();
+
+        for &(page_number, _) in &pages {
+            debug!(
+                "mmu: limpando a página {:#04X}, a pedido do replacer",
+                page_number
+            );
+        }
+
+        let elapsed = self.write_back_pages(&pages);
+
+        for &(page_number, _) in &pages {
+            self.state.page_table.mark_clean(page_number);
+            self.store_pte(page_number);
+            self.stats.cleaned_pages.fetch_add(1, Ordering::Relaxed);
+            self.stats.flushes.fetch_add(1, Ordering::Relaxed);
+        }
+
+        elapsed
+    }
+
+    /// Guarda a falha do carregador, se houve, para o acesso em andamento
+    /// devolvê-la. Só a primeira falha de um acesso é guardada: as outras
+    /// costumam ser consequências dela.
+    fn record_loader_error(&mut self, result: Result<(), LoaderError>, frame_index: Option<usize>) {
+        if let Err(error) = result {
+            warn!("mmu: {}", error);
+            self.state.loader_error.get_or_insert((error, frame_index));
+        }
+    }
+
+    /// Salva a página suja no disco, antes que seu frame seja reaproveitado,
+    /// devolvendo o tempo gasto.
+    fn write_back(&mut self, page_number: usize, entry: PageTableEntry) -> Duration {
+        self.stats.writebacks.fetch_add(1, Ordering::Relaxed);
+        self.write_back_pages(&[(page_number, entry)])
+    }
+
+    /// Salva as páginas sujas no disco, devolvendo o tempo gasto. Se o
+    /// carregador escreve em lote (veja `LoaderCapabilities::batch_flush`),
+    /// as páginas vão todas numa chamada só. Quem chama conta as escritas,
+    /// em `writebacks` ou `flushes`.
+    fn write_back_pages(&mut self, pages: &[(usize, PageTableEntry)]) -> Duration {
+        for &(page_number, entry) in pages {
+            debug!(
+                "mmu: página {:#06X} suja, salvando antes de sobrescrever",
+                page_number
+            );
+
+            // Com a viagem no tempo, guardamos o conteúdo anterior da
+            // página no disco, para poder desfazer a escrita.
+            if self.time_travel.is_some() {
+                let previous = match self.loader.page_ref(page_number) {
+                    Some(page) => page.to_vec(),
+                    None => {
+                        let mut previous = vec![0; MEM_SIZE / FRAME_COUNT];
+                        let result = self.loader.try_load_page_into(page_number, &mut previous);
+                        self.record_loader_error(result, Some(entry.frame_index));
+                        previous
+                    }
+                };
+
+                let time = self.stats.accesses().saturating_sub(1);
+                if let Some(time_travel) = self.time_travel.as_mut() {
+                    time_travel.journal.push_back(JournalEntry {
+                        time,
+                        page_number,
+                        previous,
+                    });
+                }
+            }
+        }
+
+        let phase_start = Instant::now();
+
+        if self.state.capabilities.batch_flush && pages.len() > 1 {
+            let batch: Vec<(usize, &[u8])> = pages
+                .iter()
+                .map(|&(page_number, entry)| {
+                    let frame = &self.memory[Self::frame_idx_to_range(entry.frame_index)];
+                    (page_number, frame)
+                })
+                .collect();
+
+            let result = self.loader.try_flush_pages(&batch);
+            // A falha diz qual página não foi escrita; o frame é o dela.
+            let frame_index = result.as_ref().err().and_then(|error| {
+                pages
+                    .iter()
+                    .find(|&&(page_number, _)| page_number == error.page_number)
+                    .map(|(_, entry)| entry.frame_index)
+            });
+            self.record_loader_error(result, frame_index);
+        } else {
+            for &(page_number, entry) in pages {
+                let frame = &self.memory[Self::frame_idx_to_range(entry.frame_index)];
+                let result = self.loader.try_flush_page(page_number, frame);
+                self.record_loader_error(result, Some(entry.frame_index));
+            }
+        }
+
+        let elapsed = self.record_phase(FaultPhase::Writeback, phase_start);
+
+        for &(page_number, entry) in pages {
+            self.replacer
+                .page_event(PageEvent::FlushedDirty(page_number));
+
+            // O acesso atual já foi contado, então o seu índice é
+            // `accesses() - 1`. Num flush sem acessos, como o de uma Mmu
+            // que carregou um estado (veja `load_state`), não há acesso
+            // atual.
+            let (dirtied_at, dirtied_at_tick) = entry.dirtied_at;
+            self.stats.dirty_lifetime_accesses.record(
+                self.stats
+                    .accesses()
+                    .saturating_sub(1)
+                    .saturating_sub(dirtied_at),
+            );
+            self.stats
+                .dirty_lifetime_ticks
+                .record(self.stats.ticks().saturating_sub(dirtied_at_tick));
+        }
+
+        elapsed
+    }
+
+    /// Tira do frame compartilhado, depois que a vítima `victim` saiu dele,
+    /// todas as outras páginas, para que o frame possa ser reaproveitado.
+    /// Devolve o tempo gasto com writebacks.
+    fn evict_sharers(&mut self, frame_idx: usize, victim: usize) -> Duration {
+        let mut elapsed = Duration::ZERO;
+
+        for page_number in self.state.shared.remove_frame(frame_idx) {
+            if page_number == victim {
+                continue;
+            }
+
+            debug!(
+                "mmu: página {:#04X} sai junto, pois compartilhava o frame {:#04X}",
+                page_number, frame_idx
+            );
+
+            let entry = self.state.page_table.get(page_number).unwrap();
+            if entry.dirty {
+                elapsed += self.write_back(page_number, entry);
+            }
+
+            self.state.page_table.invalidate(page_number);
+            self.state.refcounts.decrement(frame_idx);
+            self.store_pte(page_number);
+            self.state.loaded_at.remove(&page_number);
+            if let Some(ghost) = self.state.ghost.as_mut() {
+                ghost.insert(page_number);
+            }
+            self.replacer.page_event(PageEvent::Unmapped(page_number));
+            self.replacer.page_event(PageEvent::Evicted(page_number));
+        }
+
+        self.update_sharing_stats();
+
+        elapsed
+    }
+
+    /// Quebra o copy-on-write da página, que está no frame compartilhado
+    /// `frame_idx`: ela ganha um frame só seu (livre, ou de uma página
+    /// substituída) com uma cópia do conteúdo.
+    ///
+    /// Retorna o novo frame e, se houve substituição, a página substituída e
+    /// se ela precisou de writeback.
+    fn break_cow(
+        &mut self,
+        page_number: usize,
+        frame_idx: usize,
+    ) -> (usize, Option<(usize, bool)>) {
+        let content = self.memory[Self::frame_idx_to_range(frame_idx)].to_vec();
+        let entry = self.state.page_table.get(page_number).unwrap();
+
+        // A página sai do frame antes da escolha do novo, para não ser
+        // escolhida como vítima.
+        self.state.shared.remove(frame_idx, page_number);
+        self.state.page_table.invalidate(page_number);
+        self.state.refcounts.decrement(frame_idx);
+        self.replacer.page_event(PageEvent::Unmapped(page_number));
+
+        let mut measured = Duration::ZERO;
+        let (new_frame_idx, evicted) = self.take_frame(page_number, &mut measured);
+
+        self.state.page_table.set(page_number, new_frame_idx);
+        self.state.refcounts.increment(new_frame_idx);
+        if entry.dirty {
+            self.state
+                .page_table
+                .mark_dirty(page_number, entry.dirtied_at);
+        }
+        self.memory[Self::frame_idx_to_range(new_frame_idx)].copy_from_slice(&content);
+        self.replacer.page_event(PageEvent::Loaded(page_number));
+
+        debug!(
+            "mmu: COW da página {:#04X} quebrado, frame {:#04X} -> {:#04X}",
+            page_number, frame_idx, new_frame_idx
+        );
+
+        self.stats.cow_breaks.fetch_add(1, Ordering::Relaxed);
+        self.update_sharing_stats();
+        self.demote_broken_huge_pages();
+        self.check_refcounts();
+
+        (new_frame_idx, evicted)
+    }
+
+    /// Em builds de debug, confere os contadores de referências: cada um
+    /// deve ser igual ao número de entradas da page table que apontam para o
+    /// frame (e ao número de páginas, num frame compartilhado), e um frame
+    /// deve estar na lista de livres se e somente se ninguém o usa. Um erro
+    /// aqui é um bug na Mmu, então causa um panic.
+    fn check_refcounts(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let frames = self
+            .state
+            .page_table
+            .iter()
+            .map(|(_, entry)| entry.frame_index);
+        if let Err(mismatch) = self.state.refcounts.verify(frames) {
+            panic!("mmu: contagem de referências errada: {}", mismatch);
+        }
+
+        let mut free = vec![false; FRAME_COUNT];
+        for &frame_idx in &self.state.free_frames {
+            assert!(
+                !free[frame_idx],
+                "mmu: frame {:#04X} duas vezes na lista de livres",
+                frame_idx
+            );
+            free[frame_idx] = true;
+        }
+
+        for (frame_idx, &free) in free.iter().enumerate() {
+            let count = self.state.refcounts.get(frame_idx);
+            let used = count > 0 || self.is_page_table_frame(frame_idx);
+
+            assert!(
+                free != used,
+                "mmu: frame {:#04X} com {} referências {}",
+                frame_idx,
+                count,
+                if free {
+                    "está na lista de livres"
+                } else {
+                    "vazou: não está na lista de livres"
+                }
+            );
+
+            if self.state.shared.is_shared(frame_idx) {
+                assert_eq!(
+                    count,
+                    self.state.shared.sharers(frame_idx).len(),
+                    "mmu: frame compartilhado {:#04X} com contador errado",
+                    frame_idx
+                );
+            }
+        }
+    }
+
+    /// Atualiza as estatísticas de compartilhamento e o histórico.
+    fn update_sharing_stats(&mut self) {
+        let saved = self.state.shared.saved_frames();
+
+        self.stats
+            .shared_frames
+            .store(self.state.shared.shared_frames(), Ordering::Relaxed);
+        self.stats.saved_frames.store(saved, Ordering::Relaxed);
+        self.stats
+            .peak_saved_frames
+            .fetch_max(saved, Ordering::Relaxed);
+
+        if self.state.sharing_history.last().map(|(_, last)| *last) != Some(saved) {
+            self.state
+                .sharing_history
+                .push((self.stats.accesses(), saved));
+        }
+    }
+
+    /// Registra o tempo desde `start` como a duração da fase, devolvendo-o.
+    fn record_phase(&self, phase: FaultPhase, start: Instant) -> Duration {
+        let elapsed = start.elapsed();
+        self.stats.fault_timings.record(phase, elapsed);
+        elapsed
+    }
+
+    /// Devolve as páginas escritas desde a chamada anterior (ou desde o
+    /// início) e recomeça o registro, como o `KVM_GET_DIRTY_LOG` do Linux.
+    /// Veja o módulo `dirty_log`.
+    pub fn collect_and_clear_dirty_bitmap(&mut self) -> DirtyBitmap {
+        let bitmap = std::mem::replace(&mut self.state.dirty_log, DirtyBitmap::new(PAGE_COUNT));
+        self.checkpoint();
+
+        bitmap
+    }
+
+    /// O conteúdo atual de uma página, lido do seu frame se ela está em
+    /// memória ou do disco se não está. Não conta como acesso e não muda
+    /// nada na Mmu: é como o hypervisor lê a memória do guest para copiá-la.
+    /// O erro é o do carregador, se a página não está em memória e ele falha.
+    pub fn peek_page(&mut self, page_number: usize) -> Result<Vec<u8>, LoaderError> {
+        if let Some(page) = self.peek_page_ref(page_number) {
+            return Ok(page.to_vec());
+        }
+
+        let mut page = vec![0; MEM_SIZE / FRAME_COUNT];
+        self.loader.try_load_page_into(page_number, &mut page)?;
+        Ok(page)
+    }
+
+    /// Como `peek_page`, mas sem cópia: devolve o próprio frame, se a página
+    /// está em memória, ou o buffer do carregador (veja
+    /// `PageLoader::page_ref`). `None` se a página não está em memória e o
+    /// carregador não tem um buffer para ela; aí só `peek_page` serve.
+    pub fn peek_page_ref(&self, page_number: usize) -> Option<&[u8]> {
+        match self.state.page_table.get(page_number) {
+            Some(entry) => Some(&self.memory[Self::frame_idx_to_range(entry.frame_index)]),
+            None => self.loader.page_ref(page_number),
+        }
+    }
+
+    /// Preenche o espaço de endereçamento virtual com `image`, a partir do
+    /// endereço `base_address`, como se o programa tivesse sido carregado ali.
+    /// As páginas em memória são escritas nos seus frames (e ficam sujas);
+    /// as outras são escritas direto no disco, sem serem carregadas. Nada
+    /// disso conta como acesso.
+    ///
+    /// Páginas do mapeamento da page table (veja `map_page_table`) são
+    /// ignoradas. É uma operação de preparação: a viagem no tempo não volta
+    /// para antes dela.
+    ///
+    /// Se o carregador falha, a carga para na página que falhou, que fica
+    /// como estava; as anteriores já foram escritas.
+    pub fn load_image(&mut self, base_address: usize, image: &[u8]) -> Result<(), LoaderError> {
+        let page_size = MEM_SIZE / FRAME_COUNT;
+
+        assert!(
+            base_address + image.len() <= PAGE_COUNT * page_size,
+            "imagem de {} bytes em {:#06X} não cabe no espaço de endereçamento",
+            image.len(),
+            base_address
+        );
+
+        let result = self.write_image(base_address, image);
+
+        if result.is_ok() {
+            debug!(
+                "mmu: imagem de {} bytes carregada em {:#06X}",
+                image.len(),
+                base_address
+            );
+        }
+
+        // As escritas no disco não estão no journal, então não dá para
+        // voltar para antes delas.
+        if let Some(time_travel) = self.time_travel.as_mut() {
+            time_travel.clear();
+        }
+        self.checkpoint();
+
+        result
+    }
+
+    /// Escreve a imagem, página por página, para `load_image`, parando na
+    /// primeira falha do carregador.
+    fn write_image(&mut self, base_address: usize, image: &[u8]) -> Result<(), LoaderError> {
+        let page_size = MEM_SIZE / FRAME_COUNT;
+        let mut address = base_address;
+        let mut remaining = image;
+
+        while !remaining.is_empty() {
+            let page_number = address / page_size;
+            let page_offset = address % page_size;
+            let len = remaining.len().min(page_size - page_offset);
+            let (chunk, rest) = remaining.split_at(len);
+
+            match self.state.page_table.get(page_number) {
+                Some(entry) if self.is_page_table_frame(entry.frame_index) => {
+                    warn!(
+                        "mmu: imagem na página {:#04X} ignorada: a página mapeia a page table",
+                        page_number
+                    );
+                }
+                // Um frame compartilhado não pode ser alterado: a página sai
+                // dele e vai, já alterada, para o disco.
+                Some(entry) if self.state.shared.is_shared(entry.frame_index) => {
+                    let mut page =
+                        self.memory[Self::frame_idx_to_range(entry.frame_index)].to_vec();
+                    page[page_offset..page_offset + len].copy_from_slice(chunk);
+                    self.loader.try_flush_page(page_number, &page)?;
+                    self.release_page(page_number);
+                }
+                Some(entry) => {
+                    let frame_range = Self::frame_idx_to_range(entry.frame_index);
+                    let start = frame_range.start + page_offset;
+                    self.memory[start..start + len].copy_from_slice(chunk);
+
+                    self.state
+                        .page_table
+                        .mark_dirty(page_number, (self.stats.accesses(), self.stats.ticks()));
+                    self.store_pte(page_number);
+                }
+                None => {
+                    let mut page = match self.loader.page_ref(page_number) {
+                        Some(page) => page.to_vec(),
+                        None => {
+                            let mut page = vec![0; page_size];
+                            self.loader.try_load_page_into(page_number, &mut page)?;
+                            page
+                        }
+                    };
+                    page[page_offset..page_offset + len].copy_from_slice(chunk);
+                    self.loader.try_flush_page(page_number, &page)?;
+                }
+            }
+
+            self.state.dirty_log.set(page_number);
+
+            address += len;
+            remaining = rest;
+        }
+
+        Ok(())
+    }
+
+    /// O estado da page table, para ser guardado como TOML (veja o módulo
+    /// `state`): as páginas residentes, da carregada há mais tempo para a
+    /// mais recente, com seus frames e flags. As páginas do mapeamento da
+    /// page table (veja `map_page_table`) ficam de fora.
+    pub fn export_state(&self) -> PageTableState {
+        let mut pages: Vec<((usize, usize), PageState)> = self
+            .state
+            .page_table
+            .iter()
+            .filter(|(_, entry)| !self.is_page_table_frame(entry.frame_index))
+            .map(|(page_number, entry)| {
+                let loaded_at = (
+                    self.state.loaded_at.get(&page_number).copied().unwrap_or(0),
+                    self.state
+                        .state_order
+                        .get(&page_number)
+                        .copied()
+                        .unwrap_or(usize::MAX),
+                );
+                let page = PageState {
+                    page: page_number,
+                    frame: entry.frame_index,
+                    dirty: entry.dirty,
+                    referenced: entry.referenced,
+                    pinned: entry.pinned,
+                    age: entry.age,
+                    software: entry.software,
+                };
+
+                (loaded_at, page)
+            })
+            .collect();
+        pages.sort_by_key(|&(loaded_at, page)| (loaded_at, page.page));
+
+        PageTableState {
+            frames: FRAME_COUNT,
+            pages: pages.into_iter().map(|(_, page)| page).collect(),
+        }
+    }
+
+    /// Carrega o estado inicial da page table (veja o módulo `state`) numa
+    /// Mmu sem páginas em memória: cada página vai para o seu frame, com o
+    /// conteúdo lido do loader e as flags do estado. Nada disso conta como
+    /// acesso.
+    ///
+    /// O replacer recebe as páginas não fixadas como se tivessem sido
+    /// carregadas na ordem do estado, e depois um `Touched` para cada página
+    /// com o bit de referência ligado e um `Dirtied` para cada página suja,
+    /// como em `replace_policy`.
+    ///
+    /// O estado é conferido inteiro, e o conteúdo das páginas lido do
+    /// carregador, antes de qualquer mudança: num erro, a Mmu continua
+    /// vazia. É uma operação de preparação: a viagem no tempo
+    /// não volta para antes dela.
+    pub fn load_state(&mut self, state: &PageTableState) -> Result<(), StateError> {
+        if state.frames != FRAME_COUNT {
+            return Err(StateError::FrameCount {
+                expected: FRAME_COUNT,
+                found: state.frames,
+            });
+        }
+
+        if self.state.page_table.iter().next().is_some() {
+            return Err(StateError::NotEmpty);
+        }
+
+        let mut pages = BTreeSet::new();
+        let mut frames = BTreeSet::new();
+        for page in &state.pages {
+            if page.page >= PAGE_COUNT {
+                return Err(StateError::PageOutOfRange(page.page));
+            }
+            if page.frame >= FRAME_COUNT {
+                return Err(StateError::FrameOutOfRange(page.frame));
+            }
+            if !pages.insert(page.page) {
+                return Err(StateError::DuplicatePage(page.page));
+            }
+            if !frames.insert(page.frame) || !self.state.free_frames.contains(&page.frame) {
+                return Err(StateError::FrameInUse(page.frame));
+            }
+        }
+
+        let mut contents = Vec::with_capacity(state.pages.len());
+        for page in &state.pages {
+            let mut content = vec![0; MEM_SIZE / FRAME_COUNT];
+            self.loader
+                .try_load_page_into(page.page, &mut content)
+                .map_err(StateError::Loader)?;
+            contents.push(content);
+        }
+
+        let now = (self.stats.accesses(), self.stats.ticks());
+
+        for (i, (page, content)) in state.pages.iter().zip(contents).enumerate() {
+            self.state
+                .free_frames
+                .retain(|&frame_idx| frame_idx != page.frame);
+            self.state.page_table.set(page.page, page.frame);
+            self.state.refcounts.increment(page.frame);
+            self.memory[Self::frame_idx_to_range(page.frame)].copy_from_slice(&content);
+
+            if page.dirty {
+                self.state.page_table.mark_dirty(page.page, now);
+            }
+            if page.referenced {
+                self.state.page_table.mark_referenced(page.page);
+            }
+            self.state.page_table.set_age(page.page, page.age);
+            self.state.page_table.set_pinned(page.page, page.pinned);
+            for bit in 0..SOFTWARE_BITS {
+                self.state.page_table.set_software_bit(
+                    page.page,
+                    bit,
+                    page.software & (1 << bit) != 0,
+                );
+            }
+
+            self.store_pte(page.page);
+            self.state
+                .loaded_at
+                .insert(page.page, self.stats.accesses());
+            self.state.state_order.insert(page.page, i);
+        }
+
+        let unpinned = || state.pages.iter().filter(|page| !page.pinned);
+
+        for page in unpinned() {
+            self.replacer.page_event(PageEvent::Loaded(page.page));
+        }
+
+        for page in unpinned() {
+            if page.referenced {
+                self.replacer.page_event(PageEvent::Touched(page.page));
+            }
+            if page.dirty {
+                self.replacer.page_event(PageEvent::Dirtied(page.page));
+            }
+        }
+
+        debug!(
+            "mmu: estado carregado ({} páginas residentes)",
+            state.pages.len()
+        );
+
+        self.check_refcounts();
+
+        if let Some(time_travel) = self.time_travel.as_mut() {
+            time_travel.clear();
+        }
+        self.checkpoint();
+
+        Ok(())
+    }
+
+    /// Coloca a page table dentro da memória física, nos frames a partir de
+    /// `base_frame`, que ficam reservados para ela. A partir daí, toda
+    /// tradução lê a entrada da página da memória, e toda mudança na page
+    /// table (uma página carregada, suja, referenciada...) é escrita de volta
+    /// nela; as leituras e escritas aparecem nas estatísticas.
+    ///
+    /// Cada entrada ocupa `PTE_SIZE` bytes (veja `PageTableEntry::encode`),
+    /// então a entrada da página `p` fica no endereço físico
+    /// `base + p * PTE_SIZE`. Os frames precisam estar livres, e o índice da
+    /// entrada precisa endereçar todos (veja `PTE_MAX_FRAMES`).
+    pub fn place_page_table_in_memory(&mut self, base_frame: usize) -> Result<(), PlacementError> {
+        if self.state.page_table_frames.is_some() {
+            return Err(PlacementError::AlreadyPlaced);
+        }
+
+        if FRAME_COUNT > PTE_MAX_FRAMES {
+            return Err(PlacementError::TooManyFrames(FRAME_COUNT));
+        }
+
+        let page_size = MEM_SIZE / FRAME_COUNT;
+        let frame_count = (PAGE_COUNT * PTE_SIZE).div_ceil(page_size);
+        let frames = base_frame..base_frame + frame_count;
+
+        if frames.end > FRAME_COUNT {
+            return Err(PlacementError::OutOfRange);
+        }
+
+        if let Some(frame) = frames
+            .clone()
+            .find(|frame| !self.state.free_frames.contains(frame))
+        {
+            return Err(PlacementError::FrameInUse(frame));
+        }
+
+        debug!(
+            "mmu: page table colocada nos frames {:#04X}..{:#04X}",
+            frames.start, frames.end
+        );
+
+        self.state
+            .free_frames
+            .retain(|frame| !frames.contains(frame));
+        self.state.page_table_frames = Some(frames);
+        self.store_all_ptes();
+        self.checkpoint();
+
+        Ok(())
+    }
+
+    /// O endereço físico da page table, se ela está na memória.
+    pub fn page_table_address(&self) -> Option<usize> {
+        self.state
+            .page_table_frames
+            .as_ref()
+            .map(|frames| Self::frame_idx_to_range(frames.start).start)
+    }
+
+    /// Mapeia a page table (que deve estar na memória) nas páginas virtuais
+    /// a partir de `first_page`, um mapeamento "recursivo": lendo essas
+    /// páginas, o programa vê as entradas da sua própria page table, inclusive
+    /// as dessas páginas. O mapeamento é somente leitura -- escritas nele são
+    /// ignoradas -- e suas páginas nunca são substituídas.
+    pub fn map_page_table(&mut self, first_page: usize) {
+        let Some(frames) = self.state.page_table_frames.clone() else {
+            warn!("mmu: a page table não está na memória");
+            return;
+        };
+
+        for (page_number, frame_idx) in (first_page..).zip(frames) {
+            self.release_page(page_number);
+            self.state.page_table.set(page_number, frame_idx);
+            self.state.refcounts.increment(frame_idx);
+        }
+
+        self.store_all_ptes();
+        self.checkpoint();
+    }
+
+    /// Se o frame está reservado para a page table.
+    fn is_page_table_frame(&self, frame_idx: usize) -> bool {
+        self.state
+            .page_table_frames
+            .as_ref()
+            .is_some_and(|frames| frames.contains(&frame_idx))
+    }
+
+    /// O endereço físico da entrada da página, se a page table está na
+    /// memória.
+    fn pte_range(&self, page_number: usize) -> Option<Range<usize>> {
+        let start = self.page_table_address()? + page_number * PTE_SIZE;
+
+        Some(start..start + PTE_SIZE)
+    }
+
+    /// O frame da página, se ela está em memória: lido da entrada na memória
+    /// física, se a page table está lá, ou direto da page table.
+    fn lookup_frame(&self, page_number: usize) -> Option<usize> {
+        match self.pte_range(page_number) {
+            Some(range) => {
+                self.stats.page_table_reads.fetch_add(1, Ordering::Relaxed);
+                PageTableEntry::decode(self.memory[range].try_into().unwrap())
+            }
+            None => self
+                .state
+                .page_table
+                .get(page_number)
+                .map(|entry| entry.frame_index),
+        }
+    }
+
+    /// Escreve a entrada da página na memória física, se a page table está
+    /// lá e a entrada mudou.
+    fn store_pte(&mut self, page_number: usize) {
+        let Some(range) = self.pte_range(page_number) else {
+            return;
+        };
+
+        let encoded = PageTableEntry::encode(self.state.page_table.get(page_number));
+
+        if self.memory[range.clone()] != encoded {
+            self.memory[range].copy_from_slice(&encoded);
+            self.stats.page_table_writes.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Escreve na memória física todas as entradas que mudaram.
+    fn store_all_ptes(&mut self) {
+        if self.state.page_table_frames.is_some() {
+            for page_number in 0..PAGE_COUNT {
+                self.store_pte(page_number);
+            }
+        }
+    }
+
+    // Função principal que faz a translação entre um endereço virtual e um
+    // endereço físico (no nosso caso, modelado por um range dentro da array de
+    // memória e um offset dentro desse range).
+    fn translate_addr(&mut self, address: usize, kind: AccessKind) -> (Range<usize>, usize) {
+        let address = address & 0xFFFF; // trunca o endereco para 16 bits
+
+        // Os frames têm `PAGE_SIZE` bytes (veja `try_new`).
+        let page_number = (address & 0xFF00) >> 8; // top 8 bits
+        let page_offset = address & 0x00FF; // bottom 8 bits
+
+        info!(
+            "mmu: acesso addr {:#06X} page_num={:#02X} page_offset={:#02X}",
+            address, page_number, page_offset
+        );
+
+        let time = self.stats.accesses();
+
+        self.state.regions.record_access(page_number, kind);
+
+        if self.state.strict && self.state.regions.find_live(page_number).is_none() {
+            let violation = AccessViolation {
+                time,
+                kind,
+                address,
+                region: self.state.regions.find_latest(page_number).cloned(),
+            };
+
+            warn!("mmu: {}", violation);
+            self.state.violations.push(violation);
+        }
+
+        // Com o TLB, um hit dispensa a leitura da page table.
+        let resident_frame = self
+            .state
+            .page_table
+            .get(page_number)
+            .map(|entry| entry.frame_index);
+        let tlb_hit = self
+            .state
+            .tlb
+            .as_mut()
+            .and_then(|tlb| tlb.lookup(page_number, resident_frame));
+        match tlb_hit {
+            Some(pages) => {
+                self.stats.tlb_hits.fetch_add(1, Ordering::Relaxed);
+                if pages > 1 {
+                    self.stats.tlb_huge_hits.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            None if self.state.tlb.is_some() => {
+                self.stats.tlb_misses.fetch_add(1, Ordering::Relaxed);
+            }
+            None => {}
+        }
+        let tlb_hit = tlb_hit.is_some();
+        let frame_idx = if tlb_hit {
+            resident_frame
+        } else {
+            self.lookup_frame(page_number)
+        };
+
+        let (frame_idx, hit, evicted) = match frame_idx {
+            Some(frame_idx) => {
+                // Se houve page hit, já sabemos imediatamente qual o frame
+                // que queremos acessar.
+                debug!("mmu: page hit");
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                self.stats.fault_bursts.record(time, false);
+                (frame_idx, true, None)
+            }
+            None => {
+                // Se houve page fault, vamos escolher qual o frame será carregado,
+                // e vamos carregar a página nele.
+                debug!("mmu: page fault! tratando...");
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                self.stats.fault_bursts.record(time, true);
+                if self.state.swapped_pages.remove(&page_number) {
+                    self.stats.swap_refaults.fetch_add(1, Ordering::Relaxed);
+                }
+                let (frame_idx, evicted) = self.handle_page_fault(page_number);
+                (frame_idx, false, evicted)
+            }
+        };
+
+        if kind == AccessKind::Fetch {
+            self.stats
+                .instruction_fetches
+                .fetch_add(1, Ordering::Relaxed);
+            if !hit {
+                self.stats
+                    .instruction_misses
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        if let Some(three_cs) = self.state.three_cs.as_mut() {
+            three_cs.access(page_number, hit);
+        }
+
+        if !self.state.partitions.is_empty() {
+            let owner = self
+                .state
+                .regions
+                .find_live(page_number)
+                .map(|region| region.pid);
+            self.state.partitions.access(owner, hit);
+        }
+
+        // Uma escrita num frame compartilhado quebra o copy-on-write: a
+        // página ganha um frame só seu, com uma cópia do conteúdo.
+        let (frame_idx, evicted) =
+            if kind == AccessKind::Write && self.state.shared.is_shared(frame_idx) {
+                let (frame_idx, cow_evicted) = self.break_cow(page_number, frame_idx);
+                (frame_idx, evicted.or(cow_evicted))
+            } else {
+                (frame_idx, evicted)
+            };
+
+        if let Some(tiers) = self.state.tiers.as_mut() {
+            tiers.access(page_number, frame_idx);
+        }
+
+        if let (false, Some(tlb)) = (tlb_hit, self.state.tlb.as_mut()) {
+            let first_page = huge_page_of(page_number);
+
+            match self.state.page_table.get(first_page) {
+                Some(first) if self.state.huge_pages.contains(&first_page) => {
+                    tlb.insert_huge(first_page, HUGE_PAGE_PAGES, first.frame_index);
+                }
+                _ => tlb.insert(page_number, frame_idx),
+            }
+        }
+
+        // Quando a ação é uma escrita, também vamos marcar a dirty flag
+        // para que a página seja reescrita de volta em disco.
+        let dirtied = kind == AccessKind::Write
+            && self
+                .state
+                .page_table
+                .get(page_number)
+                .is_some_and(|entry| !entry.dirty);
+        if kind == AccessKind::Write {
+            self.state
+                .page_table
+                .mark_dirty(page_number, (time, self.stats.ticks()));
+        }
+
+        self.state.page_table.mark_referenced(page_number);
+
+        // Emite os eventos para cálculo do replacer.
+        self.replacer.page_event(PageEvent::Touched(page_number));
+        self.replacer.page_event(match kind {
+            AccessKind::Read | AccessKind::Fetch => PageEvent::Read(page_number),
+            AccessKind::Write => PageEvent::Written(page_number),
+        });
+        if dirtied {
+            self.replacer.page_event(PageEvent::Dirtied(page_number));
+        }
+        if let Some(size) = self.replacer.working_set_size() {
+            self.stats.working_set_sizes.record(size);
+        }
+
+        // Se a page table está na memória, escreve de volta as entradas que
+        // mudaram.
+        self.store_pte(page_number);
+        if let Some((evicted_page, _)) = evicted {
+            self.store_pte(evicted_page);
+        }
+
+        let record = AccessRecord {
+            time,
+            kind,
+            address,
+            page_number,
+            frame_index: frame_idx,
+            mode: self.state.mode,
+            hit,
+            evicted: evicted.map(|(page, _)| page),
+            writeback: evicted.map(|(_, dirty)| dirty).unwrap_or(false),
+        };
+
+        // Avisa os observadores e registra o acesso no trace, se habilitado.
+        for observer in self.observers.iter_mut() {
+            observer.on_access(&record);
+        }
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(record);
+        }
+
+        if let Some(interval) = self.state.tick_interval {
+            if self.stats.accesses().is_multiple_of(interval) {
+                self.age_pages();
+            }
+        }
+
+        // O tick pode ter movido a página, ao formar uma página grande.
+        let frame_idx = self.state.page_table.get(page_number).unwrap().frame_index;
+
+        // Calcula a janela do frame dentro da array memória.
+        let frame_range = Self::frame_idx_to_range(frame_idx);
+
+        debug!(
+            "mmu: página {:#02X} mapeada para frame físico idx={:#02X} [{:#02X}; {:#02X})",
+            page_number, frame_idx, &frame_range.start, &frame_range.end
+        );
 
         // Retorna o frame e o offset.
         (frame_range, page_offset)
     }
 
+    /// Traduz o endereço virtual no endereço físico correspondente, fazendo
+    /// tudo que um acesso faz (tratar a page fault, marcar a página suja...),
+    /// mas sem ler nem escrever. Usado para compor Mmus (veja o módulo
+    /// `nested`). As traduções feitas assim não são registradas pela viagem
+    /// no tempo. Os erros são os de `read`.
+    pub fn translate(&mut self, address: usize, kind: AccessKind) -> Result<usize, MmuError> {
+        self.check_address(kind, address)?;
+        self.check_access(kind, address)?;
+
+        let (frame_range, page_offset) = self.translate_addr(address, kind);
+        self.take_access_error(kind, address)?;
+
+        Ok(frame_range.start + page_offset)
+    }
+
+    /// A memória física inteira.
+    pub(crate) fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
     /// Lê o byte existente no endereço address.
-    pub fn read(&mut self, address: usize) -> u8 {
+    ///
+    /// Devolve um erro se o acesso não pôde acontecer (um endereço fora do
+    /// espaço de endereçamento, uma falta de privilégio...) ou se o
+    /// carregador falhou numa page fault ou no writeback da vítima. Nesse
+    /// último caso, o acesso conta mesmo assim: a página fica em memória com
+    /// o que o carregador deixou no frame.
+    pub fn read(&mut self, address: usize) -> Result<u8, MmuError> {
+        self.read_as(AccessKind::Read, address)
+    }
+
+    /// Busca o byte de instrução no endereço address, como a CPU antes de
+    /// executá-lo: uma leitura que precisa da permissão de execução da
+    /// página (veja `set_protection`). As buscas são contadas à parte dos
+    /// acessos a dados (veja `MmuStats::instruction_fetches`). Os erros são
+    /// os de `read`.
+    pub fn fetch(&mut self, address: usize) -> Result<u8, MmuError> {
+        self.read_as(AccessKind::Fetch, address)
+    }
+
+    /// Lê o byte como uma leitura ou uma busca de instrução.
+    fn read_as(&mut self, kind: AccessKind, address: usize) -> Result<u8, MmuError> {
+        self.check_address(kind, address)?;
+        self.check_access(kind, address)?;
+
         // Faz a tradução do endereço.
-        let (frame_range, page_offset) = self.translate_addr(address, false);
+        let (frame_range, page_offset) = self.translate_addr(address, kind);
 
         // Olha na array memory a partir da janela (que corresponde ao frame da página).
         let frame = &mut self.memory[frame_range];
 
         // Olha no frame considerando o offset, que é exatamente o endereço desejado.
-        frame[page_offset]
+        let value = frame[page_offset];
+
+        self.log_access(match kind {
+            AccessKind::Fetch => LoggedAccess::Fetch(address),
+            _ => LoggedAccess::Read(address),
+        });
+        self.take_access_error(kind, address)?;
+
+        Ok(value)
     }
 
-    /// Escreve um byte value no endereço address.
-    pub fn write(&mut self, address: usize, value: u8) {
-        // Faz a tradução do endereço.
-        let (frame_range, page_offset) = self.translate_addr(address, true);
+    /// Escreve um byte value no endereço address. Os erros são os de `read`.
+    pub fn write(&mut self, address: usize, value: u8) -> Result<(), MmuError> {
+        self.check_address(AccessKind::Write, address)?;
+        self.check_access(AccessKind::Write, address)?;
 
-        // Olha na array memory a partir da janela (que corresponde ao frame da página).
-        let frame = &mut self.memory[frame_range];
+        // Faz a tradução do endereço; o mapeamento da page table é somente
+        // leitura.
+        if let Some(physical) = self.translate_for_write(address) {
+            // Escreve na array memory, no frame da página e no offset do endereço.
+            self.memory[physical] = value;
+        }
+
+        self.log_access(LoggedAccess::Write(address, value));
+        Ok(self.take_access_error(AccessKind::Write, address)?)
+    }
+
+    /// Recusa um endereço fora do espaço de endereçamento: de mais de 16
+    /// bits, ou de uma página além de `PAGE_COUNT`.
+    fn check_address(&self, kind: AccessKind, address: usize) -> Result<(), MmuError> {
+        if address <= 0xFFFF && address >> 8 < PAGE_COUNT {
+            return Ok(());
+        }
+
+        warn!(
+            "mmu: acesso a {:#06X} recusado: fora do espaço de endereçamento",
+            address
+        );
+
+        Err(MmuError::InvalidAddress {
+            kind,
+            address,
+            page_count: PAGE_COUNT,
+        })
+    }
+
+    /// Devolve, com o contexto do acesso que acabou, a falha do carregador
+    /// guardada durante ele, se houve uma.
+    fn take_access_error(&mut self, kind: AccessKind, address: usize) -> Result<(), AccessError> {
+        match self.state.loader_error.take() {
+            Some((cause, frame_index)) => Err(AccessError {
+                kind,
+                address,
+                page_number: (address & 0xFFFF) >> 8,
+                frame_index,
+                cause: AccessFailure::Loader(cause),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Confere, antes da tradução, se o acesso pode acontecer: o modo da CPU,
+    /// a proteção da página, o limite de RSS do dono da página e, numa page
+    /// fault sem frame livre, se há uma vítima.
+    fn check_access(&mut self, kind: AccessKind, address: usize) -> Result<(), MmuError> {
+        self.check_privilege(kind, address)?;
+        self.check_protection(kind, address)?;
+        self.check_rss_limit(kind, address)?;
+        self.check_evictable(kind, address)
+    }
+
+    /// Reserva a vítima de um acesso que vai precisar de um frame: uma page
+    /// fault, ou uma escrita numa página de um frame compartilhado, que
+    /// quebra o COW (veja `reserve_victim`).
+    fn check_evictable(&mut self, kind: AccessKind, address: usize) -> Result<(), MmuError> {
+        let page_number = (address & 0xFFFF) >> 8;
+
+        match self.state.page_table.get(page_number) {
+            None => self.reserve_victim(kind, address, None),
+            Some(entry)
+                if kind == AccessKind::Write && self.state.shared.is_shared(entry.frame_index) =>
+            {
+                self.reserve_victim(kind, address, Some(page_number))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Escolhe a vítima de um acesso que precisa de um frame, se não há um
+    /// livre que a página possa usar (veja `fault_scope`), antes de qualquer
+    /// mudança na Mmu, e a reserva para `take_frame`. Numa quebra de COW,
+    /// `cow_page` é a página do acesso, que ainda está em memória, mas não
+    /// pode ser a vítima. Se o replacer não tem nenhuma página que possa sair
+    /// (todas fixadas, por exemplo), ou se escolhe uma página que não pode
+    /// sair, o acesso não acontece.
+    fn reserve_victim(
+        &mut self,
+        kind: AccessKind,
+        address: usize,
+        cow_page: Option<usize>,
+    ) -> Result<(), MmuError> {
+        let page_number = (address & 0xFFFF) >> 8;
+
+        let scope = self.fault_scope(page_number);
+        if !self.state.free_frames.is_empty() && scope.is_none_or(|scope| scope.use_free) {
+            return Ok(());
+        }
 
-        // Escreve no frame considerando o offset, que é exatamente o endereço desejado.
-        frame[page_offset] = value;
+        // O tempo da escolha já vai para `FaultPhase::VictimSelection`.
+        let mut measured = Duration::ZERO;
+        match self.select_victim(&mut measured, scope.map(|scope| scope.victims), cow_page) {
+            Ok(Some(reserved)) => {
+                self.state.reserved_victim = Some(reserved);
+                return Ok(());
+            }
+            Ok(None) => {}
+            Err(message) => {
+                warn!("mmu: acesso a {:#06X} recusado: {}", address, message);
+                return Err(MmuError::PageTableInconsistency {
+                    kind,
+                    address,
+                    message,
+                });
+            }
+        }
+
+        warn!(
+            "mmu: acesso a {:#06X} recusado: nenhuma página em memória pode ser substituída",
+            address
+        );
+
+        Err(AccessError {
+            kind,
+            address,
+            page_number,
+            frame_index: None,
+            cause: AccessFailure::NoEvictablePage,
+        }
+        .into())
+    }
+
+    /// Recusa um acesso que causaria uma page fault numa página de um
+    /// processo que já tem o máximo de páginas em memória.
+    fn check_rss_limit(&mut self, kind: AccessKind, address: usize) -> Result<(), AccessError> {
+        let page_number = (address & 0xFFFF) >> 8;
+
+        if self.state.rss_limits.is_empty() || self.state.page_table.get(page_number).is_some() {
+            return Ok(());
+        }
+
+        let Some(pid) = self
+            .state
+            .regions
+            .find_live(page_number)
+            .map(|region| region.pid)
+        else {
+            return Ok(());
+        };
+        let Some(&limit) = self.state.rss_limits.get(&pid) else {
+            return Ok(());
+        };
+        if self.process_stats(pid).resident_pages < limit {
+            return Ok(());
+        }
+
+        warn!(
+            "mmu: acesso a {:#06X} recusado: o processo {} está no limite de RSS ({} páginas)",
+            address, pid, limit
+        );
+        self.stats.rss_limit_hits.fetch_add(1, Ordering::Relaxed);
+
+        Err(AccessError {
+            kind,
+            address,
+            page_number,
+            frame_index: None,
+            cause: AccessFailure::RssLimit { pid, limit },
+        })
+    }
+
+    /// Confere, antes da tradução, se o acesso é permitido no modo atual da
+    /// CPU. Um acesso em modo usuário a uma página do supervisor é
+    /// registrado como uma falta e não acontece.
+    fn check_privilege(&mut self, kind: AccessKind, address: usize) -> Result<(), AccessError> {
+        let page_number = (address & 0xFFFF) >> 8;
+
+        if self.state.mode == PrivilegeMode::Supervisor
+            || !self.state.supervisor_pages.contains(&page_number)
+        {
+            return Ok(());
+        }
+
+        let fault = PrivilegeFault {
+            time: self.stats.accesses(),
+            kind,
+            address,
+            page_number,
+        };
+
+        warn!("mmu: falta de privilégio {}", fault);
+        self.state.privilege_faults.push(fault);
+
+        Err(AccessError {
+            kind,
+            address,
+            page_number,
+            frame_index: None,
+            cause: AccessFailure::Privilege(fault),
+        })
+    }
+
+    /// Recusa um acesso que a proteção da página não permite.
+    fn check_protection(&mut self, kind: AccessKind, address: usize) -> Result<(), AccessError> {
+        let page_number = (address & 0xFFFF) >> 8;
+        let protection = self.state.page_table.protection(page_number);
+
+        if protection.allows(kind) {
+            return Ok(());
+        }
+
+        warn!(
+            "mmu: acesso a {:#06X} recusado: a página {:#04X} é {}",
+            address, page_number, protection
+        );
+        self.stats.protection_faults.fetch_add(1, Ordering::Relaxed);
+
+        Err(AccessError {
+            kind,
+            address,
+            page_number,
+            frame_index: None,
+            cause: AccessFailure::Protection(protection),
+        })
+    }
+
+    /// Lê `buffer.len()` bytes a partir de `address`, que podem atravessar
+    /// várias páginas. O mesmo que `read_into`.
+    pub fn read_bytes(&mut self, address: usize, buffer: &mut [u8]) -> Result<(), MmuError> {
+        self.read_into(address, buffer)
+    }
+
+    /// Escreve `data` a partir de `address`. O mesmo que `write_from`.
+    pub fn write_bytes(&mut self, address: usize, data: &[u8]) -> Result<(), MmuError> {
+        self.write_from(address, data)
+    }
+
+    /// Lê `buffer.len()` bytes a partir de `address`, que podem atravessar
+    /// várias páginas, para carregar um buffer de uma vez. Cada página
+    /// tocada é traduzida uma vez e copiada como uma fatia, então conta como
+    /// um acesso (e no máximo uma page fault), não como um por byte.
+    ///
+    /// O endereço do último byte é conferido antes de qualquer tradução; as
+    /// outras falhas (de privilégio, do carregador...) interrompem a leitura
+    /// na página em que acontecem, com as anteriores já lidas. Os erros são
+    /// os de `read`.
+    pub fn read_into(&mut self, address: usize, buffer: &mut [u8]) -> Result<(), MmuError> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        self.check_address(AccessKind::Read, address + buffer.len() - 1)?;
+
+        let mut offset = 0;
+        while offset < buffer.len() {
+            let len = Self::chunk_len(address + offset, buffer.len() - offset);
+            self.read_slice(address + offset, &mut buffer[offset..offset + len])?;
+            offset += len;
+        }
+
+        Ok(())
+    }
+
+    /// Escreve `data` a partir de `address`, que pode atravessar várias
+    /// páginas, como para carregar a imagem de um programa. Como em
+    /// `read_into`, cada página tocada conta como um acesso, e as páginas
+    /// antes de uma falha ficam escritas. Os erros são os de `write`.
+    pub fn write_from(&mut self, address: usize, data: &[u8]) -> Result<(), MmuError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.check_address(AccessKind::Write, address + data.len() - 1)?;
+
+        let mut offset = 0;
+        while offset < data.len() {
+            let len = Self::chunk_len(address + offset, data.len() - offset);
+            self.write_slice(address + offset, &data[offset..offset + len])?;
+            offset += len;
+        }
+
+        Ok(())
+    }
+
+    /// Copia `len` bytes de `src` para `dst`, como um `memmove`: os trechos
+    /// podem se sobrepor. Cada pedaço dentro de uma página de origem e uma de
+    /// destino é traduzido uma vez em cada ponta (dois acessos) e copiado
+    /// como uma fatia.
+    ///
+    /// O pedaço é lido inteiro antes de ser escrito, porque traduzir o
+    /// destino pode causar uma page fault que reaproveita o frame da origem,
+    /// e porque origem e destino podem estar no mesmo frame. Quando o destino
+    /// começa dentro da origem, os pedaços são copiados de trás para frente,
+    /// para que nenhum byte da origem seja sobrescrito antes de ser lido.
+    ///
+    /// Como em `read_into`, o último byte das duas pontas é conferido antes
+    /// de qualquer tradução, e uma falha interrompe a cópia no pedaço em que
+    /// acontece, com os anteriores já copiados. Os erros são os de `read` e
+    /// `write`.
+    pub fn copy(&mut self, dst: usize, src: usize, len: usize) -> Result<(), MmuError> {
+        if len == 0 {
+            return Ok(());
+        }
+        self.check_address(AccessKind::Read, src + len - 1)?;
+        self.check_address(AccessKind::Write, dst + len - 1)?;
+
+        let page_size = MEM_SIZE / FRAME_COUNT;
+        let backwards = dst > src && dst < src + len;
+        let mut buffer = vec![0; page_size];
+        let mut done = 0;
+
+        while done < len {
+            let remaining = len - done;
+
+            let (offset, chunk) = if backwards {
+                // O pedaço termina no fim do que falta copiar e não atravessa
+                // o início de uma página em nenhuma das pontas.
+                let src_room = (src + remaining - 1) % page_size + 1;
+                let dst_room = (dst + remaining - 1) % page_size + 1;
+                let chunk = remaining.min(src_room).min(dst_room);
+
+                (remaining - chunk, chunk)
+            } else {
+                let chunk = Self::chunk_len(src + done, remaining)
+                    .min(Self::chunk_len(dst + done, remaining));
+
+                (done, chunk)
+            };
+
+            self.read_slice(src + offset, &mut buffer[..chunk])?;
+            self.write_slice(dst + offset, &buffer[..chunk])?;
+
+            done += chunk;
+        }
+
+        Ok(())
+    }
+
+    /// Preenche `len` bytes a partir de `dst` com `value`. Cada página tocada
+    /// conta como um acesso. Como em `write_from`, o último byte é conferido
+    /// antes de qualquer tradução. Os erros são os de `write`.
+    pub fn fill(&mut self, dst: usize, value: u8, len: usize) -> Result<(), MmuError> {
+        if len == 0 {
+            return Ok(());
+        }
+        self.check_address(AccessKind::Write, dst + len - 1)?;
+
+        let mut offset = 0;
+        while offset < len {
+            let chunk = Self::chunk_len(dst + offset, len - offset);
+            self.fill_slice(dst + offset, value, chunk)?;
+            offset += chunk;
+        }
+
+        Ok(())
+    }
+
+    /// Quantos dos `len` bytes a partir de `address` cabem na página dele.
+    fn chunk_len(address: usize, len: usize) -> usize {
+        let page_size = MEM_SIZE / FRAME_COUNT;
+
+        len.min(page_size - address % page_size)
+    }
+
+    /// Lê um pedaço dentro de uma página, com uma tradução só. Os erros são
+    /// os de `read`.
+    fn read_slice(&mut self, address: usize, buffer: &mut [u8]) -> Result<(), MmuError> {
+        self.check_address(AccessKind::Read, address)?;
+        self.check_access(AccessKind::Read, address)?;
+
+        let (frame_range, page_offset) = self.translate_addr(address, AccessKind::Read);
+        let start = frame_range.start + page_offset;
+
+        buffer.copy_from_slice(&self.memory[start..start + buffer.len()]);
+
+        self.log_access(LoggedAccess::Read(address));
+        Ok(self.take_access_error(AccessKind::Read, address)?)
+    }
+
+    /// Escreve um pedaço dentro de uma página, com uma tradução só. Os erros
+    /// são os de `write`.
+    fn write_slice(&mut self, address: usize, data: &[u8]) -> Result<(), MmuError> {
+        self.check_address(AccessKind::Write, address)?;
+        self.check_access(AccessKind::Write, address)?;
+
+        if let Some(start) = self.translate_for_write(address) {
+            self.memory[start..start + data.len()].copy_from_slice(data);
+        }
+
+        self.log_access(LoggedAccess::WriteSlice(address, data.to_vec()));
+        Ok(self.take_access_error(AccessKind::Write, address)?)
+    }
+
+    /// Preenche um pedaço dentro de uma página, com uma tradução só. Os
+    /// erros são os de `write`.
+    fn fill_slice(&mut self, address: usize, value: u8, len: usize) -> Result<(), MmuError> {
+        self.check_address(AccessKind::Write, address)?;
+        self.check_access(AccessKind::Write, address)?;
+
+        if let Some(start) = self.translate_for_write(address) {
+            self.memory[start..start + len].fill(value);
+        }
+
+        self.log_access(LoggedAccess::Fill(address, value, len));
+        Ok(self.take_access_error(AccessKind::Write, address)?)
+    }
+
+    /// Traduz o endereço para uma escrita e marca a página no registro de
+    /// sujeira, devolvendo o endereço físico, ou `None` se a página mapeia
+    /// a page table (que é somente leitura).
+    fn translate_for_write(&mut self, address: usize) -> Option<usize> {
+        let (frame_range, page_offset) = self.translate_addr(address, AccessKind::Write);
+
+        if self.is_page_table_frame(frame_range.start / (MEM_SIZE / FRAME_COUNT)) {
+            warn!(
+                "mmu: escrita em {:#06X} ignorada: a página mapeia a page table",
+                address
+            );
+            return None;
+        }
+
+        self.state.dirty_log.set((address & 0xFFFF) >> 8);
+
+        Some(frame_range.start + page_offset)
+    }
+
+    /// Lê os trechos, em ordem, e devolve seus bytes concatenados. Uma falha
+    /// interrompe a leitura no trecho em que acontece. Os erros são os de
+    /// `read`.
+    pub fn gather(&mut self, segments: &[IoSegment]) -> Result<Vec<u8>, MmuError> {
+        let mut data = vec![0; segments.iter().map(|segment| segment.len).sum()];
+        let mut offset = 0;
+
+        for segment in segments {
+            self.read_into(segment.address, &mut data[offset..offset + segment.len])?;
+            offset += segment.len;
+        }
+
+        Ok(data)
+    }
+
+    /// Distribui `data` pelos trechos, em ordem. O tamanho de `data` deve
+    /// ser a soma dos tamanhos dos trechos. Uma falha interrompe a escrita
+    /// no trecho em que acontece, com os anteriores já escritos. Os erros
+    /// são os de `write`.
+    pub fn scatter(&mut self, segments: &[IoSegment], data: &[u8]) -> Result<(), MmuError> {
+        assert_eq!(
+            data.len(),
+            segments.iter().map(|segment| segment.len).sum::<usize>(),
+            "os dados não têm o tamanho dos trechos"
+        );
+
+        let mut offset = 0;
+
+        for segment in segments {
+            self.write_from(segment.address, &data[offset..offset + segment.len])?;
+            offset += segment.len;
+        }
+
+        Ok(())
+    }
+}
+
+impl<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER,
+        LOADER,
+    > Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>
+where
+    REPLACER: PageReplacer + Clone,
+    LOADER: PageLoader,
+{
+    /// Habilita a viagem no tempo (veja `rewind`), tirando um snapshot a cada
+    /// `interval` acessos. Só é possível voltar até o momento em que a
+    /// viagem no tempo foi habilitada, e até o mais antigo dos últimos
+    /// `MAX_SNAPSHOTS` snapshots (veja o módulo `time_travel`).
+    pub fn enable_time_travel(&mut self, interval: usize) {
+        self.time_travel = Some(TimeTravel::new(interval, REPLACER::clone));
+
+        self.checkpoint();
+    }
+}
+
+impl<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    > Drop for Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>
+{
+    /// Escreve as páginas sujas no disco (veja `flush_all`), a não ser que a
+    /// Mmu já tenha sido desligada, ou que a thread esteja em pânico: no
+    /// meio de um acesso, a page table pode não estar consistente.
+    fn drop(&mut self) {
+        if self.loader.0.is_none() || std::thread::panicking() {
+            return;
+        }
+
+        match self.flush_all() {
+            Ok(0) => {}
+            Ok(flushed) => info!("mmu: {} páginas sujas escritas no disco", flushed),
+            Err(error) => warn!("mmu: páginas sujas perdidas: {}", error),
+        }
     }
 }
@@ -0,0 +1,183 @@
+//! Paginação aninhada: tradução em dois estágios, como num hypervisor.
+//!
+//! Numa máquina virtual, o sistema operacional convidado ("guest") tem sua
+//! própria page table, que traduz endereços virtuais do guest em endereços
+//! físicos do guest. Esses endereços, por sua vez, são virtuais para o
+//! hospedeiro ("host"), que os traduz com a sua page table (a "nested page
+//! table", EPT/NPT no hardware) para endereços físicos de verdade.
+//!
+//! `NestedMmu` compõe duas Mmus assim: cada acesso é traduzido pelo guest e o
+//! endereço físico resultante é traduzido pelo host. As duas podem ter page
+//! faults independentemente: o guest quando a página virtual não está em
+//! nenhum frame do guest, o host quando o frame do guest não está em nenhum
+//! frame do host.
+//!
+//! Os dados ficam na memória do guest. Como o host substitui páginas de forma
+//! transparente (o conteúdo volta igual quando a página é recarregada), a
+//! memória do host só importa para contar as faults, e não precisamos copiar
+//! os dados entre as duas.
+
+use crate::{
+    cost::CostModel,
+    error::MmuError,
+    mmu::{Mmu, MmuStats},
+    page_loader::PageLoader,
+    page_replacer::PageReplacer,
+    trace::AccessKind,
+};
+
+/// Algo que traduz endereços virtuais em físicos e tem uma memória física:
+/// uma Mmu, ou uma composição de Mmus.
+pub trait AddressSpace {
+    /// Traduz o endereço virtual no físico, fazendo tudo que um acesso faz
+    /// (contar o hit ou miss, tratar a page fault, marcar a página suja...).
+    fn translate(&mut self, address: usize, kind: AccessKind) -> Result<usize, MmuError>;
+
+    /// A memória física.
+    fn physical_memory(&mut self) -> &mut [u8];
+
+    /// As estatísticas das traduções.
+    fn stats(&self) -> &MmuStats;
+}
+
+impl<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    > AddressSpace for Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>
+{
+    fn translate(&mut self, address: usize, kind: AccessKind) -> Result<usize, MmuError> {
+        Mmu::translate(self, address, kind)
+    }
+
+    fn physical_memory(&mut self) -> &mut [u8] {
+        self.memory_mut()
+    }
+
+    fn stats(&self) -> &MmuStats {
+        &self.stats
+    }
+}
+
+/// As estatísticas combinadas de uma tradução em dois estágios.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NestedStats {
+    /// Número de acessos feitos pelo guest.
+    pub accesses: usize,
+    /// Page faults na page table do guest.
+    pub guest_faults: usize,
+    /// Page faults na page table do host (a nested page table).
+    pub host_faults: usize,
+}
+
+impl NestedStats {
+    /// Quantas faults, somando os dois estágios, cada acesso causou em
+    /// média. Um acesso pode causar até duas, então o valor pode passar de 1.
+    pub fn faults_per_access(&self) -> f64 {
+        if self.accesses == 0 {
+            return 0.0;
+        }
+
+        (self.guest_faults + self.host_faults) as f64 / self.accesses as f64
+    }
+
+    /// O tempo efetivo de acesso, em nanossegundos, com o walk em duas
+    /// dimensões (veja `nested_page_walk_time`) e as faults de cada estágio
+    /// tratadas com o modelo de custo do seu sistema.
+    pub fn effective_access_time(&self, guest: &CostModel, host: &CostModel) -> f64 {
+        if self.accesses == 0 {
+            return 0.0;
+        }
+
+        let guest_p = self.guest_faults as f64 / self.accesses as f64;
+        let host_p = self.host_faults as f64 / self.accesses as f64;
+
+        nested_page_walk_time(guest, host)
+            + guest.memory_access
+            + guest_p * guest.fault_service_time(false)
+            + host_p * host.fault_service_time(false)
+    }
+
+    pub fn print_stats(&self) {
+        println!("===== Estatísticas da tradução em dois estágios =====");
+        println!("Total de acessos: {}", self.accesses);
+        println!("  Faults do guest: {}", self.guest_faults);
+        println!("  Faults do host:  {}", self.host_faults);
+        println!("  Faults por acesso: {:.3}", self.faults_per_access());
+    }
+}
+
+/// O tempo do page walk em duas dimensões: cada uma das `g` entradas lidas
+/// pelo walk do guest é um endereço físico do guest, que precisa de um walk
+/// de `h` leituras no host, além do walk final do endereço de dados. Ao todo
+/// são `(g + 1) * (h + 1) - 1` leituras (24 com 4 níveis em cada estágio),
+/// sem cache de page walk.
+pub fn nested_page_walk_time(guest: &CostModel, host: &CostModel) -> f64 {
+    let g = guest.page_table_levels as f64;
+    let h = host.page_table_levels as f64;
+
+    ((g + 1.0) * (h + 1.0) - 1.0) * guest.memory_access
+}
+
+/// Uma Mmu de guest sobre uma Mmu de host: os endereços físicos do guest são
+/// os endereços virtuais do host.
+pub struct NestedMmu<G, H> {
+    guest: G,
+    host: H,
+}
+
+impl<G: AddressSpace, H: AddressSpace> NestedMmu<G, H> {
+    /// Compõe as duas Mmus. O espaço virtual do host deve cobrir toda a
+    /// memória física do guest.
+    pub fn new(guest: G, host: H) -> Self {
+        NestedMmu { guest, host }
+    }
+
+    /// A Mmu do guest.
+    pub fn guest(&self) -> &G {
+        &self.guest
+    }
+
+    /// A Mmu do host.
+    pub fn host(&self) -> &H {
+        &self.host
+    }
+
+    /// Traduz pelo guest e depois pelo host, devolvendo o endereço físico
+    /// do guest (onde o dado está guardado). Uma falha no guest não chega
+    /// ao host.
+    fn translate_nested(&mut self, address: usize, kind: AccessKind) -> Result<usize, MmuError> {
+        let guest_physical = self.guest.translate(address, kind)?;
+        self.host.translate(guest_physical, kind)?;
+
+        Ok(guest_physical)
+    }
+
+    /// Lê o byte no endereço virtual do guest. Os erros são os de
+    /// `Mmu::read`, de qualquer um dos estágios.
+    pub fn read(&mut self, address: usize) -> Result<u8, MmuError> {
+        let guest_physical = self.translate_nested(address, AccessKind::Read)?;
+
+        Ok(self.guest.physical_memory()[guest_physical])
+    }
+
+    /// Escreve o byte no endereço virtual do guest. Os erros são os de
+    /// `Mmu::write`, de qualquer um dos estágios.
+    pub fn write(&mut self, address: usize, value: u8) -> Result<(), MmuError> {
+        let guest_physical = self.translate_nested(address, AccessKind::Write)?;
+
+        self.guest.physical_memory()[guest_physical] = value;
+        Ok(())
+    }
+
+    /// As estatísticas combinadas dos dois estágios.
+    pub fn stats(&self) -> NestedStats {
+        NestedStats {
+            accesses: self.guest.stats().accesses(),
+            guest_faults: self.guest.stats().misses(),
+            host_faults: self.host.stats().misses(),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Swap em camadas: uma imagem base somente leitura com uma camada de
+//! alterações por cima, como um disco qcow2 com backing file.
+//!
+//! `OverlayPageLoader` lê as páginas da imagem base até que sejam escritas;
+//! a partir daí, a página passa a vir da camada de alterações (o "delta"),
+//! e a base nunca é alterada (copy-on-write). Assim vários experimentos
+//! podem partir da mesma imagem de swap intacta, sem copiá-la: no final de
+//! cada um, `discard` joga as alterações fora, ou `commit` as grava na base.
+//!
+//! ## Snapshots
+//!
+//! `snapshot("antes")` guarda o estado atual do swap com um nome, e
+//! `restore("antes")` volta a ele, quantas vezes for preciso: numa varredura
+//! de parâmetros, cada execução começa do mesmo conteúdo de disco. Dá para
+//! ramificar, restaurando um snapshot, alterando e tirando outro snapshot a
+//! partir dele. Um snapshot é só uma cópia do delta, então custa apenas as
+//! páginas alteradas em relação à base.
+
+use std::collections::{BTreeMap, HashMap};
+
+use thiserror::Error;
+
+use crate::{
+    error::LoaderError,
+    page_loader::{LoaderCapabilities, PageLoader},
+};
+
+/// Um carregador que guarda as escritas separadas de uma imagem base.
+pub struct OverlayPageLoader<B> {
+    base: B,
+    /// As páginas escritas desde o último `commit` ou `discard`.
+    delta: HashMap<usize, Vec<u8>>,
+    /// Os deltas guardados por `snapshot`, por nome.
+    snapshots: BTreeMap<String, HashMap<usize, Vec<u8>>>,
+}
+
+/// Erro ao restaurar um snapshot.
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum SnapshotError {
+    /// Nenhum snapshot foi guardado com esse nome.
+    #[error("snapshot desconhecido: {0}")]
+    Unknown(String),
+}
+
+impl<B: PageLoader> OverlayPageLoader<B> {
+    /// Coloca uma camada de alterações vazia sobre a imagem base.
+    pub fn new(base: B) -> Self {
+        OverlayPageLoader {
+            base,
+            delta: HashMap::new(),
+            snapshots: BTreeMap::new(),
+        }
+    }
+
+    /// A imagem base.
+    pub fn base(&self) -> &B {
+        &self.base
+    }
+
+    /// Se a página foi escrita na camada de alterações.
+    pub fn is_modified(&self, page_number: usize) -> bool {
+        self.delta.contains_key(&page_number)
+    }
+
+    /// Quantas páginas estão na camada de alterações.
+    pub fn delta_pages(&self) -> usize {
+        self.delta.len()
+    }
+
+    /// Grava as alterações na imagem base, em ordem de página, e esvazia a
+    /// camada. Devolve quantas páginas foram gravadas.
+    ///
+    /// Os snapshots continuam válidos: antes de sobrescrever uma página da
+    /// base, o conteúdo antigo é copiado para os snapshots que o usavam.
+    pub fn commit(&mut self) -> usize {
+        let mut pages: Vec<_> = self.delta.drain().collect();
+        pages.sort_by_key(|(page_number, _)| *page_number);
+
+        for (page_number, page) in &pages {
+            if self
+                .snapshots
+                .values()
+                .any(|snapshot| !snapshot.contains_key(page_number))
+            {
+                let mut old = vec![0; page.len()];
+                self.base.load_page_into(*page_number, &mut old);
+
+                for snapshot in self.snapshots.values_mut() {
+                    snapshot.entry(*page_number).or_insert_with(|| old.clone());
+                }
+            }
+
+            self.base.flush_page(*page_number, page);
+        }
+
+        pages.len()
+    }
+
+    /// Descarta as alterações, voltando ao conteúdo da imagem base. Devolve
+    /// quantas páginas foram descartadas.
+    pub fn discard(&mut self) -> usize {
+        let discarded = self.delta.len();
+        self.delta.clear();
+
+        discarded
+    }
+
+    /// Guarda o estado atual do swap com o nome dado, substituindo um
+    /// snapshot anterior com o mesmo nome.
+    pub fn snapshot(&mut self, name: &str) {
+        self.snapshots.insert(name.to_string(), self.delta.clone());
+    }
+
+    /// Volta o swap ao estado guardado no snapshot, descartando as
+    /// alterações feitas desde então. O snapshot continua guardado.
+    pub fn restore(&mut self, name: &str) -> Result<(), SnapshotError> {
+        let snapshot = self
+            .snapshots
+            .get(name)
+            .ok_or_else(|| SnapshotError::Unknown(name.to_string()))?;
+
+        self.delta = snapshot.clone();
+
+        Ok(())
+    }
+
+    /// Apaga o snapshot, devolvendo se ele existia.
+    pub fn delete_snapshot(&mut self, name: &str) -> bool {
+        self.snapshots.remove(name).is_some()
+    }
+
+    /// Os nomes dos snapshots guardados, em ordem.
+    pub fn snapshots(&self) -> impl Iterator<Item = &str> {
+        self.snapshots.keys().map(String::as_str)
+    }
+}
+
+impl<B: PageLoader> PageLoader for OverlayPageLoader<B> {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        match self.delta.get(&page_number) {
+            Some(page) => target.copy_from_slice(page),
+            None => self.base.load_page_into(page_number, target),
+        }
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        self.delta.insert(page_number, buffer.to_vec());
+    }
+
+    fn backend(&self) -> &str {
+        self.base.backend()
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.base.page_size()
+    }
+
+    // As escritas ficam no delta, em memória, e nunca chegam à base.
+    fn capabilities(&self) -> LoaderCapabilities {
+        LoaderCapabilities {
+            batch_flush: false,
+            asynchronous: true,
+            ..self.base.capabilities()
+        }
+    }
+
+    fn page_ref(&self, page_number: usize) -> Option<&[u8]> {
+        match self.delta.get(&page_number) {
+            Some(page) => Some(page),
+            None => self.base.page_ref(page_number),
+        }
+    }
+
+    // Só a leitura da base pode falhar; a escrita fica no delta.
+    fn try_load_page_into(
+        &mut self,
+        page_number: usize,
+        target: &mut [u8],
+    ) -> Result<(), LoaderError> {
+        match self.delta.get(&page_number) {
+            Some(page) => {
+                target.copy_from_slice(page);
+                Ok(())
+            }
+            None => self.base.try_load_page_into(page_number, target),
+        }
+    }
+}
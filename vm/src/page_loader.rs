@@ -1,3 +1,39 @@
+use std::{
+    collections::{hash_map::RandomState, BTreeMap, HashMap},
+    error::Error,
+    hash::BuildHasher,
+};
+
+use thiserror::Error;
+
+use crate::{error::LoaderError, fixtures, rng::SeededState};
+
+/// O que um carregador sabe fazer (veja `PageLoader::capabilities`). O
+/// padrão descreve o carregador mais simples: síncrono, uma página por vez,
+/// sem limite de páginas e de qualquer tamanho.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LoaderCapabilities {
+    /// Escrever várias páginas numa chamada só (veja
+    /// `PageLoader::try_flush_pages`) sai mais barato que uma por uma. A Mmu
+    /// então agrupa os writebacks que faz de uma vez: a saída de um
+    /// processo e as limpezas agendadas pelo replacer.
+    pub batch_flush: bool,
+    /// As escritas não seguram o acesso em andamento. Só então a Mmu faz as
+    /// limpezas em segundo plano agendadas pelo replacer (veja
+    /// `PageReplacer::scheduled_writebacks`); num carregador síncrono elas
+    /// custariam tanto quanto o writeback de uma vítima, e as páginas sujas
+    /// só são escritas quando saem.
+    pub asynchronous: bool,
+    /// As páginas nunca escritas são buracos, lidos como zeros, que não
+    /// ocupam espaço. A Mmu não depende disso.
+    pub sparse: bool,
+    /// Quantas páginas o carregador consegue guardar, se há um limite. A Mmu
+    /// confere que o seu espaço de endereçamento cabe nele.
+    pub max_pages: Option<usize>,
+    /// O tamanho das páginas do carregador (veja `PageLoader::page_size`).
+    pub page_size: Option<usize>,
+}
+
 /// Interface do carregador de páginas.
 pub trait PageLoader {
     /// Carrega uma página do disco em memória.
@@ -5,4 +41,311 @@ pub trait PageLoader {
 
     /// Faz o writeback de uma página de volta para o disco.
     fn flush_page(&mut self, page_number: usize, buffer: &[u8]);
+
+    /// O nome do carregador nas mensagens de erro (veja `LoaderError`).
+    fn backend(&self) -> &str {
+        "carregador"
+    }
+
+    /// O tamanho, em bytes, das páginas que o carregador guarda, se ele tem
+    /// um tamanho fixo (um swap file com o tamanho no header, por exemplo).
+    /// A Mmu confere que ele é o tamanho dos seus frames ao ser construída
+    /// (veja `Mmu::try_new`): com tamanhos diferentes, o conteúdo das
+    /// páginas ficaria cortado ou misturado entre os frames.
+    ///
+    /// `None` quer dizer que o carregador aceita páginas de qualquer
+    /// tamanho.
+    fn page_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// O que o carregador sabe fazer. A Mmu pergunta uma vez, ao ser
+    /// construída (veja `Mmu::try_new`), e liga ou desliga as suas
+    /// otimizações para este carregador.
+    ///
+    /// O padrão é o de `LoaderCapabilities::default`, com o tamanho de
+    /// `page_size`; quem o sobrescreve deve manter esse tamanho.
+    fn capabilities(&self) -> LoaderCapabilities {
+        LoaderCapabilities {
+            page_size: self.page_size(),
+            ..LoaderCapabilities::default()
+        }
+    }
+
+    /// O conteúdo de uma página sem cópia, para carregadores que já o têm
+    /// num buffer próprio (em memória ou num arquivo mapeado). A Mmu usa o
+    /// buffer para ler uma página que não está em memória sem alocar nem
+    /// copiar nada (veja `Mmu::peek_page_ref`), ou copia dele uma vez só.
+    ///
+    /// `None` quer dizer apenas que não há buffer para essa página: a Mmu
+    /// volta para `load_page_into`, que continua sendo o caminho obrigatório.
+    fn page_ref(&self, _page_number: usize) -> Option<&[u8]> {
+        None
+    }
+
+    /// Versão de `load_page_into` que pode falhar. A Mmu sempre chama esta;
+    /// um carregador que pode falhar (um arquivo, por exemplo) a implementa
+    /// e descreve a falha, em vez de causar um panic.
+    fn try_load_page_into(
+        &mut self,
+        page_number: usize,
+        target: &mut [u8],
+    ) -> Result<(), LoaderError> {
+        self.load_page_into(page_number, target);
+        Ok(())
+    }
+
+    /// Versão de `flush_page` que pode falhar (veja `try_load_page_into`).
+    fn try_flush_page(&mut self, page_number: usize, buffer: &[u8]) -> Result<(), LoaderError> {
+        self.flush_page(page_number, buffer);
+        Ok(())
+    }
+
+    /// Faz o writeback de várias páginas, cada uma com o seu buffer. A Mmu
+    /// só usa esta se o carregador anuncia `batch_flush` (veja
+    /// `capabilities`). O padrão escreve uma por uma, parando na primeira
+    /// falha.
+    fn try_flush_pages(&mut self, pages: &[(usize, &[u8])]) -> Result<(), LoaderError> {
+        for &(page_number, buffer) in pages {
+            self.try_flush_page(page_number, buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Permite usar um `Box<dyn PageLoader>` como carregador da Mmu, para
+/// escolher o carregador em tempo de execução (veja `LoaderRegistry`).
+impl<L: PageLoader + ?Sized> PageLoader for Box<L> {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        (**self).load_page_into(page_number, target);
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        (**self).flush_page(page_number, buffer);
+    }
+
+    fn backend(&self) -> &str {
+        (**self).backend()
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        (**self).page_size()
+    }
+
+    fn capabilities(&self) -> LoaderCapabilities {
+        (**self).capabilities()
+    }
+
+    fn page_ref(&self, page_number: usize) -> Option<&[u8]> {
+        (**self).page_ref(page_number)
+    }
+
+    fn try_load_page_into(
+        &mut self,
+        page_number: usize,
+        target: &mut [u8],
+    ) -> Result<(), LoaderError> {
+        (**self).try_load_page_into(page_number, target)
+    }
+
+    fn try_flush_page(&mut self, page_number: usize, buffer: &[u8]) -> Result<(), LoaderError> {
+        (**self).try_flush_page(page_number, buffer)
+    }
+
+    fn try_flush_pages(&mut self, pages: &[(usize, &[u8])]) -> Result<(), LoaderError> {
+        (**self).try_flush_pages(pages)
+    }
+}
+
+/// Um carregador que guarda as páginas em memória, num `HashMap`. Páginas que
+/// nunca foram escritas são carregadas zeradas.
+///
+/// Útil para testes e cenários que não devem depender (nem alterar) o arquivo
+/// swap.
+///
+/// Por padrão, o `HashMap` usa o hasher da biblioteca padrão, sorteado a cada
+/// execução. Nada do carregador depende da ordem do `HashMap` (`pages` itera
+/// em ordem de página), mas um teste que compara execuções byte a byte pode
+/// usar o hasher determinístico de `seeded` (veja `SeededState`).
+#[derive(Default)]
+pub struct MemoryPageLoader<S = RandomState> {
+    pages: HashMap<usize, Vec<u8>, S>,
+    /// O tamanho das páginas da imagem, se o carregador veio de uma (veja
+    /// `from_image`).
+    page_size: Option<usize>,
+}
+
+impl MemoryPageLoader {
+    /// Constrói um carregador vazio.
+    pub fn new() -> Self {
+        MemoryPageLoader {
+            pages: HashMap::new(),
+            page_size: None,
+        }
+    }
+}
+
+impl MemoryPageLoader<SeededState> {
+    /// Constrói um carregador vazio com o hasher determinístico derivado da
+    /// semente.
+    pub fn seeded(seed: u64) -> Self {
+        Self::with_hasher(SeededState::new(seed))
+    }
+}
+
+impl<S: BuildHasher> MemoryPageLoader<S> {
+    /// Constrói um carregador vazio com o hasher dado.
+    pub fn with_hasher(hasher: S) -> Self {
+        MemoryPageLoader {
+            pages: HashMap::with_hasher(hasher),
+            page_size: None,
+        }
+    }
+
+    /// Constrói um carregador com as páginas de uma imagem: a página `i` é o
+    /// trecho `i` de `page_size` bytes. Um último trecho incompleto é
+    /// completado com zeros. O carregador passa a ter páginas desse tamanho
+    /// (veja `PageLoader::page_size`).
+    pub fn from_image(image: &[u8], page_size: usize) -> Self
+    where
+        S: Default,
+    {
+        let pages = image
+            .chunks(page_size)
+            .enumerate()
+            .map(|(page_number, chunk)| {
+                let mut page = chunk.to_vec();
+                page.resize(page_size, 0);
+                (page_number, page)
+            })
+            .collect();
+
+        MemoryPageLoader {
+            pages,
+            page_size: Some(page_size),
+        }
+    }
+
+    /// O conteúdo guardado de uma página, se ela já foi escrita.
+    pub fn page(&self, page_number: usize) -> Option<&[u8]> {
+        self.pages.get(&page_number).map(Vec::as_slice)
+    }
+
+    /// As páginas guardadas, em ordem de página, qualquer que seja o hasher.
+    pub fn pages(&self) -> impl Iterator<Item = (usize, &[u8])> {
+        let mut page_numbers: Vec<_> = self.pages.keys().copied().collect();
+        page_numbers.sort_unstable();
+
+        page_numbers
+            .into_iter()
+            .map(|page_number| (page_number, self.pages[&page_number].as_slice()))
+    }
+}
+
+impl<S: BuildHasher> PageLoader for MemoryPageLoader<S> {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        match self.pages.get(&page_number) {
+            Some(page) => target.copy_from_slice(page),
+            None => target.fill(0),
+        }
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        self.pages.insert(page_number, buffer.to_vec());
+    }
+
+    fn backend(&self) -> &str {
+        "memory"
+    }
+
+    // Páginas nunca escritas não têm buffer: são zeradas por
+    // `load_page_into`.
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
+    // As escritas são cópias em memória, que não seguram o acesso.
+    fn capabilities(&self) -> LoaderCapabilities {
+        LoaderCapabilities {
+            asynchronous: true,
+            sparse: true,
+            page_size: self.page_size,
+            ..LoaderCapabilities::default()
+        }
+    }
+
+    fn page_ref(&self, page_number: usize) -> Option<&[u8]> {
+        self.page(page_number)
+    }
+}
+
+/// Uma função que constrói um carregador.
+pub type LoaderFactory = Box<dyn Fn() -> Result<Box<dyn PageLoader>, Box<dyn Error>>>;
+
+/// Erro ao construir um carregador pelo nome.
+#[derive(Debug, Error)]
+pub enum LoaderRegistryError {
+    /// Nenhum carregador foi registrado com esse nome.
+    #[error("carregador desconhecido: {0}")]
+    Unknown(String),
+    /// O carregador existe, mas não pôde ser construído.
+    #[error("erro ao construir o carregador '{0}': {1}")]
+    Failed(String, Box<dyn Error>),
+}
+
+/// Um registro de carregadores por nome, para que o carregador possa ser
+/// escolhido em tempo de execução (como o `--loader` da demo).
+pub struct LoaderRegistry {
+    factories: BTreeMap<String, LoaderFactory>,
+}
+
+impl LoaderRegistry {
+    /// Constrói um registro com os carregadores da crate: `memory` (veja
+    /// `MemoryPageLoader`) e `fixture:<nome>` para cada imagem de swap dos
+    /// fixtures (veja o módulo `fixtures`).
+    pub fn new() -> Self {
+        let mut registry = LoaderRegistry {
+            factories: BTreeMap::new(),
+        };
+
+        registry.register("memory", || Ok(Box::new(MemoryPageLoader::new())));
+
+        for name in fixtures::swap_image_names() {
+            registry.register(&format!("fixture:{}", name), move || {
+                Ok(Box::new(fixtures::swap_loader(name)?))
+            });
+        }
+
+        registry
+    }
+
+    /// Registra um carregador, substituindo outro com o mesmo nome.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Result<Box<dyn PageLoader>, Box<dyn Error>> + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// Constrói o carregador registrado com esse nome.
+    pub fn create(&self, name: &str) -> Result<Box<dyn PageLoader>, LoaderRegistryError> {
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or_else(|| LoaderRegistryError::Unknown(name.to_string()))?;
+
+        factory().map_err(|err| LoaderRegistryError::Failed(name.to_string(), err))
+    }
+
+    /// Os nomes registrados, em ordem.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+}
+
+impl Default for LoaderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -1,12 +1,71 @@
-use std::collections::VecDeque;
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt,
+};
+
+use thiserror::Error;
+
+use crate::{rng::Rng, workload::Access};
 
 /// Um evento de uma página, disparado pela Mmu.  O algoritmo replacer pode ou
 /// não usar esses eventos para seus cálculos.
+///
+/// Num acesso, a ordem é `Loaded` (se houve uma page fault), `Touched`,
+/// `Read` ou `Written` e, se a escrita sujou a página, `Dirtied`. Numa
+/// substituição, `FlushedDirty` (se a vítima estava suja) vem antes do
+/// `Evicted`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PageEvent {
     /// A página foi tocada (leitura ou escrita).
     Touched(usize),
+    /// A página foi lida. Vem logo depois do `Touched` da leitura.
+    Read(usize),
+    /// A página foi escrita. Vem logo depois do `Touched` da escrita, em
+    /// toda escrita, suja a página ou não.
+    Written(usize),
     /// A página foi carregada do disco.
     Loaded(usize),
+    /// A página foi desmapeada (liberada) e não está mais em memória. O
+    /// replacer não deve mais escolhê-la.
+    Unmapped(usize),
+    /// A página saiu da memória numa substituição: é a vítima escolhida por
+    /// `pick_replacement_page`, que o replacer já esqueceu, ou uma página
+    /// que dividia o frame com ela, que recebeu um `Unmapped` antes. O
+    /// conteúdo continua no disco, e a página pode voltar.
+    Evicted(usize),
+    /// A página foi escrita pela primeira vez desde que foi carregada: o
+    /// dirty bit foi ligado, e ela vai precisar de um writeback para sair.
+    /// Vem logo depois do `Written`.
+    Dirtied(usize),
+    /// A página suja foi escrita no disco. Se ela continua em memória (um
+    /// writeback agendado por `scheduled_writebacks`, por exemplo), agora
+    /// está limpa.
+    FlushedDirty(usize),
+}
+
+/// A vítima de uma substituição: a página escolhida pelo replacer e o frame
+/// que ela ocupa, que a Mmu vai reaproveitar.
+///
+/// O replacer só conhece páginas; quem sabe em que frame cada uma está é a
+/// page table da Mmu, que completa a escolha com o frame (e confere que a
+/// página escolhida está mesmo em memória).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Victim {
+    /// O número da página virtual que sai da memória.
+    pub page: usize,
+    /// O frame que ela ocupava.
+    pub frame: usize,
+}
+
+/// Erro ao configurar um parâmetro de um replacer.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConfigError {
+    /// O replacer não tem um parâmetro com esse nome.
+    #[error("parâmetro desconhecido: {0}")]
+    UnknownKey(String),
+    /// O valor não é válido para o parâmetro.
+    #[error("valor inválido para {key}: {value}")]
+    InvalidValue { key: String, value: f64 },
 }
 
 /// A interface do algoritmo de substituição de página.
@@ -14,12 +73,136 @@ pub trait PageReplacer {
     /// Avia ao replacer que houve um evento de página.
     fn page_event(&mut self, _event: PageEvent) {}
 
+    /// Avisa ao replacer que houve um tick do relógio (veja `Mmu::tick`).
+    /// Políticas que amostram os bits de referência periodicamente usam
+    /// isso.
+    fn tick(&mut self) {}
+
+    /// O tamanho do working set, para políticas que o calculam: quantas
+    /// páginas foram usadas dentro da janela da política. A Mmu registra o
+    /// valor depois de cada acesso (veja `MmuStats::working_set_sizes`).
+    fn working_set_size(&self) -> Option<usize> {
+        None
+    }
+
     /// Funcão principal da interface: escolhe uma página
     /// a ser substituída.
-    fn pick_replacement_page(&mut self) -> usize;
+    ///
+    /// O replacer lida só com números de página, nunca com frames: deve
+    /// devolver uma página em memória, isto é, que recebeu um `Loaded` e
+    /// ainda não recebeu `Unmapped` nem foi escolhida antes, e passa a
+    /// esquecê-la. A Mmu procura o frame da página na page table (veja
+    /// `Victim`); devolver uma página fora da memória é uma inconsistência,
+    /// e a Mmu recusa o acesso com `MmuError::PageTableInconsistency`.
+    ///
+    /// Páginas fixadas (veja `Mmu::pin_page`) também não podem ser
+    /// escolhidas. O replacer não precisa filtrá-las: ao fixar uma página, a
+    /// Mmu manda um `Unmapped`, e ao liberá-la, um `Loaded`, então um
+    /// replacer que só devolve páginas que conhece nunca escolhe uma fixada.
+    /// Escolher uma (um replacer que guarda páginas por conta própria, por
+    /// exemplo) também é.
+    ///
+    /// `None` quer dizer que nenhuma página pode sair: o replacer não tem
+    /// páginas (todas fixadas, por exemplo) ou um replacer próprio decidiu
+    /// que nenhuma das suas é substituível. A Mmu recusa o acesso que
+    /// precisava da vítima com `AccessFailure::NoEvictablePage`.
+    fn pick_replacement_page(&mut self) -> Option<usize>;
+
+    /// As páginas sujas que o replacer decidiu limpar durante a última
+    /// escolha, em vez de escolhê-las como vítimas. A Mmu pergunta depois de
+    /// cada `pick_replacement_page`, escreve essas páginas no disco e as
+    /// marca como limpas; elas continuam em memória.
+    fn scheduled_writebacks(&mut self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// Explica, em uma frase, por que a última página devolvida por
+    /// `pick_replacement_page` foi escolhida. Usado no log de auditoria.
+    fn victim_reason(&self) -> String {
+        "escolhida pelo replacer".to_string()
+    }
+
+    /// Os nomes dos parâmetros aceitos por `configure`.
+    fn parameters(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Ajusta um parâmetro do replacer pelo nome (por exemplo, o tamanho da
+    /// janela do working set). Todos os parâmetros são passados como `f64`,
+    /// para que varreduras de parâmetros possam ser automatizadas sem um
+    /// construtor específico para cada política.
+    fn configure(&mut self, key: &str, _value: f64) -> Result<(), ConfigError> {
+        Err(ConfigError::UnknownKey(key.to_string()))
+    }
+
+    /// Versão builder de `configure`: `FIFOPageReplacer::new().with("k", 2.0)?`.
+    fn with(mut self, key: &str, value: f64) -> Result<Self, ConfigError>
+    where
+        Self: Sized,
+    {
+        self.configure(key, value)?;
+        Ok(self)
+    }
+
+    /// Escreve o estado interno do replacer (filas, ponteiros, contadores) em
+    /// formato legível, para ser mostrado em logs e na demo.
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(sem estado)")
+    }
+}
+
+/// Adaptador que implementa `Display` chamando `PageReplacer::fmt_state`.
+pub struct ReplacerState<'a, R: PageReplacer + ?Sized>(pub &'a R);
+
+impl<R: PageReplacer + ?Sized> fmt::Display for ReplacerState<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_state(f)
+    }
+}
+
+/// Permite usar um `Box<dyn PageReplacer>` como replacer da Mmu, para
+/// trocar a política em tempo de execução por uma de outro tipo (veja
+/// `Mmu::replace_policy`).
+impl<R: PageReplacer + ?Sized> PageReplacer for Box<R> {
+    fn page_event(&mut self, event: PageEvent) {
+        (**self).page_event(event);
+    }
+
+    fn tick(&mut self) {
+        (**self).tick();
+    }
+
+    fn working_set_size(&self) -> Option<usize> {
+        (**self).working_set_size()
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        (**self).pick_replacement_page()
+    }
+
+    fn scheduled_writebacks(&mut self) -> Vec<usize> {
+        (**self).scheduled_writebacks()
+    }
+
+    fn victim_reason(&self) -> String {
+        (**self).victim_reason()
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        (**self).parameters()
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        (**self).configure(key, value)
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt_state(f)
+    }
 }
 
 /// Implementação do algoritmo FIFO de substituição.
+#[derive(Default, Clone)]
 pub struct FIFOPageReplacer {
     fifo: VecDeque<usize>,
 }
@@ -34,15 +217,2922 @@ impl FIFOPageReplacer {
 
 impl PageReplacer for FIFOPageReplacer {
     fn page_event(&mut self, event: PageEvent) {
-        if let PageEvent::Loaded(idx) = event {
+        match event {
             // Assim que a página foi carregada, a insira no fim da fila.
-            self.fifo.push_back(idx)
+            PageEvent::Loaded(idx) => self.fifo.push_back(idx),
+            // Uma página liberada sai da fila, onde quer que esteja.
+            PageEvent::Unmapped(idx) => self.fifo.retain(|&page| page != idx),
+            _ => {}
         }
     }
 
-    fn pick_replacement_page(&mut self) -> usize {
+    fn pick_replacement_page(&mut self) -> Option<usize> {
         // Pegue a página no começo da fila. Ela será a que foi carregada há
         // mais tempo.
-        self.fifo.pop_front().unwrap()
+        self.fifo.pop_front()
+    }
+
+    fn victim_reason(&self) -> String {
+        "a página carregada há mais tempo (início da fila)".to_string()
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A fila, da página mais antiga (próxima a sair) para a mais nova.
+        write!(f, "fifo: [")?;
+
+        for (i, page) in self.fifo.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#04X}", page)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação do algoritmo do relógio (segunda chance).
+///
+/// As páginas em memória ficam num buffer circular, na ordem em que foram
+/// carregadas, cada uma com um bit de referência, ligado a cada
+/// `PageEvent::Touched`. Para escolher uma vítima, o ponteiro percorre o
+/// buffer: uma página com o bit ligado ganha uma segunda chance (o bit é
+/// desligado e o ponteiro avança), e a primeira página com o bit desligado
+/// sai. É uma aproximação barata do LRU: uma página usada desde a última
+/// passagem do ponteiro não sai.
+///
+/// O bit de referência é uma cópia do bit `referenced` da page table, que a
+/// Mmu liga no mesmo acesso que dispara o `Touched`; o replacer desliga a
+/// sua cópia sem mexer na page table, cujo bit é do envelhecimento.
+#[derive(Default, Clone)]
+pub struct ClockPageReplacer {
+    /// As páginas e seus bits de referência.
+    pages: Vec<(usize, bool)>,
+    /// A posição do ponteiro: a próxima página a ser examinada.
+    hand: usize,
+    /// Quantas segundas chances a última escolha deu.
+    second_chances: usize,
+}
+
+impl ClockPageReplacer {
+    pub fn new() -> Self {
+        ClockPageReplacer {
+            pages: Vec::new(),
+            hand: 0,
+            second_chances: 0,
+        }
+    }
+}
+
+impl PageReplacer for ClockPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            // A página nova entra logo atrás do ponteiro, e é a última a
+            // ser examinada.
+            PageEvent::Loaded(idx) => {
+                self.pages.insert(self.hand, (idx, false));
+                self.hand += 1;
+            }
+            PageEvent::Touched(idx) => {
+                if let Some(page) = self.pages.iter_mut().find(|(page, _)| *page == idx) {
+                    page.1 = true;
+                }
+            }
+            PageEvent::Unmapped(idx) => {
+                if let Some(position) = self.pages.iter().position(|(page, _)| *page == idx) {
+                    self.pages.remove(position);
+
+                    if position < self.hand {
+                        self.hand -= 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        self.second_chances = 0;
+
+        if self.pages.is_empty() {
+            return None;
+        }
+
+        loop {
+            if self.hand >= self.pages.len() {
+                self.hand = 0;
+            }
+
+            let (page, referenced) = &mut self.pages[self.hand];
+
+            if !*referenced {
+                let page = *page;
+                self.pages.remove(self.hand);
+                return Some(page);
+            }
+
+            // Segunda chance: desliga o bit e passa para a próxima.
+            *referenced = false;
+            self.second_chances += 1;
+            self.hand += 1;
+        }
+    }
+
+    fn victim_reason(&self) -> String {
+        format!(
+            "a primeira página sem o bit de referência a partir do ponteiro ({} segundas chances)",
+            self.second_chances
+        )
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // O buffer circular, com `>` antes da página sob o ponteiro e `*`
+        // depois das páginas com o bit de referência ligado.
+        write!(f, "clock: [")?;
+
+        for (i, (page, referenced)) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            if i == self.hand % self.pages.len() {
+                write!(f, ">")?;
+            }
+
+            write!(f, "{:#04X}", page)?;
+
+            if *referenced {
+                write!(f, "*")?;
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// As contagens de uso das páginas em memória, com decaimento exponencial
+/// opcional, usadas pelo LFU e pelo MFU.
+///
+/// Sem decaimento, uma página que foi muito usada no começo da execução
+/// mantém a contagem alta para sempre, mesmo que não seja mais tocada. Com o
+/// parâmetro `decay`, cada acesso (a qualquer página) multiplica todas as
+/// contagens por `1 - decay`, então o peso de um uso cai com o tempo.
+///
+/// Multiplicar todas as contagens a cada acesso custaria caro; em vez disso,
+/// o valor de um novo uso cresce na mesma proporção, o que dá a mesma ordem
+/// entre as páginas. Quando esse valor fica grande demais, tudo é
+/// renormalizado.
+#[derive(Clone)]
+struct UseCounts {
+    /// A fração da contagem perdida a cada acesso.
+    decay: f64,
+    /// O valor de um uso agora, na escala das contagens guardadas.
+    increment: f64,
+    /// A contagem e a ordem de carga de cada página em memória.
+    pages: BTreeMap<usize, (f64, u64)>,
+    /// Quantas páginas já foram carregadas, para desempatar pela mais antiga.
+    loads: u64,
+    /// A contagem da última página escolhida, já na escala de um uso.
+    last_victim_count: f64,
+}
+
+/// Acima desse valor de um uso, as contagens são renormalizadas.
+const USE_COUNTS_RENORMALIZE: f64 = 1e100;
+
+impl UseCounts {
+    fn new() -> Self {
+        UseCounts {
+            decay: 0.0,
+            increment: 1.0,
+            pages: BTreeMap::new(),
+            loads: 0,
+            last_victim_count: 0.0,
+        }
+    }
+
+    fn frequency(&self, page_number: usize) -> Option<f64> {
+        self.pages
+            .get(&page_number)
+            .map(|(count, _)| count / self.increment)
+    }
+
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            // A página começa sem usos; o acesso que a carregou chega logo
+            // depois, como um `Touched`.
+            PageEvent::Loaded(idx) => {
+                self.pages.insert(idx, (0.0, self.loads));
+                self.loads += 1;
+            }
+            PageEvent::Touched(idx) => {
+                if self.decay > 0.0 {
+                    self.increment /= 1.0 - self.decay;
+
+                    if self.increment > USE_COUNTS_RENORMALIZE {
+                        for (count, _) in self.pages.values_mut() {
+                            *count /= self.increment;
+                        }
+                        self.increment = 1.0;
+                    }
+                }
+
+                if let Some((count, _)) = self.pages.get_mut(&idx) {
+                    *count += self.increment;
+                }
+            }
+            PageEvent::Unmapped(idx) => {
+                self.pages.remove(&idx);
+            }
+            _ => {}
+        }
+    }
+
+    /// Tira e devolve a página com a menor contagem e, no empate, a
+    /// carregada há mais tempo. Com `most`, a ordem é invertida: sai a de
+    /// maior contagem e, no empate, a carregada há menos tempo.
+    fn pick(&mut self, most: bool) -> Option<usize> {
+        let (&page, &(count, _)) = self.pages.iter().min_by(|(_, a), (_, b)| {
+            let order = a.0.total_cmp(&b.0).then(a.1.cmp(&b.1));
+
+            if most {
+                order.reverse()
+            } else {
+                order
+            }
+        })?;
+
+        self.pages.remove(&page);
+        self.last_victim_count = count / self.increment;
+
+        Some(page)
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "decay" if (0.0..1.0).contains(&value) => {
+                self.decay = value;
+                Ok(())
+            }
+            "decay" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+        // As páginas em ordem de page number, com as contagens em usos.
+        write!(f, "{} (decay {}): [", name, self.decay)?;
+
+        for (i, (page, (count, _))) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#04X}: {:.2}", page, count / self.increment)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação do algoritmo LFU (a página usada menos vezes sai), com
+/// decaimento exponencial opcional das contagens (o parâmetro `decay`).
+///
+/// Sem decaimento, uma página muito usada no começo da execução nunca mais
+/// sai; com ele, uma página quente que esfriou acaba podendo sair.
+#[derive(Clone)]
+pub struct LFUPageReplacer {
+    counts: UseCounts,
+}
+
+impl LFUPageReplacer {
+    pub fn new() -> Self {
+        LFUPageReplacer {
+            counts: UseCounts::new(),
+        }
+    }
+
+    /// A contagem da página em número de usos, já com o decaimento.
+    pub fn frequency(&self, page_number: usize) -> Option<f64> {
+        self.counts.frequency(page_number)
+    }
+}
+
+impl Default for LFUPageReplacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageReplacer for LFUPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        self.counts.page_event(event);
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        self.counts.pick(false)
+    }
+
+    fn victim_reason(&self) -> String {
+        format!(
+            "a página usada menos vezes ({:.2} usos)",
+            self.counts.last_victim_count
+        )
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["decay"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        self.counts.configure(key, value)
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.counts.fmt_state(f, "lfu")
+    }
+}
+
+/// Implementação do algoritmo MFU (a página usada mais vezes sai), com as
+/// mesmas contagens do LFU, inclusive o decaimento.
+///
+/// A ideia por trás dele é que uma página com poucos usos acabou de ser
+/// carregada e ainda vai ser usada. Quase sempre é uma heurística ruim, mas
+/// num laço maior que a memória ela acerta: entre páginas com a mesma
+/// contagem sai a carregada por último, que é a que acabou de passar e só
+/// volta depois de todas as outras.
+#[derive(Clone)]
+pub struct MFUPageReplacer {
+    counts: UseCounts,
+}
+
+impl MFUPageReplacer {
+    pub fn new() -> Self {
+        MFUPageReplacer {
+            counts: UseCounts::new(),
+        }
+    }
+
+    /// A contagem da página em número de usos, já com o decaimento.
+    pub fn frequency(&self, page_number: usize) -> Option<f64> {
+        self.counts.frequency(page_number)
+    }
+}
+
+impl Default for MFUPageReplacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageReplacer for MFUPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        self.counts.page_event(event);
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        self.counts.pick(true)
+    }
+
+    fn victim_reason(&self) -> String {
+        format!(
+            "a página usada mais vezes ({:.2} usos)",
+            self.counts.last_victim_count
+        )
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["decay"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        self.counts.configure(key, value)
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.counts.fmt_state(f, "mfu")
+    }
+}
+
+/// Implementação do LRU exato: a página usada há mais tempo sai.
+///
+/// Num sistema de verdade, o LRU exato precisaria atualizar uma lista (ou um
+/// timestamp) a cada acesso à memória, o que é caro demais; aqui ele serve
+/// de referência para as aproximações, como o relógio e o
+/// `KRefClockReplacer`.
+#[derive(Default, Clone)]
+pub struct LRUPageReplacer {
+    /// As páginas, da usada há mais tempo para a mais recente.
+    order: VecDeque<usize>,
+}
+
+impl LRUPageReplacer {
+    pub fn new() -> Self {
+        LRUPageReplacer {
+            order: VecDeque::new(),
+        }
+    }
+}
+
+impl PageReplacer for LRUPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => self.order.push_back(idx),
+            // Uma página usada vai para o fim da fila.
+            PageEvent::Touched(idx) => {
+                if let Some(position) = self.order.iter().position(|&page| page == idx) {
+                    self.order.remove(position);
+                    self.order.push_back(idx);
+                }
+            }
+            PageEvent::Unmapped(idx) => self.order.retain(|&page| page != idx),
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        self.order.pop_front()
+    }
+
+    fn victim_reason(&self) -> String {
+        "a página usada há mais tempo (início da fila)".to_string()
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A fila, da página usada há mais tempo para a mais recente.
+        write!(f, "lru: [")?;
+
+        for (i, page) in self.order.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#04X}", page)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação do MRU: a página usada mais recentemente sai.
+///
+/// Parece o contrário do que se quer, mas é o que o ótimo faz num laço maior
+/// que a memória: a página que acabou de ser usada é a que vai demorar mais
+/// para ser usada de novo. Onde o LRU erra todos os acessos do laço, o MRU
+/// guarda quase toda a memória cheia de páginas do laço e erra só as que
+/// não cabem, como nos exemplos dos livros-texto. Em cargas com localidade,
+/// é das piores políticas.
+#[derive(Default, Clone)]
+pub struct MRUPageReplacer {
+    /// As páginas, da usada há mais tempo para a mais recente.
+    order: VecDeque<usize>,
+}
+
+impl MRUPageReplacer {
+    pub fn new() -> Self {
+        MRUPageReplacer {
+            order: VecDeque::new(),
+        }
+    }
+}
+
+impl PageReplacer for MRUPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => self.order.push_back(idx),
+            // Uma página usada vai para o fim da fila.
+            PageEvent::Touched(idx) => {
+                if let Some(position) = self.order.iter().position(|&page| page == idx) {
+                    self.order.remove(position);
+                    self.order.push_back(idx);
+                }
+            }
+            PageEvent::Unmapped(idx) => self.order.retain(|&page| page != idx),
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        self.order.pop_back()
+    }
+
+    fn victim_reason(&self) -> String {
+        "a página usada mais recentemente (fim da fila)".to_string()
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A fila, da página usada há mais tempo para a mais recente (a
+        // próxima a sair).
+        write!(f, "mru: [")?;
+
+        for (i, page) in self.order.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#04X}", page)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Uma página no buffer circular do `KRefClockReplacer`.
+#[derive(Copy, Clone)]
+struct KRefPage {
+    page_number: usize,
+    /// O bit de referência desde o último tick.
+    referenced: bool,
+    /// Os bits de referência dos últimos `k` ticks, o mais recente no bit
+    /// mais significativo.
+    history: u32,
+}
+
+/// Implementação de um relógio com `k` bits de referência: uma aproximação
+/// do LRU que só custa `k + 1` bits por página e um deslocamento por página
+/// a cada tick.
+///
+/// Como no relógio, cada `PageEvent::Touched` liga o bit de referência da
+/// página. A cada tick, o bit entra no histórico de `k` bits (como no
+/// algoritmo Aging da page table) e é desligado. A vítima é a página com o
+/// menor valor de bit atual e histórico, lidos como um número: a que não foi
+/// usada há mais ticks. Entre páginas empatadas, sai a primeira a partir do
+/// ponteiro, como no relógio.
+///
+/// Com `k = 0`, só o bit atual conta, como no relógio (mas o bit só é
+/// desligado nos ticks); quanto maior `k`, mais a ordem se aproxima do LRU,
+/// que distinguiria também acessos dentro do mesmo tick.
+#[derive(Clone)]
+pub struct KRefClockReplacer {
+    /// O número de bits do histórico.
+    k: u32,
+    pages: Vec<KRefPage>,
+    /// A posição do ponteiro: a primeira página examinada no empate.
+    hand: usize,
+    /// O bit atual e o histórico da última página escolhida.
+    last_victim: (bool, u32),
+}
+
+impl KRefClockReplacer {
+    /// O maior número de bits de histórico.
+    pub const MAX_BITS: u32 = 32;
+
+    /// Constrói um relógio com `k` bits de histórico, no máximo `MAX_BITS`.
+    pub fn new(k: u32) -> Self {
+        KRefClockReplacer {
+            k: k.min(Self::MAX_BITS),
+            pages: Vec::new(),
+            hand: 0,
+            last_victim: (false, 0),
+        }
+    }
+
+    /// O número de bits do histórico.
+    pub fn bits(&self) -> u32 {
+        self.k
+    }
+
+    /// Os bits de estado guardados por página: o histórico e o bit atual.
+    pub fn bits_per_page(&self) -> u32 {
+        self.k + 1
+    }
+
+    /// A chave de escolha da vítima: a menor sai.
+    fn key(&self, page: &KRefPage) -> u64 {
+        ((page.referenced as u64) << self.k) | page.history as u64
+    }
+}
+
+impl PageReplacer for KRefClockReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            // A página nova entra logo atrás do ponteiro, como no relógio.
+            PageEvent::Loaded(idx) => {
+                self.pages.insert(
+                    self.hand,
+                    KRefPage {
+                        page_number: idx,
+                        referenced: false,
+                        history: 0,
+                    },
+                );
+                self.hand += 1;
+            }
+            PageEvent::Touched(idx) => {
+                if let Some(page) = self.pages.iter_mut().find(|page| page.page_number == idx) {
+                    page.referenced = true;
+                }
+            }
+            PageEvent::Unmapped(idx) => {
+                if let Some(position) = self.pages.iter().position(|page| page.page_number == idx) {
+                    self.pages.remove(position);
+
+                    if position < self.hand {
+                        self.hand -= 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self) {
+        for page in &mut self.pages {
+            page.history = match self.k {
+                0 => 0,
+                k => (page.history >> 1) | ((page.referenced as u32) << (k - 1)),
+            };
+            page.referenced = false;
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        let len = self.pages.len();
+
+        // A menor chave, examinando a partir do ponteiro; `min_by_key`
+        // devolve a primeira das empatadas.
+        let position = (0..len)
+            .map(|i| (self.hand + i) % len)
+            .min_by_key(|&position| self.key(&self.pages[position]))?;
+
+        let page = self.pages.remove(position);
+        self.last_victim = (page.referenced, page.history);
+        self.hand = position;
+
+        Some(page.page_number)
+    }
+
+    fn victim_reason(&self) -> String {
+        let (referenced, history) = self.last_victim;
+
+        format!(
+            "a página usada há mais ticks (bit {}, histórico {:0width$b})",
+            referenced as u8,
+            history,
+            width = self.k as usize
+        )
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["k"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "k" if value.fract() == 0.0 && (0.0..=Self::MAX_BITS as f64).contains(&value) => {
+                // O histórico antigo é descartado, pois foi amostrado com
+                // outro número de bits.
+                self.k = value as u32;
+                for page in &mut self.pages {
+                    page.history = 0;
+                }
+                Ok(())
+            }
+            "k" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // O buffer circular, com `>` antes da página sob o ponteiro e, para
+        // cada página, o bit atual e o histórico.
+        write!(f, "clock-{}: [", self.k)?;
+
+        for (i, page) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            if i == self.hand % self.pages.len() {
+                write!(f, ">")?;
+            }
+
+            write!(
+                f,
+                "{:#04X} {}|{:0width$b}",
+                page.page_number,
+                page.referenced as u8,
+                page.history,
+                width = self.k as usize
+            )?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação do S3-FIFO: três filas FIFO, uma pequena de "probatório",
+/// uma principal e uma fila fantasma.
+///
+/// Toda página nova entra na fila pequena. Quando ela chega ao fim da fila,
+/// só vai para a principal se foi usada de novo depois de carregada; senão
+/// sai da memória, e o seu número fica guardado na fila fantasma. Uma
+/// página carregada de novo enquanto está na fantasma foi tirada cedo demais,
+/// e entra direto na principal. Assim, páginas usadas uma vez só (uma
+/// varredura) passam só pela fila pequena, sem tirar as páginas quentes da
+/// principal.
+///
+/// A fila principal é um FIFO com reinserção: cada página tem um contador
+/// de usos de 0 a 3, e uma página no fim da fila com o contador positivo
+/// volta para o começo com ele decrementado, em vez de sair.
+///
+/// O replacer não sabe quantos frames a Mmu tem; o tamanho da fila pequena é
+/// uma fração (o parâmetro `small`, 10% por padrão) das páginas em memória,
+/// e a fantasma guarda tantas páginas quanto a principal.
+#[derive(Clone)]
+pub struct S3FIFOPageReplacer {
+    /// A fração das páginas em memória reservada para a fila pequena.
+    small_fraction: f64,
+    /// A fila pequena: páginas e contadores de uso, da mais antiga para a
+    /// mais nova.
+    small: VecDeque<(usize, u8)>,
+    /// A fila principal.
+    main: VecDeque<(usize, u8)>,
+    /// As páginas tiradas da memória pela fila pequena, da mais antiga para
+    /// a mais nova.
+    ghost: VecDeque<usize>,
+    /// Se a última página escolhida saiu da fila pequena.
+    last_from_small: bool,
+}
+
+/// O maior valor do contador de usos do S3-FIFO.
+const S3FIFO_MAX_FREQUENCY: u8 = 3;
+
+impl S3FIFOPageReplacer {
+    pub fn new() -> Self {
+        S3FIFOPageReplacer {
+            small_fraction: 0.1,
+            small: VecDeque::new(),
+            main: VecDeque::new(),
+            ghost: VecDeque::new(),
+            last_from_small: false,
+        }
+    }
+
+    /// O tamanho da fila pequena a partir do qual ela perde páginas.
+    fn small_target(&self) -> usize {
+        (((self.small.len() + self.main.len()) as f64 * self.small_fraction) as usize).max(1)
+    }
+
+    /// Tira páginas da fila pequena até achar uma para sair; as usadas de
+    /// novo vão para a principal. Devolve `None` se a fila esvaziou.
+    fn evict_small(&mut self) -> Option<usize> {
+        while let Some((page, frequency)) = self.small.pop_front() {
+            // O acesso que carregou a página já conta um uso.
+            if frequency > 1 {
+                self.main.push_back((page, 0));
+                continue;
+            }
+
+            self.ghost.push_back(page);
+            while self.ghost.len() > self.main.len().max(1) {
+                self.ghost.pop_front();
+            }
+
+            return Some(page);
+        }
+
+        None
+    }
+
+    /// Tira a primeira página da fila principal com o contador zerado,
+    /// reinserindo as outras.
+    fn evict_main(&mut self) -> Option<usize> {
+        while let Some((page, frequency)) = self.main.pop_front() {
+            if frequency == 0 {
+                return Some(page);
+            }
+
+            self.main.push_back((page, frequency - 1));
+        }
+
+        None
+    }
+}
+
+impl Default for S3FIFOPageReplacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageReplacer for S3FIFOPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => match self.ghost.iter().position(|&page| page == idx) {
+                Some(position) => {
+                    self.ghost.remove(position);
+                    self.main.push_back((idx, 0));
+                }
+                None => self.small.push_back((idx, 0)),
+            },
+            PageEvent::Touched(idx) => {
+                if let Some((_, frequency)) = self
+                    .small
+                    .iter_mut()
+                    .chain(self.main.iter_mut())
+                    .find(|(page, _)| *page == idx)
+                {
+                    *frequency = (*frequency + 1).min(S3FIFO_MAX_FREQUENCY);
+                }
+            }
+            PageEvent::Unmapped(idx) => {
+                self.small.retain(|&(page, _)| page != idx);
+                self.main.retain(|&(page, _)| page != idx);
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        if self.small.len() >= self.small_target() || self.main.is_empty() {
+            if let Some(page) = self.evict_small() {
+                self.last_from_small = true;
+                return Some(page);
+            }
+        }
+
+        // A fila pequena mandou todas as suas páginas para a principal, ou
+        // ainda não estava cheia.
+        self.last_from_small = false;
+        self.evict_main()
+    }
+
+    fn victim_reason(&self) -> String {
+        if self.last_from_small {
+            "a página mais antiga da fila pequena, sem usos depois de carregada".to_string()
+        } else {
+            "a primeira página da fila principal sem usos desde a última passagem".to_string()
+        }
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["small"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "small" if value > 0.0 && value < 1.0 => {
+                self.small_fraction = value;
+                Ok(())
+            }
+            "small" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // As três filas, da página mais antiga para a mais nova, com o
+        // contador de usos de cada página em memória.
+        let queue = |f: &mut fmt::Formatter<'_>, pages: &VecDeque<(usize, u8)>| {
+            for (i, (page, frequency)) in pages.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+
+                write!(f, "{:#04X}: {}", page, frequency)?;
+            }
+
+            Ok(())
+        };
+
+        write!(f, "s3-fifo: pequena [")?;
+        queue(f, &self.small)?;
+        write!(f, "], principal [")?;
+        queue(f, &self.main)?;
+        write!(f, "], fantasma [")?;
+
+        for (i, page) in self.ghost.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#04X}", page)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação do 2Q, de Johnson e Shasha: uma fila FIFO para as páginas
+/// novas (A1in), uma fila fantasma para as que saíram dela (A1out) e um LRU
+/// para as páginas reusadas (Am).
+///
+/// Toda página nova entra na A1in, e os usos dela ali não contam: são os
+/// acessos correlacionados logo depois da carga. Quando a A1in passa do
+/// tamanho alvo, a página mais antiga sai da memória e o seu número fica na
+/// A1out. Uma página carregada de novo enquanto está na A1out foi reusada
+/// depois de um intervalo, e entra no Am, que é um LRU comum. Assim, uma
+/// varredura sequencial passa só pela A1in e pela A1out, sem tirar as
+/// páginas quentes do Am, ao contrário do LRU, que as tira.
+///
+/// Como no S3-FIFO, o replacer não sabe quantos frames a Mmu tem: o tamanho
+/// alvo da A1in (o parâmetro `kin`, 25% por padrão) e o da A1out (`kout`,
+/// 50% por padrão) são frações das páginas em memória, como no artigo.
+#[derive(Clone)]
+pub struct TwoQPageReplacer {
+    /// A fração das páginas em memória reservada para a A1in.
+    kin: f64,
+    /// O tamanho da A1out, como fração das páginas em memória.
+    kout: f64,
+    /// A A1in, da página mais antiga para a mais nova.
+    a1in: VecDeque<usize>,
+    /// A A1out: as páginas que saíram da A1in, da mais antiga para a mais
+    /// nova.
+    a1out: VecDeque<usize>,
+    /// O Am, da página usada há mais tempo para a mais recente.
+    am: VecDeque<usize>,
+    /// Se a última página escolhida saiu da A1in.
+    last_from_a1in: bool,
+}
+
+impl TwoQPageReplacer {
+    pub fn new() -> Self {
+        TwoQPageReplacer {
+            kin: 0.25,
+            kout: 0.5,
+            a1in: VecDeque::new(),
+            a1out: VecDeque::new(),
+            am: VecDeque::new(),
+            last_from_a1in: false,
+        }
+    }
+
+    /// As páginas em memória.
+    fn resident(&self) -> usize {
+        self.a1in.len() + self.am.len()
+    }
+
+    /// O tamanho alvo da A1in.
+    fn a1in_target(&self) -> usize {
+        ((self.resident() as f64 * self.kin) as usize).max(1)
+    }
+
+    /// O tamanho máximo da A1out.
+    fn a1out_capacity(&self) -> usize {
+        ((self.resident() as f64 * self.kout) as usize).max(1)
+    }
+}
+
+impl Default for TwoQPageReplacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageReplacer for TwoQPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => match self.a1out.iter().position(|&page| page == idx) {
+                Some(position) => {
+                    self.a1out.remove(position);
+                    self.am.push_back(idx);
+                }
+                None => self.a1in.push_back(idx),
+            },
+            // Só os usos no Am contam; na A1in, eles são correlacionados com
+            // a carga.
+            PageEvent::Touched(idx) => {
+                if let Some(position) = self.am.iter().position(|&page| page == idx) {
+                    self.am.remove(position);
+                    self.am.push_back(idx);
+                }
+            }
+            PageEvent::Unmapped(idx) => {
+                self.a1in.retain(|&page| page != idx);
+                self.am.retain(|&page| page != idx);
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        if self.a1in.len() > self.a1in_target() || self.am.is_empty() {
+            if let Some(page) = self.a1in.pop_front() {
+                self.a1out.push_back(page);
+                while self.a1out.len() > self.a1out_capacity() {
+                    self.a1out.pop_front();
+                }
+
+                self.last_from_a1in = true;
+                return Some(page);
+            }
+        }
+
+        self.last_from_a1in = false;
+        self.am.pop_front()
+    }
+
+    fn victim_reason(&self) -> String {
+        if self.last_from_a1in {
+            "a página mais antiga da A1in, que passou do tamanho alvo".to_string()
+        } else {
+            "a página usada há mais tempo no Am".to_string()
+        }
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["kin", "kout"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "kin" if value > 0.0 && value < 1.0 => {
+                self.kin = value;
+                Ok(())
+            }
+            "kout" if value > 0.0 => {
+                self.kout = value;
+                Ok(())
+            }
+            "kin" | "kout" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // As três filas, da página mais antiga para a mais nova.
+        let queue = |f: &mut fmt::Formatter<'_>, pages: &VecDeque<usize>| {
+            for (i, page) in pages.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+
+                write!(f, "{:#04X}", page)?;
+            }
+
+            Ok(())
+        };
+
+        write!(f, "2q: a1in [")?;
+        queue(f, &self.a1in)?;
+        write!(f, "], a1out [")?;
+        queue(f, &self.a1out)?;
+        write!(f, "], am [")?;
+        queue(f, &self.am)?;
+        write!(f, "]")
+    }
+}
+
+/// Implementação do LRU segmentado (SLRU): um LRU dividido em dois
+/// segmentos, o de prova e o protegido.
+///
+/// Toda página carregada entra no segmento de prova. Se for usada de novo
+/// enquanto está lá, ela é promovida ao segmento protegido; quando o
+/// protegido passa da sua capacidade, a página usada há mais tempo nele
+/// volta para o de prova, onde ganha mais uma chance. A vítima é a página
+/// usada há mais tempo no segmento de prova, e só sai do protegido se o de
+/// prova está vazio. Assim, uma página usada uma vez só nunca tira uma
+/// página reusada da memória.
+///
+/// O acesso que carregou a página não conta como reuso. A capacidade do
+/// segmento protegido (o parâmetro `protected`, 80% por padrão) é uma
+/// fração das páginas em memória, como no 2Q: um protegido grande guarda
+/// mais páginas quentes, mas deixa pouco tempo para uma página nova provar
+/// que é quente antes de sair.
+#[derive(Clone)]
+pub struct SLRUPageReplacer {
+    /// A fração das páginas em memória reservada para o segmento protegido.
+    protected_share: f64,
+    /// O segmento de prova, da página usada há mais tempo para a mais
+    /// recente.
+    probation: VecDeque<usize>,
+    /// O segmento protegido, na mesma ordem.
+    protected: VecDeque<usize>,
+    /// A página que acabou de ser carregada, cujo próximo uso é o do próprio
+    /// acesso que a carregou.
+    just_loaded: Option<usize>,
+    /// Se a última página escolhida saiu do segmento protegido.
+    last_from_protected: bool,
+}
+
+impl SLRUPageReplacer {
+    pub fn new() -> Self {
+        SLRUPageReplacer {
+            protected_share: 0.8,
+            probation: VecDeque::new(),
+            protected: VecDeque::new(),
+            just_loaded: None,
+            last_from_protected: false,
+        }
+    }
+
+    /// A capacidade do segmento protegido.
+    fn protected_capacity(&self) -> usize {
+        let resident = self.probation.len() + self.protected.len();
+
+        ((resident as f64 * self.protected_share) as usize).max(1)
+    }
+}
+
+impl Default for SLRUPageReplacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageReplacer for SLRUPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => {
+                self.probation.push_back(idx);
+                self.just_loaded = Some(idx);
+            }
+            PageEvent::Touched(idx) => {
+                if self.just_loaded.take() == Some(idx) {
+                    return;
+                }
+
+                if let Some(position) = self.protected.iter().position(|&page| page == idx) {
+                    self.protected.remove(position);
+                    self.protected.push_back(idx);
+                } else if let Some(position) = self.probation.iter().position(|&page| page == idx) {
+                    self.probation.remove(position);
+                    self.protected.push_back(idx);
+
+                    while self.protected.len() > self.protected_capacity() {
+                        let page = self.protected.pop_front().unwrap();
+                        self.probation.push_back(page);
+                    }
+                }
+            }
+            PageEvent::Unmapped(idx) => {
+                self.probation.retain(|&page| page != idx);
+                self.protected.retain(|&page| page != idx);
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        if let Some(page) = self.probation.pop_front() {
+            self.last_from_protected = false;
+            return Some(page);
+        }
+
+        self.last_from_protected = true;
+        self.protected.pop_front()
+    }
+
+    fn victim_reason(&self) -> String {
+        if self.last_from_protected {
+            "a página usada há mais tempo no segmento protegido, com o de prova vazio".to_string()
+        } else {
+            "a página usada há mais tempo no segmento de prova".to_string()
+        }
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["protected"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "protected" if value > 0.0 && value < 1.0 => {
+                self.protected_share = value;
+                Ok(())
+            }
+            "protected" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Os dois segmentos, da página usada há mais tempo para a mais
+        // recente.
+        let segment = |f: &mut fmt::Formatter<'_>, pages: &VecDeque<usize>| {
+            for (i, page) in pages.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+
+                write!(f, "{:#04X}", page)?;
+            }
+
+            Ok(())
+        };
+
+        write!(f, "slru: prova [")?;
+        segment(f, &self.probation)?;
+        write!(f, "], protegido [")?;
+        segment(f, &self.protected)?;
+        write!(f, "]")
+    }
+}
+
+/// Implementação do LRU-K, de O'Neil, O'Neil e Weikum: sai a página cujo
+/// K-ésimo uso mais recente é o mais antigo.
+///
+/// O LRU olha só o último uso, então uma página usada uma vez numa
+/// varredura parece tão quente quanto uma usada sempre. O LRU-K guarda os
+/// tempos (em acessos) dos K últimos usos de cada página e compara o
+/// K-ésimo: uma página com menos de K usos tem distância infinita e sai
+/// primeiro, da usada há mais tempo para a mais recente. Com K = 1, é o
+/// LRU.
+///
+/// Usos muito próximos (um programa lendo vários bytes da mesma página, por
+/// exemplo) não dizem nada sobre a frequência. Um uso até `crp` acessos
+/// depois do anterior (o *correlated reference period*) é correlacionado
+/// com ele: só atualiza o último uso, sem entrar no histórico, e a página
+/// não pode ser escolhida até o período passar (a não ser que todas estejam
+/// nele). Quando um uso não correlacionado chega, o histórico anda, com os
+/// tempos anteriores adiantados pela duração do período correlacionado que
+/// acabou, como no artigo. O padrão é `crp` 0: todo uso conta.
+///
+/// O histórico de uma página que saiu da memória é guardado por `rip`
+/// acessos (o *retained information period*, 1024 por padrão), para que ela
+/// volte com os seus usos anteriores em vez de parecer nova.
+#[derive(Clone)]
+pub struct LRUKPageReplacer {
+    k: usize,
+    /// O período, em acessos, no qual um novo uso é correlacionado.
+    crp: usize,
+    /// Por quantos acessos o histórico de uma página fora da memória fica.
+    rip: usize,
+    /// Quantos acessos o replacer viu.
+    now: usize,
+    /// Os tempos dos K últimos usos não correlacionados (o mais recente
+    /// primeiro, 0 se não houve) e do último uso de cada página, em memória
+    /// ou guardada.
+    history: BTreeMap<usize, (Vec<usize>, usize)>,
+    /// As páginas em memória.
+    resident: BTreeSet<usize>,
+    /// Há quantos acessos foi o K-ésimo uso da última vítima, se ela tinha
+    /// K usos.
+    last_victim_distance: Option<usize>,
+}
+
+impl LRUKPageReplacer {
+    pub fn new(k: usize) -> Self {
+        LRUKPageReplacer {
+            k: k.max(1),
+            crp: 0,
+            rip: 1024,
+            now: 0,
+            history: BTreeMap::new(),
+            resident: BTreeSet::new(),
+            last_victim_distance: None,
+        }
+    }
+
+    /// O tempo do K-ésimo uso e do último uso da página, 0 se não houve.
+    fn key(&self, page_number: usize) -> (usize, usize) {
+        self.history
+            .get(&page_number)
+            .map_or((0, 0), |(uses, last)| (uses[self.k - 1], *last))
+    }
+}
+
+impl Default for LRUKPageReplacer {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl PageReplacer for LRUKPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => {
+                self.resident.insert(idx);
+            }
+            PageEvent::Touched(idx) => {
+                self.now += 1;
+                let now = self.now;
+
+                let (uses, last) = self
+                    .history
+                    .entry(idx)
+                    .or_insert_with(|| (vec![0; self.k], 0));
+
+                if *last == 0 || now - *last > self.crp {
+                    // Um uso novo: o período correlacionado anterior acabou.
+                    let correlated = *last - uses[0];
+
+                    for i in (1..uses.len()).rev() {
+                        uses[i] = if uses[i - 1] == 0 {
+                            0
+                        } else {
+                            uses[i - 1] + correlated
+                        };
+                    }
+                    uses[0] = now;
+                }
+
+                *last = now;
+            }
+            PageEvent::Unmapped(idx) => {
+                self.resident.remove(&idx);
+                self.history.remove(&idx);
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        let eligible = |page: &&usize| {
+            let (_, last) = self.key(**page);
+            last == 0 || self.now - last > self.crp
+        };
+
+        let victim = *self
+            .resident
+            .iter()
+            .filter(eligible)
+            .min_by_key(|&&page| self.key(page))
+            .or_else(|| self.resident.iter().min_by_key(|&&page| self.key(page)))?;
+
+        let (kth, _) = self.key(victim);
+        self.last_victim_distance = (kth != 0).then(|| self.now - kth);
+        self.resident.remove(&victim);
+
+        let (now, rip) = (self.now, self.rip);
+        let resident = &self.resident;
+        self.history.retain(|page, (_, last)| {
+            *page == victim || resident.contains(page) || now - *last <= rip
+        });
+
+        Some(victim)
+    }
+
+    fn victim_reason(&self) -> String {
+        match self.last_victim_distance {
+            Some(distance) => format!(
+                "a página com o {}º uso mais antigo, há {} acessos",
+                self.k, distance
+            ),
+            None => format!(
+                "uma página com menos de {} usos, a usada há mais tempo",
+                self.k
+            ),
+        }
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["k", "crp", "rip"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "k" if value >= 1.0 && value.fract() == 0.0 => {
+                // Os históricos guardados mudam de tamanho: os usos que
+                // faltam ficam como "não houve".
+                self.k = value as usize;
+                for (uses, _) in self.history.values_mut() {
+                    uses.resize(self.k, 0);
+                }
+                Ok(())
+            }
+            "crp" if value >= 0.0 && value.fract() == 0.0 => {
+                self.crp = value as usize;
+                Ok(())
+            }
+            "rip" if value >= 0.0 && value.fract() == 0.0 => {
+                self.rip = value as usize;
+                Ok(())
+            }
+            "k" | "crp" | "rip" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // As páginas em memória, com há quantos acessos foi cada um dos K
+        // últimos usos, ou `-` se não houve.
+        write!(f, "lru-{} (crp={}): [", self.k, self.crp)?;
+
+        for (i, page) in self.resident.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#04X}", page)?;
+            let uses = self.history.get(page).map_or(&[][..], |(uses, _)| uses);
+            for j in 0..self.k {
+                match uses.get(j) {
+                    Some(&time) if time != 0 => write!(f, " {}", self.now - time)?,
+                    _ => write!(f, " -")?,
+                }
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Uma página no relógio do Clock-Pro.
+#[derive(Clone)]
+struct ClockProPage {
+    page_number: usize,
+    hot: bool,
+    /// Se a página está em memória. Uma página fria fora da memória fica no
+    /// relógio só enquanto está no período de teste.
+    resident: bool,
+    /// Se a página (fria) está no período de teste.
+    test: bool,
+    referenced: bool,
+}
+
+/// Implementação do Clock-Pro, de Jiang, Chen e Zhang: uma aproximação do
+/// LIRS com ponteiros de relógio.
+///
+/// O LRU erra numa varredura maior que a memória porque olha para a
+/// recência; o LIRS olha para a distância de reuso, e o Clock-Pro a
+/// aproxima com três ponteiros num relógio só. As páginas são quentes
+/// (reusadas a uma distância curta) ou frias, e uma página fria nova passa
+/// por um período de teste: se for usada de novo durante ele, a sua
+/// distância de reuso é curta o bastante para ela virar quente. O período
+/// de teste continua depois que a página sai da memória, com ela no relógio
+/// só como metadado (uma página de teste), e uma página de teste que volta
+/// entra já quente.
+///
+/// - O ponteiro frio escolhe as vítimas entre as páginas frias em memória,
+///   como um relógio de segunda chance: uma página fria com o bit de
+///   referência ligado vira quente, se está no período de teste, ou começa
+///   um período de teste;
+/// - o ponteiro quente esfria a primeira página quente sem o bit de
+///   referência, quando há quentes demais, e termina os períodos de teste
+///   por onde passa;
+/// - o ponteiro de teste termina os períodos de teste mais antigos quando
+///   há páginas de teste demais (mais que `test` vezes as páginas em
+///   memória, o mesmo número por padrão).
+///
+/// A quantidade alvo de páginas frias em memória se adapta: cresce quando
+/// uma página de teste volta (mais páginas frias teriam acertado) e diminui
+/// quando um período de teste termina sem reuso. O acesso que carregou a
+/// página não conta como reuso.
+#[derive(Clone)]
+pub struct ClockProPageReplacer {
+    /// A fração das páginas em memória guardada como páginas de teste.
+    test_share: f64,
+    /// O relógio. As páginas novas entram logo atrás do ponteiro quente (a
+    /// cabeça da lista).
+    pages: Vec<ClockProPage>,
+    hand_hot: usize,
+    hand_cold: usize,
+    hand_test: usize,
+    /// A quantidade alvo de páginas frias em memória.
+    cold_target: usize,
+    /// A página que acabou de ser carregada, cujo próximo uso é o do próprio
+    /// acesso que a carregou.
+    just_loaded: Option<usize>,
+    /// Se a última vítima ficou no relógio como página de teste.
+    last_kept_for_test: bool,
+}
+
+impl ClockProPageReplacer {
+    pub fn new() -> Self {
+        ClockProPageReplacer {
+            test_share: 1.0,
+            pages: Vec::new(),
+            hand_hot: 0,
+            hand_cold: 0,
+            hand_test: 0,
+            cold_target: 1,
+            just_loaded: None,
+            last_kept_for_test: false,
+        }
+    }
+
+    /// As páginas em memória.
+    fn resident(&self) -> usize {
+        self.pages.iter().filter(|page| page.resident).count()
+    }
+
+    /// A quantidade alvo de páginas quentes.
+    fn hot_target(&self) -> usize {
+        self.resident().saturating_sub(self.cold_target).max(1)
+    }
+
+    /// Quantas páginas de teste podem ficar no relógio.
+    fn test_capacity(&self) -> usize {
+        (self.resident() as f64 * self.test_share) as usize
+    }
+
+    fn count(&self, filter: impl Fn(&ClockProPage) -> bool) -> usize {
+        self.pages.iter().filter(|page| filter(page)).count()
+    }
+
+    /// Tira a página da posição do relógio, mantendo os ponteiros nas
+    /// mesmas páginas (ou na seguinte, se apontavam para ela).
+    fn remove(&mut self, position: usize) -> ClockProPage {
+        for hand in [&mut self.hand_hot, &mut self.hand_cold, &mut self.hand_test] {
+            if *hand > position {
+                *hand -= 1;
+            }
+        }
+
+        self.pages.remove(position)
+    }
+
+    /// Põe a página na cabeça da lista, logo atrás do ponteiro quente.
+    fn insert_at_head(&mut self, page: ClockProPage) {
+        let position = self.hand_hot.min(self.pages.len());
+
+        for hand in [&mut self.hand_hot, &mut self.hand_cold, &mut self.hand_test] {
+            if *hand >= position {
+                *hand += 1;
+            }
+        }
+
+        self.pages.insert(position, page);
+    }
+
+    /// Termina o período de teste da página na posição, que sai do relógio
+    /// se não está em memória. Devolve se ela saiu.
+    fn end_test(&mut self, position: usize) -> bool {
+        self.pages[position].test = false;
+        self.cold_target = self.cold_target.saturating_sub(1).max(1);
+
+        if !self.pages[position].resident {
+            self.remove(position);
+            return true;
+        }
+
+        false
+    }
+
+    /// Move o ponteiro quente até esfriar uma página quente.
+    fn run_hand_hot(&mut self) {
+        if self.count(|page| page.hot) == 0 {
+            return;
+        }
+
+        loop {
+            if self.hand_hot >= self.pages.len() {
+                self.hand_hot = 0;
+            }
+
+            let page = &mut self.pages[self.hand_hot];
+
+            if page.hot {
+                if page.referenced {
+                    page.referenced = false;
+                } else {
+                    page.hot = false;
+                    self.hand_hot += 1;
+                    return;
+                }
+            } else if page.test && self.end_test(self.hand_hot) {
+                continue;
+            }
+
+            self.hand_hot += 1;
+        }
+    }
+
+    /// Move o ponteiro de teste até que as páginas de teste caibam.
+    fn run_hand_test(&mut self) {
+        while self.count(|page| !page.resident) > self.test_capacity() {
+            if self.hand_test >= self.pages.len() {
+                self.hand_test = 0;
+            }
+
+            let page = &self.pages[self.hand_test];
+
+            if page.hot || !page.test || !self.end_test(self.hand_test) {
+                self.hand_test += 1;
+            }
+        }
+    }
+}
+
+impl Default for ClockProPageReplacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageReplacer for ClockProPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => {
+                let page = match self.pages.iter().position(|page| page.page_number == idx) {
+                    // Uma página de teste voltou: mais páginas frias em
+                    // memória a teriam guardado.
+                    Some(position) => {
+                        self.remove(position);
+                        self.cold_target += 1;
+
+                        ClockProPage {
+                            page_number: idx,
+                            hot: true,
+                            resident: true,
+                            test: false,
+                            referenced: false,
+                        }
+                    }
+                    None => ClockProPage {
+                        page_number: idx,
+                        hot: false,
+                        resident: true,
+                        test: true,
+                        referenced: false,
+                    },
+                };
+
+                let hot = page.hot;
+                self.insert_at_head(page);
+                self.cold_target = self.cold_target.min(self.resident().max(2) - 1);
+                self.just_loaded = Some(idx);
+
+                if hot && self.count(|page| page.hot) > self.hot_target() {
+                    self.run_hand_hot();
+                }
+            }
+            PageEvent::Touched(idx) => {
+                if self.just_loaded.take() == Some(idx) {
+                    return;
+                }
+
+                if let Some(page) = self
+                    .pages
+                    .iter_mut()
+                    .find(|page| page.page_number == idx && page.resident)
+                {
+                    page.referenced = true;
+                }
+            }
+            PageEvent::Unmapped(idx) => {
+                if let Some(position) = self.pages.iter().position(|page| page.page_number == idx) {
+                    self.remove(position);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        if self.count(|page| page.resident) == 0 {
+            return None;
+        }
+
+        loop {
+            if self.count(|page| page.resident && !page.hot) == 0 {
+                self.run_hand_hot();
+            }
+
+            if self.hand_cold >= self.pages.len() {
+                self.hand_cold = 0;
+            }
+
+            let page = &mut self.pages[self.hand_cold];
+
+            if page.hot || !page.resident {
+                self.hand_cold += 1;
+                continue;
+            }
+
+            if page.referenced {
+                // Um reuso: no período de teste, a página vira quente; fora
+                // dele, começa um. Nos dois casos, vai para a cabeça.
+                page.referenced = false;
+                let promoted = page.test;
+                page.hot = promoted;
+                page.test = !promoted;
+
+                let page = self.remove(self.hand_cold);
+                self.insert_at_head(page);
+
+                if promoted && self.count(|page| page.hot) > self.hot_target() {
+                    self.run_hand_hot();
+                }
+                continue;
+            }
+
+            // A vítima. No período de teste, ela fica no relógio como
+            // página de teste.
+            let victim = page.page_number;
+            self.last_kept_for_test = page.test;
+
+            if page.test {
+                page.resident = false;
+                self.hand_cold += 1;
+            } else {
+                self.remove(self.hand_cold);
+            }
+
+            self.run_hand_test();
+
+            return Some(victim);
+        }
+    }
+
+    fn victim_reason(&self) -> String {
+        if self.last_kept_for_test {
+            "a primeira página fria sem o bit de referência, mantida como página de teste"
+                .to_string()
+        } else {
+            "a primeira página fria sem o bit de referência a partir do ponteiro frio".to_string()
+        }
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["test"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "test" if value >= 0.0 => {
+                self.test_share = value;
+                Ok(())
+            }
+            "test" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // O relógio a partir da cabeça, com `Q` para as páginas quentes, `F`
+        // para as frias, `T` para as de teste fora da memória, `*` para o bit
+        // de referência e `t` para o período de teste.
+        write!(f, "clock-pro (frias alvo={}): [", self.cold_target)?;
+
+        let head = self.hand_hot.min(self.pages.len());
+        let order = (head..self.pages.len()).chain(0..head);
+
+        for (i, position) in order.enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            let page = &self.pages[position];
+            let kind = match (page.hot, page.resident) {
+                (true, _) => "Q",
+                (false, true) => "F",
+                (false, false) => "T",
+            };
+
+            write!(f, "{:#04X} {}", page.page_number, kind)?;
+            if page.referenced {
+                write!(f, "*")?;
+            }
+            if page.test && page.resident {
+                write!(f, "t")?;
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação da substituição aleatória: sai uma página em memória
+/// sorteada, com a mesma probabilidade para todas.
+///
+/// É a política de referência mais simples: não usa nenhuma informação
+/// sobre os acessos, e qualquer política que se preze precisa ser melhor
+/// que ela. O sorteio usa um `Rng` derivado da semente (veja o módulo
+/// `rng`), então a mesma semente escolhe sempre as mesmas vítimas.
+#[derive(Clone)]
+pub struct RandomPageReplacer {
+    /// As páginas em memória, em ordem de carga (com buracos preenchidos
+    /// pela última, a cada saída).
+    pages: Vec<usize>,
+    rng: Rng,
+}
+
+impl RandomPageReplacer {
+    /// Constrói o replacer a partir da semente da execução.
+    pub fn new(seed: u64) -> Self {
+        RandomPageReplacer {
+            pages: Vec::new(),
+            rng: Rng::for_component(seed, "replacer"),
+        }
+    }
+}
+
+impl PageReplacer for RandomPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => self.pages.push(idx),
+            PageEvent::Unmapped(idx) => {
+                if let Some(position) = self.pages.iter().position(|&page| page == idx) {
+                    self.pages.swap_remove(position);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        if self.pages.is_empty() {
+            return None;
+        }
+
+        let position = self.rng.below(self.pages.len());
+
+        Some(self.pages.swap_remove(position))
+    }
+
+    fn victim_reason(&self) -> String {
+        format!(
+            "sorteada entre as {} páginas em memória",
+            self.pages.len() + 1
+        )
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["seed"]
+    }
+
+    /// A semente pode ser trocada no meio da execução: o sorteio recomeça
+    /// a partir dela.
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "seed" if value >= 0.0 && value.fract() == 0.0 => {
+                self.rng = Rng::for_component(value as u64, "replacer");
+                Ok(())
+            }
+            "seed" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "random: [")?;
+
+        for (i, page) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#04X}", page)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação do NRU (Not Recently Used), com os bits de referência e
+/// dirty.
+///
+/// As páginas em memória são divididas em quatro classes, pelos bits
+/// (referenciada, suja): 0 = (não, não), 1 = (não, sim), 2 = (sim, não) e
+/// 3 = (sim, sim). A vítima sai da menor classe não vazia: uma página não
+/// usada recentemente sai antes de uma usada, e, entre elas, uma limpa sai
+/// antes de uma suja, que custaria um writeback. Dentro da classe, sai a
+/// carregada há mais tempo.
+///
+/// O bit de referência é desligado a cada tick (veja `Mmu::tick`), como na
+/// interrupção do relógio do sistema operacional; sem ticks, toda página
+/// tocada fica referenciada para sempre. O dirty bit vem do
+/// `PageEvent::Dirtied`, e é desligado quando a página é escrita no disco
+/// sem sair da memória (`PageEvent::FlushedDirty`).
+#[derive(Default, Clone)]
+pub struct NRUPageReplacer {
+    /// As páginas, em ordem de carga, com os bits (referenciada, suja).
+    pages: VecDeque<(usize, bool, bool)>,
+    /// A classe da última página escolhida.
+    last_class: usize,
+}
+
+impl NRUPageReplacer {
+    pub fn new() -> Self {
+        NRUPageReplacer {
+            pages: VecDeque::new(),
+            last_class: 0,
+        }
+    }
+
+    fn class(referenced: bool, dirty: bool) -> usize {
+        2 * referenced as usize + dirty as usize
+    }
+
+    fn find(&mut self, page_number: usize) -> Option<&mut (usize, bool, bool)> {
+        self.pages
+            .iter_mut()
+            .find(|(page, _, _)| *page == page_number)
+    }
+}
+
+impl PageReplacer for NRUPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => self.pages.push_back((idx, false, false)),
+            PageEvent::Touched(idx) => {
+                if let Some((_, referenced, _)) = self.find(idx) {
+                    *referenced = true;
+                }
+            }
+            PageEvent::Dirtied(idx) => {
+                if let Some((_, _, dirty)) = self.find(idx) {
+                    *dirty = true;
+                }
+            }
+            PageEvent::FlushedDirty(idx) => {
+                if let Some((_, _, dirty)) = self.find(idx) {
+                    *dirty = false;
+                }
+            }
+            PageEvent::Unmapped(idx) => self.pages.retain(|&(page, _, _)| page != idx),
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self) {
+        for (_, referenced, _) in &mut self.pages {
+            *referenced = false;
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        // `min_by_key` devolve a primeira página da menor classe, que é a
+        // carregada há mais tempo.
+        let position = (0..self.pages.len()).min_by_key(|&i| {
+            let (_, referenced, dirty) = self.pages[i];
+            Self::class(referenced, dirty)
+        })?;
+
+        let (page, referenced, dirty) = self.pages.remove(position)?;
+        self.last_class = Self::class(referenced, dirty);
+
+        Some(page)
+    }
+
+    fn victim_reason(&self) -> String {
+        let description = match self.last_class {
+            0 => "não referenciada, limpa",
+            1 => "não referenciada, suja",
+            2 => "referenciada, limpa",
+            _ => "referenciada, suja",
+        };
+
+        format!(
+            "a página mais antiga da menor classe não vazia ({}: {})",
+            self.last_class, description
+        )
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // As páginas em ordem de carga, com os bits R e D.
+        write!(f, "nru: [")?;
+
+        for (i, (page, referenced, dirty)) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(
+                f,
+                "{:#04X} {}{}",
+                page,
+                if *referenced { 'R' } else { '-' },
+                if *dirty { 'D' } else { '-' }
+            )?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação do algoritmo Aging: cada página em memória tem um contador
+/// de 8 bits, o histórico do seu bit de referência.
+///
+/// A cada tick, o contador de cada página é deslocado para a direita, o bit
+/// de referência entra no bit mais significativo e é desligado. Assim, o
+/// contador de uma página usada no último tick é maior que o de qualquer
+/// página usada só antes, e a vítima é a de menor contador: a que está há
+/// mais tempo sem uso, com a resolução de um tick e a memória de 8 ticks.
+/// No empate, sai uma página não referenciada desde o último tick e, entre
+/// elas, a carregada há mais tempo.
+///
+/// Os ticks vêm da Mmu (veja `Mmu::set_tick_interval`) ou do próprio
+/// replacer: com o parâmetro `interval` positivo, ele faz um tick a cada
+/// `interval` acessos, contados pelos `PageEvent::Touched`. Com os dois,
+/// os contadores envelhecem nos dois ritmos.
+#[derive(Default, Clone)]
+pub struct AgingPageReplacer {
+    /// As páginas, em ordem de carga, com o bit de referência e o contador.
+    pages: Vec<(usize, bool, u8)>,
+    /// A cada quantos acessos o replacer faz um tick; 0 para só os ticks da
+    /// Mmu.
+    interval: usize,
+    /// Os acessos desde o último tick próprio.
+    touches: usize,
+    /// O bit de referência e o contador da última página escolhida.
+    last_victim: (bool, u8),
+}
+
+impl AgingPageReplacer {
+    pub fn new() -> Self {
+        AgingPageReplacer {
+            pages: Vec::new(),
+            interval: 0,
+            touches: 0,
+            last_victim: (false, 0),
+        }
+    }
+}
+
+impl PageReplacer for AgingPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => self.pages.push((idx, false, 0)),
+            PageEvent::Touched(idx) => {
+                if let Some((_, referenced, _)) =
+                    self.pages.iter_mut().find(|(page, _, _)| *page == idx)
+                {
+                    *referenced = true;
+                }
+
+                if self.interval > 0 {
+                    self.touches += 1;
+
+                    if self.touches == self.interval {
+                        self.touches = 0;
+                        self.tick();
+                    }
+                }
+            }
+            PageEvent::Unmapped(idx) => self.pages.retain(|&(page, _, _)| page != idx),
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self) {
+        for (_, referenced, counter) in &mut self.pages {
+            *counter = (*counter >> 1) | ((*referenced as u8) << 7);
+            *referenced = false;
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        // `min_by_key` devolve a primeira das empatadas, a carregada há mais
+        // tempo.
+        let position = (0..self.pages.len()).min_by_key(|&i| {
+            let (_, referenced, counter) = self.pages[i];
+            (counter, referenced)
+        })?;
+
+        let (page, referenced, counter) = self.pages.remove(position);
+        self.last_victim = (referenced, counter);
+
+        Some(page)
+    }
+
+    fn victim_reason(&self) -> String {
+        let (referenced, counter) = self.last_victim;
+
+        format!(
+            "a página de menor contador (contador {:08b}, bit {})",
+            counter, referenced as u8
+        )
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["interval"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "interval" if value >= 0.0 && value.fract() == 0.0 => {
+                self.interval = value as usize;
+                self.touches = 0;
+                Ok(())
+            }
+            "interval" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // As páginas em ordem de carga, com o bit de referência e o contador.
+        write!(f, "aging: [")?;
+
+        for (i, (page, referenced, counter)) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{:#04X} {}|{:08b}", page, *referenced as u8, counter)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação da política do working set, com uma janela de `tau`
+/// acessos de tempo virtual.
+///
+/// O working set no acesso `t` são as páginas usadas nos acessos de
+/// `t - tau + 1` a `t`. Uma página em memória fora dele não é usada há mais
+/// de `tau` acessos, e é a vítima; entre várias, sai a carregada há mais
+/// tempo, como no WSClock. Se todas as páginas em memória estão no working
+/// set, ele não cabe na memória, e sai a usada há mais tempo.
+///
+/// O tempo virtual é contado pelos `PageEvent::Touched`, um por acesso.
+/// O working set inclui as páginas que já saíram da memória, então o seu
+/// tamanho (veja `working_set_size`) pode passar do número de frames.
+#[derive(Clone)]
+pub struct WorkingSetPageReplacer {
+    tau: usize,
+    /// O tempo virtual: o número de acessos até agora.
+    now: usize,
+    /// As páginas em memória, em ordem de carga.
+    pages: Vec<usize>,
+    /// O último uso de cada página usada dentro da janela, em memória ou
+    /// não.
+    last_use: BTreeMap<usize, usize>,
+    /// Se a última página escolhida estava fora do working set.
+    last_outside: bool,
+}
+
+impl WorkingSetPageReplacer {
+    /// Constrói a política com uma janela de `tau` acessos (pelo menos 1).
+    pub fn new(tau: usize) -> Self {
+        WorkingSetPageReplacer {
+            tau: tau.max(1),
+            now: 0,
+            pages: Vec::new(),
+            last_use: BTreeMap::new(),
+            last_outside: false,
+        }
+    }
+
+    /// O tamanho da janela, em acessos.
+    pub fn tau(&self) -> usize {
+        self.tau
+    }
+
+    /// Se a página foi usada dentro da janela.
+    fn in_window(&self, page_number: usize) -> bool {
+        self.last_use
+            .get(&page_number)
+            .is_some_and(|&time| self.now - time < self.tau)
+    }
+}
+
+impl PageReplacer for WorkingSetPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => self.pages.push(idx),
+            PageEvent::Touched(idx) => {
+                self.now += 1;
+                self.last_use.insert(idx, self.now);
+
+                // Esquece as páginas que saíram da janela.
+                let (now, tau) = (self.now, self.tau);
+                self.last_use.retain(|_, time| now - *time < tau);
+            }
+            PageEvent::Unmapped(idx) => self.pages.retain(|&page| page != idx),
+            _ => {}
+        }
+    }
+
+    fn working_set_size(&self) -> Option<usize> {
+        Some(self.last_use.len())
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        let outside = self.pages.iter().position(|&page| !self.in_window(page));
+        self.last_outside = outside.is_some();
+
+        let position = outside.or_else(|| {
+            (0..self.pages.len())
+                .min_by_key(|&i| self.last_use.get(&self.pages[i]).copied().unwrap_or(0))
+        })?;
+
+        Some(self.pages.remove(position))
+    }
+
+    fn victim_reason(&self) -> String {
+        if self.last_outside {
+            format!(
+                "a página mais antiga fora do working set (sem uso nos últimos {} acessos)",
+                self.tau
+            )
+        } else {
+            "o working set não cabe na memória: a página usada há mais tempo".to_string()
+        }
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["tau"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "tau" if value >= 1.0 && value.fract() == 0.0 => {
+                self.tau = value as usize;
+                let (now, tau) = (self.now, self.tau);
+                self.last_use.retain(|_, time| now - *time < tau);
+                Ok(())
+            }
+            "tau" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // As páginas em ordem de carga, com há quantos acessos cada uma foi
+        // usada, ou `-` se foi fora da janela.
+        write!(f, "ws (tau={}, |W|={}): [", self.tau, self.last_use.len())?;
+
+        for (i, page) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            match self.last_use.get(page) {
+                Some(time) => write!(f, "{:#04X} {}", page, self.now - time)?,
+                None => write!(f, "{:#04X} -", page)?,
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Uma página do WSClock.
+#[derive(Clone)]
+struct WSClockPage {
+    page_number: usize,
+    referenced: bool,
+    dirty: bool,
+    /// O tempo virtual do último uso visto pelo ponteiro.
+    last_use: usize,
+}
+
+/// Implementação do WSClock: o relógio, com os tempos do último uso de cada
+/// página, para aproximar a política do working set (veja
+/// `WorkingSetPageReplacer`) sem procurar em todas as páginas.
+///
+/// O ponteiro percorre as páginas em memória, em círculo. Uma página
+/// referenciada tem o bit desligado e o último uso atualizado para agora, e
+/// fica. Uma página não referenciada há mais de `tau` acessos está fora do
+/// working set: se está limpa, é a vítima; se está suja, a sua escrita no
+/// disco é agendada (veja `PageReplacer::scheduled_writebacks`) e o ponteiro
+/// continua, pois uma página limpa sai mais barato. Na volta seguinte, a
+/// página já foi escrita e pode sair.
+///
+/// Se o ponteiro dá duas voltas sem vítima, todas as páginas estão no working
+/// set, e sai a primeira página limpa a partir do ponteiro (ou a página sob
+/// ele, se todas estão sujas).
+///
+/// O tempo virtual é contado pelos `PageEvent::Touched`, um por acesso. Os
+/// bits de referência são desligados pelo ponteiro e a cada tick (veja
+/// `Mmu::tick`); sem ticks, uma página usada uma vez só continua no working
+/// set até o ponteiro passar por ela.
+#[derive(Clone)]
+pub struct WSClockPageReplacer {
+    tau: usize,
+    /// O tempo virtual: o número de acessos até agora.
+    now: usize,
+    pages: Vec<WSClockPage>,
+    hand: usize,
+    /// As páginas cujas escritas foram agendadas e ainda não foram pedidas
+    /// pela Mmu.
+    scheduled: Vec<usize>,
+    /// Se a última vítima estava fora do working set.
+    last_outside: bool,
+}
+
+impl WSClockPageReplacer {
+    /// Constrói o WSClock com uma janela de `tau` acessos (pelo menos 1).
+    pub fn new(tau: usize) -> Self {
+        WSClockPageReplacer {
+            tau: tau.max(1),
+            now: 0,
+            pages: Vec::new(),
+            hand: 0,
+            scheduled: Vec::new(),
+            last_outside: false,
+        }
+    }
+
+    /// O tamanho da janela, em acessos.
+    pub fn tau(&self) -> usize {
+        self.tau
+    }
+
+    fn find(&mut self, page_number: usize) -> Option<&mut WSClockPage> {
+        self.pages
+            .iter_mut()
+            .find(|page| page.page_number == page_number)
+    }
+
+    /// Tira a página sob o ponteiro, que fica na página seguinte.
+    fn evict_at_hand(&mut self) -> usize {
+        let page = self.pages.remove(self.hand);
+
+        if self.hand == self.pages.len() {
+            self.hand = 0;
+        }
+
+        page.page_number
+    }
+}
+
+impl PageReplacer for WSClockPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            // A página nova entra logo atrás do ponteiro, como no relógio.
+            PageEvent::Loaded(idx) => {
+                let page = WSClockPage {
+                    page_number: idx,
+                    referenced: false,
+                    dirty: false,
+                    last_use: self.now,
+                };
+
+                self.pages.insert(self.hand, page);
+                self.hand = (self.hand + 1) % self.pages.len();
+            }
+            PageEvent::Touched(idx) => {
+                self.now += 1;
+
+                if let Some(page) = self.find(idx) {
+                    page.referenced = true;
+                }
+            }
+            PageEvent::Dirtied(idx) => {
+                if let Some(page) = self.find(idx) {
+                    page.dirty = true;
+                }
+            }
+            // Um writeback que o replacer não agendou também limpa a página.
+            PageEvent::FlushedDirty(idx) => {
+                if let Some(page) = self.find(idx) {
+                    page.dirty = false;
+                }
+            }
+            PageEvent::Unmapped(idx) => {
+                if let Some(position) = self.pages.iter().position(|page| page.page_number == idx) {
+                    self.pages.remove(position);
+
+                    if position < self.hand {
+                        self.hand -= 1;
+                    }
+                    if self.hand >= self.pages.len() {
+                        self.hand = 0;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        let len = self.pages.len();
+        self.last_outside = true;
+
+        if len == 0 {
+            return None;
+        }
+
+        for _ in 0..2 * len {
+            let now = self.now;
+            let tau = self.tau;
+            let page = &mut self.pages[self.hand];
+
+            if page.referenced {
+                page.referenced = false;
+                page.last_use = now;
+            } else if now - page.last_use > tau {
+                if !page.dirty {
+                    return Some(self.evict_at_hand());
+                }
+
+                // A escrita é feita pela Mmu antes da próxima escolha; na
+                // próxima volta, a página já está limpa.
+                page.dirty = false;
+                self.scheduled.push(page.page_number);
+            }
+
+            self.hand = (self.hand + 1) % len;
+        }
+
+        self.last_outside = false;
+        if let Some(position) = (0..len)
+            .map(|i| (self.hand + i) % len)
+            .find(|&position| !self.pages[position].dirty)
+        {
+            self.hand = position;
+        }
+
+        Some(self.evict_at_hand())
+    }
+
+    /// Como na interrupção do relógio, o bit de referência de cada página é
+    /// desligado, e o seu último uso passa a ser agora.
+    fn tick(&mut self) {
+        for page in &mut self.pages {
+            if page.referenced {
+                page.referenced = false;
+                page.last_use = self.now;
+            }
+        }
+    }
+
+    fn scheduled_writebacks(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.scheduled)
+    }
+
+    fn victim_reason(&self) -> String {
+        if self.last_outside {
+            format!(
+                "a primeira página limpa fora do working set (sem uso há mais de {} acessos)",
+                self.tau
+            )
+        } else {
+            "todas as páginas no working set: a primeira limpa a partir do ponteiro".to_string()
+        }
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["tau"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "tau" if value >= 1.0 && value.fract() == 0.0 => {
+                self.tau = value as usize;
+                Ok(())
+            }
+            "tau" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // O buffer circular, com `>` antes da página sob o ponteiro e, para
+        // cada página, os bits R e D e há quantos acessos foi o último uso.
+        write!(f, "wsclock (tau={}): [", self.tau)?;
+
+        for (i, page) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            if i == self.hand {
+                write!(f, ">")?;
+            }
+
+            write!(
+                f,
+                "{:#04X} {}{} {}",
+                page.page_number,
+                if page.referenced { 'R' } else { '-' },
+                if page.dirty { 'D' } else { '-' },
+                self.now - page.last_use
+            )?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Implementação do algoritmo ótimo, de Belady: sai a página cujo próximo
+/// uso está mais longe no futuro.
+///
+/// Nenhum sistema de verdade conhece o futuro, então o ótimo serve de limite
+/// inferior: com a mesma carga e o mesmo número de frames, nenhuma política
+/// tem menos misses. Por isso ele é construído com a sequência completa das
+/// páginas que vão ser acessadas (por exemplo, a de um trace; veja
+/// `from_accesses` e o módulo `fixtures`), e acompanha a posição na
+/// sequência pelos `PageEvent::Touched`, um por acesso.
+///
+/// Entre as páginas que não serão mais usadas, sai a carregada há mais
+/// tempo. Depois do fim da sequência, ou se a Mmu receber acessos
+/// diferentes dela, as escolhas continuam válidas, mas deixam de ser ótimas.
+#[derive(Clone)]
+pub struct OptimalPageReplacer {
+    /// As posições de cada página na sequência, em ordem.
+    uses: BTreeMap<usize, Vec<usize>>,
+    /// O tamanho da sequência.
+    len: usize,
+    /// A posição do próximo acesso na sequência.
+    now: usize,
+    /// As páginas em memória, na ordem em que foram carregadas.
+    pages: Vec<usize>,
+    /// A posição do próximo uso da última vítima, se ela ainda será usada.
+    last_next_use: Option<usize>,
+}
+
+impl OptimalPageReplacer {
+    /// Constrói o ótimo para a sequência de páginas dada, na ordem dos
+    /// acessos.
+    pub fn new(future: Vec<usize>) -> Self {
+        let mut uses: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+        for (position, &page) in future.iter().enumerate() {
+            uses.entry(page).or_default().push(position);
+        }
+
+        OptimalPageReplacer {
+            uses,
+            len: future.len(),
+            now: 0,
+            pages: Vec::new(),
+            last_next_use: None,
+        }
+    }
+
+    /// Constrói o ótimo para os acessos dados, com as páginas de 256 bytes
+    /// da Mmu.
+    pub fn from_accesses(accesses: &[Access]) -> Self {
+        OptimalPageReplacer::new(
+            accesses
+                .iter()
+                .map(|access| (access.address & 0xFFFF) >> 8)
+                .collect(),
+        )
+    }
+
+    /// Quantos acessos da sequência ainda não aconteceram.
+    pub fn remaining(&self) -> usize {
+        self.len.saturating_sub(self.now)
+    }
+
+    /// A posição do próximo uso da página, a partir do acesso atual.
+    fn next_use(&self, page_number: usize) -> Option<usize> {
+        let positions = self.uses.get(&page_number)?;
+        let next = positions.partition_point(|&position| position < self.now);
+
+        positions.get(next).copied()
+    }
+}
+
+impl PageReplacer for OptimalPageReplacer {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => self.pages.push(idx),
+            // A page fault é tratada antes do `Touched` do acesso que a
+            // causou, então `now` é a posição desse acesso na escolha.
+            PageEvent::Touched(_) => self.now += 1,
+            PageEvent::Unmapped(idx) => self.pages.retain(|&page| page != idx),
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        // Uma página que não será mais usada está infinitamente longe; entre
+        // elas, ganha a primeira carregada.
+        let (position, next_use) = self
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(i, &page)| (i, self.next_use(page)))
+            .max_by_key(|&(i, next_use)| (next_use.unwrap_or(usize::MAX), std::cmp::Reverse(i)))?;
+
+        self.last_next_use = next_use;
+        Some(self.pages.remove(position))
+    }
+
+    fn victim_reason(&self) -> String {
+        match self.last_next_use {
+            Some(next_use) => format!(
+                "a página cujo próximo uso é o mais distante (daqui a {} acessos)",
+                next_use - self.now
+            ),
+            None => "uma página que não será mais usada".to_string(),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // As páginas, na ordem de carga, com a distância até o próximo uso
+        // (`-` se não serão mais usadas).
+        write!(f, "ótimo ({} acessos pela frente): [", self.remaining())?;
+
+        for (i, &page) in self.pages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            match self.next_use(page) {
+                Some(next_use) => write!(f, "{:#04X} +{}", page, next_use - self.now)?,
+                None => write!(f, "{:#04X} -", page)?,
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Um replacer adaptativo: hospeda duas políticas (por exemplo, o LRU e o
+/// LFU) e deixa a que está indo melhor escolher as vítimas, trocando de uma
+/// para a outra quando a carga muda.
+///
+/// As duas políticas recebem todos os eventos, então as duas conhecem as
+/// mesmas páginas. A cada substituição, a política ativa escolhe a vítima de
+/// verdade, e a inativa escolhe, numa cópia de si mesma, a que teria
+/// escolhido; cada escolha vai para a lista fantasma da sua política. Se uma
+/// página da lista fantasma de uma política é usada de novo (carregada de
+/// volta, ou tocada, se ela só saiu na escolha hipotética), aquela escolha
+/// foi um erro: a política ganha um arrependimento. Os arrependimentos
+/// perdem a fração `decay` (5% por padrão) a cada page fault, para que os
+/// erros recentes pesem mais, e a política com menos arrependimentos passa a
+/// ser a ativa. É a ideia do ARC e do LeCaR, com a escolha determinística.
+///
+/// Como no 2Q, o replacer não sabe quantos frames a Mmu tem: cada lista
+/// fantasma guarda tantas páginas quantas estão em memória.
+#[derive(Clone)]
+pub struct AdaptivePageReplacer<A, B> {
+    first: A,
+    second: B,
+    /// A política ativa: 0 para a primeira, 1 para a segunda.
+    active: usize,
+    /// A fração dos arrependimentos perdida a cada page fault.
+    decay: f64,
+    /// Os arrependimentos de cada política.
+    regrets: [f64; 2],
+    /// As escolhas de cada política, da mais antiga para a mais nova.
+    ghosts: [VecDeque<usize>; 2],
+    /// Quantas páginas estão em memória.
+    resident: usize,
+    /// Quantas vezes a política ativa mudou.
+    switches: usize,
+}
+
+impl<A: PageReplacer + Clone, B: PageReplacer + Clone> AdaptivePageReplacer<A, B> {
+    /// Constrói o replacer com as duas políticas, começando pela primeira.
+    pub fn new(first: A, second: B) -> Self {
+        AdaptivePageReplacer {
+            first,
+            second,
+            active: 0,
+            decay: 0.05,
+            regrets: [0.0; 2],
+            ghosts: [VecDeque::new(), VecDeque::new()],
+            resident: 0,
+            switches: 0,
+        }
+    }
+
+    /// A política ativa: 0 para a primeira, 1 para a segunda.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Os arrependimentos de cada política, já com o decaimento.
+    pub fn regrets(&self) -> [f64; 2] {
+        self.regrets
+    }
+
+    /// Quantas vezes a política ativa mudou.
+    pub fn switches(&self) -> usize {
+        self.switches
+    }
+
+    /// A página foi usada: se ela está na lista fantasma de uma política,
+    /// a escolha dessa política foi um erro.
+    fn reused(&mut self, page_number: usize) {
+        for (ghost, regret) in self.ghosts.iter_mut().zip(&mut self.regrets) {
+            if let Some(position) = ghost.iter().position(|&page| page == page_number) {
+                ghost.remove(position);
+                *regret += 1.0;
+            }
+        }
+
+        let other = 1 - self.active;
+        if self.regrets[other] < self.regrets[self.active] {
+            self.active = other;
+            self.switches += 1;
+        }
+    }
+
+    fn remember(&mut self, policy: usize, page_number: usize) {
+        let ghost = &mut self.ghosts[policy];
+
+        ghost.push_back(page_number);
+        while ghost.len() > self.resident.max(1) {
+            ghost.pop_front();
+        }
+    }
+}
+
+impl<A: PageReplacer + Clone, B: PageReplacer + Clone> PageReplacer for AdaptivePageReplacer<A, B> {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(idx) => {
+                for regret in &mut self.regrets {
+                    *regret *= 1.0 - self.decay;
+                }
+
+                self.resident += 1;
+                self.reused(idx);
+            }
+            PageEvent::Touched(idx) => self.reused(idx),
+            PageEvent::Unmapped(_) => self.resident = self.resident.saturating_sub(1),
+            _ => {}
+        }
+
+        self.first.page_event(event);
+        self.second.page_event(event);
+    }
+
+    fn tick(&mut self) {
+        self.first.tick();
+        self.second.tick();
+    }
+
+    fn working_set_size(&self) -> Option<usize> {
+        match self.active {
+            0 => self.first.working_set_size(),
+            _ => self.second.working_set_size(),
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        // A inativa escolhe numa cópia, sem esquecer a página; a ativa
+        // escolhe de verdade, e a inativa esquece a vítima.
+        let (victim, hypothetical) = match self.active {
+            0 => {
+                let hypothetical = self.second.clone().pick_replacement_page();
+                let victim = self.first.pick_replacement_page()?;
+                self.second.page_event(PageEvent::Unmapped(victim));
+                (victim, hypothetical)
+            }
+            _ => {
+                let hypothetical = self.first.clone().pick_replacement_page();
+                let victim = self.second.pick_replacement_page()?;
+                self.first.page_event(PageEvent::Unmapped(victim));
+                (victim, hypothetical)
+            }
+        };
+
+        self.resident = self.resident.saturating_sub(1);
+        self.remember(self.active, victim);
+        if let Some(page) = hypothetical {
+            self.remember(1 - self.active, page);
+        }
+
+        Some(victim)
+    }
+
+    fn scheduled_writebacks(&mut self) -> Vec<usize> {
+        match self.active {
+            0 => self.first.scheduled_writebacks(),
+            _ => self.second.scheduled_writebacks(),
+        }
+    }
+
+    fn victim_reason(&self) -> String {
+        let reason = match self.active {
+            0 => self.first.victim_reason(),
+            _ => self.second.victim_reason(),
+        };
+
+        format!(
+            "{}, pela {} política ({:.1} arrependimentos contra {:.1})",
+            reason,
+            ["primeira", "segunda"][self.active],
+            self.regrets[self.active],
+            self.regrets[1 - self.active]
+        )
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["decay"]
+    }
+
+    fn configure(&mut self, key: &str, value: f64) -> Result<(), ConfigError> {
+        match key {
+            "decay" if (0.0..1.0).contains(&value) => {
+                self.decay = value;
+                Ok(())
+            }
+            "decay" => Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            }),
+            _ => Err(ConfigError::UnknownKey(key.to_string())),
+        }
+    }
+
+    fn fmt_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A política ativa, os arrependimentos e as trocas, e depois o
+        // estado de cada política, a ativa marcada com `*`.
+        write!(
+            f,
+            "adaptativo ({} trocas, arrependimentos {:.2} / {:.2}): ",
+            self.switches, self.regrets[0], self.regrets[1]
+        )?;
+
+        write!(f, "{}", if self.active == 0 { "*" } else { "" })?;
+        self.first.fmt_state(f)?;
+        write!(f, "; {}", if self.active == 1 { "*" } else { "" })?;
+        self.second.fmt_state(f)
+    }
+}
+
+/// Um `PageReplacer` que pode ser clonado dentro de uma caixa. Um
+/// `Box<dyn CloneablePageReplacer>` é um replacer que implementa `Clone`, e
+/// assim uma Mmu com a política escolhida em tempo de execução ainda pode
+/// usar a viagem no tempo (veja `Mmu::enable_time_travel`).
+pub trait CloneablePageReplacer: PageReplacer {
+    fn clone_box(&self) -> Box<dyn CloneablePageReplacer>;
+}
+
+impl<R: PageReplacer + Clone + 'static> CloneablePageReplacer for R {
+    fn clone_box(&self) -> Box<dyn CloneablePageReplacer> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CloneablePageReplacer> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+/// Uma função que constrói um replacer, a partir da semente da execução.
+pub type ReplacerFactory = Box<dyn Fn(u64) -> Box<dyn CloneablePageReplacer>>;
+
+/// Um registro de replacers por nome, para que a política possa ser
+/// escolhida em tempo de execução (como o `--replacer` da demo).
+pub struct ReplacerRegistry {
+    factories: BTreeMap<String, ReplacerFactory>,
+}
+
+impl ReplacerRegistry {
+    /// Constrói um registro com as políticas da crate, com os parâmetros
+    /// padrão. O ótimo fica de fora, porque precisa conhecer os acessos de
+    /// antemão.
+    pub fn new() -> Self {
+        let mut registry = ReplacerRegistry {
+            factories: BTreeMap::new(),
+        };
+
+        registry.register("fifo", |_| Box::new(FIFOPageReplacer::new()));
+        registry.register("clock", |_| Box::new(ClockPageReplacer::new()));
+        registry.register("clock-pro", |_| Box::new(ClockProPageReplacer::new()));
+        registry.register("lfu", |_| Box::new(LFUPageReplacer::new()));
+        registry.register("mfu", |_| Box::new(MFUPageReplacer::new()));
+        registry.register("mru", |_| Box::new(MRUPageReplacer::new()));
+        registry.register("lru", |_| Box::new(LRUPageReplacer::new()));
+        registry.register("lru-2", |_| Box::new(LRUKPageReplacer::new(2)));
+        registry.register("s3-fifo", |_| Box::new(S3FIFOPageReplacer::new()));
+        registry.register("2q", |_| Box::new(TwoQPageReplacer::new()));
+        registry.register("slru", |_| Box::new(SLRUPageReplacer::new()));
+        registry.register("nru", |_| Box::new(NRUPageReplacer::new()));
+        registry.register("aging", |_| Box::new(AgingPageReplacer::new()));
+        registry.register("ws", |_| Box::new(WorkingSetPageReplacer::new(16)));
+        registry.register("wsclock", |_| Box::new(WSClockPageReplacer::new(16)));
+        registry.register("random", |seed| Box::new(RandomPageReplacer::new(seed)));
+        registry.register("adaptive", |_| {
+            Box::new(AdaptivePageReplacer::new(
+                LRUPageReplacer::new(),
+                LFUPageReplacer::new(),
+            ))
+        });
+
+        registry
+    }
+
+    /// Registra um replacer, substituindo outro com o mesmo nome.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(u64) -> Box<dyn CloneablePageReplacer> + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// Constrói o replacer registrado com esse nome, se existe.
+    pub fn create(&self, name: &str, seed: u64) -> Option<Box<dyn CloneablePageReplacer>> {
+        self.factories.get(name).map(|factory| factory(seed))
+    }
+
+    /// Os nomes registrados, em ordem.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+}
+
+impl Default for ReplacerRegistry {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -1,3 +1,34 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::region::Permissions;
+
+/// O tamanho, em bytes, de uma entrada da page table guardada na memória
+/// física (veja `Mmu::place_page_table_in_memory`): o índice do frame, em 16
+/// bits little-endian, um byte de flags e um byte com a proteção.
+pub const PTE_SIZE: usize = 4;
+
+/// O maior número de frames que o índice de uma entrada guardada na memória
+/// física consegue endereçar.
+pub const PTE_MAX_FRAMES: usize = 1 << 16;
+
+const PTE_VALID: u8 = 1 << 0;
+const PTE_DIRTY: u8 = 1 << 1;
+const PTE_REFERENCED: u8 = 1 << 2;
+const PTE_PINNED: u8 = 1 << 3;
+
+const PTE_READ: u8 = 1 << 0;
+const PTE_WRITE: u8 = 1 << 1;
+const PTE_EXECUTE: u8 = 1 << 2;
+
+/// Quantos bits de software cada entrada tem (veja
+/// `PageTableEntry::software`). Na entrada guardada na memória física, eles
+/// ocupam os 4 bits mais altos do byte de flags.
+pub const SOFTWARE_BITS: u32 = 4;
+
+const PTE_SOFTWARE_SHIFT: u32 = 8 - SOFTWARE_BITS;
+
 /// Uma entrada na Page Table.
 #[derive(Copy, Clone, Default, Debug)]
 pub struct PageTableEntry {
@@ -6,13 +37,129 @@ pub struct PageTableEntry {
     /// Indica se houveram alterações na página que devem ser reescritas
     /// no disco.
     pub dirty: bool,
+    /// O bit de referência: ligado a cada acesso à página, desligado a cada
+    /// tick.
+    pub referenced: bool,
+    /// O histórico do bit de referência nos últimos 8 ticks (o contador do
+    /// algoritmo Aging): a cada tick, o contador é deslocado para a direita e
+    /// o bit de referência entra no bit mais significativo.
+    pub age: u8,
+    /// Quando a página ficou suja, como `(acesso, tick)`. Só faz sentido se
+    /// `dirty` está ligado.
+    pub dirtied_at: (usize, usize),
+    /// Se a página está fixada em memória (veja `Mmu::pin_page`): ela não
+    /// é substituída nem muda de frame. O flag é da entrada, e some com ela
+    /// quando a página é desmapeada.
+    pub pinned: bool,
+    /// Os bits de software: `SOFTWARE_BITS` bits que o hardware não usa, à
+    /// disposição de quem está fora da Mmu (um corretor, um tracer, uma
+    /// política) para marcar a página. Uma cópia de
+    /// `PageTable::software_bits`, que os guarda mesmo com a página fora da
+    /// memória.
+    pub software: u8,
+    /// Os acessos que a página permite (veja `Mmu::set_protection`). Como os
+    /// bits de software, é uma cópia do que a page table guarda para a
+    /// página (veja `PageTable::protection`). Na entrada guardada na memória
+    /// física, ela ocupa o último byte.
+    pub protection: Permissions,
+}
+
+impl PageTableEntry {
+    /// Codifica a entrada como ela é guardada na memória física. `None` é
+    /// uma entrada inválida.
+    ///
+    /// Entra em pânico se o frame não cabe no índice da entrada (veja
+    /// `PTE_MAX_FRAMES`).
+    pub fn encode(entry: Option<PageTableEntry>) -> [u8; PTE_SIZE] {
+        match entry {
+            Some(entry) => {
+                let mut flags = PTE_VALID;
+
+                if entry.dirty {
+                    flags |= PTE_DIRTY;
+                }
+
+                if entry.referenced {
+                    flags |= PTE_REFERENCED;
+                }
+
+                if entry.pinned {
+                    flags |= PTE_PINNED;
+                }
+
+                flags |= entry.software << PTE_SOFTWARE_SHIFT;
+
+                let mut protection = 0;
+
+                if entry.protection.read {
+                    protection |= PTE_READ;
+                }
+
+                if entry.protection.write {
+                    protection |= PTE_WRITE;
+                }
+
+                if entry.protection.execute {
+                    protection |= PTE_EXECUTE;
+                }
+
+                let frame_index = u16::try_from(entry.frame_index)
+                    .expect("page table: o frame não cabe no índice da entrada")
+                    .to_le_bytes();
+
+                [frame_index[0], frame_index[1], flags, protection]
+            }
+            None => [0; PTE_SIZE],
+        }
+    }
+
+    /// Decodifica uma entrada guardada na memória física, devolvendo o
+    /// frame da página se a entrada é válida.
+    pub fn decode(bytes: [u8; PTE_SIZE]) -> Option<usize> {
+        (bytes[2] & PTE_VALID != 0).then_some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+    }
+
+    /// Decodifica a proteção de uma entrada guardada na memória física.
+    pub fn decode_protection(bytes: [u8; PTE_SIZE]) -> Permissions {
+        Permissions {
+            read: bytes[3] & PTE_READ != 0,
+            write: bytes[3] & PTE_WRITE != 0,
+            execute: bytes[3] & PTE_EXECUTE != 0,
+        }
+    }
+}
+
+/// Erro ao colocar a page table na memória física.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum PlacementError {
+    /// A page table não cabe na memória a partir desse frame.
+    #[error("a page table não cabe na memória")]
+    OutOfRange,
+    /// O frame já está sendo usado por uma página.
+    #[error("o frame {0:#04X} já está em uso")]
+    FrameInUse(usize),
+    /// A page table já está na memória.
+    #[error("a page table já está na memória")]
+    AlreadyPlaced,
+    /// Há mais frames do que o índice de uma entrada consegue endereçar
+    /// (veja `PTE_MAX_FRAMES`).
+    #[error(
+        "{0} frames não cabem no índice de uma entrada (no máximo {})",
+        PTE_MAX_FRAMES
+    )]
+    TooManyFrames(usize),
 }
 
 /// Um wrapper sobre a Page Table.
+#[derive(Clone)]
 pub struct PageTable<const PAGE_TABLE_SIZE: usize> {
     /// A Page Table. Se table[page_number] é um None, a página é inválida
     /// e deve ser carregada; se é Some(_), é válida e pode ser usada.
     table: [Option<PageTableEntry>; PAGE_TABLE_SIZE],
+    /// Os bits de software de cada página, válida ou não.
+    software: [u8; PAGE_TABLE_SIZE],
+    /// A proteção de cada página, válida ou não.
+    protection: [Permissions; PAGE_TABLE_SIZE],
 }
 
 impl<const PAGE_TABLE_SIZE: usize> PageTable<PAGE_TABLE_SIZE> {
@@ -20,6 +167,8 @@ impl<const PAGE_TABLE_SIZE: usize> PageTable<PAGE_TABLE_SIZE> {
     pub fn new() -> Self {
         PageTable {
             table: [None; PAGE_TABLE_SIZE],
+            software: [0; PAGE_TABLE_SIZE],
+            protection: [Permissions::ALL; PAGE_TABLE_SIZE],
         }
     }
 
@@ -27,13 +176,17 @@ impl<const PAGE_TABLE_SIZE: usize> PageTable<PAGE_TABLE_SIZE> {
     pub fn set(&mut self, page_number: usize, frame_index: usize) {
         self.table[page_number] = Some(PageTableEntry {
             frame_index,
-            dirty: false,
+            ..Default::default()
         });
     }
 
     /// Busca um item na page table.
     pub fn get(&self, page_number: usize) -> Option<PageTableEntry> {
-        self.table[page_number]
+        self.table[page_number].map(|entry| PageTableEntry {
+            software: self.software[page_number],
+            protection: self.protection[page_number],
+            ..entry
+        })
     }
 
     /// Invalida uma página.
@@ -41,10 +194,163 @@ impl<const PAGE_TABLE_SIZE: usize> PageTable<PAGE_TABLE_SIZE> {
         self.table[page_number] = None;
     }
 
-    /// Marca uma página como dirty.
-    pub fn mark_dirty(&mut self, idx: usize) {
+    /// Itera sobre as páginas válidas, em ordem de page number.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, PageTableEntry)> + '_ {
+        (0..PAGE_TABLE_SIZE)
+            .filter_map(|page_number| self.get(page_number).map(|entry| (page_number, entry)))
+    }
+
+    /// Os bits de software da página, no bit 0 em diante.
+    ///
+    /// Os bits são da página, não da entrada: eles continuam os mesmos
+    /// quando a entrada é invalidada e refeita, como numa substituição, numa
+    /// quebra de copy-on-write ou numa mudança de frame, e só são apagados
+    /// com `clear_software_bits`.
+    pub fn software_bits(&self, page_number: usize) -> u8 {
+        self.software[page_number]
+    }
+
+    /// Liga ou desliga o bit de software `bit` da página.
+    pub fn set_software_bit(&mut self, page_number: usize, bit: u32, value: bool) {
+        assert!(
+            bit < SOFTWARE_BITS,
+            "page table: o bit de software {} não existe",
+            bit
+        );
+
+        if value {
+            self.software[page_number] |= 1 << bit;
+        } else {
+            self.software[page_number] &= !(1 << bit);
+        }
+    }
+
+    /// Apaga todos os bits de software da página.
+    pub fn clear_software_bits(&mut self, page_number: usize) {
+        self.software[page_number] = 0;
+    }
+
+    /// Os acessos que a página permite. Como os bits de software, a proteção
+    /// é da página, não da entrada.
+    pub fn protection(&self, page_number: usize) -> Permissions {
+        self.protection[page_number]
+    }
+
+    /// Muda a proteção da página.
+    pub fn set_protection(&mut self, page_number: usize, protection: Permissions) {
+        self.protection[page_number] = protection;
+    }
+
+    /// Se a página é válida e está fixada.
+    pub fn is_pinned(&self, page_number: usize) -> bool {
+        self.table[page_number].is_some_and(|entry| entry.pinned)
+    }
+
+    /// Fixa ou libera uma página válida.
+    pub fn set_pinned(&mut self, page_number: usize, pinned: bool) {
+        let page = self.table[page_number].as_mut().unwrap();
+
+        page.pinned = pinned;
+    }
+
+    /// Move uma página válida para outro frame, mantendo o resto da entrada.
+    pub fn move_to_frame(&mut self, page_number: usize, frame_index: usize) {
+        let page = self.table[page_number].as_mut().unwrap();
+
+        page.frame_index = frame_index;
+    }
+
+    /// Marca uma página como dirty. Se ela estava limpa, guarda `now` (como
+    /// `(acesso, tick)`) como o momento em que ficou suja.
+    pub fn mark_dirty(&mut self, idx: usize, now: (usize, usize)) {
         let page = self.table[idx].as_mut().unwrap();
 
+        if !page.dirty {
+            page.dirtied_at = now;
+        }
+
         page.dirty = true;
     }
+
+    /// Marca uma página como limpa, depois que ela foi escrita no disco.
+    pub fn mark_clean(&mut self, idx: usize) {
+        let page = self.table[idx].as_mut().unwrap();
+
+        page.dirty = false;
+    }
+
+    /// Liga o bit de referência de uma página.
+    pub fn mark_referenced(&mut self, idx: usize) {
+        let page = self.table[idx].as_mut().unwrap();
+
+        page.referenced = true;
+    }
+
+    /// Substitui o histórico do bit de referência de uma página válida.
+    pub fn set_age(&mut self, idx: usize, age: u8) {
+        let page = self.table[idx].as_mut().unwrap();
+
+        page.age = age;
+    }
+
+    /// Envelhece todas as páginas válidas: o bit de referência entra no
+    /// contador de idade e é desligado.
+    pub fn age_all(&mut self) {
+        for page in self.table.iter_mut().flatten() {
+            page.age = (page.age >> 1) | ((page.referenced as u8) << 7);
+            page.referenced = false;
+        }
+    }
+}
+
+impl<const PAGE_TABLE_SIZE: usize> Default for PageTable<PAGE_TABLE_SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lista as páginas válidas, uma por linha, no formato
+/// `página 0x0C -> frame 0x03 [D] [R] [P] [S 0101] [r-x]`, onde `[D]` indica
+/// uma página suja, `[R]` uma página referenciada desde o último tick, `[P]`
+/// uma página fixada, `[S ...]` os bits de software, se algum está ligado, e
+/// `[r-x]` a proteção, se a página não permite tudo.
+impl<const PAGE_TABLE_SIZE: usize> fmt::Display for PageTable<PAGE_TABLE_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (page_number, entry) in self.iter() {
+            write!(
+                f,
+                "página {:#04X} -> frame {:#04X}",
+                page_number, entry.frame_index
+            )?;
+
+            if entry.dirty {
+                write!(f, " [D]")?;
+            }
+
+            if entry.referenced {
+                write!(f, " [R]")?;
+            }
+
+            if entry.pinned {
+                write!(f, " [P]")?;
+            }
+
+            if entry.software != 0 {
+                write!(
+                    f,
+                    " [S {:0width$b}]",
+                    entry.software,
+                    width = SOFTWARE_BITS as usize
+                )?;
+            }
+
+            if entry.protection != Permissions::ALL {
+                write!(f, " [{}]", entry.protection)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
 }
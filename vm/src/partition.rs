@@ -0,0 +1,273 @@
+//! Partição dos frames entre processos, para qualidade de serviço.
+//!
+//! Como o Cache Allocation Technology da Intel faz com as vias da cache, um
+//! processo pode reservar frames só seus (veja `Mmu::set_frame_reservation`):
+//! nenhum outro processo pode ocupá-los, então um vizinho barulhento, que
+//! varre muita memória, não tira dele o seu working set. Os frames que
+//! sobram formam o pool compartilhado, disputado por todos os processos sem
+//! reserva e pelas páginas fora de regiões.
+//!
+//! Numa page fault, a página vai para a partição do seu processo:
+//!
+//! - abaixo da reserva, usa um frame livre, ou tira uma página do pool
+//!   compartilhado, se algum processo está ocupando o que é seu;
+//! - na reserva, com empréstimo (`Reservation::borrow`), disputa o pool
+//!   compartilhado como os outros; sem empréstimo, só pode substituir uma
+//!   página sua;
+//! - sem reserva, só pode usar o pool compartilhado, e com ele cheio
+//!   substitui uma página de lá, mesmo com frames reservados livres.
+//!
+//! A vítima continua sendo escolhida pelo replacer: as páginas de fora da
+//! partição que ele escolhe voltam para ele como recém-carregadas, como as
+//! poupadas pela swappiness (veja o módulo `region`).
+//!
+//! É o compromisso entre isolamento e utilização: a reserva protege o
+//! processo dos vizinhos, mas os seus frames ficam ociosos quando ele não
+//! os usa, e um processo isolado não cresce além dela.
+
+use std::{collections::BTreeMap, fmt};
+
+use thiserror::Error;
+
+use crate::region::Pid;
+
+/// A reserva de frames de um processo.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Reservation {
+    /// Quantos frames são só do processo.
+    pub frames: usize,
+    /// Se o processo pode usar o pool compartilhado depois de encher a
+    /// reserva.
+    pub borrow: bool,
+}
+
+impl Reservation {
+    /// Uma reserva isolada: o processo nunca passa de `frames` frames.
+    pub fn isolated(frames: usize) -> Self {
+        Reservation {
+            frames,
+            borrow: false,
+        }
+    }
+
+    /// Uma reserva que cresce pelo pool compartilhado.
+    pub fn borrowing(frames: usize) -> Self {
+        Reservation {
+            frames,
+            borrow: true,
+        }
+    }
+}
+
+/// Uma partição da memória física.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Partition {
+    /// Os frames reservados de um processo.
+    Reserved(Pid),
+    /// O pool compartilhado.
+    Shared,
+}
+
+impl fmt::Display for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Partition::Reserved(pid) => write!(f, "processo {}", pid),
+            Partition::Shared => write!(f, "compartilhado"),
+        }
+    }
+}
+
+/// Os acessos e as page faults das páginas de uma partição.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartitionStats {
+    pub accesses: usize,
+    pub faults: usize,
+}
+
+impl PartitionStats {
+    /// A fração dos acessos que causou page fault.
+    pub fn fault_rate(&self) -> f64 {
+        self.faults as f64 / self.accesses.max(1) as f64
+    }
+}
+
+/// Erro ao reservar frames.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum PartitionError {
+    /// Uma reserva de zero frames.
+    #[error("a reserva do processo {0} não tem frames")]
+    EmptyReservation(Pid),
+    /// As reservas não deixariam nenhum frame para o pool compartilhado.
+    #[error("as reservas somariam {reserved} frames, e a memória tem {available}: o pool compartilhado precisa de pelo menos um")]
+    Oversubscribed {
+        /// Quantos frames as reservas somariam.
+        reserved: usize,
+        /// Quantos frames a Mmu tem para as páginas.
+        available: usize,
+    },
+}
+
+/// De que páginas a vítima de uma page fault pode ser.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum VictimScope {
+    /// Das cobradas do pool compartilhado.
+    Shared,
+    /// Das do processo.
+    Process(Pid),
+}
+
+/// Onde uma page fault pode buscar o seu frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FaultScope {
+    /// Se a página pode usar um frame livre.
+    pub use_free: bool,
+    /// De onde vem a vítima, sem um frame livre que ela possa usar.
+    pub victims: VictimScope,
+}
+
+/// As reservas e os contadores de cada partição.
+#[derive(Clone, Debug, Default)]
+pub struct FramePartitions {
+    reservations: BTreeMap<Pid, Reservation>,
+    stats: BTreeMap<Partition, PartitionStats>,
+}
+
+impl FramePartitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Se nenhum processo tem reserva.
+    pub fn is_empty(&self) -> bool {
+        self.reservations.is_empty()
+    }
+
+    pub fn reservation(&self, pid: Pid) -> Option<Reservation> {
+        self.reservations.get(&pid).copied()
+    }
+
+    /// As reservas, por processo.
+    pub fn reservations(&self) -> impl Iterator<Item = (Pid, Reservation)> + '_ {
+        self.reservations
+            .iter()
+            .map(|(&pid, &reservation)| (pid, reservation))
+    }
+
+    /// Quantos frames todas as reservas somam.
+    pub fn reserved_frames(&self) -> usize {
+        self.reservations
+            .values()
+            .map(|reservation| reservation.frames)
+            .sum()
+    }
+
+    /// A partição das páginas do processo (`None` para as páginas fora de
+    /// regiões).
+    pub fn partition_of(&self, owner: Option<Pid>) -> Partition {
+        match owner {
+            Some(pid) if self.reservations.contains_key(&pid) => Partition::Reserved(pid),
+            _ => Partition::Shared,
+        }
+    }
+
+    pub fn stats(&self, partition: Partition) -> PartitionStats {
+        self.stats.get(&partition).copied().unwrap_or_default()
+    }
+
+    /// Troca a reserva do processo, conferindo que sobra pelo menos um
+    /// frame dos `available` para o pool compartilhado.
+    pub(crate) fn set(
+        &mut self,
+        pid: Pid,
+        reservation: Option<Reservation>,
+        available: usize,
+    ) -> Result<(), PartitionError> {
+        let Some(reservation) = reservation else {
+            self.reservations.remove(&pid);
+            return Ok(());
+        };
+
+        if reservation.frames == 0 {
+            return Err(PartitionError::EmptyReservation(pid));
+        }
+
+        let reserved = self.reserved_frames() - self.reservation(pid).map_or(0, |r| r.frames)
+            + reservation.frames;
+        if reserved >= available {
+            return Err(PartitionError::Oversubscribed {
+                reserved,
+                available,
+            });
+        }
+
+        self.reservations.insert(pid, reservation);
+        Ok(())
+    }
+
+    /// Conta um acesso a uma página do processo.
+    pub(crate) fn access(&mut self, owner: Option<Pid>, hit: bool) {
+        let stats = self.stats.entry(self.partition_of(owner)).or_default();
+
+        stats.accesses += 1;
+        if !hit {
+            stats.faults += 1;
+        }
+    }
+}
+
+/// Uma linha do relatório das partições.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PartitionRow {
+    pub partition: Partition,
+    /// Quantos frames a partição tem: a reserva, ou o que sobra dela para o
+    /// pool compartilhado.
+    pub frames: usize,
+    /// Se o processo pode emprestar frames do pool compartilhado.
+    pub borrow: bool,
+    /// Quantas páginas do processo estão em memória; no pool compartilhado,
+    /// quantas páginas estão cobradas dele.
+    pub resident_pages: usize,
+    pub stats: PartitionStats,
+}
+
+/// O relatório das partições (veja `Mmu::partition_report`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartitionReport {
+    /// Uma linha para cada reserva e, por último, o pool compartilhado.
+    pub rows: Vec<PartitionRow>,
+}
+
+impl PartitionReport {
+    /// A linha da partição, se ela existe.
+    pub fn row(&self, partition: Partition) -> Option<&PartitionRow> {
+        self.rows.iter().find(|row| row.partition == partition)
+    }
+
+    pub fn print_report(&self) {
+        println!("===== Partições de frames =====");
+        println!(
+            "{:<16} {:>7} {:>10} {:>9} {:>8} {:>7}",
+            "partição", "frames", "residentes", "acessos", "faults", "taxa"
+        );
+
+        for row in &self.rows {
+            let name = match row.partition {
+                Partition::Reserved(_) if row.borrow => format!("{} (+)", row.partition),
+                partition => partition.to_string(),
+            };
+
+            println!(
+                "{:<16} {:>7} {:>10} {:>9} {:>8} {:>6.2}%",
+                name,
+                row.frames,
+                row.resident_pages,
+                row.stats.accesses,
+                row.stats.faults,
+                row.stats.fault_rate() * 100.0
+            );
+        }
+        if self.rows.iter().any(|row| row.borrow) {
+            println!("(+) empresta frames do pool compartilhado");
+        }
+    }
+}
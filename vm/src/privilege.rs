@@ -0,0 +1,67 @@
+//! Os modos de privilégio da CPU: supervisor e usuário.
+//!
+//! Como o bit U/S das entradas da page table do x86, cada página pode ser
+//! marcada como só do supervisor (veja `Mmu::set_supervisor_only`). A Mmu
+//! guarda o modo atual da CPU (veja `Mmu::set_mode`), e cada acesso é feito
+//! nesse modo: um acesso em modo usuário a uma página do supervisor é uma
+//! falta de privilégio, e não acontece. É o que separa o kernel dos
+//! processos: o kernel fica mapeado no espaço de endereçamento de todos
+//! eles, mas só pode ser usado depois de uma chamada de sistema, que troca o
+//! modo.
+//!
+//! A CPU começa em modo supervisor, como depois do boot, e nenhuma página é
+//! do supervisor até ser marcada; sem marcar páginas, nada muda.
+//!
+//! A falta é registrada (veja `Mmu::privilege_faults`) e devolvida por
+//! `Mmu::read` e `Mmu::write`, como as falhas do carregador (veja o módulo
+//! `error`). A página não é traduzida, então a falta não conta como acesso,
+//! não causa page fault e não aparece no trace.
+
+use std::fmt;
+
+use crate::trace::AccessKind;
+
+/// O modo de privilégio da CPU.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PrivilegeMode {
+    /// O modo do kernel, que acessa todas as páginas.
+    #[default]
+    Supervisor,
+    /// O modo dos processos, que não acessa as páginas do supervisor.
+    User,
+}
+
+impl fmt::Display for PrivilegeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrivilegeMode::Supervisor => write!(f, "supervisor"),
+            PrivilegeMode::User => write!(f, "usuário"),
+        }
+    }
+}
+
+/// Um acesso em modo usuário a uma página do supervisor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PrivilegeFault {
+    /// Quantos acessos tinham sido feitos antes da falta.
+    pub time: usize,
+    pub kind: AccessKind,
+    pub address: usize,
+    pub page_number: usize,
+}
+
+impl fmt::Display for PrivilegeFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            AccessKind::Read => "leitura",
+            AccessKind::Write => "escrita",
+            AccessKind::Fetch => "busca de instrução",
+        };
+
+        write!(
+            f,
+            "depois do acesso #{}: {} em {:#06X}, em modo usuário, na página {:#04X}, que é do supervisor",
+            self.time, kind, self.address, self.page_number
+        )
+    }
+}
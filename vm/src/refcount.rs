@@ -0,0 +1,147 @@
+//! Contagem de referências dos frames.
+//!
+//! Com o compartilhamento de frames (veja o módulo `sharing`), um frame pode
+//! estar mapeado em várias páginas, e só pode voltar para a lista de frames
+//! livres quando a última delas sai dele. Como no `struct page` do Linux,
+//! cada frame tem um contador com o número de entradas da page table que
+//! apontam para ele.
+//!
+//! Quase todo frame tem 0 ou 1 referência, então o contador de cada frame
+//! ocupa um byte só. Um frame com `u8::MAX` referências ou mais (um frame de
+//! zeros compartilhado por todos os processos, por exemplo) tem o contador
+//! marcado como "transbordado", e a contagem de verdade fica numa tabela à
+//! parte, que só guarda esses frames.
+//!
+//! Um contador errado é um bug sério: com uma referência a menos, o frame é
+//! liberado enquanto ainda está mapeado; com uma a mais, ele nunca volta
+//! para a lista de livres (um vazamento). Em builds de debug, a Mmu confere
+//! os contadores contra a page table depois de cada operação que mexe neles
+//! (veja `FrameRefCounts::verify`).
+
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+/// O valor do contador de um frame cuja contagem está na tabela de
+/// transbordo.
+const OVERFLOW: u8 = u8::MAX;
+
+/// Um contador diferente do número de referências de verdade.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Error)]
+#[error("frame {frame_index:#04X}: contador em {counted}, mas {actual} páginas apontam para ele")]
+pub struct RefCountMismatch {
+    pub frame_index: usize,
+    /// O valor do contador.
+    pub counted: usize,
+    /// O número de entradas da page table que apontam para o frame.
+    pub actual: usize,
+}
+
+/// Os contadores de referências de todos os frames.
+#[derive(Clone, Debug)]
+pub struct FrameRefCounts {
+    /// Um contador por frame; `OVERFLOW` indica que a contagem está em
+    /// `overflow`.
+    counts: Vec<u8>,
+    /// A contagem dos frames com `OVERFLOW` referências ou mais.
+    overflow: BTreeMap<usize, usize>,
+}
+
+impl FrameRefCounts {
+    /// Constrói os contadores de `frame_count` frames, todos zerados.
+    pub fn new(frame_count: usize) -> Self {
+        FrameRefCounts {
+            counts: vec![0; frame_count],
+            overflow: BTreeMap::new(),
+        }
+    }
+
+    /// O número de referências ao frame.
+    pub fn get(&self, frame_index: usize) -> usize {
+        match self.counts[frame_index] {
+            OVERFLOW => self.overflow[&frame_index],
+            count => count as usize,
+        }
+    }
+
+    /// Conta mais uma referência ao frame, devolvendo a nova contagem.
+    pub fn increment(&mut self, frame_index: usize) -> usize {
+        let count = self.get(frame_index) + 1;
+        self.set(frame_index, count);
+
+        count
+    }
+
+    /// Desconta uma referência ao frame, devolvendo a nova contagem. Um
+    /// frame sem referências é um bug na Mmu, então isso causa um panic.
+    pub fn decrement(&mut self, frame_index: usize) -> usize {
+        let count = self
+            .get(frame_index)
+            .checked_sub(1)
+            .unwrap_or_else(|| panic!("frame {:#04X} sem referências", frame_index));
+        self.set(frame_index, count);
+
+        count
+    }
+
+    fn set(&mut self, frame_index: usize, count: usize) {
+        if count >= OVERFLOW as usize {
+            self.counts[frame_index] = OVERFLOW;
+            self.overflow.insert(frame_index, count);
+        } else {
+            self.counts[frame_index] = count as u8;
+            self.overflow.remove(&frame_index);
+        }
+    }
+
+    /// Quantos frames estão na tabela de transbordo.
+    pub fn overflowed(&self) -> usize {
+        self.overflow.len()
+    }
+
+    /// Quantos frames têm pelo menos uma referência.
+    pub fn frames_in_use(&self) -> usize {
+        self.counts.iter().filter(|&&count| count > 0).count()
+    }
+
+    /// Move os contadores de frames, depois de uma compactação: cada
+    /// `(antigo, novo)` leva a contagem do frame antigo para o novo. Os
+    /// frames antigos que não são destino de nenhum movimento ficam zerados.
+    pub fn move_frames(&mut self, moves: &[(usize, usize)]) {
+        let counts: Vec<_> = moves
+            .iter()
+            .map(|&(old, _)| {
+                let count = self.get(old);
+                self.set(old, 0);
+                count
+            })
+            .collect();
+
+        for (&(_, new), count) in moves.iter().zip(counts) {
+            self.set(new, count);
+        }
+    }
+
+    /// Confere os contadores contra `frames`, o frame de cada entrada válida
+    /// da page table, devolvendo o primeiro contador errado.
+    pub fn verify(&self, frames: impl IntoIterator<Item = usize>) -> Result<(), RefCountMismatch> {
+        let mut actual = vec![0; self.counts.len()];
+        for frame_index in frames {
+            actual[frame_index] += 1;
+        }
+
+        for (frame_index, &actual) in actual.iter().enumerate() {
+            let counted = self.get(frame_index);
+
+            if counted != actual {
+                return Err(RefCountMismatch {
+                    frame_index,
+                    counted,
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,480 @@
+//! Regiões: intervalos nomeados de páginas virtuais.
+//!
+//! Cada região pertence a um processo, identificado por um `Pid`. Não há
+//! (ainda) um espaço de endereçamento por processo: as regiões de todos os
+//! processos dividem as mesmas páginas virtuais, e o `Pid` serve para
+//! contabilizar o uso de memória de cada um.
+//!
+//! Uma região é mapeada (`Mmu::map_region`) e depois desmapeada
+//! (`Mmu::unmap_region`), como uma chamada a `mmap`/`munmap`. O mapa de regiões
+//! guarda o histórico completo, inclusive das regiões já desmapeadas, para que
+//! um acesso inválido possa ser explicado: "a página pertencia à região 'heap',
+//! mapeada no acesso #3 e desmapeada no acesso #12".
+//!
+//! Cada região também declara suas permissões e conta as leituras, escritas
+//! e buscas de instrução feitas nela. As permissões da região não são
+//! impostas: o `PermissionReport` do fim da execução mostra os acessos que
+//! as contrariam ("a região 'code' foi escrita 3 vezes"), para achar
+//! escritas acidentais num programa. Para impor as mesmas permissões a
+//! páginas, com uma falta de proteção, veja `Mmu::set_protection`.
+//!
+//! Por fim, cada região tem uma *swappiness*, de 0 a 100, que diz o quanto
+//! as suas páginas podem ser escolhidas como vítimas de substituição, como
+//! um `mlock` parcial: uma região com swappiness 25 só aceita uma a cada
+//! quatro escolhas do replacer; nas outras, a página ganha mais uma chance
+//! (veja `RegionMap::accept_victim`). Com 100, o padrão, o replacer decide
+//! sozinho.
+
+use std::{fmt, ops::Range};
+
+use thiserror::Error;
+
+use crate::trace::AccessKind;
+
+/// O identificador de um processo.
+pub type Pid = usize;
+
+/// A swappiness de uma região recém-mapeada: suas páginas são substituídas
+/// sempre que o replacer as escolhe.
+pub const DEFAULT_SWAPPINESS: usize = 100;
+
+/// Uma região que não pôde ser mapeada.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum RegionError {
+    /// As páginas passam do espaço de endereçamento da Mmu.
+    #[error("as páginas {pages:#04X?} passam das {page_count} páginas do espaço de endereçamento")]
+    OutOfRange {
+        pages: Range<usize>,
+        /// Quantas páginas a Mmu tem, `PAGE_COUNT`.
+        page_count: usize,
+    },
+}
+
+/// As permissões declaradas de uma região, ou a proteção de uma página
+/// (veja `Mmu::set_protection`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Permissions {
+    pub const NONE: Permissions = Permissions {
+        read: false,
+        write: false,
+        execute: false,
+    };
+    pub const READ_ONLY: Permissions = Permissions {
+        read: true,
+        write: false,
+        execute: false,
+    };
+    pub const READ_WRITE: Permissions = Permissions {
+        read: true,
+        write: true,
+        execute: false,
+    };
+    pub const READ_EXECUTE: Permissions = Permissions {
+        read: true,
+        write: false,
+        execute: true,
+    };
+    pub const ALL: Permissions = Permissions {
+        read: true,
+        write: true,
+        execute: true,
+    };
+
+    /// Lê permissões no formato do `ls`/`/proc/<pid>/maps`, como `r-x` ou
+    /// `rw-`. O `x` pode ser omitido (`rw` é `rw-`), e `r`, `w`, `x` e `-`
+    /// sozinhos também valem.
+    pub fn parse(text: &str) -> Option<Permissions> {
+        let flags: Vec<char> = match text {
+            "r" => vec!['r'],
+            "w" => vec!['-', 'w'],
+            "x" => vec!['-', '-', 'x'],
+            "-" => vec!['-'],
+            _ if (2..=3).contains(&text.chars().count()) => text.chars().collect(),
+            _ => return None,
+        };
+
+        let flag = |i: usize, letter: char| match flags.get(i) {
+            Some(&flag) if flag == letter => Some(true),
+            Some('-') | None => Some(false),
+            Some(_) => None,
+        };
+
+        Some(Permissions {
+            read: flag(0, 'r')?,
+            write: flag(1, 'w')?,
+            execute: flag(2, 'x')?,
+        })
+    }
+
+    /// Se o tipo de acesso é permitido.
+    pub fn allows(&self, kind: AccessKind) -> bool {
+        match kind {
+            AccessKind::Read => self.read,
+            AccessKind::Write => self.write,
+            AccessKind::Fetch => self.execute,
+        }
+    }
+}
+
+/// Tudo permitido: a proteção de uma página até `Mmu::set_protection`.
+impl Default for Permissions {
+    fn default() -> Self {
+        Permissions::ALL
+    }
+}
+
+impl fmt::Display for Permissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            if self.read { 'r' } else { '-' },
+            if self.write { 'w' } else { '-' },
+            if self.execute { 'x' } else { '-' }
+        )
+    }
+}
+
+/// Uma região de páginas virtuais e seu ciclo de vida.
+#[derive(Clone, Debug)]
+pub struct Region {
+    /// O processo dono da região.
+    pub pid: Pid,
+    /// O nome da região.
+    pub name: String,
+    /// As páginas da região.
+    pub pages: Range<usize>,
+    /// O acesso (tempo virtual) no qual a região foi mapeada.
+    pub mapped_at: usize,
+    /// O acesso no qual a região foi desmapeada, se já foi.
+    pub unmapped_at: Option<usize>,
+    /// As permissões declaradas no mapeamento.
+    pub permissions: Permissions,
+    /// Quantas leituras foram feitas na região enquanto ela estava viva.
+    pub reads: usize,
+    /// Quantas escritas foram feitas na região enquanto ela estava viva.
+    pub writes: usize,
+    /// Quantas buscas de instrução foram feitas na região enquanto ela
+    /// estava viva.
+    pub fetches: usize,
+    /// O quanto as páginas da região podem ser substituídas, de 0 a 100.
+    pub swappiness: usize,
+    /// A swappiness acumulada desde a última página da região aceita como
+    /// vítima; uma página é aceita quando o crédito chega a 100.
+    pub swap_credit: usize,
+    /// Quantas vezes uma página da região escolhida pelo replacer foi
+    /// poupada pela swappiness.
+    pub spared: usize,
+}
+
+impl Region {
+    /// Se a região ainda está mapeada.
+    pub fn is_live(&self) -> bool {
+        self.unmapped_at.is_none()
+    }
+
+    /// Quantos acessos do tipo foram feitos na região.
+    pub fn accesses(&self, kind: AccessKind) -> usize {
+        match kind {
+            AccessKind::Read => self.reads,
+            AccessKind::Write => self.writes,
+            AccessKind::Fetch => self.fetches,
+        }
+    }
+}
+
+/// Todas as regiões já mapeadas, em ordem de mapeamento.
+#[derive(Default, Clone)]
+pub struct RegionMap {
+    regions: Vec<Region>,
+}
+
+impl RegionMap {
+    /// Constrói um mapa vazio.
+    pub fn new() -> Self {
+        RegionMap {
+            regions: Vec::new(),
+        }
+    }
+
+    /// Mapeia uma nova região.
+    pub fn map(
+        &mut self,
+        pid: Pid,
+        name: &str,
+        pages: Range<usize>,
+        permissions: Permissions,
+        time: usize,
+    ) {
+        self.regions.push(Region {
+            pid,
+            name: name.to_string(),
+            pages,
+            mapped_at: time,
+            unmapped_at: None,
+            permissions,
+            reads: 0,
+            writes: 0,
+            fetches: 0,
+            swappiness: DEFAULT_SWAPPINESS,
+            swap_credit: 0,
+            spared: 0,
+        });
+    }
+
+    /// Muda a swappiness da região viva do processo com esse nome, limitada
+    /// a 100. Devolve se a região existe.
+    pub fn set_swappiness(&mut self, pid: Pid, name: &str, swappiness: usize) -> bool {
+        let Some(region) = self
+            .regions
+            .iter_mut()
+            .find(|region| region.is_live() && region.pid == pid && region.name == name)
+        else {
+            return false;
+        };
+
+        region.swappiness = swappiness.min(100);
+        region.swap_credit = 0;
+
+        true
+    }
+
+    /// Muda as páginas da região viva do processo com esse nome, como o
+    /// `brk` faz com o heap e uma pilha que cresce, devolvendo as páginas
+    /// que ela tinha.
+    pub fn resize(&mut self, pid: Pid, name: &str, pages: Range<usize>) -> Option<Range<usize>> {
+        let region = self
+            .regions
+            .iter_mut()
+            .find(|region| region.is_live() && region.pid == pid && region.name == name)?;
+
+        Some(std::mem::replace(&mut region.pages, pages))
+    }
+
+    /// Decide se a página escolhida pelo replacer pode mesmo ser substituída.
+    /// A cada escolha, a região da página acumula a sua swappiness; a página
+    /// é aceita quando o acumulado chega a 100, e poupada caso contrário.
+    /// Assim, uma região com swappiness `s` aceita `s` de cada 100 escolhas,
+    /// sem sorteio. Páginas fora de regiões vivas são sempre aceitas.
+    pub fn accept_victim(&mut self, page_number: usize) -> bool {
+        let Some(region) = self
+            .regions
+            .iter_mut()
+            .find(|region| region.is_live() && region.pages.contains(&page_number))
+        else {
+            return true;
+        };
+
+        region.swap_credit += region.swappiness;
+
+        if region.swap_credit >= 100 {
+            region.swap_credit -= 100;
+            true
+        } else {
+            region.spared += 1;
+            false
+        }
+    }
+
+    /// Conta um acesso na região viva que contém a página, se existe.
+    pub fn record_access(&mut self, page_number: usize, kind: AccessKind) {
+        let Some(region) = self
+            .regions
+            .iter_mut()
+            .find(|region| region.is_live() && region.pages.contains(&page_number))
+        else {
+            return;
+        };
+
+        match kind {
+            AccessKind::Read => region.reads += 1,
+            AccessKind::Write => region.writes += 1,
+            AccessKind::Fetch => region.fetches += 1,
+        }
+    }
+
+    /// Desmapeia a região viva do processo com esse nome, devolvendo suas
+    /// páginas.
+    pub fn unmap(&mut self, pid: Pid, name: &str, time: usize) -> Option<Range<usize>> {
+        let region = self
+            .regions
+            .iter_mut()
+            .find(|region| region.is_live() && region.pid == pid && region.name == name)?;
+
+        region.unmapped_at = Some(time);
+
+        Some(region.pages.clone())
+    }
+
+    /// A região viva que contém a página, se existe.
+    pub fn find_live(&self, page_number: usize) -> Option<&Region> {
+        self.regions
+            .iter()
+            .find(|region| region.is_live() && region.pages.contains(&page_number))
+    }
+
+    /// A região mais recente (viva ou não) que contém a página.
+    pub fn find_latest(&self, page_number: usize) -> Option<&Region> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|region| region.pages.contains(&page_number))
+    }
+
+    /// Itera sobre todas as regiões, vivas ou não.
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter()
+    }
+
+    /// Os processos que têm pelo menos uma região viva, em ordem.
+    pub fn pids(&self) -> Vec<Pid> {
+        let mut pids: Vec<_> = self
+            .regions
+            .iter()
+            .filter(|region| region.is_live())
+            .map(|region| region.pid)
+            .collect();
+
+        pids.sort();
+        pids.dedup();
+
+        pids
+    }
+}
+
+/// O relatório de permissões: os acessos feitos em cada região comparados
+/// com as permissões declaradas.
+pub struct PermissionReport {
+    /// Todas as regiões, vivas ou não, em ordem de mapeamento.
+    pub regions: Vec<Region>,
+}
+
+impl PermissionReport {
+    /// Monta o relatório a partir do mapa de regiões.
+    pub fn from_regions(regions: &RegionMap) -> Self {
+        PermissionReport {
+            regions: regions.iter().cloned().collect(),
+        }
+    }
+
+    /// O total de acessos não permitidos, somando todas as regiões.
+    pub fn violations(&self) -> usize {
+        self.regions
+            .iter()
+            .flat_map(|region| {
+                [AccessKind::Read, AccessKind::Write, AccessKind::Fetch]
+                    .into_iter()
+                    .filter(|kind| !region.permissions.allows(*kind))
+                    .map(|kind| region.accesses(kind))
+            })
+            .sum()
+    }
+
+    pub fn print_report(&self) {
+        println!("===== Permissões =====");
+
+        for region in &self.regions {
+            println!(
+                "processo {}, região '{}' ({}): {} leituras, {} escritas, {} buscas",
+                region.pid,
+                region.name,
+                region.permissions,
+                region.reads,
+                region.writes,
+                region.fetches
+            );
+
+            for (kind, verb) in [
+                (AccessKind::Read, "lida"),
+                (AccessKind::Write, "escrita"),
+                (AccessKind::Fetch, "executada"),
+            ] {
+                let count = region.accesses(kind);
+
+                if count > 0 && !region.permissions.allows(kind) {
+                    println!(
+                        "  ! a região '{}' foi {} {} vezes sem permissão",
+                        region.name, verb, count
+                    );
+                }
+            }
+        }
+
+        println!("Acessos não permitidos: {}", self.violations());
+    }
+}
+
+/// Um acesso a uma página que não pertence a nenhuma região viva, detectado
+/// no modo estrito.
+#[derive(Clone, Debug)]
+pub struct AccessViolation {
+    /// O acesso no qual a violação aconteceu.
+    pub time: usize,
+    /// Se foi uma leitura ou escrita.
+    pub kind: AccessKind,
+    /// O endereço acessado.
+    pub address: usize,
+    /// A região que continha a página mais recentemente, se alguma. `None`
+    /// significa que a página nunca foi mapeada.
+    pub region: Option<Region>,
+}
+
+impl fmt::Display for AccessViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            AccessKind::Read => "leitura",
+            AccessKind::Write => "escrita",
+            AccessKind::Fetch => "busca de instrução",
+        };
+
+        write!(
+            f,
+            "acesso #{}: {} em {:#06X} ",
+            self.time, kind, self.address
+        )?;
+
+        match &self.region {
+            Some(region) => write!(
+                f,
+                "após unmap da região '{}' do processo {} (mapeada no acesso #{}, desmapeada no acesso #{})",
+                region.name,
+                region.pid,
+                region.mapped_at,
+                region.unmapped_at.unwrap_or_default()
+            ),
+            None => write!(f, "em página nunca mapeada"),
+        }
+    }
+}
+
+/// O uso de memória de um processo.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProcessStats {
+    /// Tamanho virtual: páginas em regiões vivas do processo.
+    pub virtual_pages: usize,
+    /// Resident set size: dessas páginas, quantas estão em memória.
+    pub resident_pages: usize,
+    /// Dessas páginas em memória, quantas estão em frames compartilhados
+    /// com copy-on-write.
+    pub shared_pages: usize,
+    /// O tamanho de uma página, para converter as contagens em bytes.
+    pub page_size: usize,
+}
+
+impl ProcessStats {
+    /// O tamanho virtual (VSZ) em bytes.
+    pub fn virtual_size(&self) -> usize {
+        self.virtual_pages * self.page_size
+    }
+
+    /// O resident set size (RSS) em bytes.
+    pub fn resident_set_size(&self) -> usize {
+        self.resident_pages * self.page_size
+    }
+}
@@ -0,0 +1,138 @@
+//! Um gerador de números pseudo-aleatórios determinístico.
+//!
+//! Toda parte estocástica da simulação (geradores de carga, replacers
+//! aleatórios...) recebe um `Rng` construído a partir de uma única semente
+//! `u64`. Com a mesma semente, a mesma execução produz exatamente os mesmos
+//! acessos, as mesmas substituições e as mesmas estatísticas, o que é
+//! essencial para comparar resultados (e para resolver dúvidas na correção
+//! dos trabalhos). Componentes novos (injeção de falhas, ASLR...) devem
+//! seguir o mesmo esquema: receber a semente e derivar o próprio `Rng` com
+//! `Rng::for_component`.
+//!
+//! Não usamos a crate `rand` de propósito: o algoritmo (SplitMix64) é fixo e
+//! documentado, então a sequência gerada não muda com atualizações de
+//! dependências.
+//!
+//! Pelo mesmo motivo, os `HashMap` da crate não dependem da ordem de
+//! iteração para nada que sai numa estatística, num trace ou num arquivo:
+//! quem precisa de ordem usa um `BTreeMap` ou ordena as chaves. O
+//! `RandomState` da biblioteca padrão sorteia as chaves do hash a cada
+//! execução; `SeededState` é a opção determinística, para quem quer que até
+//! a ordem interna de um `HashMap` (e, com ela, o tempo de cada operação) se
+//! repita byte a byte entre execuções (veja `MemoryPageLoader::with_hasher`).
+
+use std::hash::{BuildHasher, Hasher};
+
+/// Um gerador SplitMix64.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Constrói um gerador a partir de uma semente.
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Constrói um gerador independente para um componente, a partir da
+    /// semente da execução e de um identificador do componente. Assim cada
+    /// componente tem sua própria sequência, e adicionar um componente novo
+    /// não muda a sequência dos outros.
+    pub fn for_component(seed: u64, component: &str) -> Self {
+        // FNV-1a do nome do componente, misturado à semente.
+        let hash = component.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+        });
+
+        let mut rng = Rng::new(seed ^ hash);
+        Rng::new(rng.next_u64())
+    }
+
+    /// O próximo número da sequência.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Um número uniforme em `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        // Os 53 bits mais altos, que cabem exatamente na mantissa.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Um número uniforme em `[0, n)`. `n` deve ser maior que zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize
+    }
+
+    /// `true` com probabilidade `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+}
+
+/// Um `BuildHasher` determinístico: os hashes dependem só da semente e dos
+/// dados, e não mudam entre execuções, plataformas ou versões do Rust (o
+/// algoritmo é o FNV-1a com a semente misturada, finalizado pelo
+/// SplitMix64). Não resiste a ataques de colisão, o que não importa numa
+/// simulação.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SeededState {
+    seed: u64,
+}
+
+impl SeededState {
+    /// Constrói o estado a partir da semente da execução.
+    pub fn new(seed: u64) -> Self {
+        SeededState {
+            seed: Rng::for_component(seed, "hash").next_u64(),
+        }
+    }
+}
+
+impl BuildHasher for SeededState {
+    type Hasher = SeededHasher;
+
+    fn build_hasher(&self) -> SeededHasher {
+        SeededHasher {
+            state: 0xCBF2_9CE4_8422_2325 ^ self.seed,
+        }
+    }
+}
+
+/// O `Hasher` de `SeededState`.
+#[derive(Clone, Debug)]
+pub struct SeededHasher {
+    state: u64,
+}
+
+impl Hasher for SeededHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state = (self.state ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    // Os inteiros entram sempre como 8 bytes little-endian, para que o hash
+    // seja o mesmo em qualquer plataforma.
+    fn write_u32(&mut self, value: u32) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        Rng::new(self.state).next_u64()
+    }
+}
@@ -0,0 +1,1838 @@
+//! Cenários prontos para demonstrações em aula.
+//!
+//! Cada cenário monta sua própria Mmu (com um `MemoryPageLoader`, para não
+//! depender do arquivo swap), executa uma sequência de passos e devolve um
+//! relatório que pode ser impresso pela demo.
+
+use std::ops::Range;
+
+use crate::{
+    cost::CostModel,
+    histogram::Histogram,
+    mmu::{Mmu, MmuStats},
+    nested::{AddressSpace, NestedMmu, NestedStats},
+    page_loader::{MemoryPageLoader, PageLoader},
+    page_replacer::{
+        AdaptivePageReplacer, AgingPageReplacer, ClockPageReplacer, ClockProPageReplacer,
+        FIFOPageReplacer, KRefClockReplacer, LFUPageReplacer, LRUKPageReplacer, LRUPageReplacer,
+        MFUPageReplacer, MRUPageReplacer, NRUPageReplacer, OptimalPageReplacer, PageReplacer,
+        RandomPageReplacer, S3FIFOPageReplacer, SLRUPageReplacer, TwoQPageReplacer,
+        WSClockPageReplacer, WorkingSetPageReplacer,
+    },
+    partition::{PartitionReport, PartitionStats, Reservation},
+    rng::Rng,
+    shadow::{ShadowMmu, ShadowStats},
+    thp::HUGE_PAGE_PAGES,
+    three_cs::MissBreakdown,
+    tier::{PromotionPolicy, TierConfig, TierReport},
+    tlb::TlbCounts,
+    trace::AccessKind,
+    workload::{Access, Pattern, WorkloadGenerator},
+};
+
+/// Uma região nomeada de páginas virtuais.
+struct Region {
+    name: char,
+    pages: Range<usize>,
+}
+
+impl Region {
+    fn new(name: char, first_page: usize, page_count: usize) -> Self {
+        Region {
+            name,
+            pages: first_page..first_page + page_count,
+        }
+    }
+}
+
+/// O estado da memória física em um momento do cenário de fragmentação.
+pub struct FragmentationSnapshot {
+    /// Uma descrição do passo.
+    pub label: String,
+    /// Um caractere por frame: o nome da região que o ocupa, ou `.` se livre.
+    pub heatmap: String,
+    /// Para cada região viva, em quantos trechos de frames contíguos ela está
+    /// espalhada. 1 significa que a região está contígua na memória física.
+    pub region_runs: Vec<(char, usize)>,
+    /// O número de frames livres.
+    pub free_frames: usize,
+    /// O maior trecho de frames livres contíguos.
+    pub largest_free_run: usize,
+}
+
+impl FragmentationSnapshot {
+    fn take(label: &str, frame_map: &[Option<usize>], regions: &[Region]) -> Self {
+        let region_of = |page: usize| regions.iter().find(|region| region.pages.contains(&page));
+
+        let heatmap = frame_map
+            .iter()
+            .map(|frame| match frame.and_then(region_of) {
+                Some(region) => region.name,
+                None => '.',
+            })
+            .collect();
+
+        let region_runs = regions
+            .iter()
+            .map(|region| {
+                // Um trecho começa em cada página da região que não está no
+                // frame seguinte ao da página anterior.
+                let mut frames: Vec<_> = frame_map
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, page)| page.is_some_and(|page| region.pages.contains(&page)))
+                    .map(|(frame_idx, _)| frame_idx)
+                    .collect();
+                frames.sort();
+
+                let runs = frames
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, frame)| *i == 0 || frames[i - 1] + 1 != **frame)
+                    .count();
+
+                (region.name, runs)
+            })
+            .collect();
+
+        let mut largest_free_run = 0;
+        let mut current_run = 0;
+        for frame in frame_map {
+            if frame.is_none() {
+                current_run += 1;
+                largest_free_run = largest_free_run.max(current_run);
+            } else {
+                current_run = 0;
+            }
+        }
+
+        FragmentationSnapshot {
+            label: label.to_string(),
+            heatmap,
+            region_runs,
+            free_frames: frame_map.iter().filter(|frame| frame.is_none()).count(),
+            largest_free_run,
+        }
+    }
+}
+
+/// O relatório do cenário de fragmentação.
+pub struct DefragReport {
+    /// O estado da memória física depois de cada passo.
+    pub snapshots: Vec<FragmentationSnapshot>,
+    /// Se todas as páginas mantiveram seu conteúdo depois da compactação.
+    pub contents_preserved: bool,
+}
+
+impl DefragReport {
+    pub fn print_report(&self) {
+        println!("===== Cenário: fragmentação e compactação =====");
+
+        for snapshot in &self.snapshots {
+            println!("{}", snapshot.label);
+            println!("  frames: [{}]", snapshot.heatmap);
+
+            let runs: Vec<_> = snapshot
+                .region_runs
+                .iter()
+                .map(|(name, runs)| format!("{}={}", name, runs))
+                .collect();
+            println!("  trechos por região: {}", runs.join(" "));
+            println!(
+                "  frames livres: {} (maior trecho contíguo: {})",
+                snapshot.free_frames, snapshot.largest_free_run
+            );
+        }
+
+        println!(
+            "Conteúdo preservado após compactação: {}",
+            if self.contents_preserved {
+                "sim"
+            } else {
+                "NÃO"
+            }
+        );
+    }
+}
+
+/// Cenário de fragmentação da memória física:
+///
+/// 1. aloca quatro regiões de 3 páginas (A, B, C, D), que ocupam frames
+///    contíguos;
+/// 2. libera B e D, abrindo buracos;
+/// 3. aloca uma região maior (E, 6 páginas), que só cabe espalhada pelos
+///    buracos;
+/// 4. compacta a memória, deixando cada região contígua e todos os frames
+///    livres juntos no final.
+///
+/// Cada página é escrita com um byte próprio, e no final o cenário verifica
+/// que a compactação não alterou o conteúdo visto pelos endereços virtuais.
+pub fn defragmentation() -> DefragReport {
+    let mut mmu =
+        Mmu::<{ 16 * 256 }, 16, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+
+    let mut regions = vec![
+        Region::new('A', 0x10, 3),
+        Region::new('B', 0x20, 3),
+        Region::new('C', 0x30, 3),
+        Region::new('D', 0x40, 3),
+    ];
+
+    let touch_region = |mmu: &mut Mmu<{ 16 * 256 }, 16, 256, _, _>, region: &Region| {
+        for page in region.pages.clone() {
+            mmu.write(page << 8, page as u8).unwrap();
+        }
+    };
+
+    let mut snapshots = Vec::new();
+
+    for region in &regions {
+        touch_region(&mut mmu, region);
+    }
+    snapshots.push(FragmentationSnapshot::take(
+        "1. alocadas A, B, C e D",
+        &mmu.frame_map(),
+        &regions,
+    ));
+
+    for name in ['B', 'D'] {
+        let region = regions.iter().find(|region| region.name == name).unwrap();
+        for page in region.pages.clone() {
+            mmu.unmap_page(page);
+        }
+    }
+    regions.retain(|region| region.name != 'B' && region.name != 'D');
+    snapshots.push(FragmentationSnapshot::take(
+        "2. liberadas B e D",
+        &mmu.frame_map(),
+        &regions,
+    ));
+
+    let region_e = Region::new('E', 0x50, 6);
+    touch_region(&mut mmu, &region_e);
+    regions.push(region_e);
+    snapshots.push(FragmentationSnapshot::take(
+        "3. alocada E",
+        &mmu.frame_map(),
+        &regions,
+    ));
+
+    mmu.compact();
+    snapshots.push(FragmentationSnapshot::take(
+        "4. compactada",
+        &mmu.frame_map(),
+        &regions,
+    ));
+
+    let contents_preserved = regions
+        .iter()
+        .flat_map(|region| region.pages.clone())
+        .all(|page| mmu.read(page << 8) == Ok(page as u8));
+
+    DefragReport {
+        snapshots,
+        contents_preserved,
+    }
+}
+
+/// O relatório do cenário de virtualização.
+pub struct VirtualizationReport {
+    /// As estatísticas da execução com paginação aninhada.
+    pub nested: NestedStats,
+    /// As estatísticas da mesma carga com shadow page tables.
+    pub shadow: ShadowStats,
+    /// O tempo efetivo de acesso sem virtualização: só o walk e as faults do
+    /// guest, como se ele rodasse direto no hardware.
+    pub native_eat: f64,
+    /// O tempo efetivo de acesso com o walk em duas dimensões e as faults
+    /// dos dois estágios.
+    pub nested_eat: f64,
+    /// O tempo efetivo de acesso com o walk pela shadow e os VM exits para
+    /// mantê-la.
+    pub shadow_eat: f64,
+}
+
+impl VirtualizationReport {
+    pub fn print_report(&self) {
+        println!("===== Cenário: paginação aninhada x shadow page tables =====");
+        self.nested.print_stats();
+        self.shadow.print_stats();
+        println!(
+            "Tempo efetivo de acesso nativo:   {:.2} ns",
+            self.native_eat
+        );
+        println!(
+            "Tempo efetivo de acesso aninhado: {:.2} ns",
+            self.nested_eat
+        );
+        println!(
+            "Tempo efetivo de acesso shadow:   {:.2} ns",
+            self.shadow_eat
+        );
+    }
+}
+
+/// Um guest com 16 frames e um host com só 8 frames para manter os 16
+/// frames do guest.
+fn virtualization_mmus() -> (impl AddressSpace, impl AddressSpace) {
+    let guest =
+        Mmu::<{ 16 * 256 }, 16, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    let host =
+        Mmu::<{ 8 * 256 }, 8, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+
+    (guest, host)
+}
+
+/// Cenário de virtualização: o guest executa uma carga Zipf sobre 64
+/// páginas, primeiro com paginação aninhada e depois com shadow page tables
+/// (veja `virtualization_mmus`). As page tables têm 4 níveis, como no
+/// x86-64.
+pub fn virtualization() -> VirtualizationReport {
+    let accesses =
+        WorkloadGenerator::new(0, Pattern::Zipf { exponent: 1.2 }, 64, 256).generate(4096);
+
+    let (guest, host) = virtualization_mmus();
+    let mut nested = NestedMmu::new(guest, host);
+
+    for access in &accesses {
+        match access.kind {
+            AccessKind::Read | AccessKind::Fetch => {
+                nested.read(access.address).unwrap();
+            }
+            AccessKind::Write => nested.write(access.address, access.value).unwrap(),
+        }
+    }
+
+    let (guest, host) = virtualization_mmus();
+    let mut shadow = ShadowMmu::new(guest, host);
+
+    for access in &accesses {
+        match access.kind {
+            AccessKind::Read | AccessKind::Fetch => {
+                shadow.read(access.address).unwrap();
+            }
+            AccessKind::Write => shadow.write(access.address, access.value).unwrap(),
+        }
+    }
+
+    let cost = CostModel {
+        page_table_levels: 4,
+        ..CostModel::default()
+    };
+
+    VirtualizationReport {
+        nested: nested.stats(),
+        shadow: shadow.stats(),
+        native_eat: nested.guest().stats().effective_access_time(&cost),
+        nested_eat: nested.stats().effective_access_time(&cost, &cost),
+        shadow_eat: shadow.stats().effective_access_time(&cost, &cost),
+    }
+}
+
+/// Uma rodada de cópia da migração ao vivo.
+pub struct MigrationRound {
+    /// Quantas páginas foram copiadas na rodada.
+    pub pages_copied: usize,
+    /// Quantas páginas o guest escreveu enquanto a rodada copiava.
+    pub pages_dirtied: usize,
+}
+
+/// O relatório de uma migração ao vivo.
+pub struct MigrationReport {
+    /// As rodadas de pré-cópia, com o guest rodando.
+    pub rounds: Vec<MigrationRound>,
+    /// Se o número de páginas sujas caiu até o limite antes do máximo de
+    /// rodadas.
+    pub converged: bool,
+    /// Quantas páginas foram copiadas com o guest parado, no final.
+    pub stop_and_copy_pages: usize,
+    /// Se a memória no destino ficou igual à da origem.
+    pub contents_match: bool,
+    /// Quantos frames do guest o balão liberou.
+    pub balloon_frames_freed: usize,
+}
+
+impl MigrationReport {
+    /// Quantas páginas foram enviadas ao todo, contando as cópias repetidas.
+    pub fn total_pages_copied(&self) -> usize {
+        self.rounds
+            .iter()
+            .map(|round| round.pages_copied)
+            .sum::<usize>()
+            + self.stop_and_copy_pages
+    }
+
+    /// O tempo com o guest parado, em nanossegundos: enviar as páginas do
+    /// stop-and-copy.
+    pub fn downtime(&self, cost: &CostModel) -> f64 {
+        self.stop_and_copy_pages as f64 * cost.page_transfer
+    }
+
+    /// O tempo total da migração, em nanossegundos.
+    pub fn total_time(&self, cost: &CostModel) -> f64 {
+        self.total_pages_copied() as f64 * cost.page_transfer
+    }
+
+    pub fn print_report(&self) {
+        println!("===== Cenário: migração ao vivo =====");
+
+        for (i, round) in self.rounds.iter().enumerate() {
+            println!(
+                "rodada {:>2}: {:>3} páginas copiadas, {:>3} sujas durante a cópia",
+                i, round.pages_copied, round.pages_dirtied
+            );
+        }
+
+        println!(
+            "{} após {} rodadas",
+            if self.converged {
+                "Convergiu"
+            } else {
+                "NÃO convergiu"
+            },
+            self.rounds.len()
+        );
+        println!(
+            "Páginas copiadas com o guest parado: {}",
+            self.stop_and_copy_pages
+        );
+        println!(
+            "Memória no destino igual à da origem: {}",
+            if self.contents_match { "sim" } else { "NÃO" }
+        );
+
+        if self.balloon_frames_freed > 0 {
+            println!("Frames liberados pelo balão: {}", self.balloon_frames_freed);
+        }
+    }
+}
+
+/// As páginas de memória do guest nos cenários de migração.
+const MIGRATION_PAGES: usize = 32;
+
+/// Migra um guest por pré-cópia. O guest (16 frames) executa uma carga
+/// hot/cold, com a fração de escritas dada, sobre as páginas que não foram
+/// devolvidas ao balão, enquanto o hypervisor:
+///
+/// 1. infla o balão: o guest, que já usou todas as páginas, devolve
+///    `balloon_pages` delas (as últimas, ainda residentes), cujos frames
+///    ficam livres e que não precisam ser copiadas;
+/// 2. copia todas as outras páginas para o destino;
+/// 3. pega as páginas escritas durante a cópia
+///    (`Mmu::collect_and_clear_dirty_bitmap`) e as copia de novo, repetindo
+///    enquanto houver mais que 4 páginas sujas (até 30 rodadas);
+/// 4. para o guest e copia as páginas que sobraram.
+///
+/// Copiar uma página leva o tempo de 8 acessos do guest, então quanto mais
+/// páginas uma rodada copia, mais o guest escreve durante ela.
+fn migrate(write_ratio: f64, balloon_pages: usize) -> MigrationReport {
+    const ACCESSES_PER_PAGE_COPY: usize = 8;
+    const DIRTY_THRESHOLD: usize = 4;
+    const MAX_ROUNDS: usize = 30;
+
+    let pages = MIGRATION_PAGES - balloon_pages;
+
+    let mut mmu =
+        Mmu::<{ 16 * 256 }, 16, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    let mut generator = WorkloadGenerator::new(
+        0,
+        Pattern::HotCold {
+            hot_pages: 4,
+            hot_fraction: 0.9,
+        },
+        pages,
+        256,
+    )
+    .with_write_ratio(write_ratio);
+    let mut destination = MemoryPageLoader::new();
+
+    // O guest usa toda a sua memória antes do balão inflar.
+    for page_number in 0..MIGRATION_PAGES {
+        mmu.write(page_number << 8, page_number as u8).unwrap();
+    }
+
+    let free_frames = |mmu: &Mmu<{ 16 * 256 }, 16, 256, _, _>| {
+        mmu.frame_map().iter().filter(|page| page.is_none()).count()
+    };
+    let free_before = free_frames(&mmu);
+
+    for page_number in pages..MIGRATION_PAGES {
+        mmu.unmap_page(page_number);
+    }
+
+    let balloon_frames_freed = free_frames(&mmu) - free_before;
+    assert!(
+        balloon_pages == 0 || balloon_frames_freed > 0,
+        "o balão de {} páginas não liberou nenhum frame",
+        balloon_pages
+    );
+
+    let mut rounds = Vec::new();
+    let mut to_copy: Vec<usize> = (0..pages).collect();
+
+    // A primeira coleta só zera o registro: a primeira rodada copia tudo.
+    mmu.collect_and_clear_dirty_bitmap();
+
+    loop {
+        for &page_number in &to_copy {
+            destination.flush_page(page_number, &mmu.peek_page(page_number).unwrap());
+
+            for access in generator.generate(ACCESSES_PER_PAGE_COPY) {
+                access.apply(&mut mmu).unwrap();
+            }
+        }
+
+        let dirty = mmu.collect_and_clear_dirty_bitmap();
+
+        rounds.push(MigrationRound {
+            pages_copied: to_copy.len(),
+            pages_dirtied: dirty.count(),
+        });
+
+        to_copy = dirty.iter().collect();
+
+        if to_copy.len() <= DIRTY_THRESHOLD || rounds.len() == MAX_ROUNDS {
+            break;
+        }
+    }
+
+    let converged = to_copy.len() <= DIRTY_THRESHOLD;
+
+    // Stop-and-copy: o guest não roda mais, então nada fica sujo.
+    for &page_number in &to_copy {
+        destination.flush_page(page_number, &mmu.peek_page(page_number).unwrap());
+    }
+
+    let contents_match = (0..pages).all(|page_number| {
+        destination.page(page_number) == Some(&mmu.peek_page(page_number).unwrap()[..])
+    });
+
+    MigrationReport {
+        rounds,
+        converged,
+        stop_and_copy_pages: to_copy.len(),
+        contents_match,
+        balloon_frames_freed,
+    }
+}
+
+/// Cenário de migração ao vivo por pré-cópia (veja `migrate`), sem balão e
+/// com 30% de escritas.
+pub fn live_migration() -> MigrationReport {
+    migrate(0.3, 0)
+}
+
+/// Uma migração do cenário de balão e migração.
+pub struct BalloonMigrationRun {
+    /// A fração dos acessos do guest que são escritas.
+    pub write_ratio: f64,
+    /// Quantas páginas o guest devolveu ao balão antes da migração.
+    pub balloon_pages: usize,
+    /// O resultado da migração.
+    pub migration: MigrationReport,
+}
+
+/// O relatório do cenário de balão e migração.
+pub struct BalloonMigrationReport {
+    /// As migrações, uma para cada combinação de taxa de escrita e balão.
+    pub runs: Vec<BalloonMigrationRun>,
+    /// O modelo de custo usado para estimar os tempos.
+    pub cost: CostModel,
+}
+
+impl BalloonMigrationReport {
+    pub fn print_report(&self) {
+        println!("===== Cenário: balão e migração ao vivo =====");
+        println!(
+            "{:>9} {:>6} {:>8} {:>10} {:>10} {:>14} {:>12}",
+            "escritas", "balão", "rodadas", "convergiu", "enviadas", "tempo total", "downtime"
+        );
+
+        for run in &self.runs {
+            println!(
+                "{:>8.0}% {:>6} {:>8} {:>10} {:>10} {:>11.1} ms {:>9.1} µs",
+                run.write_ratio * 100.0,
+                run.balloon_pages,
+                run.migration.rounds.len(),
+                if run.migration.converged {
+                    "sim"
+                } else {
+                    "não"
+                },
+                run.migration.total_pages_copied(),
+                run.migration.total_time(&self.cost) / 1e6,
+                run.migration.downtime(&self.cost) / 1e3
+            );
+        }
+    }
+}
+
+/// Cenário de balão e migração: migra o mesmo guest (veja `migrate`) com
+/// taxas de escrita diferentes, sem balão e com metade da memória devolvida
+/// ao balão antes, e estima o tempo total e o downtime de cada migração com
+/// o modelo de custo padrão.
+pub fn balloon_migration() -> BalloonMigrationReport {
+    let mut runs = Vec::new();
+
+    for write_ratio in [0.05, 0.2, 0.5, 0.9] {
+        for balloon_pages in [0, MIGRATION_PAGES / 2] {
+            runs.push(BalloonMigrationRun {
+                write_ratio,
+                balloon_pages,
+                migration: migrate(write_ratio, balloon_pages),
+            });
+        }
+    }
+
+    BalloonMigrationReport {
+        runs,
+        cost: CostModel::default(),
+    }
+}
+
+/// As páginas do processo pai no cenário de fork.
+const FORK_PAGES: usize = 32;
+
+/// Um fork do cenário de fork e exec.
+pub struct ForkRun {
+    /// A fração das páginas que o filho escreveu antes do exec.
+    pub touch_fraction: f64,
+    /// Quantas páginas o filho escreveu.
+    pub pages_touched: usize,
+    /// Quantas quebras de copy-on-write as escritas causaram.
+    pub cow_breaks: usize,
+    /// Quantos frames o compartilhamento economizava logo depois do fork.
+    pub saved_after_fork: usize,
+    /// Quantos frames ainda eram economizados no momento do exec.
+    pub saved_before_exec: usize,
+    /// Quantos frames são compartilhados depois do exec (deveria ser 0).
+    pub shared_after_exec: usize,
+    /// Se, no exec, o filho via o conteúdo do pai nas páginas que não
+    /// escreveu, e o pai não via nenhuma escrita do filho.
+    pub contents_match: bool,
+}
+
+/// O relatório do cenário de fork e exec.
+pub struct ForkReport {
+    /// As páginas do processo pai.
+    pub pages: usize,
+    /// Um fork para cada fração de páginas escritas pelo filho.
+    pub runs: Vec<ForkRun>,
+    /// O modelo de custo usado para estimar os tempos.
+    pub cost: CostModel,
+}
+
+impl ForkReport {
+    /// O tempo de um fork que copia todas as páginas na hora, em
+    /// nanossegundos.
+    pub fn eager_copy_time(&self) -> f64 {
+        self.pages as f64 * self.cost.page_copy
+    }
+
+    /// O tempo gasto com as quebras de copy-on-write do fork, em
+    /// nanossegundos.
+    pub fn cow_time(&self, run: &ForkRun) -> f64 {
+        run.cow_breaks as f64 * self.cost.cow_break_time()
+    }
+
+    pub fn print_report(&self) {
+        println!("===== Cenário: fork e exec com copy-on-write =====");
+        println!(
+            "{} páginas no pai; copiar todas no fork levaria {:.1} µs",
+            self.pages,
+            self.eager_copy_time() / 1e3
+        );
+        println!(
+            "Cada quebra de COW custa {:.1} µs, contra {:.1} µs de uma escrita normal",
+            self.cost.cow_break_time() / 1e3,
+            self.cost.memory_access / 1e3
+        );
+        println!(
+            "{:>9} {:>8} {:>8} {:>16} {:>16} {:>12} {:>9}",
+            "escritas",
+            "páginas",
+            "quebras",
+            "economia (fork)",
+            "economia (exec)",
+            "tempo COW",
+            "conteúdo"
+        );
+
+        for run in &self.runs {
+            println!(
+                "{:>8.0}% {:>8} {:>8} {:>9} frames {:>9} frames {:>9.1} µs {:>9}",
+                run.touch_fraction * 100.0,
+                run.pages_touched,
+                run.cow_breaks,
+                run.saved_after_fork,
+                run.saved_before_exec,
+                self.cow_time(run) / 1e3,
+                if run.contents_match { "ok" } else { "ERRO" }
+            );
+
+            if run.shared_after_exec != 0 {
+                println!(
+                    "  {} frames continuaram compartilhados depois do exec!",
+                    run.shared_after_exec
+                );
+            }
+        }
+    }
+}
+
+/// Um fork seguido de exec:
+///
+/// 1. o pai escreve o número de cada uma das suas páginas no primeiro byte
+///    dela;
+/// 2. o fork compartilha todas as páginas do pai com as do filho
+///    (`Mmu::share_page`), sem copiar nada;
+/// 3. o filho escreve uma vez em `touch_fraction` das suas páginas,
+///    escolhidas ao acaso, quebrando o COW de cada uma;
+/// 4. o exec descarta todas as páginas do filho.
+///
+/// A Mmu tem frames para as páginas do pai e do filho, então nenhuma página
+/// é substituída e todas as quebras acham um frame livre.
+fn fork(touch_fraction: f64) -> ForkRun {
+    let mut mmu = Mmu::<{ 2 * FORK_PAGES * 256 }, { 2 * FORK_PAGES }, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+    let mut rng = Rng::for_component(0, "fork");
+    let child = |page_number: usize| FORK_PAGES + page_number;
+
+    for page_number in 0..FORK_PAGES {
+        mmu.write(page_number << 8, page_number as u8).unwrap();
+    }
+
+    for page_number in 0..FORK_PAGES {
+        mmu.share_page(page_number, child(page_number)).unwrap();
+    }
+
+    let saved_after_fork = mmu.stats().saved_frames();
+
+    // Embaralha as páginas (Fisher-Yates) e o filho escreve nas primeiras.
+    let mut pages: Vec<usize> = (0..FORK_PAGES).collect();
+    for i in (1..pages.len()).rev() {
+        pages.swap(i, rng.below(i + 1));
+    }
+
+    let pages_touched = (touch_fraction * FORK_PAGES as f64).round() as usize;
+    let touched = &pages[..pages_touched];
+
+    for &page_number in touched {
+        mmu.write((child(page_number) << 8) + 1, 0xFF).unwrap();
+    }
+
+    let saved_before_exec = mmu.stats().saved_frames();
+
+    let contents_match = (0..FORK_PAGES).all(|page_number| {
+        let parent = mmu.peek_page(page_number).unwrap();
+        let child = mmu.peek_page(child(page_number)).unwrap();
+
+        parent[0] == page_number as u8
+            && parent[1] == 0
+            && child[0] == page_number as u8
+            && child[1]
+                == if touched.contains(&page_number) {
+                    0xFF
+                } else {
+                    0
+                }
+    });
+
+    for page_number in 0..FORK_PAGES {
+        mmu.unmap_page(child(page_number));
+    }
+
+    ForkRun {
+        touch_fraction,
+        pages_touched,
+        cow_breaks: mmu.stats().cow_breaks(),
+        saved_after_fork,
+        saved_before_exec,
+        shared_after_exec: mmu.stats().shared_frames(),
+        contents_match,
+    }
+}
+
+/// Cenário de fork e exec: repete o fork (veja `fork`) com o filho
+/// escrevendo em cada uma das frações de páginas dadas, e compara o tempo
+/// das quebras de COW com o de um fork que copia tudo, pelo modelo de custo
+/// padrão.
+pub fn fork_exec(touch_fractions: &[f64]) -> ForkReport {
+    ForkReport {
+        pages: FORK_PAGES,
+        runs: touch_fractions
+            .iter()
+            .map(|&touch_fraction| fork(touch_fraction.clamp(0.0, 1.0)))
+            .collect(),
+        cost: CostModel::default(),
+    }
+}
+
+/// Uma execução do cenário de swapping de processos.
+pub struct ProcessSwapRun {
+    /// Se o processo voltou com o working set (`true`) ou por demanda.
+    pub prefault: bool,
+    /// Quantas páginas do processo saíram da memória com ele.
+    pub swapped_out_pages: usize,
+    /// Quantas páginas voltaram de uma vez, com o working set.
+    pub prefaulted: usize,
+    /// Quantas page faults, depois da volta, foram em páginas que saíram
+    /// com o processo.
+    pub refaults: usize,
+    /// Quantas page faults o processo teve depois da volta.
+    pub misses_after: usize,
+}
+
+/// O relatório do cenário de swapping de processos.
+pub struct ProcessSwapReport {
+    pub runs: Vec<ProcessSwapRun>,
+    /// O modelo de custo usado para estimar os tempos.
+    pub cost: CostModel,
+}
+
+impl ProcessSwapReport {
+    /// O tempo para trazer o processo de volta e tratar as suas page faults
+    /// depois disso, em nanossegundos (sem contar writebacks): cada página do
+    /// working set é só uma leitura do disco, sem o trap e as trocas de
+    /// contexto de uma page fault.
+    pub fn swap_in_time(&self, run: &ProcessSwapRun) -> f64 {
+        run.prefaulted as f64 * self.cost.page_read
+            + run.misses_after as f64 * self.cost.fault_service_time(false)
+    }
+
+    pub fn print_report(&self) {
+        println!("===== Cenário: swapping de processos =====");
+        println!(
+            "{:>10} {:>7} {:>12} {:>9} {:>7} {:>10}",
+            "volta", "saíram", "carregadas", "refaults", "faults", "tempo"
+        );
+
+        for run in &self.runs {
+            println!(
+                "{:>10} {:>7} {:>12} {:>9} {:>7} {:>7.1} ms",
+                if run.prefault { "prefault" } else { "demanda" },
+                run.swapped_out_pages,
+                run.prefaulted,
+                run.refaults,
+                run.misses_after,
+                self.swap_in_time(run) / 1e6
+            );
+        }
+
+        if let [demand, prefault] = &self.runs[..] {
+            if demand.refaults > 0 {
+                println!(
+                    "O working set evitou {:.0}% dos refaults",
+                    (1.0 - prefault.refaults as f64 / demand.refaults as f64) * 100.0
+                );
+            }
+        }
+    }
+}
+
+/// Dois processos revezam a memória (16 frames), cada um com 32 páginas e
+/// uma carga hot/cold com 8 páginas quentes:
+///
+/// 1. o processo 1 executa, com um tick a cada 16 acessos;
+/// 2. ele sai da memória (`Mmu::swap_out_process`), guardando o seu
+///    working set, e o processo 2 executa no seu lugar;
+/// 3. o processo 2 sai da memória e o processo 1 volta
+///    (`Mmu::swap_in_process`), com ou sem o working set, e executa de novo.
+fn swap_process(prefault: bool) -> ProcessSwapRun {
+    const PAGES: usize = 32;
+    const ACCESSES: usize = 500;
+
+    let mut mmu =
+        Mmu::<{ 16 * 256 }, 16, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    mmu.set_tick_interval(Some(16));
+    mmu.map_region(1, "dados", 0..PAGES).unwrap();
+    mmu.map_region(2, "dados", PAGES..2 * PAGES).unwrap();
+
+    let pattern = Pattern::HotCold {
+        hot_pages: 8,
+        hot_fraction: 0.95,
+    };
+    let mut first = WorkloadGenerator::new(1, pattern, PAGES, 256).with_write_ratio(0.3);
+    let mut second = WorkloadGenerator::new(2, pattern, PAGES, 256).with_write_ratio(0.3);
+
+    for access in first.generate(ACCESSES) {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    let swapped_out_pages = mmu.swap_out_process(1).unwrap();
+
+    for mut access in second.generate(ACCESSES) {
+        access.address += PAGES << 8;
+        access.apply(&mut mmu).unwrap();
+    }
+
+    mmu.swap_out_process(2).unwrap();
+    let prefaulted = mmu.swap_in_process(1, prefault).unwrap();
+
+    let (misses, refaults) = (mmu.stats.misses(), mmu.stats.swap_refaults());
+
+    for access in first.generate(ACCESSES) {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    ProcessSwapRun {
+        prefault,
+        swapped_out_pages,
+        prefaulted,
+        refaults: mmu.stats.swap_refaults() - refaults,
+        misses_after: mmu.stats.misses() - misses,
+    }
+}
+
+/// Cenário de swapping de processos: traz o mesmo processo de volta por
+/// demanda e com o working set guardado na saída (veja `swap_process`), e
+/// compara os refaults e o tempo estimado com o modelo de custo padrão.
+pub fn process_swap() -> ProcessSwapReport {
+    ProcessSwapReport {
+        runs: vec![swap_process(false), swap_process(true)],
+        cost: CostModel::default(),
+    }
+}
+
+/// Uma execução do cenário de páginas grandes.
+pub struct HugePageRun {
+    /// O número de frames da Mmu.
+    pub frames: usize,
+    /// Se as páginas grandes transparentes estavam habilitadas.
+    pub thp: bool,
+    /// Os hits e misses do TLB.
+    pub tlb: TlbCounts,
+    /// Os misses (page faults) da Mmu.
+    pub page_faults: usize,
+    pub promotions: usize,
+    pub demotions: usize,
+    /// As páginas movidas para formar páginas grandes.
+    pub migrated_pages: usize,
+}
+
+/// O relatório do cenário de páginas grandes.
+pub struct HugePageReport {
+    /// As páginas virtuais usadas pela carga.
+    pub pages: usize,
+    /// As entradas do TLB.
+    pub tlb_entries: usize,
+    pub runs: Vec<HugePageRun>,
+}
+
+impl HugePageReport {
+    pub fn print_report(&self) {
+        println!("===== Cenário: páginas grandes transparentes =====");
+        println!(
+            "{} páginas, TLB de {} entradas, páginas grandes de {} páginas",
+            self.pages, self.tlb_entries, HUGE_PAGE_PAGES
+        );
+        println!(
+            "{:>7} {:>4} {:>11} {:>9} {:>7} {:>9} {:>9} {:>8}",
+            "frames", "THP", "misses TLB", "acerto", "faults", "formadas", "desfeitas", "movidas"
+        );
+
+        for run in &self.runs {
+            println!(
+                "{:>7} {:>4} {:>11} {:>8.2}% {:>7} {:>9} {:>9} {:>8}",
+                run.frames,
+                if run.thp { "sim" } else { "não" },
+                run.tlb.misses,
+                run.tlb.hit_rate() * 100.0,
+                run.page_faults,
+                run.promotions,
+                run.demotions,
+                run.migrated_pages
+            );
+        }
+    }
+}
+
+/// As páginas usadas pela carga do cenário de páginas grandes.
+const HUGE_PAGE_SCENARIO_PAGES: usize = 48;
+
+/// Executa uma carga Zipf sobre 48 páginas, com um tick a cada 64 acessos e
+/// um TLB de 8 entradas, numa Mmu com `FRAME_COUNT` frames. Com
+/// memória de sobra, as páginas grandes se formam e ficam; com menos frames
+/// que páginas, as substituições as desfazem.
+fn huge_pages<const MEM_SIZE: usize, const FRAME_COUNT: usize>(thp: bool) -> HugePageRun {
+    let mut mmu = Mmu::<MEM_SIZE, FRAME_COUNT, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+    mmu.enable_tlb(8);
+    mmu.set_tick_interval(Some(64));
+    if thp {
+        mmu.enable_thp();
+    }
+
+    let mut generator = WorkloadGenerator::new(
+        0,
+        Pattern::Zipf { exponent: 0.6 },
+        HUGE_PAGE_SCENARIO_PAGES,
+        256,
+    );
+
+    for access in generator.generate(20_000) {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    HugePageRun {
+        frames: FRAME_COUNT,
+        thp,
+        tlb: mmu.tlb().unwrap().counts(),
+        page_faults: mmu.stats.misses(),
+        promotions: mmu.stats.thp_promotions(),
+        demotions: mmu.stats.thp_demotions(),
+        migrated_pages: mmu.stats.thp_migrated_pages(),
+    }
+}
+
+/// Cenário de páginas grandes transparentes: a mesma carga (veja
+/// `huge_pages`) com e sem THP, com memória de sobra e com pouca memória.
+pub fn transparent_huge_pages() -> HugePageReport {
+    HugePageReport {
+        pages: HUGE_PAGE_SCENARIO_PAGES,
+        tlb_entries: 8,
+        runs: vec![
+            huge_pages::<{ 64 * 256 }, 64>(false),
+            huge_pages::<{ 64 * 256 }, 64>(true),
+            huge_pages::<{ 40 * 256 }, 40>(false),
+            huge_pages::<{ 40 * 256 }, 40>(true),
+        ],
+    }
+}
+
+/// O relatório do cenário de memória em camadas: uma execução para cada
+/// política de promoção.
+pub struct TieredMemoryReport {
+    pub pages: usize,
+    pub runs: Vec<TierReport>,
+}
+
+impl TieredMemoryReport {
+    pub fn print_report(&self) {
+        println!("===== Cenário: memória em camadas =====");
+        println!(
+            "{} páginas, todas em memória; o conjunto quente muda no meio da carga",
+            self.pages
+        );
+
+        for run in &self.runs {
+            println!();
+            run.print_report();
+        }
+    }
+}
+
+/// As páginas e os frames do cenário de memória em camadas: todas as
+/// páginas cabem na memória, mas só um quarto delas na camada rápida.
+const TIER_PAGES: usize = 32;
+const TIER_FAST_FRAMES: usize = 8;
+
+/// Carrega as 32 páginas em ordem, de modo que as primeiras ficam com os
+/// frames rápidos, e depois executa duas fases de uma carga com 6 páginas
+/// quentes, de outras páginas em cada fase, com um tick a cada 100 acessos.
+fn tiered_memory_run(policy: PromotionPolicy) -> TierReport {
+    let mut mmu = Mmu::<{ TIER_PAGES * 256 }, TIER_PAGES, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+    mmu.enable_tiers(TierConfig::new(TIER_FAST_FRAMES, policy));
+    mmu.set_tick_interval(Some(100));
+
+    for page_number in 0..TIER_PAGES {
+        mmu.read(page_number << 8).unwrap();
+    }
+
+    // O gerador põe as páginas quentes no começo; deslocamos as páginas
+    // para que o conjunto quente comece longe dos frames rápidos.
+    for (seed, offset) in [(0, 16), (1, 8)] {
+        let pattern = Pattern::HotCold {
+            hot_pages: 6,
+            hot_fraction: 0.9,
+        };
+
+        for access in WorkloadGenerator::new(seed, pattern, TIER_PAGES, 256).generate(10_000) {
+            let page_number = ((access.address >> 8) + offset) % TIER_PAGES;
+            Access {
+                address: page_number << 8 | (access.address & 0xFF),
+                ..access
+            }
+            .apply(&mut mmu)
+            .unwrap();
+        }
+    }
+
+    mmu.tier_report().unwrap()
+}
+
+/// Cenário de memória em camadas: a mesma carga (veja `tiered_memory_run`)
+/// sem migrações e com a promoção das páginas quentes.
+pub fn tiered_memory() -> TieredMemoryReport {
+    TieredMemoryReport {
+        pages: TIER_PAGES,
+        runs: vec![
+            tiered_memory_run(PromotionPolicy::Static),
+            tiered_memory_run(PromotionPolicy::HotPages {
+                threshold: 4,
+                budget: 4,
+            }),
+        ],
+    }
+}
+
+/// As fases do cenário de partições: o que cada processo faz.
+pub const PARTITION_PHASES: [&str; 3] = [
+    "vizinho barulhento",
+    "processo 1 ocioso",
+    "processo 1 cresce",
+];
+
+/// Uma configuração do cenário de partições.
+pub struct PartitionRun {
+    /// A reserva do processo 1.
+    pub reservation: Option<Reservation>,
+    /// Para cada fase, os acessos e as page faults dos processos 1 e 2.
+    pub phases: Vec<[PartitionStats; 2]>,
+    /// O relatório das partições no fim, se houve reserva.
+    pub partitions: Option<PartitionReport>,
+}
+
+/// O relatório do cenário de partições.
+pub struct PartitionScenarioReport {
+    pub frames: usize,
+    pub runs: Vec<PartitionRun>,
+}
+
+impl PartitionScenarioReport {
+    pub fn print_report(&self) {
+        println!("===== Cenário: partições de frames =====");
+        println!(
+            "{} frames, LRU; taxa de page faults de cada processo em cada fase",
+            self.frames
+        );
+
+        for (i, phase) in PARTITION_PHASES.iter().enumerate() {
+            println!("{}. {}", i + 1, phase);
+        }
+
+        print!("{:<32}", "reserva do processo 1");
+        for i in 0..PARTITION_PHASES.len() {
+            print!(
+                " {:>8} {:>8}",
+                format!("{}. P1", i + 1),
+                format!("{}. P2", i + 1)
+            );
+        }
+        println!();
+
+        for run in &self.runs {
+            let label = match run.reservation {
+                None => "nenhuma".to_string(),
+                Some(reservation) if reservation.borrow => {
+                    format!("{} frames, com empréstimo", reservation.frames)
+                }
+                Some(reservation) => format!("{} frames, isolada", reservation.frames),
+            };
+
+            print!("{:<32}", label);
+            for stats in &run.phases {
+                for process in stats {
+                    if process.accesses == 0 {
+                        print!(" {:>8}", "-");
+                    } else {
+                        print!(" {:>7.1}%", process.fault_rate() * 100.0);
+                    }
+                }
+            }
+            println!();
+        }
+
+        for report in self.runs.iter().filter_map(|run| run.partitions.as_ref()) {
+            println!();
+            report.print_report();
+        }
+    }
+}
+
+/// Os frames e as páginas do cenário de partições.
+const PARTITION_FRAMES: usize = 16;
+const PARTITION_HOT_PAGES: usize = 6;
+const PARTITION_STREAM_PAGES: usize = 128;
+
+/// Dois processos dividem 16 frames, com o LRU, em três fases:
+///
+/// 1. o processo 1 percorre em laço as suas 6 páginas quentes, enquanto o
+///    processo 2 varre 128 páginas, três a cada acesso do processo 1;
+/// 2. o processo 1 para, e o processo 2 percorre em laço 14 páginas;
+/// 3. o processo 2 para, e o processo 1 percorre em laço 9 páginas.
+fn partition_run(reservation: Option<Reservation>) -> PartitionRun {
+    let mut mmu = Mmu::<{ PARTITION_FRAMES * 256 }, PARTITION_FRAMES, 256, _, _>::new(
+        LRUPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+    mmu.map_region(1, "interativo", 0..16).unwrap();
+    mmu.map_region(2, "lote", 16..16 + PARTITION_STREAM_PAGES)
+        .unwrap();
+    mmu.set_frame_reservation(1, reservation).unwrap();
+
+    let mut phases = vec![[PartitionStats::default(); 2]; PARTITION_PHASES.len()];
+    let mut access = |mmu: &mut Mmu<_, _, _, _, _>, phase: usize, pid: usize, page: usize| {
+        let misses = mmu.stats.misses();
+        mmu.read(page << 8).unwrap();
+
+        let stats = &mut phases[phase][pid - 1];
+        stats.accesses += 1;
+        stats.faults += mmu.stats.misses() - misses;
+    };
+
+    for i in 0..600 {
+        access(&mut mmu, 0, 1, i % PARTITION_HOT_PAGES);
+        for j in 0..3 {
+            access(&mut mmu, 0, 2, 16 + (3 * i + j) % PARTITION_STREAM_PAGES);
+        }
+    }
+
+    for i in 0..1200 {
+        access(&mut mmu, 1, 2, 16 + i % 14);
+    }
+
+    for i in 0..1200 {
+        access(&mut mmu, 2, 1, i % 9);
+    }
+
+    PartitionRun {
+        reservation,
+        phases,
+        partitions: mmu.partition_report(),
+    }
+}
+
+/// Cenário de partições de frames: a mesma carga (veja `partition_run`) sem
+/// reserva, com 6 frames isolados para o processo 1 e com 6 frames que ele
+/// pode aumentar pelo pool compartilhado. A reserva protege o processo 1 do
+/// vizinho na primeira fase, mas deixa frames ociosos na segunda; sem
+/// empréstimo, ela também o limita na terceira.
+pub fn frame_partitions() -> PartitionScenarioReport {
+    PartitionScenarioReport {
+        frames: PARTITION_FRAMES,
+        runs: vec![
+            partition_run(None),
+            partition_run(Some(Reservation::isolated(PARTITION_HOT_PAGES))),
+            partition_run(Some(Reservation::borrowing(PARTITION_HOT_PAGES))),
+        ],
+    }
+}
+
+/// O relatório do cenário de persistência.
+pub struct PersistenceReport {
+    pub pages: usize,
+    pub frames: usize,
+    /// Quantas páginas foram escritas de novo, depois da primeira passada.
+    pub rewritten_pages: usize,
+    /// Os writebacks das páginas que saíram da memória durante as escritas.
+    pub eviction_writebacks: usize,
+    /// Quantos bytes do disco estavam desatualizados antes do desligamento:
+    /// o que uma queda da máquina perderia.
+    pub stale_bytes: usize,
+    /// Quantas páginas sujas o desligamento escreveu.
+    pub flushed_pages: usize,
+    /// As page faults da nova Mmu, ao ler todas as páginas de volta.
+    pub reload_faults: usize,
+    /// Quantos bytes a nova Mmu leu diferentes do que foi escrito.
+    pub mismatched_bytes: usize,
+}
+
+impl PersistenceReport {
+    pub fn print_report(&self) {
+        println!("===== Cenário: persistência =====");
+        println!(
+            "{} páginas escritas com {} frames, {} delas reescritas",
+            self.pages, self.frames, self.rewritten_pages
+        );
+        println!(
+            "Writebacks durante as escritas: {}",
+            self.eviction_writebacks
+        );
+        println!(
+            "Bytes desatualizados no disco antes do desligamento: {}",
+            self.stale_bytes
+        );
+        println!("Páginas escritas no desligamento: {}", self.flushed_pages);
+        println!(
+            "Nova Mmu: {} page faults, {} bytes diferentes",
+            self.reload_faults, self.mismatched_bytes
+        );
+
+        if self.mismatched_bytes == 0 {
+            println!("Todos os bytes voltaram");
+        } else {
+            println!("{} bytes não voltaram!", self.mismatched_bytes);
+        }
+    }
+}
+
+/// As páginas e os frames do cenário de persistência.
+const PERSISTENCE_PAGES: usize = 48;
+const PERSISTENCE_FRAMES: usize = 8;
+
+/// O conteúdo da página na geração dada: cada byte depende da página, da
+/// posição e da geração, então uma página trocada ou velha não passa.
+fn persistence_page(page_number: usize, generation: u8) -> Vec<u8> {
+    (0..256)
+        .map(|offset| {
+            (page_number as u8).wrapping_mul(37) ^ offset as u8 ^ generation.wrapping_mul(0x5A)
+        })
+        .collect()
+}
+
+/// A geração final da página: as múltiplas de 3 são reescritas.
+fn persistence_generation(page_number: usize) -> u8 {
+    u8::from(page_number.is_multiple_of(3))
+}
+
+/// Quantos bytes de `data` diferem da geração final da página (todos, se a
+/// página não existe).
+fn persistence_mismatches(page_number: usize, data: Option<&[u8]>) -> usize {
+    let expected = persistence_page(page_number, persistence_generation(page_number));
+
+    match data {
+        Some(data) => expected.iter().zip(data).filter(|(a, b)| a != b).count(),
+        None => expected.len(),
+    }
+}
+
+/// Cenário de persistência: escreve 48 páginas com 8 frames (com o LRU),
+/// então quase todas saem sujas da memória, e reescreve uma a cada três,
+/// que voltam do disco e ficam sujas de novo. A Mmu é desligada
+/// (`Mmu::shutdown`), e uma nova, sobre o mesmo carregador, lê todas as
+/// páginas de volta, conferindo cada byte.
+pub fn persistence() -> PersistenceReport {
+    let mut mmu = Mmu::<{ PERSISTENCE_FRAMES * 256 }, PERSISTENCE_FRAMES, 256, _, _>::new(
+        LRUPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+
+    for page_number in 0..PERSISTENCE_PAGES {
+        mmu.write_from(page_number << 8, &persistence_page(page_number, 0))
+            .unwrap();
+    }
+
+    let rewritten: Vec<usize> = (0..PERSISTENCE_PAGES)
+        .filter(|&page_number| persistence_generation(page_number) == 1)
+        .collect();
+    for &page_number in &rewritten {
+        mmu.write_from(page_number << 8, &persistence_page(page_number, 1))
+            .unwrap();
+    }
+
+    let stats = mmu.stats_handle();
+    let eviction_writebacks = stats.writebacks();
+    let stale_bytes = (0..PERSISTENCE_PAGES)
+        .map(|page_number| persistence_mismatches(page_number, mmu.loader().page(page_number)))
+        .sum();
+
+    let loader = mmu.shutdown().unwrap();
+
+    let mut fresh = Mmu::<{ PERSISTENCE_FRAMES * 256 }, PERSISTENCE_FRAMES, 256, _, _>::new(
+        LRUPageReplacer::new(),
+        loader,
+    );
+    let mut data = vec![0; 256];
+    let mut mismatched_bytes = 0;
+
+    for page_number in 0..PERSISTENCE_PAGES {
+        fresh.read_into(page_number << 8, &mut data).unwrap();
+        mismatched_bytes += persistence_mismatches(page_number, Some(&data));
+    }
+
+    PersistenceReport {
+        pages: PERSISTENCE_PAGES,
+        frames: PERSISTENCE_FRAMES,
+        rewritten_pages: rewritten.len(),
+        eviction_writebacks,
+        stale_bytes,
+        flushed_pages: stats.flushes(),
+        reload_faults: fresh.stats.misses(),
+        mismatched_bytes,
+    }
+}
+
+/// Os misses de cada política de substituição com uma carga.
+pub struct PolicyRow {
+    /// A descrição da carga.
+    pub workload: String,
+    /// Os misses de cada política, na ordem de `PolicyReport::policies`.
+    pub misses: Vec<usize>,
+}
+
+/// O relatório da comparação de políticas de substituição.
+pub struct PolicyReport {
+    pub frames: usize,
+    pub pages: usize,
+    /// Os acessos de cada carga.
+    pub accesses: usize,
+    pub policies: Vec<&'static str>,
+    pub rows: Vec<PolicyRow>,
+    /// Os misses do algoritmo ótimo com cada carga de `rows`, o limite
+    /// inferior para as outras políticas.
+    pub optimal_misses: Vec<usize>,
+    /// Os números de bits de histórico dos relógios comparados com o LRU.
+    pub history_bits: Vec<u32>,
+    /// Os misses de cada carga com o LRU e com o relógio de cada número de
+    /// bits de `history_bits`, nessa ordem.
+    pub lru_rows: Vec<PolicyRow>,
+    /// A carga com uma região protegida, com cada swappiness.
+    pub swappiness_rows: Vec<SwappinessRow>,
+    /// A carga em duas fases, com e sem troca de política entre elas.
+    pub switch_rows: Vec<SwitchRow>,
+    /// A carga de `victim_rows` e `three_cs_rows`.
+    pub victim_workload: &'static str,
+    /// A idade das vítimas e a distância até o refault com cada política.
+    pub victim_rows: Vec<VictimRow>,
+    /// Os misses de cada política pelos três Cs (veja o módulo `three_cs`).
+    pub three_cs_rows: Vec<(&'static str, MissBreakdown)>,
+}
+
+/// As distribuições da idade das vítimas e da distância até o refault com
+/// uma política, em acessos (veja `MmuStats::victim_ages`).
+pub struct VictimRow {
+    pub policy: &'static str,
+    pub victim_ages: Histogram,
+    pub refault_distances: Histogram,
+    /// As vítimas que não voltaram até o fim da carga.
+    pub unreturned: usize,
+}
+
+/// Os misses de cada fase da carga em duas fases (veja `phase_misses`).
+pub struct SwitchRow {
+    /// A política, ou as políticas antes e depois da troca.
+    pub policy: &'static str,
+    pub misses: [usize; 2],
+}
+
+/// Os misses de cada política com uma swappiness da região protegida.
+pub struct SwappinessRow {
+    pub swappiness: usize,
+    /// Os misses de cada política, na ordem de `PolicyReport::policies`.
+    pub misses: Vec<usize>,
+    /// Os misses nos acessos à região protegida, de cada política.
+    pub protected_misses: Vec<usize>,
+}
+
+impl PolicyReport {
+    pub fn print_report(&self) {
+        println!("===== Cenário: políticas de substituição =====");
+        println!(
+            "{} frames, {} páginas, {} acessos por carga",
+            self.frames, self.pages, self.accesses
+        );
+
+        print!("{:<28}", "carga");
+        for policy in &self.policies {
+            print!(" {:>9}", policy);
+        }
+        println!(" {:>9}", "ótimo");
+
+        for (row, optimal) in self.rows.iter().zip(&self.optimal_misses) {
+            print!("{:<28}", row.workload);
+            for misses in &row.misses {
+                print!(" {:>9}", misses);
+            }
+            println!(" {:>9}", optimal);
+        }
+
+        println!(
+            "Aproximações do LRU, com um tick a cada {} acessos (k bits de histórico):",
+            POLICY_TICK_INTERVAL
+        );
+        print!("{:<28} {:>8}", "carga", "LRU");
+        for k in &self.history_bits {
+            print!(" {:>8}", format!("k={}", k));
+        }
+        println!();
+
+        for row in &self.lru_rows {
+            print!("{:<28}", row.workload);
+            for misses in &row.misses {
+                print!(" {:>8}", misses);
+            }
+            println!();
+        }
+
+        // A diferença de cada aproximação para o LRU, em média sobre as
+        // cargas; o LRU não é ótimo, então ela pode ser negativa.
+        print!("{:<28} {:>8}", "diferença para o LRU", "-");
+        for i in 1..=self.history_bits.len() {
+            let excess = self
+                .lru_rows
+                .iter()
+                .map(|row| (row.misses[i] as f64 - row.misses[0] as f64) / row.misses[0] as f64)
+                .sum::<f64>()
+                / self.lru_rows.len() as f64;
+            print!(" {:>7.1}%", excess * 100.0);
+        }
+        println!();
+
+        print!("{:<28} {:>8}", "bits por página", "-");
+        for k in &self.history_bits {
+            print!(" {:>8}", k + 1);
+        }
+        println!();
+
+        println!(
+            "Região protegida de {} páginas (30% dos acessos) e um laço de {} páginas:",
+            SWAPPINESS_PROTECTED_PAGES,
+            POLICY_PAGES - SWAPPINESS_PROTECTED_PAGES
+        );
+        print!("{:<28}", "swappiness");
+        for policy in &self.policies {
+            print!(" {:>16}", policy);
+        }
+        println!();
+
+        for row in &self.swappiness_rows {
+            print!("{:<28}", row.swappiness);
+            for (misses, protected) in row.misses.iter().zip(&row.protected_misses) {
+                print!(" {:>8} ({:>5})", misses, protected);
+            }
+            println!();
+        }
+        println!("(entre parênteses, os misses na região protegida)");
+
+        println!(
+            "Duas fases de {} acessos, com as páginas quentes trocando de lugar:",
+            self.accesses
+        );
+        println!("{:<28} {:>8} {:>8}", "política", "fase 1", "fase 2");
+        for row in &self.switch_rows {
+            println!(
+                "{:<28} {:>8} {:>8}",
+                row.policy, row.misses[0], row.misses[1]
+            );
+        }
+
+        println!(
+            "Idade das vítimas e distância até o refault, em acessos ({}):",
+            self.victim_workload
+        );
+        println!(
+            "{:<12} {:>8} {:>8} {:>8} {:>10} {:>8} {:>8} {:>11}",
+            "política", "vítimas", "idade", "p50", "distância", "p50", "p90", "não voltam"
+        );
+        for row in &self.victim_rows {
+            println!(
+                "{:<12} {:>8} {:>8.1} {:>8} {:>10.1} {:>8} {:>8} {:>10.1}%",
+                row.policy,
+                row.victim_ages.count(),
+                row.victim_ages.mean(),
+                row.victim_ages.percentile(0.5),
+                row.refault_distances.mean(),
+                row.refault_distances.percentile(0.5),
+                row.refault_distances.percentile(0.9),
+                row.unreturned as f64 * 100.0 / row.victim_ages.count().max(1) as f64
+            );
+        }
+        println!(
+            "(idade e distância: médias; os percentis são estimados pelos baldes do histograma)"
+        );
+
+        println!("Misses pelos três Cs ({}):", self.victim_workload);
+        println!(
+            "{:<12} {:>8} {:>10} {:>8}",
+            "política", "frios", "capacidade", "conflito"
+        );
+        for (policy, breakdown) in &self.three_cs_rows {
+            println!(
+                "{:<12} {:>8} {:>10} {:>8}",
+                policy, breakdown.cold, breakdown.capacity, breakdown.conflict
+            );
+        }
+    }
+}
+
+/// Os frames da Mmu da comparação de políticas.
+const POLICY_FRAMES: usize = 8;
+
+/// As páginas virtuais usadas pelas cargas da comparação de políticas.
+const POLICY_PAGES: usize = 32;
+
+/// A cada quantos acessos a Mmu da comparação de políticas faz um tick.
+const POLICY_TICK_INTERVAL: usize = 16;
+
+/// Executa os acessos numa Mmu com `POLICY_FRAMES` frames e o replacer dado,
+/// com um tick a cada `POLICY_TICK_INTERVAL` acessos, e devolve o número de
+/// misses.
+fn policy_misses<R: PageReplacer>(replacer: R, accesses: &[Access]) -> usize {
+    policy_stats(replacer, accesses).0.misses()
+}
+
+/// Como `policy_misses`, mas devolve todas as estatísticas da Mmu e os
+/// misses pelos três Cs.
+fn policy_stats<R: PageReplacer>(replacer: R, accesses: &[Access]) -> (MmuStats, MissBreakdown) {
+    let mut mmu = Mmu::<{ POLICY_FRAMES * 256 }, POLICY_FRAMES, 256, _, _>::new(
+        replacer,
+        MemoryPageLoader::new(),
+    );
+    mmu.set_tick_interval(Some(POLICY_TICK_INTERVAL));
+    mmu.enable_three_cs();
+
+    for access in accesses {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    (MmuStats::clone(&mmu.stats), mmu.miss_breakdown().unwrap())
+}
+
+/// As páginas da região protegida da carga com swappiness.
+const SWAPPINESS_PROTECTED_PAGES: usize = 6;
+
+/// Executa, numa Mmu com `POLICY_FRAMES` frames e o replacer dado, uma carga
+/// com duas regiões: a protegida, com `SWAPPINESS_PROTECTED_PAGES` páginas
+/// e a swappiness dada, recebe 30% dos acessos, sorteados; o resto percorre
+/// em laço as outras páginas, que não cabem na memória.
+///
+/// Devolve o número de misses e quantos deles foram na região protegida.
+fn swappiness_misses<R: PageReplacer>(replacer: R, swappiness: usize) -> (usize, usize) {
+    let mut mmu = Mmu::<{ POLICY_FRAMES * 256 }, POLICY_FRAMES, 256, _, _>::new(
+        replacer,
+        MemoryPageLoader::new(),
+    );
+    mmu.map_region(1, "protegida", 0..SWAPPINESS_PROTECTED_PAGES)
+        .unwrap();
+    mmu.map_region(2, "laço", SWAPPINESS_PROTECTED_PAGES..POLICY_PAGES)
+        .unwrap();
+    mmu.set_region_swappiness(1, "protegida", swappiness);
+
+    let mut rng = Rng::for_component(0, "swappiness");
+    let loop_pages = POLICY_PAGES - SWAPPINESS_PROTECTED_PAGES;
+    let mut protected_misses = 0;
+
+    for i in 0..5000 {
+        if rng.chance(0.3) {
+            let misses = mmu.stats.misses();
+            mmu.read(rng.below(SWAPPINESS_PROTECTED_PAGES) << 8)
+                .unwrap();
+            protected_misses += mmu.stats.misses() - misses;
+        } else {
+            mmu.read((SWAPPINESS_PROTECTED_PAGES + i % loop_pages) << 8)
+                .unwrap();
+        }
+    }
+
+    (mmu.stats.misses(), protected_misses)
+}
+
+/// Executa uma carga em duas fases numa Mmu com `POLICY_FRAMES` frames: na
+/// primeira, 90% dos acessos vão para as páginas 0 a 5; na segunda, para as
+/// páginas 16 a 21. Entre as fases, se `switch_to` é dado, a política é
+/// trocada por ele (veja `Mmu::replace_policy`).
+///
+/// Devolve os misses de cada fase.
+fn phase_misses(
+    replacer: Box<dyn PageReplacer>,
+    switch_to: Option<Box<dyn PageReplacer>>,
+    accesses: usize,
+) -> [usize; 2] {
+    let mut mmu = Mmu::<{ POLICY_FRAMES * 256 }, POLICY_FRAMES, 256, _, _>::new(
+        replacer,
+        MemoryPageLoader::new(),
+    );
+    let pattern = Pattern::HotCold {
+        hot_pages: 6,
+        hot_fraction: 0.9,
+    };
+    let mut generator = WorkloadGenerator::new(0, pattern, POLICY_PAGES, 256);
+
+    for access in generator.generate(accesses) {
+        access.apply(&mut mmu).unwrap();
+    }
+    let first = mmu.stats.misses();
+
+    if let Some(replacer) = switch_to {
+        mmu.replace_policy(replacer);
+    }
+
+    for mut access in generator.generate(accesses) {
+        access.address = (access.address + (16 << 8)) % (POLICY_PAGES << 8);
+        access.apply(&mut mmu).unwrap();
+    }
+
+    [first, mmu.stats.misses() - first]
+}
+
+/// Um LFU cujas contagens perdem 1% a cada acesso.
+fn decaying_lfu() -> LFUPageReplacer {
+    LFUPageReplacer::new().with("decay", 0.01).unwrap()
+}
+
+/// As políticas comparadas, na ordem de `PolicyReport::policies`, cada uma
+/// com um replacer novo.
+fn policies() -> Vec<(&'static str, Box<dyn PageReplacer>)> {
+    vec![
+        ("FIFO", Box::new(FIFOPageReplacer::new())),
+        ("Clock", Box::new(ClockPageReplacer::new())),
+        ("LFU", Box::new(LFUPageReplacer::new())),
+        ("LFU 1%", Box::new(decaying_lfu())),
+        ("MFU", Box::new(MFUPageReplacer::new())),
+        ("MRU", Box::new(MRUPageReplacer::new())),
+        ("S3-FIFO", Box::new(S3FIFOPageReplacer::new())),
+        ("NRU", Box::new(NRUPageReplacer::new())),
+        ("Aging", Box::new(AgingPageReplacer::new())),
+        ("WS", Box::new(WorkingSetPageReplacer::new(16))),
+        ("WSClock", Box::new(WSClockPageReplacer::new(16))),
+        ("2Q", Box::new(TwoQPageReplacer::new())),
+        ("SLRU", Box::new(SLRUPageReplacer::new())),
+        ("LRU-2", Box::new(LRUKPageReplacer::new(2))),
+        ("Clock-Pro", Box::new(ClockProPageReplacer::new())),
+        ("aleatório", Box::new(RandomPageReplacer::new(0))),
+        (
+            "LRU/LFU",
+            Box::new(AdaptivePageReplacer::new(
+                LRUPageReplacer::new(),
+                LFUPageReplacer::new(),
+            )),
+        ),
+    ]
+}
+
+/// Cenário de comparação das políticas de substituição: a mesma carga de
+/// cada padrão em todas as políticas, com 8 frames para 32 páginas, e no
+/// algoritmo ótimo, que conhece a carga de antemão. Depois,
+/// uma carga com uma região protegida (veja `swappiness_misses`), com
+/// swappiness de 100 a 0, e uma carga em duas fases (veja `phase_misses`),
+/// com e sem troca de política entre elas.
+pub fn replacement_policies() -> PolicyReport {
+    let accesses = 5000;
+    let patterns = [
+        ("uniforme", Pattern::Uniform),
+        ("laço de 12 páginas", Pattern::Loop { pages: 12 }),
+        ("Zipf, expoente 0,8", Pattern::Zipf { exponent: 0.8 }),
+        (
+            "90% em 6 páginas quentes",
+            Pattern::HotCold {
+                hot_pages: 6,
+                hot_fraction: 0.9,
+            },
+        ),
+    ];
+
+    let workloads: Vec<_> = patterns
+        .into_iter()
+        .map(|(name, pattern)| {
+            (
+                name,
+                WorkloadGenerator::new(0, pattern, POLICY_PAGES, 256).generate(accesses),
+            )
+        })
+        .collect();
+
+    let rows = workloads
+        .iter()
+        .map(|(name, workload)| PolicyRow {
+            workload: name.to_string(),
+            misses: policies()
+                .into_iter()
+                .map(|(_, replacer)| policy_misses(replacer, workload))
+                .collect(),
+        })
+        .collect();
+
+    let optimal_misses = workloads
+        .iter()
+        .map(|(_, workload)| policy_misses(OptimalPageReplacer::from_accesses(workload), workload))
+        .collect();
+
+    let history_bits = vec![0, 1, 2, 4, 8];
+    let lru_rows = workloads
+        .iter()
+        .map(|(name, workload)| PolicyRow {
+            workload: name.to_string(),
+            misses: std::iter::once(policy_misses(LRUPageReplacer::new(), workload))
+                .chain(
+                    history_bits
+                        .iter()
+                        .map(|&k| policy_misses(KRefClockReplacer::new(k), workload)),
+                )
+                .collect(),
+        })
+        .collect();
+
+    let swappiness_rows = [100, 50, 10, 0]
+        .into_iter()
+        .map(|swappiness| {
+            let (misses, protected_misses) = policies()
+                .into_iter()
+                .map(|(_, replacer)| swappiness_misses(replacer, swappiness))
+                .unzip();
+
+            SwappinessRow {
+                swappiness,
+                misses,
+                protected_misses,
+            }
+        })
+        .collect();
+
+    let switch_rows = vec![
+        SwitchRow {
+            policy: "FIFO",
+            misses: phase_misses(Box::new(FIFOPageReplacer::new()), None, accesses),
+        },
+        SwitchRow {
+            policy: "LFU",
+            misses: phase_misses(Box::new(LFUPageReplacer::new()), None, accesses),
+        },
+        SwitchRow {
+            policy: "LFU, depois Clock",
+            misses: phase_misses(
+                Box::new(LFUPageReplacer::new()),
+                Some(Box::new(ClockPageReplacer::new())),
+                accesses,
+            ),
+        },
+        SwitchRow {
+            policy: "LFU, depois LFU zerado",
+            misses: phase_misses(
+                Box::new(LFUPageReplacer::new()),
+                Some(Box::new(LFUPageReplacer::new())),
+                accesses,
+            ),
+        },
+    ];
+
+    let (victim_workload, workload) = &workloads[2];
+    let (victim_rows, three_cs_rows) = policies()
+        .into_iter()
+        .map(|(policy, replacer)| {
+            let (stats, breakdown) = policy_stats(replacer, workload);
+
+            let row = VictimRow {
+                policy,
+                victim_ages: stats.victim_ages().clone(),
+                refault_distances: stats.refault_distances().clone(),
+                unreturned: stats.unreturned_victims(),
+            };
+
+            (row, (policy, breakdown))
+        })
+        .unzip();
+
+    PolicyReport {
+        frames: POLICY_FRAMES,
+        pages: POLICY_PAGES,
+        accesses,
+        policies: policies().into_iter().map(|(name, _)| name).collect(),
+        rows,
+        optimal_misses,
+        history_bits,
+        lru_rows,
+        swappiness_rows,
+        switch_rows,
+        victim_workload,
+        victim_rows,
+        three_cs_rows,
+    }
+}
@@ -0,0 +1,208 @@
+//! Shadow page tables: a alternativa à paginação aninhada sem suporte do
+//! hardware.
+//!
+//! Sem EPT/NPT, o hardware só sabe fazer uma tradução. O hypervisor então
+//! mantém, para o guest, uma "shadow page table" que traduz direto de
+//! endereços virtuais do guest para endereços físicos do host, juntando as
+//! duas page tables. O walk fica barato (um estágio só), mas manter a shadow
+//! sincronizada custa saídas para o hypervisor (VM exits):
+//!
+//! - a page table do guest fica protegida contra escrita, então o
+//!   tratamento de cada page fault do guest, que escreve nela (mapeia a
+//!   página nova e invalida a substituída), causa um trap, e o hypervisor
+//!   atualiza a shadow de uma vez;
+//! - uma página que o guest já mapeou mas que não está na shadow (porque o
+//!   host substituiu o frame dela) causa uma "hidden fault", e o hypervisor
+//!   preenche a entrada;
+//! - quando o host substitui uma página, as entradas da shadow que apontavam
+//!   para o frame dela são invalidadas (sem custo para o guest).
+//!
+//! Cada trap é um VM exit só, então um acesso causa no máximo um.
+//!
+//! `ShadowMmu` compõe as mesmas duas Mmus de `NestedMmu`, mas conta esses
+//! eventos e calcula o tempo efetivo de acesso com o walk de um estágio, para
+//! comparar as duas abordagens na mesma carga.
+
+use std::collections::HashMap;
+
+use crate::{cost::CostModel, error::MmuError, nested::AddressSpace, trace::AccessKind};
+
+/// As estatísticas de uma execução com shadow page tables.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShadowStats {
+    /// Número de acessos feitos pelo guest.
+    pub accesses: usize,
+    /// Page faults na page table do guest.
+    pub guest_faults: usize,
+    /// Page faults no host.
+    pub host_faults: usize,
+    /// Page faults do guest interceptadas pela proteção contra escrita da
+    /// sua page table: uma por fault.
+    pub write_protect_traps: usize,
+    /// Acessos sem page fault no guest a páginas que não estavam na shadow
+    /// (as hidden faults).
+    pub shadow_fills: usize,
+}
+
+impl ShadowStats {
+    /// O número de saídas para o hypervisor.
+    pub fn vm_exits(&self) -> usize {
+        self.write_protect_traps + self.shadow_fills
+    }
+
+    /// O número de saídas para o hypervisor por acesso.
+    pub fn vm_exits_per_access(&self) -> f64 {
+        if self.accesses == 0 {
+            return 0.0;
+        }
+
+        self.vm_exits() as f64 / self.accesses as f64
+    }
+
+    /// O tempo efetivo de acesso, em nanossegundos: o walk de um estágio
+    /// (pela shadow, com os níveis do guest), as faults de cada estágio e as
+    /// saídas para o hypervisor.
+    pub fn effective_access_time(&self, guest: &CostModel, host: &CostModel) -> f64 {
+        if self.accesses == 0 {
+            return 0.0;
+        }
+
+        let guest_p = self.guest_faults as f64 / self.accesses as f64;
+        let host_p = self.host_faults as f64 / self.accesses as f64;
+
+        guest.page_walk_time()
+            + guest.memory_access
+            + guest_p * guest.fault_service_time(false)
+            + host_p * host.fault_service_time(false)
+            + self.vm_exits_per_access() * host.vm_exit
+    }
+
+    pub fn print_stats(&self) {
+        println!("===== Estatísticas da shadow page table =====");
+        println!("Total de acessos: {}", self.accesses);
+        println!("  Faults do guest: {}", self.guest_faults);
+        println!("  Faults do host:  {}", self.host_faults);
+        println!(
+            "  Traps de escrita na page table: {}",
+            self.write_protect_traps
+        );
+        println!("  Preenchimentos da shadow:       {}", self.shadow_fills);
+        println!("  VM exits por acesso: {:.3}", self.vm_exits_per_access());
+    }
+}
+
+/// Uma entrada da shadow: o frame do guest e o frame do host da página.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct ShadowEntry {
+    guest_frame: usize,
+    host_frame: usize,
+}
+
+/// Uma Mmu de guest sobre uma Mmu de host, traduzida por uma shadow page
+/// table mantida pelo hypervisor.
+pub struct ShadowMmu<G, H> {
+    guest: G,
+    host: H,
+    /// A shadow: página virtual do guest -> frames do guest e do host.
+    shadow: HashMap<usize, ShadowEntry>,
+    stats: ShadowStats,
+}
+
+impl<G: AddressSpace, H: AddressSpace> ShadowMmu<G, H> {
+    /// Compõe as duas Mmus. O espaço virtual do host deve cobrir toda a
+    /// memória física do guest.
+    pub fn new(guest: G, host: H) -> Self {
+        ShadowMmu {
+            guest,
+            host,
+            shadow: HashMap::new(),
+            stats: ShadowStats::default(),
+        }
+    }
+
+    /// A Mmu do guest.
+    pub fn guest(&self) -> &G {
+        &self.guest
+    }
+
+    /// A Mmu do host.
+    pub fn host(&self) -> &H {
+        &self.host
+    }
+
+    /// As estatísticas até agora.
+    pub fn stats(&self) -> ShadowStats {
+        self.stats
+    }
+
+    /// Traduz o endereço, mantendo a shadow sincronizada, e devolve o
+    /// endereço físico do guest (onde o dado está guardado). Um acesso que
+    /// falha num dos estágios não muda a shadow nem as estatísticas.
+    fn translate_shadow(&mut self, address: usize, kind: AccessKind) -> Result<usize, MmuError> {
+        let page_number = (address & 0xFFFF) >> 8;
+
+        let guest_misses = self.guest.stats().misses();
+        let guest_physical = self.guest.translate(address, kind)?;
+        let guest_fault = self.guest.stats().misses() > guest_misses;
+
+        let host_misses = self.host.stats().misses();
+        let host_physical = self.host.translate(guest_physical, kind)?;
+        let host_fault = self.host.stats().misses() > host_misses;
+
+        let entry = ShadowEntry {
+            guest_frame: guest_physical >> 8,
+            host_frame: host_physical >> 8,
+        };
+
+        self.stats.accesses += 1;
+
+        if guest_fault {
+            self.stats.guest_faults += 1;
+
+            // O guest escreveu a entrada da página nova e, se reaproveitou o
+            // frame de outra página, invalidou a entrada dela. O hypervisor
+            // trata as duas escritas no mesmo trap.
+            self.stats.write_protect_traps += 1;
+            self.shadow
+                .retain(|_, shadow| shadow.guest_frame != entry.guest_frame);
+        }
+
+        if host_fault {
+            self.stats.host_faults += 1;
+
+            // O host reaproveitou um frame: as entradas que apontavam para
+            // ele não valem mais.
+            self.shadow
+                .retain(|_, shadow| shadow.host_frame != entry.host_frame);
+        }
+
+        // A entrada que falta é preenchida no trap da page fault do guest,
+        // se houve uma; senão, é uma hidden fault.
+        if self.shadow.get(&page_number) != Some(&entry) {
+            if !guest_fault {
+                self.stats.shadow_fills += 1;
+            }
+
+            self.shadow.insert(page_number, entry);
+        }
+
+        Ok(guest_physical)
+    }
+
+    /// Lê o byte no endereço virtual do guest. Os erros são os de
+    /// `Mmu::read`, de qualquer um dos estágios.
+    pub fn read(&mut self, address: usize) -> Result<u8, MmuError> {
+        let guest_physical = self.translate_shadow(address, AccessKind::Read)?;
+
+        Ok(self.guest.physical_memory()[guest_physical])
+    }
+
+    /// Escreve o byte no endereço virtual do guest. Os erros são os de
+    /// `Mmu::write`, de qualquer um dos estágios.
+    pub fn write(&mut self, address: usize, value: u8) -> Result<(), MmuError> {
+        let guest_physical = self.translate_shadow(address, AccessKind::Write)?;
+
+        self.guest.physical_memory()[guest_physical] = value;
+        Ok(())
+    }
+}
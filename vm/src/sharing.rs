@@ -0,0 +1,108 @@
+//! Compartilhamento de frames com copy-on-write (COW).
+//!
+//! Num `fork`, o processo filho começa com uma cópia da memória do pai, mas
+//! copiar tudo seria caro e quase sempre inútil (o filho costuma chamar
+//! `exec` logo em seguida). Em vez disso, as páginas do filho apontam para os
+//! mesmos frames das do pai, e os dois ficam protegidos contra escrita: a
+//! primeira escrita em qualquer um deles "quebra" o COW, dando à página um
+//! frame próprio com uma cópia do conteúdo.
+//!
+//! `Mmu::share_page` faz uma página virtual apontar para o frame de outra.
+//! Enquanto o frame é compartilhado:
+//!
+//! - uma escrita em qualquer uma das páginas quebra o COW (o que pode causar
+//!   uma substituição, se não há frame livre);
+//! - se o replacer escolhe uma das páginas como vítima, todas as páginas do
+//!   frame saem da memória juntas, como o `try_to_unmap` do Linux, já que o
+//!   frame só pode ser reaproveitado quando ninguém mais aponta para ele;
+//! - desmapear uma das páginas só a tira do frame, que continua com as
+//!   outras.
+//!
+//! `SharedFrames` guarda quais páginas apontam para cada frame
+//! compartilhado; o número de referências a cada frame, compartilhado ou
+//! não, fica nos contadores do módulo `refcount`. As estatísticas (quantos frames são compartilhados, quantas
+//! quebras de COW aconteceram e quanta memória o compartilhamento economiza)
+//! ficam em `MmuStats`.
+
+use std::collections::BTreeMap;
+
+/// As páginas que apontam para cada frame compartilhado.
+#[derive(Clone, Debug, Default)]
+pub struct SharedFrames {
+    /// Frame -> páginas, em ordem. Só frames com duas ou mais páginas.
+    frames: BTreeMap<usize, Vec<usize>>,
+}
+
+impl SharedFrames {
+    /// Constrói um mapa sem nenhum frame compartilhado.
+    pub fn new() -> Self {
+        SharedFrames {
+            frames: BTreeMap::new(),
+        }
+    }
+
+    /// Registra que a página `page_number` passou a apontar para o frame,
+    /// que já era de `owner`.
+    pub fn share(&mut self, frame_index: usize, owner: usize, page_number: usize) {
+        let pages = self
+            .frames
+            .entry(frame_index)
+            .or_insert_with(|| vec![owner]);
+
+        if let Err(position) = pages.binary_search(&page_number) {
+            pages.insert(position, page_number);
+        }
+    }
+
+    /// Se o frame é compartilhado por mais de uma página.
+    pub fn is_shared(&self, frame_index: usize) -> bool {
+        self.frames.contains_key(&frame_index)
+    }
+
+    /// As páginas que apontam para o frame, em ordem. Vazio se o frame não
+    /// é compartilhado.
+    pub fn sharers(&self, frame_index: usize) -> &[usize] {
+        self.frames
+            .get(&frame_index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Tira a página do frame. Se sobra uma página só, o frame deixa de ser
+    /// compartilhado.
+    pub fn remove(&mut self, frame_index: usize, page_number: usize) {
+        let Some(pages) = self.frames.get_mut(&frame_index) else {
+            return;
+        };
+
+        pages.retain(|&page| page != page_number);
+
+        if pages.len() < 2 {
+            self.frames.remove(&frame_index);
+        }
+    }
+
+    /// Esquece o frame, devolvendo as páginas que apontavam para ele.
+    pub fn remove_frame(&mut self, frame_index: usize) -> Vec<usize> {
+        self.frames.remove(&frame_index).unwrap_or_default()
+    }
+
+    /// Troca os índices dos frames (depois de uma compactação).
+    pub fn remap(&mut self, new_frame_index: impl Fn(usize) -> usize) {
+        self.frames = std::mem::take(&mut self.frames)
+            .into_iter()
+            .map(|(frame_index, pages)| (new_frame_index(frame_index), pages))
+            .collect();
+    }
+
+    /// Quantos frames são compartilhados.
+    pub fn shared_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Quantos frames o compartilhamento economiza: sem ele, cada página
+    /// precisaria do seu próprio frame.
+    pub fn saved_frames(&self) -> usize {
+        self.frames.values().map(|pages| pages.len() - 1).sum()
+    }
+}
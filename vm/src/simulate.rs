@@ -0,0 +1,554 @@
+//! Comparação de políticas de substituição: a mesma sequência de acessos em
+//! várias Mmus, uma para cada política e cada tamanho de memória.
+//!
+//! ```
+//! use vm::simulate::{compare_replacers, Geometry};
+//!
+//! let trace = vm::fixtures::trace("loop_12_pages").unwrap();
+//! let geometries = [Geometry::new(8), Geometry::new(16)];
+//! let comparison = compare_replacers(&trace, &geometries, &["fifo", "lru", "mru"]).unwrap();
+//!
+//! // Com 16 frames o laço cabe na memória, e só há misses frios.
+//! assert_eq!(comparison.stats("lru", Geometry::new(16)).unwrap().misses(), 12);
+//! ```
+//!
+//! O número de frames é um parâmetro de tipo da Mmu, então ele precisa ser
+//! conhecido em tempo de compilação, e cada número é uma Mmu compilada à
+//! parte: a comparação aceita de 1 a 16 frames, 24, 32, 48, 64, 128 e 256
+//! (veja `FRAME_COUNTS`). As páginas são sempre as 256 páginas de
+//! 256 bytes do espaço de endereçamento da Mmu, e as páginas começam
+//! zeradas, num `MemoryPageLoader`.
+//!
+//! Para ver como uma política se comporta conforme a memória cresce,
+//! `miss_rate_curve` dá a taxa de misses para cada número de frames, pronta
+//! para um gráfico. `belady_anomaly` usa a mesma curva, com o FIFO, para
+//! procurar a anomalia de Belady numa sequência de páginas. E, para ver
+//! onde duas políticas se separam, `diff_replacers` compara os traces das
+//! duas execuções (veja o módulo `trace_diff`).
+
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+use thiserror::Error;
+
+use crate::{
+    mmu::{Mmu, MmuStats},
+    page_loader::MemoryPageLoader,
+    page_replacer::{FIFOPageReplacer, PageReplacer, ReplacerRegistry},
+    trace::{AccessKind, AccessRecord},
+    trace_diff::TraceDiff,
+    workload::Access,
+};
+
+/// O formato da memória física de uma Mmu da comparação.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Geometry {
+    /// Quantos frames a memória tem, um de `FRAME_COUNTS`.
+    pub frames: usize,
+}
+
+impl Geometry {
+    pub fn new(frames: usize) -> Self {
+        Geometry { frames }
+    }
+}
+
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} frames", self.frames)
+    }
+}
+
+/// Erro ao montar uma comparação.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SimulateError {
+    /// Não há replacer registrado com esse nome.
+    #[error("replacer desconhecido: {0}")]
+    UnknownReplacer(String),
+    /// O número de frames não está em `FRAME_COUNTS`.
+    #[error("memória de {0} não suportada (use de 1 a 16 frames, 24, 32, 48, 64, 128 ou 256)")]
+    UnsupportedGeometry(Geometry),
+    /// A página não existe no espaço de endereçamento da Mmu.
+    #[error("a página {0:#04X} não existe")]
+    PageOutOfRange(usize),
+}
+
+/// A execução de uma política numa geometria.
+#[derive(Debug, Clone)]
+pub struct ReplacerRun {
+    /// O nome da política no registro.
+    pub replacer: String,
+    pub geometry: Geometry,
+    pub stats: MmuStats,
+}
+
+/// O resultado de `compare_replacers`: uma execução para cada par de
+/// política e geometria.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// Quantos acessos a sequência tinha.
+    pub accesses: usize,
+    /// As execuções, geometria por geometria, com as políticas na ordem
+    /// pedida.
+    pub runs: Vec<ReplacerRun>,
+}
+
+impl Comparison {
+    /// As estatísticas da política na geometria, se ela foi executada.
+    pub fn stats(&self, replacer: &str, geometry: Geometry) -> Option<&MmuStats> {
+        self.runs
+            .iter()
+            .find(|run| run.replacer == replacer && run.geometry == geometry)
+            .map(|run| &run.stats)
+    }
+
+    /// As políticas, na ordem pedida.
+    pub fn replacers(&self) -> Vec<&str> {
+        let mut replacers: Vec<&str> = Vec::new();
+
+        for run in &self.runs {
+            if !replacers.contains(&run.replacer.as_str()) {
+                replacers.push(&run.replacer);
+            }
+        }
+
+        replacers
+    }
+
+    /// As geometrias, na ordem pedida.
+    pub fn geometries(&self) -> Vec<Geometry> {
+        let mut geometries = Vec::new();
+
+        for run in &self.runs {
+            if !geometries.contains(&run.geometry) {
+                geometries.push(run.geometry);
+            }
+        }
+
+        geometries
+    }
+
+    /// Imprime uma tabela com os misses e a taxa de misses de cada política
+    /// (nas linhas) em cada geometria (nas colunas).
+    pub fn print_report(&self) {
+        let geometries = self.geometries();
+
+        println!("===== Comparação de políticas =====");
+        println!("{} acessos", self.accesses);
+
+        print!("{:<12}", "política");
+        for geometry in &geometries {
+            print!(" {:>20}", geometry.to_string());
+        }
+        println!();
+
+        for replacer in self.replacers() {
+            print!("{:<12}", replacer);
+            for &geometry in &geometries {
+                match self.stats(replacer, geometry) {
+                    Some(stats) => print!(
+                        " {:>10} ({:>6.2} %)",
+                        stats.misses(),
+                        100.0 * stats.misses() as f64 / self.accesses.max(1) as f64
+                    ),
+                    None => print!(" {:>20}", "-"),
+                }
+            }
+            println!();
+        }
+    }
+}
+
+/// Executa `trace` numa Mmu nova para cada política de `replacers` (nomes
+/// do `ReplacerRegistry`, com a semente 0) em cada geometria, e devolve as
+/// estatísticas de todas as execuções.
+///
+/// Os nomes, as geometrias e os endereços da sequência são conferidos antes
+/// de qualquer execução.
+pub fn compare_replacers(
+    trace: &[Access],
+    geometries: &[Geometry],
+    replacers: &[&str],
+) -> Result<Comparison, SimulateError> {
+    compare_registered(trace, geometries, &ReplacerRegistry::new(), replacers)
+}
+
+/// Como `compare_replacers`, mas com as políticas de `registry`, que pode
+/// ter replacers próprios ou configurados (veja `ReplacerRegistry::register`).
+pub fn compare_registered(
+    trace: &[Access],
+    geometries: &[Geometry],
+    registry: &ReplacerRegistry,
+    replacers: &[&str],
+) -> Result<Comparison, SimulateError> {
+    for &name in replacers {
+        if registry.create(name, 0).is_none() {
+            return Err(SimulateError::UnknownReplacer(name.to_string()));
+        }
+    }
+
+    for &geometry in geometries {
+        if !FRAME_COUNTS.contains(&geometry.frames) {
+            return Err(SimulateError::UnsupportedGeometry(geometry));
+        }
+    }
+
+    check_trace(trace)?;
+
+    let mut runs = Vec::new();
+
+    for &geometry in geometries {
+        for &name in replacers {
+            let replacer = registry.create(name, 0).unwrap();
+
+            runs.push(ReplacerRun {
+                replacer: name.to_string(),
+                geometry,
+                stats: run(geometry.frames, replacer, trace, false).0,
+            });
+        }
+    }
+
+    Ok(Comparison {
+        accesses: trace.len(),
+        runs,
+    })
+}
+
+/// Um ponto da curva de misses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    pub frames: usize,
+    pub misses: usize,
+    /// `misses` sobre o número de acessos, de 0 a 1.
+    pub miss_rate: f64,
+}
+
+/// A taxa de misses de uma política para cada número de frames, devolvida
+/// por `miss_rate_curve`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissRateCurve {
+    /// Quantos acessos a sequência tinha.
+    pub accesses: usize,
+    /// Os pontos, na ordem pedida.
+    pub points: Vec<CurvePoint>,
+}
+
+impl MissRateCurve {
+    /// A taxa de misses com `frames` frames, se esse ponto foi calculado.
+    pub fn miss_rate(&self, frames: usize) -> Option<f64> {
+        self.points
+            .iter()
+            .find(|point| point.frames == frames)
+            .map(|point| point.miss_rate)
+    }
+
+    /// Escreve a curva em CSV, um ponto por linha, para abrir numa planilha
+    /// ou no gnuplot.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "frames,misses,miss_rate")?;
+
+        for point in &self.points {
+            writeln!(
+                out,
+                "{},{},{:.6}",
+                point.frames, point.misses, point.miss_rate
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Imprime a curva como uma tabela, com uma barra para cada ponto.
+    pub fn print_report(&self) {
+        println!("===== Curva de misses =====");
+        println!("{} acessos", self.accesses);
+        println!("{:>6} {:>10} {:>9}", "frames", "misses", "taxa");
+
+        for point in &self.points {
+            println!(
+                "{:>6} {:>10} {:>7.2} % {}",
+                point.frames,
+                point.misses,
+                100.0 * point.miss_rate,
+                "#".repeat((40.0 * point.miss_rate).round() as usize)
+            );
+        }
+    }
+}
+
+/// Executa `trace` numa Mmu nova para cada número de `frame_counts`, com um
+/// replacer novo de `replacer_factory` em cada uma, e devolve a taxa de
+/// misses de cada execução.
+///
+/// Os números de frames são conferidos contra `FRAME_COUNTS` antes de
+/// qualquer execução.
+///
+/// ```
+/// use vm::{page_replacer::LRUPageReplacer, simulate::miss_rate_curve};
+///
+/// let trace = vm::fixtures::trace("loop_12_pages").unwrap();
+/// let curve = miss_rate_curve(&trace, &[4, 11, 12], LRUPageReplacer::new).unwrap();
+///
+/// // O LRU erra todos os acessos do laço até que ele caiba na memória.
+/// assert_eq!(curve.miss_rate(11), Some(1.0));
+/// assert!(curve.miss_rate(12).unwrap() < 1.0);
+/// ```
+pub fn miss_rate_curve<F, R>(
+    trace: &[Access],
+    frame_counts: &[usize],
+    replacer_factory: F,
+) -> Result<MissRateCurve, SimulateError>
+where
+    F: Fn() -> R,
+    R: PageReplacer + 'static,
+{
+    for &frames in frame_counts {
+        if !FRAME_COUNTS.contains(&frames) {
+            return Err(SimulateError::UnsupportedGeometry(Geometry::new(frames)));
+        }
+    }
+
+    check_trace(trace)?;
+
+    let points = frame_counts
+        .iter()
+        .map(|&frames| {
+            let misses = run(frames, Box::new(replacer_factory()), trace, false)
+                .0
+                .misses();
+
+            CurvePoint {
+                frames,
+                misses,
+                miss_rate: misses as f64 / trace.len().max(1) as f64,
+            }
+        })
+        .collect();
+
+    Ok(MissRateCurve {
+        accesses: trace.len(),
+        points,
+    })
+}
+
+/// Dois números de frames consecutivos em que o FIFO teve mais faltas com
+/// mais memória.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeladyAnomaly {
+    pub frames: usize,
+    pub faults: usize,
+    /// As faltas com `frames + 1` frames, mais que `faults`.
+    pub faults_with_one_more: usize,
+}
+
+/// O resultado de `belady_anomaly`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeladyReport {
+    /// A sequência de páginas.
+    pub reference: Vec<usize>,
+    /// As faltas do FIFO para cada número de frames, de 1 em diante.
+    pub faults: Vec<(usize, usize)>,
+    /// Os números de frames em que um frame a mais aumentou as faltas.
+    pub anomalies: Vec<BeladyAnomaly>,
+}
+
+impl BeladyReport {
+    /// Se a anomalia aconteceu em algum número de frames.
+    pub fn occurred(&self) -> bool {
+        !self.anomalies.is_empty()
+    }
+
+    /// Imprime as faltas para cada número de frames, marcando as que
+    /// aumentaram em relação ao número anterior.
+    pub fn print_report(&self) {
+        println!("===== Anomalia de Belady (FIFO) =====");
+        let reference: Vec<String> = self.reference.iter().map(usize::to_string).collect();
+        println!("sequência: {}", reference.join(" "));
+        println!("{:>6} {:>7}", "frames", "faltas");
+
+        for &(frames, faults) in &self.faults {
+            let anomaly = self
+                .anomalies
+                .iter()
+                .any(|anomaly| anomaly.frames + 1 == frames);
+
+            println!(
+                "{:>6} {:>7}{}",
+                frames,
+                faults,
+                if anomaly {
+                    "  <- mais que com um frame a menos"
+                } else {
+                    ""
+                }
+            );
+        }
+
+        if self.occurred() {
+            println!("a anomalia aconteceu {} vez(es)", self.anomalies.len());
+        } else {
+            println!("a anomalia não aconteceu");
+        }
+    }
+}
+
+/// Executa a sequência de páginas `reference` com o FIFO em memórias de 1 a
+/// `max_frames` frames, e procura a anomalia de Belady: um frame a mais
+/// levando a mais faltas. Cada página é lida uma vez, no seu primeiro byte.
+/// `max_frames` vai até 16, o último de uma sequência de números
+/// consecutivos em `FRAME_COUNTS`.
+///
+/// Como a anomalia com `F` e `F + k` frames sempre aparece também entre
+/// algum par de números consecutivos entre eles, basta comparar cada número
+/// de frames com o anterior.
+///
+/// ```
+/// use vm::simulate::belady_anomaly;
+///
+/// // A sequência clássica: 9 faltas com 3 frames, mas 10 com 4.
+/// let report = belady_anomaly(&[1, 2, 3, 4, 1, 2, 5, 1, 2, 3, 4, 5], 5).unwrap();
+///
+/// assert!(report.occurred());
+/// assert_eq!(report.anomalies[0].frames, 3);
+/// assert_eq!(report.anomalies[0].faults, 9);
+/// assert_eq!(report.anomalies[0].faults_with_one_more, 10);
+/// ```
+pub fn belady_anomaly(
+    reference: &[usize],
+    max_frames: usize,
+) -> Result<BeladyReport, SimulateError> {
+    if let Some(&page) = reference.iter().find(|&&page| page >= 256) {
+        return Err(SimulateError::PageOutOfRange(page));
+    }
+
+    let trace: Vec<Access> = reference
+        .iter()
+        .map(|&page| Access {
+            kind: AccessKind::Read,
+            address: page << 8,
+            value: 0,
+        })
+        .collect();
+    let frame_counts: Vec<usize> = (1..=max_frames).collect();
+    let curve = miss_rate_curve(&trace, &frame_counts, FIFOPageReplacer::new)?;
+
+    let faults: Vec<(usize, usize)> = curve
+        .points
+        .iter()
+        .map(|point| (point.frames, point.misses))
+        .collect();
+    let anomalies = faults
+        .windows(2)
+        .filter(|pair| pair[1].1 > pair[0].1)
+        .map(|pair| BeladyAnomaly {
+            frames: pair[0].0,
+            faults: pair[0].1,
+            faults_with_one_more: pair[1].1,
+        })
+        .collect();
+
+    Ok(BeladyReport {
+        reference: reference.to_vec(),
+        faults,
+        anomalies,
+    })
+}
+
+/// Executa `trace` com as duas políticas (nomes do `ReplacerRegistry`, com
+/// a semente 0) na mesma geometria, e compara os traces das execuções.
+pub fn diff_replacers(
+    trace: &[Access],
+    geometry: Geometry,
+    replacers: [&str; 2],
+) -> Result<TraceDiff, SimulateError> {
+    let registry = ReplacerRegistry::new();
+
+    for name in replacers {
+        if registry.create(name, 0).is_none() {
+            return Err(SimulateError::UnknownReplacer(name.to_string()));
+        }
+    }
+
+    if !FRAME_COUNTS.contains(&geometry.frames) {
+        return Err(SimulateError::UnsupportedGeometry(geometry));
+    }
+
+    check_trace(trace)?;
+
+    let [a, b] = replacers.map(|name| {
+        let replacer = registry.create(name, 0).unwrap();
+        run(geometry.frames, replacer, trace, true).1
+    });
+
+    // As duas execuções fizeram os mesmos acessos.
+    let diff =
+        TraceDiff::from_records(&a, &b).unwrap_or_else(|error| panic!("simulate: {}", error));
+
+    Ok(diff.with_labels(replacers[0], replacers[1]))
+}
+
+/// Define `FRAME_COUNTS` e `run` a partir da mesma lista de números de
+/// frames: cada um é uma Mmu diferente, compilada à parte.
+macro_rules! frame_counts {
+    ($($frames:literal),*) => {
+        /// Os números de frames aceitos: todos de 1 a 16, para curvas
+        /// detalhadas e para a anomalia de Belady, e alguns maiores, mais
+        /// espaçados.
+        pub const FRAME_COUNTS: &[usize] = &[$($frames),*];
+
+        /// Executa a sequência numa Mmu com `frames` frames, que já foi
+        /// conferido contra `FRAME_COUNTS`. Com `traced`, devolve também os
+        /// registros dos acessos.
+        fn run(
+            frames: usize,
+            replacer: Box<dyn PageReplacer>,
+            trace: &[Access],
+            traced: bool,
+        ) -> (MmuStats, Vec<AccessRecord>) {
+            match frames {
+                $($frames => run_mmu::<{ $frames * 256 }, $frames>(replacer, trace, traced),)*
+                frames => unreachable!("simulate: {} frames não é um número suportado", frames),
+            }
+        }
+    };
+}
+
+frame_counts!(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 24, 32, 48, 64, 128, 256);
+
+fn run_mmu<const MEM_SIZE: usize, const FRAME_COUNT: usize>(
+    replacer: Box<dyn PageReplacer>,
+    trace: &[Access],
+    traced: bool,
+) -> (MmuStats, Vec<AccessRecord>) {
+    let mut mmu = Mmu::<MEM_SIZE, FRAME_COUNT, 256, _, _>::new(replacer, MemoryPageLoader::new());
+    if traced {
+        mmu.enable_trace();
+    }
+
+    // Os endereços já foram conferidos (veja `check_trace`), e a memória
+    // nunca falha.
+    for access in trace {
+        access
+            .apply(&mut mmu)
+            .unwrap_or_else(|error| panic!("simulate: {}", error));
+    }
+
+    let records = mmu
+        .trace()
+        .map(|trace| trace.records().to_vec())
+        .unwrap_or_default();
+
+    (MmuStats::clone(&mmu.stats), records)
+}
+
+/// Recusa uma sequência com um endereço fora das 256 páginas das Mmus das
+/// execuções.
+fn check_trace(trace: &[Access]) -> Result<(), SimulateError> {
+    match trace.iter().find(|access| access.address > 0xFFFF) {
+        Some(access) => Err(SimulateError::PageOutOfRange(access.address >> 8)),
+        None => Ok(()),
+    }
+}
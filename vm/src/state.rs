@@ -0,0 +1,322 @@
+//! O estado da page table num documento TOML legível, para montar à mão a
+//! configuração inicial de um exercício (veja `Mmu::load_state`) ou guardar
+//! a de uma execução (veja `Mmu::export_state`):
+//!
+//! ```toml
+//! # Quatro frames, três ocupados; a página 0x0C está suja.
+//! frames = 4
+//!
+//! [[page]]
+//! page = 0x0A
+//! frame = 0
+//!
+//! [[page]]
+//! page = 0x0C
+//! frame = 3
+//! dirty = true
+//! referenced = true
+//!
+//! [[page]]
+//! page = 0x01
+//! frame = 1
+//! pinned = true
+//! age = 0b1100_0000
+//! software = 0b0101
+//! ```
+//!
+//! Cada `[[page]]` é uma página residente: `page` e `frame` são
+//! obrigatórios; `dirty`, `referenced` e `pinned` são falsos, e `age` e
+//! `software` são zero, se ausentes. A ordem das páginas no documento é a
+//! ordem em que elas foram carregadas, a mais antiga primeiro: é a fila do
+//! FIFO, por exemplo. `frames` é o número de frames da Mmu, conferido ao
+//! carregar.
+//!
+//! A crate não tem dependências, então o documento é lido por um leitor
+//! próprio, de um subconjunto do TOML: chaves simples, inteiros (decimais,
+//! `0x` ou `0b`, com `_` entre os dígitos), booleanos e comentários com `#`.
+//! Todo documento escrito por `to_toml` é TOML válido.
+
+use std::fmt::Write;
+
+use thiserror::Error;
+
+use crate::{error::LoaderError, page_table::SOFTWARE_BITS};
+
+/// Uma página residente no estado da page table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageState {
+    pub page: usize,
+    pub frame: usize,
+    pub dirty: bool,
+    pub referenced: bool,
+    pub pinned: bool,
+    /// O histórico do bit de referência (veja `PageTableEntry::age`).
+    pub age: u8,
+    /// Os bits de software (veja `PageTableEntry::software`).
+    pub software: u8,
+}
+
+/// O estado da page table: as páginas residentes, da carregada há mais
+/// tempo para a mais recente.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageTableState {
+    /// Quantos frames a Mmu tem.
+    pub frames: usize,
+    pub pages: Vec<PageState>,
+}
+
+/// Erro ao ler ou carregar o estado da page table.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum StateError {
+    /// Uma linha do documento não pôde ser lida. A linha começa em 1.
+    #[error("linha {line}: {message}")]
+    Parse { line: usize, message: String },
+    /// O documento é de uma Mmu com outro número de frames.
+    #[error("o estado é de uma memória com {found} frames, e esta tem {expected}")]
+    FrameCount { expected: usize, found: usize },
+    /// A página não existe no espaço de endereçamento.
+    #[error("a página {0:#04X} não existe")]
+    PageOutOfRange(usize),
+    /// O frame não existe na memória física.
+    #[error("o frame {0:#04X} não existe")]
+    FrameOutOfRange(usize),
+    /// A página aparece mais de uma vez.
+    #[error("a página {0:#04X} aparece mais de uma vez")]
+    DuplicatePage(usize),
+    /// O frame já é de outra página, ou da page table.
+    #[error("o frame {0:#04X} já está em uso")]
+    FrameInUse(usize),
+    /// A Mmu já tem páginas em memória.
+    #[error("a memória já tem páginas carregadas")]
+    NotEmpty,
+    /// O carregador não conseguiu ler o conteúdo de uma página.
+    #[error(transparent)]
+    Loader(LoaderError),
+}
+
+impl PageTableState {
+    /// Escreve o estado como um documento TOML, que `from_toml` lê de volta.
+    /// Os campos falsos ou zerados são omitidos.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+
+        // Escrever numa String nunca falha, então podemos ignorar os erros.
+        let _ = writeln!(out, "frames = {}", self.frames);
+
+        for page in &self.pages {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[page]]");
+            let _ = writeln!(out, "page = {:#04X}", page.page);
+            let _ = writeln!(out, "frame = {}", page.frame);
+
+            for (key, value) in [
+                ("dirty", page.dirty),
+                ("referenced", page.referenced),
+                ("pinned", page.pinned),
+            ] {
+                if value {
+                    let _ = writeln!(out, "{} = true", key);
+                }
+            }
+
+            if page.age != 0 {
+                let _ = writeln!(out, "age = {:#010b}", page.age);
+            }
+
+            if page.software != 0 {
+                let _ = writeln!(
+                    out,
+                    "software = {:#0width$b}",
+                    page.software,
+                    width = SOFTWARE_BITS as usize + 2
+                );
+            }
+        }
+
+        out
+    }
+
+    /// Lê um documento escrito à mão ou por `to_toml`. Só a forma do
+    /// documento é conferida aqui; as páginas e os frames são conferidos
+    /// contra a Mmu por `Mmu::load_state`.
+    pub fn from_toml(text: &str) -> Result<PageTableState, StateError> {
+        let mut frames = None;
+        let mut pages = Vec::new();
+        // A página em leitura e a linha do seu `[[page]]`, com as chaves já
+        // vistas.
+        let mut current: Option<(usize, PartialPage)> = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let line = match line.split_once('#') {
+                Some((line, _comment)) => line.trim(),
+                None => line.trim(),
+            };
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: &str| StateError::Parse {
+                line: i + 1,
+                message: format!("{}: '{}'", message, line),
+            };
+
+            if line == "[[page]]" {
+                if let Some((start, page)) = current.take() {
+                    pages.push(page.finish(start)?);
+                }
+                current = Some((i + 1, PartialPage::default()));
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), parse_value(value.trim())))
+                .ok_or_else(|| error("esperava 'chave = valor' ou '[[page]]'"))?;
+            let value = value.ok_or_else(|| error("valor inválido"))?;
+
+            match &mut current {
+                None if key == "frames" => {
+                    if frames.is_some() {
+                        return Err(error("chave repetida"));
+                    }
+                    frames = Some(
+                        value
+                            .integer()
+                            .ok_or_else(|| error("esperava um inteiro"))?,
+                    );
+                }
+                None => return Err(error("chave desconhecida")),
+                Some((_, page)) => page.set(key, value).map_err(error)?,
+            }
+        }
+
+        if let Some((start, page)) = current {
+            pages.push(page.finish(start)?);
+        }
+
+        Ok(PageTableState {
+            frames: frames.ok_or(StateError::Parse {
+                line: 1,
+                message: "falta a chave 'frames'".to_string(),
+            })?,
+            pages,
+        })
+    }
+}
+
+/// Um valor do documento.
+#[derive(Clone, Copy)]
+enum Value {
+    Integer(usize),
+    Boolean(bool),
+}
+
+impl Value {
+    fn integer(self) -> Option<usize> {
+        match self {
+            Value::Integer(value) => Some(value),
+            Value::Boolean(_) => None,
+        }
+    }
+
+    fn boolean(self) -> Option<bool> {
+        match self {
+            Value::Boolean(value) => Some(value),
+            Value::Integer(_) => None,
+        }
+    }
+}
+
+fn parse_value(text: &str) -> Option<Value> {
+    match text {
+        "true" => return Some(Value::Boolean(true)),
+        "false" => return Some(Value::Boolean(false)),
+        _ => {}
+    }
+
+    let (digits, radix) = if let Some(digits) = text.strip_prefix("0x") {
+        (digits, 16)
+    } else if let Some(digits) = text.strip_prefix("0b") {
+        (digits, 2)
+    } else {
+        (text, 10)
+    };
+
+    // Como no TOML, `_` só pode ficar entre dois dígitos.
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return None;
+    }
+
+    usize::from_str_radix(&digits.replace('_', ""), radix)
+        .ok()
+        .map(Value::Integer)
+}
+
+/// Uma página em leitura: as chaves vistas até agora.
+#[derive(Default)]
+struct PartialPage {
+    page: Option<usize>,
+    frame: Option<usize>,
+    dirty: Option<bool>,
+    referenced: Option<bool>,
+    pinned: Option<bool>,
+    age: Option<u8>,
+    software: Option<u8>,
+}
+
+impl PartialPage {
+    fn set(&mut self, key: &str, value: Value) -> Result<(), &'static str> {
+        fn store<T>(
+            slot: &mut Option<T>,
+            value: Option<T>,
+            kind: &'static str,
+        ) -> Result<(), &'static str> {
+            if slot.is_some() {
+                return Err("chave repetida");
+            }
+            *slot = Some(value.ok_or(kind)?);
+            Ok(())
+        }
+
+        let integer = value.integer();
+        let boolean = value.boolean();
+        let byte = |bits: u32| {
+            integer
+                .filter(|&value| value < 1 << bits)
+                .map(|value| value as u8)
+        };
+
+        match key {
+            "page" => store(&mut self.page, integer, "esperava um inteiro"),
+            "frame" => store(&mut self.frame, integer, "esperava um inteiro"),
+            "dirty" => store(&mut self.dirty, boolean, "esperava um booleano"),
+            "referenced" => store(&mut self.referenced, boolean, "esperava um booleano"),
+            "pinned" => store(&mut self.pinned, boolean, "esperava um booleano"),
+            "age" => store(&mut self.age, byte(8), "esperava um inteiro de 8 bits"),
+            "software" => store(
+                &mut self.software,
+                byte(SOFTWARE_BITS),
+                "esperava um inteiro de 4 bits",
+            ),
+            _ => Err("chave desconhecida"),
+        }
+    }
+
+    /// A página completa; `line` é a linha do seu `[[page]]`.
+    fn finish(self, line: usize) -> Result<PageState, StateError> {
+        let missing = |key: &str| StateError::Parse {
+            line,
+            message: format!("falta a chave '{}' da página", key),
+        };
+
+        Ok(PageState {
+            page: self.page.ok_or_else(|| missing("page"))?,
+            frame: self.frame.ok_or_else(|| missing("frame"))?,
+            dirty: self.dirty.unwrap_or(false),
+            referenced: self.referenced.unwrap_or(false),
+            pinned: self.pinned.unwrap_or(false),
+            age: self.age.unwrap_or(0),
+            software: self.software.unwrap_or(0),
+        })
+    }
+}
@@ -0,0 +1,31 @@
+//! Os tipos de sincronização usados pela crate.
+//!
+//! A Mmu em si é usada por uma thread só (todas as operações pegam
+//! `&mut self`). O que é compartilhado entre threads são as estatísticas:
+//! `MmuStats` e os seus histogramas são atualizados pela Mmu e lidos ao vivo
+//! por outra thread (veja `Mmu::stats_handle`).
+//!
+//! Todos os atômicos e `Arc`s passam por este módulo, e não por `std::sync`
+//! direto, para que possam ser trocados num lugar só pelos do `loom`, um
+//! model checker que executa o código concorrente em todas as intercalações
+//! possíveis. Compilando com `--cfg loom`, a crate usa os tipos do `loom`,
+//! e os testes em `tests/loom.rs` rodam dentro de `loom::model`:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test -p vm --test loom --release
+//! ```
+//!
+//! Fora de `loom::model`, os tipos do `loom` entram em pânico, então com
+//! `--cfg loom` só esses testes fazem sentido.
+
+#[cfg(not(loom))]
+pub(crate) use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[cfg(loom)]
+pub(crate) use loom::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
@@ -0,0 +1,760 @@
+//! Chamadas de sistema: a interface dos processos com a memória.
+//!
+//! As regiões (veja o módulo `region`) são a visão da Mmu: páginas com
+//! dono, nome e permissões. Um programa de usuário não escolhe as suas
+//! páginas; ele pede memória ao kernel, que escolhe por ele. O `Kernel`
+//! deste módulo faz esse papel, com as chamadas de sistema do Unix que
+//! mexem na memória:
+//!
+//! - `spawn` cria um processo, reservando as páginas do seu heap;
+//! - `brk`/`sbrk` movem o fim do heap (o *break*), mapeando ou liberando as
+//!   páginas reservadas, como o `malloc` faz por baixo;
+//! - `mmap`/`munmap` mapeiam e desmapeiam regiões anônimas;
+//! - `fork` copia o processo, compartilhando as páginas com copy-on-write
+//!   (veja o módulo `sharing`);
+//! - `grow_stack` estende a pilha para baixo, como o kernel faz numa falta
+//!   logo abaixo dela;
+//! - `exit` desmapeia tudo o que o processo tinha.
+//!
+//! Como as regiões, todos os processos dividem as mesmas páginas virtuais:
+//! o filho do `fork` ganha páginas próprias, com o mesmo conteúdo, em outros
+//! endereços (veja `Kernel::process`). O heap cresce para cima a partir das
+//! páginas mais baixas livres, e os `mmap` ocupam as mais altas, como no
+//! layout do Linux. A página 0 nunca é usada, para que o endereço 0 seja
+//! inválido como um `NULL`, e as páginas do supervisor (veja o módulo
+//! `privilege`) também não. A pilha é reservada junto com o processo, nas
+//! páginas mais altas livres, e começa vazia.
+//!
+//! Como o `setrlimit` do Unix, `Kernel::set_limits` limita o tamanho do
+//! heap, da pilha e do conjunto residente de cada processo (veja `Limits`).
+//! Passar de um limite é um erro da chamada de sistema, ou, no caso do RSS,
+//! uma falta de acesso na Mmu (veja `Mmu::set_rss_limit`).
+//!
+//! Os erros têm os nomes dos `errno` do POSIX (veja `SyscallError`).
+
+use std::{collections::BTreeMap, fmt, ops::Range, slice};
+
+use log::{debug, warn};
+use thiserror::Error;
+
+use crate::{
+    mmu::Mmu,
+    page_loader::PageLoader,
+    page_replacer::PageReplacer,
+    region::{Permissions, Pid},
+};
+
+/// O nome da região do heap de cada processo.
+pub const HEAP_REGION: &str = "heap";
+
+/// O nome da região da pilha de cada processo.
+pub const STACK_REGION: &str = "stack";
+
+/// Quantas páginas cada processo reserva para a pilha.
+pub const STACK_PAGES: usize = 8;
+
+/// Um recurso limitado por `Limits`, com o nome do `RLIMIT_*` do POSIX.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Resource {
+    /// O tamanho do heap (`RLIMIT_DATA`).
+    Heap,
+    /// O tamanho da pilha (`RLIMIT_STACK`).
+    Stack,
+    /// O tamanho do conjunto residente (`RLIMIT_RSS`).
+    Rss,
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resource::Heap => write!(f, "RLIMIT_DATA"),
+            Resource::Stack => write!(f, "RLIMIT_STACK"),
+            Resource::Rss => write!(f, "RLIMIT_RSS"),
+        }
+    }
+}
+
+/// Os limites de um processo, em bytes. `None` é sem limite, além do que
+/// já foi reservado.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub heap: Option<usize>,
+    pub stack: Option<usize>,
+    /// Arredondado para baixo para páginas inteiras.
+    pub rss: Option<usize>,
+}
+
+impl Limits {
+    /// O limite do recurso.
+    pub fn get(&self, resource: Resource) -> Option<usize> {
+        match resource {
+            Resource::Heap => self.heap,
+            Resource::Stack => self.stack,
+            Resource::Rss => self.rss,
+        }
+    }
+
+    /// Troca o limite do recurso.
+    pub fn set(&mut self, resource: Resource, bytes: Option<usize>) {
+        match resource {
+            Resource::Heap => self.heap = bytes,
+            Resource::Stack => self.stack = bytes,
+            Resource::Rss => self.rss = bytes,
+        }
+    }
+}
+
+/// Quantas vezes cada limite recusou um crescimento.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LimitHits {
+    pub heap: usize,
+    pub stack: usize,
+    /// As faltas recusadas pela Mmu (veja `MmuStats::rss_limit_hits`).
+    pub rss: usize,
+}
+
+impl LimitHits {
+    pub fn print_report(&self) {
+        println!("===== Limites =====");
+        println!("{} ao heap ({})", self.heap, Resource::Heap);
+        println!("{} à pilha ({})", self.stack, Resource::Stack);
+        println!("{} ao conjunto residente ({})", self.rss, Resource::Rss);
+    }
+}
+
+/// Uma chamada de sistema que falhou.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Error)]
+pub enum SyscallError {
+    /// Não há páginas livres o bastante, ou o break sairia do heap
+    /// reservado (`ENOMEM`).
+    #[error("ENOMEM: não há páginas livres o bastante")]
+    NoMemory,
+    /// O processo não existe (`ESRCH`).
+    #[error("ESRCH: o processo {0} não existe")]
+    NoSuchProcess(Pid),
+    /// O endereço não é o começo de um mapeamento do processo, ou o tamanho
+    /// pedido é zero (`EINVAL`).
+    #[error("EINVAL: argumento inválido")]
+    InvalidArgument,
+    /// O crescimento passaria do limite do processo (veja `Limits`).
+    #[error("ENOMEM: o limite {0} foi atingido")]
+    LimitExceeded(Resource),
+    /// O kernel não conseguiu acessar a memória do processo no endereço
+    /// dado, por exemplo para copiar as suas páginas num `fork` (`EFAULT`).
+    #[error("EFAULT: o acesso a {0:#06X} falhou")]
+    Fault(usize),
+}
+
+/// Uma região anônima mapeada por `mmap`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    /// O nome da região na Mmu, `mmap@<página>`.
+    pub name: String,
+    pub pages: Range<usize>,
+    pub permissions: Permissions,
+}
+
+/// Um processo, do ponto de vista do kernel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Process {
+    pub pid: Pid,
+    /// O processo que fez o `fork`, se este foi criado por um.
+    pub parent: Option<Pid>,
+    /// As páginas reservadas para o heap. Só as que ficam abaixo do break
+    /// estão mapeadas, na região `HEAP_REGION`.
+    pub heap: Range<usize>,
+    /// O break: o endereço do fim do heap.
+    pub brk: usize,
+    /// As páginas reservadas para a pilha, que cresce para baixo a partir
+    /// do fim. Só as que ficam acima de `stack_bottom` estão mapeadas, na
+    /// região `STACK_REGION`.
+    pub stack: Range<usize>,
+    /// A página mais baixa da pilha mapeada, ou o fim da reserva se a pilha
+    /// está vazia.
+    pub stack_bottom: usize,
+    /// Os mapeamentos feitos por `mmap`, em ordem.
+    pub mappings: Vec<Mapping>,
+    pub limits: Limits,
+}
+
+impl Process {
+    /// As páginas do heap mapeadas agora, até o break.
+    pub fn heap_pages(&self) -> Range<usize> {
+        self.heap.start..((self.brk + 0xFF) >> 8)
+    }
+
+    /// As páginas da pilha mapeadas agora.
+    pub fn stack_pages(&self) -> Range<usize> {
+        self.stack_bottom..self.stack.end
+    }
+}
+
+/// Onde procurar páginas livres.
+#[derive(Copy, Clone)]
+enum Placement {
+    /// A partir das páginas mais baixas, para o heap.
+    Bottom,
+    /// A partir das mais altas, para os `mmap`.
+    Top,
+}
+
+/// O kernel: os processos e as suas chamadas de sistema.
+pub struct Kernel {
+    processes: BTreeMap<Pid, Process>,
+    next_pid: Pid,
+    limit_hits: LimitHits,
+}
+
+impl Kernel {
+    /// Constrói um kernel sem processos.
+    pub fn new() -> Self {
+        Kernel {
+            processes: BTreeMap::new(),
+            next_pid: 1,
+            limit_hits: LimitHits::default(),
+        }
+    }
+
+    /// O processo, se existe.
+    pub fn process(&self, pid: Pid) -> Option<&Process> {
+        self.processes.get(&pid)
+    }
+
+    /// Os processos vivos, em ordem de pid.
+    pub fn processes(&self) -> impl Iterator<Item = &Process> {
+        self.processes.values()
+    }
+
+    /// Cria um processo com `heap_pages` páginas reservadas para o heap e
+    /// `STACK_PAGES` para a pilha, que começam vazios. O pid não repete o de
+    /// nenhum processo com regiões vivas na Mmu, mesmo que mapeadas sem o
+    /// kernel.
+    pub fn spawn<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        heap_pages: usize,
+    ) -> Result<Pid, SyscallError> {
+        let heap = self
+            .find_free(mmu, heap_pages, Placement::Bottom, &[])
+            .ok_or(SyscallError::NoMemory)?;
+        let stack = self
+            .find_free(mmu, STACK_PAGES, Placement::Top, slice::from_ref(&heap))
+            .ok_or(SyscallError::NoMemory)?;
+
+        Ok(self.create(mmu, heap, stack))
+    }
+
+    /// Registra um processo novo, sem nada mapeado, com as reservas dadas.
+    fn create<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        heap: Range<usize>,
+        stack: Range<usize>,
+    ) -> Pid {
+        let pid = mmu
+            .regions()
+            .pids()
+            .last()
+            .map_or(self.next_pid, |pid| self.next_pid.max(pid + 1));
+        self.next_pid = pid + 1;
+
+        debug!(
+            "kernel: processo {} criado, heap em {:#04X?}, pilha em {:#04X?}",
+            pid, heap, stack
+        );
+
+        self.processes.insert(
+            pid,
+            Process {
+                pid,
+                parent: None,
+                brk: heap.start << 8,
+                heap,
+                stack_bottom: stack.end,
+                stack,
+                mappings: Vec::new(),
+                limits: Limits::default(),
+            },
+        );
+
+        pid
+    }
+
+    /// Troca os limites do processo. Os limites só valem para o crescimento
+    /// que vier depois: o que já está mapeado fica como está.
+    pub fn set_limits<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+        limits: Limits,
+    ) -> Result<(), SyscallError> {
+        let process = self
+            .processes
+            .get_mut(&pid)
+            .ok_or(SyscallError::NoSuchProcess(pid))?;
+
+        debug!("kernel: limites do processo {}: {:?}", pid, limits);
+
+        process.limits = limits;
+        mmu.set_rss_limit(pid, limits.rss.map(|bytes| bytes >> 8));
+
+        Ok(())
+    }
+
+    /// Os limites do processo, se ele existe.
+    pub fn limits(&self, pid: Pid) -> Option<Limits> {
+        self.processes.get(&pid).map(|process| process.limits)
+    }
+
+    /// Quantas vezes os limites recusaram um crescimento, de todos os
+    /// processos.
+    pub fn limit_hits<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &self,
+        mmu: &Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+    ) -> LimitHits {
+        LimitHits {
+            rss: mmu.stats.rss_limit_hits(),
+            ..self.limit_hits
+        }
+    }
+
+    /// Move o break do processo para `address`, mapeando as páginas do heap
+    /// que passam a ficar abaixo dele (vazias) e liberando as que ficam
+    /// acima. Devolve o novo break.
+    pub fn brk<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+        address: usize,
+    ) -> Result<usize, SyscallError> {
+        let process = self
+            .processes
+            .get_mut(&pid)
+            .ok_or(SyscallError::NoSuchProcess(pid))?;
+
+        if address < process.heap.start << 8 || address > process.heap.end << 8 {
+            return Err(SyscallError::NoMemory);
+        }
+        if process
+            .limits
+            .heap
+            .is_some_and(|limit| address - (process.heap.start << 8) > limit)
+        {
+            debug!("kernel: processo {} no limite do heap", pid);
+            self.limit_hits.heap += 1;
+            return Err(SyscallError::LimitExceeded(Resource::Heap));
+        }
+
+        let previous = process.heap_pages();
+        process.brk = address;
+        let pages = process.heap_pages();
+
+        debug!(
+            "kernel: break do processo {} em {:#06X}, heap em {:#04X?}",
+            pid, address, pages
+        );
+
+        // O heap fica na reserva do processo, que `find_free` achou dentro
+        // do espaço de endereçamento.
+        if previous.is_empty() && !pages.is_empty() {
+            mmu.map_region(pid, HEAP_REGION, pages)
+                .unwrap_or_else(|error| panic!("kernel: {}", error));
+        } else if !previous.is_empty() && pages.is_empty() {
+            mmu.unmap_region(pid, HEAP_REGION);
+        } else if previous != pages {
+            mmu.resize_region(pid, HEAP_REGION, pages)
+                .unwrap_or_else(|error| panic!("kernel: {}", error));
+        }
+
+        Ok(address)
+    }
+
+    /// Move o break do processo `increment` bytes, para frente ou para
+    /// trás, devolvendo o break anterior: o começo da memória nova, como no
+    /// `sbrk` do Unix.
+    pub fn sbrk<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+        increment: isize,
+    ) -> Result<usize, SyscallError> {
+        let previous = self
+            .processes
+            .get(&pid)
+            .ok_or(SyscallError::NoSuchProcess(pid))?
+            .brk;
+        let address = previous
+            .checked_add_signed(increment)
+            .ok_or(SyscallError::NoMemory)?;
+
+        self.brk(mmu, pid, address)?;
+
+        Ok(previous)
+    }
+
+    /// Estende a pilha do processo para baixo até a página de `address`,
+    /// mapeando as páginas novas, vazias. Devolve o endereço do novo fundo da
+    /// pilha. Um endereço fora da reserva da pilha é `ENOMEM`, como uma falta
+    /// de segmentação; um que já está na pilha não muda nada.
+    pub fn grow_stack<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+        address: usize,
+    ) -> Result<usize, SyscallError> {
+        let process = self
+            .processes
+            .get_mut(&pid)
+            .ok_or(SyscallError::NoSuchProcess(pid))?;
+
+        let page_number = address >> 8;
+        if !process.stack.contains(&page_number) {
+            return Err(SyscallError::NoMemory);
+        }
+        if page_number >= process.stack_bottom {
+            return Ok(process.stack_bottom << 8);
+        }
+        if process
+            .limits
+            .stack
+            .is_some_and(|limit| (process.stack.end - page_number) << 8 > limit)
+        {
+            debug!("kernel: processo {} no limite da pilha", pid);
+            self.limit_hits.stack += 1;
+            return Err(SyscallError::LimitExceeded(Resource::Stack));
+        }
+
+        let was_empty = process.stack_pages().is_empty();
+        process.stack_bottom = page_number;
+        let pages = process.stack_pages();
+
+        debug!("kernel: pilha do processo {} em {:#04X?}", pid, pages);
+
+        // Como o heap, a pilha fica na reserva do processo.
+        if was_empty {
+            mmu.map_region(pid, STACK_REGION, pages)
+                .unwrap_or_else(|error| panic!("kernel: {}", error));
+        } else {
+            mmu.resize_region(pid, STACK_REGION, pages)
+                .unwrap_or_else(|error| panic!("kernel: {}", error));
+        }
+
+        Ok(page_number << 8)
+    }
+
+    /// Mapeia `pages` páginas anônimas, vazias, para o processo, devolvendo
+    /// o endereço da primeira.
+    pub fn mmap<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+        pages: usize,
+        permissions: Permissions,
+    ) -> Result<usize, SyscallError> {
+        if !self.processes.contains_key(&pid) {
+            return Err(SyscallError::NoSuchProcess(pid));
+        }
+        if pages == 0 {
+            return Err(SyscallError::InvalidArgument);
+        }
+
+        let pages = self
+            .find_free(mmu, pages, Placement::Top, &[])
+            .ok_or(SyscallError::NoMemory)?;
+
+        Ok(self.map_anonymous(mmu, pid, pages, permissions) << 8)
+    }
+
+    /// Desmapeia o mapeamento do processo que começa em `address`,
+    /// liberando as suas páginas.
+    pub fn munmap<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+        address: usize,
+    ) -> Result<(), SyscallError> {
+        let process = self
+            .processes
+            .get_mut(&pid)
+            .ok_or(SyscallError::NoSuchProcess(pid))?;
+
+        let index = process
+            .mappings
+            .iter()
+            .position(|mapping| mapping.pages.start << 8 == address)
+            .ok_or(SyscallError::InvalidArgument)?;
+        let mapping = process.mappings.remove(index);
+
+        mmu.unmap_region(pid, &mapping.name);
+
+        Ok(())
+    }
+
+    /// Cria um filho do processo, com um heap, uma pilha e mapeamentos do
+    /// mesmo tamanho e com o mesmo conteúdo, em outras páginas, e com os
+    /// mesmos limites. Cada página do filho
+    /// compartilha o frame da página do pai com copy-on-write (veja
+    /// `Mmu::share_page`), ou é copiada se o frame não pode ser
+    /// compartilhado. Devolve o pid do filho.
+    pub fn fork<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+    ) -> Result<Pid, SyscallError> {
+        let parent = self
+            .processes
+            .get(&pid)
+            .ok_or(SyscallError::NoSuchProcess(pid))?
+            .clone();
+
+        // Escolhe todas as páginas antes de mapear qualquer uma, para não
+        // deixar um filho pela metade sem memória.
+        let heap = self
+            .find_free(mmu, parent.heap.len(), Placement::Bottom, &[])
+            .ok_or(SyscallError::NoMemory)?;
+        let stack = self
+            .find_free(
+                mmu,
+                parent.stack.len(),
+                Placement::Top,
+                slice::from_ref(&heap),
+            )
+            .ok_or(SyscallError::NoMemory)?;
+        let mut taken = vec![heap.clone(), stack.clone()];
+        for mapping in &parent.mappings {
+            let pages = self
+                .find_free(mmu, mapping.pages.len(), Placement::Top, &taken)
+                .ok_or(SyscallError::NoMemory)?;
+            taken.push(pages);
+        }
+
+        let child = self.create(mmu, heap.clone(), stack.clone());
+        let brk = (heap.start << 8) + parent.brk - (parent.heap.start << 8);
+
+        debug!("kernel: processo {} criado por fork de {}", child, pid);
+
+        self.processes.get_mut(&child).unwrap().parent = Some(pid);
+        self.brk(mmu, child, brk)?;
+        share_pages(mmu, parent.heap_pages(), heap.start)?;
+
+        if !parent.stack_pages().is_empty() {
+            let stack_bottom = stack.end - parent.stack_pages().len();
+            self.grow_stack(mmu, child, stack_bottom << 8)?;
+            share_pages(mmu, parent.stack_pages(), stack_bottom)?;
+        }
+        self.set_limits(mmu, child, parent.limits)?;
+
+        for (mapping, pages) in parent.mappings.iter().zip(taken.into_iter().skip(2)) {
+            let first_page = self.map_anonymous(mmu, child, pages, mapping.permissions);
+            share_pages(mmu, mapping.pages.clone(), first_page)?;
+        }
+
+        Ok(child)
+    }
+
+    /// Termina o processo, desmapeando o heap, a pilha e todos os
+    /// mapeamentos.
+    pub fn exit<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+    ) -> Result<(), SyscallError> {
+        let process = self
+            .processes
+            .remove(&pid)
+            .ok_or(SyscallError::NoSuchProcess(pid))?;
+
+        debug!("kernel: processo {} terminou", pid);
+
+        if !process.heap_pages().is_empty() {
+            mmu.unmap_region(pid, HEAP_REGION);
+        }
+        if !process.stack_pages().is_empty() {
+            mmu.unmap_region(pid, STACK_REGION);
+        }
+        mmu.set_rss_limit(pid, None);
+        for mapping in &process.mappings {
+            mmu.unmap_region(pid, &mapping.name);
+        }
+
+        Ok(())
+    }
+
+    /// Mapeia as páginas como um `mmap` do processo, devolvendo a primeira.
+    fn map_anonymous<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &mut self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pid: Pid,
+        pages: Range<usize>,
+        permissions: Permissions,
+    ) -> usize {
+        let name = format!("mmap@{:#04X}", pages.start);
+
+        // As páginas vêm de `find_free`, ou do pai no `fork`, dentro do
+        // espaço de endereçamento.
+        mmu.map_region_with_permissions(pid, &name, pages.clone(), permissions)
+            .unwrap_or_else(|error| panic!("kernel: {}", error));
+        self.processes
+            .get_mut(&pid)
+            .unwrap()
+            .mappings
+            .push(Mapping {
+                name,
+                pages: pages.clone(),
+                permissions,
+            });
+
+        pages.start
+    }
+
+    /// O primeiro intervalo de `pages` páginas livres, a partir de baixo ou
+    /// de cima. Uma página é livre se não é a página 0, não é do supervisor,
+    /// não está numa região viva, num heap ou pilha reservados ou em
+    /// `taken`.
+    fn find_free<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &self,
+        mmu: &Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+        pages: usize,
+        placement: Placement,
+        taken: &[Range<usize>],
+    ) -> Option<Range<usize>> {
+        let is_free = |page_number: usize| {
+            page_number != 0
+                && !mmu.is_supervisor_only(page_number)
+                && mmu.regions().find_live(page_number).is_none()
+                && !self.processes.values().any(|process| {
+                    process.heap.contains(&page_number) || process.stack.contains(&page_number)
+                })
+                && !taken.iter().any(|range| range.contains(&page_number))
+        };
+
+        if pages > PAGE_COUNT {
+            return None;
+        }
+
+        let starts = 0..=PAGE_COUNT - pages;
+        let mut candidates: Box<dyn Iterator<Item = usize>> = match placement {
+            Placement::Bottom => Box::new(starts),
+            Placement::Top => Box::new(starts.rev()),
+        };
+
+        candidates
+            .find(|&start| (start..start + pages).all(is_free))
+            .map(|start| start..start + pages)
+    }
+}
+
+impl Default for Kernel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Faz cada página a partir de `first_page` compartilhar o frame da página
+/// correspondente em `pages`, ou copia o conteúdo se não der. Uma página que
+/// não pôde ser lida interrompe a cópia com `EFAULT`.
+fn share_pages<
+    const MEM_SIZE: usize,
+    const FRAME_COUNT: usize,
+    const PAGE_COUNT: usize,
+    REPLACER: PageReplacer,
+    LOADER: PageLoader,
+>(
+    mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+    pages: Range<usize>,
+    first_page: usize,
+) -> Result<(), SyscallError> {
+    for (src, dst) in pages.zip(first_page..) {
+        let shared = mmu.share_page(src, dst).map_err(|error| {
+            warn!("kernel: {}", error);
+            SyscallError::Fault(src << 8)
+        })?;
+
+        if !shared {
+            mmu.copy(dst << 8, src << 8, 1 << 8).map_err(|error| {
+                warn!("kernel: {}", error);
+                SyscallError::Fault(src << 8)
+            })?;
+        }
+    }
+
+    Ok(())
+}
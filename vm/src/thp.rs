@@ -0,0 +1,26 @@
+//! Páginas grandes transparentes (THP, "transparent huge pages").
+//!
+//! Uma página grande cobre `HUGE_PAGE_PAGES` páginas base virtuais
+//! consecutivas, alinhadas, que estão em frames consecutivos, também
+//! alinhados. Como a tradução de uma é a da primeira mais um deslocamento,
+//! uma entrada só do TLB cobre todas (veja o módulo `tlb`), multiplicando o
+//! reach.
+//!
+//! Ninguém pede uma página grande: com `Mmu::enable_thp`, a cada tick a Mmu
+//! procura, como o `khugepaged` do Linux, trechos alinhados de páginas base
+//! todas residentes e os promove, movendo as páginas (como na compactação)
+//! para um grupo alinhado de frames livres ou já ocupados pelo próprio
+//! trecho. Quando uma das páginas sai do seu frame (é substituída,
+//! desmapeada, ou quebra um copy-on-write), a página grande é desfeita
+//! ("demoted") e volta a ser um conjunto de páginas base.
+//!
+//! Promoções, demoções, páginas movidas e hits no TLB em entradas de
+//! páginas grandes ficam em `MmuStats`.
+
+/// Quantas páginas base formam uma página grande.
+pub const HUGE_PAGE_PAGES: usize = 8;
+
+/// A primeira página da página grande que conteria a página.
+pub fn huge_page_of(page_number: usize) -> usize {
+    page_number - page_number % HUGE_PAGE_PAGES
+}
@@ -0,0 +1,138 @@
+//! Classificação dos misses nos "três Cs".
+//!
+//! A forma clássica de analisar os misses de um cache (e a memória física é
+//! um cache das páginas do disco) é separá-los pela causa:
+//!
+//! - **frio** (*compulsory*): o primeiro acesso à página, que erraria mesmo
+//!   com uma memória infinita;
+//! - **capacidade**: a página já foi usada, mas não cabe junto com as outras
+//!   que estão em uso: erraria também numa memória totalmente associativa,
+//!   com o mesmo número de frames e substituição LRU;
+//! - **conflito**: o resto, que a memória totalmente associativa com LRU
+//!   acertaria.
+//!
+//! Para isso, o classificador roda duas simulações à sombra da Mmu: uma
+//! memória infinita, que só guarda quais páginas já foram usadas, e uma
+//! memória LRU totalmente associativa com o mesmo número de frames. Numa
+//! cache associativa por conjuntos, os misses de conflito vêm das páginas que
+//! disputam o mesmo conjunto; os frames da Mmu (e o TLB, veja o módulo `tlb`)
+//! são totalmente associativos, então aqui eles vêm das escolhas da política
+//! de substituição, que às vezes tira uma página que o LRU teria mantido.
+//! Uma política melhor que o LRU numa carga tem poucos misses de conflito,
+//! mas não negativos: os acertos dela onde o LRU erraria não aparecem.
+//!
+//! A lista fantasma (veja o módulo `ghost`) faz uma separação parecida, mais
+//! barata, entre misses frios e refaults; a diferença é que ela esquece as
+//! páginas substituídas há muito tempo, e a memória infinita não.
+
+use std::collections::{BTreeSet, VecDeque};
+
+/// A causa de um miss.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MissClass {
+    Cold,
+    Capacity,
+    Conflict,
+}
+
+/// Quantos misses de cada causa.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MissBreakdown {
+    pub cold: usize,
+    pub capacity: usize,
+    pub conflict: usize,
+}
+
+impl MissBreakdown {
+    /// O total de misses.
+    pub fn total(&self) -> usize {
+        self.cold + self.capacity + self.conflict
+    }
+
+    pub fn print_report(&self) {
+        let total = self.total().max(1) as f64;
+
+        println!("===== Misses pelos três Cs =====");
+        println!(
+            "  Frios:      {:>6} ({:>6.2} %)",
+            self.cold,
+            self.cold as f64 * 100.0 / total
+        );
+        println!(
+            "  Capacidade: {:>6} ({:>6.2} %)",
+            self.capacity,
+            self.capacity as f64 * 100.0 / total
+        );
+        println!(
+            "  Conflito:   {:>6} ({:>6.2} %)",
+            self.conflict,
+            self.conflict as f64 * 100.0 / total
+        );
+    }
+}
+
+/// O classificador: as simulações à sombra e as contagens.
+#[derive(Clone, Debug)]
+pub struct ThreeCs {
+    /// A memória infinita: as páginas já usadas.
+    seen: BTreeSet<usize>,
+    /// A memória LRU totalmente associativa, da página usada há mais tempo
+    /// para a mais recente.
+    lru: VecDeque<usize>,
+    frames: usize,
+    breakdown: MissBreakdown,
+}
+
+impl ThreeCs {
+    /// Constrói um classificador para uma memória de `frames` frames.
+    pub fn new(frames: usize) -> Self {
+        ThreeCs {
+            seen: BTreeSet::new(),
+            lru: VecDeque::with_capacity(frames),
+            frames,
+            breakdown: MissBreakdown::default(),
+        }
+    }
+
+    /// Registra um acesso à página, que acertou ou não a memória de verdade,
+    /// e atualiza as simulações. Devolve a causa, se foi um miss.
+    pub fn access(&mut self, page_number: usize, hit: bool) -> Option<MissClass> {
+        let first_use = self.seen.insert(page_number);
+
+        let lru_hit = match self.lru.iter().position(|&page| page == page_number) {
+            Some(position) => {
+                self.lru.remove(position);
+                true
+            }
+            None => {
+                if self.lru.len() == self.frames {
+                    self.lru.pop_front();
+                }
+                false
+            }
+        };
+        self.lru.push_back(page_number);
+
+        if hit {
+            return None;
+        }
+
+        let class = if first_use {
+            self.breakdown.cold += 1;
+            MissClass::Cold
+        } else if !lru_hit {
+            self.breakdown.capacity += 1;
+            MissClass::Capacity
+        } else {
+            self.breakdown.conflict += 1;
+            MissClass::Conflict
+        };
+
+        Some(class)
+    }
+
+    /// Os misses classificados até agora.
+    pub fn breakdown(&self) -> MissBreakdown {
+        self.breakdown
+    }
+}
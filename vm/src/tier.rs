@@ -0,0 +1,279 @@
+//! Memória em camadas: uma parte dos frames é rápida (DRAM) e o resto é
+//! lento (memória atrás de CXL, ou persistente), com custos de acesso
+//! diferentes. Habilitada por `Mmu::enable_tiers`.
+//!
+//! Os primeiros `TierConfig::fast_frames` frames são a camada rápida. As
+//! page faults usam um frame rápido livre, se há; senão, qualquer um. A
+//! cada tick, a política de promoção (veja `PromotionPolicy`) olha quantas
+//! vezes cada página residente foi acessada desde o tick anterior e move as
+//! páginas quentes da camada lenta para a rápida, rebaixando as frias da
+//! rápida para abrir espaço, como o NUMA balancing do Linux faz entre os
+//! nós de memória.
+//!
+//! Páginas fixadas, compartilhadas, da page table ou de páginas grandes não
+//! mudam de frame, e ficam na camada em que foram carregadas.
+//!
+//! No fim, o `TierReport` mostra a fração dos acessos servida por cada
+//! camada, o tempo médio de acesso e o tráfego das migrações.
+
+use std::collections::BTreeMap;
+
+/// A camada de um frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tier {
+    Fast,
+    Slow,
+}
+
+/// Como as páginas migram entre as camadas, a cada tick.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PromotionPolicy {
+    /// As páginas ficam no frame em que foram carregadas. Serve de base
+    /// para comparar as outras políticas.
+    Static,
+    /// As páginas da camada lenta com pelo menos `threshold` acessos desde
+    /// o tick anterior sobem, as mais acessadas primeiro, até `budget`
+    /// páginas por tick. Sem frame rápido livre, a página sobe trocando de
+    /// frame com a página da camada rápida menos acessada no período, que
+    /// desce, se essa teve menos acessos que ela.
+    HotPages { threshold: usize, budget: usize },
+}
+
+/// A configuração da memória em camadas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TierConfig {
+    /// Quantos frames, a partir do 0, são da camada rápida.
+    pub fast_frames: usize,
+    /// O custo de um acesso à camada rápida, em nanossegundos.
+    pub fast_latency: f64,
+    /// O custo de um acesso à camada lenta, em nanossegundos.
+    pub slow_latency: f64,
+    pub policy: PromotionPolicy,
+}
+
+impl TierConfig {
+    /// Uma camada rápida de `fast_frames` frames, com os custos típicos de
+    /// DRAM local (100 ns) e de memória atrás de CXL (250 ns).
+    pub fn new(fast_frames: usize, policy: PromotionPolicy) -> Self {
+        TierConfig {
+            fast_frames,
+            fast_latency: 100.0,
+            slow_latency: 250.0,
+            policy,
+        }
+    }
+
+    /// A camada do frame.
+    pub fn tier_of(&self, frame_index: usize) -> Tier {
+        if frame_index < self.fast_frames {
+            Tier::Fast
+        } else {
+            Tier::Slow
+        }
+    }
+}
+
+/// Os contadores da memória em camadas.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TierStats {
+    /// Acessos a páginas em frames rápidos.
+    pub fast_accesses: usize,
+    /// Acessos a páginas em frames lentos.
+    pub slow_accesses: usize,
+    /// Páginas movidas da camada lenta para a rápida.
+    pub promotions: usize,
+    /// Páginas movidas da camada rápida para a lenta.
+    pub demotions: usize,
+}
+
+impl TierStats {
+    /// A fração dos acessos servida pela camada.
+    pub fn hit_rate(&self, tier: Tier) -> f64 {
+        let accesses = match tier {
+            Tier::Fast => self.fast_accesses,
+            Tier::Slow => self.slow_accesses,
+        };
+
+        accesses as f64 / (self.fast_accesses + self.slow_accesses).max(1) as f64
+    }
+}
+
+/// Uma migração decidida pela política.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Migration {
+    /// A página sobe para o frame rápido livre `to`.
+    Promote { page: usize, to: usize },
+    /// A página lenta `promoted` troca de frame com a rápida `demoted`.
+    Swap { promoted: usize, demoted: usize },
+}
+
+/// O estado da memória em camadas dentro da Mmu.
+#[derive(Clone, Debug)]
+pub struct TieredMemory {
+    config: TierConfig,
+    stats: TierStats,
+    /// Quantas vezes cada página foi acessada desde o último tick.
+    heat: BTreeMap<usize, usize>,
+}
+
+impl TieredMemory {
+    pub fn new(config: TierConfig) -> Self {
+        TieredMemory {
+            config,
+            stats: TierStats::default(),
+            heat: BTreeMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &TierConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> &TierStats {
+        &self.stats
+    }
+
+    /// Conta um acesso à página, no frame em que ela está.
+    pub(crate) fn access(&mut self, page_number: usize, frame_index: usize) {
+        match self.config.tier_of(frame_index) {
+            Tier::Fast => self.stats.fast_accesses += 1,
+            Tier::Slow => self.stats.slow_accesses += 1,
+        }
+
+        *self.heat.entry(page_number).or_insert(0) += 1;
+    }
+
+    /// Decide as migrações deste tick e recomeça a contagem dos acessos.
+    ///
+    /// `movable` são as páginas residentes que podem mudar de frame, com os
+    /// seus frames, e `free_fast` os frames rápidos livres.
+    pub(crate) fn plan(
+        &mut self,
+        movable: &[(usize, usize)],
+        free_fast: &[usize],
+    ) -> Vec<Migration> {
+        let heat = std::mem::take(&mut self.heat);
+        let heat_of = |page_number: usize| heat.get(&page_number).copied().unwrap_or(0);
+
+        let PromotionPolicy::HotPages { threshold, budget } = self.config.policy else {
+            return Vec::new();
+        };
+
+        // As quentes da camada lenta, da mais acessada para a menos, e as
+        // da camada rápida, da menos acessada para a mais.
+        let (fast, slow): (Vec<_>, Vec<_>) = movable
+            .iter()
+            .map(|&(page_number, frame_index)| (page_number, frame_index, heat_of(page_number)))
+            .partition(|&(_, frame_index, _)| self.config.tier_of(frame_index) == Tier::Fast);
+
+        let mut hot: Vec<_> = slow
+            .into_iter()
+            .filter(|&(_, _, heat)| heat >= threshold.max(1))
+            .collect();
+        hot.sort_by_key(|&(page_number, _, heat)| (std::cmp::Reverse(heat), page_number));
+
+        let mut cold = fast;
+        cold.sort_by_key(|&(page_number, _, heat)| (heat, page_number));
+        let mut cold = cold.into_iter();
+
+        let mut free_fast = free_fast.iter().copied();
+        let mut migrations = Vec::new();
+
+        for (page_number, _, page_heat) in hot.into_iter().take(budget) {
+            if let Some(to) = free_fast.next() {
+                migrations.push(Migration::Promote {
+                    page: page_number,
+                    to,
+                });
+                self.stats.promotions += 1;
+                continue;
+            }
+
+            match cold.next() {
+                Some((demoted, _, cold_heat)) if cold_heat < page_heat => {
+                    migrations.push(Migration::Swap {
+                        promoted: page_number,
+                        demoted,
+                    });
+                    self.stats.promotions += 1;
+                    self.stats.demotions += 1;
+                }
+                // As páginas rápidas que sobraram são pelo menos tão quentes
+                // quanto as lentas que sobraram.
+                _ => break,
+            }
+        }
+
+        migrations
+    }
+}
+
+/// O relatório da memória em camadas (veja `Mmu::tier_report`).
+#[derive(Clone, Debug)]
+pub struct TierReport {
+    pub config: TierConfig,
+    /// Quantos frames a camada lenta tem.
+    pub slow_frames: usize,
+    pub page_size: usize,
+    pub stats: TierStats,
+}
+
+impl TierReport {
+    /// O tempo médio de um acesso à memória, em nanossegundos, com o custo
+    /// da camada de cada acesso.
+    pub fn average_latency(&self) -> f64 {
+        self.stats.hit_rate(Tier::Fast) * self.config.fast_latency
+            + self.stats.hit_rate(Tier::Slow) * self.config.slow_latency
+    }
+
+    /// Quantos bytes as migrações copiaram: uma página por promoção e por
+    /// rebaixamento.
+    pub fn migration_bytes(&self) -> usize {
+        (self.stats.promotions + self.stats.demotions) * self.page_size
+    }
+
+    pub fn print_report(&self) {
+        let policy = match self.config.policy {
+            PromotionPolicy::Static => "estática".to_string(),
+            PromotionPolicy::HotPages { threshold, budget } => format!(
+                "páginas quentes (>= {} acessos por tick, até {} por tick)",
+                threshold, budget
+            ),
+        };
+
+        println!("===== Memória em camadas =====");
+        println!("Política: {}", policy);
+        for (name, tier, frames, latency, accesses) in [
+            (
+                "rápida",
+                Tier::Fast,
+                self.config.fast_frames,
+                self.config.fast_latency,
+                self.stats.fast_accesses,
+            ),
+            (
+                "lenta",
+                Tier::Slow,
+                self.slow_frames,
+                self.config.slow_latency,
+                self.stats.slow_accesses,
+            ),
+        ] {
+            println!(
+                "  Camada {:<6} {:>4} frames, {:>5.0} ns: {:>8} acessos ({:>6.2} %)",
+                name,
+                frames,
+                latency,
+                accesses,
+                self.stats.hit_rate(tier) * 100.0
+            );
+        }
+        println!("Tempo médio de acesso: {:.1} ns", self.average_latency());
+        println!(
+            "Migrações: {} promoções, {} rebaixamentos ({} bytes copiados)",
+            self.stats.promotions,
+            self.stats.demotions,
+            self.migration_bytes()
+        );
+    }
+}
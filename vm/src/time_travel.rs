@@ -0,0 +1,134 @@
+//! Viagem no tempo: voltar a simulação N acessos (veja `Mmu::rewind`).
+//!
+//! Guardar o estado inteiro da Mmu a cada acesso seria caro demais, então
+//! guardamos:
+//!
+//! - snapshots periódicos do estado da Mmu (memória, page table, replacer,
+//!   estatísticas...), a cada `interval` acessos e depois de toda operação
+//!   que não é um acesso (mapear uma região, um tick manual, compactar...);
+//! - o log de todos os acessos (leituras e escritas, com os valores);
+//! - um journal das escritas no disco: antes de cada writeback, o conteúdo
+//!   anterior da página no disco é guardado.
+//!
+//! Para voltar ao acesso T, desfazemos as escritas no disco feitas desde o
+//! último snapshot anterior a T, restauramos esse snapshot e reexecutamos os
+//! acessos do log até T. Como a simulação é determinística, o estado
+//! resultante é exatamente o que existia no acesso T.
+//!
+//! Cada snapshot tem uma cópia da memória inteira, então só os últimos
+//! `MAX_SNAPSHOTS` são guardados. Quando o mais antigo sai, os acessos e as
+//! escritas no disco anteriores a ele saem junto: não há mais como voltar
+//! para antes dele.
+
+use std::collections::VecDeque;
+
+use thiserror::Error;
+
+use crate::error::LoaderError;
+
+/// Um acesso guardado no log, para ser reexecutado.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoggedAccess {
+    Read(usize),
+    /// Uma busca de instrução (veja `Mmu::fetch`).
+    Fetch(usize),
+    Write(usize, u8),
+    /// Uma escrita de vários bytes dentro de uma página (veja `Mmu::copy`).
+    WriteSlice(usize, Vec<u8>),
+    /// `len` bytes iguais a partir do endereço, dentro de uma página (veja
+    /// `Mmu::fill`).
+    Fill(usize, u8, usize),
+}
+
+/// Quantos snapshots a viagem no tempo guarda, no máximo.
+pub const MAX_SNAPSHOTS: usize = 64;
+
+/// O conteúdo de uma página no disco antes de um writeback.
+pub(crate) struct JournalEntry {
+    /// O acesso durante o qual o writeback aconteceu.
+    pub time: usize,
+    pub page_number: usize,
+    pub previous: Vec<u8>,
+}
+
+/// O estado de viagem no tempo da Mmu: snapshots de tipo `S` e uma função
+/// para clonar o replacer, de tipo `R`.
+pub(crate) struct TimeTravel<R, S> {
+    /// A cada quantos acessos tirar um snapshot.
+    pub interval: usize,
+    /// Os snapshots, como `(acesso, estado)`, em ordem de tempo. No máximo
+    /// `MAX_SNAPSHOTS`.
+    pub snapshots: VecDeque<(usize, S)>,
+    /// Os acessos desde o snapshot mais antigo, como `(acesso, operação)`,
+    /// em ordem de tempo.
+    pub accesses: VecDeque<(usize, LoggedAccess)>,
+    /// As escritas no disco desde o snapshot mais antigo, em ordem de tempo.
+    pub journal: VecDeque<JournalEntry>,
+    /// Clona o replacer. Guardamos a função para que só `enable_time_travel`
+    /// precise exigir `REPLACER: Clone`.
+    pub clone_replacer: fn(&R) -> R,
+}
+
+impl<R, S> TimeTravel<R, S> {
+    /// Constrói o estado vazio, com um snapshot a cada `interval` acessos.
+    pub fn new(interval: usize, clone_replacer: fn(&R) -> R) -> Self {
+        TimeTravel {
+            interval: interval.max(1),
+            snapshots: VecDeque::new(),
+            accesses: VecDeque::new(),
+            journal: VecDeque::new(),
+            clone_replacer,
+        }
+    }
+
+    /// Guarda um snapshot. Passando de `MAX_SNAPSHOTS`, o mais antigo sai, e
+    /// com ele o histórico anterior ao novo mais antigo.
+    pub fn push_snapshot(&mut self, time: usize, snapshot: S) {
+        self.snapshots.push_back((time, snapshot));
+
+        if self.snapshots.len() <= MAX_SNAPSHOTS {
+            return;
+        }
+
+        self.snapshots.pop_front();
+
+        let earliest = self.snapshots[0].0;
+        while self
+            .accesses
+            .front()
+            .is_some_and(|(time, _)| *time < earliest)
+        {
+            self.accesses.pop_front();
+        }
+        while self
+            .journal
+            .front()
+            .is_some_and(|entry| entry.time < earliest)
+        {
+            self.journal.pop_front();
+        }
+    }
+
+    /// Esquece todo o histórico. O próximo snapshot passa a ser o ponto mais
+    /// antigo para onde é possível voltar.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.accesses.clear();
+        self.journal.clear();
+    }
+}
+
+/// Erro ao voltar no tempo.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RewindError {
+    /// A viagem no tempo não foi habilitada.
+    #[error("viagem no tempo não habilitada")]
+    Disabled,
+    /// Não há histórico suficiente: só é possível voltar `available` acessos.
+    #[error("histórico insuficiente: só é possível voltar {available} acessos")]
+    TooFar { available: usize },
+    /// O carregador falhou ao desfazer uma escrita no disco (veja
+    /// `Mmu::rewind`).
+    #[error(transparent)]
+    Loader(LoaderError),
+}
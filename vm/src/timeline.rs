@@ -0,0 +1,120 @@
+//! Linha do tempo de ocupação dos frames.
+//!
+//! A partir de um trace, reconstrói, para cada frame, os intervalos (em tempo
+//! virtual, isto é, índice de acesso) durante os quais cada página o ocupou.
+//! Isso é o que um gráfico de Gantt de residência precisa: uma linha por
+//! frame, uma barra por página.
+//!
+//! Como só olhamos para o trace, a linha do tempo só é completa se o trace foi
+//! habilitado antes do primeiro acesso.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::trace::AccessRecord;
+
+/// Um intervalo durante o qual uma página ocupou um frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameInterval {
+    /// A página que ocupou o frame.
+    pub page_number: usize,
+    /// O acesso que carregou a página.
+    pub start: usize,
+    /// O acesso que substituiu a página, ou `None` se ela ainda está no frame
+    /// no final do trace.
+    pub end: Option<usize>,
+}
+
+/// A linha do tempo de todos os frames usados em um trace.
+pub struct FrameTimeline {
+    /// Os intervalos de cada frame, em ordem de índice de frame.
+    frames: BTreeMap<usize, Vec<FrameInterval>>,
+    /// O fim do trace, em tempo virtual: o tempo do último acesso mais um,
+    /// usado para fechar os intervalos abertos. Um trace habilitado depois
+    /// dos primeiros acessos não começa no tempo 0, então o número de
+    /// registros não serve.
+    accesses: usize,
+}
+
+impl FrameTimeline {
+    /// Reconstrói a linha do tempo a partir dos registros de um trace.
+    pub fn from_records(records: &[AccessRecord]) -> Self {
+        let mut frames: BTreeMap<usize, Vec<FrameInterval>> = BTreeMap::new();
+
+        // Só os misses mudam a ocupação de um frame: a página anterior (se
+        // havia) sai, e a nova entra.
+        for record in records.iter().filter(|record| !record.hit) {
+            let intervals = frames.entry(record.frame_index).or_default();
+
+            if let Some(last) = intervals.last_mut() {
+                last.end = Some(record.time);
+            }
+
+            intervals.push(FrameInterval {
+                page_number: record.page_number,
+                start: record.time,
+                end: None,
+            });
+        }
+
+        FrameTimeline {
+            frames,
+            accesses: records.last().map_or(0, |record| record.time + 1),
+        }
+    }
+
+    /// Os intervalos de um frame, em ordem de tempo.
+    pub fn intervals(&self, frame_index: usize) -> &[FrameInterval] {
+        self.frames
+            .get(&frame_index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Exporta a linha do tempo como JSON, no formato:
+    ///
+    /// ```text
+    /// {
+    ///   "accesses": 10,
+    ///   "frames": [
+    ///     { "frame": 0, "intervals": [
+    ///       { "page": 12, "start": 0, "end": 10, "resident": true }
+    ///     ] }
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Intervalos ainda abertos terminam no fim do trace e têm `resident`
+    /// verdadeiro.
+    pub fn write_json<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"accesses\": {},", self.accesses)?;
+        writeln!(out, "  \"frames\": [")?;
+
+        for (i, (frame_index, intervals)) in self.frames.iter().enumerate() {
+            writeln!(out, "    {{ \"frame\": {}, \"intervals\": [", frame_index)?;
+
+            for (j, interval) in intervals.iter().enumerate() {
+                let separator = if j + 1 < intervals.len() { "," } else { "" };
+
+                writeln!(
+                    out,
+                    "      {{ \"page\": {}, \"start\": {}, \"end\": {}, \"resident\": {} }}{}",
+                    interval.page_number,
+                    interval.start,
+                    interval.end.unwrap_or(self.accesses),
+                    interval.end.is_none(),
+                    separator
+                )?;
+            }
+
+            let separator = if i + 1 < self.frames.len() { "," } else { "" };
+            writeln!(out, "    ] }}{}", separator)?;
+        }
+
+        writeln!(out, "  ]")?;
+        writeln!(out, "}}")
+    }
+}
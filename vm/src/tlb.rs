@@ -0,0 +1,263 @@
+//! TLB: um cache das traduções de página para frame.
+//!
+//! Cada acesso precisa da tradução da sua página; com o TLB, as traduções
+//! usadas recentemente ficam num cache pequeno e totalmente associativo, e
+//! só um miss no TLB precisa ler a page table (o "page walk"). Habilitado
+//! por `Mmu::enable_tlb`.
+//!
+//! O quanto de memória o TLB cobre é o seu *reach*: o número de entradas
+//! vezes o tamanho da página de cada uma. Se o working set de um programa é
+//! maior que o reach, o TLB erra mesmo quando todas as páginas estão em
+//! memória -- o argumento clássico a favor de páginas grandes, que cobrem
+//! mais memória com a mesma entrada. Por isso cada entrada guarda quantas
+//! páginas base ela cobre, e os hits e misses são contados por tamanho de
+//! página.
+//!
+//! O TLB não é avisado quando uma página sai da memória ou muda de frame.
+//! Em vez disso, cada hit é conferido com a page table: uma entrada velha é
+//! descartada e conta como miss, como se tivesse sido invalidada (o
+//! "shootdown" de um sistema de verdade) no momento da mudança. Já uma
+//! mudança de proteção (veja `Mmu::set_protection`) não muda o frame, e
+//! invalida as entradas da página explicitamente (veja `Tlb::invalidate`).
+//!
+//! A consulta acontece dentro da tradução, com a Mmu emprestada por
+//! `&mut self`: não há uma page table compartilhada entre threads, nem um
+//! lock para um hit evitar. Um caminho rápido sem lock (um seqlock ou uma
+//! cópia das entradas no estilo RCU) só faz sentido quando a Mmu puder ser
+//! usada por várias threads (veja o módulo `sync`).
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// Uma tradução no TLB.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct TlbEntry {
+    /// A primeira página coberta.
+    page_number: usize,
+    /// Quantas páginas base a entrada cobre.
+    pages: usize,
+    /// O frame da primeira página; as seguintes estão nos frames seguintes.
+    frame_index: usize,
+}
+
+impl TlbEntry {
+    fn covers(&self, page_number: usize) -> bool {
+        (self.page_number..self.page_number + self.pages).contains(&page_number)
+    }
+
+    fn frame_of(&self, page_number: usize) -> usize {
+        self.frame_index + (page_number - self.page_number)
+    }
+}
+
+/// Os hits e misses do TLB com páginas de um tamanho.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlbCounts {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl TlbCounts {
+    /// A fração das traduções que acertou o TLB.
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / (self.hits + self.misses).max(1) as f64
+    }
+}
+
+/// Soma os hits e misses de todos os tamanhos de página.
+fn total_counts(counts: &BTreeMap<usize, TlbCounts>) -> TlbCounts {
+    counts
+        .values()
+        .fold(TlbCounts::default(), |total, counts| TlbCounts {
+            hits: total.hits + counts.hits,
+            misses: total.misses + counts.misses,
+        })
+}
+
+/// Um TLB totalmente associativo, com substituição LRU.
+#[derive(Clone, Debug)]
+pub struct Tlb {
+    capacity: usize,
+    /// O tamanho de uma página base, em bytes.
+    page_size: usize,
+    /// As entradas, da usada há mais tempo para a mais recente.
+    entries: VecDeque<TlbEntry>,
+    /// Os hits e misses por tamanho de página, em bytes.
+    counts: BTreeMap<usize, TlbCounts>,
+}
+
+impl Tlb {
+    /// Constrói um TLB vazio com `capacity` entradas, para páginas base de
+    /// `page_size` bytes.
+    pub fn new(capacity: usize, page_size: usize) -> Self {
+        Tlb {
+            capacity: capacity.max(1),
+            page_size,
+            entries: VecDeque::new(),
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// O número de entradas.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Procura a tradução da página. `frame_index` é o frame da página
+    /// segundo a page table (`None` se ela não está em memória): uma entrada
+    /// que discorda dele é velha, e é descartada.
+    ///
+    /// Num hit, devolve quantas páginas base a entrada cobre (mais de uma
+    /// numa página grande). Um miss só é contado quando a tradução é
+    /// guardada, por `insert` ou `insert_huge`, já com o tamanho da página.
+    pub fn lookup(&mut self, page_number: usize, frame_index: Option<usize>) -> Option<usize> {
+        let position = self
+            .entries
+            .iter()
+            .position(|entry| entry.covers(page_number))?;
+
+        let entry = self.entries.remove(position).unwrap();
+
+        if Some(entry.frame_of(page_number)) != frame_index {
+            return None;
+        }
+
+        self.counts
+            .entry(entry.pages * self.page_size)
+            .or_default()
+            .hits += 1;
+        self.entries.push_back(entry);
+
+        Some(entry.pages)
+    }
+
+    /// Guarda a tradução de uma página base, depois de um miss.
+    pub fn insert(&mut self, page_number: usize, frame_index: usize) {
+        self.insert_huge(page_number, 1, frame_index);
+    }
+
+    /// Guarda a tradução de uma página grande, de `pages` páginas base a
+    /// partir de `page_number` em frames consecutivos a partir de
+    /// `frame_index`, depois de um miss.
+    pub fn insert_huge(&mut self, page_number: usize, pages: usize, frame_index: usize) {
+        self.counts
+            .entry(pages * self.page_size)
+            .or_default()
+            .misses += 1;
+
+        let entry = TlbEntry {
+            page_number,
+            pages,
+            frame_index,
+        };
+
+        self.entries
+            .retain(|other| !(other.covers(entry.page_number) || entry.covers(other.page_number)));
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    /// Descarta as traduções que cobrem a página, como o shootdown de um
+    /// sistema de verdade. Uma entrada de página grande cai inteira.
+    pub fn invalidate(&mut self, page_number: usize) {
+        self.entries.retain(|entry| !entry.covers(page_number));
+    }
+
+    /// Descarta todas as traduções.
+    pub fn flush(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Os hits e misses, por tamanho de página em bytes.
+    pub fn counts_by_page_size(&self) -> &BTreeMap<usize, TlbCounts> {
+        &self.counts
+    }
+
+    /// Os hits e misses de todos os tamanhos de página somados.
+    pub fn counts(&self) -> TlbCounts {
+        total_counts(&self.counts)
+    }
+
+    /// O reach máximo, em bytes, com todas as entradas cobrindo uma página
+    /// base.
+    pub fn base_reach(&self) -> usize {
+        self.capacity * self.page_size
+    }
+
+    /// O reach atual, em bytes: a memória coberta pelas entradas presentes.
+    pub fn reach(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| entry.pages * self.page_size)
+            .sum()
+    }
+}
+
+/// O relatório do TLB ao fim de uma execução (veja `Mmu::tlb_report`).
+#[derive(Clone, Debug)]
+pub struct TlbReport {
+    pub capacity: usize,
+    pub page_size: usize,
+    /// Os hits e misses por tamanho de página, em bytes.
+    pub counts: BTreeMap<usize, TlbCounts>,
+    /// O reach com todas as entradas em páginas base, em bytes.
+    pub base_reach: usize,
+    /// O reach das entradas presentes no fim da execução, em bytes.
+    pub reach: usize,
+    /// O tamanho do working set no fim da execução, em bytes (veja
+    /// `Mmu::working_set_size`).
+    pub working_set: usize,
+}
+
+impl TlbReport {
+    /// A fração do working set que cabe no reach máximo do TLB.
+    pub fn coverage(&self) -> f64 {
+        if self.working_set == 0 {
+            return 1.0;
+        }
+
+        (self.base_reach as f64 / self.working_set as f64).min(1.0)
+    }
+
+    /// Os hits e misses de todos os tamanhos de página somados.
+    pub fn counts(&self) -> TlbCounts {
+        total_counts(&self.counts)
+    }
+
+    pub fn print_report(&self) {
+        let total = self.counts();
+
+        println!("===== TLB =====");
+        println!(
+            "Entradas: {} (reach de {} bytes com páginas de {} bytes)",
+            self.capacity, self.base_reach, self.page_size
+        );
+        println!(
+            "  Hits:   {:>6} ({:>6.2} %)",
+            total.hits,
+            total.hit_rate() * 100.0
+        );
+        println!("  Misses: {:>6}", total.misses);
+
+        if self.counts.len() > 1 {
+            for (page_size, counts) in &self.counts {
+                println!(
+                    "  Páginas de {:>6} bytes: {:>6} hits, {:>6} misses ({:>6.2} %)",
+                    page_size,
+                    counts.hits,
+                    counts.misses,
+                    counts.hit_rate() * 100.0
+                );
+            }
+        }
+
+        println!(
+            "Working set: {} bytes, {:.0}% cobertos pelo reach",
+            self.working_set,
+            self.coverage() * 100.0
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Trace: registro de todos os acessos feitos à Mmu.
+//!
+//! Quando habilitado (veja `Mmu::enable_trace`), cada leitura ou escrita gera
+//! um `AccessRecord`, contendo o endereço, a página, o frame e o que aconteceu
+//! durante a tradução (hit, miss, substituição, writeback).
+//!
+//! O trace pode ser exportado em alguns formatos para ser comparado com outros
+//! simuladores:
+//!
+//! - CSV, uma linha por acesso, com todos os campos do registro;
+//! - o formato texto do `lackey` do valgrind (`--trace-mem=yes`), que é aceito
+//!   pela maioria dos simuladores de cache usados em aula;
+//! - o formato binário de traces do ChampSim.
+//!
+//! Além do trace, quem usa a Mmu pode registrar um `AccessObserver`, que é
+//! chamado a cada acesso com o mesmo registro. É assim que a demo mostra o
+//! resultado de cada acesso sem depender dos logs.
+
+use std::io::{self, Write};
+
+use crate::privilege::PrivilegeMode;
+
+/// O tipo de um acesso à memória.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+    /// A busca de uma instrução, que é uma leitura numa página executável
+    /// (veja `Mmu::fetch`).
+    Fetch,
+}
+
+/// Um acesso registrado pela Mmu.
+#[derive(Copy, Clone, Debug)]
+pub struct AccessRecord {
+    /// O índice do acesso, começando em 0. Funciona como o "tempo virtual" da
+    /// simulação.
+    pub time: usize,
+    /// Se o acesso foi uma leitura ou uma escrita.
+    pub kind: AccessKind,
+    /// O endereço virtual acessado.
+    pub address: usize,
+    /// A página do endereço acessado.
+    pub page_number: usize,
+    /// O frame no qual a página estava (ou foi carregada).
+    pub frame_index: usize,
+    /// O modo de privilégio da CPU no acesso (veja o módulo `privilege`).
+    pub mode: PrivilegeMode,
+    /// Se o acesso foi um page hit.
+    pub hit: bool,
+    /// A página que foi substituída para abrir espaço, se houve substituição.
+    pub evicted: Option<usize>,
+    /// Se a página substituída estava suja e foi escrita de volta no disco.
+    pub writeback: bool,
+}
+
+/// Um observador dos acessos feitos à Mmu (veja `Mmu::add_observer`).
+pub trait AccessObserver {
+    /// Chamado depois de cada acesso, já com o resultado da tradução.
+    fn on_access(&mut self, record: &AccessRecord);
+}
+
+impl<F: FnMut(&AccessRecord)> AccessObserver for F {
+    fn on_access(&mut self, record: &AccessRecord) {
+        self(record)
+    }
+}
+
+/// O log de acessos de uma execução.
+#[derive(Default)]
+pub struct Trace {
+    records: Vec<AccessRecord>,
+}
+
+impl Trace {
+    /// Constrói um trace vazio.
+    pub fn new() -> Self {
+        Trace {
+            records: Vec::new(),
+        }
+    }
+
+    /// Adiciona um registro ao fim do trace.
+    pub fn push(&mut self, record: AccessRecord) {
+        self.records.push(record);
+    }
+
+    /// Descarta os registros a partir do acesso `time`.
+    pub fn truncate_at(&mut self, time: usize) {
+        self.records.retain(|record| record.time < time);
+    }
+
+    /// Todos os registros, em ordem de acesso.
+    pub fn records(&self) -> &[AccessRecord] {
+        &self.records
+    }
+
+    /// Exporta o trace como CSV, com header.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "time,kind,address,page,frame,hit,evicted,writeback")?;
+
+        for record in &self.records {
+            let kind = match record.kind {
+                AccessKind::Read => "R",
+                AccessKind::Write => "W",
+                AccessKind::Fetch => "X",
+            };
+
+            // Uma página não substituída fica com o campo vazio.
+            let evicted = record
+                .evicted
+                .map(|page| format!("{:#04X}", page))
+                .unwrap_or_default();
+
+            writeln!(
+                out,
+                "{},{},{:#06X},{:#04X},{:#04X},{},{},{}",
+                record.time,
+                kind,
+                record.address,
+                record.page_number,
+                record.frame_index,
+                record.hit as u8,
+                evicted,
+                record.writeback as u8
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Exporta o trace no formato do `lackey` do valgrind.
+    ///
+    /// Cada linha é ` L <endereço>,<tamanho>` para leituras,
+    /// ` S <endereço>,<tamanho>` para escritas e `I  <endereço>,<tamanho>`
+    /// (sem o espaço no começo) para buscas de instrução. Como a Mmu só faz
+    /// acessos de um byte, o tamanho é sempre 1.
+    pub fn write_lackey<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for record in &self.records {
+            match record.kind {
+                AccessKind::Read => writeln!(out, " L {:08x},1", record.address)?,
+                AccessKind::Write => writeln!(out, " S {:08x},1", record.address)?,
+                AccessKind::Fetch => writeln!(out, "I  {:08x},1", record.address)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Exporta o trace no formato binário do ChampSim.
+    ///
+    /// Cada acesso vira uma instrução (a struct `input_instr` do ChampSim, de
+    /// 64 bytes, little-endian) sem registradores e sem branch. Como não temos
+    /// um program counter, usamos o índice do acesso como `ip`, o que mantém
+    /// cada instrução única. Leituras vão para `source_memory[0]` e escritas
+    /// para `destination_memory[0]`; uma busca de instrução não tem operando
+    /// de memória, e usa o endereço buscado como `ip`.
+    pub fn write_champsim<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for record in &self.records {
+            let mut destination_memory = [0u64; 2];
+            let mut source_memory = [0u64; 4];
+
+            let mut ip = record.time as u64;
+
+            match record.kind {
+                AccessKind::Read => source_memory[0] = record.address as u64,
+                AccessKind::Write => destination_memory[0] = record.address as u64,
+                AccessKind::Fetch => ip = record.address as u64,
+            }
+
+            out.write_all(&ip.to_le_bytes())?;
+            // is_branch, branch_taken
+            out.write_all(&[0, 0])?;
+            // destination_registers[2], source_registers[4]
+            out.write_all(&[0; 6])?;
+
+            for addr in destination_memory.iter().chain(source_memory.iter()) {
+                out.write_all(&addr.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+//! Diferença entre os traces de duas execuções da mesma sequência de
+//! acessos, como duas políticas de substituição ou dois tamanhos de memória
+//! (veja `simulate::diff_replacers`).
+//!
+//! As estatísticas dizem quantas page faults cada execução teve, mas não
+//! onde elas se separaram. A `TraceDiff` compara os registros acesso a
+//! acesso (veja `Mmu::enable_trace`) e mostra:
+//!
+//! - a primeira divergência: o primeiro acesso que as execuções trataram de
+//!   forma diferente (uma acertou e a outra errou, ou as duas substituíram
+//!   páginas diferentes);
+//! - a diferença acumulada de page faults ao longo do tempo, para ver se a
+//!   vantagem de uma execução é constante ou vem de uma fase da carga;
+//! - as páginas tratadas de forma mais diferente, com as page faults e as
+//!   substituições de cada execução.
+//!
+//! ```
+//! use vm::simulate::{diff_replacers, Geometry};
+//!
+//! let trace = vm::fixtures::trace("loop_12_pages").unwrap();
+//! let diff = diff_replacers(&trace, Geometry::new(8), ["fifo", "mru"]).unwrap();
+//!
+//! // No laço, o MRU guarda parte das páginas e o FIFO não guarda nenhuma.
+//! assert!(diff.fault_difference.last().unwrap() < &0);
+//! ```
+
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use thiserror::Error;
+
+use crate::trace::AccessRecord;
+
+/// Os traces não são da mesma sequência de acessos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum TraceDiffError {
+    /// Os traces têm tamanhos diferentes.
+    #[error("os traces têm {} e {} acessos", .0[0], .0[1])]
+    DifferentLengths([usize; 2]),
+    /// O acesso de índice `index` não é o mesmo nos dois traces.
+    #[error("o acesso {index} é diferente nos dois traces")]
+    DifferentAccess { index: usize },
+}
+
+/// O primeiro acesso que as execuções trataram de forma diferente.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// O índice do acesso nos traces.
+    pub index: usize,
+    pub page_number: usize,
+    /// Se o acesso foi um page hit, em cada execução.
+    pub hit: [bool; 2],
+    /// A página substituída, em cada execução.
+    pub evicted: [Option<usize>; 2],
+}
+
+/// Como as execuções trataram uma página.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageDiff {
+    pub page_number: usize,
+    /// As page faults na página, em cada execução.
+    pub faults: [usize; 2],
+    /// Quantas vezes a página foi substituída, em cada execução.
+    pub evictions: [usize; 2],
+}
+
+impl PageDiff {
+    /// Quantas page faults a segunda execução teve a mais na página
+    /// (negativo se a menos).
+    pub fn fault_difference(&self) -> isize {
+        self.faults[1] as isize - self.faults[0] as isize
+    }
+}
+
+/// A diferença entre os traces de duas execuções.
+#[derive(Debug, Clone)]
+pub struct TraceDiff {
+    /// Os nomes das execuções, para o relatório.
+    pub labels: [String; 2],
+    /// As page faults de cada execução.
+    pub faults: [usize; 2],
+    pub first_divergence: Option<Divergence>,
+    /// Quantas page faults a segunda execução tinha a mais (negativo se a
+    /// menos) depois de cada acesso.
+    pub fault_difference: Vec<isize>,
+    /// As páginas que as execuções trataram de forma diferente, da maior
+    /// diferença de page faults para a menor.
+    pub pages: Vec<PageDiff>,
+}
+
+impl TraceDiff {
+    /// Compara os registros de duas execuções, que devem ter feito os mesmos
+    /// acessos, na mesma ordem.
+    pub fn from_records(a: &[AccessRecord], b: &[AccessRecord]) -> Result<Self, TraceDiffError> {
+        if a.len() != b.len() {
+            return Err(TraceDiffError::DifferentLengths([a.len(), b.len()]));
+        }
+
+        let mut faults = [0; 2];
+        let mut first_divergence = None;
+        let mut fault_difference = Vec::with_capacity(a.len());
+        let mut pages: BTreeMap<usize, PageDiff> = BTreeMap::new();
+
+        for (index, records) in a.iter().zip(b).enumerate() {
+            let records = [records.0, records.1];
+            if (records[0].kind, records[0].address) != (records[1].kind, records[1].address) {
+                return Err(TraceDiffError::DifferentAccess { index });
+            }
+
+            let hit = records.map(|record| record.hit);
+            let evicted = records.map(|record| record.evicted);
+
+            if first_divergence.is_none() && (hit[0] != hit[1] || evicted[0] != evicted[1]) {
+                first_divergence = Some(Divergence {
+                    index,
+                    page_number: records[0].page_number,
+                    hit,
+                    evicted,
+                });
+            }
+
+            for (run, record) in records.iter().enumerate() {
+                if !record.hit {
+                    faults[run] += 1;
+                    Self::page(&mut pages, record.page_number).faults[run] += 1;
+                }
+                if let Some(victim) = record.evicted {
+                    Self::page(&mut pages, victim).evictions[run] += 1;
+                }
+            }
+
+            fault_difference.push(faults[1] as isize - faults[0] as isize);
+        }
+
+        let mut pages: Vec<PageDiff> = pages
+            .into_values()
+            .filter(|page| {
+                page.faults[0] != page.faults[1] || page.evictions[0] != page.evictions[1]
+            })
+            .collect();
+        pages.sort_by_key(|page| {
+            (
+                Reverse(page.fault_difference().unsigned_abs()),
+                Reverse(page.evictions[0].abs_diff(page.evictions[1])),
+                page.page_number,
+            )
+        });
+
+        Ok(TraceDiff {
+            labels: ["A".to_string(), "B".to_string()],
+            faults,
+            first_divergence,
+            fault_difference,
+            pages,
+        })
+    }
+
+    /// Dá nomes às execuções, para o relatório.
+    pub fn with_labels(mut self, a: &str, b: &str) -> Self {
+        self.labels = [a.to_string(), b.to_string()];
+        self
+    }
+
+    /// Quantos acessos os traces têm.
+    pub fn accesses(&self) -> usize {
+        self.fault_difference.len()
+    }
+
+    fn page(pages: &mut BTreeMap<usize, PageDiff>, page_number: usize) -> &mut PageDiff {
+        pages.entry(page_number).or_insert(PageDiff {
+            page_number,
+            faults: [0; 2],
+            evictions: [0; 2],
+        })
+    }
+
+    /// Imprime o relatório, com a diferença acumulada em até 10 pontos e as
+    /// 10 páginas de maior diferença.
+    pub fn print_report(&self) {
+        let [a, b] = &self.labels;
+
+        println!("===== Diferença entre execuções: {} x {} =====", a, b);
+        println!(
+            "{} acessos; page faults: {} {}, {} {} ({:+})",
+            self.accesses(),
+            a,
+            self.faults[0],
+            b,
+            self.faults[1],
+            self.fault_difference.last().copied().unwrap_or(0)
+        );
+
+        let Some(divergence) = self.first_divergence else {
+            println!("As execuções trataram todos os acessos da mesma forma");
+            return;
+        };
+
+        let treatment = |run: usize| match (divergence.hit[run], divergence.evicted[run]) {
+            (true, _) => "acertou".to_string(),
+            (false, None) => "carregou a página sem substituir".to_string(),
+            (false, Some(victim)) => format!("substituiu a página {:#04X}", victim),
+        };
+        println!(
+            "Primeira divergência no acesso {} (página {:#04X}): {} {}, {} {}",
+            divergence.index,
+            divergence.page_number,
+            a,
+            treatment(0),
+            b,
+            treatment(1)
+        );
+
+        println!("Diferença acumulada de page faults ({} - {}):", b, a);
+        println!("{:>10} {:>10}", "acesso", "diferença");
+        let mut samples: Vec<usize> = (1..=10)
+            .map(|i| (i * self.accesses()).div_ceil(10).saturating_sub(1))
+            .collect();
+        samples.dedup();
+        for index in samples {
+            println!("{:>10} {:>+10}", index, self.fault_difference[index]);
+        }
+
+        println!("Páginas tratadas de forma mais diferente:");
+        println!(
+            "{:>8} {:>12} {:>12} {:>12} {:>12}",
+            "página",
+            format!("faults {}", a),
+            format!("faults {}", b),
+            format!("saídas {}", a),
+            format!("saídas {}", b)
+        );
+        for page in self.pages.iter().take(10) {
+            println!(
+                "{:>8} {:>12} {:>12} {:>12} {:>12}",
+                format!("{:#04X}", page.page_number),
+                page.faults[0],
+                page.faults[1],
+                page.evictions[0],
+                page.evictions[1]
+            );
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Leitura e escrita de valores tipados na memória paginada.
+//!
+//! Habilitado pela feature `typed`. `Mmu::read_struct` e `Mmu::write_struct`
+//! movem um valor inteiro de uma vez, no estilo da crate `zerocopy`: o tipo
+//! diz como virar bytes (`AsBytes`) e como ser reconstruído a partir deles
+//! (`FromBytes`). Os bytes passam por `Mmu::read_bytes`/`write_bytes`, então
+//! um valor pode atravessar o limite entre duas páginas, e os erros são os
+//! deles.
+//!
+//! Os inteiros são guardados em little-endian. Para uma struct própria, basta
+//! implementar os dois traits campo a campo.
+
+use crate::{error::MmuError, mmu::Mmu, page_loader::PageLoader, page_replacer::PageReplacer};
+
+/// Um tipo que pode ser reconstruído a partir de `SIZE` bytes.
+pub trait FromBytes: Sized {
+    /// Quantos bytes o valor ocupa na memória.
+    const SIZE: usize;
+
+    /// Reconstrói o valor a partir de exatamente `SIZE` bytes.
+    fn from_bytes(bytes: &[u8]) -> Self;
+}
+
+/// Um tipo que pode ser escrito como `SIZE` bytes.
+pub trait AsBytes {
+    /// Quantos bytes o valor ocupa na memória.
+    const SIZE: usize;
+
+    /// Escreve o valor em exatamente `SIZE` bytes.
+    fn write_bytes(&self, bytes: &mut [u8]);
+}
+
+macro_rules! impl_int {
+    ($($ty:ty),*) => {
+        $(
+            impl FromBytes for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn from_bytes(bytes: &[u8]) -> Self {
+                    <$ty>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+
+            impl AsBytes for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn write_bytes(&self, bytes: &mut [u8]) {
+                    bytes.copy_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl<T: FromBytes, const N: usize> FromBytes for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        std::array::from_fn(|i| T::from_bytes(&bytes[i * T::SIZE..(i + 1) * T::SIZE]))
+    }
+}
+
+impl<T: AsBytes, const N: usize> AsBytes for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        for (value, chunk) in self.iter().zip(bytes.chunks_mut(T::SIZE)) {
+            value.write_bytes(chunk);
+        }
+    }
+}
+
+impl<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    > Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>
+{
+    /// Lê um valor de tipo `T` a partir do endereço virtual `address`.
+    pub fn read_struct<T: FromBytes>(&mut self, address: usize) -> Result<T, MmuError> {
+        let mut bytes = vec![0; T::SIZE];
+        self.read_bytes(address, &mut bytes)?;
+
+        Ok(T::from_bytes(&bytes))
+    }
+
+    /// Escreve o valor a partir do endereço virtual `address`.
+    pub fn write_struct<T: AsBytes>(&mut self, address: usize, value: &T) -> Result<(), MmuError> {
+        let mut bytes = vec![0; T::SIZE];
+        value.write_bytes(&mut bytes);
+
+        self.write_bytes(address, &bytes)
+    }
+}
@@ -0,0 +1,70 @@
+//! Acessos de mais de um byte: `Mmu::read_u16`, `read_u32`, `read_u64` e as
+//! escritas correspondentes, na ordem de bytes escolhida com
+//! `Mmu::set_endianness` (little-endian, como no x86, se não escolhida).
+//!
+//! Uma palavra pode atravessar o limite entre duas páginas, como numa CPU
+//! que não exige alinhamento: cada parte é traduzida separadamente, então o
+//! acesso conta como dois, e pode causar duas page faults. O endereço do
+//! último byte é conferido antes de qualquer tradução; as outras falhas da
+//! segunda página (de privilégio, do carregador...) só aparecem depois que
+//! a primeira parte já foi acessada.
+
+use crate::{error::MmuError, mmu::Mmu, page_loader::PageLoader, page_replacer::PageReplacer};
+
+/// A ordem dos bytes de um valor de mais de um byte na memória.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// O byte menos significativo primeiro, no menor endereço.
+    #[default]
+    Little,
+    /// O byte mais significativo primeiro.
+    Big,
+}
+
+macro_rules! impl_words {
+    ($($ty:ty => $read:ident, $write:ident;)*) => {
+        impl<
+                const MEM_SIZE: usize,
+                const FRAME_COUNT: usize,
+                const PAGE_COUNT: usize,
+                REPLACER: PageReplacer,
+                LOADER: PageLoader,
+            > Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>
+        {
+            $(
+                #[doc = concat!(
+                    "Lê um `", stringify!($ty), "` a partir de `address`, na ordem de ",
+                    "bytes da Mmu. Os erros são os de `read`."
+                )]
+                pub fn $read(&mut self, address: usize) -> Result<$ty, MmuError> {
+                    let mut bytes = [0; std::mem::size_of::<$ty>()];
+                    self.read_into(address, &mut bytes)?;
+
+                    Ok(match self.endianness() {
+                        Endianness::Little => <$ty>::from_le_bytes(bytes),
+                        Endianness::Big => <$ty>::from_be_bytes(bytes),
+                    })
+                }
+
+                #[doc = concat!(
+                    "Escreve um `", stringify!($ty), "` a partir de `address`, na ordem de ",
+                    "bytes da Mmu. Os erros são os de `write`."
+                )]
+                pub fn $write(&mut self, address: usize, value: $ty) -> Result<(), MmuError> {
+                    let bytes = match self.endianness() {
+                        Endianness::Little => value.to_le_bytes(),
+                        Endianness::Big => value.to_be_bytes(),
+                    };
+
+                    self.write_from(address, &bytes)
+                }
+            )*
+        }
+    };
+}
+
+impl_words! {
+    u16 => read_u16, write_u16;
+    u32 => read_u32, write_u32;
+    u64 => read_u64, write_u64;
+}
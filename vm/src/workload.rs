@@ -0,0 +1,161 @@
+//! Geradores de carga: sequências sintéticas de acessos.
+//!
+//! Um `WorkloadGenerator` produz acessos seguindo um `Pattern` (uniforme,
+//! laço, Zipf...) sobre as primeiras páginas do espaço de endereçamento. Toda
+//! a aleatoriedade vem de um `Rng` derivado da semente da execução (veja o
+//! módulo `rng`), então a mesma semente gera sempre a mesma carga.
+
+use crate::{
+    error::MmuError, mmu::Mmu, page_loader::PageLoader, page_replacer::PageReplacer, rng::Rng,
+    trace::AccessKind,
+};
+
+/// O padrão de acesso às páginas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// Todas as páginas com a mesma probabilidade.
+    Uniform,
+    /// Percorre as primeiras `pages` páginas em ordem, repetidamente.
+    Loop { pages: usize },
+    /// A página de posição `k` (começando em 1) tem probabilidade
+    /// proporcional a `1 / k^exponent`.
+    Zipf { exponent: f64 },
+    /// Uma fração `hot_fraction` dos acessos vai para as primeiras
+    /// `hot_pages` páginas; o resto é uniforme sobre as outras.
+    HotCold { hot_pages: usize, hot_fraction: f64 },
+}
+
+/// Um acesso gerado.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Access {
+    pub kind: AccessKind,
+    pub address: usize,
+    /// O valor escrito, se é uma escrita.
+    pub value: u8,
+}
+
+impl Access {
+    /// Executa o acesso na Mmu, devolvendo o erro do acesso (veja
+    /// `Mmu::read`).
+    pub fn apply<
+        const MEM_SIZE: usize,
+        const FRAME_COUNT: usize,
+        const PAGE_COUNT: usize,
+        REPLACER: PageReplacer,
+        LOADER: PageLoader,
+    >(
+        &self,
+        mmu: &mut Mmu<MEM_SIZE, FRAME_COUNT, PAGE_COUNT, REPLACER, LOADER>,
+    ) -> Result<(), MmuError> {
+        match self.kind {
+            AccessKind::Read => mmu.read(self.address).map(|_| ()),
+            AccessKind::Write => mmu.write(self.address, self.value),
+            AccessKind::Fetch => mmu.fetch(self.address).map(|_| ()),
+        }
+    }
+}
+
+/// Gera acessos sobre `page_count` páginas de `page_size` bytes.
+pub struct WorkloadGenerator {
+    rng: Rng,
+    pattern: Pattern,
+    page_count: usize,
+    page_size: usize,
+    write_ratio: f64,
+    /// A próxima página do padrão `Loop`.
+    position: usize,
+    /// A distribuição acumulada do padrão `Zipf`.
+    zipf_cdf: Vec<f64>,
+}
+
+impl WorkloadGenerator {
+    /// Constrói um gerador. Por padrão, 30% dos acessos são escritas.
+    pub fn new(seed: u64, pattern: Pattern, page_count: usize, page_size: usize) -> Self {
+        let zipf_cdf = match pattern {
+            Pattern::Zipf { exponent } => {
+                let weights: Vec<f64> = (1..=page_count)
+                    .map(|k| 1.0 / (k as f64).powf(exponent))
+                    .collect();
+                let total: f64 = weights.iter().sum();
+
+                weights
+                    .iter()
+                    .scan(0.0, |acc, weight| {
+                        *acc += weight / total;
+                        Some(*acc)
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        WorkloadGenerator {
+            rng: Rng::for_component(seed, "workload"),
+            pattern,
+            page_count,
+            page_size,
+            write_ratio: 0.3,
+            position: 0,
+            zipf_cdf,
+        }
+    }
+
+    /// Muda a fração dos acessos que são escritas.
+    pub fn with_write_ratio(mut self, write_ratio: f64) -> Self {
+        self.write_ratio = write_ratio;
+        self
+    }
+
+    /// A próxima página, segundo o padrão.
+    fn next_page(&mut self) -> usize {
+        match self.pattern {
+            Pattern::Uniform => self.rng.below(self.page_count),
+            Pattern::Loop { pages } => {
+                let page = self.position;
+                self.position = (self.position + 1) % pages.clamp(1, self.page_count);
+                page
+            }
+            Pattern::Zipf { .. } => {
+                let x = self.rng.next_f64();
+                self.zipf_cdf
+                    .partition_point(|&p| p <= x)
+                    .min(self.page_count - 1)
+            }
+            Pattern::HotCold {
+                hot_pages,
+                hot_fraction,
+            } => {
+                let hot_pages = hot_pages.clamp(1, self.page_count);
+
+                if hot_pages == self.page_count || self.rng.chance(hot_fraction) {
+                    self.rng.below(hot_pages)
+                } else {
+                    hot_pages + self.rng.below(self.page_count - hot_pages)
+                }
+            }
+        }
+    }
+
+    /// O próximo acesso.
+    pub fn next_access(&mut self) -> Access {
+        let page = self.next_page();
+        let offset = self.rng.below(self.page_size);
+
+        let kind = if self.rng.chance(self.write_ratio) {
+            AccessKind::Write
+        } else {
+            AccessKind::Read
+        };
+
+        Access {
+            kind,
+            address: page * self.page_size + offset,
+            value: self.rng.next_u64() as u8,
+        }
+    }
+
+    /// Os próximos `n` acessos.
+    pub fn generate(&mut self, n: usize) -> Vec<Access> {
+        (0..n).map(|_| self.next_access()).collect()
+    }
+}
@@ -0,0 +1,41 @@
+//! Os acessos que `Mmu::read` e `Mmu::write` recusam (veja `MmuError`).
+
+use vm::{
+    error::MmuError, mmu::Mmu, page_loader::MemoryPageLoader, page_replacer::FIFOPageReplacer,
+    trace::AccessKind,
+};
+
+#[test]
+fn an_address_beyond_16_bits_is_refused() {
+    let mut mmu =
+        Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+
+    assert_eq!(
+        mmu.write(0x1_0010, 1),
+        Err(MmuError::InvalidAddress {
+            kind: AccessKind::Write,
+            address: 0x1_0010,
+            page_count: 256,
+        })
+    );
+
+    // O endereço não é truncado para 0x0010: nada foi escrito nem contado.
+    assert_eq!(mmu.stats.accesses(), 0);
+    assert_eq!(mmu.read(0x0010), Ok(0));
+}
+
+#[test]
+fn an_address_past_the_last_page_is_refused() {
+    let mut mmu =
+        Mmu::<{ 4 * 256 }, 4, 16, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+
+    assert_eq!(mmu.read(0x0F00), Ok(0));
+
+    let error = mmu.read(0x1000).unwrap_err();
+    assert!(error.access_error().is_none());
+    assert_eq!(
+        error.to_string(),
+        "leitura em 0x1000: o endereço está fora das 16 páginas do espaço de endereçamento"
+    );
+    assert_eq!(mmu.stats.accesses(), 1);
+}
@@ -0,0 +1,118 @@
+//! O replacer adaptativo (veja `AdaptivePageReplacer`).
+
+use vm::{
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::{
+        AdaptivePageReplacer, LFUPageReplacer, LRUPageReplacer, MRUPageReplacer, PageReplacer,
+        ReplacerRegistry,
+    },
+    trace::AccessKind,
+    workload::{Access, Pattern, WorkloadGenerator},
+};
+
+const FRAMES: usize = 8;
+
+type TestMmu<R> = Mmu<{ FRAMES * 256 }, FRAMES, 256, R, MemoryPageLoader>;
+
+fn run<R: PageReplacer>(replacer: R, accesses: &[Access]) -> TestMmu<R> {
+    let mut mmu = Mmu::new(replacer, MemoryPageLoader::new());
+
+    for access in accesses {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    mmu
+}
+
+/// Um laço sobre uma página a mais do que cabe na memória: o LRU erra
+/// todos os acessos, o MRU quase nenhum.
+fn loop_larger_than_memory(len: usize) -> Vec<Access> {
+    (0..len)
+        .map(|i| Access {
+            kind: AccessKind::Read,
+            address: (i % (FRAMES + 1)) << 8,
+            value: 0,
+        })
+        .collect()
+}
+
+fn hot_cold(seed: u64) -> Vec<Access> {
+    let pattern = Pattern::HotCold {
+        hot_pages: 6,
+        hot_fraction: 0.9,
+    };
+
+    WorkloadGenerator::new(seed, pattern, 32, 256).generate(3000)
+}
+
+fn lru_mru() -> AdaptivePageReplacer<LRUPageReplacer, MRUPageReplacer> {
+    AdaptivePageReplacer::new(LRUPageReplacer::new(), MRUPageReplacer::new())
+}
+
+#[test]
+fn the_adaptive_replacer_follows_the_policy_that_fits_the_load() {
+    // No laço, o LRU começa ativo, perde e passa a vez ao MRU.
+    let accesses = loop_larger_than_memory(900);
+    let lru = run(LRUPageReplacer::new(), &accesses).stats.misses();
+    let mru = run(MRUPageReplacer::new(), &accesses).stats.misses();
+    let adaptive = run(lru_mru(), &accesses);
+
+    assert_eq!(lru, accesses.len());
+    assert_eq!(adaptive.replacer().active(), 1);
+    assert!(
+        adaptive.stats.misses() < mru + lru / 4,
+        "adaptativo: {} misses, MRU: {}",
+        adaptive.stats.misses(),
+        mru
+    );
+
+    // Com localidade, o LRU fica ativo e o adaptativo fica perto dele.
+    let accesses = hot_cold(0);
+    let lru = run(LRUPageReplacer::new(), &accesses).stats.misses();
+    let mru = run(MRUPageReplacer::new(), &accesses).stats.misses();
+    let adaptive = run(lru_mru(), &accesses);
+
+    assert_eq!(adaptive.replacer().active(), 0);
+    assert!(
+        adaptive.stats.misses() < (lru + mru) / 2,
+        "adaptativo: {} misses, LRU: {}, MRU: {}",
+        adaptive.stats.misses(),
+        lru,
+        mru
+    );
+}
+
+#[test]
+fn the_adaptive_replacer_switches_when_the_load_changes_phase() {
+    let mut accesses = hot_cold(1);
+    accesses.extend(loop_larger_than_memory(900));
+    accesses.extend(hot_cold(2));
+
+    let mut mmu: TestMmu<_> = Mmu::new(lru_mru(), MemoryPageLoader::new());
+    let mut active = Vec::new();
+    for access in &accesses {
+        access.apply(&mut mmu).unwrap();
+        if active.last() != Some(&mmu.replacer().active()) {
+            active.push(mmu.replacer().active());
+        }
+    }
+
+    // O LRU na primeira fase, o MRU no laço e o LRU de novo no fim.
+    assert_eq!(active.first(), Some(&0));
+    assert_eq!(active.last(), Some(&0));
+    assert!(active.contains(&1));
+    assert!(mmu.replacer().switches() >= 2);
+}
+
+#[test]
+fn the_adaptive_replacer_is_registered_and_configurable() {
+    let replacer = ReplacerRegistry::new().create("adaptive", 0).unwrap();
+    assert_eq!(replacer.parameters(), &["decay"]);
+
+    assert!(
+        AdaptivePageReplacer::new(LRUPageReplacer::new(), LFUPageReplacer::new())
+            .with("decay", 1.0)
+            .is_err()
+    );
+}
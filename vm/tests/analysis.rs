@@ -0,0 +1,58 @@
+//! A sugestão de frames a partir das distâncias de reuso (veja
+//! `vm::analysis`).
+
+use vm::{
+    analysis::LocalityReport,
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::{LRUPageReplacer, PageReplacer},
+    trace::AccessKind,
+    workload::{Access, Pattern, WorkloadGenerator},
+};
+
+const FRAMES: usize = 8;
+
+/// Executa os acessos numa Mmu de `FRAMES` frames com o trace habilitado.
+fn run<R: PageReplacer>(
+    replacer: R,
+    accesses: &[Access],
+) -> Mmu<{ FRAMES * 256 }, FRAMES, 256, R, MemoryPageLoader> {
+    let mut mmu = Mmu::new(replacer, MemoryPageLoader::new());
+    mmu.enable_trace();
+
+    for access in accesses {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    mmu
+}
+
+#[test]
+fn the_predicted_lru_hit_rate_matches_the_mmu() {
+    let accesses =
+        WorkloadGenerator::new(0, Pattern::Zipf { exponent: 0.8 }, 32, 256).generate(3000);
+    let mmu = run(LRUPageReplacer::new(), &accesses);
+    let report = LocalityReport::from_records(mmu.trace().unwrap().records());
+
+    let hits = mmu.stats.hits() as f64 / mmu.stats.accesses() as f64;
+    assert!((report.lru_hit_rate(FRAMES) - hits).abs() < 1e-9);
+}
+
+#[test]
+fn a_loop_needs_all_of_its_pages_in_memory() {
+    let accesses: Vec<Access> = (0..1000)
+        .map(|i| Access {
+            kind: AccessKind::Read,
+            address: (i % 6) << 8,
+            value: 0,
+        })
+        .collect();
+    let mmu = run(LRUPageReplacer::new(), &accesses);
+    let report = LocalityReport::from_records(mmu.trace().unwrap().records());
+
+    // Com 5 frames, o LRU erra todos os acessos do laço; com 6, só os 6
+    // primeiros.
+    assert_eq!(report.lru_hit_rate(5), 0.0);
+    assert_eq!(report.frames_for_hit_rate(0.95), Some(6));
+    assert_eq!(report.frames_for_hit_rate(0.995), None);
+}
@@ -0,0 +1,45 @@
+//! A busca pela anomalia de Belady (veja `vm::simulate::belady_anomaly`).
+
+use vm::simulate::{belady_anomaly, BeladyAnomaly, Geometry, SimulateError};
+
+#[test]
+fn the_classic_reference_string_shows_the_anomaly() {
+    let reference = [1, 2, 3, 4, 1, 2, 5, 1, 2, 3, 4, 5];
+    let report = belady_anomaly(&reference, 6).unwrap();
+
+    assert_eq!(
+        report.faults,
+        vec![(1, 12), (2, 12), (3, 9), (4, 10), (5, 5), (6, 5)]
+    );
+    assert_eq!(
+        report.anomalies,
+        vec![BeladyAnomaly {
+            frames: 3,
+            faults: 9,
+            faults_with_one_more: 10,
+        }]
+    );
+    assert_eq!(report.reference, reference.to_vec());
+}
+
+#[test]
+fn a_loop_has_no_anomaly() {
+    let reference: Vec<usize> = (0..10).flat_map(|_| 0..6).collect();
+    let report = belady_anomaly(&reference, 8).unwrap();
+
+    assert!(!report.occurred());
+    assert_eq!(report.faults.len(), 8);
+    assert_eq!(report.faults[7], (8, 6));
+}
+
+#[test]
+fn bad_pages_and_frame_counts_are_rejected() {
+    assert_eq!(
+        belady_anomaly(&[1, 2, 256], 4).unwrap_err(),
+        SimulateError::PageOutOfRange(256)
+    );
+    assert_eq!(
+        belady_anomaly(&[1, 2, 3], 17).unwrap_err(),
+        SimulateError::UnsupportedGeometry(Geometry::new(17))
+    );
+}
@@ -0,0 +1,109 @@
+//! As leituras e escritas de buffers inteiros (`Mmu::read_into` e
+//! `Mmu::write_from`), e as cópias e preenchimentos (`Mmu::copy` e
+//! `Mmu::fill`).
+
+mod common;
+
+use vm::{
+    error::MmuError,
+    mmu::{IoSegment, Mmu},
+    page_loader::MemoryPageLoader,
+    page_replacer::FIFOPageReplacer,
+    trace::AccessKind,
+};
+
+type SmallMmu = common::SmallMmu<{ 4 * 256 }, 4>;
+
+#[test]
+fn a_buffer_counts_one_access_per_page() {
+    let mut mmu: SmallMmu = common::mmu();
+    let image: Vec<u8> = (0..600).map(|i| i as u8).collect();
+
+    // 600 bytes a partir do meio da página 1 tocam as páginas 1 a 3.
+    mmu.write_from(0x0180, &image).unwrap();
+    assert_eq!(mmu.stats.accesses(), 3);
+    assert_eq!(mmu.stats.misses(), 3);
+
+    let mut buffer = vec![0; image.len()];
+    mmu.read_into(0x0180, &mut buffer).unwrap();
+    assert_eq!(buffer, image);
+    assert_eq!(mmu.stats.accesses(), 6);
+    assert_eq!(mmu.stats.misses(), 3);
+
+    mmu.read_into(0x0180, &mut []).unwrap();
+    assert_eq!(mmu.stats.accesses(), 6);
+}
+
+#[test]
+fn a_buffer_past_the_address_space_is_refused_before_any_access() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    assert_eq!(
+        mmu.write_from(0xFF00, &[0xAA; 0x101]),
+        Err(MmuError::InvalidAddress {
+            kind: AccessKind::Write,
+            address: 0x1_0000,
+            page_count: 256,
+        })
+    );
+    assert_eq!(mmu.stats.accesses(), 0);
+
+    let mut buffer = [0; 4];
+    assert!(mmu.read_into(0xFFFE, &mut buffer).is_err());
+    assert_eq!(mmu.stats.accesses(), 0);
+}
+
+#[test]
+fn copies_and_fills_past_the_pages_are_refused_before_any_access() {
+    // Só 4 páginas: o endereço 0x0500 não existe.
+    let mut mmu =
+        Mmu::<{ 4 * 256 }, 4, 4, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    let invalid = |kind, address| MmuError::InvalidAddress {
+        kind,
+        address,
+        page_count: 4,
+    };
+
+    assert_eq!(
+        mmu.fill(0x0500, 1, 1),
+        Err(invalid(AccessKind::Write, 0x0500))
+    );
+    assert_eq!(
+        mmu.fill(0x03FF, 1, 2),
+        Err(invalid(AccessKind::Write, 0x0400))
+    );
+    assert_eq!(
+        mmu.copy(0x0500, 0x0000, 4),
+        Err(invalid(AccessKind::Write, 0x0503))
+    );
+    assert_eq!(
+        mmu.copy(0x0000, 0x03FE, 4),
+        Err(invalid(AccessKind::Read, 0x0401))
+    );
+    assert_eq!(
+        mmu.translate(0x0500, AccessKind::Read),
+        Err(invalid(AccessKind::Read, 0x0500))
+    );
+    assert_eq!(mmu.stats.accesses(), 0);
+
+    // O primeiro trecho é lido antes da falha do segundo.
+    let segments = [
+        IoSegment {
+            address: 0x0000,
+            len: 2,
+        },
+        IoSegment {
+            address: 0x03FF,
+            len: 2,
+        },
+    ];
+    assert_eq!(
+        mmu.gather(&segments),
+        Err(invalid(AccessKind::Read, 0x0400))
+    );
+    assert_eq!(
+        mmu.scatter(&segments, &[1, 2, 3, 4]),
+        Err(invalid(AccessKind::Write, 0x0400))
+    );
+    assert_eq!(mmu.read(0x0001), Ok(2));
+}
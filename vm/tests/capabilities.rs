@@ -0,0 +1,155 @@
+//! As capacidades dos carregadores, e o que a Mmu liga ou desliga com elas
+//! (veja `PageLoader::capabilities`).
+
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use vm::{
+    error::{LoaderError, LoaderMismatch},
+    page_loader::{LoaderCapabilities, MemoryPageLoader, PageLoader},
+    page_replacer::{FIFOPageReplacer, WSClockPageReplacer},
+};
+
+/// Um carregador em memória com as capacidades dadas, que anota o tamanho
+/// de cada escrita que recebe: 1 para `try_flush_page`, o tamanho do lote
+/// para `try_flush_pages`.
+struct RecordingLoader {
+    inner: MemoryPageLoader,
+    capabilities: LoaderCapabilities,
+    flushes: Rc<RefCell<Vec<usize>>>,
+}
+
+impl RecordingLoader {
+    fn new(capabilities: LoaderCapabilities) -> (Self, Rc<RefCell<Vec<usize>>>) {
+        let flushes = Rc::new(RefCell::new(Vec::new()));
+        let loader = RecordingLoader {
+            inner: MemoryPageLoader::new(),
+            capabilities,
+            flushes: Rc::clone(&flushes),
+        };
+
+        (loader, flushes)
+    }
+}
+
+impl PageLoader for RecordingLoader {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        self.inner.load_page_into(page_number, target);
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        self.flushes.borrow_mut().push(1);
+        self.inner.flush_page(page_number, buffer);
+    }
+
+    fn capabilities(&self) -> LoaderCapabilities {
+        self.capabilities
+    }
+
+    fn try_flush_pages(&mut self, pages: &[(usize, &[u8])]) -> Result<(), LoaderError> {
+        self.flushes.borrow_mut().push(pages.len());
+        for &(page_number, buffer) in pages {
+            self.inner.flush_page(page_number, buffer);
+        }
+
+        Ok(())
+    }
+}
+
+type SmallMmu<R, L> = common::SmallMmu<{ 8 * 256 }, 8, R, L>;
+
+/// Suja 3 páginas de um processo e o tira da memória.
+fn swap_out(
+    capabilities: LoaderCapabilities,
+) -> (SmallMmu<FIFOPageReplacer, RecordingLoader>, Vec<usize>) {
+    let (loader, flushes) = RecordingLoader::new(capabilities);
+    let mut mmu = SmallMmu::new(FIFOPageReplacer::new(), loader);
+
+    mmu.map_region(1, "dados", 0..4).unwrap();
+    for page in 0..3 {
+        mmu.write(page << 8, 0x10 + page as u8).unwrap();
+    }
+    mmu.read(3 << 8).unwrap();
+
+    assert_eq!(mmu.swap_out_process(1), Ok(4));
+    let flushes = flushes.borrow().clone();
+
+    (mmu, flushes)
+}
+
+#[test]
+fn a_batch_loader_gets_the_writebacks_of_a_swap_out_together() {
+    let batch = LoaderCapabilities {
+        batch_flush: true,
+        ..LoaderCapabilities::default()
+    };
+
+    let (mut mmu, flushes) = swap_out(batch);
+    assert_eq!(flushes, vec![3]);
+    assert_eq!(mmu.stats.writebacks(), 3);
+    assert_eq!(mmu.read(2 << 8).unwrap(), 0x12);
+
+    let (_, flushes) = swap_out(LoaderCapabilities::default());
+    assert_eq!(flushes, vec![1, 1, 1]);
+}
+
+/// Como no teste do WSClock em `replacement.rs`: 4 páginas sujas saem do
+/// working set, e a page fault seguinte agenda a limpeza delas.
+fn wsclock_cleaned_pages(asynchronous: bool) -> usize {
+    let (loader, _) = RecordingLoader::new(LoaderCapabilities {
+        asynchronous,
+        ..LoaderCapabilities::default()
+    });
+    let mut mmu = SmallMmu::new(WSClockPageReplacer::new(8), loader);
+    mmu.set_tick_interval(Some(4));
+
+    for page in 0..4 {
+        mmu.write(page << 8, 1).unwrap();
+    }
+    for i in 0..20 {
+        mmu.read((4 + i % 4) << 8).unwrap();
+    }
+    mmu.read(8 << 8).unwrap();
+
+    // Limpa ou não, a página que saiu tem o conteúdo escrito.
+    assert_eq!(mmu.read(0).unwrap(), 1);
+
+    mmu.stats.cleaned_pages()
+}
+
+#[test]
+fn background_cleaning_needs_an_asynchronous_loader() {
+    assert_eq!(wsclock_cleaned_pages(true), 4);
+    assert_eq!(wsclock_cleaned_pages(false), 0);
+
+    assert!(MemoryPageLoader::new().capabilities().asynchronous);
+}
+
+#[test]
+fn a_loader_with_too_few_pages_is_rejected() {
+    let (loader, _) = RecordingLoader::new(LoaderCapabilities {
+        max_pages: Some(128),
+        ..LoaderCapabilities::default()
+    });
+
+    // A caixa repassa as capacidades do carregador de dentro.
+    let boxed: Box<dyn PageLoader> = Box::new(loader);
+    assert_eq!(boxed.capabilities().max_pages, Some(128));
+
+    let err = SmallMmu::try_new(FIFOPageReplacer::new(), boxed)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        LoaderMismatch::PageCount {
+            backend: "carregador".to_string(),
+            max_pages: 128,
+            page_count: 256,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "o carregador 'carregador' guarda só 128 páginas, mas a Mmu tem 256"
+    );
+}
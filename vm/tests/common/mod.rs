@@ -0,0 +1,23 @@
+//! O que os testes de integração têm em comum.
+//!
+//! Cada arquivo em `tests/` é uma crate à parte, que inclui este módulo com
+//! `mod common;` e usa só parte dele.
+
+#![allow(dead_code)]
+
+use vm::{mmu::Mmu, page_loader::MemoryPageLoader, page_replacer::FIFOPageReplacer};
+
+/// Uma Mmu pequena, de `FRAME_COUNT` frames e 256 páginas de 256 bytes, que
+/// por padrão substitui por FIFO e guarda as páginas em memória. `MEM_SIZE`
+/// precisa ser `FRAME_COUNT * 256`.
+pub type SmallMmu<
+    const MEM_SIZE: usize,
+    const FRAME_COUNT: usize,
+    R = FIFOPageReplacer,
+    L = MemoryPageLoader,
+> = Mmu<MEM_SIZE, FRAME_COUNT, 256, R, L>;
+
+/// Uma `SmallMmu` nova, com FIFO e as páginas em memória.
+pub fn mmu<const MEM_SIZE: usize, const FRAME_COUNT: usize>() -> SmallMmu<MEM_SIZE, FRAME_COUNT> {
+    Mmu::new(FIFOPageReplacer::new(), MemoryPageLoader::new())
+}
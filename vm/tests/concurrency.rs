@@ -0,0 +1,73 @@
+//! As estatísticas são a única parte da Mmu compartilhada entre threads
+//! (veja `Mmu::stats_handle`): uma thread lê os contadores enquanto a Mmu
+//! roda em outra.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    sync::Arc,
+    thread,
+};
+
+use vm::{
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::FIFOPageReplacer,
+    workload::{Pattern, WorkloadGenerator},
+};
+
+#[test]
+fn live_stats_only_grow_and_end_equal() {
+    let mut mmu =
+        Mmu::<{ 8 * 256 }, 8, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    let stats = mmu.stats_handle();
+    let done = Arc::new(AtomicBool::new(false));
+
+    let reader = {
+        let done = done.clone();
+
+        thread::spawn(move || {
+            let mut last = [0; 4];
+            let mut samples = 0;
+
+            loop {
+                let finished = done.load(Ordering::Acquire);
+                let now = [
+                    stats.hits(),
+                    stats.misses(),
+                    stats.writebacks(),
+                    stats.dirty_lifetime_accesses().count(),
+                ];
+
+                for (now, last) in now.iter().zip(&last) {
+                    assert!(now >= last, "contador diminuiu: {:?} -> {:?}", last, now);
+                }
+
+                last = now;
+                samples += 1;
+
+                if finished {
+                    return (last, samples);
+                }
+            }
+        })
+    };
+
+    let mut generator = WorkloadGenerator::new(3, Pattern::Zipf { exponent: 0.8 }, 64, 256);
+    for access in generator.generate(20_000) {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    done.store(true, Ordering::Release);
+    let (last, samples) = reader.join().unwrap();
+
+    assert!(samples > 0);
+    assert_eq!(
+        last,
+        [
+            mmu.stats.hits(),
+            mmu.stats.misses(),
+            mmu.stats.writebacks(),
+            mmu.stats.dirty_lifetime_accesses().count(),
+        ]
+    );
+}
@@ -0,0 +1,89 @@
+//! O swap criptografado (veja o módulo `encrypted_loader`).
+
+mod common;
+
+use vm::{
+    encrypted_loader::EncryptedPageLoader,
+    mmu::{Mmu, PAGE_SIZE},
+    page_loader::{MemoryPageLoader, PageLoader},
+    page_replacer::FIFOPageReplacer,
+};
+
+type SmallMmu =
+    common::SmallMmu<{ 2 * 256 }, 2, FIFOPageReplacer, EncryptedPageLoader<MemoryPageLoader>>;
+
+const KEY: u64 = 0x5EC2_E7A0;
+
+fn mmu() -> SmallMmu {
+    Mmu::new(
+        FIFOPageReplacer::new(),
+        EncryptedPageLoader::new(MemoryPageLoader::new(), KEY),
+    )
+}
+
+fn plaintext() -> Vec<u8> {
+    (0..PAGE_SIZE).map(|i| (i * 7) as u8).collect()
+}
+
+/// Escreve o texto na página 0 e a tira da memória com as páginas 1 e 2.
+fn write_and_evict(mmu: &mut SmallMmu) {
+    for (offset, byte) in plaintext().into_iter().enumerate() {
+        mmu.write(offset, byte).unwrap();
+    }
+
+    mmu.read(0x100).unwrap();
+    mmu.read(0x200).unwrap();
+    assert!(!mmu.frame_map().contains(&Some(0)));
+}
+
+#[test]
+fn an_evicted_page_is_stored_encrypted_and_reloaded_in_plaintext() {
+    let mut mmu = mmu();
+    write_and_evict(&mut mmu);
+
+    let stored = mmu.loader().inner().page(0).unwrap();
+    assert_eq!(stored.len(), PAGE_SIZE);
+    assert_ne!(stored, plaintext().as_slice());
+
+    assert_eq!(mmu.peek_page(0).unwrap(), plaintext());
+    for (offset, byte) in plaintext().into_iter().enumerate() {
+        assert_eq!(mmu.read(offset), Ok(byte));
+    }
+}
+
+#[test]
+fn a_wrong_key_does_not_decrypt_the_page() {
+    let mut mmu = mmu();
+    write_and_evict(&mut mmu);
+    let ciphertext = mmu.loader().inner().page(0).unwrap().to_vec();
+
+    // Um carregador com outra chave, com o texto cifrado da página no seu
+    // carregador de baixo.
+    let mut loader = EncryptedPageLoader::new(MemoryPageLoader::new(), KEY + 1);
+    loader.flush_page(0, &[0; PAGE_SIZE]);
+    loader.inner_mut().flush_page(0, &ciphertext);
+
+    let mut page = vec![0; PAGE_SIZE];
+    loader.load_page_into(0, &mut page);
+    assert_ne!(page, plaintext());
+    assert_ne!(page, ciphertext);
+}
+
+#[test]
+fn a_key_rotation_keeps_the_pages_readable() {
+    let mut mmu = mmu();
+    write_and_evict(&mut mmu);
+    let before = mmu.loader().inner().page(0).unwrap().to_vec();
+
+    mmu.loader_mut().start_key_rotation(KEY + 1);
+    assert_eq!(mmu.loader().pending_pages(), 1);
+
+    // Antes da página ser recifrada, ela é lida com a chave antiga...
+    assert_eq!(mmu.peek_page(0).unwrap(), plaintext());
+
+    // ...e depois, com a nova.
+    assert_eq!(mmu.loader_mut().rotation_step(1), 1);
+    assert!(!mmu.loader().is_rotating());
+    assert_ne!(mmu.loader().inner().page(0).unwrap(), before.as_slice());
+    assert_eq!(mmu.peek_page(0).unwrap(), plaintext());
+}
@@ -0,0 +1,78 @@
+//! A ordem dos eventos de página que a Mmu entrega ao replacer.
+
+use std::sync::{Arc, Mutex};
+
+use vm::{
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::{NRUPageReplacer, PageEvent, PageReplacer},
+};
+
+/// Um FIFO que registra todos os eventos que recebe.
+#[derive(Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<PageEvent>>>,
+    order: Vec<usize>,
+}
+
+impl PageReplacer for Recorder {
+    fn page_event(&mut self, event: PageEvent) {
+        self.events.lock().unwrap().push(event);
+        match event {
+            PageEvent::Loaded(page) => self.order.push(page),
+            PageEvent::Unmapped(page) => self.order.retain(|&p| p != page),
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        (!self.order.is_empty()).then(|| self.order.remove(0))
+    }
+}
+
+#[test]
+fn accesses_and_evictions_emit_events_in_order() {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+    let mut mmu = Mmu::<512, 2, 256, _, _>::new(recorder, MemoryPageLoader::new());
+
+    mmu.write(0x000, 1).unwrap();
+    mmu.write(0x001, 2).unwrap();
+    mmu.read(0x100).unwrap();
+    mmu.read(0x200).unwrap();
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            PageEvent::Loaded(0),
+            PageEvent::Touched(0),
+            PageEvent::Written(0),
+            PageEvent::Dirtied(0),
+            PageEvent::Touched(0),
+            PageEvent::Written(0),
+            PageEvent::Loaded(1),
+            PageEvent::Touched(1),
+            PageEvent::Read(1),
+            PageEvent::FlushedDirty(0),
+            PageEvent::Evicted(0),
+            PageEvent::Loaded(2),
+            PageEvent::Touched(2),
+            PageEvent::Read(2),
+        ]
+    );
+}
+
+#[test]
+fn nru_treats_a_flushed_page_as_clean() {
+    let mut nru = NRUPageReplacer::new();
+
+    nru.page_event(PageEvent::Loaded(0));
+    nru.page_event(PageEvent::Dirtied(0));
+    nru.page_event(PageEvent::Loaded(1));
+    nru.page_event(PageEvent::Dirtied(1));
+    nru.page_event(PageEvent::FlushedDirty(1));
+    nru.tick();
+
+    // As duas sem referência; só a 0 continua suja.
+    assert_eq!(nru.pick_replacement_page(), Some(1));
+}
@@ -0,0 +1,49 @@
+//! As rajadas de page faults seguidas (veja `vm::fault_burst`).
+
+mod common;
+
+use vm::fault_burst::STORM_FAULTS;
+
+const FRAMES: usize = 8;
+
+type SmallMmu = common::SmallMmu<{ FRAMES * 256 }, FRAMES>;
+
+#[test]
+fn a_phase_change_is_one_storm_at_the_change() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    // Fase 1: 4 páginas, que cabem na memória.
+    for i in 0..400 {
+        mmu.read((i % 4) << 8).unwrap();
+    }
+    // Fase 2: 20 páginas novas de uma vez, depois um laço nas últimas 4.
+    for page in 0x10..0x24 {
+        mmu.read(page << 8).unwrap();
+    }
+    for i in 0..400 {
+        mmu.read((0x20 + i % 4) << 8).unwrap();
+    }
+
+    let bursts = mmu.stats.fault_bursts();
+    assert_eq!(bursts.largest(), Some(400..420));
+    assert_eq!(bursts.storms(), 1);
+    assert_eq!(bursts.lengths().count(), 2);
+    assert_eq!(bursts.current(), 0);
+    assert!(!bursts.is_thrashing(mmu.stats.accesses()));
+}
+
+#[test]
+fn a_loop_larger_than_memory_is_thrashing() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    for i in 0..600 {
+        mmu.read((i % (FRAMES + 4)) << 8).unwrap();
+    }
+
+    let bursts = mmu.stats.fault_bursts();
+    assert_eq!(bursts.largest(), Some(0..600));
+    assert_eq!(bursts.current(), 600);
+    assert_eq!(bursts.storm_faults(), 600);
+    assert!(bursts.current() >= STORM_FAULTS);
+    assert!(bursts.is_thrashing(mmu.stats.accesses()));
+}
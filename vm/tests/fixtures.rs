@@ -0,0 +1,85 @@
+//! Os fixtures embutidos: todos precisam ser lidos sem erro, e os números
+//! abaixo só mudam com uma versão nova dos dados (veja o módulo `fixtures`).
+
+use vm::{
+    fixtures::{self, FixtureError},
+    mmu::Mmu,
+    page_loader::{LoaderRegistry, PageLoader},
+    page_replacer::FIFOPageReplacer,
+    trace::AccessKind,
+};
+
+#[test]
+fn every_trace_parses() {
+    for name in fixtures::trace_names() {
+        let accesses = fixtures::trace(name).unwrap();
+        assert!(!accesses.is_empty(), "{} está vazio", name);
+    }
+}
+
+#[test]
+fn the_traces_have_the_documented_shape() {
+    let looping = fixtures::trace("loop_12_pages").unwrap();
+    assert_eq!(looping.len(), 600);
+    assert!(looping.iter().all(|access| access.kind == AccessKind::Read));
+    assert!(looping.iter().all(|access| access.address >> 8 < 12));
+
+    let transpose = fixtures::trace("matrix_transpose_16").unwrap();
+    let writes = transpose
+        .iter()
+        .filter(|access| access.kind == AccessKind::Write)
+        .count();
+    assert_eq!((transpose.len(), writes), (512, 256));
+}
+
+#[test]
+fn a_loop_larger_than_memory_misses_every_access() {
+    let mut mmu = Mmu::<{ 8 * 256 }, 8, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        fixtures::swap_loader("page_numbers_16").unwrap(),
+    );
+
+    for access in fixtures::trace("loop_12_pages").unwrap() {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    assert_eq!(mmu.stats.misses(), 600);
+}
+
+#[test]
+fn swap_images_load_their_pages() {
+    let mut mmu = Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        fixtures::swap_loader("page_numbers_16").unwrap(),
+    );
+
+    for page in 0..16 {
+        assert_eq!(mmu.read((page << 8) | 0x42).unwrap(), page as u8);
+    }
+    // Depois do fim da imagem, as páginas começam zeradas.
+    assert_eq!(mmu.read(0x1000).unwrap(), 0);
+
+    let mut loader = LoaderRegistry::new()
+        .create("fixture:text_4_pages")
+        .unwrap();
+    let mut page = [0; 256];
+    loader.load_page_into(2, &mut page);
+    assert!(page.starts_with(b"pagina 02 "));
+}
+
+#[test]
+fn errors_name_the_fixture_or_the_line() {
+    assert_eq!(
+        fixtures::trace("nao_existe").unwrap_err(),
+        FixtureError::Unknown("nao_existe".to_string())
+    );
+
+    assert!(matches!(
+        fixtures::parse_trace("r 0x0000\n"),
+        Err(FixtureError::Parse { line: 1, .. })
+    ));
+    assert!(matches!(
+        fixtures::parse_trace("# vm-fixture trace v1\n# ok\nr 0x0100\nw 0x0100 0x100\n"),
+        Err(FixtureError::Parse { line: 4, .. })
+    ));
+}
@@ -0,0 +1,102 @@
+//! O `malloc`/`free` dentro do heap de um processo (veja o módulo `heap`).
+
+mod common;
+
+use vm::{
+    heap::{Block, Heap, HeapError, Strategy},
+    syscall::{Kernel, SyscallError},
+};
+
+type SmallMmu = common::SmallMmu<{ 32 * 256 }, 32>;
+
+#[test]
+fn first_fit_reuses_and_coalesces_freed_blocks() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut kernel = Kernel::new();
+    let pid = kernel.spawn(&mmu, 1).unwrap();
+    let mut heap = Heap::new(pid, Strategy::FirstFit);
+
+    let a = heap.malloc(&mut kernel, &mut mmu, 10).unwrap();
+    let b = heap.malloc(&mut kernel, &mut mmu, 20).unwrap();
+    let c = heap.malloc(&mut kernel, &mut mmu, 4).unwrap();
+    assert_eq!((a, b, c), (0x102, 0x10E, 0x124));
+    assert_eq!(kernel.process(pid).unwrap().brk, 0x128);
+    mmu.write_bytes(c, b"fim!").unwrap();
+
+    // O primeiro bloco livre que cabe é dividido.
+    heap.free(&mut mmu, a).unwrap();
+    assert_eq!(heap.malloc(&mut kernel, &mut mmu, 6), Ok(a));
+    assert_eq!(
+        heap.blocks(&mut mmu)[..2],
+        [
+            Block {
+                address: 0x100,
+                size: 8,
+                used: true
+            },
+            Block {
+                address: 0x108,
+                size: 4,
+                used: false
+            },
+        ]
+    );
+
+    // O resto livre e `b` são juntados para caber um pedido maior.
+    heap.free(&mut mmu, b).unwrap();
+    assert_eq!(heap.stats(&mut mmu).largest_free, 26);
+    assert_eq!(heap.malloc(&mut kernel, &mut mmu, 24), Ok(0x10A));
+    assert_eq!(kernel.process(pid).unwrap().brk, 0x128);
+
+    let mut data = [0; 4];
+    mmu.read_bytes(c, &mut data).unwrap();
+    assert_eq!(&data, b"fim!");
+
+    assert_eq!(heap.free(&mut mmu, b), Err(HeapError::InvalidPointer(b)));
+    assert_eq!(
+        heap.malloc(&mut kernel, &mut mmu, 0x100),
+        Err(HeapError::OutOfMemory(Some(SyscallError::NoMemory)))
+    );
+}
+
+#[test]
+fn buddy_splits_in_powers_of_two_and_touches_only_the_pages_it_needs() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut kernel = Kernel::new();
+    let pid = kernel.spawn(&mmu, 16).unwrap();
+    let mut heap = Heap::new(pid, Strategy::Buddy);
+
+    let small = heap.malloc(&mut kernel, &mut mmu, 10).unwrap();
+    let large = heap.malloc(&mut kernel, &mut mmu, 100).unwrap();
+    assert_eq!((small, large), (0x102, 0x182));
+
+    // A arena inteira foi mapeada, mas só as páginas com cabeçalhos (os
+    // das metades divididas) foram carregadas.
+    assert_eq!(mmu.process_stats(pid).virtual_pages, 16);
+    assert_eq!(mmu.process_stats(pid).resident_pages, 5);
+
+    let stats = heap.stats(&mut mmu);
+    assert_eq!(
+        (stats.heap_bytes, stats.used_bytes, stats.requested_bytes),
+        (4096, 16 + 128, 110)
+    );
+    assert!(stats.internal_fragmentation() > 0.2);
+    assert_eq!(stats.largest_free, 3840);
+
+    assert_eq!(
+        heap.malloc(&mut kernel, &mut mmu, 4096),
+        Err(HeapError::OutOfMemory(None))
+    );
+
+    // Liberados, os irmãos se juntam de volta num bloco só.
+    heap.free(&mut mmu, small).unwrap();
+    heap.free(&mut mmu, large).unwrap();
+    assert_eq!(
+        heap.blocks(&mut mmu),
+        [Block {
+            address: 0x100,
+            size: 4096,
+            used: false
+        }]
+    );
+}
@@ -0,0 +1,41 @@
+//! As buscas de instrução, contadas à parte dos acessos a dados (veja
+//! `Mmu::fetch`).
+
+mod common;
+
+use vm::region::Permissions;
+
+type SmallMmu = common::SmallMmu<{ 2 * 256 }, 2>;
+
+#[test]
+fn fetches_and_data_accesses_are_counted_apart() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.set_protection(0x10..0x11, Permissions::READ_EXECUTE);
+
+    // Um laço de código na página 0x10 lendo e escrevendo a página 0x20.
+    for i in 0..4 {
+        mmu.fetch(0x1000 + i).unwrap();
+        let value = mmu.read(0x2000 + i).unwrap();
+        mmu.write(0x2000 + i, value + 1).unwrap();
+    }
+
+    let stats = &mmu.stats;
+    assert_eq!(stats.accesses(), 12);
+    assert_eq!(stats.instruction_fetches(), 4);
+    assert_eq!(stats.instruction_misses(), 1);
+    assert_eq!(stats.data_accesses(), 8);
+    assert_eq!(stats.data_misses(), 1);
+}
+
+#[test]
+fn a_refused_fetch_is_not_counted() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.set_protection(0x20..0x21, Permissions::READ_WRITE);
+
+    assert!(mmu.fetch(0x2000).is_err());
+    assert_eq!(mmu.read(0x2000), Ok(0));
+
+    assert_eq!(mmu.stats.instruction_fetches(), 0);
+    assert_eq!(mmu.stats.data_accesses(), 1);
+    assert_eq!(mmu.stats.protection_faults(), 1);
+}
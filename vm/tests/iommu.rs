@@ -0,0 +1,118 @@
+//! A tradução dos endereços de I/O pela IOMMU (veja o módulo `iommu`).
+
+mod common;
+
+use vm::{
+    iommu::{Iommu, IommuError, IommuFault},
+    trace::AccessKind,
+};
+
+type SmallMmu = common::SmallMmu<{ 4 * 256 }, 4>;
+
+#[test]
+fn a_mapped_io_page_reaches_the_frame_of_the_pinned_page() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut iommu = Iommu::<4>::new();
+    mmu.write(0x1010, 0xAB).unwrap();
+
+    iommu.map_page(&mut mmu, 0x02, 0x10).unwrap();
+    assert!(mmu.is_pinned(0x10));
+
+    let mut buffer = [0; 2];
+    assert_eq!(iommu.read(&mut mmu, 0x0210, &mut buffer), Ok(()));
+    assert_eq!(buffer, [0xAB, 0x00]);
+
+    assert_eq!(iommu.write(&mut mmu, 0x0211, &[0xCD]), Ok(()));
+    assert_eq!(mmu.read(0x1011), Ok(0xCD));
+
+    assert_eq!(iommu.stats().translations, 2);
+    assert!(iommu.faults().is_empty());
+    assert!(mmu.dma_violations().is_empty());
+}
+
+#[test]
+fn an_unmapped_io_page_faults() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut iommu = Iommu::<4>::new();
+    iommu.map_page(&mut mmu, 0x00, 0x10).unwrap();
+    iommu.unmap_page(&mut mmu, 0x00).unwrap();
+    assert!(!mmu.is_pinned(0x10));
+
+    let mut buffer = [0; 1];
+    let fault = iommu.read(&mut mmu, 0x0005, &mut buffer).unwrap_err();
+
+    assert_eq!(
+        fault,
+        IommuFault::Unmapped {
+            kind: AccessKind::Read,
+            iova: 0x0005
+        }
+    );
+    assert_eq!(buffer, [0xFF]);
+    assert_eq!(iommu.faults(), [fault]);
+    assert_eq!(iommu.stats().faults, 1);
+}
+
+#[test]
+fn an_address_beyond_the_io_pages_faults() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut iommu = Iommu::<4>::new();
+    iommu.map_page(&mut mmu, 0x03, 0x10).unwrap();
+
+    // A escrita começa na última página de I/O e passa dela.
+    let fault = iommu.write(&mut mmu, 0x03FF, &[1, 2]).unwrap_err();
+
+    assert_eq!(
+        fault,
+        IommuFault::OutOfRange {
+            kind: AccessKind::Write,
+            iova: 0x0400
+        }
+    );
+    assert_eq!(mmu.read(0x10FF), Ok(1));
+    assert_eq!(iommu.stats().bytes_written, 1);
+    assert_eq!(iommu.faults(), [fault]);
+    assert_eq!(iommu.frame(0x04), None);
+}
+
+#[test]
+fn mapping_an_io_page_beyond_the_io_pages_fails_without_pinning() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut iommu = Iommu::<4>::new();
+    let error = IommuError::OutOfRange {
+        io_page: 0x04,
+        io_page_count: 4,
+    };
+
+    assert_eq!(iommu.map_page(&mut mmu, 0x04, 0x10), Err(error.clone()));
+    assert!(!mmu.is_pinned(0x10));
+    assert_eq!(iommu.map(0x04, 0), Err(error.clone()));
+    assert_eq!(iommu.unmap(0x04), Err(error.clone()));
+    assert_eq!(iommu.unmap_page(&mut mmu, 0x04), Err(error));
+}
+
+#[test]
+fn compaction_leaves_the_pinned_frame_of_an_io_page_in_place() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut iommu = Iommu::<4>::new();
+    for page in 0..3 {
+        mmu.write(page << 8, page as u8 + 1).unwrap();
+    }
+    iommu.map_page(&mut mmu, 0x01, 0x02).unwrap();
+    assert_eq!(iommu.frame(0x01), Some(2));
+
+    mmu.unmap_page(0x00);
+    mmu.unmap_page(0x01);
+    mmu.write(0x0300, 4).unwrap();
+    mmu.compact();
+
+    // A página 3 vai para o primeiro frame, e a fixada fica onde o
+    // dispositivo a vê.
+    assert_eq!(mmu.frame_map(), [Some(3), None, Some(2), None]);
+    assert!(mmu.is_pinned(0x02));
+    assert_eq!(iommu.write(&mut mmu, 0x0101, &[0xEE]), Ok(()));
+    assert_eq!(mmu.read(0x0201), Ok(0xEE));
+    assert_eq!(mmu.read(0x0200), Ok(3));
+    assert_eq!(mmu.read(0x0300), Ok(4));
+    assert!(mmu.dma_violations().is_empty());
+}
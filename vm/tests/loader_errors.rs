@@ -0,0 +1,326 @@
+//! As falhas dos carregadores chegam a quem fez o acesso, com o contexto
+//! (veja o módulo `error`).
+
+use vm::{
+    error::{AccessError, AccessFailure, LoaderError, LoaderOperation, MmuError},
+    mmu::Mmu,
+    overlay_loader::OverlayPageLoader,
+    page_loader::{MemoryPageLoader, PageLoader},
+    page_replacer::FIFOPageReplacer,
+    state::StateError,
+    time_travel::RewindError,
+    trace::AccessKind,
+};
+
+/// Um carregador em memória que falha ao carregar ou escrever uma página.
+struct FaultyLoader {
+    inner: MemoryPageLoader,
+    page: usize,
+    operation: LoaderOperation,
+}
+
+impl FaultyLoader {
+    fn new(page: usize, operation: LoaderOperation) -> Self {
+        FaultyLoader {
+            inner: MemoryPageLoader::new(),
+            page,
+            operation,
+        }
+    }
+
+    fn check(&self, operation: LoaderOperation, page_number: usize) -> Result<(), LoaderError> {
+        if operation == self.operation && page_number == self.page {
+            return Err(LoaderError::new(
+                self.backend(),
+                operation,
+                page_number,
+                "disco com defeito",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl PageLoader for FaultyLoader {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        self.inner.load_page_into(page_number, target);
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        self.inner.flush_page(page_number, buffer);
+    }
+
+    fn backend(&self) -> &str {
+        "defeituoso"
+    }
+
+    fn try_load_page_into(
+        &mut self,
+        page_number: usize,
+        target: &mut [u8],
+    ) -> Result<(), LoaderError> {
+        self.check(LoaderOperation::Load, page_number)?;
+        self.inner.try_load_page_into(page_number, target)
+    }
+
+    fn try_flush_page(&mut self, page_number: usize, buffer: &[u8]) -> Result<(), LoaderError> {
+        self.check(LoaderOperation::Flush, page_number)?;
+        self.inner.try_flush_page(page_number, buffer)
+    }
+}
+
+#[test]
+fn a_failed_load_names_the_access_page_frame_and_backend() {
+    let mut mmu = Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0x12, LoaderOperation::Load),
+    );
+
+    assert_eq!(mmu.read(0x0100), Ok(0));
+
+    let error = mmu.read(0x1234).unwrap_err();
+    assert_eq!(
+        error,
+        MmuError::Access(AccessError {
+            kind: AccessKind::Read,
+            address: 0x1234,
+            page_number: 0x12,
+            frame_index: Some(1),
+            cause: AccessFailure::Loader(LoaderError::new(
+                "defeituoso",
+                LoaderOperation::Load,
+                0x12,
+                "disco com defeito"
+            )),
+        })
+    );
+    assert_eq!(
+        error.to_string(),
+        "leitura em 0x1234 (página 0x12, frame 0x01): o carregador 'defeituoso' não \
+         conseguiu carregar a página 0x12: disco com defeito"
+    );
+
+    // O acesso conta mesmo assim, e o próximo não herda a falha.
+    assert_eq!(mmu.stats.accesses(), 2);
+    assert_eq!(mmu.read(0x1235), Ok(0));
+}
+
+#[test]
+fn a_failed_writeback_is_reported_by_the_access_that_evicted_the_page() {
+    let mut mmu = Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        OverlayPageLoader::new(FaultyLoader::new(0x00, LoaderOperation::Flush)),
+    );
+
+    mmu.write(0x0000, 0xAA).unwrap();
+    mmu.read(0x0100).unwrap();
+
+    // O overlay guarda as escritas sem tocar a base, então não falha.
+    assert_eq!(mmu.write(0x0200, 1), Ok(()));
+
+    let mut mmu = Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0x00, LoaderOperation::Flush),
+    );
+
+    mmu.write(0x0000, 0xAA).unwrap();
+    mmu.read(0x0100).unwrap();
+
+    let error = mmu.write(0x0200, 1).unwrap_err();
+    let error = error.access_error().unwrap();
+    assert_eq!((error.kind, error.page_number), (AccessKind::Write, 0x02));
+    assert!(matches!(
+        &error.cause,
+        AccessFailure::Loader(cause)
+            if (cause.operation, cause.page_number) == (LoaderOperation::Flush, 0x00)
+    ));
+}
+
+#[test]
+fn read_error_describes_the_context() {
+    let mut mmu = Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0x12, LoaderOperation::Load),
+    );
+
+    let error = mmu.read(0x1234).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("leitura em 0x1234 (página 0x12"));
+}
+
+#[test]
+fn a_failed_sync_leaves_the_page_dirty() {
+    let mut mmu = Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0x01, LoaderOperation::Flush),
+    );
+
+    mmu.write(0x0000, 0xAA).unwrap();
+    mmu.write(0x0100, 0xBB).unwrap();
+
+    let error = mmu.flush_all().unwrap_err();
+    assert_eq!(
+        (error.operation, error.page_number),
+        (LoaderOperation::Flush, 0x01)
+    );
+
+    // A página 0 foi escrita e ficou limpa; a 1 é tentada de novo.
+    assert_eq!(
+        mmu.loader().inner.page(0x00).map(|page| page[0]),
+        Some(0xAA)
+    );
+    assert!(mmu.flush_all().is_err());
+}
+
+#[test]
+fn a_failed_swap_out_keeps_the_page_it_could_not_write() {
+    let mut mmu = Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0x01, LoaderOperation::Flush),
+    );
+    mmu.map_region(1, "dados", 0..3).unwrap();
+    for page in 0..3 {
+        mmu.write(page << 8, 0x10 + page as u8).unwrap();
+    }
+
+    let error = mmu.swap_out_process(1).unwrap_err();
+    assert_eq!(
+        (error.operation, error.page_number),
+        (LoaderOperation::Flush, 0x01)
+    );
+
+    // As páginas 0 e 2 saíram; a 1 ficou em memória, suja, sem perder o
+    // conteúdo.
+    assert_eq!(
+        mmu.frame_map().iter().flatten().collect::<Vec<_>>(),
+        [&0x01]
+    );
+    assert_eq!(
+        mmu.loader().inner.page(0x02).map(|page| page[0]),
+        Some(0x12)
+    );
+    let misses = mmu.stats.misses();
+    assert_eq!(mmu.read(0x0100), Ok(0x11));
+    assert_eq!(mmu.stats.misses(), misses);
+    assert!(mmu.flush_page(0x01).is_err());
+}
+
+#[test]
+fn peeking_at_a_page_that_cannot_be_loaded_returns_the_error() {
+    let mut mmu = Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0x12, LoaderOperation::Load),
+    );
+
+    let error = mmu.peek_page(0x12).unwrap_err();
+    assert_eq!(
+        (error.operation, error.page_number),
+        (LoaderOperation::Load, 0x12)
+    );
+    assert_eq!(mmu.peek_page(0x13), Ok(vec![0; 256]));
+    assert_eq!(mmu.stats.accesses(), 0);
+}
+
+#[test]
+fn an_image_stops_at_the_page_that_cannot_be_loaded() {
+    let mut mmu = Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0x01, LoaderOperation::Load),
+    );
+
+    let error = mmu.load_image(0x0080, &[0xAA; 512]).unwrap_err();
+    assert_eq!(
+        (error.operation, error.page_number),
+        (LoaderOperation::Load, 0x01)
+    );
+
+    // A página 0 foi escrita; a 1 e a 2 ficaram como estavam.
+    assert_eq!(
+        mmu.loader().inner.page(0x00).map(|page| page[0x80]),
+        Some(0xAA)
+    );
+    assert_eq!(mmu.loader().inner.page(0x01), None);
+    assert_eq!(mmu.loader().inner.page(0x02), None);
+}
+
+#[test]
+fn a_state_with_a_page_that_cannot_be_loaded_leaves_the_mmu_empty() {
+    let mut source =
+        Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    source.read(0x0000).unwrap();
+    source.read(0x0100).unwrap();
+    let state = source.export_state();
+
+    let mut mmu = Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0x01, LoaderOperation::Load),
+    );
+
+    let error = mmu.load_state(&state).unwrap_err();
+    assert!(matches!(
+        error,
+        StateError::Loader(LoaderError {
+            page_number: 0x01,
+            ..
+        })
+    ));
+    assert_eq!(mmu.frame_map(), vec![None; 4]);
+}
+
+#[test]
+fn a_rewind_that_cannot_undo_a_writeback_returns_the_error() {
+    let mut mmu = Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0xFF, LoaderOperation::Flush),
+    );
+    mmu.enable_time_travel(16);
+
+    mmu.write(0x0000, 0xAA).unwrap();
+    mmu.read(0x0100).unwrap();
+    mmu.read(0x0200).unwrap();
+
+    // Desfazer o writeback da página 0 precisa escrevê-la de novo.
+    mmu.loader_mut().page = 0x00;
+
+    let error = mmu.rewind(1).unwrap_err();
+    assert!(matches!(
+        error,
+        RewindError::Loader(LoaderError {
+            page_number: 0x00,
+            ..
+        })
+    ));
+
+    // O histórico foi descartado.
+    assert!(matches!(mmu.rewind(1), Err(RewindError::TooFar { .. })));
+}
+
+#[test]
+fn a_swap_in_stops_at_the_page_that_cannot_be_loaded() {
+    let mut mmu = Mmu::<{ 8 * 256 }, 8, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        FaultyLoader::new(0xFF, LoaderOperation::Load),
+    );
+    mmu.map_region(1, "dados", 0..4).unwrap();
+    for page in 0..4 {
+        mmu.write(page << 8, 0x10 + page as u8).unwrap();
+    }
+    mmu.swap_out_process(1).unwrap();
+
+    mmu.loader_mut().page = 0x01;
+
+    let error = mmu.swap_in_process(1, true).unwrap_err();
+    assert_eq!(
+        (error.operation, error.page_number),
+        (LoaderOperation::Load, 0x01)
+    );
+
+    // A página 0 voltou; a que falhou e as seguintes voltam por demanda.
+    let frame_map = mmu.frame_map();
+    assert!(frame_map.contains(&Some(0x00)));
+    assert_eq!(frame_map.iter().flatten().count(), 1);
+    assert_eq!(mmu.stats.swap_prefaults(), 1);
+    assert_eq!(mmu.read(0x0200), Ok(0x12));
+}
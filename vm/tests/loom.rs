@@ -0,0 +1,50 @@
+//! Os contadores de compartilhamento de frames, lidos por outra thread
+//! enquanto a Mmu compartilha uma página e quebra o copy-on-write, em todas
+//! as intercalações possíveis (veja o módulo `sync`). Só compila com
+//! `--cfg loom`:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test -p vm --test loom --release
+//! ```
+
+#![cfg(loom)]
+
+use loom::thread;
+
+use vm::{mmu::Mmu, page_loader::MemoryPageLoader, page_replacer::FIFOPageReplacer};
+
+type SmallMmu = Mmu<{ 4 * 256 }, 4, 4, FIFOPageReplacer, MemoryPageLoader>;
+
+#[test]
+fn a_concurrent_reader_sees_the_shared_frame_counters_in_range() {
+    loom::model(|| {
+        let mut mmu = SmallMmu::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+        mmu.write(0x000, 0xAB).unwrap();
+
+        let stats = mmu.stats_handle();
+        let reader = thread::spawn(move || {
+            let cow_breaks = stats.cow_breaks();
+            let shared = stats.shared_frames();
+            let peak = stats.peak_saved_frames();
+
+            // Só um frame chega a ser compartilhado, e uma vez quebrado o
+            // copy-on-write o contador não volta atrás.
+            assert!(shared <= 1, "{} frames compartilhados", shared);
+            assert!(peak <= 1, "pico de {} frames economizados", peak);
+            assert!(cow_breaks <= 1, "{} quebras de COW", cow_breaks);
+            assert!(stats.cow_breaks() >= cow_breaks);
+        });
+
+        assert_eq!(mmu.share_page(0, 1), Ok(true));
+        mmu.write(0x100, 0xCD).unwrap();
+
+        reader.join().unwrap();
+
+        assert_eq!(mmu.stats.shared_frames(), 0);
+        assert_eq!(mmu.stats.saved_frames(), 0);
+        assert_eq!(mmu.stats.peak_saved_frames(), 1);
+        assert_eq!(mmu.stats.cow_breaks(), 1);
+        assert_eq!(mmu.read(0x000), Ok(0xAB));
+        assert_eq!(mmu.read(0x100), Ok(0xCD));
+    });
+}
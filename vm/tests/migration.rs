@@ -0,0 +1,31 @@
+//! A migração ao vivo, com e sem o balão (veja `scenario::balloon_migration`).
+
+use vm::scenario;
+
+#[test]
+fn the_balloon_frees_frames_and_every_migration_matches() {
+    let report = scenario::balloon_migration();
+
+    for run in &report.runs {
+        assert!(run.migration.contents_match);
+
+        if run.balloon_pages == 0 {
+            assert_eq!(run.migration.balloon_frames_freed, 0);
+        } else {
+            // As páginas devolvidas são as últimas usadas, todas residentes.
+            assert_eq!(run.migration.balloon_frames_freed, run.balloon_pages);
+        }
+    }
+}
+
+#[test]
+fn the_balloon_shrinks_the_migration() {
+    let report = scenario::balloon_migration();
+
+    for pair in report.runs.chunks(2) {
+        let (without, with) = (&pair[0].migration, &pair[1].migration);
+
+        assert_eq!(pair[0].write_ratio, pair[1].write_ratio);
+        assert!(with.total_pages_copied() < without.total_pages_copied());
+    }
+}
@@ -0,0 +1,73 @@
+//! A proteção de intervalos de endereços virtuais (veja `Mmu::mprotect`).
+
+mod common;
+
+use vm::{error::AccessFailure, region::Permissions};
+
+type SmallMmu = common::SmallMmu<{ 2 * 256 }, 2>;
+
+#[test]
+fn a_partially_covered_page_is_protected_whole() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    // Do meio da página 0x10 ao primeiro byte da 0x12.
+    let pages = mmu.mprotect(0x1080..0x1201, Permissions::READ_ONLY);
+
+    assert_eq!(pages, 0x10..0x13);
+    for page_number in 0x10..0x13 {
+        assert_eq!(mmu.protection(page_number), Permissions::READ_ONLY);
+    }
+    assert_eq!(mmu.protection(0x0F), Permissions::ALL);
+    assert_eq!(mmu.protection(0x13), Permissions::ALL);
+
+    // Os bytes da página 0x10 antes do intervalo mudaram junto.
+    assert!(mmu.write(0x1000, 1).is_err());
+    assert!(mmu.write(0x1300, 1).is_ok());
+}
+
+#[test]
+fn an_empty_range_changes_nothing() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    let pages = mmu.mprotect(0x1080..0x1080, Permissions::NONE);
+
+    assert!(pages.is_empty());
+    assert_eq!(mmu.protection(0x10), Permissions::ALL);
+}
+
+#[test]
+fn a_guard_page_stops_a_stack_overflow() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    // A pilha ocupa as páginas 0x21..0x24 e cresce para baixo, até a página
+    // de guarda 0x20.
+    mmu.mprotect(0x2100..0x2400, Permissions::READ_WRITE);
+    mmu.mprotect(0x2000..0x2100, Permissions::NONE);
+
+    assert!(mmu.write(0x2100, 0xAA).is_ok());
+    let error = mmu.write(0x20FF, 0xAA).unwrap_err();
+    let error = error.access_error().unwrap();
+    assert_eq!(error.cause, AccessFailure::Protection(Permissions::NONE));
+    assert_eq!(mmu.stats.protection_faults(), 1);
+}
+
+#[test]
+fn a_read_only_data_segment_can_be_read_but_not_written() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.write_from(0x3000, b"constante").unwrap();
+
+    mmu.mprotect(0x3000..0x3009, Permissions::READ_ONLY);
+
+    let mut buffer = [0; 9];
+    mmu.read_into(0x3000, &mut buffer).unwrap();
+    assert_eq!(&buffer, b"constante");
+    assert!(mmu.write(0x3004, b'X').is_err());
+    assert!(mmu.fetch(0x3004).is_err());
+}
+
+#[test]
+#[should_panic]
+fn a_range_past_the_address_space_panics() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.mprotect(0xFF00..0x10001, Permissions::NONE);
+}
@@ -0,0 +1,155 @@
+//! O swap em camadas sobre uma imagem base (veja o módulo
+//! `overlay_loader`).
+
+mod common;
+
+use vm::{
+    mmu::{Mmu, PAGE_SIZE},
+    overlay_loader::{OverlayPageLoader, SnapshotError},
+    page_loader::{MemoryPageLoader, PageLoader},
+    page_replacer::FIFOPageReplacer,
+};
+
+type SmallMmu =
+    common::SmallMmu<{ 2 * 256 }, 2, FIFOPageReplacer, OverlayPageLoader<MemoryPageLoader>>;
+
+/// Uma imagem de 4 páginas; cada byte é o número da página.
+fn base() -> MemoryPageLoader {
+    let image: Vec<u8> = (0..4 * PAGE_SIZE).map(|i| (i / PAGE_SIZE) as u8).collect();
+
+    MemoryPageLoader::from_image(&image, PAGE_SIZE)
+}
+
+fn mmu() -> SmallMmu {
+    Mmu::new(FIFOPageReplacer::new(), OverlayPageLoader::new(base()))
+}
+
+/// Tira as páginas da memória, escrevendo de volta as sujas.
+fn evict_all(mmu: &mut SmallMmu) {
+    mmu.read(0x3000).unwrap();
+    mmu.read(0x3100).unwrap();
+}
+
+#[test]
+fn unmodified_pages_are_read_from_the_base() {
+    let mut mmu = mmu();
+
+    assert_eq!(mmu.read(0x0000), Ok(0));
+    assert_eq!(mmu.read(0x0210), Ok(2));
+    evict_all(&mut mmu);
+
+    // Páginas limpas não passam pela camada de alterações.
+    assert_eq!(mmu.loader().delta_pages(), 0);
+    assert_eq!(mmu.read(0x0110), Ok(1));
+}
+
+#[test]
+fn a_writeback_copies_the_page_up_and_leaves_the_base_alone() {
+    let mut mmu = mmu();
+
+    mmu.write(0x0110, 0xAA).unwrap();
+    evict_all(&mut mmu);
+
+    let overlay = mmu.loader();
+    assert!(overlay.is_modified(1));
+    assert!(!overlay.is_modified(0));
+    assert_eq!(overlay.delta_pages(), 1);
+    assert_eq!(overlay.base().page(1).unwrap()[0x10], 1);
+
+    // A página volta da camada, com o resto do conteúdo da base.
+    assert_eq!(mmu.read(0x0110), Ok(0xAA));
+    assert_eq!(mmu.read(0x0111), Ok(1));
+}
+
+#[test]
+fn commit_writes_the_changes_to_the_base() {
+    let mut mmu = mmu();
+
+    mmu.write(0x0110, 0xAA).unwrap();
+    mmu.write(0x0220, 0xBB).unwrap();
+    evict_all(&mut mmu);
+
+    assert_eq!(mmu.loader_mut().commit(), 2);
+
+    let overlay = mmu.loader();
+    assert_eq!(overlay.delta_pages(), 0);
+    assert_eq!(overlay.base().page(1).unwrap()[0x10], 0xAA);
+    assert_eq!(overlay.base().page(2).unwrap()[0x20], 0xBB);
+    assert_eq!(mmu.read(0x0220), Ok(0xBB));
+}
+
+#[test]
+fn discard_goes_back_to_the_base() {
+    let mut mmu = mmu();
+
+    mmu.write(0x0110, 0xAA).unwrap();
+    evict_all(&mut mmu);
+
+    assert_eq!(mmu.loader_mut().discard(), 1);
+    assert!(!mmu.loader().is_modified(1));
+    assert_eq!(mmu.read(0x0110), Ok(1));
+
+    let mut page = vec![0; PAGE_SIZE];
+    mmu.loader_mut().load_page_into(1, &mut page);
+    assert_eq!(page, vec![1; PAGE_SIZE]);
+}
+
+#[test]
+fn restore_goes_back_to_a_snapshot_and_can_branch() {
+    let mut mmu = mmu();
+
+    mmu.write(0x0110, 0xAA).unwrap();
+    evict_all(&mut mmu);
+    mmu.loader_mut().snapshot("antes");
+
+    mmu.write(0x0110, 0xBB).unwrap();
+    mmu.write(0x0220, 0xCC).unwrap();
+    evict_all(&mut mmu);
+    mmu.loader_mut().snapshot("depois");
+
+    assert_eq!(mmu.loader_mut().restore("antes"), Ok(()));
+    assert_eq!(mmu.loader().delta_pages(), 1);
+    assert_eq!(mmu.read(0x0110), Ok(0xAA));
+    assert_eq!(mmu.read(0x0220), Ok(2));
+
+    // O snapshot continua guardado, e dá para voltar ao outro ramo.
+    evict_all(&mut mmu);
+    assert_eq!(mmu.loader_mut().restore("depois"), Ok(()));
+    assert_eq!(mmu.read(0x0110), Ok(0xBB));
+    assert_eq!(mmu.read(0x0220), Ok(0xCC));
+
+    assert_eq!(
+        mmu.loader().snapshots().collect::<Vec<_>>(),
+        ["antes", "depois"]
+    );
+}
+
+#[test]
+fn a_snapshot_survives_a_commit() {
+    let mut overlay = OverlayPageLoader::new(base());
+    overlay.snapshot("limpo");
+
+    overlay.flush_page(1, &[0xAA; PAGE_SIZE]);
+    assert_eq!(overlay.commit(), 1);
+    assert_eq!(overlay.base().page(1).unwrap(), &[0xAA; PAGE_SIZE]);
+
+    // O conteúdo antigo da base foi guardado no snapshot antes do commit.
+    overlay.restore("limpo").unwrap();
+    let mut page = vec![0; PAGE_SIZE];
+    overlay.load_page_into(1, &mut page);
+    assert_eq!(page, vec![1; PAGE_SIZE]);
+}
+
+#[test]
+fn an_unknown_or_deleted_snapshot_cannot_be_restored() {
+    let mut overlay = OverlayPageLoader::new(base());
+    overlay.snapshot("a");
+
+    assert!(overlay.delete_snapshot("a"));
+    assert!(!overlay.delete_snapshot("a"));
+    assert_eq!(
+        overlay.restore("a"),
+        Err(SnapshotError::Unknown("a".to_string()))
+    );
+    assert_eq!(overlay.snapshots().count(), 0);
+}
@@ -0,0 +1,48 @@
+//! Leituras de páginas sem cópia (`PageLoader::page_ref` e
+//! `Mmu::peek_page_ref`).
+
+use vm::{
+    mmu::Mmu,
+    overlay_loader::OverlayPageLoader,
+    page_loader::{MemoryPageLoader, PageLoader},
+    page_replacer::FIFOPageReplacer,
+};
+
+#[test]
+fn peek_page_ref_borrows_the_frame_or_the_loader_buffer() {
+    let mut mmu =
+        Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+
+    mmu.write(0x0010, 0xA0).unwrap();
+    mmu.write(0x0110, 0xA1).unwrap();
+    // A página 0 sai da memória, suja, e vai para o carregador.
+    mmu.write(0x0210, 0xA2).unwrap();
+
+    let frame = mmu.peek_page_ref(0x02).unwrap();
+    assert_eq!(frame[0x10], 0xA2);
+
+    let on_disk = mmu.peek_page_ref(0x00).unwrap();
+    assert_eq!(on_disk[0x10], 0xA0);
+    assert!(std::ptr::eq(on_disk, mmu.loader().page(0x00).unwrap()));
+
+    // Uma página nunca escrita não tem buffer, mas `peek_page` a lê zerada.
+    assert!(mmu.peek_page_ref(0x30).is_none());
+    assert_eq!(mmu.peek_page(0x30).unwrap(), vec![0; 256]);
+    assert_eq!(
+        mmu.peek_page(0x00).unwrap(),
+        mmu.loader().page(0x00).unwrap()
+    );
+}
+
+#[test]
+fn the_overlay_exposes_the_delta_before_the_base() {
+    let image: Vec<u8> = (0..2 * 256).map(|i| (i / 256) as u8 + 1).collect();
+    let base: MemoryPageLoader = MemoryPageLoader::from_image(&image, 256);
+    let mut overlay = OverlayPageLoader::new(base);
+
+    overlay.flush_page(1, &[0xFF; 256]);
+
+    assert_eq!(overlay.page_ref(0).unwrap(), &[1; 256][..]);
+    assert_eq!(overlay.page_ref(1).unwrap(), &[0xFF; 256][..]);
+    assert!(overlay.page_ref(2).is_none());
+}
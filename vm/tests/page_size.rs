@@ -0,0 +1,72 @@
+//! A conferência do tamanho das páginas do carregador (veja
+//! `Mmu::try_new`).
+
+mod common;
+
+use vm::{
+    error::LoaderMismatch,
+    mmu::Mmu,
+    overlay_loader::OverlayPageLoader,
+    page_loader::{MemoryPageLoader, PageLoader},
+    page_replacer::FIFOPageReplacer,
+};
+
+type SmallMmu<L> = common::SmallMmu<{ 4 * 256 }, 4, FIFOPageReplacer, L>;
+
+fn image_loader(page_size: usize) -> MemoryPageLoader {
+    MemoryPageLoader::from_image(&vec![0xAB; 4 * page_size], page_size)
+}
+
+#[test]
+fn a_loader_with_other_page_size_is_rejected() {
+    let result = SmallMmu::try_new(FIFOPageReplacer::new(), image_loader(512));
+    let err = result.err().unwrap();
+
+    assert_eq!(
+        err,
+        LoaderMismatch::PageSize {
+            backend: "memory".to_string(),
+            loader_page_size: 512,
+            frame_size: 256,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "o carregador 'memory' tem páginas de 512 bytes, mas os frames da Mmu têm 256 bytes"
+    );
+
+    // Quem embrulha outro carregador repassa o tamanho dele.
+    let overlay = OverlayPageLoader::new(image_loader(4096));
+    assert_eq!(overlay.page_size(), Some(4096));
+    assert!(SmallMmu::try_new(FIFOPageReplacer::new(), overlay).is_err());
+}
+
+#[test]
+fn loaders_with_the_frame_size_or_no_size_are_accepted() {
+    let mut mmu = SmallMmu::try_new(FIFOPageReplacer::new(), image_loader(256)).unwrap();
+    assert_eq!(mmu.read(0x0310).unwrap(), 0xAB);
+
+    assert_eq!(MemoryPageLoader::new().page_size(), None);
+    assert!(SmallMmu::try_new(FIFOPageReplacer::new(), MemoryPageLoader::new()).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "mmu: o carregador 'memory' tem páginas de 512 bytes")]
+fn new_panics_on_a_mismatch_instead_of_shearing_pages() {
+    SmallMmu::new(FIFOPageReplacer::new(), image_loader(512));
+}
+
+#[test]
+fn frames_other_than_256_bytes_are_rejected() {
+    type BigFrameMmu = Mmu<{ 4 * 4096 }, 4, 256, FIFOPageReplacer, MemoryPageLoader>;
+
+    let err = BigFrameMmu::try_new(FIFOPageReplacer::new(), MemoryPageLoader::new())
+        .err()
+        .unwrap();
+
+    assert_eq!(err, LoaderMismatch::FrameSize { frame_size: 4096 });
+    assert_eq!(
+        err.to_string(),
+        "os frames da Mmu têm 4096 bytes, mas a tradução só suporta páginas de 256 bytes"
+    );
+}
@@ -0,0 +1,117 @@
+//! As reservas de frames dos processos (veja o módulo `partition`).
+
+mod common;
+
+use vm::{
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::LRUPageReplacer,
+    partition::{Partition, PartitionError, Reservation},
+};
+
+type SmallMmu = common::SmallMmu<{ 8 * 256 }, 8, LRUPageReplacer>;
+
+/// Uma Mmu com 8 frames, o processo 1 nas páginas 0 a 7 e o processo 2 nas
+/// páginas 8 a 63.
+fn mmu(reservation: Option<Reservation>) -> SmallMmu {
+    let mut mmu = Mmu::new(LRUPageReplacer::new(), MemoryPageLoader::new());
+    mmu.map_region(1, "interativo", 0..8).unwrap();
+    mmu.map_region(2, "lote", 8..64).unwrap();
+    mmu.set_frame_reservation(1, reservation).unwrap();
+    mmu
+}
+
+#[test]
+fn a_reservation_isolates_the_process_from_a_noisy_neighbor() {
+    let mut faults = Vec::new();
+
+    for reservation in [None, Some(Reservation::isolated(3))] {
+        let mut mmu = mmu(reservation);
+
+        for i in 0..200 {
+            mmu.read((i % 3) << 8).unwrap();
+            for j in 0..3 {
+                mmu.read((8 + (3 * i + j) % 56) << 8).unwrap();
+            }
+        }
+
+        faults.push(mmu.partitions().stats(Partition::Reserved(1)).faults);
+    }
+
+    // Sem reserva, o processo 1 não é uma partição; com ela, só as faltas
+    // frias.
+    assert_eq!(faults, [0, 3]);
+}
+
+#[test]
+fn an_isolated_process_stays_within_its_reservation() {
+    let mut mmu = mmu(Some(Reservation::isolated(3)));
+
+    for page in 0..6 {
+        mmu.read(page << 8).unwrap();
+    }
+    assert_eq!(mmu.process_stats(1).resident_pages, 3);
+
+    // Os frames livres ficam para o pool compartilhado.
+    for page in 8..13 {
+        mmu.read(page << 8).unwrap();
+    }
+    assert_eq!(mmu.process_stats(2).resident_pages, 5);
+    assert_eq!(mmu.process_stats(1).resident_pages, 3);
+}
+
+#[test]
+fn reserved_frames_are_kept_for_the_process() {
+    let mut mmu = mmu(Some(Reservation::borrowing(3)));
+
+    // O processo 2 enche o pool compartilhado, e não entra na reserva.
+    for page in 8..16 {
+        mmu.read(page << 8).unwrap();
+    }
+    assert_eq!(mmu.process_stats(2).resident_pages, 5);
+
+    // O processo 1 usa a reserva sem tirar páginas do processo 2, e depois
+    // cresce pelo pool compartilhado.
+    for page in 0..3 {
+        mmu.read(page << 8).unwrap();
+    }
+    assert_eq!(mmu.process_stats(2).resident_pages, 5);
+
+    mmu.read(3 << 8).unwrap();
+    assert_eq!(mmu.process_stats(1).resident_pages, 4);
+    assert_eq!(mmu.process_stats(2).resident_pages, 4);
+
+    let report = mmu.partition_report().unwrap();
+    let shared = report.row(Partition::Shared).unwrap();
+    assert_eq!((shared.frames, shared.resident_pages), (5, 5));
+    assert_eq!(shared.stats.faults, 8);
+}
+
+#[test]
+fn reservations_leave_a_shared_pool() {
+    let mut mmu = mmu(None);
+
+    assert_eq!(
+        mmu.set_frame_reservation(1, Some(Reservation::isolated(0))),
+        Err(PartitionError::EmptyReservation(1))
+    );
+
+    mmu.set_frame_reservation(1, Some(Reservation::isolated(4)))
+        .unwrap();
+    assert_eq!(
+        mmu.set_frame_reservation(2, Some(Reservation::isolated(4))),
+        Err(PartitionError::Oversubscribed {
+            reserved: 8,
+            available: 8,
+        })
+    );
+
+    // Trocar a reserva do processo 1 não conta a antiga.
+    mmu.set_frame_reservation(1, Some(Reservation::isolated(7)))
+        .unwrap();
+    assert_eq!(mmu.frame_reservation(1), Some(Reservation::isolated(7)));
+    assert!(mmu.partition_report().is_some());
+
+    mmu.set_frame_reservation(1, None).unwrap();
+    assert!(mmu.partition_report().is_none());
+}
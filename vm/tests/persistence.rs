@@ -0,0 +1,131 @@
+//! O conteúdo da memória sobrevive ao desligamento da Mmu (veja
+//! `Mmu::flush_all`, `Mmu::shutdown` e o `Drop` da Mmu).
+
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use vm::{
+    cost::CostModel,
+    mmu::Mmu,
+    page_loader::{MemoryPageLoader, PageLoader},
+    page_replacer::FIFOPageReplacer,
+    scenario,
+};
+
+type SmallMmu<L> = common::SmallMmu<{ 4 * 256 }, 4, FIFOPageReplacer, L>;
+
+#[test]
+fn a_fresh_mmu_reads_back_every_byte_after_a_shutdown() {
+    let report = scenario::persistence();
+
+    assert_eq!(report.mismatched_bytes, 0);
+    assert_eq!(report.reload_faults, report.pages);
+    // As páginas que ficaram em memória só chegam ao disco no desligamento.
+    assert_eq!(report.flushed_pages, report.frames);
+    assert_eq!(report.stale_bytes, report.frames * 256);
+}
+
+#[test]
+fn flush_all_writes_the_dirty_pages_and_keeps_them_resident() {
+    let mut mmu: SmallMmu<_> = common::mmu();
+
+    mmu.write_from(0x00F0, &[0x5A; 0x20]).unwrap();
+    mmu.read(0x0200).unwrap();
+    assert_eq!(mmu.loader().page(0x00), None);
+
+    assert_eq!(mmu.flush_all(), Ok(2));
+    assert_eq!(mmu.loader().page(0x00).unwrap()[0xF0], 0x5A);
+    assert_eq!(mmu.loader().page(0x01).unwrap()[0x0F], 0x5A);
+    assert_eq!(mmu.stats.flushes(), 2);
+    assert_eq!(mmu.stats.writebacks(), 0);
+
+    // Já limpas, as páginas não são escritas de novo, nem ao sair.
+    assert_eq!(mmu.flush_all(), Ok(0));
+    for page in 0x10..0x14 {
+        mmu.read(page << 8).unwrap();
+    }
+    assert_eq!(mmu.stats.flushes(), 2);
+    assert_eq!(mmu.stats.writebacks(), 0);
+}
+
+#[test]
+fn flushes_do_not_count_as_fault_writebacks() {
+    let cost = CostModel::default();
+    let mut mmu: SmallMmu<_> = common::mmu();
+
+    // Uma page fault, sem vítima, e as páginas sujas escritas várias vezes.
+    mmu.write(0x0010, 0x11).unwrap();
+    let eat = mmu.stats.effective_access_time(&cost);
+    for value in 0..4 {
+        mmu.write(0x0010, value).unwrap();
+        mmu.flush_all().unwrap();
+    }
+
+    assert_eq!(mmu.stats.misses(), 1);
+    assert_eq!(mmu.stats.flushes(), 4);
+    assert_eq!(mmu.stats.writebacks(), 0);
+
+    // Os hits baixam o EAT; com os flushes contados como writebacks das
+    // faults, `d` passaria de 1 e o EAT subiria.
+    assert!(mmu.stats.effective_access_time(&cost) < eat);
+}
+
+#[test]
+fn shutdown_hands_the_loader_to_a_new_mmu() {
+    let mut mmu: SmallMmu<_> = common::mmu();
+    mmu.write_u32(0x01FE, 0xDEAD_BEEF).unwrap();
+
+    let loader = mmu.shutdown().unwrap();
+    let mut mmu: SmallMmu<_> = Mmu::new(FIFOPageReplacer::new(), loader);
+
+    assert_eq!(mmu.read_u32(0x01FE), Ok(0xDEAD_BEEF));
+    assert_eq!(mmu.stats.misses(), 2);
+}
+
+#[test]
+fn flush_page_writes_only_that_page() {
+    let mut mmu: SmallMmu<_> = common::mmu();
+    mmu.write(0x0010, 0x11).unwrap();
+    mmu.write(0x0110, 0x22).unwrap();
+
+    assert_eq!(mmu.flush_page(0x01), Ok(true));
+    assert_eq!(mmu.loader().page(0x00), None);
+    assert_eq!(mmu.loader().page(0x01).unwrap()[0x10], 0x22);
+
+    // Limpa, ou fora da memória, a página não é escrita.
+    assert_eq!(mmu.flush_page(0x01), Ok(false));
+    assert_eq!(mmu.flush_page(0x30), Ok(false));
+    assert_eq!(mmu.stats.flushes(), 1);
+    assert_eq!(mmu.flush_all(), Ok(1));
+}
+
+/// Um carregador que continua acessível depois que a Mmu sai de escopo.
+struct SharedLoader(Rc<RefCell<MemoryPageLoader>>);
+
+impl PageLoader for SharedLoader {
+    fn load_page_into(&mut self, page_number: usize, target: &mut [u8]) {
+        self.0.borrow_mut().load_page_into(page_number, target);
+    }
+
+    fn flush_page(&mut self, page_number: usize, buffer: &[u8]) {
+        self.0.borrow_mut().flush_page(page_number, buffer);
+    }
+}
+
+#[test]
+fn dropping_the_mmu_writes_the_dirty_pages() {
+    let disk = Rc::new(RefCell::new(MemoryPageLoader::new()));
+
+    {
+        let mut mmu: SmallMmu<_> =
+            Mmu::new(FIFOPageReplacer::new(), SharedLoader(Rc::clone(&disk)));
+        mmu.write_u32(0x02FC, 0xCAFE_F00D).unwrap();
+        assert_eq!(disk.borrow().page(0x02), None);
+    }
+
+    assert_eq!(
+        disk.borrow().page(0x02).unwrap()[0xFC..],
+        [0x0D, 0xF0, 0xFE, 0xCA]
+    );
+}
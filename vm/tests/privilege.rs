@@ -0,0 +1,79 @@
+//! Os acessos em modo usuário às páginas do supervisor (veja o módulo
+//! `privilege`).
+
+mod common;
+
+use vm::{
+    error::{AccessError, AccessFailure, MmuError},
+    privilege::{PrivilegeFault, PrivilegeMode},
+    trace::AccessKind,
+};
+
+type SmallMmu = common::SmallMmu<{ 4 * 256 }, 4>;
+
+fn mmu() -> SmallMmu {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.set_supervisor_only(0xC0..0x100, true);
+
+    mmu
+}
+
+#[test]
+fn a_user_access_to_a_supervisor_page_faults_without_happening() {
+    let mut mmu = mmu();
+
+    // O supervisor acessa tudo.
+    mmu.write(0xC010, 0x42).unwrap();
+    mmu.set_mode(PrivilegeMode::User);
+
+    let fault = PrivilegeFault {
+        time: 1,
+        kind: AccessKind::Read,
+        address: 0xC010,
+        page_number: 0xC0,
+    };
+    assert_eq!(
+        mmu.read(0xC010),
+        Err(MmuError::Access(AccessError {
+            kind: AccessKind::Read,
+            address: 0xC010,
+            page_number: 0xC0,
+            frame_index: None,
+            cause: AccessFailure::Privilege(fault),
+        }))
+    );
+    assert!(mmu.write(0xC010, 0).is_err());
+
+    // As faltas não contam como acesso, e as páginas do usuário funcionam.
+    assert_eq!(mmu.stats.accesses(), 1);
+    assert_eq!(mmu.privilege_faults().len(), 2);
+    assert_eq!(mmu.privilege_faults()[0], fault);
+    assert_eq!(mmu.write(0x1000, 1), Ok(()));
+
+    // De volta ao supervisor, a escrita barrada não aconteceu.
+    mmu.set_mode(PrivilegeMode::Supervisor);
+    assert_eq!(mmu.read(0xC010).unwrap(), 0x42);
+}
+
+#[test]
+fn unmarked_pages_are_shared_by_both_modes() {
+    let mut mmu = mmu();
+    mmu.set_supervisor_only(0xC0..0xC1, false);
+    mmu.set_mode(PrivilegeMode::User);
+
+    assert!(!mmu.is_supervisor_only(0xC0));
+    assert!(mmu.is_supervisor_only(0xC1));
+    assert_eq!(mmu.read(0xC000), Ok(0));
+    assert!(mmu.privilege_faults().is_empty());
+}
+
+#[test]
+fn a_privilege_fault_is_described_with_the_access() {
+    let mut mmu = mmu();
+    mmu.set_mode(PrivilegeMode::User);
+
+    assert_eq!(
+        mmu.write(0xC100, 1).unwrap_err().to_string(),
+        "escrita em 0xC100 (página 0xC1): a página é só do supervisor, e a CPU está em modo usuário"
+    );
+}
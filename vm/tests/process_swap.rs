@@ -0,0 +1,55 @@
+//! A saída e a volta de um processo inteiro (veja `Mmu::swap_out_process` e
+//! `Mmu::swap_in_process`).
+
+mod common;
+
+type SmallMmu = common::SmallMmu<{ 8 * 256 }, 8>;
+
+/// Escreve nas 4 páginas de um processo e o tira da memória.
+fn swapped_out() -> SmallMmu {
+    let mut mmu: SmallMmu = common::mmu();
+
+    mmu.map_region(1, "dados", 0..4).unwrap();
+    for page in 0..4 {
+        mmu.write(page << 8, 0x10 + page as u8).unwrap();
+    }
+
+    assert_eq!(mmu.swap_out_process(1), Ok(4));
+    assert_eq!(mmu.frame_map(), vec![None; 8]);
+
+    mmu
+}
+
+#[test]
+fn prefault_brings_the_working_set_back_with_its_contents() {
+    let mut mmu = swapped_out();
+
+    assert_eq!(mmu.swap_in_process(1, true), Ok(4));
+    assert_eq!(mmu.stats.swap_prefaults(), 4);
+
+    for page in 0..4 {
+        assert!(mmu.frame_map().contains(&Some(page)));
+    }
+
+    let misses = mmu.stats.misses();
+    for page in 0..4 {
+        assert_eq!(mmu.read(page << 8), Ok(0x10 + page as u8));
+    }
+    assert_eq!(mmu.stats.misses(), misses);
+    assert_eq!(mmu.stats.swap_refaults(), 0);
+}
+
+#[test]
+fn without_prefault_the_process_comes_back_on_demand() {
+    let mut mmu = swapped_out();
+
+    assert_eq!(mmu.swap_in_process(1, false), Ok(0));
+    assert_eq!(mmu.frame_map(), vec![None; 8]);
+
+    let misses = mmu.stats.misses();
+    for page in 0..4 {
+        assert_eq!(mmu.read(page << 8), Ok(0x10 + page as u8));
+    }
+    assert_eq!(mmu.stats.misses(), misses + 4);
+    assert_eq!(mmu.stats.swap_refaults(), 4);
+}
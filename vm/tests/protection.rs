@@ -0,0 +1,133 @@
+//! A proteção das páginas e as faltas de proteção (veja
+//! `Mmu::set_protection`).
+
+mod common;
+
+use vm::{
+    error::{AccessFailure, MmuError},
+    page_table::PageTableEntry,
+    region::Permissions,
+    trace::AccessKind,
+};
+
+type SmallMmu = common::SmallMmu<{ 2 * 256 }, 2>;
+
+fn protection_fault(result: Result<impl std::fmt::Debug, MmuError>) -> (AccessKind, Permissions) {
+    let error = result.unwrap_err();
+    let error = error.access_error().unwrap();
+
+    match error.cause {
+        AccessFailure::Protection(protection) => (error.kind, protection),
+        ref cause => panic!("não é uma falta de proteção: {:?}", cause),
+    }
+}
+
+#[test]
+fn code_and_data_pages_enforce_w_xor_x() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.write(0x1000, 0x90).unwrap();
+
+    mmu.set_protection(0x10..0x12, Permissions::READ_EXECUTE);
+    mmu.set_protection(0x20..0x21, Permissions::READ_WRITE);
+
+    assert_eq!(mmu.fetch(0x1000), Ok(0x90));
+    assert_eq!(mmu.read(0x1100), Ok(0));
+    assert_eq!(
+        protection_fault(mmu.write(0x1000, 0xCC)),
+        (AccessKind::Write, Permissions::READ_EXECUTE)
+    );
+
+    mmu.write(0x2000, 0xCC).unwrap();
+    assert_eq!(
+        protection_fault(mmu.fetch(0x2000)),
+        (AccessKind::Fetch, Permissions::READ_WRITE)
+    );
+
+    // As faltas não contam como acessos, e a escrita não aconteceu.
+    assert_eq!(mmu.stats.protection_faults(), 2);
+    assert_eq!(mmu.stats.accesses(), 4);
+    assert_eq!(mmu.read(0x1000), Ok(0x90));
+}
+
+#[test]
+fn a_fault_on_a_missing_page_does_not_load_it() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.set_protection(0x30..0x31, Permissions::NONE);
+
+    assert_eq!(
+        protection_fault(mmu.read(0x3000)),
+        (AccessKind::Read, Permissions::NONE)
+    );
+    assert_eq!(mmu.stats.misses(), 0);
+    assert!(!mmu.frame_map().contains(&Some(0x30)));
+}
+
+#[test]
+fn the_protection_stays_with_the_page_across_evictions() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.set_protection(0x10..0x11, Permissions::READ_ONLY);
+
+    mmu.read(0x1000).unwrap();
+    assert!(mmu
+        .fmt_state()
+        .contains("página 0x10 -> frame 0x00 [R] [r--]"));
+
+    // Duas páginas novas tiram a 0x10 da memória.
+    mmu.read(0x2000).unwrap();
+    mmu.read(0x3000).unwrap();
+    assert!(!mmu.frame_map().contains(&Some(0x10)));
+
+    assert_eq!(mmu.protection(0x10), Permissions::READ_ONLY);
+    assert!(mmu.write(0x1000, 1).is_err());
+
+    mmu.set_protection(0x10..0x11, Permissions::default());
+    assert_eq!(mmu.write(0x1000, 1), Ok(()));
+}
+
+#[test]
+fn permissions_parse_the_maps_format() {
+    assert_eq!(Permissions::parse("r-x"), Some(Permissions::READ_EXECUTE));
+    assert_eq!(Permissions::parse("rw"), Some(Permissions::READ_WRITE));
+    assert_eq!(Permissions::parse("rwx"), Some(Permissions::ALL));
+    assert_eq!(Permissions::parse("-"), Some(Permissions::NONE));
+    assert_eq!(
+        Permissions::parse("x"),
+        Some(Permissions {
+            read: false,
+            write: false,
+            execute: true,
+        })
+    );
+    assert_eq!(Permissions::parse("xr"), None);
+    assert_eq!(Permissions::parse("rwxr"), None);
+    assert_eq!(Permissions::READ_WRITE.to_string(), "rw-");
+}
+
+#[test]
+fn an_encoded_entry_keeps_the_protection_and_a_wide_frame_index() {
+    let entry = PageTableEntry {
+        frame_index: 0x1234,
+        protection: Permissions::READ_EXECUTE,
+        ..Default::default()
+    };
+    let encoded = PageTableEntry::encode(Some(entry));
+
+    assert_eq!(PageTableEntry::decode(encoded), Some(0x1234));
+    assert_eq!(
+        PageTableEntry::decode_protection(encoded),
+        Permissions::READ_EXECUTE
+    );
+}
+
+#[test]
+fn the_page_table_in_memory_follows_mprotect() {
+    let mut mmu: common::SmallMmu<{ 16 * 256 }, 16> = common::mmu();
+    mmu.place_page_table_in_memory(0).unwrap();
+    mmu.map_page_table(0xFC);
+
+    mmu.write(0x0000, 1).unwrap();
+    assert_eq!(mmu.read(0xFC03), Ok(0b111));
+
+    mmu.set_protection(0x00..0x01, Permissions::READ_EXECUTE);
+    assert_eq!(mmu.read(0xFC03), Ok(0b101));
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use vm::{refcount::FrameRefCounts, rng::Rng};
+
+type SmallMmu = common::SmallMmu<{ 8 * 256 }, 8>;
+
+/// O número de páginas que deveriam apontar para cada frame, calculado a
+/// partir do mapa de frames e dos frames compartilhados.
+fn expected_counts(mmu: &SmallMmu) -> Vec<usize> {
+    mmu.frame_map()
+        .iter()
+        .enumerate()
+        .map(
+            |(frame_idx, page)| match mmu.shared_frames().sharers(frame_idx) {
+                [] => page.is_some() as usize,
+                sharers => sharers.len(),
+            },
+        )
+        .collect()
+}
+
+fn counts(mmu: &SmallMmu) -> Vec<usize> {
+    (0..8)
+        .map(|frame_idx| mmu.refcounts().get(frame_idx))
+        .collect()
+}
+
+#[test]
+fn counts_overflow_into_the_table_and_back() {
+    let mut refcounts = FrameRefCounts::new(4);
+
+    for expected in 1..=300 {
+        assert_eq!(refcounts.increment(2), expected);
+    }
+
+    assert_eq!(refcounts.get(2), 300);
+    assert_eq!(refcounts.overflowed(), 1);
+    assert_eq!(refcounts.frames_in_use(), 1);
+
+    for expected in (0..300).rev() {
+        assert_eq!(refcounts.decrement(2), expected);
+    }
+
+    assert_eq!(refcounts.get(2), 0);
+    assert_eq!(refcounts.overflowed(), 0);
+    assert_eq!(refcounts.frames_in_use(), 0);
+}
+
+#[test]
+#[should_panic(expected = "sem referências")]
+fn decrementing_an_unreferenced_frame_panics() {
+    FrameRefCounts::new(4).decrement(1);
+}
+
+#[test]
+fn verify_finds_leaked_and_missing_references() {
+    let mut refcounts = FrameRefCounts::new(4);
+    refcounts.increment(0);
+    refcounts.increment(1);
+
+    assert!(refcounts.verify([0, 1]).is_ok());
+
+    let mismatch = refcounts.verify([0]).unwrap_err();
+    assert_eq!(
+        (mismatch.frame_index, mismatch.counted, mismatch.actual),
+        (1, 1, 0)
+    );
+
+    let mismatch = refcounts.verify([0, 1, 3]).unwrap_err();
+    assert_eq!(
+        (mismatch.frame_index, mismatch.counted, mismatch.actual),
+        (3, 0, 1)
+    );
+}
+
+#[test]
+fn moving_frames_keeps_the_counts() {
+    let mut refcounts = FrameRefCounts::new(4);
+    refcounts.increment(2);
+    for _ in 0..300 {
+        refcounts.increment(3);
+    }
+
+    refcounts.move_frames(&[(2, 0), (3, 1)]);
+
+    assert!(refcounts
+        .verify(std::iter::once(0).chain(std::iter::repeat_n(1, 300)))
+        .is_ok());
+}
+
+#[test]
+fn a_frame_shared_by_every_page_overflows() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    mmu.write(0, 0x42).unwrap();
+    for page_number in 1..256 {
+        assert!(mmu.share_page(0, page_number).unwrap());
+    }
+
+    let frame_idx = mmu.frame_map().iter().position(Option::is_some).unwrap();
+    assert_eq!(mmu.refcounts().get(frame_idx), 256);
+    assert_eq!(mmu.refcounts().overflowed(), 1);
+
+    // Uma escrita quebra o COW: uma referência a menos, e o frame sai da
+    // tabela de transbordo.
+    mmu.write(0x1000, 1).unwrap();
+    assert_eq!(mmu.refcounts().get(frame_idx), 255);
+    assert_eq!(mmu.refcounts().frames_in_use(), 2);
+
+    mmu.write(0x2000, 1).unwrap();
+    assert_eq!(mmu.refcounts().get(frame_idx), 254);
+    assert_eq!(mmu.refcounts().overflowed(), 0);
+
+    for page_number in 0..256 {
+        mmu.unmap_page(page_number);
+    }
+
+    assert_eq!(mmu.refcounts().frames_in_use(), 0);
+    assert_eq!(counts(&mmu), vec![0; 8]);
+}
+
+#[test]
+fn counts_match_the_page_table_under_random_operations() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut rng = Rng::new(7);
+
+    mmu.enable_time_travel(16);
+
+    for _ in 0..5000 {
+        let page_number = rng.below(24);
+
+        match rng.below(10) {
+            0..=3 => {
+                mmu.read((page_number << 8) + rng.below(256)).unwrap();
+            }
+            4..=6 => mmu
+                .write((page_number << 8) + rng.below(256), rng.below(256) as u8)
+                .unwrap(),
+            7 => {
+                mmu.share_page(page_number, rng.below(24)).unwrap();
+            }
+            8 => mmu.unmap_page(page_number),
+            _ if rng.chance(0.2) => mmu.compact(),
+            _ => {
+                let _ = mmu.rewind(rng.below(8));
+            }
+        }
+
+        assert_eq!(counts(&mmu), expected_counts(&mmu));
+    }
+}
@@ -0,0 +1,54 @@
+//! O mapeamento de regiões (veja o módulo `region`).
+
+use vm::{
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::FIFOPageReplacer,
+    region::{Permissions, RegionError},
+};
+
+/// Só 4 páginas: as regiões precisam caber nelas.
+type TinyMmu = Mmu<{ 4 * 256 }, 4, 4, FIFOPageReplacer, MemoryPageLoader>;
+
+fn mmu() -> TinyMmu {
+    Mmu::new(FIFOPageReplacer::new(), MemoryPageLoader::new())
+}
+
+#[test]
+fn a_region_past_the_address_space_is_refused() {
+    let mut mmu = mmu();
+    let error = RegionError::OutOfRange {
+        pages: 2..10,
+        page_count: 4,
+    };
+
+    assert_eq!(mmu.map_region(1, "a", 2..10), Err(error.clone()));
+    assert_eq!(
+        mmu.map_region_with_permissions(1, "a", 2..10, Permissions::READ_ONLY),
+        Err(error)
+    );
+    assert!(mmu.regions().find_live(2).is_none());
+
+    // Sem a região, o limite de RSS não vê nenhuma página do processo.
+    mmu.set_rss_limit(1, Some(1));
+    assert_eq!(mmu.read(0x0200), Ok(0));
+    assert_eq!(mmu.read(0x0300), Ok(0));
+}
+
+#[test]
+fn a_region_cannot_grow_past_the_address_space() {
+    let mut mmu = mmu();
+    mmu.map_region(1, "a", 2..4).unwrap();
+
+    assert_eq!(
+        mmu.resize_region(1, "a", 2..5),
+        Err(RegionError::OutOfRange {
+            pages: 2..5,
+            page_count: 4,
+        })
+    );
+    assert_eq!(mmu.regions().find_live(3).unwrap().pages, 2..4);
+
+    assert_eq!(mmu.resize_region(1, "a", 3..4), Ok(true));
+    assert_eq!(mmu.resize_region(1, "b", 0..1), Ok(false));
+}
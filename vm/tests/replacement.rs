@@ -0,0 +1,966 @@
+//! Propriedades qualitativas das políticas de substituição, que protegem as
+//! políticas de regressões: não comparamos números exatos de misses, só
+//! relações que qualquer implementação correta precisa respeitar.
+//!
+//! O LRU exato e o ótimo (Belady) são modelos de referência definidos aqui:
+//! o LRU como um `PageReplacer` que roda na Mmu de verdade, e o ótimo
+//! calculado a partir da sequência completa de páginas.
+
+use std::collections::VecDeque;
+
+use vm::{
+    error::{AccessFailure, MmuError},
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::{
+        AgingPageReplacer, ClockPageReplacer, ClockProPageReplacer, ConfigError, FIFOPageReplacer,
+        KRefClockReplacer, LFUPageReplacer, LRUKPageReplacer, LRUPageReplacer, MFUPageReplacer,
+        MRUPageReplacer, NRUPageReplacer, OptimalPageReplacer, PageEvent, PageReplacer,
+        RandomPageReplacer, ReplacerRegistry, S3FIFOPageReplacer, SLRUPageReplacer,
+        TwoQPageReplacer, WSClockPageReplacer, WorkingSetPageReplacer,
+    },
+    three_cs::MissBreakdown,
+    trace::AccessKind,
+    workload::{Access, Pattern, WorkloadGenerator},
+};
+
+const FRAMES: usize = 8;
+const PAGES: usize = 32;
+
+/// LRU exato: uma fila da página usada há mais tempo para a mais recente.
+#[derive(Default)]
+struct ReferenceLru {
+    order: VecDeque<usize>,
+}
+
+impl PageReplacer for ReferenceLru {
+    fn page_event(&mut self, event: PageEvent) {
+        match event {
+            PageEvent::Loaded(page) => self.order.push_back(page),
+            PageEvent::Touched(page) => {
+                if let Some(position) = self.order.iter().position(|&p| p == page) {
+                    self.order.remove(position);
+                    self.order.push_back(page);
+                }
+            }
+            PageEvent::Unmapped(page) => self.order.retain(|&p| p != page),
+            _ => {}
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        self.order.pop_front()
+    }
+}
+
+/// Executa os acessos numa Mmu com `FRAMES` frames e devolve o número de
+/// misses.
+fn misses<R: PageReplacer>(replacer: R, accesses: &[Access]) -> usize {
+    let mut mmu =
+        Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(replacer, MemoryPageLoader::new());
+
+    for access in accesses {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    mmu.stats.misses()
+}
+
+/// Como `misses`, mas com um tick a cada 16 acessos.
+fn misses_with_ticks<R: PageReplacer>(replacer: R, accesses: &[Access]) -> usize {
+    let mut mmu =
+        Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(replacer, MemoryPageLoader::new());
+    mmu.set_tick_interval(Some(16));
+
+    for access in accesses {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    mmu.stats.misses()
+}
+
+/// O número de misses do algoritmo ótimo: em cada substituição, sai a página
+/// cujo próximo uso está mais longe no futuro.
+fn optimal_misses(accesses: &[Access]) -> usize {
+    let pages: Vec<usize> = accesses.iter().map(|access| access.address >> 8).collect();
+    let mut resident: Vec<usize> = Vec::new();
+    let mut misses = 0;
+
+    for (i, &page) in pages.iter().enumerate() {
+        if resident.contains(&page) {
+            continue;
+        }
+
+        misses += 1;
+
+        if resident.len() == FRAMES {
+            let next_use = |page: usize| {
+                pages[i + 1..]
+                    .iter()
+                    .position(|&p| p == page)
+                    .unwrap_or(usize::MAX)
+            };
+            let victim = (0..FRAMES).max_by_key(|&j| next_use(resident[j])).unwrap();
+            resident.swap_remove(victim);
+        }
+
+        resident.push(page);
+    }
+
+    misses
+}
+
+fn workload(seed: u64, pattern: Pattern) -> Vec<Access> {
+    WorkloadGenerator::new(seed, pattern, PAGES, 256).generate(3000)
+}
+
+/// Um laço sobre 12 páginas frias intercalado com acessos a 3 páginas
+/// quentes. As quentes são reusadas a cada 6 acessos e cabem na memória com
+/// folga; o laço não cabe.
+fn looping_with_hot_set() -> Vec<Access> {
+    (0..3000)
+        .map(|i| {
+            let page = if i % 2 == 0 {
+                i / 2 % 3
+            } else {
+                3 + i / 2 % 12
+            };
+
+            Access {
+                kind: AccessKind::Read,
+                address: page << 8,
+                value: 0,
+            }
+        })
+        .collect()
+}
+
+fn patterns() -> [Pattern; 4] {
+    [
+        Pattern::Uniform,
+        Pattern::Loop { pages: 12 },
+        Pattern::Zipf { exponent: 0.8 },
+        Pattern::HotCold {
+            hot_pages: 6,
+            hot_fraction: 0.9,
+        },
+    ]
+}
+
+#[test]
+fn lru_beats_fifo_on_a_loop_with_a_hot_set() {
+    let accesses = looping_with_hot_set();
+
+    let lru = misses(ReferenceLru::default(), &accesses);
+    let fifo = misses(FIFOPageReplacer::new(), &accesses);
+
+    assert!(lru < fifo, "LRU: {} misses, FIFO: {} misses", lru, fifo);
+}
+
+#[test]
+fn clock_falls_between_lru_and_fifo_on_a_loop_with_a_hot_set() {
+    let accesses = looping_with_hot_set();
+
+    let lru = misses(ReferenceLru::default(), &accesses);
+    let clock = misses(ClockPageReplacer::new(), &accesses);
+    let fifo = misses(FIFOPageReplacer::new(), &accesses);
+
+    // Uma página quente sempre foi usada desde a última passagem do
+    // ponteiro, mas a página fria recém-carregada também: quando todas têm o
+    // bit ligado, o relógio vira um FIFO e pode tirar uma quente. Ele fica
+    // entre o LRU e o FIFO.
+    assert!(
+        lru <= clock,
+        "relógio: {} misses, LRU: {} misses",
+        clock,
+        lru
+    );
+    assert!(
+        clock < fifo,
+        "relógio: {} misses, FIFO: {} misses",
+        clock,
+        fifo
+    );
+}
+
+#[test]
+fn clock_stays_within_ten_percent_of_lru_on_zipf() {
+    let zipf = patterns()
+        .into_iter()
+        .find(|pattern| matches!(pattern, Pattern::Zipf { .. }))
+        .unwrap();
+
+    // Sem um laço que o engane, o bit de referência separa bem as páginas
+    // populares das raras, e o relógio erra pouco mais que o LRU.
+    for seed in 0..3 {
+        let accesses = workload(seed, zipf);
+        let lru = misses(ReferenceLru::default(), &accesses);
+        let clock = misses(ClockPageReplacer::new(), &accesses);
+
+        assert!(
+            clock as f64 <= lru as f64 * 1.1,
+            "semente {}: relógio com {} misses, LRU com {}",
+            seed,
+            clock,
+            lru
+        );
+    }
+}
+
+#[test]
+fn a_loop_larger_than_memory_defeats_fifo_clock_and_lru() {
+    let accesses = workload(0, Pattern::Loop { pages: FRAMES + 1 });
+
+    assert_eq!(misses(FIFOPageReplacer::new(), &accesses), accesses.len());
+    assert_eq!(misses(ClockPageReplacer::new(), &accesses), accesses.len());
+    assert_eq!(misses(ReferenceLru::default(), &accesses), accesses.len());
+    assert!(optimal_misses(&accesses) < accesses.len() / 2);
+}
+
+#[test]
+fn mru_beats_lru_on_a_loop_larger_than_memory() {
+    // Num laço de N + 1 páginas em N frames, o MRU tira a página que acabou
+    // de ser usada e guarda as outras: erra uma vez a cada volta, a partir
+    // da segunda, enquanto o LRU erra sempre.
+    let accesses = page_accesses((0..900).map(|i| i % (FRAMES + 1)));
+    let mru = misses(MRUPageReplacer::new(), &accesses);
+
+    assert_eq!(misses(ReferenceLru::default(), &accesses), accesses.len());
+    assert_eq!(mru, optimal_misses(&accesses));
+    assert!(mru < accesses.len() / 4, "MRU: {} misses", mru);
+
+    // Com localidade, o MRU é pior que o LRU.
+    let accesses = workload(
+        0,
+        Pattern::HotCold {
+            hot_pages: 6,
+            hot_fraction: 0.9,
+        },
+    );
+    assert!(misses(MRUPageReplacer::new(), &accesses) > misses(ReferenceLru::default(), &accesses));
+}
+
+#[test]
+fn optimal_is_a_lower_bound() {
+    for seed in 0..3 {
+        for pattern in patterns() {
+            let accesses = workload(seed, pattern);
+            let optimal = optimal_misses(&accesses);
+
+            for (name, misses) in [
+                ("FIFO", misses(FIFOPageReplacer::new(), &accesses)),
+                ("LRU", misses(ReferenceLru::default(), &accesses)),
+                ("relógio", misses(ClockPageReplacer::new(), &accesses)),
+                ("LFU", misses(LFUPageReplacer::new(), &accesses)),
+                ("MFU", misses(MFUPageReplacer::new(), &accesses)),
+                ("MRU", misses(MRUPageReplacer::new(), &accesses)),
+                ("S3-FIFO", misses(S3FIFOPageReplacer::new(), &accesses)),
+                ("2Q", misses(TwoQPageReplacer::new(), &accesses)),
+                ("SLRU", misses(SLRUPageReplacer::new(), &accesses)),
+                ("LRU-2", misses(LRUKPageReplacer::new(2), &accesses)),
+                ("Clock-Pro", misses(ClockProPageReplacer::new(), &accesses)),
+                ("NRU", misses(NRUPageReplacer::new(), &accesses)),
+                (
+                    "aleatório",
+                    misses(RandomPageReplacer::new(seed), &accesses),
+                ),
+                (
+                    "relógio de 4 bits",
+                    misses(KRefClockReplacer::new(4), &accesses),
+                ),
+            ] {
+                assert!(
+                    optimal <= misses,
+                    "{:?}, semente {}: ótimo com {} misses, {} com {}",
+                    pattern,
+                    seed,
+                    optimal,
+                    name,
+                    misses
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn the_optimal_replacer_matches_the_reference_model() {
+    for seed in 0..3 {
+        for pattern in patterns() {
+            let accesses = workload(seed, pattern);
+
+            assert_eq!(
+                misses(OptimalPageReplacer::from_accesses(&accesses), &accesses),
+                optimal_misses(&accesses),
+                "{:?}, semente {}",
+                pattern,
+                seed
+            );
+        }
+    }
+
+    // Sem a sequência, o ótimo não sabe nada do futuro e sai a página
+    // carregada há mais tempo, como no FIFO.
+    let accesses = workload(0, Pattern::Uniform);
+    assert_eq!(
+        misses(OptimalPageReplacer::new(Vec::new()), &accesses),
+        misses(FIFOPageReplacer::new(), &accesses)
+    );
+}
+
+#[test]
+fn a_region_with_no_swappiness_stays_in_memory() {
+    let mut mmu = Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+    mmu.map_region(1, "protegida", 0..2).unwrap();
+    assert!(mmu.set_region_swappiness(1, "protegida", 0));
+    assert!(!mmu.set_region_swappiness(1, "outra", 0));
+
+    mmu.read(0x000).unwrap();
+    mmu.read(0x100).unwrap();
+    for access in workload(0, Pattern::Loop { pages: 12 }) {
+        mmu.read(access.address + 0x200).unwrap();
+    }
+
+    let misses = mmu.stats.misses();
+    mmu.read(0x000).unwrap();
+    mmu.read(0x100).unwrap();
+    assert_eq!(mmu.stats.misses(), misses);
+    assert!(mmu.stats.swappiness_skips() > 0);
+}
+
+/// 200 leituras da página 0, seguidas de um laço sobre as páginas 1 a 8.
+/// Devolve se a página 0 ainda está em memória no fim.
+fn stale_hot_page_survives(replacer: LFUPageReplacer) -> bool {
+    let mut mmu =
+        Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(replacer, MemoryPageLoader::new());
+
+    for _ in 0..200 {
+        mmu.read(0x000).unwrap();
+    }
+    for i in 0..2000 {
+        mmu.read((1 + i % FRAMES) << 8).unwrap();
+    }
+
+    mmu.frame_map().contains(&Some(0))
+}
+
+#[test]
+fn decay_lets_a_stale_hot_page_go() {
+    assert!(stale_hot_page_survives(LFUPageReplacer::new()));
+    assert!(!stale_hot_page_survives(
+        LFUPageReplacer::new().with("decay", 0.05).unwrap()
+    ));
+
+    assert!(matches!(
+        LFUPageReplacer::new().with("decay", 1.0),
+        Err(ConfigError::InvalidValue { .. })
+    ));
+}
+
+#[test]
+fn replacing_the_policy_replays_the_resident_pages() {
+    let mut mmu = Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(
+        ReferenceLru::default(),
+        MemoryPageLoader::new(),
+    );
+
+    for access in workload(0, Pattern::Zipf { exponent: 0.8 }) {
+        access.apply(&mut mmu).unwrap();
+    }
+    mmu.pin_page(mmu.frame_map()[0].unwrap()).unwrap();
+
+    let previous = mmu.replace_policy(ReferenceLru::default());
+    let replayed = mmu.replace_policy(ReferenceLru::default());
+
+    // O novo replacer recebeu as mesmas páginas que o anterior conhecia: as
+    // residentes, menos a fixada.
+    let sorted = |replacer: &ReferenceLru| {
+        let mut pages: Vec<_> = replacer.order.iter().copied().collect();
+        pages.sort();
+        pages
+    };
+    let mut resident: Vec<_> = mmu
+        .frame_map()
+        .into_iter()
+        .flatten()
+        .filter(|&page| !mmu.is_pinned(page))
+        .collect();
+    resident.sort();
+
+    assert_eq!(sorted(&previous), resident);
+    assert_eq!(sorted(&replayed), resident);
+
+    let misses = mmu.stats.misses();
+    for access in workload(1, Pattern::Uniform) {
+        access.apply(&mut mmu).unwrap();
+    }
+    assert!(mmu.stats.misses() > misses);
+}
+
+#[test]
+fn the_library_lru_matches_the_reference_model() {
+    for pattern in patterns() {
+        let accesses = workload(0, pattern);
+
+        assert_eq!(
+            misses(LRUPageReplacer::new(), &accesses),
+            misses(ReferenceLru::default(), &accesses)
+        );
+    }
+}
+
+#[test]
+fn more_history_bits_get_closer_to_lru() {
+    let accesses = looping_with_hot_set();
+    let lru = misses_with_ticks(ReferenceLru::default(), &accesses);
+
+    let distance = |k| misses_with_ticks(KRefClockReplacer::new(k), &accesses).abs_diff(lru);
+
+    assert!(
+        distance(8) <= distance(0),
+        "8 bits: {} misses de diferença, 0 bits: {}",
+        distance(8),
+        distance(0)
+    );
+}
+
+#[test]
+fn mfu_wins_on_a_loop_and_loses_on_a_hot_set() {
+    let looping = workload(0, Pattern::Loop { pages: FRAMES + 1 });
+    assert!(misses(MFUPageReplacer::new(), &looping) < misses(LFUPageReplacer::new(), &looping));
+
+    let hot = looping_with_hot_set();
+    assert!(misses(MFUPageReplacer::new(), &hot) > misses(LFUPageReplacer::new(), &hot));
+}
+
+#[test]
+fn s3fifo_keeps_the_hot_set_through_a_scan() {
+    let mut mmu = Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(
+        S3FIFOPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+    let mut hot_misses = 0;
+
+    // As páginas frias são usadas uma vez a cada passagem do laço, e saem
+    // pela fila pequena; as quentes, depois do primeiro reuso, ficam na
+    // principal.
+    for access in looping_with_hot_set() {
+        let misses = mmu.stats.misses();
+        access.apply(&mut mmu).unwrap();
+
+        if access.address >> 8 < 3 {
+            hot_misses += mmu.stats.misses() - misses;
+        }
+    }
+
+    assert_eq!(hot_misses, 3);
+
+    let accesses = looping_with_hot_set();
+    let fifo = misses(FIFOPageReplacer::new(), &accesses);
+    assert!(mmu.stats.misses() < fifo);
+}
+
+/// Uma varredura sequencial por 40 páginas frias, em laço, com um acesso a
+/// uma das 3 páginas quentes a cada 4 acessos. Entre dois usos de uma página
+/// quente há 9 páginas frias diferentes, então ela não cabe no LRU.
+fn hot_set_with_scan() -> Vec<Access> {
+    (0..4000)
+        .map(|i| {
+            let page = if i % 4 == 0 {
+                i / 4 % 3
+            } else {
+                8 + (i - i / 4 - 1) % 40
+            };
+
+            Access {
+                kind: AccessKind::Read,
+                address: page << 8,
+                value: 0,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn twoq_keeps_the_hot_set_through_a_scan_that_flushes_lru() {
+    let accesses = hot_set_with_scan();
+    let hot = accesses.len() / 4;
+
+    // O LRU erra todos os acessos.
+    assert_eq!(misses(ReferenceLru::default(), &accesses), accesses.len());
+
+    // No 2Q, as páginas frias passam só pela A1in; as quentes voltam
+    // enquanto estão na A1out e ficam no Am. A A1out precisa lembrar das
+    // páginas que saíram entre dois usos de uma quente.
+    let twoq = misses(
+        TwoQPageReplacer::new().with("kout", 2.0).unwrap(),
+        &accesses,
+    );
+    assert!(
+        twoq < accesses.len() - hot + 10,
+        "2Q: {} misses, {} acessos frios",
+        twoq,
+        accesses.len() - hot
+    );
+
+    // Com a A1out pequena demais, nenhuma página chega ao Am.
+    assert_eq!(
+        misses(
+            TwoQPageReplacer::new().with("kout", 0.25).unwrap(),
+            &accesses
+        ),
+        accesses.len()
+    );
+}
+
+fn page_accesses(pages: impl Iterator<Item = usize>) -> Vec<Access> {
+    pages
+        .map(|page| Access {
+            kind: AccessKind::Read,
+            address: page << 8,
+            value: 0,
+        })
+        .collect()
+}
+
+/// Uma varredura por 40 páginas frias, em laço, intercalada com 5 páginas
+/// quentes: a cada 4 acessos, dois seguidos numa página quente e dois em
+/// páginas frias. Entre dois usos de uma página quente há 8 páginas frias e
+/// as outras 4 quentes, então ela não cabe no LRU.
+fn hot_pairs_with_scan() -> Vec<Access> {
+    page_accesses((0..4000).map(|i| {
+        if i % 4 < 2 {
+            i / 4 % 5
+        } else {
+            8 + (i / 4 * 2 + i % 2) % 40
+        }
+    }))
+}
+
+/// Uma varredura por 200 páginas em que cada página é usada de novo só
+/// depois de outras 3 serem carregadas.
+fn delayed_second_use() -> Vec<Access> {
+    page_accesses((0..4000).map(|i| {
+        if i % 2 == 0 {
+            8 + i / 2 % 200
+        } else {
+            8 + (i / 2 + 197) % 200
+        }
+    }))
+}
+
+#[test]
+fn slru_segment_sizing_trades_the_hot_set_for_probation_time() {
+    let slru = |protected, accesses: &[Access]| {
+        misses(
+            SLRUPageReplacer::new()
+                .with("protected", protected)
+                .unwrap(),
+            accesses,
+        )
+    };
+
+    // Com o segmento protegido grande, as 5 páginas quentes ficam nele e só
+    // as frias erram; pequeno, ele não as guarda, e o SLRU erra como o LRU.
+    let accesses = hot_pairs_with_scan();
+    let lru = misses(ReferenceLru::default(), &accesses);
+    assert_eq!(lru, accesses.len() * 3 / 4);
+    assert_eq!(slru(0.8, &accesses), accesses.len() / 2 + 5);
+    assert_eq!(slru(0.2, &accesses), lru);
+
+    // Para ser promovida, uma página precisa ficar no segmento de prova até
+    // o segundo uso: com o protegido grande, o de prova é curto demais.
+    let accesses = delayed_second_use();
+    let lru = misses(ReferenceLru::default(), &accesses);
+    assert_eq!(slru(0.2, &accesses), lru);
+    assert!(slru(0.8, &accesses) > accesses.len() * 9 / 10);
+}
+
+/// 3 páginas quentes, cada uma usada uma vez a cada 15 acessos, entre as
+/// páginas frias de uma varredura por 40 páginas, cada uma usada duas vezes
+/// seguidas. Entre dois usos de uma página quente há 6 páginas frias e as
+/// outras 2 quentes, então ela não cabe no LRU.
+fn correlated_scan() -> Vec<Access> {
+    page_accesses((0..5000).map(|i| {
+        let round = i / 5;
+        match i % 5 {
+            0 => round % 3,
+            j => 8 + (round * 2 + (j - 1) / 2) % 40,
+        }
+    }))
+}
+
+#[test]
+fn lru_k_tells_correlated_uses_from_reuse() {
+    for pattern in patterns() {
+        let accesses = workload(0, pattern);
+        assert_eq!(
+            misses(LRUKPageReplacer::new(1), &accesses),
+            misses(ReferenceLru::default(), &accesses)
+        );
+    }
+
+    let accesses = correlated_scan();
+    let lru = misses(ReferenceLru::default(), &accesses);
+    assert_eq!(lru, accesses.len() * 3 / 5);
+
+    // Sem o período correlacionado, os dois usos seguidos de uma página fria
+    // contam como reuso, e ela parece mais quente que as quentes.
+    assert_eq!(misses(LRUKPageReplacer::new(2), &accesses), lru);
+
+    // Com ele, só as frias erram, depois dos dois primeiros usos de cada
+    // página quente.
+    let lru2 = LRUKPageReplacer::new(2).with("crp", 1.0).unwrap();
+    assert_eq!(misses(lru2, &accesses), accesses.len() * 2 / 5 + 6);
+}
+
+/// Executa `looping_with_hot_set` com as páginas quentes escritas e um tick a
+/// cada 16 acessos, e devolve o número de writebacks.
+fn writebacks_with_dirty_hot_set<R: PageReplacer>(replacer: R) -> usize {
+    let mut mmu =
+        Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(replacer, MemoryPageLoader::new());
+    mmu.set_tick_interval(Some(16));
+
+    for mut access in looping_with_hot_set() {
+        if access.address >> 8 < 3 {
+            access.kind = AccessKind::Write;
+        }
+        access.apply(&mut mmu).unwrap();
+    }
+
+    mmu.stats.writebacks()
+}
+
+#[test]
+fn nru_prefers_clean_victims() {
+    let nru = writebacks_with_dirty_hot_set(NRUPageReplacer::new());
+    let fifo = writebacks_with_dirty_hot_set(FIFOPageReplacer::new());
+
+    assert!(nru < fifo, "NRU: {} writebacks, FIFO: {}", nru, fifo);
+}
+
+#[test]
+fn fifo_on_a_loop_evicts_at_a_fixed_age_and_refaults_at_a_fixed_distance() {
+    // Num laço de 12 páginas em 8 frames, o FIFO tira cada página 8 acessos
+    // depois de carregá-la, e ela volta 4 acessos depois.
+    let mut mmu = Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+
+    for i in 0..120 {
+        mmu.read((i % 12) << 8).unwrap();
+    }
+
+    let ages = mmu.stats.victim_ages();
+    let distances = mmu.stats.refault_distances();
+
+    assert_eq!(ages.count(), 120 - FRAMES);
+    assert_eq!((ages.mean(), ages.max()), (8.0, 8));
+    assert_eq!((distances.mean(), distances.max()), (4.0, 4));
+    assert_eq!(mmu.stats.unreturned_victims(), 4);
+}
+
+#[test]
+fn aging_ticks_by_itself_like_the_mmu_ticks_it() {
+    let accesses = workload(3, Pattern::Zipf { exponent: 0.8 });
+    let self_ticking = AgingPageReplacer::new().with("interval", 16.0).unwrap();
+
+    assert_eq!(
+        misses(self_ticking, &accesses),
+        misses_with_ticks(AgingPageReplacer::new(), &accesses)
+    );
+    assert!(
+        misses_with_ticks(AgingPageReplacer::new(), &accesses)
+            < misses(FIFOPageReplacer::new(), &accesses)
+    );
+}
+
+/// Os misses frios e de capacidade de um laço de 12 páginas em 8 frames com
+/// o FIFO, com uma lista fantasma de `capacity` páginas.
+fn cold_and_capacity_misses(capacity: usize) -> (usize, usize) {
+    let mut mmu = Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(
+        FIFOPageReplacer::new(),
+        MemoryPageLoader::new(),
+    );
+    mmu.enable_ghost_list(capacity);
+
+    for i in 0..120 {
+        mmu.read((i % 12) << 8).unwrap();
+    }
+
+    assert_eq!(
+        mmu.stats.cold_misses() + mmu.stats.capacity_misses(),
+        mmu.stats.misses()
+    );
+
+    (mmu.stats.cold_misses(), mmu.stats.capacity_misses())
+}
+
+#[test]
+fn the_ghost_list_tells_refaults_from_cold_misses() {
+    // Cada página volta 3 substituições depois de sair: uma lista com 4
+    // páginas ainda a lembra, uma com 3 não.
+    assert_eq!(cold_and_capacity_misses(FRAMES), (12, 108));
+    assert_eq!(cold_and_capacity_misses(4), (12, 108));
+    assert_eq!(cold_and_capacity_misses(3), (120, 0));
+}
+
+/// Os misses pelos três Cs de uma carga, e o total de misses.
+fn three_cs<R: PageReplacer>(replacer: R, accesses: &[Access]) -> (MissBreakdown, usize) {
+    let mut mmu =
+        Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(replacer, MemoryPageLoader::new());
+    mmu.enable_three_cs();
+
+    for access in accesses {
+        access.apply(&mut mmu).unwrap();
+    }
+
+    (mmu.miss_breakdown().unwrap(), mmu.stats.misses())
+}
+
+#[test]
+fn lru_has_no_conflict_misses() {
+    for pattern in patterns() {
+        let accesses = workload(5, pattern);
+
+        let (lru, lru_misses) = three_cs(LRUPageReplacer::new(), &accesses);
+        assert_eq!(lru.total(), lru_misses);
+        assert_eq!(lru.conflict, 0);
+
+        // Os misses frios não dependem da política.
+        let (fifo, fifo_misses) = three_cs(FIFOPageReplacer::new(), &accesses);
+        assert_eq!(fifo.total(), fifo_misses);
+        assert_eq!(fifo.cold, lru.cold);
+    }
+}
+
+#[test]
+fn the_working_set_counts_the_pages_used_in_the_window() {
+    let mut mmu = Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(
+        WorkingSetPageReplacer::new(5),
+        MemoryPageLoader::new(),
+    );
+
+    // Num laço, cada janela de 5 acessos tem 5 páginas diferentes; o working
+    // set inclui as que já saíram da memória.
+    for i in 0..120 {
+        mmu.read((i % 12) << 8).unwrap();
+    }
+
+    let sizes = mmu.stats.working_set_sizes();
+    assert_eq!(sizes.count(), 120);
+    assert_eq!(sizes.max(), 5);
+
+    // Com 4 páginas em laço, o working set cabe e não há mais misses.
+    let misses = mmu.stats.misses();
+    for i in 0..40 {
+        mmu.read((i % 4) << 8).unwrap();
+    }
+    assert_eq!(mmu.stats.misses() - misses, 4);
+}
+
+#[test]
+fn wsclock_cleans_dirty_pages_outside_the_working_set_before_evicting() {
+    let mut mmu = Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(
+        WSClockPageReplacer::new(8),
+        MemoryPageLoader::new(),
+    );
+    mmu.set_tick_interval(Some(4));
+
+    // As páginas 0 a 3 ficam sujas e saem do working set; as 4 a 7 são
+    // usadas o tempo todo.
+    for page in 0..4 {
+        mmu.write(page << 8, 1).unwrap();
+    }
+    for i in 0..20 {
+        mmu.read((4 + i % 4) << 8).unwrap();
+    }
+
+    // A page fault agenda a escrita das 4 páginas sujas, e a primeira delas,
+    // já limpa, sai na volta seguinte, sem outro writeback.
+    mmu.read(8 << 8).unwrap();
+    assert_eq!(mmu.stats.cleaned_pages(), 4);
+    assert_eq!(mmu.stats.flushes(), 4);
+    assert_eq!(mmu.stats.writebacks(), 0);
+
+    let misses = mmu.stats.misses();
+    for page in 1..8 {
+        mmu.read(page << 8).unwrap();
+    }
+    assert_eq!(mmu.stats.misses(), misses);
+
+    // O conteúdo escrito no disco é o da página.
+    assert_eq!(mmu.read(0).unwrap(), 1);
+}
+
+#[test]
+fn clock_pro_keeps_hot_pages_through_a_loop_larger_than_memory() {
+    // Num laço maior que a memória, o relógio erra sempre; o Clock-Pro
+    // esquenta parte do laço e a guarda, como o ótimo faz.
+    for pages in [FRAMES + 1, 12] {
+        let accesses = workload(0, Pattern::Loop { pages });
+        let clock_pro = misses(ClockProPageReplacer::new(), &accesses);
+
+        assert_eq!(misses(ClockPageReplacer::new(), &accesses), accesses.len());
+        assert!(
+            clock_pro < accesses.len() * 2 / 3,
+            "laço de {} páginas: {} misses",
+            pages,
+            clock_pro
+        );
+    }
+
+    // As páginas usadas uma vez só numa varredura não tiram as quentes.
+    let accesses = correlated_scan();
+    assert!(
+        misses(ClockProPageReplacer::new(), &accesses)
+            < misses(ClockPageReplacer::new(), &accesses)
+    );
+
+    let accesses = workload(
+        0,
+        Pattern::HotCold {
+            hot_pages: 6,
+            hot_fraction: 0.9,
+        },
+    );
+    assert!(
+        misses(ClockProPageReplacer::new(), &accesses) < misses(ReferenceLru::default(), &accesses)
+    );
+}
+
+#[test]
+fn the_registry_builds_the_same_policies_by_name() {
+    let registry = ReplacerRegistry::new();
+    let accesses = workload(0, Pattern::Zipf { exponent: 0.8 });
+
+    for name in registry.names() {
+        let replacer = registry.create(name, 0).unwrap();
+        assert!(misses(replacer.clone(), &accesses) >= optimal_misses(&accesses));
+    }
+
+    assert_eq!(
+        misses(registry.create("clock-pro", 0).unwrap(), &accesses),
+        misses(ClockProPageReplacer::new(), &accesses)
+    );
+    assert!(registry.create("ótimo", 0).is_none());
+}
+
+/// Um replacer com bug: escolhe sempre uma página que nunca foi carregada.
+struct PicksAbsentPage;
+
+impl PageReplacer for PicksAbsentPage {
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        Some(0x7F)
+    }
+}
+
+#[test]
+fn a_victim_outside_memory_is_reported_with_the_page() {
+    let mut mmu =
+        Mmu::<{ FRAMES * 256 }, FRAMES, 256, _, _>::new(PicksAbsentPage, MemoryPageLoader::new());
+    for page in 0..FRAMES {
+        mmu.read(page << 8).unwrap();
+    }
+
+    let error = mmu.read(FRAMES << 8).unwrap_err();
+
+    assert_eq!(
+        error,
+        MmuError::PageTableInconsistency {
+            kind: AccessKind::Read,
+            address: FRAMES << 8,
+            message: "o replacer escolheu a página 0x7F, que não está em memória".to_string(),
+        }
+    );
+    assert_eq!(mmu.stats.accesses(), FRAMES);
+}
+
+#[test]
+fn a_fault_with_every_page_pinned_fails_without_touching_memory() {
+    let mut mmu =
+        Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    mmu.pin_page(0x00).unwrap();
+    mmu.pin_page(0x01).unwrap();
+    let accesses = mmu.stats.accesses();
+
+    let error = mmu.read(0x0200).unwrap_err();
+
+    assert_eq!(
+        error.access_error().unwrap().cause,
+        AccessFailure::NoEvictablePage
+    );
+    assert_eq!(mmu.stats.accesses(), accesses);
+    assert!(mmu.is_pinned(0x00) && mmu.is_pinned(0x01));
+
+    // Liberada uma página, o mesmo acesso a substitui.
+    mmu.unpin_page(0x00);
+    assert_eq!(mmu.read(0x0200), Ok(0));
+    assert_eq!(mmu.frame_map()[0], Some(0x02));
+}
+
+#[test]
+fn pinning_a_page_that_cannot_be_loaded_returns_the_error() {
+    let mut mmu =
+        Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    mmu.pin_page(0x00).unwrap();
+    mmu.pin_page(0x01).unwrap();
+
+    let error = mmu.pin_page(0x02).unwrap_err();
+
+    assert_eq!(
+        error.access_error().unwrap().cause,
+        AccessFailure::NoEvictablePage
+    );
+    assert!(!mmu.is_pinned(0x02));
+}
+
+/// Um replacer com bug: ignora o `Unmapped` da fixação e continua
+/// escolhendo a página fixada.
+#[derive(Default)]
+struct IgnoresUnmapped {
+    order: VecDeque<usize>,
+}
+
+impl PageReplacer for IgnoresUnmapped {
+    fn page_event(&mut self, event: PageEvent) {
+        if let PageEvent::Loaded(page) = event {
+            self.order.push_back(page);
+        }
+    }
+
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        self.order.pop_front()
+    }
+}
+
+#[test]
+fn pinning_sets_the_entry_flag_and_a_pinned_victim_is_reported() {
+    let mut mmu =
+        Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(IgnoresUnmapped::default(), MemoryPageLoader::new());
+    mmu.pin_page(0x00).unwrap();
+    mmu.read(0x0100).unwrap();
+
+    assert!(mmu.is_pinned(0x00));
+    assert!(mmu
+        .fmt_state()
+        .contains("página 0x00 -> frame 0x00 [R] [P]"));
+
+    mmu.unpin_page(0x00);
+    assert!(!mmu.fmt_state().contains("[P]"));
+
+    mmu.pin_page(0x00).unwrap();
+    assert_eq!(
+        mmu.read(0x0200),
+        Err(MmuError::PageTableInconsistency {
+            kind: AccessKind::Read,
+            address: 0x0200,
+            message: "o replacer escolheu a página 0x00, que está fixada".to_string(),
+        })
+    );
+    assert!(mmu.is_pinned(0x00));
+}
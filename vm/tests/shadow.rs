@@ -0,0 +1,53 @@
+//! Os VM exits das shadow page tables (veja o módulo `shadow`).
+
+mod common;
+
+use vm::{nested::AddressSpace, shadow::ShadowMmu};
+
+type Guest = common::SmallMmu<{ 4 * 256 }, 4>;
+
+fn shadow<const HOST_MEM: usize, const HOST_FRAMES: usize>(
+) -> ShadowMmu<Guest, common::SmallMmu<HOST_MEM, HOST_FRAMES>> {
+    ShadowMmu::new(common::mmu(), common::mmu())
+}
+
+#[test]
+fn each_guest_fault_is_one_vm_exit() {
+    // O host tem frames para toda a memória do guest, então nenhuma entrada
+    // da shadow é invalidada pelo host.
+    let mut mmu = shadow::<{ 8 * 256 }, 8>();
+
+    for round in 0..4 {
+        for page_number in 0..8 {
+            mmu.write(page_number << 8, round).unwrap();
+        }
+    }
+
+    let stats = mmu.stats();
+    assert_eq!(stats.accesses, 32);
+    assert_eq!(stats.guest_faults, mmu.guest().stats().misses());
+    assert_eq!(stats.guest_faults, 32);
+    assert_eq!(stats.write_protect_traps, stats.guest_faults);
+    assert_eq!(stats.shadow_fills, 0);
+    assert_eq!(stats.vm_exits(), stats.guest_faults);
+}
+
+#[test]
+fn a_host_eviction_costs_a_hidden_fault() {
+    // Com 2 frames no host para os 4 do guest, páginas residentes no guest
+    // saem da shadow quando o host substitui o frame delas.
+    let mut mmu = shadow::<{ 2 * 256 }, 2>();
+
+    for _ in 0..4 {
+        for page_number in 0..4 {
+            mmu.read(page_number << 8).unwrap();
+        }
+    }
+
+    let stats = mmu.stats();
+    assert_eq!(stats.guest_faults, 4);
+    assert!(stats.host_faults > 4);
+    assert!(stats.shadow_fills > 0);
+    assert_eq!(stats.vm_exits(), stats.guest_faults + stats.shadow_fills);
+    assert!(stats.vm_exits() <= stats.accesses);
+}
@@ -0,0 +1,134 @@
+//! O compartilhamento de frames com copy-on-write (veja o módulo `sharing`).
+
+mod common;
+
+use vm::{
+    error::AccessFailure,
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::{FIFOPageReplacer, PageReplacer},
+    region::Permissions,
+};
+
+type SmallMmu = common::SmallMmu<{ 4 * 256 }, 4>;
+
+#[test]
+fn sharing_a_page_that_cannot_be_read_returns_the_error() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.write(0x2000, 0x22).unwrap();
+    mmu.set_protection(0x10..0x11, Permissions::NONE);
+
+    let error = mmu.share_page(0x10, 0x20).unwrap_err();
+
+    assert_eq!(
+        error.access_error().unwrap().cause,
+        AccessFailure::Protection(Permissions::NONE)
+    );
+    assert_eq!(mmu.stats.shared_frames(), 0);
+    assert_eq!(mmu.read(0x2000), Ok(0x22));
+}
+
+#[test]
+fn a_write_to_a_shared_page_breaks_cow() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.write(0x1000, 0x11).unwrap();
+    assert!(mmu.share_page(0x10, 0x20).unwrap());
+    assert_eq!(mmu.stats.shared_frames(), 1);
+
+    mmu.write(0x2000, 0x22).unwrap();
+
+    assert_eq!(mmu.stats.cow_breaks(), 1);
+    assert_eq!(mmu.stats.shared_frames(), 0);
+    assert_eq!(mmu.read(0x1000), Ok(0x11));
+    assert_eq!(mmu.read(0x2000), Ok(0x22));
+    assert_eq!(mmu.refcounts().frames_in_use(), 2);
+}
+
+#[test]
+fn evicting_a_shared_frame_evicts_every_sharer() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.write(0x1000, 0x11).unwrap();
+    mmu.share_page(0x10, 0x20).unwrap();
+    for page in [0x30, 0x40, 0x50] {
+        mmu.read(page << 8).unwrap();
+    }
+
+    // A página 0x10 é a mais antiga: a sua substituição leva a 0x20 junto.
+    mmu.read(0x6000).unwrap();
+
+    let frame_map = mmu.frame_map();
+    assert!(!frame_map.contains(&Some(0x10)));
+    assert!(!frame_map.contains(&Some(0x20)));
+    assert_eq!(mmu.stats.shared_frames(), 0);
+    assert_eq!(mmu.read(0x1000), Ok(0x11));
+}
+
+#[test]
+fn pinning_a_shared_page_gives_it_its_own_frame() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.write(0x1000, 0x11).unwrap();
+    mmu.share_page(0x10, 0x20).unwrap();
+
+    mmu.pin_page(0x20).unwrap();
+
+    assert!(mmu.is_pinned(0x20));
+    assert_eq!(mmu.stats.cow_breaks(), 1);
+    assert_eq!(mmu.stats.shared_frames(), 0);
+    let frame_map = mmu.frame_map();
+    assert!(frame_map.contains(&Some(0x10)));
+    assert!(frame_map.contains(&Some(0x20)));
+    assert_eq!(mmu.read(0x2000), Ok(0x11));
+}
+
+#[test]
+fn breaking_cow_with_every_other_page_pinned_evicts_a_sharer() {
+    let mut mmu =
+        Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    mmu.write(0x1000, 0x11).unwrap();
+    mmu.pin_page(0x30).unwrap();
+    mmu.share_page(0x10, 0x20).unwrap();
+
+    mmu.write(0x2001, 0x22).unwrap();
+
+    assert_eq!(mmu.frame_map(), vec![Some(0x20), Some(0x30)]);
+    assert_eq!(mmu.read(0x2000), Ok(0x11));
+    assert_eq!(mmu.read(0x2001), Ok(0x22));
+    assert_eq!(mmu.read(0x1000), Ok(0x11));
+}
+
+/// Um replacer que nunca tem vítima.
+struct NoVictims;
+
+impl PageReplacer for NoVictims {
+    fn pick_replacement_page(&mut self) -> Option<usize> {
+        None
+    }
+}
+
+#[test]
+fn breaking_cow_without_a_victim_fails_without_touching_memory() {
+    let mut mmu = Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(NoVictims, MemoryPageLoader::new());
+    mmu.write(0x1000, 0x11).unwrap();
+    mmu.read(0x3000).unwrap();
+    mmu.share_page(0x10, 0x20).unwrap();
+    let accesses = mmu.stats.accesses();
+
+    let error = mmu.write(0x2000, 0x22).unwrap_err();
+
+    assert_eq!(
+        error.access_error().unwrap().cause,
+        AccessFailure::NoEvictablePage
+    );
+    assert_eq!(mmu.stats.accesses(), accesses);
+    assert_eq!(mmu.stats.cow_breaks(), 0);
+    assert_eq!(mmu.read(0x2000), Ok(0x11));
+
+    let error = mmu.pin_page(0x20).unwrap_err();
+
+    assert_eq!(
+        error.access_error().unwrap().cause,
+        AccessFailure::NoEvictablePage
+    );
+    assert!(!mmu.is_pinned(0x20));
+    assert_eq!(mmu.stats.shared_frames(), 1);
+}
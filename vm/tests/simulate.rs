@@ -0,0 +1,117 @@
+//! A comparação de políticas (veja `vm::simulate`).
+
+use vm::{
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::{LFUPageReplacer, LRUPageReplacer, PageReplacer, ReplacerRegistry},
+    simulate::{
+        compare_registered, compare_replacers, miss_rate_curve, Geometry, SimulateError,
+        FRAME_COUNTS,
+    },
+    workload::{Pattern, WorkloadGenerator},
+};
+
+#[test]
+fn the_comparison_matches_running_each_mmu_by_hand() {
+    let trace = WorkloadGenerator::new(3, Pattern::Zipf { exponent: 0.8 }, 32, 256).generate(2000);
+    let geometries = [Geometry::new(4), Geometry::new(16)];
+    let comparison = compare_replacers(&trace, &geometries, &["lru", "fifo", "clock"]).unwrap();
+
+    assert_eq!(comparison.accesses, 2000);
+    assert_eq!(comparison.runs.len(), 6);
+    assert_eq!(comparison.replacers(), vec!["lru", "fifo", "clock"]);
+    assert_eq!(comparison.geometries(), geometries.to_vec());
+
+    let mut mmu =
+        Mmu::<{ 4 * 256 }, 4, 256, _, _>::new(LRUPageReplacer::new(), MemoryPageLoader::new());
+    for access in &trace {
+        access.apply(&mut mmu).unwrap();
+    }
+    let lru = comparison.stats("lru", Geometry::new(4)).unwrap();
+    assert_eq!(lru.misses(), mmu.stats.misses());
+    assert_eq!(lru.writebacks(), mmu.stats.writebacks());
+
+    // Mais memória nunca piora o LRU, que é uma política de pilha.
+    let larger = comparison.stats("lru", Geometry::new(16)).unwrap();
+    assert!(larger.misses() <= lru.misses());
+    assert!(comparison.stats("lru", Geometry::new(8)).is_none());
+}
+
+#[test]
+fn unknown_replacers_and_geometries_are_rejected() {
+    let trace = vm::fixtures::trace("loop_12_pages").unwrap();
+
+    assert_eq!(
+        compare_replacers(&trace, &[Geometry::new(8)], &["lru", "belady"]).unwrap_err(),
+        SimulateError::UnknownReplacer("belady".to_string())
+    );
+    assert_eq!(
+        compare_replacers(&trace, &[Geometry::new(100)], &["lru"]).unwrap_err(),
+        SimulateError::UnsupportedGeometry(Geometry::new(100))
+    );
+}
+
+#[test]
+fn a_registry_brings_configured_replacers() {
+    let trace = WorkloadGenerator::new(3, Pattern::Zipf { exponent: 0.8 }, 32, 256).generate(2000);
+
+    let mut registry = ReplacerRegistry::new();
+    registry.register("lfu-1%", |_| {
+        Box::new(LFUPageReplacer::new().with("decay", 0.01).unwrap())
+    });
+
+    let geometry = Geometry::new(4);
+    let comparison =
+        compare_registered(&trace, &[geometry], &registry, &["lfu", "lfu-1%"]).unwrap();
+    assert_ne!(
+        comparison.stats("lfu", geometry).unwrap().misses(),
+        comparison.stats("lfu-1%", geometry).unwrap().misses()
+    );
+}
+
+#[test]
+fn the_lru_miss_rate_curve_never_rises() {
+    let trace = WorkloadGenerator::new(5, Pattern::Zipf { exponent: 0.8 }, 48, 256).generate(2000);
+    let frames: Vec<usize> = FRAME_COUNTS
+        .iter()
+        .copied()
+        .take_while(|&frames| frames <= 48)
+        .collect();
+    let curve = miss_rate_curve(&trace, &frames, LRUPageReplacer::new).unwrap();
+
+    assert_eq!(curve.accesses, 2000);
+    assert_eq!(curve.points.len(), 19);
+    for pair in curve.points.windows(2) {
+        assert!(pair[1].misses <= pair[0].misses);
+    }
+
+    // O último ponto é a mesma execução que a comparação faria.
+    let comparison = compare_replacers(&trace, &[Geometry::new(48)], &["lru"]).unwrap();
+    assert_eq!(
+        curve.points[18].misses,
+        comparison.stats("lru", Geometry::new(48)).unwrap().misses()
+    );
+    assert_eq!(
+        curve.miss_rate(48),
+        Some(curve.points[18].misses as f64 / 2000.0)
+    );
+}
+
+#[test]
+fn the_curve_is_written_as_csv() {
+    let trace = vm::fixtures::trace("loop_12_pages").unwrap();
+    let curve = miss_rate_curve(&trace, &[11, 12], LRUPageReplacer::new).unwrap();
+
+    let mut csv = Vec::new();
+    curve.write_csv(&mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "frames,misses,miss_rate\n11,600,1.000000\n12,12,0.020000\n"
+    );
+
+    assert_eq!(
+        miss_rate_curve(&trace, &[8, 17], LRUPageReplacer::new).unwrap_err(),
+        SimulateError::UnsupportedGeometry(Geometry::new(17))
+    );
+    assert!(!FRAME_COUNTS.contains(&17));
+}
@@ -0,0 +1,59 @@
+//! Os bits de software da page table (veja `Mmu::set_software_bit`).
+
+mod common;
+
+use vm::page_table::PageTableEntry;
+
+type SmallMmu = common::SmallMmu<{ 2 * 256 }, 2>;
+
+#[test]
+fn software_bits_survive_eviction_and_are_cleared_by_unmapping() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    // Marcar uma página não a carrega.
+    mmu.set_software_bit(0x10, 0, true);
+    mmu.set_software_bit(0x10, 3, true);
+    assert_eq!(mmu.stats.misses(), 0);
+    assert_eq!(mmu.software_bits(0x10), 0b1001);
+
+    mmu.write(0x1000, 0xAB).unwrap();
+    assert!(mmu.reference_history(0x10).is_some());
+
+    // A página sai da memória e volta com as mesmas marcas.
+    mmu.read(0x2000).unwrap();
+    mmu.read(0x3000).unwrap();
+    assert!(mmu.reference_history(0x10).is_none());
+    assert!(mmu.software_bit(0x10, 3));
+    mmu.set_software_bit(0x10, 0, false);
+    assert_eq!(mmu.read(0x1000).unwrap(), 0xAB);
+    assert_eq!(mmu.software_bits(0x10), 0b1000);
+
+    mmu.unmap_page(0x10);
+    assert_eq!(mmu.software_bits(0x10), 0);
+}
+
+#[test]
+#[should_panic(expected = "o bit de software 4 não existe")]
+fn there_are_only_four_software_bits() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.set_software_bit(0, 4, true);
+}
+
+#[test]
+fn software_bits_take_the_high_flag_bits_of_an_encoded_entry() {
+    let entry = PageTableEntry {
+        frame_index: 3,
+        dirty: true,
+        software: 0b1001,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        PageTableEntry::encode(Some(entry)),
+        [3, 0, 0b1001_0011, 0b111]
+    );
+    assert_eq!(
+        PageTableEntry::decode(PageTableEntry::encode(Some(entry))),
+        Some(3)
+    );
+}
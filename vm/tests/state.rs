@@ -0,0 +1,165 @@
+//! O estado da page table em TOML (veja `vm::state`).
+
+mod common;
+
+use vm::{
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::FIFOPageReplacer,
+    state::{PageState, PageTableState, StateError},
+};
+
+type SmallMmu = common::SmallMmu<{ 4 * 256 }, 4>;
+
+const EXERCISE: &str = "
+# Três páginas em memória; a 0x0C chegou por último.
+frames = 4
+
+[[page]]
+page = 0x0A
+frame = 2
+
+[[page]]
+page = 0x01
+frame = 0
+dirty = true
+software = 0b0101
+
+[[page]]
+page = 0x0C   # a mais recente
+frame = 3
+referenced = true
+age = 0b1000_0000
+";
+
+#[test]
+fn a_loaded_state_places_pages_and_orders_the_fifo_queue() {
+    let mut mmu: SmallMmu = common::mmu();
+    let state = PageTableState::from_toml(EXERCISE).unwrap();
+    mmu.load_state(&state).unwrap();
+
+    assert_eq!(
+        mmu.frame_map(),
+        vec![Some(0x01), None, Some(0x0A), Some(0x0C)]
+    );
+    assert_eq!(mmu.software_bits(0x01), 0b0101);
+    assert_eq!(mmu.reference_history(0x0C), Some(0b1000_0000));
+    assert_eq!(mmu.stats.accesses(), 0);
+
+    // A página nova vai para o frame livre; a seguinte tira a 0x0A, a
+    // primeira da fila, e não a 0x01, que tem o frame menor.
+    mmu.read(0x2000).unwrap();
+    assert_eq!(mmu.frame_map()[1], Some(0x20));
+    mmu.read(0x3000).unwrap();
+    assert_eq!(mmu.frame_map()[2], Some(0x30));
+    assert_eq!(mmu.stats.writebacks(), 0);
+
+    // A 0x01 estava suja: ela sai com writeback.
+    mmu.read(0x4000).unwrap();
+    assert_eq!(mmu.frame_map()[0], Some(0x40));
+    assert_eq!(mmu.stats.writebacks(), 1);
+}
+
+#[test]
+fn an_exported_state_reads_back_the_same() {
+    let mut original: SmallMmu = common::mmu();
+    original.write(0x0500, 0xAB).unwrap();
+    original.read(0x0300).unwrap();
+    original.pin_page(0x07).unwrap();
+    original.set_software_bit(0x03, 2, true);
+    original.tick();
+
+    let state = original.export_state();
+    assert_eq!(
+        state.pages.iter().map(|page| page.page).collect::<Vec<_>>(),
+        vec![0x05, 0x03, 0x07]
+    );
+    assert_eq!(
+        state.pages[0],
+        PageState {
+            page: 0x05,
+            frame: 0,
+            dirty: true,
+            age: 0b1000_0000,
+            ..Default::default()
+        }
+    );
+    assert!(state.pages[2].pinned);
+
+    let text = state.to_toml();
+    assert_eq!(PageTableState::from_toml(&text).unwrap(), state);
+
+    // Carregado noutra Mmu, o estado é o mesmo, e a fixação também.
+    let mut copy: SmallMmu = common::mmu();
+    copy.load_state(&state).unwrap();
+    assert_eq!(copy.export_state(), state);
+    assert!(copy.is_pinned(0x07));
+}
+
+#[test]
+fn a_page_loaded_from_state_comes_from_the_loader() {
+    let mut loader = MemoryPageLoader::new();
+    vm::page_loader::PageLoader::flush_page(&mut loader, 0x0A, &[0x5A; 256]);
+
+    let mut mmu: Mmu<{ 4 * 256 }, 4, 256, _, _> = Mmu::new(FIFOPageReplacer::new(), loader);
+    let state = PageTableState::from_toml(EXERCISE).unwrap();
+    mmu.load_state(&state).unwrap();
+
+    assert_eq!(mmu.read(0x0A10).unwrap(), 0x5A);
+    assert_eq!(mmu.stats.hits(), 1);
+}
+
+#[test]
+fn invalid_states_are_rejected_before_anything_changes() {
+    let parse = |text: &str| PageTableState::from_toml(text);
+
+    assert!(matches!(
+        parse("frames = 4\n[[page]]\nframe = 1\n"),
+        Err(StateError::Parse { line: 2, .. })
+    ));
+    assert!(matches!(
+        parse("frames = 4\n[[page]]\npage = 1\nframe = 1\ncolor = 3\n"),
+        Err(StateError::Parse { line: 5, .. })
+    ));
+    assert!(matches!(
+        parse("frames = 4\n[[page]]\npage = 1\nframe = 1\nsoftware = 0x10\n"),
+        Err(StateError::Parse { line: 5, .. })
+    ));
+    assert!(matches!(
+        parse("[[page]]\npage = 1\nframe = 1\n"),
+        Err(StateError::Parse { .. })
+    ));
+
+    let mut mmu: SmallMmu = common::mmu();
+    let load = |mmu: &mut Mmu<_, _, _, _, _>, text: &str| mmu.load_state(&parse(text).unwrap());
+
+    assert_eq!(
+        load(&mut mmu, "frames = 8"),
+        Err(StateError::FrameCount {
+            expected: 4,
+            found: 8
+        })
+    );
+    assert_eq!(
+        load(&mut mmu, "frames = 4\n[[page]]\npage = 1\nframe = 4\n"),
+        Err(StateError::FrameOutOfRange(4))
+    );
+    assert_eq!(
+        load(
+            &mut mmu,
+            "frames = 4\n[[page]]\npage = 1\nframe = 0\n[[page]]\npage = 2\nframe = 0\n"
+        ),
+        Err(StateError::FrameInUse(0))
+    );
+    assert_eq!(
+        load(
+            &mut mmu,
+            "frames = 4\n[[page]]\npage = 1\nframe = 0\n[[page]]\npage = 1\nframe = 1\n"
+        ),
+        Err(StateError::DuplicatePage(1))
+    );
+    assert_eq!(mmu.frame_map(), vec![None; 4]);
+
+    mmu.read(0x0100).unwrap();
+    assert_eq!(load(&mut mmu, "frames = 4"), Err(StateError::NotEmpty));
+}
@@ -0,0 +1,216 @@
+//! As chamadas de sistema sobre os processos e regiões da Mmu (veja o
+//! módulo `syscall`).
+
+mod common;
+
+use vm::{
+    error::AccessFailure,
+    region::Permissions,
+    syscall::{Kernel, Limits, Resource, SyscallError, HEAP_REGION, STACK_REGION},
+};
+
+type SmallMmu = common::SmallMmu<{ 8 * 256 }, 8>;
+
+#[test]
+fn sbrk_grows_and_shrinks_the_heap_inside_its_reservation() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut kernel = Kernel::new();
+
+    let pid = kernel.spawn(&mmu, 4).unwrap();
+    assert_eq!(kernel.process(pid).unwrap().heap, 1..5);
+
+    // O heap começa depois da página 0, e cresce por páginas inteiras.
+    assert_eq!(kernel.sbrk(&mut mmu, pid, 0x180), Ok(0x100));
+    assert_eq!(kernel.sbrk(&mut mmu, pid, 0), Ok(0x280));
+    assert_eq!(mmu.process_stats(pid).virtual_pages, 2);
+
+    mmu.write(0x27F, 0xAB).unwrap();
+    assert_eq!(
+        kernel.brk(&mut mmu, pid, 0x501),
+        Err(SyscallError::NoMemory)
+    );
+    assert_eq!(kernel.brk(&mut mmu, pid, 0x500), Ok(0x500));
+    assert_eq!(mmu.read(0x27F).unwrap(), 0xAB);
+
+    // Encolher libera as páginas: o que estava nelas se perde.
+    assert_eq!(kernel.sbrk(&mut mmu, pid, -0x300), Ok(0x500));
+    assert_eq!(mmu.process_stats(pid).virtual_pages, 1);
+    kernel.brk(&mut mmu, pid, 0x100).unwrap();
+    assert!(mmu.regions().iter().all(|region| !region.is_live()));
+    kernel.brk(&mut mmu, pid, 0x300).unwrap();
+    assert_eq!(mmu.read(0x27F).unwrap(), 0);
+}
+
+#[test]
+fn mmap_takes_the_highest_free_pages_and_munmap_releases_them() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut kernel = Kernel::new();
+    let pid = kernel.spawn(&mmu, 1).unwrap();
+
+    let first = kernel
+        .mmap(&mut mmu, pid, 2, Permissions::READ_WRITE)
+        .unwrap();
+    let second = kernel
+        .mmap(&mut mmu, pid, 1, Permissions::READ_ONLY)
+        .unwrap();
+    // As páginas mais altas são da pilha, reservada pelo spawn.
+    assert_eq!(kernel.process(pid).unwrap().stack, 0xF8..0x100);
+    assert_eq!((first, second), (0xF600, 0xF500));
+    assert_eq!(mmu.regions().find_live(0xF7).unwrap().name, "mmap@0xF6");
+
+    assert_eq!(
+        kernel.mmap(&mut mmu, pid, 0, Permissions::READ_WRITE),
+        Err(SyscallError::InvalidArgument)
+    );
+    assert_eq!(
+        kernel.munmap(&mut mmu, pid, 0xFF00),
+        Err(SyscallError::InvalidArgument)
+    );
+    assert_eq!(
+        kernel.mmap(&mut mmu, 42, 1, Permissions::READ_WRITE),
+        Err(SyscallError::NoSuchProcess(42))
+    );
+
+    kernel.munmap(&mut mmu, pid, first).unwrap();
+    assert!(mmu.regions().find_live(0xF6).is_none());
+    assert_eq!(kernel.process(pid).unwrap().mappings.len(), 1);
+
+    // As páginas livres voltam a ser usadas, e um pedido grande demais falha.
+    assert_eq!(
+        kernel.mmap(&mut mmu, pid, 2, Permissions::READ_WRITE),
+        Ok(0xF600)
+    );
+    assert_eq!(
+        kernel.mmap(&mut mmu, pid, 0x100, Permissions::READ_WRITE),
+        Err(SyscallError::NoMemory)
+    );
+}
+
+#[test]
+fn fork_shares_the_parent_pages_with_copy_on_write() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut kernel = Kernel::new();
+    let parent = kernel.spawn(&mmu, 2).unwrap();
+
+    let heap = kernel.sbrk(&mut mmu, parent, 0x100).unwrap();
+    let buffer = kernel
+        .mmap(&mut mmu, parent, 1, Permissions::READ_WRITE)
+        .unwrap();
+    mmu.write(heap, 0x11).unwrap();
+    mmu.write(buffer + 1, 0x22).unwrap();
+
+    let child = kernel.fork(&mut mmu, parent).unwrap();
+    let process = kernel.process(child).unwrap().clone();
+    assert_eq!(process.parent, Some(parent));
+    assert_eq!(process.heap, 3..5);
+    assert_eq!(process.heap_pages(), 3..4);
+    assert_eq!(process.stack, 0xEF..0xF7);
+    assert_eq!(process.mappings[0].pages, 0xEE..0xEF);
+
+    // O filho vê o conteúdo do pai, sem copiar nenhum frame.
+    let child_heap = process.heap.start << 8;
+    let child_buffer = process.mappings[0].pages.start << 8;
+    assert_eq!(mmu.read(child_heap).unwrap(), 0x11);
+    assert_eq!(mmu.read(child_buffer + 1).unwrap(), 0x22);
+    assert_eq!(mmu.stats.shared_frames(), 2);
+
+    // Uma escrita do filho quebra o compartilhamento.
+    mmu.write(child_heap, 0x33).unwrap();
+    assert_eq!(mmu.read(heap).unwrap(), 0x11);
+    assert_eq!(mmu.stats.cow_breaks(), 1);
+
+    kernel.exit(&mut mmu, child).unwrap();
+    assert!(mmu
+        .regions()
+        .iter()
+        .all(|region| region.is_live() == (region.pid == parent)));
+    assert_eq!(
+        kernel.exit(&mut mmu, child),
+        Err(SyscallError::NoSuchProcess(child))
+    );
+    assert_eq!(mmu.read(buffer + 1).unwrap(), 0x22);
+    assert!(kernel
+        .process(parent)
+        .is_some_and(|process| process.heap_pages() == (1..2)));
+    assert_eq!(mmu.regions().find_live(1).unwrap().name, HEAP_REGION);
+}
+
+#[test]
+fn heap_and_stack_limits_turn_growth_into_errors() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut kernel = Kernel::new();
+    let pid = kernel.spawn(&mmu, 4).unwrap();
+    let limits = Limits {
+        heap: Some(0x200),
+        stack: Some(0x300),
+        rss: None,
+    };
+    kernel.set_limits(&mut mmu, pid, limits).unwrap();
+
+    // O heap cresce até o limite, mesmo com a reserva maior.
+    assert_eq!(kernel.sbrk(&mut mmu, pid, 0x200), Ok(0x100));
+    assert_eq!(
+        kernel.sbrk(&mut mmu, pid, 1),
+        Err(SyscallError::LimitExceeded(Resource::Heap))
+    );
+    assert_eq!(kernel.process(pid).unwrap().brk, 0x300);
+
+    // A pilha cresce para baixo, a partir do fim da reserva.
+    assert_eq!(kernel.grow_stack(&mut mmu, pid, 0xFEFF), Ok(0xFE00));
+    mmu.write(0xFFFF, 0x44).unwrap();
+    assert_eq!(kernel.grow_stack(&mut mmu, pid, 0xFD10), Ok(0xFD00));
+    assert_eq!(mmu.read(0xFFFF).unwrap(), 0x44);
+    assert_eq!(mmu.regions().find_live(0xFD).unwrap().name, STACK_REGION);
+    assert_eq!(
+        kernel.grow_stack(&mut mmu, pid, 0xFC00),
+        Err(SyscallError::LimitExceeded(Resource::Stack))
+    );
+    assert_eq!(
+        kernel.grow_stack(&mut mmu, pid, 0xF700),
+        Err(SyscallError::NoMemory)
+    );
+
+    let hits = kernel.limit_hits(&mmu);
+    assert_eq!((hits.heap, hits.stack, hits.rss), (1, 1, 0));
+
+    // O filho herda os limites e a pilha.
+    let child = kernel.fork(&mut mmu, pid).unwrap();
+    assert_eq!(kernel.limits(child), Some(limits));
+    let process = kernel.process(child).unwrap();
+    assert_eq!(process.stack_pages().len(), 3);
+    assert_eq!(mmu.read((process.stack.end << 8) - 1).unwrap(), 0x44);
+}
+
+#[test]
+fn rss_limit_refuses_faults_beyond_the_resident_set() {
+    let mut mmu: SmallMmu = common::mmu();
+    let mut kernel = Kernel::new();
+    let pid = kernel.spawn(&mmu, 4).unwrap();
+    kernel.sbrk(&mut mmu, pid, 0x400).unwrap();
+    kernel
+        .set_limits(
+            &mut mmu,
+            pid,
+            Limits {
+                rss: Some(0x2FF),
+                ..Limits::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(mmu.rss_limit(pid), Some(2));
+
+    mmu.write(0x100, 1).unwrap();
+    mmu.write(0x200, 2).unwrap();
+    let error = mmu.write(0x300, 3).unwrap_err();
+    let error = error.access_error().unwrap();
+    assert_eq!(error.cause, AccessFailure::RssLimit { pid, limit: 2 });
+    assert_eq!(error.frame_index, None);
+
+    // As páginas que já estão na memória continuam acessíveis.
+    assert_eq!(mmu.read(0x200), Ok(2));
+    assert_eq!(mmu.stats.rss_limit_hits(), 1);
+    assert_eq!(kernel.limit_hits(&mmu).rss, 1);
+
+    kernel.exit(&mut mmu, pid).unwrap();
+    assert_eq!(mmu.rss_limit(pid), None);
+}
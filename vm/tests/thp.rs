@@ -0,0 +1,89 @@
+//! As páginas grandes transparentes (veja o módulo `thp`).
+
+mod common;
+
+use vm::thp::HUGE_PAGE_PAGES;
+
+type SmallMmu = common::SmallMmu<{ 16 * 256 }, 16>;
+
+fn mmu() -> SmallMmu {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.enable_thp();
+    mmu
+}
+
+fn touch(mmu: &mut SmallMmu, pages: std::ops::Range<usize>) {
+    for page_number in pages {
+        mmu.write(page_number << 8, page_number as u8).unwrap();
+    }
+}
+
+#[test]
+fn a_run_of_resident_pages_is_promoted_on_tick() {
+    let mut mmu = mmu();
+
+    touch(&mut mmu, 0..HUGE_PAGE_PAGES - 1);
+    mmu.tick();
+    assert!(mmu.huge_pages().is_empty());
+
+    touch(&mut mmu, HUGE_PAGE_PAGES - 1..HUGE_PAGE_PAGES + 2);
+    mmu.tick();
+
+    assert_eq!(mmu.huge_pages().iter().copied().collect::<Vec<_>>(), [0]);
+    assert_eq!(mmu.stats.thp_promotions(), 1);
+
+    // As páginas estão em frames consecutivos, e o conteúdo não mudou.
+    let frame_map = mmu.frame_map();
+    let first_frame = frame_map.iter().position(|&page| page == Some(0)).unwrap();
+    for page_number in 0..HUGE_PAGE_PAGES {
+        assert_eq!(frame_map[first_frame + page_number], Some(page_number));
+        assert_eq!(mmu.read(page_number << 8), Ok(page_number as u8));
+    }
+}
+
+#[test]
+fn evicting_one_page_demotes_the_huge_page() {
+    let mut mmu = mmu();
+
+    touch(&mut mmu, 0..HUGE_PAGE_PAGES);
+    mmu.tick();
+    assert_eq!(mmu.stats.thp_promotions(), 1);
+
+    // Com 16 frames, as páginas 8..16 enchem a memória, e a página 16 tira
+    // a página 0, a mais antiga, da memória.
+    touch(&mut mmu, HUGE_PAGE_PAGES..16);
+    assert_eq!(mmu.huge_pages().len(), 1);
+
+    touch(&mut mmu, 16..17);
+    assert!(!mmu.frame_map().contains(&Some(0)));
+    assert!(mmu.huge_pages().is_empty());
+    assert_eq!(mmu.stats.thp_demotions(), 1);
+}
+
+#[test]
+fn a_huge_page_covers_its_pages_with_one_tlb_entry() {
+    let mut mmu = mmu();
+    mmu.enable_tlb(4);
+
+    touch(&mut mmu, 0..HUGE_PAGE_PAGES);
+    mmu.tick();
+    assert_eq!(mmu.stats.tlb_huge_hits(), 0);
+
+    // A primeira página erra e guarda a entrada da página grande; as outras
+    // acertam nela, mesmo com o TLB menor que a página grande.
+    for page_number in 0..HUGE_PAGE_PAGES {
+        mmu.read(page_number << 8).unwrap();
+    }
+
+    assert_eq!(mmu.stats.tlb_huge_hits(), HUGE_PAGE_PAGES - 1);
+
+    let huge = mmu
+        .tlb()
+        .unwrap()
+        .counts_by_page_size()
+        .get(&(HUGE_PAGE_PAGES * 256))
+        .copied()
+        .unwrap();
+    assert_eq!((huge.hits, huge.misses), (HUGE_PAGE_PAGES - 1, 1));
+    assert_eq!(mmu.tlb().unwrap().reach(), HUGE_PAGE_PAGES * 256);
+}
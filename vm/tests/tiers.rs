@@ -0,0 +1,102 @@
+//! A memória em camadas (veja `vm::tier`).
+
+mod common;
+
+use vm::tier::{PromotionPolicy, Tier, TierConfig};
+
+type TieredMmu = common::SmallMmu<{ 8 * 256 }, 8>;
+
+/// Uma Mmu de 8 frames, 2 deles rápidos, com as páginas 0 a 7 carregadas
+/// em ordem e escritas com o próprio número.
+fn mmu(policy: PromotionPolicy) -> TieredMmu {
+    let mut mmu: TieredMmu = common::mmu();
+    mmu.enable_tiers(TierConfig::new(2, policy));
+
+    for page in 0..8 {
+        mmu.write(page << 8, page as u8).unwrap();
+    }
+
+    mmu
+}
+
+fn frame_of(mmu: &TieredMmu, page: usize) -> usize {
+    mmu.frame_map()
+        .iter()
+        .position(|&p| p == Some(page))
+        .unwrap()
+}
+
+fn heat(mmu: &mut TieredMmu, pages: &[usize], times: usize) {
+    for _ in 0..times {
+        for &page in pages {
+            mmu.read(page << 8).unwrap();
+        }
+    }
+}
+
+#[test]
+fn static_placement_never_moves_pages() {
+    let mut mmu = mmu(PromotionPolicy::Static);
+    heat(&mut mmu, &[6, 7], 10);
+    mmu.tick();
+
+    assert_eq!(frame_of(&mmu, 6), 6);
+    let report = mmu.tier_report().unwrap();
+    assert_eq!(report.stats.fast_accesses, 2);
+    assert_eq!(report.stats.slow_accesses, 26);
+    assert_eq!(report.stats.promotions, 0);
+    assert_eq!(report.slow_frames, 6);
+    assert!((report.average_latency() - (2.0 * 100.0 + 26.0 * 250.0) / 28.0).abs() < 1e-9);
+}
+
+#[test]
+fn hot_pages_swap_places_with_cold_fast_pages() {
+    let mut mmu = mmu(PromotionPolicy::HotPages {
+        threshold: 3,
+        budget: 4,
+    });
+    heat(&mut mmu, &[6, 7], 5);
+    heat(&mut mmu, &[0], 1);
+    // A página 5 foi acessada só 2 vezes, abaixo do limite.
+    heat(&mut mmu, &[5], 1);
+    mmu.tick();
+
+    let config = *mmu.tiers().unwrap().config();
+    assert_eq!(config.tier_of(frame_of(&mmu, 6)), Tier::Fast);
+    assert_eq!(config.tier_of(frame_of(&mmu, 7)), Tier::Fast);
+    assert_eq!(config.tier_of(frame_of(&mmu, 0)), Tier::Slow);
+    assert_eq!(config.tier_of(frame_of(&mmu, 5)), Tier::Slow);
+
+    // O conteúdo vai junto com a página.
+    for page in 0..8 {
+        assert_eq!(mmu.read(page << 8).unwrap(), page as u8);
+    }
+
+    let stats = *mmu.tiers().unwrap().stats();
+    assert_eq!((stats.promotions, stats.demotions), (2, 2));
+    assert_eq!(mmu.tier_report().unwrap().migration_bytes(), 4 * 256);
+    assert_eq!(mmu.stats.misses(), 8);
+}
+
+#[test]
+fn faults_prefer_free_fast_frames_and_pinned_pages_stay() {
+    let mut mmu = mmu(PromotionPolicy::HotPages {
+        threshold: 1,
+        budget: 8,
+    });
+
+    // A página 1 sai e libera um frame rápido, que a próxima falta usa.
+    mmu.unmap_page(1);
+    mmu.read(9 << 8).unwrap();
+    assert_eq!(frame_of(&mmu, 9), 1);
+
+    // Uma página fixada não sobe, por mais quente que esteja; a 7 sobe no
+    // lugar da 0, que não foi usada.
+    mmu.pin_page(6).unwrap();
+    heat(&mut mmu, &[6, 7, 9], 4);
+    mmu.tick();
+
+    assert_eq!(frame_of(&mmu, 6), 6);
+    assert_eq!(frame_of(&mmu, 7), 0);
+    assert_eq!(frame_of(&mmu, 0), 7);
+}
@@ -0,0 +1,111 @@
+//! A viagem no tempo (veja o módulo `time_travel`).
+
+mod common;
+
+use vm::{
+    region::Permissions,
+    time_travel::{RewindError, MAX_SNAPSHOTS},
+};
+
+type SmallMmu = common::SmallMmu<{ 2 * 256 }, 2>;
+
+/// O que a volta no tempo precisa restaurar: o conteúdo das páginas (dos
+/// frames ou do disco), as estatísticas, o mapa de frames e o disco.
+#[derive(Debug, PartialEq)]
+struct Observed {
+    pages: Vec<Vec<u8>>,
+    stats: (usize, usize, usize, usize),
+    frame_map: Vec<Option<usize>>,
+    swap: Vec<Vec<u8>>,
+}
+
+fn observe(mmu: &mut SmallMmu) -> Observed {
+    Observed {
+        pages: (0..4).map(|page| mmu.peek_page(page).unwrap()).collect(),
+        stats: (
+            mmu.stats.accesses(),
+            mmu.stats.hits(),
+            mmu.stats.misses(),
+            mmu.stats.writebacks(),
+        ),
+        frame_map: mmu.frame_map(),
+        // Uma página nunca escrita no disco é lida como zeros, e desfazer a
+        // sua primeira escrita a deixa zerada.
+        swap: (0..4)
+            .map(|page| mmu.loader().page(page).map_or(vec![0; 256], <[u8]>::to_vec))
+            .collect(),
+    }
+}
+
+#[test]
+fn rewinding_restores_the_checkpointed_state() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.enable_time_travel(4);
+
+    // Quatro acessos, com dois writebacks: o snapshot do acesso 4.
+    mmu.write(0x0000, 1).unwrap();
+    mmu.write(0x0100, 2).unwrap();
+    mmu.write(0x0200, 3).unwrap();
+    mmu.read(0x0000).unwrap();
+    let checkpoint = observe(&mut mmu);
+
+    mmu.write(0x0300, 4).unwrap();
+    mmu.write(0x0100, 5).unwrap();
+    let between = observe(&mut mmu);
+
+    mmu.write(0x0000, 9).unwrap();
+    mmu.read(0x0200).unwrap();
+    assert_ne!(observe(&mut mmu), between);
+
+    // Entre dois snapshots: restaura o do acesso 4 e refaz dois acessos.
+    mmu.rewind(2).unwrap();
+    assert_eq!(observe(&mut mmu), between);
+
+    mmu.rewind(2).unwrap();
+    assert_eq!(observe(&mut mmu), checkpoint);
+}
+
+#[test]
+fn only_the_last_snapshots_are_kept() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.enable_time_travel(1);
+
+    for i in 0..2 * MAX_SNAPSHOTS {
+        mmu.write((i % 4) << 8, i as u8).unwrap();
+    }
+
+    assert_eq!(
+        mmu.rewind(MAX_SNAPSHOTS),
+        Err(RewindError::TooFar {
+            available: MAX_SNAPSHOTS - 1
+        })
+    );
+    assert_eq!(mmu.rewind(MAX_SNAPSHOTS - 1), Ok(()));
+    assert_eq!(mmu.stats.accesses(), MAX_SNAPSHOTS + 1);
+}
+
+#[test]
+fn instruction_fetches_are_replayed_as_fetches() {
+    let mut mmu: SmallMmu = common::mmu();
+    // Uma página só de execução: refeitas como leituras, as buscas seriam
+    // faltas de proteção.
+    mmu.set_protection(
+        0..1,
+        Permissions {
+            read: false,
+            write: false,
+            execute: true,
+        },
+    );
+    mmu.enable_time_travel(8);
+
+    for address in 0..4 {
+        mmu.fetch(address).unwrap();
+    }
+    mmu.rewind(1).unwrap();
+
+    assert_eq!(mmu.stats.accesses(), 3);
+    assert_eq!(mmu.stats.instruction_fetches(), 3);
+    assert_eq!(mmu.stats.data_accesses(), 0);
+    assert_eq!(mmu.stats.protection_faults(), 0);
+}
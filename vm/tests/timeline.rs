@@ -0,0 +1,41 @@
+//! A linha do tempo de ocupação dos frames (veja o módulo `timeline`).
+
+mod common;
+
+use vm::timeline::FrameTimeline;
+
+type SmallMmu = common::SmallMmu<{ 2 * 256 }, 2>;
+
+fn json(timeline: &FrameTimeline) -> String {
+    let mut out = Vec::new();
+    timeline.write_json(&mut out).unwrap();
+
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn open_intervals_end_after_the_last_access_of_a_late_trace() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    // Os três primeiros acessos ficam de fora do trace.
+    for address in [0x0000, 0x0100, 0x0000] {
+        mmu.read(address).unwrap();
+    }
+
+    mmu.enable_trace();
+    mmu.read(0x0200).unwrap();
+    mmu.read(0x0100).unwrap();
+
+    let records = mmu.trace().unwrap().records();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].time, 3);
+
+    let timeline = FrameTimeline::from_records(records);
+    let frame = records[0].frame_index;
+    assert_eq!(timeline.intervals(frame)[0].start, 3);
+    assert_eq!(timeline.intervals(frame)[0].end, None);
+
+    let json = json(&timeline);
+    assert!(json.contains("\"accesses\": 5,"), "{}", json);
+    assert!(json.contains("\"start\": 3, \"end\": 5, \"resident\": true"));
+}
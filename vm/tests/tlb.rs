@@ -0,0 +1,114 @@
+//! O TLB e o seu efeito no tempo efetivo de acesso (veja `Mmu::enable_tlb`).
+
+mod common;
+
+use vm::{cost::CostModel, region::Permissions};
+
+type SmallMmu = common::SmallMmu<{ 2 * 256 }, 2>;
+
+fn tlb_counts(mmu: &SmallMmu) -> (usize, usize) {
+    (mmu.stats.tlb_hits(), mmu.stats.tlb_misses())
+}
+
+#[test]
+fn repeated_translations_hit_the_tlb() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.enable_tlb(4);
+
+    mmu.read(0x0000).unwrap();
+    mmu.read(0x0010).unwrap();
+    mmu.read(0x0100).unwrap();
+    mmu.read(0x0020).unwrap();
+
+    assert_eq!(tlb_counts(&mmu), (2, 2));
+
+    let counts = mmu.tlb().unwrap().counts();
+    assert_eq!((counts.hits, counts.misses), (2, 2));
+}
+
+#[test]
+fn without_a_tlb_nothing_is_counted() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    mmu.read(0x0000).unwrap();
+    mmu.read(0x0000).unwrap();
+
+    assert_eq!(tlb_counts(&mmu), (0, 0));
+    assert_eq!(mmu.stats.page_walk_rate(), 1.0);
+}
+
+#[test]
+fn an_evicted_page_misses_the_tlb() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.enable_tlb(4);
+
+    mmu.read(0x0000).unwrap();
+    mmu.read(0x0100).unwrap();
+    // Com dois frames, a página 2 tira a página 0 da memória; a entrada da
+    // página 0 fica no TLB, mas está velha.
+    mmu.read(0x0200).unwrap();
+    assert!(!mmu.frame_map().contains(&Some(0)));
+
+    mmu.read(0x0000).unwrap();
+    assert_eq!(tlb_counts(&mmu), (0, 4));
+
+    mmu.read(0x0000).unwrap();
+    assert_eq!(tlb_counts(&mmu), (1, 4));
+}
+
+#[test]
+fn mprotect_invalidates_the_translation() {
+    let mut mmu: SmallMmu = common::mmu();
+    mmu.enable_tlb(4);
+
+    mmu.read(0x0000).unwrap();
+    mmu.read(0x0100).unwrap();
+    mmu.read(0x0000).unwrap();
+    assert_eq!(tlb_counts(&mmu), (1, 2));
+
+    mmu.mprotect(0x0000..0x0100, Permissions::READ_ONLY);
+
+    // A página continua em memória, mas a tradução dela saiu do TLB; a da
+    // página 1 continua lá.
+    mmu.read(0x0000).unwrap();
+    mmu.read(0x0100).unwrap();
+    assert_eq!(tlb_counts(&mmu), (2, 3));
+    assert_eq!(mmu.stats.misses(), 2);
+}
+
+#[test]
+fn the_tlb_lowers_the_effective_access_time() {
+    let cost = CostModel {
+        page_table_levels: 4,
+        ..CostModel::default()
+    };
+
+    let run = |tlb: bool| {
+        let mut mmu: SmallMmu = common::mmu();
+        if tlb {
+            mmu.enable_tlb(4);
+        }
+
+        for _ in 0..8 {
+            mmu.read(0x0000).unwrap();
+            mmu.read(0x0100).unwrap();
+        }
+
+        mmu
+    };
+
+    let without = run(false);
+    let with = run(true);
+
+    assert_eq!(with.stats.misses(), without.stats.misses());
+    assert_eq!(tlb_counts(&with), (14, 2));
+
+    // Sem o TLB, todo acesso paga o walk; com ele, só 2 dos 16.
+    let walk = cost.page_walk_time();
+    let saved = (1.0 - 2.0 / 16.0) * walk;
+    let difference =
+        without.stats.effective_access_time(&cost) - with.stats.effective_access_time(&cost);
+
+    assert!((difference - saved).abs() < 1e-6);
+    assert!(with.stats.total_time(&cost) < without.stats.total_time(&cost));
+}
@@ -0,0 +1,87 @@
+//! A diferença entre os traces de duas execuções (veja o módulo
+//! `trace_diff`).
+
+use vm::{
+    mmu::Mmu,
+    page_loader::MemoryPageLoader,
+    page_replacer::FIFOPageReplacer,
+    simulate::{diff_replacers, Geometry},
+    trace::AccessKind,
+    trace_diff::{Divergence, TraceDiff, TraceDiffError},
+    workload::Access,
+};
+
+fn reads(pages: &[usize]) -> Vec<Access> {
+    pages
+        .iter()
+        .map(|&page| Access {
+            kind: AccessKind::Read,
+            address: page << 8,
+            value: 0,
+        })
+        .collect()
+}
+
+#[test]
+fn the_first_divergence_is_the_first_different_victim() {
+    let trace = reads(&[1, 2, 3, 1, 4, 1, 2]);
+    let diff = diff_replacers(&trace, Geometry::new(3), ["fifo", "lru"]).unwrap();
+
+    // O FIFO tira a página 1, a mais antiga; o LRU, a 2, que ficou mais
+    // tempo sem acesso.
+    assert_eq!(
+        diff.first_divergence,
+        Some(Divergence {
+            index: 4,
+            page_number: 4,
+            hit: [false, false],
+            evicted: [Some(1), Some(2)],
+        })
+    );
+    assert_eq!(diff.faults, [6, 5]);
+    assert_eq!(diff.fault_difference, [0, 0, 0, 0, 0, -1, -1]);
+
+    // A página 1 voltou só no FIFO.
+    let page = diff.pages[0];
+    assert_eq!(
+        (page.page_number, page.faults, page.fault_difference()),
+        (1, [2, 1], -1)
+    );
+}
+
+#[test]
+fn identical_runs_have_no_divergence() {
+    let trace = vm::fixtures::trace("loop_12_pages").unwrap();
+    let diff = diff_replacers(&trace, Geometry::new(8), ["fifo", "lru"]).unwrap();
+
+    assert_eq!(diff.first_divergence, None);
+    assert!(diff.pages.is_empty());
+    assert!(diff
+        .fault_difference
+        .iter()
+        .all(|&difference| difference == 0));
+    assert_eq!(diff.labels, ["fifo", "lru"]);
+}
+
+#[test]
+fn traces_of_different_accesses_are_refused() {
+    let mut mmu =
+        Mmu::<{ 2 * 256 }, 2, 256, _, _>::new(FIFOPageReplacer::new(), MemoryPageLoader::new());
+    mmu.enable_trace();
+    for access in reads(&[1, 2, 3]) {
+        access.apply(&mut mmu).unwrap();
+    }
+    let a = mmu.trace().unwrap().records().to_vec();
+
+    assert_eq!(
+        TraceDiff::from_records(&a, &a[..2]).unwrap_err(),
+        TraceDiffError::DifferentLengths([3, 2])
+    );
+
+    let mut b = a.clone();
+    b[1].address += 0x100;
+    assert_eq!(
+        TraceDiff::from_records(&a, &b).unwrap_err(),
+        TraceDiffError::DifferentAccess { index: 1 }
+    );
+}
@@ -0,0 +1,58 @@
+//! Os acessos de mais de um byte (veja o módulo `word`).
+
+mod common;
+
+use vm::{error::MmuError, trace::AccessKind, word::Endianness};
+
+type SmallMmu = common::SmallMmu<{ 4 * 256 }, 4>;
+
+#[test]
+fn words_follow_the_endianness_of_the_mmu() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    mmu.write_u32(0x0010, 0x1122_3344).unwrap();
+    assert_eq!(mmu.read(0x0010), Ok(0x44));
+    assert_eq!(mmu.read_u16(0x0012), Ok(0x1122));
+
+    mmu.set_endianness(Endianness::Big);
+    assert_eq!(mmu.read_u32(0x0010), Ok(0x4433_2211));
+
+    mmu.write_u64(0x0020, 0x0102_0304_0506_0708).unwrap();
+    assert_eq!(mmu.read(0x0020), Ok(0x01));
+    assert_eq!(mmu.read_u64(0x0020), Ok(0x0102_0304_0506_0708));
+}
+
+#[test]
+fn a_word_across_two_pages_is_translated_twice() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    mmu.write_u32(0x01FE, 0xAABB_CCDD).unwrap();
+
+    // Duas traduções, e uma page fault em cada página.
+    assert_eq!(mmu.stats.accesses(), 2);
+    assert_eq!(mmu.stats.misses(), 2);
+    assert_eq!(mmu.read_u16(0x01FE), Ok(0xCCDD));
+    assert_eq!(mmu.read_u16(0x0200), Ok(0xAABB));
+
+    // Com as duas páginas fora da memória, a leitura as traz de volta.
+    for page in 0x10..0x14 {
+        mmu.read(page << 8).unwrap();
+    }
+    assert_eq!(mmu.read_u32(0x01FE), Ok(0xAABB_CCDD));
+}
+
+#[test]
+fn a_word_past_the_address_space_is_refused_before_any_access() {
+    let mut mmu: SmallMmu = common::mmu();
+
+    assert_eq!(
+        mmu.write_u16(0xFFFF, 0x1234),
+        Err(MmuError::InvalidAddress {
+            kind: AccessKind::Write,
+            address: 0x1_0000,
+            page_count: 256,
+        })
+    );
+    assert_eq!(mmu.stats.accesses(), 0);
+    assert_eq!(mmu.read(0xFFFF), Ok(0));
+}